@@ -0,0 +1,45 @@
+use criterion::{criterion_group, criterion_main, Criterion, Throughput};
+
+criterion_main!(request_head);
+criterion_group!(request_head, parse);
+
+/// Same as `heph_http::MAX_HEADERS`.
+const MAX_HEADERS: usize = 64;
+
+/// Request head send by the TechEmpower plaintext benchmark.
+const PLAINTEXT: &[u8] = b"GET /plaintext HTTP/1.1\r\n\
+    Host: server\r\n\
+    User-Agent: Mozilla/5.0 (X11; Linux x86_64) Gecko/20130501 Firefox/30.0 AppleWebKit/600.00 Chrome/30.0.0000.0 Trident/10.0 Safari/600.00\r\n\
+    Cookie: uid=12345678901234567890; __utma=1.1234567890.1234567890.1234567890.1234567890.12; wd=2560x1600\r\n\
+    Accept: text/plain,text/html;q=0.9,application/xhtml+xml;q=0.9,application/xml;q=0.8,*/*;q=0.7\r\n\
+    Accept-Language: en-US,en;q=0.5\r\n\
+    Connection: keep-alive\r\n\
+    \r\n";
+
+/// Request head send by the TechEmpower JSON benchmark.
+const JSON: &[u8] = b"GET /json HTTP/1.1\r\n\
+    Host: server\r\n\
+    User-Agent: Mozilla/5.0 (X11; Linux x86_64) Gecko/20130501 Firefox/30.0 AppleWebKit/600.00 Chrome/30.0.0000.0 Trident/10.0 Safari/600.00\r\n\
+    Cookie: uid=12345678901234567890; __utma=1.1234567890.1234567890.1234567890.1234567890.12; wd=2560x1600\r\n\
+    Accept: application/json,text/html;q=0.9,application/xhtml+xml;q=0.9,application/xml;q=0.8,*/*;q=0.7\r\n\
+    Accept-Language: en-US,en;q=0.5\r\n\
+    Connection: keep-alive\r\n\
+    \r\n";
+
+pub fn parse(c: &mut Criterion) {
+    let mut group = c.benchmark_group("Parsing request head");
+    for (name, head) in [("plaintext", PLAINTEXT), ("json", JSON)] {
+        group.throughput(Throughput::Bytes(head.len() as u64));
+        group.bench_function(name, |b| {
+            b.iter(|| {
+                let mut headers = [httparse::EMPTY_HEADER; MAX_HEADERS];
+                let mut request = httparse::Request::new(&mut headers);
+                match request.parse(criterion::black_box(head)) {
+                    Ok(httparse::Status::Complete(head_length)) => head_length,
+                    _ => unreachable!(),
+                }
+            });
+        });
+    }
+    group.finish();
+}
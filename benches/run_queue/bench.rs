@@ -0,0 +1,146 @@
+use std::cmp::Ordering;
+use std::collections::BinaryHeap;
+use std::pin::Pin;
+use std::time::{Duration, Instant};
+
+use criterion::measurement::Measurement;
+use criterion::{criterion_group, criterion_main, BenchmarkGroup, BenchmarkId, Criterion};
+use heph_sched::{fair_runtime, Priority, Schedulable};
+use rand::{Rng, SeedableRng};
+use rand_xoshiro::Xoshiro128PlusPlus;
+
+criterion_main!(run_queues);
+criterion_group!(run_queues, scheduling_cycle);
+
+/// Number of processes in the run queue.
+const SIZES: [usize; 3] = [100, 10_000, 100_000];
+
+/// Priorities used.
+const PRIORITIES: [Priority; 3] = [Priority::HIGH, Priority::NORMAL, Priority::LOW];
+
+pub fn scheduling_cycle(c: &mut Criterion) {
+    let mut group = c.benchmark_group("Scheduling cycle");
+    binary_heap::cycle(&mut group);
+    fifo::cycle(&mut group);
+    group.finish();
+}
+
+/// Model of a process in the run queue.
+#[derive(Debug)]
+struct Process {
+    priority: Priority,
+    fair_runtime: Duration,
+    unaccounted_runtime: Duration,
+}
+
+impl Process {
+    /// "Run" the process.
+    fn run(&mut self, prng: &mut Xoshiro128PlusPlus) {
+        let elapsed = Duration::from_micros(prng.gen_range(1..100));
+        let fair_elapsed = fair_runtime(elapsed, self.priority);
+        self.fair_runtime += fair_elapsed;
+        self.unaccounted_runtime += fair_elapsed;
+    }
+}
+
+impl Schedulable for Process {
+    fn priority(&self) -> Priority {
+        self.priority
+    }
+
+    fn deadline(&self) -> Option<Instant> {
+        None
+    }
+
+    fn take_unaccounted_runtime(mut self: Pin<&mut Self>) -> Duration {
+        std::mem::take(&mut self.unaccounted_runtime)
+    }
+}
+
+fn new_prng() -> Xoshiro128PlusPlus {
+    #[allow(clippy::unreadable_literal)]
+    const SEED: [u8; 16] = 173328903770940342687532334189206051087_u128.to_be_bytes();
+    Xoshiro128PlusPlus::from_seed(SEED)
+}
+
+/// Returns `n` new processes.
+fn processes(n: usize) -> impl Iterator<Item = Box<Process>> {
+    let mut prng = new_prng();
+    (0..n).map(move |i| {
+        let mut process = Box::new(Process {
+            priority: PRIORITIES[i % PRIORITIES.len()],
+            fair_runtime: Duration::ZERO,
+            unaccounted_runtime: Duration::ZERO,
+        });
+        process.run(&mut prng);
+        process
+    })
+}
+
+/// Previous implementation: all processes ordered by fair runtime.
+mod binary_heap {
+    use super::*;
+
+    struct Ordered(Box<Process>);
+
+    impl Ord for Ordered {
+        fn cmp(&self, other: &Self) -> Ordering {
+            (other.0.fair_runtime)
+                .cmp(&(self.0.fair_runtime))
+                .then_with(|| self.0.priority.cmp(&other.0.priority))
+        }
+    }
+
+    impl PartialOrd for Ordered {
+        fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+            Some(self.cmp(other))
+        }
+    }
+
+    impl Eq for Ordered {}
+
+    impl PartialEq for Ordered {
+        fn eq(&self, other: &Self) -> bool {
+            self.cmp(other) == Ordering::Equal
+        }
+    }
+
+    pub fn cycle<M: Measurement>(group: &mut BenchmarkGroup<M>) {
+        for size in SIZES {
+            group.bench_with_input(BenchmarkId::new("BinaryHeap", size), &size, |b, size| {
+                let mut heap: BinaryHeap<Ordered> = processes(*size).map(Ordered).collect();
+                let mut prng = new_prng();
+                b.iter(|| {
+                    let mut process = heap.pop().unwrap();
+                    process.0.run(&mut prng);
+                    heap.push(process);
+                });
+            });
+        }
+    }
+}
+
+/// Current implementation: `heph_sched::RunQueue`, a FIFO queue per priority
+/// using deficit round robin.
+mod fifo {
+    use super::*;
+
+    use heph_sched::RunQueue;
+
+    pub fn cycle<M: Measurement>(group: &mut BenchmarkGroup<M>) {
+        for size in SIZES {
+            group.bench_with_input(BenchmarkId::new("FIFO", size), &size, |b, size| {
+                let mut run_queue = RunQueue::empty();
+                for process in processes(*size) {
+                    run_queue.add(Box::into_pin(process));
+                }
+                let mut prng = new_prng();
+                b.iter(|| {
+                    let mut process = run_queue.remove().unwrap();
+                    process.run(&mut prng);
+                    run_queue.add(process);
+                });
+            });
+        }
+    }
+}
@@ -10,7 +10,12 @@ use heph::actor_ref::{ActorRef, SendError};
 use heph::rt::{self, Runtime, RuntimeRef};
 use heph::spawn::{ActorOptions, SyncActorOptions};
 use heph::supervisor::{NoSupervisor, SupervisorStrategy};
-use heph::trace::Trace;
+use heph::trace::{Trace, TraceAllocator};
+
+// Add an "Allocation spike" trace event if an actor makes 100 or more
+// allocations in a single run.
+#[global_allocator]
+static ALLOCATOR: TraceAllocator = TraceAllocator::new().max_allocations(100);
 
 fn main() -> Result<(), rt::Error> {
     let mut runtime_setup = Runtime::setup();
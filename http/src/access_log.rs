@@ -0,0 +1,154 @@
+//! Module with the access log middleware.
+//!
+//! [`AccessLogMiddleware`] logs a single line for each request once the
+//! wrapped handler returned a response. The line includes the method, path and
+//! version of the request, the status of the response, the time it took to
+//! handle the request and the request id (if any, see the [`request_id`]
+//! module).
+//!
+//! The requests are logged using the `access` log target at the info level.
+//!
+//! [`request_id`]: crate::request_id
+//!
+//! # Examples
+//!
+//! ```
+//! use heph_http::access_log::AccessLogMiddleware;
+//! use heph_http::body::EmptyBody;
+//! use heph_http::handler::Handler;
+//! use heph_http::{Request, Response};
+//!
+//! async fn handler(_: Request<EmptyBody>) -> Response<EmptyBody> {
+//!     Response::ok()
+//! }
+//!
+//! let middleware = AccessLogMiddleware::new(handler);
+//! # fn assert_handler<H: Handler<Req>, Req>(_: H) {}
+//! # assert_handler::<_, (Request<EmptyBody>,)>(middleware);
+//! ```
+
+use std::fmt;
+use std::future::Future;
+use std::pin::Pin;
+use std::task::{self, ready, Poll};
+use std::time::Instant;
+
+use log::info;
+
+use crate::handler::{AsRequest, Handler, Layer};
+use crate::{HeaderName, Method, Response, Version};
+
+/// Log target used to log the requests.
+const TARGET: &str = "access";
+
+/// [`Layer`] that wraps handlers in [`AccessLogMiddleware`].
+#[derive(Copy, Clone, Debug, Default)]
+pub struct AccessLog;
+
+impl<H> Layer<H> for AccessLog {
+    type Handler = AccessLogMiddleware<H>;
+
+    fn layer(&self, handler: H) -> Self::Handler {
+        AccessLogMiddleware::new(handler)
+    }
+}
+
+/// [`Handler`] that logs all requests, see the [module documentation].
+///
+/// [module documentation]: crate::access_log
+#[derive(Debug)]
+pub struct AccessLogMiddleware<H> {
+    handler: H,
+}
+
+impl<H> AccessLogMiddleware<H> {
+    /// Create new access log middleware, wrapping `handler`.
+    pub const fn new(handler: H) -> AccessLogMiddleware<H> {
+        AccessLogMiddleware { handler }
+    }
+}
+
+impl<H, B, Req> Handler<Req> for AccessLogMiddleware<H>
+where
+    H: Handler<Req, Response = Response<B>>,
+    Req: AsRequest,
+{
+    type Response = Response<B>;
+    type Future = AccessLogFuture<H::Future>;
+
+    fn handle(&self, request: Req) -> Self::Future {
+        let r = request.as_request();
+        let entry = Entry {
+            method: r.method(),
+            path: r.path().to_owned(),
+            version: r.version(),
+            request_id: r
+                .headers()
+                .get_value::<&str>(&HeaderName::X_REQUEST_ID)
+                .ok()
+                .flatten()
+                .map(ToOwned::to_owned),
+            start: Instant::now(),
+        };
+        AccessLogFuture {
+            future: self.handler.handle(request),
+            entry: Some(entry),
+        }
+    }
+}
+
+/// Information about the request to log.
+#[derive(Debug)]
+struct Entry {
+    method: Method,
+    path: String,
+    version: Version,
+    request_id: Option<String>,
+    start: Instant,
+}
+
+/// [`Future`] for the [`Handler`] implementation of [`AccessLogMiddleware`].
+pub struct AccessLogFuture<Fut> {
+    future: Fut,
+    /// `None` after completion.
+    entry: Option<Entry>,
+}
+
+impl<Fut, B> Future for AccessLogFuture<Fut>
+where
+    Fut: Future<Output = Response<B>>,
+{
+    type Output = Response<B>;
+
+    fn poll(self: Pin<&mut Self>, ctx: &mut task::Context<'_>) -> Poll<Self::Output> {
+        // SAFETY: not moving the future.
+        let this = unsafe { self.get_unchecked_mut() };
+        let response = ready!(unsafe { Pin::new_unchecked(&mut this.future) }.poll(ctx));
+        let entry = this
+            .entry
+            .take()
+            .expect("polled AccessLogFuture after completion");
+        info!(
+            target: TARGET,
+            "{} {} {}: status={}, elapsed={:?}, request_id={}",
+            entry.method,
+            entry.path,
+            entry.version,
+            response.status(),
+            entry.start.elapsed(),
+            entry.request_id.as_deref().unwrap_or("-"),
+        );
+        Poll::Ready(response)
+    }
+}
+
+impl<Fut> fmt::Debug for AccessLogFuture<Fut>
+where
+    Fut: fmt::Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("AccessLogFuture")
+            .field("future", &self.future)
+            .finish()
+    }
+}
@@ -0,0 +1,191 @@
+//! Module with the authentication middleware.
+//!
+//! [`AuthMiddleware`] only passes requests to the wrapped handler if they're
+//! authorised. Whether or not a request is authorised is determined by a
+//! function that is called with a reference to the request, for example one
+//! that checks the bearer token (see [`bearer_token`]). If the request is not
+//! authorised a 401 Unauthorized response is returned (see [`Unauthorized`]).
+//!
+//! # Examples
+//!
+//! ```
+//! use heph_http::auth::{bearer_token, AuthMiddleware};
+//! use heph_http::body::EmptyBody;
+//! use heph_http::handler::Handler;
+//! use heph_http::{Request, Response};
+//!
+//! async fn handler(_: Request<EmptyBody>) -> Response<EmptyBody> {
+//!     Response::ok()
+//! }
+//!
+//! fn is_authorised(request: &Request<EmptyBody>) -> bool {
+//!     bearer_token(request) == Some("secret")
+//! }
+//!
+//! let middleware = AuthMiddleware::new(handler, is_authorised);
+//! # fn assert_handler<H: Handler<Req>, Req>(_: H) {}
+//! # assert_handler::<_, (Request<EmptyBody>,)>(middleware);
+//! ```
+
+use std::fmt;
+use std::future::Future;
+use std::pin::Pin;
+use std::task::{self, Poll};
+
+use crate::body::{EmptyBody, OneshotBody};
+use crate::handler::{AsRequest, Handler, Layer};
+use crate::{HeaderName, Request, Response};
+
+/// Returns the bearer token from the Authorization header of `request`, if
+/// any.
+pub fn bearer_token<B>(request: &Request<B>) -> Option<&str> {
+    let value: &str = request
+        .headers()
+        .get_value(&HeaderName::AUTHORIZATION)
+        .ok()??;
+    let (scheme, token) = value.split_once(' ')?;
+    if scheme.eq_ignore_ascii_case("bearer") {
+        Some(token.trim())
+    } else {
+        None
+    }
+}
+
+/// Response used by [`AuthMiddleware`] when the request is not authorised.
+///
+/// Can be converted into a 401 Unauthorized [`Response`].
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct Unauthorized;
+
+impl From<Unauthorized> for Response<EmptyBody> {
+    fn from(_: Unauthorized) -> Response<EmptyBody> {
+        Response::unauthorized()
+    }
+}
+
+impl<'b> From<Unauthorized> for Response<OneshotBody<'b>> {
+    fn from(err: Unauthorized) -> Response<OneshotBody<'b>> {
+        Response::<EmptyBody>::from(err).with_body(OneshotBody::new(b""))
+    }
+}
+
+/// [`Layer`] that wraps handlers in [`AuthMiddleware`], using (a clone of)
+/// the function `F` to authorise requests.
+#[derive(Copy, Clone, Debug)]
+pub struct Auth<F> {
+    is_authorised: F,
+}
+
+impl<F> Auth<F> {
+    /// Create a new `Auth` layer.
+    pub const fn new(is_authorised: F) -> Auth<F> {
+        Auth { is_authorised }
+    }
+}
+
+impl<H, F> Layer<H> for Auth<F>
+where
+    F: Clone,
+{
+    type Handler = AuthMiddleware<H, F>;
+
+    fn layer(&self, handler: H) -> Self::Handler {
+        AuthMiddleware::new(handler, self.is_authorised.clone())
+    }
+}
+
+/// [`Handler`] that only handles authorised requests, see the [module
+/// documentation].
+///
+/// [module documentation]: crate::auth
+pub struct AuthMiddleware<H, F> {
+    handler: H,
+    is_authorised: F,
+}
+
+impl<H, F> AuthMiddleware<H, F> {
+    /// Create new authentication middleware, wrapping `handler`.
+    ///
+    /// `is_authorised` is called with a reference to the request and should
+    /// return `true` if the request is authorised.
+    pub const fn new(handler: H, is_authorised: F) -> AuthMiddleware<H, F> {
+        AuthMiddleware {
+            handler,
+            is_authorised,
+        }
+    }
+}
+
+impl<H, F, Req> Handler<Req> for AuthMiddleware<H, F>
+where
+    H: Handler<Req>,
+    H::Response: From<Unauthorized>,
+    Req: AsRequest,
+    F: Fn(&Request<Req::Body>) -> bool,
+{
+    type Response = H::Response;
+    type Future = AuthFuture<H::Future, H::Response>;
+
+    fn handle(&self, request: Req) -> Self::Future {
+        let state = if (self.is_authorised)(request.as_request()) {
+            AuthState::Handle(self.handler.handle(request))
+        } else {
+            AuthState::Unauthorized(Some(Unauthorized.into()))
+        };
+        AuthFuture { state }
+    }
+}
+
+impl<H, F> fmt::Debug for AuthMiddleware<H, F>
+where
+    H: fmt::Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("AuthMiddleware")
+            .field("handler", &self.handler)
+            .finish()
+    }
+}
+
+/// [`Future`] for the [`Handler`] implementation of [`AuthMiddleware`].
+pub struct AuthFuture<Fut, Res> {
+    state: AuthState<Fut, Res>,
+}
+
+enum AuthState<Fut, Res> {
+    /// The request is authorised and handled by the wrapped handler.
+    Handle(Fut),
+    /// The request is not authorised.
+    Unauthorized(Option<Res>),
+}
+
+impl<Fut, Res> Future for AuthFuture<Fut, Res>
+where
+    Fut: Future<Output = Res>,
+{
+    type Output = Res;
+
+    fn poll(self: Pin<&mut Self>, ctx: &mut task::Context<'_>) -> Poll<Self::Output> {
+        // SAFETY: not moving the future.
+        match unsafe { &mut self.get_unchecked_mut().state } {
+            AuthState::Handle(future) => unsafe { Pin::new_unchecked(future) }.poll(ctx),
+            AuthState::Unauthorized(response) => {
+                Poll::Ready(response.take().expect("polled AuthFuture after completion"))
+            }
+        }
+    }
+}
+
+impl<Fut, Res> fmt::Debug for AuthFuture<Fut, Res>
+where
+    Fut: fmt::Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut f = f.debug_struct("AuthFuture");
+        match &self.state {
+            AuthState::Handle(future) => f.field("future", future),
+            AuthState::Unauthorized(_) => f.field("unauthorized", &true),
+        }
+        .finish()
+    }
+}
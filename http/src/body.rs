@@ -2,10 +2,13 @@
 //!
 //! See the [`Body`] trait.
 
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
 use std::io::{self, IoSlice};
 use std::marker::PhantomData;
 use std::num::NonZeroUsize;
 use std::stream::Stream;
+use std::sync::Arc;
 
 use heph::net::tcp::stream::{FileSend, SendAll, TcpStream};
 
@@ -21,6 +24,7 @@ use heph::net::tcp::stream::{FileSend, SendAll, TcpStream};
 ///   uses HTTP chunked encoding to transfer the body.
 /// * [`FileBody`]: uses a file as body, sending it's content using the
 ///   `sendfile(2)` system call.
+/// * [`SharedBody`]: body consisting of a shared, reference counted buffer.
 pub trait Body<'a>: PrivateBody<'a> {
     /// Length of the body, or the body will be chunked.
     fn length(&self) -> BodyLength;
@@ -42,6 +46,7 @@ mod private {
     use std::num::NonZeroUsize;
     use std::pin::Pin;
     use std::stream::Stream;
+    use std::sync::Arc;
     use std::task::{self, Poll};
 
     use heph::net::tcp::stream::FileSend;
@@ -67,6 +72,14 @@ mod private {
         ) -> Self::WriteBody<'stream, 'head>
         where
             'body: 'head;
+
+        /// Returns the strong entity-tag of the body, if it's cheap to
+        /// determine.
+        ///
+        /// Used to automatically set the ETag header in responses.
+        fn etag(&self) -> Option<u64> {
+            None
+        }
     }
 
     /// See [`super::OneshotBody`].
@@ -111,6 +124,49 @@ mod private {
         }
     }
 
+    /// See [`super::SharedBody`].
+    #[derive(Debug)]
+    pub struct SendSharedBody<'s, 'h> {
+        pub(super) stream: &'s mut TcpStream,
+        pub(super) head: &'h [u8],
+        pub(super) body: Arc<[u8]>,
+        /// Number of bytes written, including the HTTP head.
+        pub(super) written: usize,
+    }
+
+    impl<'s, 'h> Future for SendSharedBody<'s, 'h> {
+        type Output = io::Result<()>;
+
+        fn poll(self: Pin<&mut Self>, _: &mut task::Context<'_>) -> Poll<Self::Output> {
+            let SendSharedBody {
+                stream,
+                head,
+                body,
+                written,
+            } = Pin::into_inner(self);
+            while *written < head.len() + body.len() {
+                let bufs = if *written < head.len() {
+                    [IoSlice::new(&head[*written..]), IoSlice::new(body)]
+                } else {
+                    [
+                        IoSlice::new(&[]),
+                        IoSlice::new(&body[*written - head.len()..]),
+                    ]
+                };
+                match stream.try_send_vectored(&bufs) {
+                    Ok(0) => return Poll::Ready(Err(io::ErrorKind::WriteZero.into())),
+                    Ok(n) => *written += n,
+                    Err(ref err) if err.kind() == io::ErrorKind::WouldBlock => {
+                        return Poll::Pending
+                    }
+                    Err(ref err) if err.kind() == io::ErrorKind::Interrupted => continue,
+                    Err(err) => return Poll::Ready(Err(err)),
+                }
+            }
+            Poll::Ready(Ok(()))
+        }
+    }
+
     /// See [`super::StreamingBody`].
     #[derive(Debug)]
     pub struct SendStreamingBody<'s, 'h, 'b, B> {
@@ -349,7 +405,7 @@ mod private {
 }
 
 pub(crate) use private::{PrivateBody, SendChunkedBody, SendStreamingBody};
-use private::{SendFileBody, SendOneshotBody};
+use private::{SendFileBody, SendOneshotBody, SendSharedBody};
 
 /// An empty body.
 #[derive(Copy, Clone, Debug)]
@@ -630,3 +686,130 @@ where
         }
     }
 }
+
+/// Body consisting of a shared, reference counted buffer.
+///
+/// Cloning the body only increases the reference count, the bytes are not
+/// copied. This makes it useful for bodies that are send multiple times, e.g.
+/// static content or responses in the [response cache].
+///
+/// When the body is used in a response the ETag header is automatically set
+/// (if not already present) to a strong entity-tag based on the content of the
+/// body, see [`SharedBody::etag`].
+///
+/// [response cache]: crate::cache
+#[derive(Debug, Clone)]
+pub struct SharedBody {
+    bytes: Arc<[u8]>,
+    /// Hash of `bytes`, used as entity-tag.
+    hash: u64,
+}
+
+impl SharedBody {
+    /// Create a new shared body.
+    ///
+    /// This hashes `bytes` to determine the entity-tag of the body, so it's
+    /// best to create the body once and clone it afterwards.
+    pub fn new<B>(bytes: B) -> SharedBody
+    where
+        B: Into<Arc<[u8]>>,
+    {
+        let bytes = bytes.into();
+        let mut hasher = DefaultHasher::new();
+        bytes.hash(&mut hasher);
+        let hash = hasher.finish();
+        SharedBody { bytes, hash }
+    }
+
+    /// Returns the bytes that make up the body.
+    pub fn bytes(&self) -> &[u8] {
+        &*self.bytes
+    }
+
+    /// Returns the strong entity-tag of the body, including the quotes, e.g.
+    /// `"d41d8cd98f00b204"`.
+    ///
+    /// Bodies with the same content have the same entity-tag, within a single
+    /// build of the application.
+    pub fn etag(&self) -> String {
+        format!("\"{:016x}\"", self.hash)
+    }
+}
+
+impl<'b> Body<'b> for SharedBody {
+    fn length(&self) -> BodyLength {
+        BodyLength::Known(self.bytes.len())
+    }
+}
+
+impl<'b> PrivateBody<'b> for SharedBody {
+    type WriteBody<'s, 'h> = SendSharedBody<'s, 'h>;
+
+    fn write_message<'s, 'h>(
+        self,
+        stream: &'s mut TcpStream,
+        head: &'h [u8],
+    ) -> Self::WriteBody<'s, 'h>
+    where
+        'b: 'h,
+    {
+        SendSharedBody {
+            stream,
+            head,
+            body: self.bytes,
+            written: 0,
+        }
+    }
+
+    fn etag(&self) -> Option<u64> {
+        Some(self.hash)
+    }
+}
+
+impl From<Vec<u8>> for SharedBody {
+    fn from(body: Vec<u8>) -> Self {
+        SharedBody::new(body)
+    }
+}
+
+impl From<String> for SharedBody {
+    fn from(body: String) -> Self {
+        SharedBody::new(body.into_bytes())
+    }
+}
+
+impl From<&[u8]> for SharedBody {
+    fn from(body: &[u8]) -> Self {
+        SharedBody::new(body)
+    }
+}
+
+impl From<&str> for SharedBody {
+    fn from(body: &str) -> Self {
+        SharedBody::new(body.as_bytes())
+    }
+}
+
+impl PartialEq<[u8]> for SharedBody {
+    fn eq(&self, other: &[u8]) -> bool {
+        self.bytes().eq(other)
+    }
+}
+
+impl PartialEq<&[u8]> for SharedBody {
+    fn eq(&self, other: &&[u8]) -> bool {
+        self.bytes().eq(*other)
+    }
+}
+
+impl PartialEq<str> for SharedBody {
+    fn eq(&self, other: &str) -> bool {
+        self.bytes().eq(other.as_bytes())
+    }
+}
+
+impl PartialEq<&str> for SharedBody {
+    fn eq(&self, other: &&str) -> bool {
+        self.bytes().eq(other.as_bytes())
+    }
+}
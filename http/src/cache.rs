@@ -0,0 +1,555 @@
+//! Module with the response caching middleware.
+//!
+//! [`CacheMiddleware`] keeps responses in memory so that read-heavy endpoints
+//! don't have to recreate the same response for each request. Responses are
+//! cached based on the method and path of the request, and optionally the
+//! values of selected request headers (see [`CacheConfig::vary`]), similar to
+//! the Vary header.
+//!
+//! Only responses to GET and HEAD requests with a 200 OK status and a body with
+//! a known length are cached. Responses with a Cache-Control header containing
+//! `no-store` or `private` are never cached.
+//!
+//! Cached responses are cloned for each request that uses them, using
+//! [`SharedBody`] as body type means the body is not copied and the ETag
+//! header is set automatically.
+//!
+//! [`SharedBody`]: crate::body::SharedBody
+//!
+//! Cached responses expire after a time-to-live (TTL). The cache is further
+//! limited in the number of entries and the total size of the cached responses,
+//! if either limit is reached the least recently used responses are removed.
+//!
+//! The state of the cache is kept in a [`ResponseCache`]. Like the
+//! [`RateLimiter`] the cache can be cloned cheaply and all clones share the
+//! same state, but the state is **not** shared between worker threads.
+//!
+//! [`RateLimiter`]: crate::rate_limit::RateLimiter
+//!
+//! # Metrics
+//!
+//! The cache keeps track of the number of hits, misses and evictions, see
+//! [`ResponseCache::metrics`]. [`ResponseCache::log_metrics`] logs them using
+//! the `metrics` log target, the same target used by the runtime when it
+//! receives a [`User2`] signal.
+//!
+//! [`User2`]: heph::rt::Signal::User2
+//!
+//! # Examples
+//!
+//! ```
+//! use std::time::Duration;
+//!
+//! use heph_http::body::OneshotBody;
+//! use heph_http::cache::{CacheConfig, CacheMiddleware, ResponseCache};
+//! use heph_http::handler::Handler;
+//! use heph_http::{HeaderName, Request, Response};
+//!
+//! async fn handler<B>(_: Request<B>) -> Response<OneshotBody<'static>> {
+//!     Response::ok().with_body("Hello world".into())
+//! }
+//!
+//! let config = CacheConfig::new(Duration::from_secs(60))
+//!     .max_entries(1000)
+//!     .max_size(10 * 1024 * 1024)
+//!     // Cache different responses for different languages.
+//!     .vary(HeaderName::ACCEPT_LANGUAGE);
+//! let cache = ResponseCache::new(config);
+//! let middleware = CacheMiddleware::new(handler, cache);
+//! # use heph_http::body::EmptyBody;
+//! # fn assert_handler<H: Handler<Req>, Req>(_: H) {}
+//! # assert_handler::<_, (Request<EmptyBody>,)>(middleware);
+//! ```
+
+use std::cell::RefCell;
+use std::collections::{BTreeMap, HashMap};
+use std::future::Future;
+use std::pin::Pin;
+use std::rc::Rc;
+use std::task::{self, ready, Poll};
+use std::time::{Duration, Instant};
+use std::{fmt, str};
+
+use log::info;
+
+use crate::body::{Body, BodyLength};
+use crate::handler::{Handler, Layer};
+use crate::head::ResponseHead;
+use crate::{HeaderName, Headers, Method, Request, Response, StatusCode};
+
+#[doc(no_inline)]
+pub use crate::handler::AsRequest;
+
+/// Configuration of a [`ResponseCache`].
+#[derive(Clone, Debug)]
+pub struct CacheConfig {
+    /// Time after which a cached response expires.
+    ttl: Duration,
+    /// Maximum number of cached responses.
+    max_entries: usize,
+    /// Maximum total size of all cached responses, in bytes.
+    max_size: usize,
+    /// Request headers that are part of the key.
+    vary: Vec<HeaderName<'static>>,
+}
+
+impl CacheConfig {
+    /// Create a new configuration where responses expire after `ttl`.
+    ///
+    /// By default the cache is limited to 1024 entries and a total size of 16
+    /// MB.
+    pub const fn new(ttl: Duration) -> CacheConfig {
+        CacheConfig {
+            ttl,
+            max_entries: 1024,
+            max_size: 16 * 1024 * 1024,
+            vary: Vec::new(),
+        }
+    }
+
+    /// Set the maximum number of cached responses.
+    pub const fn max_entries(mut self, max_entries: usize) -> Self {
+        self.max_entries = max_entries;
+        self
+    }
+
+    /// Set the maximum total size of all cached responses, in bytes.
+    ///
+    /// The size of a response is the size of its headers and body. Responses
+    /// larger than this limit are never cached.
+    pub const fn max_size(mut self, max_size: usize) -> Self {
+        self.max_size = max_size;
+        self
+    }
+
+    /// Include the value of the request header `name` in the key of the
+    /// cache, i.e. requests with different values for the header get different
+    /// responses.
+    pub fn vary(mut self, name: HeaderName<'static>) -> Self {
+        self.vary.push(name);
+        self
+    }
+}
+
+/// In-memory cache of responses used by [`CacheMiddleware`].
+///
+/// See the [module documentation] for more information.
+///
+/// [module documentation]: crate::cache
+pub struct ResponseCache<B> {
+    inner: Rc<RefCell<CacheInner<B>>>,
+}
+
+struct CacheInner<B> {
+    config: CacheConfig,
+    entries: HashMap<CacheKey, CacheEntry<B>>,
+    /// Keys in the order in which they were last used, indexed by `tick`.
+    lru: BTreeMap<u64, CacheKey>,
+    /// Monotonically increasing counter used to order `lru`.
+    tick: u64,
+    /// Total size of all entries.
+    size: usize,
+    hits: u64,
+    misses: u64,
+    evictions: u64,
+}
+
+/// Key of a cached response.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+struct CacheKey {
+    method: Method,
+    path: String,
+    /// Values of the headers in [`CacheConfig::vary`], in the same order.
+    headers: Vec<Option<Box<[u8]>>>,
+}
+
+struct CacheEntry<B> {
+    head: ResponseHead,
+    body: B,
+    /// Size of the `head` and `body`.
+    size: usize,
+    expires: Instant,
+    /// Key in [`CacheInner::lru`].
+    tick: u64,
+}
+
+/// Metrics of a [`ResponseCache`], see [`ResponseCache::metrics`].
+#[derive(Copy, Clone, Debug)]
+#[non_exhaustive]
+pub struct CacheMetrics {
+    /// Number of cached responses.
+    pub entries: usize,
+    /// Total size of all cached responses, in bytes.
+    pub size: usize,
+    /// Number of requests answered using a cached response.
+    pub hits: u64,
+    /// Number of cacheable requests for which no cached response was found.
+    pub misses: u64,
+    /// Number of responses removed from the cache because they expired or to
+    /// stay within the configured limits.
+    pub evictions: u64,
+}
+
+impl<B> ResponseCache<B> {
+    /// Create a new empty `ResponseCache`.
+    pub fn new(config: CacheConfig) -> ResponseCache<B> {
+        ResponseCache {
+            inner: Rc::new(RefCell::new(CacheInner {
+                config,
+                entries: HashMap::new(),
+                lru: BTreeMap::new(),
+                tick: 0,
+                size: 0,
+                hits: 0,
+                misses: 0,
+                evictions: 0,
+            })),
+        }
+    }
+
+    /// Returns the number of cached responses.
+    pub fn len(&self) -> usize {
+        self.inner.borrow().entries.len()
+    }
+
+    /// Returns `true` if no responses are cached.
+    pub fn is_empty(&self) -> bool {
+        self.inner.borrow().entries.is_empty()
+    }
+
+    /// Remove all cached responses.
+    pub fn clear(&self) {
+        let mut inner = self.inner.borrow_mut();
+        inner.entries.clear();
+        inner.lru.clear();
+        inner.size = 0;
+    }
+
+    /// Gather metrics about the cache.
+    pub fn metrics(&self) -> CacheMetrics {
+        let inner = self.inner.borrow();
+        CacheMetrics {
+            entries: inner.entries.len(),
+            size: inner.size,
+            hits: inner.hits,
+            misses: inner.misses,
+            evictions: inner.evictions,
+        }
+    }
+
+    /// Log the metrics of the cache, see [`ResponseCache::metrics`].
+    pub fn log_metrics(&self) {
+        info!(target: "metrics", "response cache metrics: {:?}", self.metrics());
+    }
+
+    /// Returns the key for `request`, or `None` if the request can't be
+    /// cached.
+    fn key<RB>(&self, request: &Request<RB>) -> Option<CacheKey> {
+        if !matches!(request.method(), Method::Get | Method::Head) {
+            return None;
+        }
+        let inner = self.inner.borrow();
+        let headers = request.headers();
+        Some(CacheKey {
+            method: request.method(),
+            path: request.path().to_owned(),
+            headers: inner
+                .config
+                .vary
+                .iter()
+                .map(|name| headers.get_bytes(name).map(Box::from))
+                .collect(),
+        })
+    }
+
+    /// Returns the cached response for `key`, if any.
+    fn get(&self, key: &CacheKey, now: Instant) -> Option<Response<B>>
+    where
+        B: Clone,
+    {
+        let mut inner = self.inner.borrow_mut();
+        let inner = &mut *inner;
+        let tick = match inner.entries.get(key) {
+            Some(entry) if entry.expires > now => entry.tick,
+            Some(_) => {
+                inner.remove(key);
+                inner.evictions += 1;
+                inner.misses += 1;
+                return None;
+            }
+            None => {
+                inner.misses += 1;
+                return None;
+            }
+        };
+
+        // Mark the entry as most recently used.
+        let new_tick = inner.next_tick();
+        let key = inner.lru.remove(&tick).unwrap();
+        let entry = inner.entries.get_mut(&key).unwrap();
+        entry.tick = new_tick;
+        let response = Response::new(
+            entry.head.version(),
+            entry.head.status(),
+            entry.head.headers().clone(),
+            entry.body.clone(),
+        );
+        let _ = inner.lru.insert(new_tick, key);
+        inner.hits += 1;
+        Some(response)
+    }
+
+    /// Add `response` to the cache, if it's cacheable.
+    fn insert(&self, key: CacheKey, response: &Response<B>, now: Instant)
+    where
+        B: Body<'static> + Clone,
+    {
+        if response.status() != StatusCode::OK || !is_storable(response.headers()) {
+            return;
+        }
+        let body_size = match response.body().length() {
+            BodyLength::Known(size) => size,
+            BodyLength::Chunked => return,
+        };
+        let size = headers_size(response.headers()) + body_size;
+
+        let mut inner = self.inner.borrow_mut();
+        let inner = &mut *inner;
+        if size > inner.config.max_size || inner.config.max_entries == 0 {
+            return;
+        }
+        inner.remove(&key);
+        inner.evict(now, size);
+
+        let tick = inner.next_tick();
+        let entry = CacheEntry {
+            head: ResponseHead::new(
+                response.version(),
+                response.status(),
+                response.headers().clone(),
+            ),
+            body: response.body().clone(),
+            size,
+            expires: now + inner.config.ttl,
+            tick,
+        };
+        inner.size += size;
+        let _ = inner.lru.insert(tick, key.clone());
+        let _ = inner.entries.insert(key, entry);
+    }
+}
+
+impl<B> CacheInner<B> {
+    fn next_tick(&mut self) -> u64 {
+        let tick = self.tick;
+        self.tick += 1;
+        tick
+    }
+
+    /// Remove the entry with `key`, if any.
+    fn remove(&mut self, key: &CacheKey) {
+        if let Some(entry) = self.entries.remove(key) {
+            let _ = self.lru.remove(&entry.tick);
+            self.size -= entry.size;
+        }
+    }
+
+    /// Remove expired entries and, if needed, the least recently used entries
+    /// to make room for a new entry of `size` bytes.
+    fn evict(&mut self, now: Instant, size: usize) {
+        let before = self.entries.len();
+        let lru = &mut self.lru;
+        let mut removed_size = 0;
+        self.entries.retain(|_, entry| {
+            if entry.expires > now {
+                true
+            } else {
+                let _ = lru.remove(&entry.tick);
+                removed_size += entry.size;
+                false
+            }
+        });
+        self.size -= removed_size;
+
+        while self.entries.len() >= self.config.max_entries
+            || self.size + size > self.config.max_size
+        {
+            let key = match self.lru.values().next() {
+                Some(key) => key.clone(),
+                None => break,
+            };
+            self.remove(&key);
+        }
+        self.evictions += (before - self.entries.len()) as u64;
+    }
+}
+
+/// Returns `false` if the Cache-Control header in `headers` forbids caching
+/// the response.
+fn is_storable(headers: &Headers) -> bool {
+    headers
+        .get_all(&HeaderName::CACHE_CONTROL)
+        .filter_map(|header| str::from_utf8(header.value()).ok())
+        .flat_map(|value| value.split(','))
+        .all(|directive| {
+            let directive = directive.trim();
+            !directive.eq_ignore_ascii_case("no-store")
+                && !directive.eq_ignore_ascii_case("private")
+        })
+}
+
+/// Returns the (approximate) size of `headers` in bytes.
+fn headers_size(headers: &Headers) -> usize {
+    headers
+        .iter()
+        .map(|header| header.name().as_ref().len() + header.value().len())
+        .sum()
+}
+
+impl<B> Clone for ResponseCache<B> {
+    fn clone(&self) -> ResponseCache<B> {
+        ResponseCache {
+            inner: self.inner.clone(),
+        }
+    }
+}
+
+impl<B> fmt::Debug for ResponseCache<B> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let inner = self.inner.borrow();
+        f.debug_struct("ResponseCache")
+            .field("config", &inner.config)
+            .field("metrics", &self.metrics())
+            .finish()
+    }
+}
+
+/// [`Handler`] that caches the responses of the wrapped handler, see the
+/// [module documentation].
+///
+/// [module documentation]: crate::cache
+pub struct CacheMiddleware<H, B> {
+    handler: H,
+    cache: ResponseCache<B>,
+}
+
+impl<H, B> CacheMiddleware<H, B> {
+    /// Create new caching middleware, wrapping `handler`.
+    pub const fn new(handler: H, cache: ResponseCache<B>) -> CacheMiddleware<H, B> {
+        CacheMiddleware { handler, cache }
+    }
+}
+
+impl<H, B, Req> Handler<Req> for CacheMiddleware<H, B>
+where
+    H: Handler<Req, Response = Response<B>>,
+    B: Body<'static> + Clone,
+    Req: AsRequest,
+{
+    type Response = Response<B>;
+    type Future = CacheFuture<H::Future, B>;
+
+    fn handle(&self, request: Req) -> Self::Future {
+        let key = match self.cache.key(request.as_request()) {
+            Some(key) => key,
+            None => {
+                return CacheFuture {
+                    state: CacheState::Uncached(self.handler.handle(request)),
+                }
+            }
+        };
+        let state = match self.cache.get(&key, Instant::now()) {
+            Some(response) => CacheState::Cached(Some(response)),
+            None => CacheState::Handle {
+                future: self.handler.handle(request),
+                key: Some(key),
+                cache: self.cache.clone(),
+            },
+        };
+        CacheFuture { state }
+    }
+}
+
+/// Using the cache as [`Layer`] wraps the handler in [`CacheMiddleware`], using
+/// (a clone of) this cache.
+impl<H, B> Layer<H> for ResponseCache<B> {
+    type Handler = CacheMiddleware<H, B>;
+
+    fn layer(&self, handler: H) -> Self::Handler {
+        CacheMiddleware::new(handler, self.clone())
+    }
+}
+
+impl<H, B> fmt::Debug for CacheMiddleware<H, B>
+where
+    H: fmt::Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("CacheMiddleware")
+            .field("handler", &self.handler)
+            .field("cache", &self.cache)
+            .finish()
+    }
+}
+
+/// [`Future`] for the [`Handler`] implementation of [`CacheMiddleware`].
+pub struct CacheFuture<Fut, B> {
+    state: CacheState<Fut, B>,
+}
+
+enum CacheState<Fut, B> {
+    /// The request is handled by the wrapped handler and the response is
+    /// added to the cache.
+    Handle {
+        future: Fut,
+        /// `None` after completion.
+        key: Option<CacheKey>,
+        cache: ResponseCache<B>,
+    },
+    /// The request can't be cached and is handled by the wrapped handler.
+    Uncached(Fut),
+    /// The response was found in the cache.
+    Cached(Option<Response<B>>),
+}
+
+impl<Fut, B> Future for CacheFuture<Fut, B>
+where
+    Fut: Future<Output = Response<B>>,
+    B: Body<'static> + Clone,
+{
+    type Output = Response<B>;
+
+    fn poll(self: Pin<&mut Self>, ctx: &mut task::Context<'_>) -> Poll<Self::Output> {
+        // SAFETY: not moving the future.
+        match unsafe { &mut self.get_unchecked_mut().state } {
+            CacheState::Handle { future, key, cache } => {
+                let response = ready!(unsafe { Pin::new_unchecked(future) }.poll(ctx));
+                let key = key.take().expect("polled CacheFuture after completion");
+                cache.insert(key, &response, Instant::now());
+                Poll::Ready(response)
+            }
+            CacheState::Uncached(future) => unsafe { Pin::new_unchecked(future) }.poll(ctx),
+            CacheState::Cached(response) => Poll::Ready(
+                response
+                    .take()
+                    .expect("polled CacheFuture after completion"),
+            ),
+        }
+    }
+}
+
+impl<Fut, B> fmt::Debug for CacheFuture<Fut, B>
+where
+    Fut: fmt::Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut f = f.debug_struct("CacheFuture");
+        match &self.state {
+            CacheState::Handle { future, .. } | CacheState::Uncached(future) => {
+                f.field("future", future)
+            }
+            CacheState::Cached(_) => f.field("cached", &true),
+        }
+        .finish()
+    }
+}
@@ -7,7 +7,7 @@ use std::pin::Pin;
 use std::task::{self, Poll};
 use std::{fmt, io};
 
-use heph::net::tcp::stream::{self, TcpStream};
+use heph::net::tcp::stream::{self, ConnectOptions, TcpStream};
 use heph::{actor, rt};
 
 use crate::body::{BodyLength, EmptyBody};
@@ -18,6 +18,55 @@ use crate::{
 };
 
 /// HTTP/1.1 client.
+///
+/// The client uses a single connection to make requests, using HTTP/1.1
+/// keep-alive to reuse it for multiple requests. A response's [`Body`] must be
+/// read (or dropped) before the next request can be made.
+///
+/// # Timeouts
+///
+/// The client doesn't have timeouts build in, instead it can be combined with
+/// the timers provided by Heph. For example by using [`Deadline`] the
+/// connecting and request futures return an [`io::Error`] of kind
+/// [`io::ErrorKind::TimedOut`] if the deadline passes.
+///
+/// [`Deadline`]: heph::timer::Deadline
+///
+/// # Examples
+///
+/// ```
+/// # #![feature(never_type)]
+/// use std::io;
+/// use std::net::SocketAddr;
+/// use std::time::Duration;
+///
+/// use heph::actor;
+/// use heph::rt::ThreadLocal;
+/// use heph::timer::Deadline;
+/// use heph_http::Client;
+///
+/// const TIMEOUT: Duration = Duration::from_secs(5);
+///
+/// async fn http_actor(
+///     mut ctx: actor::Context<!, ThreadLocal>,
+///     address: SocketAddr,
+/// ) -> io::Result<()> {
+///     let connect = Client::connect(&mut ctx, address)?;
+///     let mut client = Deadline::after(&mut ctx, TIMEOUT, connect).await?;
+///
+///     // Make a GET request, timing out after 5 seconds.
+///     let mut response = Deadline::after(&mut ctx, TIMEOUT, client.get("/")).await?;
+///     let mut body = Vec::new();
+///     response.body_mut().read_all(&mut body, 1024).await?;
+///     println!("got response: {}: {:?}", response.status(), body);
+///
+///     // Reuse the connection to make another request.
+///     let response = Deadline::after(&mut ctx, TIMEOUT, client.get("/other")).await?;
+///     println!("got response: {}", response.status());
+///     Ok(())
+/// }
+/// # let _ = http_actor; // Silence dead code warnings.
+/// ```
 #[derive(Debug)]
 pub struct Client {
     stream: TcpStream,
@@ -40,6 +89,32 @@ impl Client {
         TcpStream::connect(ctx, address).map(|connect| Connect { connect })
     }
 
+    /// Create a new HTTP client, connected to `host`:`port` using `options`.
+    ///
+    /// This can be used to connect through a proxy, see
+    /// [`TcpStream::connect_to`] for more information.
+    pub fn connect_to<M, RT>(
+        ctx: &mut actor::Context<M, RT>,
+        host: &str,
+        port: u16,
+        options: ConnectOptions,
+    ) -> io::Result<ConnectTo>
+    where
+        RT: rt::Access,
+    {
+        TcpStream::connect_to(ctx, host, port, options).map(|connect| ConnectTo { connect })
+    }
+
+    /// Create a new client from a connected `stream`.
+    fn new(mut stream: TcpStream) -> io::Result<Client> {
+        stream.set_nodelay(true)?;
+        Ok(Client {
+            stream,
+            buf: Vec::with_capacity(BUF_SIZE),
+            parsed_bytes: 0,
+        })
+    }
+
     /// Send a GET request.
     ///
     /// # Notes
@@ -57,6 +132,32 @@ impl Client {
         }
     }
 
+    /// Send a POST request with `body`.
+    ///
+    /// # Notes
+    ///
+    /// Any [`ResponseError`] are turned into [`io::Error`]. If you want to
+    /// handle the `ResponseError`s separately use [`Client::request`].
+    // The returned future is only `Send` if `B` is, which isn't required.
+    #[allow(clippy::future_not_send)]
+    pub async fn post<'c, 'b, B>(
+        &'c mut self,
+        path: &str,
+        body: B,
+    ) -> io::Result<Response<Body<'c>>>
+    where
+        B: crate::Body<'b>,
+    {
+        let res = self
+            .request(Method::Post, path, &Headers::EMPTY, body)
+            .await;
+        match res {
+            Ok(Ok(response)) => Ok(response),
+            Ok(Err(err)) => Err(err.into()),
+            Err(err) => Err(err),
+        }
+    }
+
     /// Make a [`Request`] and wait (non-blocking) for a [`Response`].
     ///
     /// [`Request`]: crate::Request
@@ -477,14 +578,25 @@ impl Future for Connect {
     #[track_caller]
     fn poll(mut self: Pin<&mut Self>, ctx: &mut task::Context<'_>) -> Poll<Self::Output> {
         match Pin::new(&mut self.connect).poll(ctx) {
-            Poll::Ready(Ok(mut stream)) => {
-                stream.set_nodelay(true)?;
-                Poll::Ready(Ok(Client {
-                    stream,
-                    buf: Vec::with_capacity(BUF_SIZE),
-                    parsed_bytes: 0,
-                }))
-            }
+            Poll::Ready(Ok(stream)) => Poll::Ready(Client::new(stream)),
+            Poll::Ready(Err(err)) => Poll::Ready(Err(err)),
+            Poll::Pending => Poll::Pending,
+        }
+    }
+}
+
+/// [`Future`] behind [`Client::connect_to`].
+#[derive(Debug)]
+pub struct ConnectTo {
+    connect: stream::ConnectTo,
+}
+
+impl Future for ConnectTo {
+    type Output = io::Result<Client>;
+
+    fn poll(mut self: Pin<&mut Self>, ctx: &mut task::Context<'_>) -> Poll<Self::Output> {
+        match Pin::new(&mut self.connect).poll(ctx) {
+            Poll::Ready(Ok(stream)) => Poll::Ready(Client::new(stream)),
             Poll::Ready(Err(err)) => Poll::Ready(Err(err)),
             Poll::Pending => Poll::Pending,
         }
@@ -752,3 +864,5 @@ impl fmt::Display for ResponseError {
         f.write_str(self.as_str())
     }
 }
+
+impl std::error::Error for ResponseError {}
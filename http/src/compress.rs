@@ -0,0 +1,543 @@
+//! Module with response compression.
+//!
+//! Responses can be compressed using gzip or deflate, and brotli if the
+//! `brotli` feature is enabled. The encoding is negotiated based on the
+//! Accept-Encoding header of the request, see [`Encoding::negotiate`].
+//!
+//! There are two ways to compress a response:
+//!  * [`CompressionMiddleware`] compresses responses with a [`SharedBody`],
+//!    i.e. bodies that are already in memory, once they're returned by the
+//!    wrapped handler.
+//!  * [`CompressedBody`] compresses a streaming body (a [`Stream`] of bytes)
+//!    while it's being send, without buffering the entire body. The body is
+//!    send using chunked encoding as the length of the compressed body is not
+//!    known in advance.
+//!
+//! [`SharedBody`]: crate::body::SharedBody
+//! [`Stream`]: std::stream::Stream
+//!
+//! When combining the [`CacheMiddleware`] with the compression middleware make
+//! sure to include the Accept-Encoding header in the cache key (see
+//! [`CacheConfig::vary`]) if the cache wraps the compression middleware.
+//!
+//! [`CacheMiddleware`]: crate::cache::CacheMiddleware
+//! [`CacheConfig::vary`]: crate::cache::CacheConfig::vary
+//!
+//! This module requires the `compression` feature.
+//!
+//! # Examples
+//!
+//! Using the compression middleware.
+//!
+//! ```
+//! use heph_http::body::SharedBody;
+//! use heph_http::compress::CompressionMiddleware;
+//! use heph_http::handler::Handler;
+//! use heph_http::{Request, Response};
+//!
+//! async fn handler<B>(_: Request<B>) -> Response<SharedBody> {
+//!     Response::ok().with_body("Hello world".repeat(100).into())
+//! }
+//!
+//! let middleware = CompressionMiddleware::new(handler);
+//! # use heph_http::body::EmptyBody;
+//! # fn assert_handler<H: Handler<Req>, Req>(_: H) {}
+//! # assert_handler::<_, (Request<EmptyBody>,)>(middleware);
+//! ```
+//!
+//! Compressing a streaming body.
+//!
+//! ```
+//! use std::io;
+//! use std::stream::Stream;
+//!
+//! use heph_http::body::Body;
+//! use heph_http::compress::{CompressedBody, Encoding};
+//! use heph_http::{Header, HeaderName, Request, Response};
+//!
+//! fn respond<'b, B, S>(request: &Request<B>, stream: S) -> Option<Response<impl Body<'b>>>
+//! where
+//!     S: Stream<Item = io::Result<&'b [u8]>>,
+//! {
+//!     let encoding = Encoding::negotiate(request.headers())?;
+//!     let mut response = Response::ok().with_body(CompressedBody::new(encoding, stream));
+//!     let value = encoding.as_str().as_bytes();
+//!     response.headers_mut().append(Header::new(HeaderName::CONTENT_ENCODING, value));
+//!     response.headers_mut().append(Header::new(HeaderName::VARY, b"Accept-Encoding"));
+//!     Some(response)
+//! }
+//! ```
+
+use std::future::Future;
+use std::io::{self, Write};
+use std::marker::PhantomData;
+use std::mem::take;
+use std::pin::Pin;
+use std::stream::Stream;
+use std::task::{self, ready, Poll};
+use std::{fmt, str};
+
+use flate2::write::{GzEncoder, ZlibEncoder};
+use heph::net::TcpStream;
+
+use crate::body::{Body, BodyLength, PrivateBody, SharedBody};
+use crate::handler::{AsRequest, Handler, Layer};
+use crate::{Header, HeaderName, Headers, Response};
+
+/// Last chunk of a chunked body.
+const LAST_CHUNK: &[u8] = b"0\r\n\r\n";
+
+/// Default minimum size of a body to be compressed, see
+/// [`Compression::min_size`].
+const DEFAULT_MIN_SIZE: usize = 1024;
+
+/// Content encoding used to compress a body.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+#[non_exhaustive]
+pub enum Encoding {
+    /// Brotli, `br`.
+    ///
+    /// Requires the `brotli` feature.
+    #[cfg(feature = "brotli")]
+    Brotli,
+    /// Gzip, `gzip`.
+    Gzip,
+    /// Zlib, `deflate`.
+    Deflate,
+}
+
+impl Encoding {
+    /// All supported encodings, in order of preference.
+    const ALL: &'static [Encoding] = &[
+        #[cfg(feature = "brotli")]
+        Encoding::Brotli,
+        Encoding::Gzip,
+        Encoding::Deflate,
+    ];
+
+    /// Negotiate the encoding to use based on the Accept-Encoding header in
+    /// `headers`.
+    ///
+    /// Returns `None` if the header is not present or none of the supported
+    /// encodings are acceptable. See [`Encoding::from_accept_encoding`].
+    pub fn negotiate(headers: &Headers) -> Option<Encoding> {
+        let value = headers.get_bytes(&HeaderName::ACCEPT_ENCODING)?;
+        Encoding::from_accept_encoding(str::from_utf8(value).ok()?)
+    }
+
+    /// Select the encoding to use based on the `value` of an Accept-Encoding
+    /// header, e.g. `gzip, deflate;q=0.5`.
+    ///
+    /// The encoding with the highest quality value is selected, if multiple
+    /// encodings have the same quality value brotli is preferred over gzip,
+    /// which is preferred over deflate. The wildcard (`*`) applies to all
+    /// encodings not explicitly listed. Encodings with a quality value of zero
+    /// are never selected.
+    pub fn from_accept_encoding(value: &str) -> Option<Encoding> {
+        let mut qualities = [None; Encoding::ALL.len()];
+        let mut wildcard = None;
+        for part in value.split(',') {
+            let mut parts = part.split(';');
+            let name = parts.next().unwrap_or("").trim();
+            let quality = parts
+                .find_map(|param| {
+                    let (key, value) = param.split_once('=')?;
+                    key.trim()
+                        .eq_ignore_ascii_case("q")
+                        .then(|| value.trim().parse::<f32>().ok())
+                        .flatten()
+                })
+                .unwrap_or(1.0);
+            if name == "*" {
+                wildcard = Some(quality);
+            } else if let Some(idx) = Encoding::ALL
+                .iter()
+                .position(|e| e.as_str().eq_ignore_ascii_case(name))
+            {
+                qualities[idx] = Some(quality);
+            }
+        }
+
+        let mut selected: Option<(Encoding, f32)> = None;
+        for (encoding, quality) in Encoding::ALL.iter().zip(qualities.iter()) {
+            match quality.or(wildcard) {
+                Some(quality) if quality > 0.0 => match selected {
+                    Some((_, best)) if best >= quality => {}
+                    _ => selected = Some((*encoding, quality)),
+                },
+                _ => {}
+            }
+        }
+        selected.map(|(encoding, _)| encoding)
+    }
+
+    /// Returns the encoding as used in the Content-Encoding header.
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            #[cfg(feature = "brotli")]
+            Encoding::Brotli => "br",
+            Encoding::Gzip => "gzip",
+            Encoding::Deflate => "deflate",
+        }
+    }
+
+    /// Compress `bytes`.
+    pub fn compress(self, bytes: &[u8]) -> io::Result<Vec<u8>> {
+        let mut encoder = Encoder::new(self);
+        encoder.write(bytes)?;
+        encoder.finish()
+    }
+}
+
+impl fmt::Display for Encoding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Streaming encoder, writing into a `Vec`.
+enum Encoder {
+    #[cfg(feature = "brotli")]
+    Brotli(Box<brotli::CompressorWriter<Vec<u8>>>),
+    Gzip(GzEncoder<Vec<u8>>),
+    Deflate(ZlibEncoder<Vec<u8>>),
+}
+
+impl Encoder {
+    fn new(encoding: Encoding) -> Encoder {
+        match encoding {
+            #[cfg(feature = "brotli")]
+            Encoding::Brotli => Encoder::Brotli(Box::new(brotli::CompressorWriter::new(
+                Vec::new(),
+                4096, // Buffer size.
+                5,    // Quality, similar to gzip's default.
+                22,   // Window size.
+            ))),
+            Encoding::Gzip => Encoder::Gzip(GzEncoder::new(Vec::new(), Default::default())),
+            Encoding::Deflate => Encoder::Deflate(ZlibEncoder::new(Vec::new(), Default::default())),
+        }
+    }
+
+    /// Compress `bytes`, the output can be retrieved using
+    /// [`Encoder::take_output`].
+    fn write(&mut self, bytes: &[u8]) -> io::Result<()> {
+        match self {
+            #[cfg(feature = "brotli")]
+            Encoder::Brotli(encoder) => encoder.write_all(bytes),
+            Encoder::Gzip(encoder) => encoder.write_all(bytes),
+            Encoder::Deflate(encoder) => encoder.write_all(bytes),
+        }
+    }
+
+    /// Returns the compressed output so far.
+    fn take_output(&mut self) -> Vec<u8> {
+        match self {
+            #[cfg(feature = "brotli")]
+            Encoder::Brotli(encoder) => take(encoder.get_mut()),
+            Encoder::Gzip(encoder) => take(encoder.get_mut()),
+            Encoder::Deflate(encoder) => take(encoder.get_mut()),
+        }
+    }
+
+    /// Finish compressing, returning the remaining output.
+    fn finish(self) -> io::Result<Vec<u8>> {
+        match self {
+            #[cfg(feature = "brotli")]
+            Encoder::Brotli(encoder) => Ok(encoder.into_inner()),
+            Encoder::Gzip(encoder) => encoder.finish(),
+            Encoder::Deflate(encoder) => encoder.finish(),
+        }
+    }
+}
+
+impl fmt::Debug for Encoder {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("Encoder")
+    }
+}
+
+/// Streaming body that is compressed while it's being send.
+///
+/// The body is send using chunked encoding. Note that the Content-Encoding
+/// header is **not** set automatically, see the [module documentation] for an
+/// example.
+///
+/// [module documentation]: crate::compress
+#[derive(Debug)]
+pub struct CompressedBody<'b, B> {
+    encoding: Encoding,
+    body: B,
+    _body_lifetime: PhantomData<&'b [u8]>,
+}
+
+impl<'b, B> CompressedBody<'b, B>
+where
+    B: Stream<Item = io::Result<&'b [u8]>>,
+{
+    /// Compress the bytes of `stream` using `encoding`.
+    pub const fn new(encoding: Encoding, stream: B) -> CompressedBody<'b, B> {
+        CompressedBody {
+            encoding,
+            body: stream,
+            _body_lifetime: PhantomData,
+        }
+    }
+
+    /// Returns the encoding used to compress the body.
+    pub const fn encoding(&self) -> Encoding {
+        self.encoding
+    }
+}
+
+impl<'b, B> Body<'b> for CompressedBody<'b, B>
+where
+    B: Stream<Item = io::Result<&'b [u8]>>,
+{
+    fn length(&self) -> BodyLength {
+        BodyLength::Chunked
+    }
+}
+
+impl<'b, B> PrivateBody<'b> for CompressedBody<'b, B>
+where
+    B: Stream<Item = io::Result<&'b [u8]>>,
+{
+    type WriteBody<'s, 'h> = SendCompressedBody<'s, 'h, B>;
+
+    fn write_message<'s, 'h>(
+        self,
+        stream: &'s mut TcpStream,
+        head: &'h [u8],
+    ) -> Self::WriteBody<'s, 'h>
+    where
+        'b: 'h,
+    {
+        SendCompressedBody {
+            stream,
+            head,
+            body: self.body,
+            encoder: Some(Encoder::new(self.encoding)),
+            buf: Vec::new(),
+            written: 0,
+        }
+    }
+}
+
+/// [`Future`] behind [`CompressedBody`]'s [`Body`] implementation.
+#[derive(Debug)]
+pub struct SendCompressedBody<'s, 'h, B> {
+    stream: &'s mut TcpStream,
+    head: &'h [u8],
+    body: B,
+    /// `None` once the body is fully compressed.
+    encoder: Option<Encoder>,
+    /// Chunk(s) to write, including the chunk framing.
+    buf: Vec<u8>,
+    /// Number of bytes written from `buf`.
+    written: usize,
+}
+
+impl<'s, 'h, 'b, B> Future for SendCompressedBody<'s, 'h, B>
+where
+    B: Stream<Item = io::Result<&'b [u8]>>,
+{
+    type Output = io::Result<()>;
+
+    fn poll(self: Pin<&mut Self>, ctx: &mut task::Context<'_>) -> Poll<Self::Output> {
+        // SAFETY: not moving `body: B`, ensuring it's still pinned.
+        #[rustfmt::skip]
+        let SendCompressedBody { stream, head, body, encoder, buf, written } = unsafe { Pin::into_inner_unchecked(self) };
+        let mut body = unsafe { Pin::new_unchecked(body) };
+
+        // Send the HTTP head first.
+        while !head.is_empty() {
+            match stream.try_send(*head) {
+                Ok(0) => return Poll::Ready(Err(io::ErrorKind::WriteZero.into())),
+                Ok(n) => *head = &head[n..],
+                Err(ref err) if err.kind() == io::ErrorKind::WouldBlock => return Poll::Pending,
+                Err(ref err) if err.kind() == io::ErrorKind::Interrupted => continue,
+                Err(err) => return Poll::Ready(Err(err)),
+            }
+        }
+
+        loop {
+            // Write the compressed bytes we have.
+            while *written < buf.len() {
+                match stream.try_send(&buf[*written..]) {
+                    Ok(0) => return Poll::Ready(Err(io::ErrorKind::WriteZero.into())),
+                    Ok(n) => *written += n,
+                    Err(ref err) if err.kind() == io::ErrorKind::WouldBlock => {
+                        return Poll::Pending
+                    }
+                    Err(ref err) if err.kind() == io::ErrorKind::Interrupted => continue,
+                    Err(err) => return Poll::Ready(Err(err)),
+                }
+            }
+            buf.clear();
+            *written = 0;
+
+            let enc = match encoder.as_mut() {
+                Some(encoder) => encoder,
+                // Written the last chunk.
+                None => return Poll::Ready(Ok(())),
+            };
+            match ready!(body.as_mut().poll_next(ctx)) {
+                Some(Ok(bytes)) => {
+                    enc.write(bytes)?;
+                    extend_chunk(buf, &enc.take_output());
+                }
+                Some(Err(err)) => return Poll::Ready(Err(err)),
+                None => {
+                    // NOTE: `encoder` is `Some`, checked above.
+                    let output = encoder.take().unwrap().finish()?;
+                    extend_chunk(buf, &output);
+                    buf.extend_from_slice(LAST_CHUNK);
+                }
+            }
+        }
+    }
+}
+
+/// Add `bytes` as chunk to `buf`, if not empty.
+fn extend_chunk(buf: &mut Vec<u8>, bytes: &[u8]) {
+    if bytes.is_empty() {
+        // An empty chunk would mark the end of the body.
+        return;
+    }
+    let mut size_buf = itoa::Buffer::new();
+    buf.extend_from_slice(size_buf.format(bytes.len()).as_bytes());
+    buf.extend_from_slice(b"\r\n");
+    buf.extend_from_slice(bytes);
+    buf.extend_from_slice(b"\r\n");
+}
+
+/// [`Layer`] that wraps handlers in [`CompressionMiddleware`].
+#[derive(Copy, Clone, Debug)]
+pub struct Compression {
+    min_size: usize,
+}
+
+impl Compression {
+    /// Create a new `Compression` layer.
+    pub const fn new() -> Compression {
+        Compression {
+            min_size: DEFAULT_MIN_SIZE,
+        }
+    }
+
+    /// Only compress bodies of at least `min_size` bytes, defaults to 1024.
+    ///
+    /// Compressing small bodies is often not worth it, the compressed body can
+    /// even be larger than the original body.
+    pub const fn min_size(mut self, min_size: usize) -> Compression {
+        self.min_size = min_size;
+        self
+    }
+}
+
+impl Default for Compression {
+    fn default() -> Compression {
+        Compression::new()
+    }
+}
+
+impl<H> Layer<H> for Compression {
+    type Handler = CompressionMiddleware<H>;
+
+    fn layer(&self, handler: H) -> Self::Handler {
+        CompressionMiddleware {
+            handler,
+            min_size: self.min_size,
+        }
+    }
+}
+
+/// [`Handler`] that compresses responses, see the [module documentation].
+///
+/// Responses are not compressed if:
+///  * the request doesn't accept any of the supported encodings,
+///  * the response already has a Content-Encoding header,
+///  * the response doesn't include a body (based on the status code), or
+///  * the body is smaller than the minimum size, see
+///    [`Compression::min_size`].
+///
+/// Compressed responses get a Content-Encoding header and a Vary header with
+/// the value Accept-Encoding.
+///
+/// [module documentation]: crate::compress
+#[derive(Debug)]
+pub struct CompressionMiddleware<H> {
+    handler: H,
+    min_size: usize,
+}
+
+impl<H> CompressionMiddleware<H> {
+    /// Create new compression middleware, wrapping `handler`.
+    pub const fn new(handler: H) -> CompressionMiddleware<H> {
+        CompressionMiddleware {
+            handler,
+            min_size: DEFAULT_MIN_SIZE,
+        }
+    }
+}
+
+impl<H, Req> Handler<Req> for CompressionMiddleware<H>
+where
+    H: Handler<Req, Response = Response<SharedBody>>,
+    Req: AsRequest,
+{
+    type Response = Response<SharedBody>;
+    type Future = CompressionFuture<H::Future>;
+
+    fn handle(&self, request: Req) -> Self::Future {
+        let encoding = Encoding::negotiate(request.as_request().headers());
+        CompressionFuture {
+            future: self.handler.handle(request),
+            encoding,
+            min_size: self.min_size,
+        }
+    }
+}
+
+/// [`Future`] for the [`Handler`] implementation of [`CompressionMiddleware`].
+#[derive(Debug)]
+pub struct CompressionFuture<Fut> {
+    future: Fut,
+    encoding: Option<Encoding>,
+    min_size: usize,
+}
+
+impl<Fut> Future for CompressionFuture<Fut>
+where
+    Fut: Future<Output = Response<SharedBody>>,
+{
+    type Output = Response<SharedBody>;
+
+    fn poll(self: Pin<&mut Self>, ctx: &mut task::Context<'_>) -> Poll<Self::Output> {
+        // SAFETY: not moving the future.
+        let this = unsafe { self.get_unchecked_mut() };
+        let mut response = ready!(unsafe { Pin::new_unchecked(&mut this.future) }.poll(ctx));
+        let encoding = match this.encoding {
+            Some(encoding) => encoding,
+            None => return Poll::Ready(response),
+        };
+        if !response.status().includes_body()
+            || response.body().bytes().len() < this.min_size
+            || response
+                .headers()
+                .get(&HeaderName::CONTENT_ENCODING)
+                .is_some()
+        {
+            return Poll::Ready(response);
+        }
+
+        if let Ok(compressed) = encoding.compress(response.body().bytes()) {
+            *response.body_mut() = SharedBody::from(compressed);
+            let headers = response.headers_mut();
+            let value = encoding.as_str().as_bytes();
+            headers.append(Header::new(HeaderName::CONTENT_ENCODING, value));
+            headers.append(Header::new(HeaderName::VARY, b"Accept-Encoding"));
+        }
+        Poll::Ready(response)
+    }
+}
@@ -0,0 +1,143 @@
+//! Module with request deadlines.
+//!
+//! Clients can limit the time a server spends on a request by sending a
+//! deadline, allowing requests to be given an end-to-end time budget across
+//! multiple services. Two headers are supported:
+//!
+//!  * X-Request-Deadline: the deadline as Unix timestamp in milliseconds, e.g.
+//!    `X-Request-Deadline: 1634400000000`.
+//!  * grpc-timeout: the time remaining as (at most 8) digits followed by a unit:
+//!    `H` (hours), `M` (minutes), `S` (seconds), `m` (milliseconds), `u`
+//!    (microseconds) or `n` (nanoseconds), e.g. `grpc-timeout: 100m`.
+//!
+//! If both headers are present the earliest deadline is used. The
+//! [`HttpServer`] sets the deadline of each request using [`from_headers`],
+//! which can be retrieved using [`Request::deadline`].
+//!
+//! Handlers can use the deadline with [`heph::timer::Deadline`] to stop
+//! processing the request once it has passed. To pass the deadline on to
+//! requests made to other services (using the [`Client`]) use [`propagate`].
+//!
+//! [`HttpServer`]: crate::HttpServer
+//! [`Request::deadline`]: crate::head::RequestHead::deadline
+//! [`Client`]: crate::Client
+//!
+//! # Examples
+//!
+//! ```
+//! use std::time::{Duration, Instant};
+//!
+//! use heph_http::{deadline, Header, HeaderName, Headers};
+//!
+//! let mut headers = Headers::EMPTY;
+//! headers.append(Header::new(HeaderName::GRPC_TIMEOUT, b"100m"));
+//!
+//! let deadline = deadline::from_headers(&headers).unwrap();
+//! assert!(deadline > Instant::now());
+//! assert!(deadline <= Instant::now() + Duration::from_millis(100));
+//!
+//! // Pass the deadline on to the next service.
+//! let mut headers = Headers::EMPTY;
+//! deadline::propagate(deadline, &mut headers);
+//! assert!(headers.get(&HeaderName::GRPC_TIMEOUT).is_some());
+//! ```
+
+use std::str;
+use std::time::{Duration, Instant, SystemTime};
+
+use crate::{Header, HeaderName, Headers};
+
+/// Maximum number of digits in the value of the grpc-timeout header.
+const MAX_TIMEOUT_DIGITS: usize = 8;
+
+/// Maximum value of the grpc-timeout header (in its unit).
+const MAX_TIMEOUT_VALUE: u64 = 99_999_999;
+
+/// Units of the grpc-timeout header, with the duration of a single unit in
+/// nanoseconds. Ordered from small to large.
+const TIMEOUT_UNITS: [(u8, u64); 6] = [
+    (b'n', 1),
+    (b'u', 1_000),
+    (b'm', 1_000_000),
+    (b'S', 1_000_000_000),
+    (b'M', 60 * 1_000_000_000),
+    (b'H', 60 * 60 * 1_000_000_000),
+];
+
+/// Determine the deadline of a request based on the X-Request-Deadline and
+/// grpc-timeout `headers`.
+///
+/// Invalid header values are ignored. Returns `None` if neither header is
+/// present (or valid).
+pub fn from_headers(headers: &Headers) -> Option<Instant> {
+    let now = Instant::now();
+    let timeout = headers
+        .get_bytes(&HeaderName::GRPC_TIMEOUT)
+        .and_then(parse_timeout)
+        .and_then(|timeout| now.checked_add(timeout));
+    let deadline = headers
+        .get_bytes(&HeaderName::X_REQUEST_DEADLINE)
+        .and_then(parse_unix_deadline)
+        .and_then(|deadline| {
+            // NOTE: if the deadline has already passed we use `now`.
+            let since_epoch = SystemTime::now()
+                .duration_since(SystemTime::UNIX_EPOCH)
+                .unwrap_or_default();
+            now.checked_add(deadline.checked_sub(since_epoch).unwrap_or_default())
+        });
+    match (timeout, deadline) {
+        (Some(timeout), Some(deadline)) => Some(timeout.min(deadline)),
+        (timeout, deadline) => timeout.or(deadline),
+    }
+}
+
+/// Parse the value of a grpc-timeout header, e.g. `100m` for 100
+/// milliseconds.
+pub fn parse_timeout(value: &[u8]) -> Option<Duration> {
+    let (unit, digits) = value.split_last()?;
+    if digits.is_empty() || digits.len() > MAX_TIMEOUT_DIGITS {
+        return None;
+    }
+    let (_, nanos) = TIMEOUT_UNITS.iter().find(|(u, _)| u == unit)?;
+    let value: u64 = parse_digits(digits)?;
+    Some(Duration::from_nanos(value.saturating_mul(*nanos)))
+}
+
+/// Format `timeout` as value for the grpc-timeout header.
+///
+/// This uses the smallest unit that can represent `timeout` using at most 8
+/// digits, rounding down.
+pub fn format_timeout(timeout: Duration) -> String {
+    let nanos = timeout.as_nanos();
+    for &(unit, unit_nanos) in TIMEOUT_UNITS.iter() {
+        let value = nanos / u128::from(unit_nanos);
+        if value <= u128::from(MAX_TIMEOUT_VALUE) {
+            return format!("{}{}", value, unit as char);
+        }
+    }
+    // Timeout is too large to represent, use the largest possible value.
+    format!("{}H", MAX_TIMEOUT_VALUE)
+}
+
+/// Set the grpc-timeout header in `headers` to the time remaining until
+/// `deadline`, passing the deadline on to the next service.
+///
+/// If the deadline has already passed the timeout is set to zero.
+pub fn propagate(deadline: Instant, headers: &mut Headers) {
+    let timeout = format_timeout(deadline.saturating_duration_since(Instant::now()));
+    headers.insert(Header::new(HeaderName::GRPC_TIMEOUT, timeout.as_bytes()));
+}
+
+/// Parse the value of a X-Request-Deadline header into a duration since the
+/// Unix epoch.
+fn parse_unix_deadline(value: &[u8]) -> Option<Duration> {
+    parse_digits(value).map(Duration::from_millis)
+}
+
+/// Parse `digits` as an unsigned integer.
+fn parse_digits(digits: &[u8]) -> Option<u64> {
+    if digits.is_empty() || !digits.iter().all(u8::is_ascii_digit) {
+        return None;
+    }
+    str::from_utf8(digits).ok()?.parse().ok()
+}
@@ -0,0 +1,171 @@
+//! Module with form and query string helpers.
+//!
+//! Requires the `form` feature.
+//!
+//! [`Request::body_form`] reads the request body, encoded as
+//! `application/x-www-form-urlencoded` (as used by HTML forms), and
+//! deserialises it using [`serde`]. [`Request::query_as`] does the same for the
+//! query string of the request's path. Any errors are returned as
+//! [`FormError`], which can be converted into a response with a proper status
+//! code.
+//!
+//! # Examples
+//!
+//! ```
+//! use heph_http::body::SharedBody;
+//! use heph_http::{server, Request, Response};
+//! use serde::Deserialize;
+//!
+//! #[derive(Deserialize)]
+//! struct Search {
+//!     query: String,
+//!     page: Option<usize>,
+//! }
+//!
+//! // Handles requests such as `GET /search?query=heph&page=2`.
+//! async fn search(request: Request<server::Body<'_>>) -> Response<SharedBody> {
+//!     let search: Search = match request.query_as() {
+//!         Ok(search) => search,
+//!         // Results in a 400 Bad Request response.
+//!         Err(err) => return err.into(),
+//!     };
+//!     let page = search.page.unwrap_or(1);
+//!     let body = format!("Page {} of the results for '{}'", page, search.query);
+//!     Response::ok().with_body(body.into())
+//! }
+//! # drop(search);
+//! ```
+
+use std::{fmt, io};
+
+use serde::de::DeserializeOwned;
+
+use crate::body::SharedBody;
+use crate::server::{self, BodyTooLarge};
+use crate::{is_media_type, Header, HeaderName, Request, Response, StatusCode};
+
+/// Default maximum size of a form request body, used by
+/// [`Request::body_form`].
+pub const MAX_BODY_SIZE: usize = 64 * 1024;
+
+/// Media type of url encoded forms.
+const MEDIA_TYPE: &str = "application/x-www-form-urlencoded";
+
+impl<'a> Request<server::Body<'a>> {
+    /// Read the request body and deserialise it as url encoded form.
+    ///
+    /// This is limited to bodies up to [`MAX_BODY_SIZE`] bytes, see
+    /// [`Request::body_form_limited`] to use a different limit.
+    pub async fn body_form<T>(&mut self) -> Result<T, FormError>
+    where
+        T: DeserializeOwned,
+    {
+        self.body_form_limited(MAX_BODY_SIZE).await
+    }
+
+    /// Read the request body, up to `limit` bytes, and deserialise it as url
+    /// encoded form.
+    ///
+    /// If the request has a Content-Type header it must be
+    /// `application/x-www-form-urlencoded`, otherwise
+    /// [`FormError::UnsupportedMediaType`] is returned.
+    pub async fn body_form_limited<T>(&mut self, limit: usize) -> Result<T, FormError>
+    where
+        T: DeserializeOwned,
+    {
+        if let Some(content_type) = self.headers().get_bytes(&HeaderName::CONTENT_TYPE) {
+            if !is_media_type(content_type, MEDIA_TYPE) {
+                return Err(FormError::UnsupportedMediaType);
+            }
+        }
+
+        let mut buf = Vec::new();
+        match self.body_mut().read_all(&mut buf, limit).await {
+            Ok(()) => {}
+            Err(ref err) if BodyTooLarge::is(err) => return Err(FormError::TooLarge),
+            Err(err) => return Err(FormError::Io(err)),
+        }
+        serde_urlencoded::from_bytes(&buf).map_err(FormError::Parse)
+    }
+}
+
+impl<B> Request<B> {
+    /// Deserialise the query string of the request's path.
+    ///
+    /// If the path doesn't have a query string it's deserialised as an empty
+    /// query string.
+    pub fn query_as<T>(&self) -> Result<T, FormError>
+    where
+        T: DeserializeOwned,
+    {
+        let query = match self.path().split_once('?') {
+            Some((_, query)) => query,
+            None => "",
+        };
+        serde_urlencoded::from_str(query).map_err(FormError::Parse)
+    }
+}
+
+/// Error returned by [`Request::body_form`] and [`Request::query_as`].
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum FormError {
+    /// The Content-Type header of the request is not
+    /// `application/x-www-form-urlencoded`.
+    UnsupportedMediaType,
+    /// Request body is larger than the limit.
+    TooLarge,
+    /// I/O error reading the request body.
+    Io(io::Error),
+    /// Request body or query string is not valid, or doesn't match the
+    /// expected type.
+    Parse(serde_urlencoded::de::Error),
+}
+
+impl FormError {
+    /// Returns the proper status code for a given error.
+    pub const fn proper_status_code(&self) -> StatusCode {
+        match self {
+            FormError::UnsupportedMediaType => StatusCode::UNSUPPORTED_MEDIA_TYPE,
+            FormError::TooLarge => StatusCode::PAYLOAD_TOO_LARGE,
+            FormError::Io(_) | FormError::Parse(_) => StatusCode::BAD_REQUEST,
+        }
+    }
+}
+
+/// Response with the [proper status code] and a plain text description of the
+/// error as body.
+///
+/// [proper status code]: FormError::proper_status_code
+impl From<FormError> for Response<SharedBody> {
+    fn from(err: FormError) -> Response<SharedBody> {
+        let mut response = Response::ok().with_body(SharedBody::from(err.to_string()));
+        *response.status_mut() = err.proper_status_code();
+        response.headers_mut().insert(Header::new(
+            HeaderName::CONTENT_TYPE,
+            b"text/plain; charset=utf-8",
+        ));
+        response
+    }
+}
+
+impl fmt::Display for FormError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FormError::UnsupportedMediaType => f.write_str("expected form Content-Type"),
+            FormError::TooLarge => f.write_str("body too large"),
+            FormError::Io(err) => write!(f, "error reading body: {}", err),
+            FormError::Parse(err) => write!(f, "invalid form: {}", err),
+        }
+    }
+}
+
+impl std::error::Error for FormError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            FormError::Io(err) => Some(err),
+            FormError::Parse(err) => Some(err),
+            _ => None,
+        }
+    }
+}
@@ -1,12 +1,59 @@
-//! Module with the [`Handler`] and [`Middleware`] traits.
+//! Module with the [`Handler`], [`Middleware`] and [`Layer`] traits.
 //!
 //! [`Handler`]s are used to process a single request.
 //!
 //! [`Middleware`] is a `Handler` that wraps another handler to processes the
 //! request. The middleware itself can transform the request and/or have side
 //! effects such as logging the request.
+//!
+//! A [`Layer`] creates middleware by wrapping a handler, layers can be combined
+//! using [`Stack`] to apply multiple middlewares to a handler at once.
+//!
+//! The following middleware is included:
+//!  * [`AccessLogMiddleware`]: logs all requests,
+//!  * [`AuthMiddleware`]: rejects unauthorised requests,
+//!  * [`CacheMiddleware`]: caches responses,
+//!  * [`LoadShedMiddleware`]: sheds load when overloaded,
+//!  * [`RateLimitMiddleware`]: limits the number of requests per client,
+//!  * [`RequestIdMiddleware`]: adds a request id to requests and responses,
+//!  * [`TransformMiddleware`]: transforms request and response types.
+//!
+//! [`AccessLogMiddleware`]: crate::access_log::AccessLogMiddleware
+//! [`AuthMiddleware`]: crate::auth::AuthMiddleware
+//! [`CacheMiddleware`]: crate::cache::CacheMiddleware
+//! [`LoadShedMiddleware`]: crate::load_shed::LoadShedMiddleware
+//! [`RateLimitMiddleware`]: crate::rate_limit::RateLimitMiddleware
+//! [`RequestIdMiddleware`]: crate::request_id::RequestIdMiddleware
+//! [`TransformMiddleware`]: crate::transform::TransformMiddleware
+//!
+//! # Examples
+//!
+//! Using layers to wrap a handler in multiple middlewares.
+//!
+//! ```
+//! use heph_http::access_log::AccessLog;
+//! use heph_http::body::OneshotBody;
+//! use heph_http::handler::{Handler, Layer, Stack};
+//! use heph_http::request_id::RequestIds;
+//! use heph_http::{Request, Response};
+//!
+//! async fn handler<B>(_: Request<B>) -> Response<OneshotBody<'static>> {
+//!     Response::ok().with_body("Hello world".into())
+//! }
+//!
+//! // First add a request id to the request, then log the request (including
+//! // the request id).
+//! let layers = Stack::new(AccessLog, RequestIds::new());
+//! let handler = layers.layer(handler);
+//! # use heph_http::body::EmptyBody;
+//! # fn assert_handler<H: Handler<Req>, Req>(_: H) {}
+//! # assert_handler::<_, (Request<EmptyBody>,)>(handler);
+//! ```
 
 use std::future::Future;
+use std::net::SocketAddr;
+
+use crate::Request;
 
 /// Handler is the trait that defines how a single request is handled.
 ///
@@ -89,3 +136,105 @@ pub trait Middleware<H, Req>: Handler<Req> {
     where
         H: Handler<Req>;
 }
+
+/// Layer creates [`Middleware`] by wrapping a [`Handler`].
+///
+/// Layers make it possible to configure middleware once and apply it to
+/// multiple handlers, e.g. all routes of a [`Router`]. Multiple layers can be
+/// combined using [`Stack`].
+///
+/// [`Router`]: crate::Router
+pub trait Layer<H> {
+    /// The wrapped handler.
+    type Handler;
+
+    /// Wrap `handler`.
+    fn layer(&self, handler: H) -> Self::Handler;
+}
+
+/// Combination of two [`Layer`]s.
+///
+/// The `inner` layer wraps the handler first, the `outer` layer wraps the
+/// result of that. This means that the middleware created by the `outer` layer
+/// sees the request first and the response last.
+#[derive(Clone, Debug)]
+pub struct Stack<Inner, Outer> {
+    inner: Inner,
+    outer: Outer,
+}
+
+impl<Inner, Outer> Stack<Inner, Outer> {
+    /// Create a new stack of layers.
+    pub const fn new(inner: Inner, outer: Outer) -> Stack<Inner, Outer> {
+        Stack { inner, outer }
+    }
+
+    /// Add another layer to the stack, wrapping all current layers.
+    pub const fn push<L>(self, outer: L) -> Stack<Stack<Inner, Outer>, L> {
+        Stack { inner: self, outer }
+    }
+}
+
+impl<H, Inner, Outer> Layer<H> for Stack<Inner, Outer>
+where
+    Inner: Layer<H>,
+    Outer: Layer<Inner::Handler>,
+{
+    type Handler = Outer::Handler;
+
+    fn layer(&self, handler: H) -> Self::Handler {
+        self.outer.layer(self.inner.layer(handler))
+    }
+}
+
+/// Trait to get the [`Request`] from the request type of a [`Handler`].
+///
+/// This is implemented for `Request<B>`, `(Request<B>,)` and
+/// `(Request<B>, SocketAddr)`, the latter two are the request types of
+/// function handlers.
+pub trait AsRequest {
+    /// Body of the request.
+    type Body;
+
+    /// Returns a reference to the request.
+    fn as_request(&self) -> &Request<Self::Body>;
+
+    /// Returns a mutable reference to the request.
+    fn as_request_mut(&mut self) -> &mut Request<Self::Body>;
+}
+
+impl<B> AsRequest for Request<B> {
+    type Body = B;
+
+    fn as_request(&self) -> &Request<B> {
+        self
+    }
+
+    fn as_request_mut(&mut self) -> &mut Request<B> {
+        self
+    }
+}
+
+impl<B> AsRequest for (Request<B>,) {
+    type Body = B;
+
+    fn as_request(&self) -> &Request<B> {
+        &self.0
+    }
+
+    fn as_request_mut(&mut self) -> &mut Request<B> {
+        &mut self.0
+    }
+}
+
+impl<B> AsRequest for (Request<B>, SocketAddr) {
+    type Body = B;
+
+    fn as_request(&self) -> &Request<B> {
+        &self.0
+    }
+
+    fn as_request_mut(&mut self) -> &mut Request<B> {
+        &mut self.0
+    }
+}
@@ -740,6 +740,8 @@ impl<'n> HeaderName<'n> {
             (REFERER_ROOT, "referer-root"),
             #[doc = "X-Request-ID."]
             (X_REQUEST_ID, "x-request-id"),
+            #[doc = "grpc-timeout.\n\n<https://github.com/grpc/grpc/blob/master/doc/PROTOCOL-HTTP2.md>."]
+            (GRPC_TIMEOUT, "grpc-timeout"),
         ],
         13: [
             #[doc = "Accept-Ranges.\n\nRFC 7233 section 2.3."]
@@ -868,6 +870,8 @@ impl<'n> HeaderName<'n> {
             (PREFERENCE_APPLIED, "preference-applied"),
             #[doc = "Proxy-Authenticate.\n\nRFC 7235 section 4.3."]
             (PROXY_AUTHENTICATE, "proxy-authenticate"),
+            #[doc = "X-Request-Deadline."]
+            (X_REQUEST_DEADLINE, "x-request-deadline"),
         ],
         19: [
             #[doc = "Authentication-Info.\n\nRFC 7615 section 3."]
@@ -1074,6 +1078,8 @@ impl fmt::Display for ParseIntError {
     }
 }
 
+impl std::error::Error for ParseIntError {}
+
 macro_rules! int_impl {
     ($( $ty: ty ),+) => {
         $(
@@ -1128,6 +1134,8 @@ impl fmt::Display for ParseTimeError {
     }
 }
 
+impl std::error::Error for ParseTimeError {}
+
 /// Parses the value following RFC7231 section 7.1.1.1.
 impl FromHeaderValue<'_> for SystemTime {
     type Err = ParseTimeError;
@@ -8,7 +8,7 @@ use crate::cmp_lower_case;
 /// HTTP method.
 ///
 /// RFC 7231 section 4.
-#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
 pub enum Method {
     /// GET method.
     ///
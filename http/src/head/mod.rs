@@ -1,6 +1,7 @@
 //! Module with the type part of a HTTP message head.
 
 use std::fmt;
+use std::time::Instant;
 
 pub mod header;
 pub mod method;
@@ -24,6 +25,7 @@ pub struct RequestHead {
     pub(crate) path: String,
     version: Version,
     pub(crate) headers: Headers,
+    deadline: Option<Instant>,
 }
 
 impl RequestHead {
@@ -39,6 +41,7 @@ impl RequestHead {
             path,
             version,
             headers,
+            deadline: None,
         }
     }
 
@@ -96,6 +99,22 @@ impl RequestHead {
         self.headers.get_value(name)
     }
 
+    /// Returns the deadline of the request, if any.
+    ///
+    /// Requests from the [`HttpServer`] have their deadline set based on the
+    /// headers of the request, see the [`deadline`] module.
+    ///
+    /// [`HttpServer`]: crate::HttpServer
+    /// [`deadline`]: crate::deadline
+    pub const fn deadline(&self) -> Option<Instant> {
+        self.deadline
+    }
+
+    /// Returns a mutable reference to the deadline of this request.
+    pub const fn deadline_mut(&mut self) -> &mut Option<Instant> {
+        &mut self.deadline
+    }
+
     /// Get the header’s value with `name` or return `default`.
     ///
     /// If no header with `name` is found or the [`FromHeaderValue`]
@@ -139,6 +158,7 @@ impl fmt::Debug for RequestHead {
             .field("path", &self.path)
             .field("version", &self.version)
             .field("headers", &self.headers)
+            .field("deadline", &self.deadline)
             .finish()
     }
 }
@@ -0,0 +1,162 @@
+//! Module with JSON body helpers.
+//!
+//! Requires the `json` feature.
+//!
+//! [`Request::body_json`] reads the request body and deserialises it, using
+//! [`serde`]. Any errors are returned as [`JsonError`], which can be converted
+//! into a response with a proper status code. [`Response::json`] does the
+//! reverse, serialising a value into a response body.
+//!
+//! # Examples
+//!
+//! ```
+//! use heph_http::body::SharedBody;
+//! use heph_http::{server, Request, Response};
+//! use serde_json::Value;
+//!
+//! async fn echo_json(mut request: Request<server::Body<'_>>) -> Response<SharedBody> {
+//!     let value: Value = match request.body_json().await {
+//!         Ok(value) => value,
+//!         // Results in a 400 Bad Request, 413 Payload Too Large or 415
+//!         // Unsupported Media Type response.
+//!         Err(err) => return err.into(),
+//!     };
+//!     match Response::json(&value) {
+//!         Ok(response) => response,
+//!         Err(_) => Response::internal_server_error().with_body("".into()),
+//!     }
+//! }
+//! # drop(echo_json);
+//! ```
+
+use std::{fmt, io};
+
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+use crate::body::SharedBody;
+use crate::server::{self, BodyTooLarge};
+use crate::{is_media_type, Header, HeaderName, Request, Response, StatusCode};
+
+/// Default maximum size of a JSON request body, used by
+/// [`Request::body_json`].
+pub const MAX_BODY_SIZE: usize = 1024 * 1024;
+
+/// Media type of JSON.
+const MEDIA_TYPE: &str = "application/json";
+
+impl<'a> Request<server::Body<'a>> {
+    /// Read the request body and deserialise it as JSON.
+    ///
+    /// This is limited to bodies up to [`MAX_BODY_SIZE`] bytes, see
+    /// [`Request::body_json_limited`] to use a different limit.
+    pub async fn body_json<T>(&mut self) -> Result<T, JsonError>
+    where
+        T: DeserializeOwned,
+    {
+        self.body_json_limited(MAX_BODY_SIZE).await
+    }
+
+    /// Read the request body, up to `limit` bytes, and deserialise it as JSON.
+    ///
+    /// If the request has a Content-Type header it must be
+    /// `application/json`, otherwise [`JsonError::UnsupportedMediaType`] is
+    /// returned.
+    pub async fn body_json_limited<T>(&mut self, limit: usize) -> Result<T, JsonError>
+    where
+        T: DeserializeOwned,
+    {
+        if let Some(content_type) = self.headers().get_bytes(&HeaderName::CONTENT_TYPE) {
+            if !is_media_type(content_type, MEDIA_TYPE) {
+                return Err(JsonError::UnsupportedMediaType);
+            }
+        }
+
+        let mut buf = Vec::new();
+        match self.body_mut().read_all(&mut buf, limit).await {
+            Ok(()) => {}
+            Err(ref err) if BodyTooLarge::is(err) => return Err(JsonError::TooLarge),
+            Err(err) => return Err(JsonError::Io(err)),
+        }
+        serde_json::from_slice(&buf).map_err(JsonError::Parse)
+    }
+}
+
+impl Response<SharedBody> {
+    /// Create a 200 OK response with `value` serialised as JSON body.
+    ///
+    /// This sets the Content-Type header to `application/json`.
+    pub fn json<T>(value: &T) -> Result<Response<SharedBody>, serde_json::Error>
+    where
+        T: Serialize + ?Sized,
+    {
+        let body = serde_json::to_vec(value)?;
+        let mut response = Response::ok().with_body(SharedBody::from(body));
+        response
+            .headers_mut()
+            .insert(Header::new(HeaderName::CONTENT_TYPE, MEDIA_TYPE.as_bytes()));
+        Ok(response)
+    }
+}
+
+/// Error returned by [`Request::body_json`].
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum JsonError {
+    /// The Content-Type header of the request is not `application/json`.
+    UnsupportedMediaType,
+    /// Request body is larger than the limit.
+    TooLarge,
+    /// I/O error reading the request body.
+    Io(io::Error),
+    /// Request body is not valid JSON, or doesn't match the expected type.
+    Parse(serde_json::Error),
+}
+
+impl JsonError {
+    /// Returns the proper status code for a given error.
+    pub const fn proper_status_code(&self) -> StatusCode {
+        match self {
+            JsonError::UnsupportedMediaType => StatusCode::UNSUPPORTED_MEDIA_TYPE,
+            JsonError::TooLarge => StatusCode::PAYLOAD_TOO_LARGE,
+            JsonError::Io(_) | JsonError::Parse(_) => StatusCode::BAD_REQUEST,
+        }
+    }
+}
+
+/// Response with the [proper status code] and a plain text description of the
+/// error as body.
+///
+/// [proper status code]: JsonError::proper_status_code
+impl From<JsonError> for Response<SharedBody> {
+    fn from(err: JsonError) -> Response<SharedBody> {
+        let mut response = Response::ok().with_body(SharedBody::from(err.to_string()));
+        *response.status_mut() = err.proper_status_code();
+        response.headers_mut().insert(Header::new(
+            HeaderName::CONTENT_TYPE,
+            b"text/plain; charset=utf-8",
+        ));
+        response
+    }
+}
+
+impl fmt::Display for JsonError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            JsonError::UnsupportedMediaType => f.write_str("expected JSON Content-Type"),
+            JsonError::TooLarge => f.write_str("body too large"),
+            JsonError::Io(err) => write!(f, "error reading body: {}", err),
+            JsonError::Parse(err) => write!(f, "invalid JSON: {}", err),
+        }
+    }
+}
+
+impl std::error::Error for JsonError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            JsonError::Io(err) => Some(err),
+            JsonError::Parse(err) => Some(err),
+            _ => None,
+        }
+    }
+}
@@ -1,4 +1,9 @@
 //! HTTP/1.1 implementation for Heph.
+//!
+//! HTTP/2 is not supported. The server only rejects HTTP/2 connections
+//! cleanly, asking the client to retry using HTTP/1.1, see
+//! [`server::Connection::next_request`]. Requests to upgrade to HTTP/2 (using
+//! the `Upgrade: h2c` header) are ignored and handled as HTTP/1.1 requests.
 
 #![feature(
     async_stream,
@@ -26,16 +31,31 @@
     variant_size_differences
 )]
 
+pub mod access_log;
+pub mod auth;
 pub mod body;
+pub mod cache;
 pub mod client;
+#[cfg(feature = "compression")]
+pub mod compress;
+pub mod deadline;
+#[cfg(feature = "form")]
+pub mod form;
 pub mod handler;
 pub mod head;
+#[cfg(feature = "json")]
+pub mod json;
+pub mod load_shed;
+pub mod rate_limit;
 mod request;
+pub mod request_id;
 mod response;
 mod route;
+pub mod router;
 pub mod server;
 mod str;
 pub mod transform;
+pub mod websocket;
 
 #[doc(no_inline)]
 pub use body::Body;
@@ -52,6 +72,8 @@ pub use head::StatusCode;
 pub use request::Request;
 pub use response::Response;
 #[doc(no_inline)]
+pub use router::Router;
+#[doc(no_inline)]
 pub use server::{Connection, HttpServer};
 
 /// Maximum size of the HTTP head (the start line and the headers).
@@ -108,6 +130,17 @@ fn trim_ws(value: &[u8]) -> &[u8] {
     &value[start..=end]
 }
 
+/// Returns `true` if the Content-Type header `value` is `media_type`, ignoring
+/// any parameters.
+#[cfg(any(feature = "json", feature = "form"))]
+fn is_media_type(value: &[u8], media_type: &str) -> bool {
+    let value = match value.iter().position(|b| *b == b';') {
+        Some(idx) => &value[..idx],
+        None => value,
+    };
+    trim_ws(value).eq_ignore_ascii_case(media_type.as_bytes())
+}
+
 /// Returns `true` if `lower_case` and `right` are a case-insensitive match.
 ///
 /// # Notes
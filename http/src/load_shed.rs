@@ -0,0 +1,298 @@
+//! Module with the load shedding middleware.
+//!
+//! [`LoadShedMiddleware`] protects a worker thread from overload. Once the
+//! worker is considered overloaded requests are not passed to the wrapped
+//! handler (or queued), instead a 503 Service Unavailable response is returned
+//! immediately (see [`ServiceUnavailable`]). This keeps the latency of the
+//! requests that are handled bounded, rather than letting all requests wait.
+//!
+//! The worker is considered overloaded if either:
+//!  * the number of requests being handled (in-flight), or
+//!  * the number of processes ready to run on the worker thread (see
+//!    [`RuntimeRef::ready_processes`]),
+//!
+//! exceeds the thresholds configured in the [`LoadShedder`]. Like the
+//! [`RateLimiter`] the load shedder can be cloned cheaply and all clones share
+//! the same state, but the state is **not** shared between worker threads.
+//!
+//! [`RateLimiter`]: crate::rate_limit::RateLimiter
+//!
+//! # Examples
+//!
+//! ```
+//! use std::time::Duration;
+//!
+//! use heph::rt::RuntimeRef;
+//! use heph_http::body::EmptyBody;
+//! use heph_http::handler::Handler;
+//! use heph_http::load_shed::{LoadShedMiddleware, LoadShedder};
+//! use heph_http::{Request, Response};
+//!
+//! async fn handler(_: Request<EmptyBody>) -> Response<EmptyBody> {
+//!     Response::ok()
+//! }
+//!
+//! # fn setup(runtime_ref: RuntimeRef) {
+//! // Allow at most 100 requests to be handled at the same time and shed load
+//! // if more than 500 processes are waiting to run, asking clients to retry
+//! // after a second.
+//! let shedder = LoadShedder::new(Duration::from_secs(1))
+//!     .max_in_flight(100)
+//!     .max_ready_processes(runtime_ref, 500);
+//! let middleware = LoadShedMiddleware::new(handler, shedder);
+//! # fn assert_handler<H: Handler<Req>, Req>(_: H) {}
+//! # assert_handler::<_, (Request<EmptyBody>,)>(middleware);
+//! # }
+//! ```
+
+use std::cell::Cell;
+use std::fmt;
+use std::future::Future;
+use std::pin::Pin;
+use std::rc::Rc;
+use std::task::{self, Poll};
+use std::time::Duration;
+
+use heph::rt::RuntimeRef;
+
+use crate::body::{EmptyBody, OneshotBody};
+use crate::handler::{Handler, Layer};
+use crate::rate_limit::set_retry_after;
+use crate::Response;
+
+/// Overload thresholds and state used by [`LoadShedMiddleware`].
+///
+/// See the [module documentation] for more information.
+///
+/// [module documentation]: crate::load_shed
+#[derive(Clone)]
+pub struct LoadShedder {
+    /// Duration sent to the clients in the Retry-After header.
+    retry_after: Duration,
+    /// Maximum number of in-flight requests.
+    max_in_flight: Option<usize>,
+    /// Maximum number of processes ready to run.
+    max_ready_processes: Option<(RuntimeRef, usize)>,
+    /// Number of requests currently being handled.
+    in_flight: Rc<Cell<usize>>,
+}
+
+impl LoadShedder {
+    /// Create a new `LoadShedder`, responding with a Retry-After header of
+    /// `retry_after` when shedding load.
+    ///
+    /// Without setting any thresholds no load is shed, see
+    /// [`LoadShedder::max_in_flight`] and
+    /// [`LoadShedder::max_ready_processes`].
+    pub fn new(retry_after: Duration) -> LoadShedder {
+        LoadShedder {
+            retry_after,
+            max_in_flight: None,
+            max_ready_processes: None,
+            in_flight: Rc::new(Cell::new(0)),
+        }
+    }
+
+    /// Shed load if more than `max` requests are being handled at the same
+    /// time.
+    pub fn max_in_flight(mut self, max: usize) -> LoadShedder {
+        self.max_in_flight = Some(max);
+        self
+    }
+
+    /// Shed load if more than `max` processes are ready to run on the worker
+    /// thread of `runtime_ref`.
+    ///
+    /// See [`RuntimeRef::ready_processes`].
+    pub fn max_ready_processes(mut self, runtime_ref: RuntimeRef, max: usize) -> LoadShedder {
+        self.max_ready_processes = Some((runtime_ref, max));
+        self
+    }
+
+    /// Returns the number of requests currently being handled.
+    pub fn in_flight(&self) -> usize {
+        self.in_flight.get()
+    }
+
+    /// Returns `true` if the worker thread is overloaded, i.e. if any of the
+    /// thresholds is exceeded.
+    pub fn is_overloaded(&self) -> bool {
+        if let Some(max) = self.max_in_flight {
+            if self.in_flight.get() >= max {
+                return true;
+            }
+        }
+        if let Some((runtime_ref, max)) = &self.max_ready_processes {
+            if runtime_ref.ready_processes() > *max {
+                return true;
+            }
+        }
+        false
+    }
+
+    /// Start handling a request, returns an error if load should be shed.
+    fn start(&self) -> Result<InFlight, ServiceUnavailable> {
+        if self.is_overloaded() {
+            Err(ServiceUnavailable {
+                retry_after: self.retry_after,
+            })
+        } else {
+            self.in_flight.set(self.in_flight.get() + 1);
+            Ok(InFlight {
+                in_flight: self.in_flight.clone(),
+            })
+        }
+    }
+}
+
+impl fmt::Debug for LoadShedder {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("LoadShedder")
+            .field("retry_after", &self.retry_after)
+            .field("max_in_flight", &self.max_in_flight)
+            .field(
+                "max_ready_processes",
+                &self.max_ready_processes.as_ref().map(|(_, max)| max),
+            )
+            .field("in_flight", &self.in_flight.get())
+            .finish()
+    }
+}
+
+/// Guard that marks a request as in-flight, decreasing the number of in-flight
+/// requests when dropped.
+#[derive(Debug)]
+struct InFlight {
+    in_flight: Rc<Cell<usize>>,
+}
+
+impl Drop for InFlight {
+    fn drop(&mut self) {
+        self.in_flight.set(self.in_flight.get() - 1);
+    }
+}
+
+/// Response used by [`LoadShedMiddleware`] when load is shed.
+///
+/// Can be converted into a 503 Service Unavailable [`Response`] with the
+/// Retry-After header set.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct ServiceUnavailable {
+    /// Duration after which the request can be retried.
+    pub retry_after: Duration,
+}
+
+impl From<ServiceUnavailable> for Response<EmptyBody> {
+    fn from(err: ServiceUnavailable) -> Response<EmptyBody> {
+        let mut response = Response::unavailable();
+        set_retry_after(response.headers_mut(), err.retry_after);
+        response
+    }
+}
+
+impl<'b> From<ServiceUnavailable> for Response<OneshotBody<'b>> {
+    fn from(err: ServiceUnavailable) -> Response<OneshotBody<'b>> {
+        Response::<EmptyBody>::from(err).with_body(OneshotBody::new(b""))
+    }
+}
+
+/// [`Handler`] that sheds load when the worker thread is overloaded, see the
+/// [module documentation].
+///
+/// [module documentation]: crate::load_shed
+pub struct LoadShedMiddleware<H> {
+    handler: H,
+    shedder: LoadShedder,
+}
+
+impl<H> LoadShedMiddleware<H> {
+    /// Create new load shedding middleware, wrapping `handler`.
+    pub const fn new(handler: H, shedder: LoadShedder) -> LoadShedMiddleware<H> {
+        LoadShedMiddleware { handler, shedder }
+    }
+}
+
+impl<H, Req> Handler<Req> for LoadShedMiddleware<H>
+where
+    H: Handler<Req>,
+    H::Response: From<ServiceUnavailable>,
+{
+    type Response = H::Response;
+    type Future = LoadShedFuture<H::Future, H::Response>;
+
+    fn handle(&self, request: Req) -> Self::Future {
+        let state = match self.shedder.start() {
+            Ok(in_flight) => LoadShedState::Handle(self.handler.handle(request), in_flight),
+            Err(err) => LoadShedState::Shed(Some(err.into())),
+        };
+        LoadShedFuture { state }
+    }
+}
+
+/// Using the load shedder as [`Layer`] wraps the handler in
+/// [`LoadShedMiddleware`], using (a clone of) this load shedder.
+impl<H> Layer<H> for LoadShedder {
+    type Handler = LoadShedMiddleware<H>;
+
+    fn layer(&self, handler: H) -> Self::Handler {
+        LoadShedMiddleware::new(handler, self.clone())
+    }
+}
+
+impl<H> fmt::Debug for LoadShedMiddleware<H>
+where
+    H: fmt::Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("LoadShedMiddleware")
+            .field("handler", &self.handler)
+            .field("shedder", &self.shedder)
+            .finish()
+    }
+}
+
+/// [`Future`] for the [`Handler`] implementation of [`LoadShedMiddleware`].
+pub struct LoadShedFuture<Fut, Res> {
+    state: LoadShedState<Fut, Res>,
+}
+
+enum LoadShedState<Fut, Res> {
+    /// The request is handled by the wrapped handler, the request is in-flight
+    /// until the future is dropped.
+    Handle(Fut, InFlight),
+    /// Load was shed.
+    Shed(Option<Res>),
+}
+
+impl<Fut, Res> Future for LoadShedFuture<Fut, Res>
+where
+    Fut: Future<Output = Res>,
+{
+    type Output = Res;
+
+    fn poll(self: Pin<&mut Self>, ctx: &mut task::Context<'_>) -> Poll<Self::Output> {
+        // SAFETY: not moving the future.
+        match unsafe { &mut self.get_unchecked_mut().state } {
+            LoadShedState::Handle(future, _) => unsafe { Pin::new_unchecked(future) }.poll(ctx),
+            LoadShedState::Shed(response) => Poll::Ready(
+                response
+                    .take()
+                    .expect("polled LoadShedFuture after completion"),
+            ),
+        }
+    }
+}
+
+impl<Fut, Res> fmt::Debug for LoadShedFuture<Fut, Res>
+where
+    Fut: fmt::Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut f = f.debug_struct("LoadShedFuture");
+        match &self.state {
+            LoadShedState::Handle(future, _) => f.field("future", future),
+            LoadShedState::Shed(_) => f.field("shed", &true),
+        }
+        .finish()
+    }
+}
@@ -79,6 +79,12 @@ done
 # X-Request-ID.
 header_names[12]+="#[doc = \"X-Request-ID.\"]|X_REQUEST_ID|x-request-id
 "
+# grpc-timeout.
+header_names[12]+="#[doc = \"grpc-timeout.\\\\n\\\\n<https://github.com/grpc/grpc/blob/master/doc/PROTOCOL-HTTP2.md>.\"]|GRPC_TIMEOUT|grpc-timeout
+"
+# X-Request-Deadline.
+header_names[18]+="#[doc = \"X-Request-Deadline.\"]|X_REQUEST_DEADLINE|x-request-deadline
+"
 
 for value_length in "${!header_names[@]}"; do
 	values="${header_names[$value_length]}"
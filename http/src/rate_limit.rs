@@ -0,0 +1,308 @@
+//! Module with the rate limiting middleware.
+//!
+//! [`RateLimitMiddleware`] limits the number of requests per key, e.g. per
+//! peer IP address, using a token bucket. Each key has a bucket of tokens, each
+//! request takes a token from the bucket and the bucket is refilled with a
+//! single token each refill interval, up to its capacity. If the bucket is
+//! empty the request is not passed to the wrapped handler, instead a 429 Too
+//! Many Requests response is returned (see [`TooManyRequests`]).
+//!
+//! The state of the buckets is kept in a [`RateLimiter`]. The rate limiter can
+//! be cloned cheaply, all clones share the same state. This allows all
+//! connection actors on a single worker thread to share the same rate limits.
+//! Note however that the state is **not** shared between worker threads.
+//!
+//! # Examples
+//!
+//! ```
+//! use std::net::SocketAddr;
+//! use std::time::Duration;
+//!
+//! use heph_http::body::EmptyBody;
+//! use heph_http::handler::Handler;
+//! use heph_http::rate_limit::{peer_ip, RateLimitMiddleware, RateLimiter};
+//! use heph_http::{Request, Response};
+//!
+//! async fn handler(_: Request<EmptyBody>, _: SocketAddr) -> Response<EmptyBody> {
+//!     Response::ok()
+//! }
+//!
+//! // Allow bursts of 10 requests, after which a single request per second is
+//! // allowed.
+//! let limiter = RateLimiter::new(10, Duration::from_secs(1));
+//! let middleware = RateLimitMiddleware::new(handler, limiter, peer_ip);
+//! # fn assert_handler<H: Handler<Req>, Req>(_: H) {}
+//! # assert_handler::<_, (Request<EmptyBody>, SocketAddr)>(middleware);
+//! ```
+
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::future::Future;
+use std::hash::Hash;
+use std::net::{IpAddr, SocketAddr};
+use std::pin::Pin;
+use std::rc::Rc;
+use std::task::{self, Poll};
+use std::time::{Duration, Instant};
+use std::{cmp, fmt};
+
+use crate::body::{EmptyBody, OneshotBody};
+use crate::handler::Handler;
+use crate::{Header, HeaderName, Headers, Request, Response};
+
+/// Number of keys after which buckets that are full are removed from the
+/// [`RateLimiter`], to prevent it from growing unbounded.
+const PRUNE_THRESHOLD: usize = 4096;
+
+/// State of the token buckets used by [`RateLimitMiddleware`].
+///
+/// See the [module documentation] for more information.
+///
+/// [module documentation]: crate::rate_limit
+pub struct RateLimiter<K> {
+    /// Maximum number of tokens in a bucket.
+    capacity: u32,
+    /// Interval in which a single token is added to a bucket.
+    refill_interval: Duration,
+    buckets: Rc<RefCell<HashMap<K, Bucket>>>,
+}
+
+/// Token bucket for a single key.
+#[derive(Debug)]
+struct Bucket {
+    /// Number of tokens left.
+    tokens: u32,
+    /// Last time the bucket was refilled.
+    last_refill: Instant,
+}
+
+impl<K> RateLimiter<K>
+where
+    K: Eq + Hash,
+{
+    /// Create a new `RateLimiter` allowing bursts of `capacity` requests per
+    /// key, adding a token each `refill_interval`.
+    ///
+    /// # Panics
+    ///
+    /// This will panic if `capacity` or `refill_interval` is zero.
+    pub fn new(capacity: u32, refill_interval: Duration) -> RateLimiter<K> {
+        assert!(
+            capacity != 0,
+            "can't create a RateLimiter with zero capacity"
+        );
+        assert!(
+            refill_interval != Duration::ZERO,
+            "can't create a RateLimiter with a zero refill interval"
+        );
+        RateLimiter {
+            capacity,
+            refill_interval,
+            buckets: Rc::new(RefCell::new(HashMap::new())),
+        }
+    }
+
+    /// Attempt to take a token for `key`.
+    ///
+    /// Returns an error with the duration after which a token is available if
+    /// the rate limit for `key` was reached.
+    pub fn check(&self, key: K) -> Result<(), Duration> {
+        self.check_at(key, Instant::now())
+    }
+
+    fn check_at(&self, key: K, now: Instant) -> Result<(), Duration> {
+        let mut buckets = self.buckets.borrow_mut();
+        if buckets.len() >= PRUNE_THRESHOLD {
+            // Full buckets are the same as buckets that don't exist.
+            buckets.retain(|_, bucket| !bucket.is_full(self, now));
+        }
+        let bucket = buckets.entry(key).or_insert_with(|| Bucket {
+            tokens: self.capacity,
+            last_refill: now,
+        });
+        bucket.refill(self, now);
+        if bucket.tokens > 0 {
+            bucket.tokens -= 1;
+            Ok(())
+        } else {
+            let elapsed = now.saturating_duration_since(bucket.last_refill);
+            Err(self.refill_interval.saturating_sub(elapsed))
+        }
+    }
+}
+
+impl Bucket {
+    /// Add all tokens accumulated since the last refill.
+    fn refill<K>(&mut self, limiter: &RateLimiter<K>, now: Instant) {
+        let elapsed = now.saturating_duration_since(self.last_refill);
+        let new_tokens = elapsed.as_nanos() / limiter.refill_interval.as_nanos();
+        if new_tokens == 0 {
+            return;
+        }
+        // NOTE: `new_tokens` is capped to `capacity`, so this can't truncate.
+        let new_tokens = cmp::min(new_tokens, u128::from(limiter.capacity)) as u32;
+        self.tokens = cmp::min(self.tokens.saturating_add(new_tokens), limiter.capacity);
+        if self.tokens == limiter.capacity {
+            self.last_refill = now;
+        } else {
+            self.last_refill += limiter.refill_interval * new_tokens;
+        }
+    }
+
+    /// Returns `true` if the bucket would be full at time `now`.
+    fn is_full<K>(&self, limiter: &RateLimiter<K>, now: Instant) -> bool {
+        let elapsed = now.saturating_duration_since(self.last_refill);
+        let new_tokens = elapsed.as_nanos() / limiter.refill_interval.as_nanos();
+        u128::from(self.tokens) + new_tokens >= u128::from(limiter.capacity)
+    }
+}
+
+impl<K> Clone for RateLimiter<K> {
+    fn clone(&self) -> RateLimiter<K> {
+        RateLimiter {
+            capacity: self.capacity,
+            refill_interval: self.refill_interval,
+            buckets: self.buckets.clone(),
+        }
+    }
+}
+
+impl<K> fmt::Debug for RateLimiter<K> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("RateLimiter")
+            .field("capacity", &self.capacity)
+            .field("refill_interval", &self.refill_interval)
+            .field("keys", &self.buckets.borrow().len())
+            .finish()
+    }
+}
+
+/// Key extractor for [`RateLimitMiddleware`] that uses the IP address of the
+/// peer.
+///
+/// This can be used with handlers that accept the request and the address of
+/// the peer, i.e. `Handler<(Request<B>, SocketAddr)>`.
+pub fn peer_ip<B>(request: &(Request<B>, SocketAddr)) -> IpAddr {
+    request.1.ip()
+}
+
+/// Response used by [`RateLimitMiddleware`] when the rate limit is reached.
+///
+/// Can be converted into a 429 Too Many Requests [`Response`] with the
+/// Retry-After header set.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct TooManyRequests {
+    /// Duration after which the request can be retried.
+    pub retry_after: Duration,
+}
+
+impl From<TooManyRequests> for Response<EmptyBody> {
+    fn from(err: TooManyRequests) -> Response<EmptyBody> {
+        let mut response = Response::too_many_requests();
+        set_retry_after(response.headers_mut(), err.retry_after);
+        response
+    }
+}
+
+/// Set the Retry-After header to `retry_after`.
+pub(crate) fn set_retry_after(headers: &mut Headers, retry_after: Duration) {
+    // Retry-After is in seconds, round up to not retry too early.
+    let mut seconds = retry_after.as_secs();
+    if retry_after.subsec_nanos() != 0 {
+        seconds += 1;
+    }
+    let value = seconds.to_string();
+    headers.insert(Header::new(HeaderName::RETRY_AFTER, value.as_bytes()));
+}
+
+impl<'b> From<TooManyRequests> for Response<OneshotBody<'b>> {
+    fn from(err: TooManyRequests) -> Response<OneshotBody<'b>> {
+        Response::<EmptyBody>::from(err).with_body(OneshotBody::new(b""))
+    }
+}
+
+/// [`Handler`] that limits the number of requests passed to the wrapped
+/// handler, see the [module documentation].
+///
+/// The key for the rate limit is determined using the key extractor `F`, which
+/// is called with a reference to the request. See [`peer_ip`] to use the IP
+/// address of the peer.
+///
+/// [module documentation]: crate::rate_limit
+pub struct RateLimitMiddleware<H, K, F> {
+    handler: H,
+    limiter: RateLimiter<K>,
+    key: F,
+}
+
+impl<H, K, F> RateLimitMiddleware<H, K, F> {
+    /// Create new rate limiting middleware, wrapping `handler`.
+    pub const fn new(handler: H, limiter: RateLimiter<K>, key: F) -> RateLimitMiddleware<H, K, F> {
+        RateLimitMiddleware {
+            handler,
+            limiter,
+            key,
+        }
+    }
+}
+
+impl<H, K, F, Req> Handler<Req> for RateLimitMiddleware<H, K, F>
+where
+    H: Handler<Req>,
+    H::Response: From<TooManyRequests>,
+    K: Eq + Hash,
+    F: Fn(&Req) -> K,
+{
+    type Response = H::Response;
+    type Future = RateLimitFuture<H::Future, H::Response>;
+
+    fn handle(&self, request: Req) -> Self::Future {
+        let key = (self.key)(&request);
+        match self.limiter.check(key) {
+            Ok(()) => RateLimitFuture::Handle(self.handler.handle(request)),
+            Err(retry_after) => {
+                RateLimitFuture::Limited(Some(TooManyRequests { retry_after }.into()))
+            }
+        }
+    }
+}
+
+impl<H, K, F> fmt::Debug for RateLimitMiddleware<H, K, F>
+where
+    H: fmt::Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("RateLimitMiddleware")
+            .field("handler", &self.handler)
+            .field("limiter", &self.limiter)
+            .finish()
+    }
+}
+
+/// [`Future`] for the [`Handler`] implementation of [`RateLimitMiddleware`].
+#[derive(Debug)]
+pub enum RateLimitFuture<Fut, Res> {
+    /// The request is handled by the wrapped handler.
+    Handle(Fut),
+    /// The rate limit was reached.
+    Limited(Option<Res>),
+}
+
+impl<Fut, Res> Future for RateLimitFuture<Fut, Res>
+where
+    Fut: Future<Output = Res>,
+{
+    type Output = Res;
+
+    fn poll(self: Pin<&mut Self>, ctx: &mut task::Context<'_>) -> Poll<Self::Output> {
+        // SAFETY: not moving the future.
+        match unsafe { self.get_unchecked_mut() } {
+            RateLimitFuture::Handle(future) => unsafe { Pin::new_unchecked(future) }.poll(ctx),
+            RateLimitFuture::Limited(response) => Poll::Ready(
+                response
+                    .take()
+                    .expect("polled RateLimitFuture after completion"),
+            ),
+        }
+    }
+}
@@ -0,0 +1,199 @@
+//! Module with the request id middleware.
+//!
+//! [`RequestIdMiddleware`] makes sure each request has an unique id in the
+//! X-Request-Id header, which can be used to correlate log lines of a single
+//! request (e.g. see the [`access_log`] module) or be passed to other services.
+//! If the request doesn't have an id (or the id is ignored, see
+//! [`RequestIds::ignore_incoming`]) a new id is generated and added to the
+//! request. The same id is added to the response.
+//!
+//! The state used to generate the ids is kept in [`RequestIds`]. Like the
+//! [`RateLimiter`] it can be cloned cheaply and all clones share the same
+//! state, but the state is **not** shared between worker threads. Ids are
+//! created from a random prefix, created when calling [`RequestIds::new`], and
+//! a counter.
+//!
+//! [`access_log`]: crate::access_log
+//! [`RateLimiter`]: crate::rate_limit::RateLimiter
+//!
+//! # Examples
+//!
+//! ```
+//! use heph_http::body::EmptyBody;
+//! use heph_http::handler::Handler;
+//! use heph_http::request_id::{RequestIdMiddleware, RequestIds};
+//! use heph_http::{HeaderName, Request, Response};
+//!
+//! async fn handler(request: Request<EmptyBody>) -> Response<EmptyBody> {
+//!     // The request always has an id.
+//!     let request_id = request.headers().get(&HeaderName::X_REQUEST_ID).unwrap();
+//!     # drop(request_id);
+//!     Response::ok()
+//! }
+//!
+//! let middleware = RequestIdMiddleware::new(handler, RequestIds::new());
+//! # fn assert_handler<H: Handler<Req>, Req>(_: H) {}
+//! # assert_handler::<_, (Request<EmptyBody>,)>(middleware);
+//! ```
+
+use std::cell::Cell;
+use std::collections::hash_map::RandomState;
+use std::fmt;
+use std::future::Future;
+use std::hash::{BuildHasher, Hasher};
+use std::pin::Pin;
+use std::rc::Rc;
+use std::task::{self, ready, Poll};
+
+use crate::handler::{AsRequest, Handler, Layer};
+use crate::{Header, HeaderName, Response};
+
+/// Generator of request ids used by [`RequestIdMiddleware`].
+///
+/// See the [module documentation] for more information.
+///
+/// [module documentation]: crate::request_id
+#[derive(Clone)]
+pub struct RequestIds {
+    /// Random prefix of all ids.
+    prefix: u32,
+    /// Counter for the next id.
+    next: Rc<Cell<u32>>,
+    /// Ignore the ids of incoming requests.
+    ignore_incoming: bool,
+}
+
+impl RequestIds {
+    /// Create a new `RequestIds` with a random prefix.
+    pub fn new() -> RequestIds {
+        // `RandomState` is randomly seeded, which is good enough for the
+        // prefix.
+        let prefix = RandomState::new().build_hasher().finish() as u32;
+        RequestIds {
+            prefix,
+            next: Rc::new(Cell::new(0)),
+            ignore_incoming: false,
+        }
+    }
+
+    /// Always generate a new id, ignoring (and overwriting) the id of incoming
+    /// requests.
+    ///
+    /// Use this if the clients aren't trusted to provide unique ids.
+    pub const fn ignore_incoming(mut self) -> RequestIds {
+        self.ignore_incoming = true;
+        self
+    }
+
+    /// Generate a new request id.
+    pub fn next_id(&self) -> String {
+        let n = self.next.get();
+        self.next.set(n.wrapping_add(1));
+        format!("{:08x}-{:08x}", self.prefix, n)
+    }
+}
+
+impl Default for RequestIds {
+    fn default() -> RequestIds {
+        RequestIds::new()
+    }
+}
+
+impl fmt::Debug for RequestIds {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("RequestIds")
+            .field("prefix", &self.prefix)
+            .field("next", &self.next.get())
+            .field("ignore_incoming", &self.ignore_incoming)
+            .finish()
+    }
+}
+
+/// Using the request ids as [`Layer`] wraps the handler in
+/// [`RequestIdMiddleware`], using (a clone of) this generator.
+impl<H> Layer<H> for RequestIds {
+    type Handler = RequestIdMiddleware<H>;
+
+    fn layer(&self, handler: H) -> Self::Handler {
+        RequestIdMiddleware::new(handler, self.clone())
+    }
+}
+
+/// [`Handler`] that adds an id to all requests and responses, see the [module
+/// documentation].
+///
+/// [module documentation]: crate::request_id
+#[derive(Debug)]
+pub struct RequestIdMiddleware<H> {
+    handler: H,
+    ids: RequestIds,
+}
+
+impl<H> RequestIdMiddleware<H> {
+    /// Create new request id middleware, wrapping `handler`.
+    pub const fn new(handler: H, ids: RequestIds) -> RequestIdMiddleware<H> {
+        RequestIdMiddleware { handler, ids }
+    }
+}
+
+impl<H, B, Req> Handler<Req> for RequestIdMiddleware<H>
+where
+    H: Handler<Req, Response = Response<B>>,
+    Req: AsRequest,
+{
+    type Response = Response<B>;
+    type Future = RequestIdFuture<H::Future>;
+
+    fn handle(&self, mut request: Req) -> Self::Future {
+        let headers = request.as_request_mut().headers_mut();
+        let incoming = if self.ids.ignore_incoming {
+            None
+        } else {
+            match headers.get_value::<&str>(&HeaderName::X_REQUEST_ID) {
+                Ok(Some(id)) if !id.is_empty() => Some(id.to_owned()),
+                _ => None,
+            }
+        };
+        let request_id = match incoming {
+            Some(request_id) => request_id,
+            None => {
+                let request_id = self.ids.next_id();
+                let header = Header::new(HeaderName::X_REQUEST_ID, request_id.as_bytes());
+                headers.insert(header);
+                request_id
+            }
+        };
+        RequestIdFuture {
+            future: self.handler.handle(request),
+            request_id: Some(request_id),
+        }
+    }
+}
+
+/// [`Future`] for the [`Handler`] implementation of [`RequestIdMiddleware`].
+#[derive(Debug)]
+pub struct RequestIdFuture<Fut> {
+    future: Fut,
+    /// `None` after completion.
+    request_id: Option<String>,
+}
+
+impl<Fut, B> Future for RequestIdFuture<Fut>
+where
+    Fut: Future<Output = Response<B>>,
+{
+    type Output = Response<B>;
+
+    fn poll(self: Pin<&mut Self>, ctx: &mut task::Context<'_>) -> Poll<Self::Output> {
+        // SAFETY: not moving the future.
+        let this = unsafe { self.get_unchecked_mut() };
+        let mut response = ready!(unsafe { Pin::new_unchecked(&mut this.future) }.poll(ctx));
+        let request_id = this
+            .request_id
+            .take()
+            .expect("polled RequestIdFuture after completion");
+        let header = Header::new(HeaderName::X_REQUEST_ID, request_id.as_bytes());
+        response.headers_mut().insert(header);
+        Poll::Ready(response)
+    }
+}
@@ -153,6 +153,11 @@ impl Response<EmptyBody> {
         Response::build_new(StatusCode::LENGTH_REQUIRED)
     }
 
+    /// Create a 429 Too Many Requests response.
+    pub const fn too_many_requests() -> Response<EmptyBody> {
+        Response::build_new(StatusCode::TOO_MANY_REQUESTS)
+    }
+
     /// Create a 500 Internal Server Error response.
     pub const fn server_error() -> Response<EmptyBody> {
         Response::build_new(StatusCode::INTERNAL_SERVER_ERROR)
@@ -37,6 +37,9 @@
 /// multiple methods and a path to match. The arm must be a async function that
 /// accepts the `request` and returns a response.
 ///
+/// Paths are matched exactly, to match paths with parameters (e.g.
+/// `/pet/{id}`) use the [`Router`] instead.
+///
 /// # Types
 ///
 /// The macro is untyped, but expects `request` to be a [`Request`] and will
@@ -54,6 +57,7 @@
 /// [`method`]: crate::head::RequestHead::method
 /// [`path`]: crate::head::RequestHead::path
 /// [`server::Body`]: crate::server::Body
+/// [`Router`]: crate::Router
 #[macro_export]
 macro_rules! route {
     (match $request: ident {
@@ -0,0 +1,347 @@
+//! Module with the [`Router`] type.
+//!
+//! The [`route!`] macro can be used to route requests to handlers based on
+//! static paths. The [`Router`] type also supports path patterns with
+//! parameters, e.g. `/pet/{id}`.
+//!
+//! # Patterns
+//!
+//! A path pattern is a path, starting with `/`, made up of segments
+//! separated by `/`. Each segment in the pattern is one of the following:
+//!  * a literal, e.g. `pet`, which must match the segment in the path
+//!    exactly,
+//!  * a parameter, e.g. `{id}`, which matches any single non-empty segment,
+//!  * a rest parameter, e.g. `{*path}`, which matches the remainder of the
+//!    path (including `/`), it must be the last segment in the pattern.
+//!
+//! The matched parameters are returned in [`Params`].
+//!
+//! The query of a request's path (the part after `?`) is ignored when
+//! matching.
+//!
+//! [`route!`]: crate::route
+//!
+//! # Examples
+//!
+//! Using the `Router` to route requests to different handlers.
+//!
+//! ```
+//! use heph_http::body::OneshotBody;
+//! use heph_http::router::{Params, Router};
+//! use heph_http::{Method, Request, Response};
+//!
+//! /// The routes of our application.
+//! #[derive(Copy, Clone, Debug)]
+//! enum Route {
+//!     Index,
+//!     GetPet,
+//!     File,
+//!     NotFound,
+//! }
+//!
+//! fn router() -> Router<Route> {
+//!     Router::new(Route::NotFound)
+//!         .with_route(&[Method::Get, Method::Head], "/", Route::Index)
+//!         .with_route(&[Method::Get], "/pet/{id}", Route::GetPet)
+//!         .with_route(&[Method::Get], "/files/{*path}", Route::File)
+//! }
+//!
+//! /// Call the correct handler for `request`.
+//! async fn handle<B>(router: &Router<Route>, request: Request<B>) -> Response<OneshotBody<'static>> {
+//!     let route = router.find(request.method(), request.path());
+//!     let handler = *route.handler();
+//!     let params = route.into_params();
+//!     match handler {
+//!         Route::Index => index(request).await,
+//!         Route::GetPet => get_pet(request, params).await,
+//!         Route::File => file(request, params).await,
+//!         Route::NotFound => not_found(request).await,
+//!     }
+//! }
+//!
+//! async fn index<B>(_: Request<B>) -> Response<OneshotBody<'static>> {
+//!     Response::ok().with_body("Index".into())
+//! }
+//!
+//! async fn get_pet<B>(_: Request<B>, params: Params) -> Response<OneshotBody<'static>> {
+//!     match params.get("id").map(str::parse::<u64>) {
+//!         Some(Ok(_id)) => Response::ok().with_body("Pet".into()),
+//!         _ => Response::bad_request().with_body("Invalid pet id".into()),
+//!     }
+//! }
+//!
+//! async fn file<B>(_: Request<B>, params: Params) -> Response<OneshotBody<'static>> {
+//!     let _path = params.get("path").unwrap();
+//!     Response::ok().with_body("File".into())
+//! }
+//!
+//! async fn not_found<B>(_: Request<B>) -> Response<OneshotBody<'static>> {
+//!     Response::not_found().with_body("Page not found".into())
+//! }
+//! #
+//! # let router = router();
+//! # heph::test::block_on(async move {
+//! #     let response = handle(&router, Request::get("/pet/123".to_owned())).await;
+//! #     assert_eq!(response.status(), heph_http::StatusCode::OK);
+//! # });
+//! ```
+
+use crate::handler::Handler;
+use crate::{Method, Request};
+
+/// Router of requests, based on the request's method and path.
+///
+/// See the [module documentation] for more information.
+///
+/// [module documentation]: crate::router
+#[derive(Debug)]
+pub struct Router<H> {
+    routes: Vec<RouteEntry<H>>,
+    not_found: H,
+}
+
+/// Single route in the [`Router`].
+#[derive(Debug)]
+struct RouteEntry<H> {
+    methods: Vec<Method>,
+    pattern: Pattern,
+    handler: H,
+}
+
+impl<H> Router<H> {
+    /// Create a new router, `not_found` is used for requests that don't match
+    /// any route.
+    pub const fn new(not_found: H) -> Router<H> {
+        Router {
+            routes: Vec::new(),
+            not_found,
+        }
+    }
+
+    /// Add a route for requests with any of the `methods` and a path matching
+    /// `pattern` to `handler`.
+    ///
+    /// Routes are matched in the order in which they are added.
+    ///
+    /// # Panics
+    ///
+    /// This will panic if `pattern` is invalid, see the [module documentation]
+    /// for the syntax.
+    ///
+    /// [module documentation]: crate::router#patterns
+    pub fn with_route(mut self, methods: &[Method], pattern: &str, handler: H) -> Router<H> {
+        self.routes.push(RouteEntry {
+            methods: methods.to_vec(),
+            pattern: Pattern::parse(pattern),
+            handler,
+        });
+        self
+    }
+
+    /// Find the route for a request with `method` and `path`.
+    ///
+    /// If no route matches this returns the not found handler, with empty
+    /// parameters.
+    pub fn find<'r>(&'r self, method: Method, path: &str) -> Route<'r, H> {
+        // Ignore the query.
+        let path = match path.find('?') {
+            Some(idx) => &path[..idx],
+            None => path,
+        };
+        for route in &self.routes {
+            if !route.methods.contains(&method) {
+                continue;
+            }
+            if let Some(params) = route.pattern.matches(path) {
+                return Route {
+                    handler: &route.handler,
+                    params,
+                    found: true,
+                };
+            }
+        }
+        Route {
+            handler: &self.not_found,
+            params: Params::new(),
+            found: false,
+        }
+    }
+
+    /// Handle `request` using the handler of the route that matches it.
+    ///
+    /// This requires all routes to use the same handler type `H`, for handlers
+    /// of different types see the example in the [module documentation].
+    ///
+    /// [module documentation]: crate::router
+    pub fn handle<B>(&self, request: Request<B>) -> H::Future
+    where
+        H: Handler<(Request<B>, Params)>,
+    {
+        let route = self.find(request.method(), request.path());
+        route.handler.handle((request, route.params))
+    }
+}
+
+/// Route found by [`Router::find`].
+#[derive(Debug)]
+pub struct Route<'r, H> {
+    handler: &'r H,
+    params: Params,
+    found: bool,
+}
+
+impl<'r, H> Route<'r, H> {
+    /// Returns the handler of the route.
+    pub const fn handler(&self) -> &'r H {
+        self.handler
+    }
+
+    /// Returns the parameters matched in the path.
+    pub const fn params(&self) -> &Params {
+        &self.params
+    }
+
+    /// Returns the parameters matched in the path.
+    pub fn into_params(self) -> Params {
+        self.params
+    }
+
+    /// Returns `true` if no route matched and the route uses the not found
+    /// handler.
+    pub const fn is_not_found(&self) -> bool {
+        !self.found
+    }
+}
+
+/// Parameters matched in a path, see the [module documentation].
+///
+/// [module documentation]: crate::router#patterns
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct Params {
+    /// Name -> value.
+    params: Vec<(String, String)>,
+}
+
+impl Params {
+    /// Create an empty set of parameters.
+    pub const fn new() -> Params {
+        Params { params: Vec::new() }
+    }
+
+    /// Returns the value of parameter `name`, if matched.
+    pub fn get(&self, name: &str) -> Option<&str> {
+        self.params
+            .iter()
+            .find_map(|(n, value)| (n == name).then(|| &**value))
+    }
+
+    /// Returns the number of parameters.
+    pub fn len(&self) -> usize {
+        self.params.len()
+    }
+
+    /// Returns `true` if there are no parameters.
+    pub fn is_empty(&self) -> bool {
+        self.params.is_empty()
+    }
+
+    /// Returns an iterator over all parameters, as `(name, value)`.
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.params.iter().map(|(name, value)| (&**name, &**value))
+    }
+
+    fn push(&mut self, name: &str, value: &str) {
+        self.params.push((name.to_owned(), value.to_owned()));
+    }
+}
+
+/// Parsed path pattern.
+#[derive(Debug)]
+struct Pattern {
+    segments: Box<[Segment]>,
+}
+
+#[derive(Debug)]
+enum Segment {
+    /// Segment must match exactly.
+    Literal(Box<str>),
+    /// Parameter matching a single segment.
+    Param(Box<str>),
+    /// Parameter matching the remainder of the path.
+    Rest(Box<str>),
+}
+
+impl Pattern {
+    fn parse(pattern: &str) -> Pattern {
+        let pattern = match pattern.strip_prefix('/') {
+            Some(pattern) => pattern,
+            None => panic!("route pattern must start with `/`: `{}`", pattern),
+        };
+        let mut segments = Vec::new();
+        let mut parts = pattern.split('/').peekable();
+        while let Some(part) = parts.next() {
+            let segment = match part.strip_prefix('{').and_then(|p| p.strip_suffix('}')) {
+                Some(name) => match name.strip_prefix('*') {
+                    Some(name) => {
+                        assert!(
+                            parts.peek().is_none(),
+                            "rest parameter must be the last segment in route pattern: `/{}`",
+                            pattern
+                        );
+                        Segment::Rest(name.into())
+                    }
+                    None => Segment::Param(name.into()),
+                },
+                None => {
+                    assert!(
+                        !part.contains(|c| c == '{' || c == '}'),
+                        "invalid segment in route pattern: `/{}`",
+                        pattern
+                    );
+                    Segment::Literal(part.into())
+                }
+            };
+            segments.push(segment);
+        }
+        Pattern {
+            segments: segments.into_boxed_slice(),
+        }
+    }
+
+    /// Returns the parameters if `path` matches the pattern.
+    fn matches(&self, path: &str) -> Option<Params> {
+        let mut params = Params::new();
+        let mut rest = Some(path.strip_prefix('/')?);
+        for segment in self.segments.iter() {
+            let part = match segment {
+                Segment::Rest(name) => {
+                    params.push(name, rest.unwrap_or(""));
+                    return Some(params);
+                }
+                Segment::Literal(_) | Segment::Param(_) => next_segment(&mut rest)?,
+            };
+            match segment {
+                Segment::Literal(literal) if part == &**literal => {}
+                Segment::Param(name) if !part.is_empty() => params.push(name, part),
+                _ => return None,
+            }
+        }
+        // The entire path must be matched.
+        match rest {
+            Some(_) => None,
+            None => Some(params),
+        }
+    }
+}
+
+/// Returns the next segment in `path`, setting `path` to the remainder.
+fn next_segment<'p>(path: &mut Option<&'p str>) -> Option<&'p str> {
+    let p = path.take()?;
+    match p.find('/') {
+        Some(idx) => {
+            *path = Some(&p[idx + 1..]);
+            Some(&p[..idx])
+        }
+        None => Some(p),
+    }
+}
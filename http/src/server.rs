@@ -24,17 +24,37 @@ use std::time::SystemTime;
 
 use heph::bytes::{Bytes, BytesVectored};
 use heph::net::{tcp, TcpServer, TcpStream};
+use heph::spawn::options::Priority;
 use heph::spawn::{ActorOptions, Spawn};
 use heph::{actor, rt, Actor, NewActor, Supervisor};
 use httpdate::HttpDate;
+use log::debug;
 
-use crate::body::{BodyLength, EmptyBody};
+use crate::body::{BodyLength, EmptyBody, OneshotBody};
+use crate::deadline;
 use crate::head::header::{FromHeaderValue, Header, HeaderName, Headers};
+use crate::head::RequestHead;
+use crate::websocket::{self, UpgradeError, WebSocket};
 use crate::{
     map_version_byte, trim_ws, Method, Request, Response, StatusCode, Version, BUF_SIZE,
     MAX_HEADERS, MAX_HEAD_SIZE, MIN_READ_SIZE,
 };
 
+/// Start of the HTTP/2 connection preface, RFC 7540 section 3.5.
+const HTTP2_PREFACE: &[u8] = b"PRI * HTTP/2.0\r\n";
+
+/// Empty HTTP/2 SETTINGS frame followed by a GOAWAY frame with the
+/// `HTTP_1_1_REQUIRED` error code, RFC 7540 sections 6.5, 6.8 and 7.
+#[rustfmt::skip]
+const HTTP2_GOAWAY: [u8; 26] = [
+    // SETTINGS: length (24 bits), type, flags, stream identifier (31 bits).
+    0, 0, 0, 0x4, 0, 0, 0, 0, 0,
+    // GOAWAY: length (24 bits), type, flags, stream identifier (31 bits).
+    0, 0, 8, 0x7, 0, 0, 0, 0, 0,
+    // Last stream identifier (31 bits), error code (32 bits).
+    0, 0, 0, 0, 0, 0, 0, 0xd,
+];
+
 /// A intermediate structure that implements [`NewActor`], creating
 /// [`HttpServer`].
 ///
@@ -49,6 +69,32 @@ impl<S, NA> Setup<S, NA> {
     pub fn local_addr(&self) -> SocketAddr {
         self.inner.local_addr()
     }
+
+    /// Keep the listening socket alive when the [`HttpServer`] is restarted.
+    ///
+    /// See [`tcp::server::Setup::with_listener_reuse`].
+    pub fn with_listener_reuse(self) -> Self {
+        Setup {
+            inner: self.inner.with_listener_reuse(),
+        }
+    }
+
+    /// Determine the priority of the actor started for each connection based
+    /// on the address of the peer.
+    ///
+    /// See [`tcp::server::Setup::with_connection_priority`].
+    ///
+    /// # Notes
+    ///
+    /// Requests within a single connection are always handled in order, as
+    /// HTTP/1.1 requires the responses to be send in the order the requests
+    /// were received (even when pipelining), so the priority applies to the
+    /// connection as a whole.
+    pub fn with_connection_priority(self, priority: fn(SocketAddr) -> Priority) -> Self {
+        Setup {
+            inner: self.inner.with_connection_priority(priority),
+        }
+    }
 }
 
 impl<S, NA> NewActor for Setup<S, NA>
@@ -387,6 +433,13 @@ impl Connection {
     /// Also see the [`Connection::last_request_version`] and
     /// [`Connection::last_request_method`] functions to properly respond to
     /// request errors.
+    ///
+    /// HTTP/2 isn't supported. If a client starts a HTTP/2 connection (using
+    /// prior knowledge) it's send a GOAWAY frame with the `HTTP_1_1_REQUIRED`
+    /// error code, asking it to retry using HTTP/1.1, and `Ok(None)` is
+    /// returned. This is logged (at the debug level) to tell it apart from a
+    /// normal close. A [`RequestError`] isn't returned as the caller would
+    /// then respond with a HTTP/1.1 response, after the HTTP/2 GOAWAY frame.
     #[allow(clippy::too_many_lines)] // TODO.
     pub async fn next_request<'a>(
         &'a mut self,
@@ -556,8 +609,11 @@ impl Connection {
                         // > message body is present).
                         None => BodyKind::Oneshot { left: 0 },
                     };
+                    let deadline = deadline::from_headers(&headers);
                     let body = Body { conn: self, kind };
-                    return Ok(Ok(Some(Request::new(method, path, version, headers, body))));
+                    let mut request = Request::new(method, path, version, headers, body);
+                    *request.deadline_mut() = deadline;
+                    return Ok(Ok(Some(request)));
                 }
                 Ok(httparse::Status::Partial) => {
                     // Buffer doesn't include the entire request head, try
@@ -574,6 +630,24 @@ impl Connection {
 
                     continue;
                 }
+                // HTTP/2 client using prior knowledge, which we don't
+                // support.
+                Err(httparse::Error::Version)
+                    if self.buf[self.parsed_bytes..].starts_with(HTTP2_PREFACE) =>
+                {
+                    // RFC 7540 section 3.5:
+                    // > The server connection preface consists of a
+                    // > potentially empty SETTINGS frame (Section 6.5) that
+                    // > MUST be the first frame the server sends in the HTTP/2
+                    // > connection.
+                    //
+                    // Followed by a GOAWAY frame telling the client to use
+                    // HTTP/1.1 instead.
+                    self.stream.send_all(&HTTP2_GOAWAY).await?;
+                    self.parsed_bytes = self.buf.len();
+                    debug!("closing HTTP/2 (prior knowledge) connection, HTTP/2 is not supported");
+                    return Ok(Ok(None));
+                }
                 Err(err) => return Ok(Err(RequestError::from_httparse(err))),
             }
         }
@@ -696,11 +770,14 @@ impl Connection {
     /// # Notes
     ///
     /// This automatically sets the "Content-Length" or "Transfer-Encoding",
-    /// "Connection" and "Date" headers if not provided in `headers`.
+    /// "Connection" and "Date" headers if not provided in `headers`. For
+    /// successful responses with a [`SharedBody`] the "ETag" header is also set
+    /// if not provided.
     ///
     /// If `request_method.`[`expects_body()`] or `status.`[`includes_body()`]
     /// returns `false` this will not write the body to the connection.
     ///
+    /// [`SharedBody`]: crate::body::SharedBody
     /// [`expects_body()`]: Method::expects_body
     /// [`includes_body()`]: StatusCode::includes_body
     #[allow(clippy::future_not_send)] // TODO.
@@ -737,6 +814,7 @@ impl Connection {
         let mut set_content_length_header = false;
         let mut set_transfer_encoding_header = false;
         let mut set_date_header = false;
+        let mut set_etag_header = false;
         for header in headers.iter() {
             let name = header.name();
             // Field-name:
@@ -756,6 +834,8 @@ impl Connection {
                 set_transfer_encoding_header = true;
             } else if name == &HeaderName::DATE {
                 set_date_header = true;
+            } else if name == &HeaderName::ETAG {
+                set_etag_header = true;
             }
         }
 
@@ -773,6 +853,14 @@ impl Connection {
             write!(&mut self.buf, "Date: {}\r\n", now).unwrap();
         }
 
+        // Provide the "ETag" header if the user didn't and the body has a
+        // cheap entity-tag, e.g. `SharedBody`.
+        if !set_etag_header && status.is_successful() {
+            if let Some(etag) = body.etag() {
+                write!(&mut self.buf, "ETag: \"{:016x}\"\r\n", etag).unwrap();
+            }
+        }
+
         // Provide the "Conent-Length" or "Transfer-Encoding" header if the user
         // didn't.
         let mut send_body = true;
@@ -808,6 +896,62 @@ impl Connection {
         Ok(())
     }
 
+    /// Upgrade the connection to a [`WebSocket`] connection.
+    ///
+    /// This performs the opening handshake (RFC 6455 section 4.2) based on
+    /// `request`, which must be the last request read from the connection. Use
+    /// [`websocket::is_upgrade_request`] to check if a request is a WebSocket
+    /// upgrade request before calling this.
+    ///
+    /// # Notes
+    ///
+    /// If `request` is not a valid upgrade request this responds with the
+    /// [proper status code] and returns an [`UpgradeError`] (as I/O error).
+    ///
+    /// See the [`websocket`] module for an example.
+    ///
+    /// [proper status code]: UpgradeError::proper_status_code
+    #[allow(clippy::future_not_send)] // TODO.
+    pub async fn upgrade(mut self, request: &RequestHead) -> io::Result<WebSocket> {
+        let accept_key = match websocket::handshake_key(request) {
+            Ok(key) => websocket::accept_key(key),
+            Err(err) => {
+                let mut headers = Headers::EMPTY;
+                headers.append(Header::new(HeaderName::CONNECTION, b"close"));
+                if let UpgradeError::UnsupportedVersion = err {
+                    headers.append(Header::new(HeaderName::SEC_WEBSOCKET_VERSION, b"13"));
+                }
+                let msg = err.to_string();
+                let body = OneshotBody::new(msg.as_bytes());
+                self.respond(err.proper_status_code(), &headers, body)
+                    .await?;
+                return Err(err.into());
+            }
+        };
+
+        self.clear_buffer();
+        if self.parsed_bytes > self.buf.len() {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "can't upgrade connection: request body not read",
+            ));
+        }
+
+        // NOTE: we can't use `send_response` as 1xx responses can't contain
+        // the "Content-Length" header.
+        let ignore_end = self.buf.len();
+        self.buf.extend_from_slice(
+            b"HTTP/1.1 101 \r\nUpgrade: websocket\r\nConnection: Upgrade\r\nSec-WebSocket-Accept: ",
+        );
+        self.buf.extend_from_slice(&accept_key);
+        self.buf.extend_from_slice(b"\r\n\r\n");
+        self.stream.send_all(&self.buf[ignore_end..]).await?;
+        self.buf.truncate(ignore_end);
+
+        // Any bytes after the request are (part of) WebSocket frames.
+        Ok(WebSocket::new(self.stream, self.buf, self.parsed_bytes))
+    }
+
     /// See [`TcpStream::peer_addr`].
     pub fn peer_addr(&mut self) -> io::Result<SocketAddr> {
         self.stream.peer_addr()
@@ -879,6 +1023,16 @@ impl Connection {
         }
     }
 
+    /// Skip the CRLF that ends the data of the previous chunk, if present.
+    ///
+    /// A chunk size line can't be empty, so this never skips (part of) the
+    /// next chunk.
+    fn skip_chunk_end(&mut self) {
+        if self.buf[self.parsed_bytes..].starts_with(b"\r\n") {
+            self.parsed_bytes += 2;
+        }
+    }
+
     /// Read a HTTP body chunk.
     ///
     /// Returns an I/O error, or an `InvalidData` error if the chunk size is
@@ -890,6 +1044,7 @@ impl Connection {
         read_complete: &mut bool,
     ) -> Poll<io::Result<()>> {
         loop {
+            self.skip_chunk_end();
             match httparse::parse_chunk_size(&self.buf[self.parsed_bytes..]) {
                 #[allow(clippy::cast_possible_truncation)] // For truncate below.
                 Ok(httparse::Status::Complete((idx, chunk_size))) => {
@@ -922,6 +1077,7 @@ impl Connection {
         read_complete: &mut bool,
     ) -> io::Result<()> {
         loop {
+            self.skip_chunk_end();
             match httparse::parse_chunk_size(&self.buf[self.parsed_bytes..]) {
                 #[allow(clippy::cast_possible_truncation)] // For truncate below.
                 Ok(httparse::Status::Complete((idx, chunk_size))) => {
@@ -1050,7 +1206,11 @@ impl<'a> Body<'a> {
     }
 
     /// Receive bytes from the request body, writing them into `buf`.
-    pub const fn recv<B>(&'a mut self, buf: B) -> Recv<'a, B>
+    ///
+    /// This can be called multiple times to read the body incrementally,
+    /// without having to keep the entire body in memory. Returns `0` once the
+    /// entire body is read (or if `buf` has no spare capacity).
+    pub const fn recv<'b, B>(&'b mut self, buf: B) -> Recv<'b, 'a, B>
     where
         B: Bytes,
     {
@@ -1058,7 +1218,9 @@ impl<'a> Body<'a> {
     }
 
     /// Receive bytes from the request body, writing them into `bufs`.
-    pub const fn recv_vectored<B>(&'a mut self, bufs: B) -> RecvVectored<'a, B>
+    ///
+    /// See [`Body::recv`].
+    pub const fn recv_vectored<'b, B>(&'b mut self, bufs: B) -> RecvVectored<'b, 'a, B>
     where
         B: BytesVectored,
     {
@@ -1075,7 +1237,7 @@ impl<'a> Body<'a> {
             let bytes = self.buf_bytes();
             let len = bytes.len();
             if limit < total + len {
-                return Err(io::Error::new(io::ErrorKind::Other, "body too large"));
+                return Err(io::Error::new(io::ErrorKind::Other, BodyTooLarge));
             }
 
             buf.extend_from_slice(bytes);
@@ -1103,24 +1265,17 @@ impl<'a> Body<'a> {
                     }
                 }
             }
-            // Continue to reading below.
-            break;
-        }
 
-        loop {
-            // Limit the read until the end of the chunk/body.
-            let chunk_len = self.chunk_len();
-            if chunk_len == 0 {
-                return Ok(());
-            } else if total + chunk_len > limit {
-                return Err(io::Error::new(io::ErrorKind::Other, "body too large"));
+            // Read the remainder of the body/chunk directly into `buf`.
+            if total + chunk_len > limit {
+                return Err(io::Error::new(io::ErrorKind::Other, BodyTooLarge));
             }
-
             (&mut *buf).reserve(chunk_len);
-            self.conn.stream.recv_n(&mut *buf, chunk_len).await?;
+            // Limit the read to not read (part of) the next request/chunk.
+            let limited_buf = (&mut *buf).limit(chunk_len);
+            self.conn.stream.recv_n(limited_buf, chunk_len).await?;
+            self.read_directly(chunk_len);
             total += chunk_len;
-
-            // FIXME: doesn't deal with chunked bodies.
         }
     }
 
@@ -1157,24 +1312,41 @@ impl<'a> Body<'a> {
 
     /// Mark `n` bytes are processed.
     fn processed(&mut self, n: usize) {
+        self.read_directly(n);
+        self.conn.parsed_bytes += n;
+    }
+
+    /// Mark `n` bytes as read directly from the stream, i.e. bypassing the
+    /// buffer of the connection.
+    fn read_directly(&mut self, n: usize) {
         // TODO: should this be `unsafe`? We don't do underflow checks...
         match &mut self.kind {
             BodyKind::Oneshot { left } => *left -= n,
             BodyKind::Chunked { left_in_chunk, .. } => *left_in_chunk -= n,
         }
-        self.conn.parsed_bytes += n;
+    }
+
+    /// Returns `true` if all chunks of a chunked body are read.
+    fn read_complete(&self) -> bool {
+        matches!(
+            self.kind,
+            BodyKind::Chunked {
+                read_complete: true,
+                ..
+            }
+        )
     }
 }
 
 /// The [`Future`] behind [`Body::recv`].
 #[derive(Debug)]
 #[must_use = "futures do nothing unless you `.await` or poll them"]
-pub struct Recv<'b, B> {
-    body: &'b mut Body<'b>,
+pub struct Recv<'b, 'c, B> {
+    body: &'b mut Body<'c>,
     buf: B,
 }
 
-impl<'b, B> Future for Recv<'b, B>
+impl<'b, 'c, B> Future for Recv<'b, 'c, B>
 where
     B: Bytes + Unpin,
 {
@@ -1186,13 +1358,17 @@ where
         let mut len = 0;
         loop {
             // Copy bytes in our buffer.
-            len += body.copy_buf_bytes(buf.as_bytes());
-            if len != 0 {
-                unsafe { buf.update_length(len) };
+            let n = body.copy_buf_bytes(buf.as_bytes());
+            if n != 0 {
+                unsafe { buf.update_length(n) };
+                len += n;
             }
 
             let limit = body.chunk_len();
             if limit == 0 {
+                if body.read_complete() {
+                    return Poll::Ready(Ok(len));
+                }
                 match &mut body.kind {
                     // Read all the bytes from the oneshot body.
                     BodyKind::Oneshot { .. } => return Poll::Ready(Ok(len)),
@@ -1218,7 +1394,10 @@ where
             let limit = body.chunk_len();
             loop {
                 match body.conn.stream.try_recv(buf.limit(limit)) {
-                    Ok(n) => return Poll::Ready(Ok(len + n)),
+                    Ok(n) => {
+                        body.read_directly(n);
+                        return Poll::Ready(Ok(len + n));
+                    }
                     Err(ref err) if err.kind() == io::ErrorKind::WouldBlock => {
                         return if len == 0 {
                             Poll::Pending
@@ -1239,12 +1418,12 @@ where
 /// The [`Future`] behind [`Body::recv_vectored`].
 #[derive(Debug)]
 #[must_use = "futures do nothing unless you `.await` or poll them"]
-pub struct RecvVectored<'b, B> {
-    body: &'b mut Body<'b>,
+pub struct RecvVectored<'b, 'c, B> {
+    body: &'b mut Body<'c>,
     bufs: B,
 }
 
-impl<'b, B> Future for RecvVectored<'b, B>
+impl<'b, 'c, B> Future for RecvVectored<'b, 'c, B>
 where
     B: BytesVectored + Unpin,
 {
@@ -1256,18 +1435,23 @@ where
         let mut len = 0;
         loop {
             // Copy bytes in our buffer.
+            let mut n = 0;
             for buf in bufs.as_bufs().as_mut() {
                 match body.copy_buf_bytes(buf) {
                     0 => break,
-                    n => len += n,
+                    copied => n += copied,
                 }
             }
-            if len != 0 {
-                unsafe { bufs.update_lengths(len) };
+            if n != 0 {
+                unsafe { bufs.update_lengths(n) };
+                len += n;
             }
 
             let limit = body.chunk_len();
             if limit == 0 {
+                if body.read_complete() {
+                    return Poll::Ready(Ok(len));
+                }
                 match &mut body.kind {
                     // Read all the bytes from the oneshot body.
                     BodyKind::Oneshot { .. } => return Poll::Ready(Ok(len)),
@@ -1293,7 +1477,10 @@ where
             let limit = body.chunk_len();
             loop {
                 match body.conn.stream.try_recv_vectored(bufs.limit(limit)) {
-                    Ok(n) => return Poll::Ready(Ok(len + n)),
+                    Ok(n) => {
+                        body.read_directly(n);
+                        return Poll::Ready(Ok(len + n));
+                    }
                     Err(ref err) if err.kind() == io::ErrorKind::WouldBlock => {
                         return if len == 0 {
                             Poll::Pending
@@ -1424,6 +1611,26 @@ impl<'c> crate::body::PrivateBody<'c> for Body<'c> {
     }
 }
 
+/// Error returned by [`Body::read_all`] if the body is larger than the limit.
+#[derive(Debug)]
+pub(crate) struct BodyTooLarge;
+
+impl BodyTooLarge {
+    /// Returns `true` if `err` was caused by `BodyTooLarge`.
+    #[cfg(any(feature = "json", feature = "form"))]
+    pub(crate) fn is(err: &io::Error) -> bool {
+        err.get_ref().map_or(false, |err| err.is::<BodyTooLarge>())
+    }
+}
+
+impl fmt::Display for BodyTooLarge {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("body too large")
+    }
+}
+
+impl std::error::Error for BodyTooLarge {}
+
 impl<'a> Drop for Body<'a> {
     fn drop(&mut self) {
         if self.is_empty() {
@@ -1612,6 +1819,14 @@ impl fmt::Display for RequestError {
     }
 }
 
+impl std::error::Error for RequestError {}
+
+impl From<RequestError> for io::Error {
+    fn from(err: RequestError) -> io::Error {
+        io::Error::new(io::ErrorKind::InvalidData, err)
+    }
+}
+
 /// The message type used by [`HttpServer`] (and [`TcpServer`]).
 ///
 #[doc(inline)]
@@ -0,0 +1,705 @@
+//! Module with the WebSocket protocol, RFC 6455.
+//!
+//! A HTTP connection can be upgraded to a WebSocket connection using
+//! [`Connection::upgrade`], which performs the opening handshake and returns a
+//! [`WebSocket`]. [`is_upgrade_request`] can be used to check if a request is
+//! a WebSocket upgrade request.
+//!
+//! [`Connection::upgrade`]: crate::server::Connection::upgrade
+//!
+//! # Examples
+//!
+//! A HTTP actor that echos all messages received on a WebSocket connection.
+//!
+//! ```
+//! #![feature(never_type)]
+//!
+//! use std::io;
+//! use std::net::SocketAddr;
+//!
+//! use heph::actor;
+//! use heph::rt::ThreadLocal;
+//! use heph_http::body::OneshotBody;
+//! use heph_http::server::Connection;
+//! use heph_http::websocket::{self, Message};
+//! use heph_http::{Headers, StatusCode};
+//!
+//! async fn http_actor(_: actor::Context<!, ThreadLocal>, mut connection: Connection, _: SocketAddr) -> io::Result<()> {
+//!     let request = match connection.next_request().await? {
+//!         Ok(Some(request)) => request,
+//!         Ok(None) => return Ok(()),
+//!         Err(err) => return connection.respond_with(err.response()).await,
+//!     };
+//!
+//!     if request.path() != "/echo" || !websocket::is_upgrade_request(&request) {
+//!         drop(request);
+//!         let body = OneshotBody::new(b"Not found");
+//!         return connection.respond(StatusCode::NOT_FOUND, &Headers::EMPTY, body).await;
+//!     }
+//!
+//!     // Drop the (empty) body of the request, this allows us to upgrade the
+//!     // connection.
+//!     let (head, _) = request.split();
+//!     let mut websocket = connection.upgrade(&head).await?;
+//!     while let Some(message) = websocket.recv().await? {
+//!         match message {
+//!             Message::Text(_) | Message::Binary(_) => websocket.send(&message).await?,
+//!             // Pings are responded to and closing the connection is handled
+//!             // by `recv`.
+//!             Message::Ping(_) | Message::Pong(_) | Message::Close(_) => {}
+//!         }
+//!     }
+//!     Ok(())
+//! }
+//! # let _ = http_actor;
+//! ```
+
+use std::fmt;
+use std::io::{self, IoSlice};
+use std::net::SocketAddr;
+
+use heph::net::TcpStream;
+
+use crate::head::header::{HeaderName, Headers};
+use crate::head::RequestHead;
+use crate::{trim_ws, Method, StatusCode, Version, MIN_READ_SIZE};
+
+/// Maximum size of a single message, including all its fragments.
+///
+/// Receiving a larger message causes the connection to be closed with
+/// [`CloseCode::MESSAGE_TOO_BIG`].
+pub const MAX_MESSAGE_SIZE: usize = 16 * 1024 * 1024;
+
+/// Maximum size of the payload of a control frame, RFC 6455 section 5.5.
+const MAX_CONTROL_PAYLOAD: usize = 125;
+
+/// GUID used in calculating the `Sec-WebSocket-Accept` header, RFC 6455
+/// section 1.3.
+const GUID: &[u8] = b"258EAFA5-E914-47DA-95CA-C5AB0DC85B11";
+
+/// Opcodes, RFC 6455 section 5.2.
+const OPCODE_CONTINUATION: u8 = 0x0;
+const OPCODE_TEXT: u8 = 0x1;
+const OPCODE_BINARY: u8 = 0x2;
+const OPCODE_CLOSE: u8 = 0x8;
+const OPCODE_PING: u8 = 0x9;
+const OPCODE_PONG: u8 = 0xA;
+
+/// Returns `true` if `request` is a valid WebSocket upgrade request.
+///
+/// See [`Connection::upgrade`] to upgrade the connection.
+///
+/// [`Connection::upgrade`]: crate::server::Connection::upgrade
+pub fn is_upgrade_request(request: &RequestHead) -> bool {
+    handshake_key(request).is_ok()
+}
+
+/// Validates the opening handshake in `request`, returning the value of the
+/// `Sec-WebSocket-Key` header.
+///
+/// RFC 6455 section 4.2.1.
+pub(crate) fn handshake_key(request: &RequestHead) -> Result<&[u8], UpgradeError> {
+    if !matches!(request.method(), Method::Get) || matches!(request.version(), Version::Http10) {
+        return Err(UpgradeError::InvalidRequest);
+    }
+
+    let headers = request.headers();
+    if !has_token(headers, &HeaderName::UPGRADE, b"websocket")
+        || !has_token(headers, &HeaderName::CONNECTION, b"upgrade")
+    {
+        return Err(UpgradeError::NotUpgrade);
+    }
+
+    match headers.get_bytes(&HeaderName::SEC_WEBSOCKET_VERSION) {
+        Some(version) if trim_ws(version) == b"13" => {}
+        _ => return Err(UpgradeError::UnsupportedVersion),
+    }
+
+    // The key must be a base64 encoded 16 byte value, which is always 24 bytes
+    // long including two bytes of padding.
+    match headers
+        .get_bytes(&HeaderName::SEC_WEBSOCKET_KEY)
+        .map(trim_ws)
+    {
+        Some(key)
+            if key.len() == 24
+                && key.ends_with(b"==")
+                && key[..22].iter().all(|b| BASE64_ALPHABET.contains(b)) =>
+        {
+            Ok(key)
+        }
+        _ => Err(UpgradeError::InvalidKey),
+    }
+}
+
+/// Returns `true` if any of the headers with `name` contains `token` in its
+/// comma separated list of values, ignoring case.
+fn has_token(headers: &Headers, name: &HeaderName<'_>, token: &[u8]) -> bool {
+    headers.get_all(name).any(|header| {
+        header
+            .value()
+            .split(|b| *b == b',')
+            .any(|value| trim_ws(value).eq_ignore_ascii_case(token))
+    })
+}
+
+/// Returns the value for the `Sec-WebSocket-Accept` header for `key`.
+pub(crate) fn accept_key(key: &[u8]) -> [u8; 28] {
+    let mut input = Vec::with_capacity(key.len() + GUID.len());
+    input.extend_from_slice(key);
+    input.extend_from_slice(GUID);
+    base64_encode(&sha1(&input))
+}
+
+/// Error validating a WebSocket upgrade request.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum UpgradeError {
+    /// Request is not a HTTP/1.1 (or later) GET request.
+    InvalidRequest,
+    /// Missing or invalid "Upgrade" or "Connection" header.
+    NotUpgrade,
+    /// Missing or unsupported "Sec-WebSocket-Version" header, only version 13
+    /// is supported.
+    UnsupportedVersion,
+    /// Missing or invalid "Sec-WebSocket-Key" header.
+    InvalidKey,
+}
+
+impl UpgradeError {
+    /// Returns the proper status code for a given error.
+    pub const fn proper_status_code(self) -> StatusCode {
+        match self {
+            UpgradeError::InvalidRequest | UpgradeError::NotUpgrade | UpgradeError::InvalidKey => {
+                StatusCode::BAD_REQUEST
+            }
+            // RFC 6455 section 4.4:
+            // > If the server doesn't support the requested version, it MUST
+            // > respond with a |Sec-WebSocket-Version| header field (or multiple
+            // > |Sec-WebSocket-Version| header fields) containing all versions
+            // > it is willing to use.
+            UpgradeError::UnsupportedVersion => StatusCode::UPGRADE_REQUIRED,
+        }
+    }
+}
+
+impl fmt::Display for UpgradeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            UpgradeError::InvalidRequest => "invalid WebSocket upgrade request",
+            UpgradeError::NotUpgrade => "not a WebSocket upgrade request",
+            UpgradeError::UnsupportedVersion => "unsupported WebSocket version",
+            UpgradeError::InvalidKey => "invalid Sec-WebSocket-Key header",
+        })
+    }
+}
+
+impl std::error::Error for UpgradeError {}
+
+impl From<UpgradeError> for io::Error {
+    fn from(err: UpgradeError) -> io::Error {
+        io::Error::new(io::ErrorKind::InvalidData, err)
+    }
+}
+
+/// WebSocket message.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum Message {
+    /// Text message.
+    Text(String),
+    /// Binary message.
+    Binary(Vec<u8>),
+    /// Ping, the payload can be at most 125 bytes.
+    Ping(Vec<u8>),
+    /// Pong, the payload can be at most 125 bytes.
+    Pong(Vec<u8>),
+    /// Close the connection, with an optional reason.
+    Close(Option<CloseFrame>),
+}
+
+impl Message {
+    /// Returns the opcode for the message.
+    const fn opcode(&self) -> u8 {
+        match self {
+            Message::Text(_) => OPCODE_TEXT,
+            Message::Binary(_) => OPCODE_BINARY,
+            Message::Ping(_) => OPCODE_PING,
+            Message::Pong(_) => OPCODE_PONG,
+            Message::Close(_) => OPCODE_CLOSE,
+        }
+    }
+}
+
+/// Reason for closing the connection, see [`Message::Close`].
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct CloseFrame {
+    /// Status code.
+    pub code: CloseCode,
+    /// Reason, may be empty. The encoded close frame can be at most 125 bytes,
+    /// leaving 123 bytes for the reason.
+    pub reason: String,
+}
+
+/// Status code used in closing a connection, RFC 6455 section 7.4.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct CloseCode(pub u16);
+
+impl CloseCode {
+    /// 1000 Normal closure.
+    pub const NORMAL: CloseCode = CloseCode(1000);
+    /// 1001 Going away, e.g. server going down.
+    pub const GOING_AWAY: CloseCode = CloseCode(1001);
+    /// 1002 Protocol error.
+    pub const PROTOCOL_ERROR: CloseCode = CloseCode(1002);
+    /// 1003 Received a type of data it can't accept.
+    pub const UNSUPPORTED_DATA: CloseCode = CloseCode(1003);
+    /// 1007 Received data within a message that is not consistent with the
+    /// type of the message, e.g. invalid UTF-8 in a text message.
+    pub const INVALID_DATA: CloseCode = CloseCode(1007);
+    /// 1008 Received a message that violates its policy.
+    pub const POLICY_VIOLATION: CloseCode = CloseCode(1008);
+    /// 1009 Received a message that is too big to process.
+    pub const MESSAGE_TOO_BIG: CloseCode = CloseCode(1009);
+    /// 1011 Encountered an unexpected condition.
+    pub const INTERNAL_ERROR: CloseCode = CloseCode(1011);
+}
+
+/// WebSocket connection.
+///
+/// Created by [`Connection::upgrade`], see the [module documentation] for an
+/// example.
+///
+/// # Notes
+///
+/// When a ping is received [`WebSocket::recv`] automatically responds with a
+/// pong. Similarly when a close frame is received a close frame is send in
+/// response (if one hasn't been send already). Both messages are still
+/// returned by `recv`.
+///
+/// [`Connection::upgrade`]: crate::server::Connection::upgrade
+/// [module documentation]: crate::websocket
+#[derive(Debug)]
+pub struct WebSocket {
+    stream: TcpStream,
+    buf: Vec<u8>,
+    /// Number of bytes of `buf` that are already parsed.
+    parsed_bytes: usize,
+    /// Fragmented message: opcode of the first frame and payload received so
+    /// far.
+    fragments: Option<(u8, Vec<u8>)>,
+    /// Whether or not a close frame was send.
+    close_send: bool,
+    /// Whether or not a close frame was received, or the connection failed.
+    close_received: bool,
+}
+
+impl WebSocket {
+    /// Create a new `WebSocket`, `buf[parsed_bytes..]` may already contain
+    /// (part of) the first frame.
+    pub(crate) fn new(stream: TcpStream, buf: Vec<u8>, parsed_bytes: usize) -> WebSocket {
+        WebSocket {
+            stream,
+            buf,
+            parsed_bytes,
+            fragments: None,
+            close_send: false,
+            close_received: false,
+        }
+    }
+
+    /// Receive the next message.
+    ///
+    /// Returns `Ok(None)` once the connection is closed, i.e. after a
+    /// [`Message::Close`] was returned or when the peer closed the connection
+    /// without sending a close frame. Fragmented messages are returned as a
+    /// single message.
+    ///
+    /// If the peer violates the protocol the connection is closed, with the
+    /// appropriate [`CloseCode`], and an `InvalidData` error is returned.
+    pub async fn recv(&mut self) -> io::Result<Option<Message>> {
+        loop {
+            if self.close_received {
+                return Ok(None);
+            }
+
+            let (fin, opcode, payload) = match self.read_frame().await? {
+                Some(frame) => frame,
+                None if self.fragments.is_some() => {
+                    return Err(io::ErrorKind::UnexpectedEof.into());
+                }
+                None => {
+                    self.close_received = true;
+                    return Ok(None);
+                }
+            };
+
+            let (opcode, payload) = match opcode {
+                OPCODE_CONTINUATION => match self.fragments.as_mut() {
+                    Some((_, data)) if data.len() + payload.len() <= MAX_MESSAGE_SIZE => {
+                        data.extend_from_slice(&payload);
+                        if !fin {
+                            continue;
+                        }
+                        // Checked the fragments above.
+                        self.fragments.take().unwrap()
+                    }
+                    Some(_) => {
+                        return Err(self
+                            .fail(CloseCode::MESSAGE_TOO_BIG, "message too big")
+                            .await)
+                    }
+                    None => {
+                        return Err(self
+                            .fail(CloseCode::PROTOCOL_ERROR, "unexpected continuation frame")
+                            .await)
+                    }
+                },
+                OPCODE_TEXT | OPCODE_BINARY if self.fragments.is_some() => {
+                    return Err(self
+                        .fail(CloseCode::PROTOCOL_ERROR, "expected continuation frame")
+                        .await)
+                }
+                OPCODE_TEXT | OPCODE_BINARY if !fin => {
+                    self.fragments = Some((opcode, payload));
+                    continue;
+                }
+                OPCODE_TEXT | OPCODE_BINARY | OPCODE_PING | OPCODE_PONG | OPCODE_CLOSE => {
+                    (opcode, payload)
+                }
+                _ => return Err(self.fail(CloseCode::PROTOCOL_ERROR, "unknown opcode").await),
+            };
+
+            let message = match opcode {
+                OPCODE_TEXT => match String::from_utf8(payload) {
+                    Ok(text) => Message::Text(text),
+                    Err(_) => {
+                        return Err(self.fail(CloseCode::INVALID_DATA, "invalid UTF-8").await)
+                    }
+                },
+                OPCODE_BINARY => Message::Binary(payload),
+                OPCODE_PING => {
+                    if !self.close_send {
+                        self.send_frame(OPCODE_PONG, &payload).await?;
+                    }
+                    Message::Ping(payload)
+                }
+                OPCODE_PONG => Message::Pong(payload),
+                OPCODE_CLOSE => {
+                    let close = match payload.len() {
+                        0 => None,
+                        1 => {
+                            return Err(self
+                                .fail(CloseCode::PROTOCOL_ERROR, "invalid close frame")
+                                .await)
+                        }
+                        _ => {
+                            let code = CloseCode(u16::from_be_bytes([payload[0], payload[1]]));
+                            match String::from_utf8(payload[2..].to_vec()) {
+                                Ok(reason) => Some(CloseFrame { code, reason }),
+                                Err(_) => {
+                                    return Err(self
+                                        .fail(CloseCode::INVALID_DATA, "invalid UTF-8")
+                                        .await)
+                                }
+                            }
+                        }
+                    };
+                    self.close_received = true;
+                    // RFC 6455 section 5.5.1:
+                    // > If an endpoint receives a Close frame and did not
+                    // > previously send a Close frame, the endpoint MUST send a
+                    // > Close frame in response. (When sending a Close frame in
+                    // > response, the endpoint typically echos the status code
+                    // > it received.)
+                    if !self.close_send {
+                        let code = close.as_ref().map(|close| close.code.0.to_be_bytes());
+                        let payload = code.as_ref().map_or(&[][..], |code| &code[..]);
+                        self.send_frame(OPCODE_CLOSE, payload).await?;
+                    }
+                    Message::Close(close)
+                }
+                _ => unreachable!(),
+            };
+            return Ok(Some(message));
+        }
+    }
+
+    /// Send `message`.
+    ///
+    /// Sending a [`Message::Close`] starts the closing handshake, after which
+    /// no more messages can be send. Use [`WebSocket::recv`] to wait for the
+    /// peer's close frame.
+    ///
+    /// Returns an `InvalidInput` error if the payload of a control message
+    /// (ping, pong or close) is too large.
+    pub async fn send(&mut self, message: &Message) -> io::Result<()> {
+        let close_payload;
+        let payload: &[u8] = match message {
+            Message::Text(text) => text.as_bytes(),
+            Message::Binary(data) | Message::Ping(data) | Message::Pong(data) => data,
+            Message::Close(None) => &[],
+            Message::Close(Some(close)) => {
+                let mut payload = Vec::with_capacity(2 + close.reason.len());
+                payload.extend_from_slice(&close.code.0.to_be_bytes());
+                payload.extend_from_slice(close.reason.as_bytes());
+                close_payload = payload;
+                &close_payload
+            }
+        };
+        self.send_frame(message.opcode(), payload).await
+    }
+
+    /// See [`TcpStream::peer_addr`].
+    pub fn peer_addr(&mut self) -> io::Result<SocketAddr> {
+        self.stream.peer_addr()
+    }
+
+    /// See [`TcpStream::local_addr`].
+    pub fn local_addr(&mut self) -> io::Result<SocketAddr> {
+        self.stream.local_addr()
+    }
+
+    /// See [`TcpStream::set_nodelay`].
+    pub fn set_nodelay(&mut self, nodelay: bool) -> io::Result<()> {
+        self.stream.set_nodelay(nodelay)
+    }
+
+    /// Read a single frame from the connection, returns `(fin, opcode,
+    /// payload)`.
+    ///
+    /// Returns `Ok(None)` if the peer closed the connection.
+    async fn read_frame(&mut self) -> io::Result<Option<(bool, u8, Vec<u8>)>> {
+        loop {
+            match parse_frame(&self.buf[self.parsed_bytes..]) {
+                Ok(Some((fin, opcode, payload, length))) => {
+                    self.parsed_bytes += length;
+                    return Ok(Some((fin, opcode, payload)));
+                }
+                Ok(None) => {} // Read some more data below.
+                Err((code, msg)) => return Err(self.fail(code, msg).await),
+            }
+
+            // Remove the parsed bytes and ensure we have space in the buffer
+            // to read into.
+            let _ = self.buf.drain(..self.parsed_bytes);
+            self.parsed_bytes = 0;
+            self.buf.reserve(MIN_READ_SIZE);
+
+            if self.stream.recv(&mut self.buf).await? == 0 {
+                return if self.buf.is_empty() {
+                    Ok(None)
+                } else {
+                    Err(io::ErrorKind::UnexpectedEof.into())
+                };
+            }
+        }
+    }
+
+    /// Send a single, unfragmented frame.
+    async fn send_frame(&mut self, opcode: u8, payload: &[u8]) -> io::Result<()> {
+        if self.close_send {
+            return Err(io::Error::new(
+                io::ErrorKind::NotConnected,
+                "WebSocket close frame already send",
+            ));
+        }
+        if is_control(opcode) && payload.len() > MAX_CONTROL_PAYLOAD {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "WebSocket control frame payload too large",
+            ));
+        }
+
+        let mut head = [0; 10];
+        let head_len = frame_head(&mut head, opcode, payload.len());
+        let mut bufs = [IoSlice::new(&head[..head_len]), IoSlice::new(payload)];
+        self.stream.send_vectored_all(&mut bufs).await?;
+        if opcode == OPCODE_CLOSE {
+            self.close_send = true;
+        }
+        Ok(())
+    }
+
+    /// Fail the connection, RFC 6455 section 7.1.7. Returns an `InvalidData`
+    /// error with `msg`.
+    async fn fail(&mut self, code: CloseCode, msg: &'static str) -> io::Error {
+        if !self.close_send {
+            // We're already returning an error, so we ignore this one.
+            let _ = self.send_frame(OPCODE_CLOSE, &code.0.to_be_bytes()).await;
+        }
+        self.close_received = true;
+        io::Error::new(io::ErrorKind::InvalidData, msg)
+    }
+}
+
+/// Returns `true` if `opcode` is a control frame.
+const fn is_control(opcode: u8) -> bool {
+    opcode & 0x8 != 0
+}
+
+/// Parse a single (masked) frame from `buf`.
+///
+/// Returns `(fin, opcode, payload, frame_length)`, `Ok(None)` if `buf`
+/// doesn't contain the entire frame or an error if the frame is invalid.
+#[allow(clippy::type_complexity)]
+fn parse_frame(
+    buf: &[u8],
+) -> Result<Option<(bool, u8, Vec<u8>, usize)>, (CloseCode, &'static str)> {
+    if buf.len() < 2 {
+        return Ok(None);
+    }
+
+    let fin = buf[0] & 0x80 != 0;
+    // We don't support any extensions, so the RSV bits must be zero.
+    if buf[0] & 0x70 != 0 {
+        return Err((CloseCode::PROTOCOL_ERROR, "invalid frame RSV bits"));
+    }
+    let opcode = buf[0] & 0x0F;
+    // RFC 6455 section 5.1:
+    // > The server MUST close the connection upon receiving a frame that is
+    // > not masked.
+    if buf[1] & 0x80 == 0 {
+        return Err((CloseCode::PROTOCOL_ERROR, "frame not masked"));
+    }
+
+    let (length, mut idx) = match buf[1] & 0x7F {
+        126 if buf.len() < 4 => return Ok(None),
+        126 => (u64::from(u16::from_be_bytes([buf[2], buf[3]])), 4),
+        127 if buf.len() < 10 => return Ok(None),
+        127 => {
+            let mut length = [0; 8];
+            length.copy_from_slice(&buf[2..10]);
+            (u64::from_be_bytes(length), 10)
+        }
+        length => (u64::from(length), 2),
+    };
+
+    if is_control(opcode) && (!fin || length > MAX_CONTROL_PAYLOAD as u64) {
+        return Err((CloseCode::PROTOCOL_ERROR, "invalid control frame"));
+    }
+    if length > MAX_MESSAGE_SIZE as u64 {
+        return Err((CloseCode::MESSAGE_TOO_BIG, "message too big"));
+    }
+    #[allow(clippy::cast_possible_truncation)] // Checked above.
+    let length = length as usize;
+
+    if buf.len() < idx + 4 + length {
+        return Ok(None);
+    }
+    let mask = [buf[idx], buf[idx + 1], buf[idx + 2], buf[idx + 3]];
+    idx += 4;
+    let payload = buf[idx..idx + length]
+        .iter()
+        .enumerate()
+        .map(|(i, b)| b ^ mask[i % 4])
+        .collect();
+    Ok(Some((fin, opcode, payload, idx + length)))
+}
+
+/// Write the head of an unmasked, final frame into `head`. Returns the length
+/// of the head.
+#[allow(clippy::cast_possible_truncation)] // Checked by the match.
+fn frame_head(head: &mut [u8; 10], opcode: u8, length: usize) -> usize {
+    head[0] = 0x80 | opcode;
+    match length {
+        0..=125 => {
+            head[1] = length as u8;
+            2
+        }
+        126..=0xFFFF => {
+            head[1] = 126;
+            head[2..4].copy_from_slice(&(length as u16).to_be_bytes());
+            4
+        }
+        _ => {
+            head[1] = 127;
+            head[2..10].copy_from_slice(&(length as u64).to_be_bytes());
+            10
+        }
+    }
+}
+
+const BASE64_ALPHABET: &[u8; 64] =
+    b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+/// Base64 encode a SHA-1 hash.
+fn base64_encode(input: &[u8; 20]) -> [u8; 28] {
+    let mut output = [b'='; 28];
+    for (input, output) in input.chunks(3).zip(output.chunks_mut(4)) {
+        let n = input.len();
+        let b = [
+            input[0],
+            *input.get(1).unwrap_or(&0),
+            *input.get(2).unwrap_or(&0),
+        ];
+        let indices = [
+            b[0] >> 2,
+            ((b[0] & 0x03) << 4) | (b[1] >> 4),
+            ((b[1] & 0x0F) << 2) | (b[2] >> 6),
+            b[2] & 0x3F,
+        ];
+        // `n` input bytes encode into `n + 1` output bytes, the remainder is
+        // padding.
+        for (output, index) in output.iter_mut().zip(indices).take(n + 1) {
+            *output = BASE64_ALPHABET[index as usize];
+        }
+    }
+    output
+}
+
+/// SHA-1 hash of `input`, RFC 3174.
+///
+/// SHA-1 shouldn't be used for anything security related, it's only used here
+/// because it's required by the WebSocket handshake.
+fn sha1(input: &[u8]) -> [u8; 20] {
+    let mut h: [u32; 5] = [0x67452301, 0xEFCDAB89, 0x98BADCFE, 0x10325476, 0xC3D2E1F0];
+
+    // Pad the input with a single 1 bit, zeros and the length in bits, to a
+    // multiple of 64 bytes.
+    let mut data = input.to_vec();
+    data.push(0x80);
+    while data.len() % 64 != 56 {
+        data.push(0);
+    }
+    data.extend_from_slice(&((input.len() as u64) * 8).to_be_bytes());
+
+    for block in data.chunks(64) {
+        let mut w = [0u32; 80];
+        for (i, word) in block.chunks(4).enumerate() {
+            w[i] = u32::from_be_bytes([word[0], word[1], word[2], word[3]]);
+        }
+        for i in 16..80 {
+            w[i] = (w[i - 3] ^ w[i - 8] ^ w[i - 14] ^ w[i - 16]).rotate_left(1);
+        }
+
+        let [mut a, mut b, mut c, mut d, mut e] = h;
+        for (i, w) in w.iter().enumerate() {
+            let (f, k) = match i {
+                0..=19 => ((b & c) | (!b & d), 0x5A827999),
+                20..=39 => (b ^ c ^ d, 0x6ED9EBA1),
+                40..=59 => ((b & c) | (b & d) | (c & d), 0x8F1BBCDC),
+                _ => (b ^ c ^ d, 0xCA62C1D6),
+            };
+            let temp = a
+                .rotate_left(5)
+                .wrapping_add(f)
+                .wrapping_add(e)
+                .wrapping_add(k)
+                .wrapping_add(*w);
+            e = d;
+            d = c;
+            c = b.rotate_left(30);
+            b = a;
+            a = temp;
+        }
+
+        for (h, v) in h.iter_mut().zip([a, b, c, d, e]) {
+            *h = h.wrapping_add(v);
+        }
+    }
+
+    let mut output = [0; 20];
+    for (output, h) in output.chunks_mut(4).zip(h) {
+        output.copy_from_slice(&h.to_be_bytes());
+    }
+    output
+}
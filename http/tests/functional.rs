@@ -15,13 +15,27 @@ fn assert_sync<T: Sync>() {}
 
 #[path = "functional"] // rustfmt can't find the files.
 mod functional {
+    mod auth;
     mod body;
+    mod cache;
     mod client;
+    #[cfg(feature = "compression")]
+    mod compress;
+    mod deadline;
+    #[cfg(feature = "form")]
+    mod form;
     mod from_header_value;
+    mod handler;
     mod header;
+    #[cfg(feature = "json")]
+    mod json;
+    mod load_shed;
     mod message;
     mod method;
+    mod rate_limit;
+    mod request_id;
     mod route;
+    mod router;
     mod server;
     mod status_code;
     mod transform;
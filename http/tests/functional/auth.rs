@@ -0,0 +1,59 @@
+//! Tests for the auth module.
+
+use heph::test;
+use heph_http::auth::{bearer_token, AuthMiddleware, Unauthorized};
+use heph_http::body::EmptyBody;
+use heph_http::handler::Handler;
+use heph_http::{Header, HeaderName, Request, Response, StatusCode};
+
+fn request(authorization: Option<&str>) -> Request<EmptyBody> {
+    let mut request = Request::get("/".to_owned());
+    if let Some(value) = authorization {
+        let header = Header::new(HeaderName::AUTHORIZATION, value.as_bytes());
+        request.headers_mut().append(header);
+    }
+    request
+}
+
+#[test]
+fn bearer_token_from_request() {
+    let tests = &[
+        (None, None),
+        (Some("Bearer abc"), Some("abc")),
+        (Some("bearer abc "), Some("abc")),
+        (Some("Basic abc"), None),
+        (Some("Bearer"), None),
+    ];
+    for (authorization, expected) in tests {
+        assert_eq!(bearer_token(&request(*authorization)), *expected);
+    }
+}
+
+#[test]
+fn unauthorized_response() {
+    let response = Response::<EmptyBody>::from(Unauthorized);
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+    assert!(response.headers().is_empty());
+}
+
+#[test]
+fn auth_middleware() {
+    async fn handler(_: Request<EmptyBody>) -> Response<EmptyBody> {
+        Response::ok()
+    }
+
+    fn is_authorised(request: &Request<EmptyBody>) -> bool {
+        bearer_token(request) == Some("secret")
+    }
+
+    let middleware = AuthMiddleware::new(handler, is_authorised);
+    let tests = &[
+        (None, StatusCode::UNAUTHORIZED),
+        (Some("Bearer wrong"), StatusCode::UNAUTHORIZED),
+        (Some("Bearer secret"), StatusCode::OK),
+    ];
+    for (authorization, expected) in tests {
+        let response = test::block_on(middleware.handle((request(*authorization),)));
+        assert_eq!(response.status(), *expected);
+    }
+}
@@ -43,6 +43,7 @@ fn size() {
     assert_size::<EmptyBody>(0);
     assert_size::<FileBody<File>>(24);
     assert_size::<OneshotBody>(16);
+    assert_size::<SharedBody>(24);
     assert_size::<StreamingBody<()>>(8);
 }
 
@@ -52,6 +53,7 @@ fn send() {
     assert_send::<EmptyBody>();
     assert_send::<FileBody<File>>();
     assert_send::<OneshotBody>();
+    assert_send::<SharedBody>();
     assert_send::<StreamingBody<()>>();
 }
 
@@ -61,6 +63,7 @@ fn sync() {
     assert_sync::<EmptyBody>();
     assert_sync::<FileBody<File>>();
     assert_sync::<OneshotBody>();
+    assert_sync::<SharedBody>();
     assert_sync::<StreamingBody<()>>();
 }
 
@@ -100,6 +103,29 @@ fn oneshot_body() {
     assert_eq!(OneshotBody::from("abc").length(), BodyLength::Known(3));
 }
 
+#[test]
+fn shared_body() {
+    let body = SharedBody::new(BODY1);
+    assert_eq!(body.bytes(), BODY1);
+    assert_eq!(body, BODY1);
+    assert_eq!(body, "Hello world!");
+    assert_eq!(body.length(), BodyLength::Known(BODY1.len()));
+    assert_eq!(SharedBody::from("abc").length(), BodyLength::Known(3));
+    assert_eq!(SharedBody::from(Vec::new()).length(), BodyLength::Known(0));
+}
+
+#[test]
+fn shared_body_etag() {
+    let body = SharedBody::new(BODY1);
+    let etag = body.etag();
+    assert!(etag.starts_with('"') && etag.ends_with('"'));
+    assert_eq!(etag.len(), 18);
+    // Clones and bodies with the same content have the same entity-tag.
+    assert_eq!(body.clone().etag(), etag);
+    assert_eq!(SharedBody::from(BODY1.to_vec()).etag(), etag);
+    assert_ne!(SharedBody::new(BODY0).etag(), etag);
+}
+
 #[test]
 fn streaming_body() {
     assert_eq!(
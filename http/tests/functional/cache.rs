@@ -0,0 +1,185 @@
+//! Tests for the cache module.
+
+use std::cell::Cell;
+use std::future::{ready, Ready};
+use std::rc::Rc;
+use std::thread::sleep;
+use std::time::Duration;
+
+use heph::test;
+use heph_http::body::{EmptyBody, OneshotBody};
+use heph_http::cache::{CacheConfig, CacheMiddleware, ResponseCache};
+use heph_http::handler::Handler;
+use heph_http::{Header, HeaderName, Headers, Method, Request, Response, StatusCode, Version};
+
+const TTL: Duration = Duration::from_millis(50);
+
+fn request(method: Method, path: &str, headers: Headers) -> Request<EmptyBody> {
+    Request::new(method, path.to_owned(), Version::Http11, headers, EmptyBody)
+}
+
+/// Handler that counts the number of calls.
+struct CountingHandler {
+    calls: Rc<Cell<usize>>,
+    headers: Headers,
+}
+
+impl CountingHandler {
+    fn new(headers: Headers) -> (CountingHandler, Rc<Cell<usize>>) {
+        let calls = Rc::new(Cell::new(0));
+        let handler = CountingHandler {
+            calls: calls.clone(),
+            headers,
+        };
+        (handler, calls)
+    }
+}
+
+impl Handler<Request<EmptyBody>> for CountingHandler {
+    type Response = Response<OneshotBody<'static>>;
+    type Future = Ready<Self::Response>;
+
+    fn handle(&self, request: Request<EmptyBody>) -> Self::Future {
+        self.calls.set(self.calls.get() + 1);
+        let status = if request.path() == "/not_found" {
+            StatusCode::NOT_FOUND
+        } else {
+            StatusCode::OK
+        };
+        let mut response = Response::ok().with_body(OneshotBody::new(b"Hello world"));
+        *response.status_mut() = status;
+        *response.headers_mut() = self.headers.clone();
+        ready(response)
+    }
+}
+
+#[test]
+fn cache_middleware() {
+    let (handler, calls) = CountingHandler::new(Headers::EMPTY);
+    let cache = ResponseCache::new(CacheConfig::new(TTL));
+    let middleware = CacheMiddleware::new(handler, cache.clone());
+
+    let tests = [
+        // First request is a miss, second a hit.
+        (Method::Get, "/", 1),
+        (Method::Get, "/", 1),
+        // HEAD requests are cached separately.
+        (Method::Head, "/", 2),
+        (Method::Head, "/", 2),
+        (Method::Get, "/other", 3),
+        (Method::Get, "/other", 3),
+        // Not cacheable.
+        (Method::Post, "/", 4),
+        (Method::Post, "/", 5),
+        (Method::Get, "/not_found", 6),
+        (Method::Get, "/not_found", 7),
+    ];
+    for (method, path, expected_calls) in tests {
+        let response = test::block_on(middleware.handle(request(method, path, Headers::EMPTY)));
+        assert_eq!(response.body(), "Hello world");
+        assert_eq!(calls.get(), expected_calls);
+    }
+
+    let metrics = cache.metrics();
+    assert_eq!(metrics.entries, 3);
+    assert_eq!(metrics.hits, 3);
+    assert_eq!(metrics.misses, 4);
+    assert_eq!(metrics.evictions, 0);
+    assert_eq!(cache.len(), 3);
+
+    // Expired responses are removed.
+    sleep(TTL);
+    let _ = test::block_on(middleware.handle(request(Method::Get, "/", Headers::EMPTY)));
+    assert_eq!(calls.get(), 8);
+
+    cache.clear();
+    assert!(cache.is_empty());
+}
+
+#[test]
+fn cache_middleware_vary() {
+    let (handler, calls) = CountingHandler::new(Headers::EMPTY);
+    let config = CacheConfig::new(TTL).vary(HeaderName::ACCEPT_LANGUAGE);
+    let middleware = CacheMiddleware::new(handler, ResponseCache::new(config));
+
+    let headers = |lang: &[u8]| Headers::from([Header::new(HeaderName::ACCEPT_LANGUAGE, lang)]);
+    let tests = [
+        (headers(b"en"), 1),
+        (headers(b"en"), 1),
+        (headers(b"nl"), 2),
+        (headers(b"nl"), 2),
+        (Headers::EMPTY, 3),
+        (Headers::EMPTY, 3),
+    ];
+    for (headers, expected_calls) in tests {
+        let _ = test::block_on(middleware.handle(request(Method::Get, "/", headers)));
+        assert_eq!(calls.get(), expected_calls);
+    }
+}
+
+#[test]
+fn cache_middleware_no_store() {
+    for value in [&b"no-store"[..], b"private", b"max-age=0, no-store"] {
+        let headers = Headers::from([Header::new(HeaderName::CACHE_CONTROL, value)]);
+        let (handler, calls) = CountingHandler::new(headers);
+        let cache = ResponseCache::new(CacheConfig::new(TTL));
+        let middleware = CacheMiddleware::new(handler, cache.clone());
+
+        for expected_calls in 1..=2 {
+            let _ = test::block_on(middleware.handle(request(Method::Get, "/", Headers::EMPTY)));
+            assert_eq!(calls.get(), expected_calls);
+        }
+        assert!(cache.is_empty());
+    }
+}
+
+#[test]
+fn cache_middleware_max_entries() {
+    let (handler, calls) = CountingHandler::new(Headers::EMPTY);
+    let cache = ResponseCache::new(CacheConfig::new(TTL).max_entries(2));
+    let middleware = CacheMiddleware::new(handler, cache.clone());
+
+    let tests = [
+        ("/1", 1),
+        ("/2", 2),
+        // Makes "/2" the least recently used.
+        ("/1", 2),
+        // Evicts "/2".
+        ("/3", 3),
+        ("/1", 3),
+        ("/3", 3),
+        ("/2", 4),
+    ];
+    for (path, expected_calls) in tests {
+        let _ = test::block_on(middleware.handle(request(Method::Get, path, Headers::EMPTY)));
+        assert_eq!(calls.get(), expected_calls);
+    }
+    assert_eq!(cache.len(), 2);
+    assert_eq!(cache.metrics().evictions, 2);
+}
+
+#[test]
+fn cache_middleware_max_size() {
+    let (handler, calls) = CountingHandler::new(Headers::EMPTY);
+    // Body is 11 bytes, so this can hold a single response.
+    let cache = ResponseCache::new(CacheConfig::new(TTL).max_size(20));
+    let middleware = CacheMiddleware::new(handler, cache.clone());
+
+    let tests = [("/1", 1), ("/1", 1), ("/2", 2), ("/1", 3)];
+    for (path, expected_calls) in tests {
+        let _ = test::block_on(middleware.handle(request(Method::Get, path, Headers::EMPTY)));
+        assert_eq!(calls.get(), expected_calls);
+    }
+    assert_eq!(cache.len(), 1);
+    assert_eq!(cache.metrics().size, 11);
+
+    // Responses larger than the maximum size are not cached.
+    let (handler, calls) = CountingHandler::new(Headers::EMPTY);
+    let cache = ResponseCache::new(CacheConfig::new(TTL).max_size(10));
+    let middleware = CacheMiddleware::new(handler, cache.clone());
+    for expected_calls in 1..=2 {
+        let _ = test::block_on(middleware.handle(request(Method::Get, "/", Headers::EMPTY)));
+        assert_eq!(calls.get(), expected_calls);
+    }
+    assert!(cache.is_empty());
+}
@@ -70,6 +70,48 @@ fn get() {
     });
 }
 
+#[test]
+fn get_reuse_connection() {
+    with_test_server!(|test_server| {
+        async fn http_actor(
+            mut ctx: actor::Context<!, ThreadSafe>,
+            address: SocketAddr,
+        ) -> io::Result<()> {
+            let mut client = Client::connect(&mut ctx, address)?.await?;
+            let headers = Headers::from([Header::new(HeaderName::CONTENT_LENGTH, b"2")]);
+            let response = client.get("/1").await?;
+            expect_response(response, Version::Http11, StatusCode::OK, &headers, b"Ok").await;
+            let response = client.get("/2").await?;
+            expect_response(response, Version::Http11, StatusCode::OK, &headers, b"Ok").await;
+            Ok(())
+        }
+
+        let (mut stream, handle) = test_server.accept(|address| {
+            let http_actor = http_actor as fn(_, _) -> _;
+            let (actor, _) = init_actor(http_actor, address).unwrap();
+            actor
+        });
+
+        for path in ["/1", "/2"] {
+            expect_request(
+                &mut stream,
+                Method::Get,
+                path,
+                Version::Http11,
+                &Headers::from([Header::new(HeaderName::USER_AGENT, USER_AGENT)]),
+                b"",
+            );
+
+            // Write response.
+            stream
+                .write_all(b"HTTP/1.1 200\r\nContent-Length: 2\r\n\r\nOk")
+                .unwrap();
+        }
+
+        handle.join().unwrap();
+    });
+}
+
 #[test]
 fn get_no_response() {
     with_test_server!(|test_server| {
@@ -231,6 +273,46 @@ fn request_with_user_agent_header() {
     });
 }
 
+#[test]
+fn post() {
+    with_test_server!(|test_server| {
+        async fn http_actor(
+            mut ctx: actor::Context<!, ThreadSafe>,
+            address: SocketAddr,
+        ) -> io::Result<()> {
+            let mut client = Client::connect(&mut ctx, address)?.await?;
+            // NOTE: using `EmptyBody` as `OneshotBody` runs into the problem
+            // described in the FIXME below.
+            let response = client.post("/", EmptyBody).await?;
+            let headers = Headers::from([Header::new(HeaderName::CONTENT_LENGTH, b"2")]);
+            expect_response(response, Version::Http11, StatusCode::OK, &headers, b"Ok").await;
+            Ok(())
+        }
+
+        let (mut stream, handle) = test_server.accept(|address| {
+            let http_actor = http_actor as fn(_, _) -> _;
+            let (actor, _) = init_actor(http_actor, address).unwrap();
+            actor
+        });
+
+        expect_request(
+            &mut stream,
+            Method::Post,
+            "/",
+            Version::Http11,
+            &Headers::from([Header::new(HeaderName::USER_AGENT, USER_AGENT)]),
+            b"",
+        );
+
+        // Write response.
+        stream
+            .write_all(b"HTTP/1.1 200\r\nContent-Length: 2\r\n\r\nOk")
+            .unwrap();
+
+        handle.join().unwrap();
+    });
+}
+
 /* FIXME: The following tests have the following problem:
 error: implementation of `body::private::PrivateBody` is not general enough
    --> http/tests/functional/client.rs:255:48
@@ -378,6 +460,47 @@ fn request_sets_content_length_header() {
         handle.join().unwrap();
     });
 }
+
+#[test]
+fn post_with_body() {
+    with_test_server!(|test_server| {
+        async fn http_actor(
+            mut ctx: actor::Context<!, ThreadSafe>,
+            address: SocketAddr,
+        ) -> io::Result<()> {
+            let mut client = Client::connect(&mut ctx, address)?.await?;
+            let response = client.post("/", OneshotBody::new(b"Hello")).await?;
+            let headers = Headers::from([Header::new(HeaderName::CONTENT_LENGTH, b"2")]);
+            expect_response(response, Version::Http11, StatusCode::OK, &headers, b"Ok").await;
+            Ok(())
+        }
+
+        let (mut stream, handle) = test_server.accept(|address| {
+            let http_actor = http_actor as fn(_, _) -> _;
+            let (actor, _) = init_actor(http_actor, address).unwrap();
+            actor
+        });
+
+        expect_request(
+            &mut stream,
+            Method::Post,
+            "/",
+            Version::Http11,
+            &Headers::from([
+                Header::new(HeaderName::USER_AGENT, USER_AGENT),
+                Header::new(HeaderName::CONTENT_LENGTH, b"5"),
+            ]),
+            b"Hello",
+        );
+
+        // Write response.
+        stream
+            .write_all(b"HTTP/1.1 200\r\nContent-Length: 2\r\n\r\nOk")
+            .unwrap();
+
+        handle.join().unwrap();
+    });
+}
 */
 
 // TODO: add test with `ChunkedBody`.
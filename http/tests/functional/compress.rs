@@ -0,0 +1,140 @@
+//! Tests for the compress module.
+
+use std::io::Read;
+
+use flate2::read::{GzDecoder, ZlibDecoder};
+use heph::test;
+use heph_http::body::{EmptyBody, SharedBody};
+use heph_http::compress::{Compression, CompressionMiddleware, Encoding};
+use heph_http::handler::{Handler, Layer};
+use heph_http::{Header, HeaderName, Request, Response, StatusCode};
+
+const BODY: &str = "Hello world! Hello world! Hello world! Hello world!";
+
+fn request(accept_encoding: Option<&str>) -> Request<EmptyBody> {
+    let mut request = Request::get("/".to_owned());
+    if let Some(value) = accept_encoding {
+        let header = Header::new(HeaderName::ACCEPT_ENCODING, value.as_bytes());
+        request.headers_mut().append(header);
+    }
+    request
+}
+
+async fn handler(_: Request<EmptyBody>) -> Response<SharedBody> {
+    Response::ok().with_body(BODY.into())
+}
+
+fn decompress(encoding: Encoding, bytes: &[u8]) -> String {
+    let mut output = String::new();
+    match encoding {
+        #[cfg(feature = "brotli")]
+        Encoding::Brotli => brotli::Decompressor::new(bytes, 4096).read_to_string(&mut output),
+        Encoding::Gzip => GzDecoder::new(bytes).read_to_string(&mut output),
+        Encoding::Deflate => ZlibDecoder::new(bytes).read_to_string(&mut output),
+    }
+    .unwrap();
+    output
+}
+
+#[test]
+fn encoding_from_accept_encoding() {
+    let tests = &[
+        ("", None),
+        ("identity", None),
+        ("gzip", Some(Encoding::Gzip)),
+        ("GZIP", Some(Encoding::Gzip)),
+        ("deflate", Some(Encoding::Deflate)),
+        ("deflate, gzip", Some(Encoding::Gzip)),
+        ("gzip;q=0.5, deflate", Some(Encoding::Deflate)),
+        ("gzip; q=0.5, deflate;q=0.8", Some(Encoding::Deflate)),
+        ("gzip;q=0, deflate;q=0", None),
+        ("gzip;q=0, br;q=0, *", Some(Encoding::Deflate)),
+        ("*;q=0", None),
+        ("compress, unknown", None),
+    ];
+    for (value, expected) in tests {
+        assert_eq!(
+            Encoding::from_accept_encoding(value),
+            *expected,
+            "{}",
+            value
+        );
+    }
+}
+
+#[test]
+fn encoding_compress() {
+    for encoding in [Encoding::Gzip, Encoding::Deflate] {
+        let compressed = encoding.compress(BODY.as_bytes()).unwrap();
+        assert_eq!(decompress(encoding, &compressed), BODY);
+    }
+}
+
+#[test]
+#[cfg(feature = "brotli")]
+fn brotli() {
+    assert_eq!(Encoding::from_accept_encoding("br"), Some(Encoding::Brotli));
+    assert_eq!(
+        Encoding::from_accept_encoding("gzip, deflate, br"),
+        Some(Encoding::Brotli)
+    );
+
+    let compressed = Encoding::Brotli.compress(BODY.as_bytes()).unwrap();
+    assert_eq!(decompress(Encoding::Brotli, &compressed), BODY);
+
+    let middleware = Compression::new().min_size(10).layer(handler);
+    let response = test::block_on(middleware.handle((request(Some("br")),)));
+    assert_eq!(response.status(), StatusCode::OK);
+    let content_encoding: &str = response
+        .headers()
+        .get_value(&HeaderName::CONTENT_ENCODING)
+        .unwrap()
+        .unwrap();
+    assert_eq!(content_encoding, "br");
+    assert_eq!(decompress(Encoding::Brotli, response.body().bytes()), BODY);
+}
+
+#[test]
+fn compression_middleware() {
+    let middleware = Compression::new().min_size(10).layer(handler);
+    for (accept, encoding) in [("gzip", Encoding::Gzip), ("deflate", Encoding::Deflate)] {
+        let response = test::block_on(middleware.handle((request(Some(accept)),)));
+        assert_eq!(response.status(), StatusCode::OK);
+        let content_encoding: &str = response
+            .headers()
+            .get_value(&HeaderName::CONTENT_ENCODING)
+            .unwrap()
+            .unwrap();
+        assert_eq!(content_encoding, encoding.as_str());
+        let vary: &str = response
+            .headers()
+            .get_value(&HeaderName::VARY)
+            .unwrap()
+            .unwrap();
+        assert_eq!(vary, "Accept-Encoding");
+        assert_eq!(decompress(encoding, response.body().bytes()), BODY);
+    }
+}
+
+#[test]
+fn compression_middleware_not_accepted() {
+    let middleware = Compression::new().min_size(10).layer(handler);
+    let response = test::block_on(middleware.handle((request(None),)));
+    assert!(response
+        .headers()
+        .get(&HeaderName::CONTENT_ENCODING)
+        .is_none());
+    assert_eq!(response.body(), BODY);
+}
+
+#[test]
+fn compression_middleware_small_body() {
+    // Default minimum size is larger than `BODY`.
+    let middleware = CompressionMiddleware::new(handler);
+    let response = test::block_on(middleware.handle((request(Some("gzip")),)));
+    assert!(response
+        .headers()
+        .get(&HeaderName::CONTENT_ENCODING)
+        .is_none());
+    assert_eq!(response.body(), BODY);
+}
@@ -0,0 +1,113 @@
+use std::time::{Duration, Instant, SystemTime};
+
+use heph_http::deadline::{format_timeout, from_headers, parse_timeout, propagate};
+use heph_http::{Header, HeaderName, Headers};
+
+#[test]
+fn parse_timeout_valid() {
+    let tests = &[
+        ("1n", Duration::from_nanos(1)),
+        ("10u", Duration::from_micros(10)),
+        ("100m", Duration::from_millis(100)),
+        ("5S", Duration::from_secs(5)),
+        ("2M", Duration::from_secs(120)),
+        ("1H", Duration::from_secs(3600)),
+        ("99999999m", Duration::from_millis(99_999_999)),
+    ];
+    for (input, expected) in tests {
+        assert_eq!(
+            parse_timeout(input.as_bytes()),
+            Some(*expected),
+            "{}",
+            input
+        );
+    }
+}
+
+#[test]
+fn parse_timeout_invalid() {
+    let tests = &["", "m", "100", "100x", "-1S", "1.5S", "123456789m"];
+    for input in tests {
+        assert_eq!(parse_timeout(input.as_bytes()), None, "{}", input);
+    }
+}
+
+#[test]
+fn format_timeout_units() {
+    let tests = &[
+        (Duration::ZERO, "0n"),
+        (Duration::from_nanos(99_999_999), "99999999n"),
+        (Duration::from_millis(100), "100000u"),
+        (Duration::from_secs(5), "5000000u"),
+        (Duration::from_secs(100_000_000), "1666666M"),
+    ];
+    for (input, expected) in tests {
+        assert_eq!(format_timeout(*input), *expected);
+        assert!(parse_timeout(expected.as_bytes()).unwrap() <= *input);
+    }
+}
+
+#[test]
+fn from_headers_grpc_timeout() {
+    let mut headers = Headers::EMPTY;
+    assert_eq!(from_headers(&headers), None);
+
+    headers.append(Header::new(HeaderName::GRPC_TIMEOUT, b"1S"));
+    let start = Instant::now();
+    let deadline = from_headers(&headers).unwrap();
+    assert!(deadline >= start + Duration::from_secs(1));
+    assert!(deadline <= Instant::now() + Duration::from_secs(1));
+}
+
+#[test]
+fn from_headers_unix_deadline() {
+    let since_epoch = SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .unwrap();
+    let value = (since_epoch + Duration::from_secs(10))
+        .as_millis()
+        .to_string();
+    let mut headers = Headers::EMPTY;
+    headers.append(Header::new(
+        HeaderName::X_REQUEST_DEADLINE,
+        value.as_bytes(),
+    ));
+    let deadline = from_headers(&headers).unwrap();
+    let remaining = deadline - Instant::now();
+    assert!(remaining > Duration::from_secs(9) && remaining <= Duration::from_secs(10));
+
+    // Uses the earliest deadline.
+    headers.append(Header::new(HeaderName::GRPC_TIMEOUT, b"1S"));
+    let deadline = from_headers(&headers).unwrap();
+    assert!(deadline <= Instant::now() + Duration::from_secs(1));
+
+    // Deadline in the past.
+    let mut headers = Headers::EMPTY;
+    headers.append(Header::new(HeaderName::X_REQUEST_DEADLINE, b"1000"));
+    assert!(from_headers(&headers).unwrap() <= Instant::now());
+}
+
+#[test]
+fn from_headers_invalid() {
+    let mut headers = Headers::EMPTY;
+    headers.append(Header::new(HeaderName::GRPC_TIMEOUT, b"soon"));
+    headers.append(Header::new(HeaderName::X_REQUEST_DEADLINE, b"tomorrow"));
+    assert_eq!(from_headers(&headers), None);
+}
+
+#[test]
+fn propagate_deadline() {
+    let mut headers = Headers::EMPTY;
+    propagate(Instant::now() + Duration::from_secs(2), &mut headers);
+    let timeout = headers.get_bytes(&HeaderName::GRPC_TIMEOUT).unwrap();
+    let timeout = parse_timeout(timeout).unwrap();
+    assert!(timeout > Duration::from_secs(1) && timeout <= Duration::from_secs(2));
+
+    // Deadline passed.
+    propagate(Instant::now() - Duration::from_secs(1), &mut headers);
+    assert_eq!(
+        headers.get_bytes(&HeaderName::GRPC_TIMEOUT),
+        Some(&b"0n"[..])
+    );
+    assert_eq!(headers.len(), 1);
+}
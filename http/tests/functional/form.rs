@@ -0,0 +1,88 @@
+//! Tests for the form module.
+
+use heph_http::body::{EmptyBody, SharedBody};
+use heph_http::form::FormError;
+use heph_http::{Headers, Method, Request, Response, StatusCode, Version};
+use serde::Deserialize;
+
+#[derive(Debug, Deserialize, PartialEq)]
+struct Search {
+    query: String,
+    page: Option<usize>,
+}
+
+fn request(path: &str) -> Request<EmptyBody> {
+    Request::new(
+        Method::Get,
+        path.to_owned(),
+        Version::Http11,
+        Headers::EMPTY,
+        EmptyBody,
+    )
+}
+
+#[test]
+fn query_as() {
+    let tests = [
+        (
+            "/search?query=heph",
+            Search {
+                query: "heph".to_owned(),
+                page: None,
+            },
+        ),
+        (
+            "/search?query=heph&page=2",
+            Search {
+                query: "heph".to_owned(),
+                page: Some(2),
+            },
+        ),
+        (
+            "/search?page=3&query=hello+world%21",
+            Search {
+                query: "hello world!".to_owned(),
+                page: Some(3),
+            },
+        ),
+    ];
+    for (path, expected) in tests {
+        let got: Search = request(path).query_as().unwrap();
+        assert_eq!(got, expected, "path: {}", path);
+    }
+}
+
+#[test]
+fn query_as_invalid() {
+    let tests = [
+        // Missing query string.
+        "/search",
+        // Missing required field.
+        "/search?page=1",
+        // Invalid type.
+        "/search?query=heph&page=abc",
+    ];
+    for path in tests {
+        let err = request(path).query_as::<Search>().unwrap_err();
+        assert!(matches!(err, FormError::Parse(_)), "path: {}", path);
+        assert_eq!(err.proper_status_code(), StatusCode::BAD_REQUEST);
+    }
+}
+
+#[test]
+fn form_error_response() {
+    let tests = [
+        (
+            FormError::UnsupportedMediaType,
+            StatusCode::UNSUPPORTED_MEDIA_TYPE,
+        ),
+        (FormError::TooLarge, StatusCode::PAYLOAD_TOO_LARGE),
+    ];
+    for (err, expected) in tests {
+        assert_eq!(err.proper_status_code(), expected);
+        let msg = err.to_string();
+        let response = Response::<SharedBody>::from(err);
+        assert_eq!(response.status(), expected);
+        assert_eq!(*response.body(), *msg);
+    }
+}
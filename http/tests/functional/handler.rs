@@ -0,0 +1,36 @@
+//! Tests for the handler module.
+
+use heph::test;
+use heph_http::access_log::AccessLog;
+use heph_http::auth::{bearer_token, Auth};
+use heph_http::body::EmptyBody;
+use heph_http::handler::{Handler, Layer, Stack};
+use heph_http::request_id::RequestIds;
+use heph_http::{Header, HeaderName, Request, Response, StatusCode};
+
+async fn handler(_: Request<EmptyBody>) -> Response<EmptyBody> {
+    Response::ok()
+}
+
+fn is_authorised(request: &Request<EmptyBody>) -> bool {
+    bearer_token(request) == Some("secret")
+}
+
+#[test]
+fn stack_layers() {
+    let layers = Stack::new(Auth::new(is_authorised), AccessLog).push(RequestIds::new());
+    let handler = layers.layer(handler);
+
+    // Unauthorised requests still get a request id, as the request id layer is
+    // the outer most layer.
+    let response = test::block_on(handler.handle((Request::get("/".to_owned()),)));
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+    assert!(response.headers().get(&HeaderName::X_REQUEST_ID).is_some());
+
+    let mut request = Request::get("/".to_owned());
+    let header = Header::new(HeaderName::AUTHORIZATION, b"Bearer secret");
+    request.headers_mut().append(header);
+    let response = test::block_on(handler.handle((request,)));
+    assert_eq!(response.status(), StatusCode::OK);
+    assert!(response.headers().get(&HeaderName::X_REQUEST_ID).is_some());
+}
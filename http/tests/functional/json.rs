@@ -0,0 +1,41 @@
+//! Tests for the json module.
+
+use heph_http::body::SharedBody;
+use heph_http::json::JsonError;
+use heph_http::{HeaderName, Response, StatusCode};
+use serde_json::json;
+
+#[test]
+fn response_json() {
+    let value = json!({ "id": 123, "name": "Heph" });
+    let response = Response::json(&value).unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let content_type: &str = response
+        .headers()
+        .get_value(&HeaderName::CONTENT_TYPE)
+        .unwrap()
+        .unwrap();
+    assert_eq!(content_type, "application/json");
+    let got: serde_json::Value = serde_json::from_slice(response.body().bytes()).unwrap();
+    assert_eq!(got, value);
+}
+
+#[test]
+fn json_error_response() {
+    let parse_err = serde_json::from_slice::<serde_json::Value>(b"{").unwrap_err();
+    let tests = [
+        (
+            JsonError::UnsupportedMediaType,
+            StatusCode::UNSUPPORTED_MEDIA_TYPE,
+        ),
+        (JsonError::TooLarge, StatusCode::PAYLOAD_TOO_LARGE),
+        (JsonError::Parse(parse_err), StatusCode::BAD_REQUEST),
+    ];
+    for (err, expected) in tests {
+        assert_eq!(err.proper_status_code(), expected);
+        let msg = err.to_string();
+        let response = Response::<SharedBody>::from(err);
+        assert_eq!(response.status(), expected);
+        assert_eq!(*response.body(), *msg);
+    }
+}
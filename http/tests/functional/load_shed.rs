@@ -0,0 +1,99 @@
+//! Tests for the load_shed module.
+
+use std::future::{pending, Pending};
+use std::pin::Pin;
+use std::time::Duration;
+
+use heph::test;
+use heph_http::body::EmptyBody;
+use heph_http::handler::Handler;
+use heph_http::load_shed::{LoadShedMiddleware, LoadShedder, ServiceUnavailable};
+use heph_http::{HeaderName, Headers, Method, Request, Response, StatusCode, Version};
+
+const RETRY_AFTER: Duration = Duration::from_secs(1);
+
+fn request() -> Request<EmptyBody> {
+    Request::new(
+        Method::Get,
+        "/".to_owned(),
+        Version::Http11,
+        Headers::EMPTY,
+        EmptyBody,
+    )
+}
+
+#[test]
+fn load_shedder_no_thresholds() {
+    let shedder = LoadShedder::new(RETRY_AFTER);
+    assert!(!shedder.is_overloaded());
+    assert_eq!(shedder.in_flight(), 0);
+}
+
+#[test]
+fn service_unavailable_response() {
+    let response = Response::<EmptyBody>::from(ServiceUnavailable {
+        retry_after: Duration::from_millis(2500),
+    });
+    assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+    let retry_after: &str = response
+        .headers()
+        .get_value(&HeaderName::RETRY_AFTER)
+        .unwrap()
+        .unwrap();
+    assert_eq!(retry_after, "3");
+}
+
+#[test]
+fn load_shed_middleware() {
+    async fn handler(_: Request<EmptyBody>) -> Response<EmptyBody> {
+        Response::ok()
+    }
+
+    let shedder = LoadShedder::new(RETRY_AFTER).max_in_flight(1);
+    let middleware = LoadShedMiddleware::new(handler, shedder.clone());
+
+    // Completed requests are no longer in-flight.
+    for _ in 0..3 {
+        let response = test::block_on(middleware.handle((request(),)));
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(shedder.in_flight(), 0);
+    }
+}
+
+#[test]
+fn load_shed_middleware_in_flight() {
+    /// Handler that never completes.
+    #[derive(Debug)]
+    struct PendingHandler;
+
+    impl Handler<Request<EmptyBody>> for PendingHandler {
+        type Response = Response<EmptyBody>;
+        type Future = Pending<Self::Response>;
+
+        fn handle(&self, _: Request<EmptyBody>) -> Self::Future {
+            pending()
+        }
+    }
+
+    let shedder = LoadShedder::new(RETRY_AFTER).max_in_flight(2);
+    let middleware = LoadShedMiddleware::new(PendingHandler, shedder.clone());
+
+    let mut future1 = Box::pin(middleware.handle(request()));
+    assert!(test::poll_future(Pin::as_mut(&mut future1)).is_pending());
+    let future2 = middleware.handle(request());
+    assert_eq!(shedder.in_flight(), 2);
+    assert!(shedder.is_overloaded());
+
+    // Load is shed.
+    let response = test::block_on(middleware.handle(request()));
+    assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+    assert!(response.headers().get(&HeaderName::RETRY_AFTER).is_some());
+    assert_eq!(shedder.in_flight(), 2);
+
+    // Dropping the futures marks the requests as done.
+    drop(future1);
+    assert_eq!(shedder.in_flight(), 1);
+    drop(future2);
+    assert_eq!(shedder.in_flight(), 0);
+    assert!(!shedder.is_overloaded());
+}
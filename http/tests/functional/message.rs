@@ -218,7 +218,7 @@ fn response_map_body() {
 
 #[test]
 fn response_builder() {
-    let tests: [(fn() -> Response<EmptyBody>, StatusCode); 16] = [
+    let tests: [(fn() -> Response<EmptyBody>, StatusCode); 17] = [
         (Response::ok, StatusCode::OK),
         (Response::created, StatusCode::CREATED),
         (Response::no_content, StatusCode::NO_CONTENT),
@@ -230,6 +230,7 @@ fn response_builder() {
         (Response::method_not_allowed, StatusCode::METHOD_NOT_ALLOWED),
         (Response::gone, StatusCode::GONE),
         (Response::length_required, StatusCode::LENGTH_REQUIRED),
+        (Response::too_many_requests, StatusCode::TOO_MANY_REQUESTS),
         (Response::server_error, StatusCode::INTERNAL_SERVER_ERROR),
         (Response::not_implemented, StatusCode::NOT_IMPLEMENTED),
         (Response::bad_gateway, StatusCode::BAD_GATEWAY),
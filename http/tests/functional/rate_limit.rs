@@ -0,0 +1,89 @@
+//! Tests for the rate_limit module.
+
+use std::net::{IpAddr, Ipv4Addr, SocketAddr};
+use std::thread::sleep;
+use std::time::Duration;
+
+use heph::test;
+use heph_http::body::EmptyBody;
+use heph_http::handler::Handler;
+use heph_http::rate_limit::{peer_ip, RateLimitMiddleware, RateLimiter, TooManyRequests};
+use heph_http::{HeaderName, Headers, Method, Request, Response, StatusCode, Version};
+
+const INTERVAL: Duration = Duration::from_millis(50);
+
+fn request() -> Request<EmptyBody> {
+    Request::new(
+        Method::Get,
+        "/".to_owned(),
+        Version::Http11,
+        Headers::EMPTY,
+        EmptyBody,
+    )
+}
+
+fn address(last: u8) -> SocketAddr {
+    SocketAddr::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, last)), 8080)
+}
+
+#[test]
+fn rate_limiter() {
+    let limiter = RateLimiter::new(2, INTERVAL);
+    assert_eq!(limiter.check("a"), Ok(()));
+    assert_eq!(limiter.check("a"), Ok(()));
+    let retry_after = limiter.check("a").unwrap_err();
+    assert!(retry_after <= INTERVAL);
+    // Other keys have their own limit.
+    assert_eq!(limiter.check("b"), Ok(()));
+
+    // Clones share the same state.
+    let limiter2 = limiter.clone();
+    assert!(limiter2.check("a").is_err());
+
+    // After the refill interval a single token is added.
+    sleep(INTERVAL);
+    assert_eq!(limiter.check("a"), Ok(()));
+    assert!(limiter.check("a").is_err());
+}
+
+#[test]
+#[should_panic = "can't create a RateLimiter with zero capacity"]
+fn rate_limiter_zero_capacity() {
+    let _ = RateLimiter::<()>::new(0, INTERVAL);
+}
+
+#[test]
+fn too_many_requests_response() {
+    let response = Response::<EmptyBody>::from(TooManyRequests {
+        retry_after: Duration::from_millis(1500),
+    });
+    assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+    let retry_after: &str = response
+        .headers()
+        .get_value(&HeaderName::RETRY_AFTER)
+        .unwrap()
+        .unwrap();
+    assert_eq!(retry_after, "2");
+}
+
+#[test]
+fn rate_limit_middleware() {
+    async fn handler(_: Request<EmptyBody>, _: SocketAddr) -> Response<EmptyBody> {
+        Response::ok()
+    }
+
+    let limiter = RateLimiter::new(1, INTERVAL);
+    let middleware = RateLimitMiddleware::new(handler, limiter, peer_ip);
+
+    let tests = [
+        (address(1), StatusCode::OK),
+        (address(1), StatusCode::TOO_MANY_REQUESTS),
+        // Different peer.
+        (address(2), StatusCode::OK),
+        (address(2), StatusCode::TOO_MANY_REQUESTS),
+    ];
+    for (address, expected_status) in tests {
+        let response = test::block_on(middleware.handle((request(), address)));
+        assert_eq!(response.status(), expected_status);
+    }
+}
@@ -0,0 +1,68 @@
+//! Tests for the request_id module.
+
+use heph::test;
+use heph_http::body::EmptyBody;
+use heph_http::handler::Handler;
+use heph_http::request_id::{RequestIdMiddleware, RequestIds};
+use heph_http::{Header, HeaderName, Request, Response};
+
+/// Returns the request id of the request in the response body.
+async fn handler(request: Request<EmptyBody>) -> Response<String> {
+    let request_id: &str = request
+        .headers()
+        .get_value(&HeaderName::X_REQUEST_ID)
+        .unwrap()
+        .unwrap();
+    Response::ok().with_body(request_id.to_owned())
+}
+
+fn request_id<B>(response: &Response<B>) -> &str {
+    response
+        .headers()
+        .get_value(&HeaderName::X_REQUEST_ID)
+        .unwrap()
+        .unwrap()
+}
+
+fn request_with_id(id: &str) -> Request<EmptyBody> {
+    let mut request = Request::get("/".to_owned());
+    let header = Header::new(HeaderName::X_REQUEST_ID, id.as_bytes());
+    request.headers_mut().append(header);
+    request
+}
+
+#[test]
+fn request_ids_unique() {
+    let ids = RequestIds::new();
+    let id1 = ids.next_id();
+    let id2 = ids.clone().next_id();
+    assert_ne!(id1, id2);
+    assert_eq!(id1.len(), 17);
+}
+
+#[test]
+fn request_id_middleware() {
+    let middleware = RequestIdMiddleware::new(handler, RequestIds::new());
+
+    let response = test::block_on(middleware.handle((Request::get("/".to_owned()),)));
+    assert_eq!(request_id(&response), response.body());
+    let response2 = test::block_on(middleware.handle((Request::get("/".to_owned()),)));
+    assert_eq!(request_id(&response2), response2.body());
+    assert_ne!(response.body(), response2.body());
+}
+
+#[test]
+fn request_id_middleware_incoming() {
+    let middleware = RequestIdMiddleware::new(handler, RequestIds::new());
+    let response = test::block_on(middleware.handle((request_with_id("abc"),)));
+    assert_eq!(request_id(&response), "abc");
+    assert_eq!(response.body(), "abc");
+}
+
+#[test]
+fn request_id_middleware_ignore_incoming() {
+    let middleware = RequestIdMiddleware::new(handler, RequestIds::new().ignore_incoming());
+    let response = test::block_on(middleware.handle((request_with_id("abc"),)));
+    assert_ne!(request_id(&response), "abc");
+    assert_eq!(request_id(&response), response.body());
+}
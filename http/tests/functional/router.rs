@@ -0,0 +1,123 @@
+//! Tests for the [`Router`] type.
+
+use heph::test::block_on;
+use heph_http::body::{EmptyBody, OneshotBody};
+use heph_http::router::{Params, Router};
+use heph_http::{Method, Request, Response, StatusCode};
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+enum Route {
+    Index,
+    Pet,
+    PetToy,
+    File,
+    NotFound,
+}
+
+fn router() -> Router<Route> {
+    Router::new(Route::NotFound)
+        .with_route(&[Method::Get, Method::Head], "/", Route::Index)
+        .with_route(&[Method::Get], "/pet/{id}", Route::Pet)
+        .with_route(&[Method::Get], "/pet/{id}/toy/{toy}", Route::PetToy)
+        .with_route(&[Method::Get], "/files/{*path}", Route::File)
+}
+
+#[test]
+fn find() {
+    let router = router();
+    let tests: &[(Method, &str, Route, &[(&str, &str)])] = &[
+        (Method::Get, "/", Route::Index, &[]),
+        (Method::Head, "/", Route::Index, &[]),
+        (Method::Get, "/?query=1", Route::Index, &[]),
+        (Method::Get, "/pet/123", Route::Pet, &[("id", "123")]),
+        (
+            Method::Get,
+            "/pet/123/toy/ball",
+            Route::PetToy,
+            &[("id", "123"), ("toy", "ball")],
+        ),
+        (
+            Method::Get,
+            "/files/a/b.txt",
+            Route::File,
+            &[("path", "a/b.txt")],
+        ),
+        (Method::Get, "/files/", Route::File, &[("path", "")]),
+        // Not found.
+        (Method::Post, "/", Route::NotFound, &[]),
+        (Method::Get, "/other", Route::NotFound, &[]),
+        (Method::Get, "/pet", Route::NotFound, &[]),
+        (Method::Get, "/pet/", Route::NotFound, &[]),
+        (Method::Get, "/pet/123/toy", Route::NotFound, &[]),
+        (Method::Get, "/pet/123/other", Route::NotFound, &[]),
+        (Method::Get, "", Route::NotFound, &[]),
+    ];
+    for (method, path, expected, expected_params) in tests.iter().copied() {
+        let route = router.find(method, path);
+        assert_eq!(*route.handler(), expected, "{} {}", method, path);
+        assert_eq!(route.is_not_found(), expected == Route::NotFound);
+        let params = route.params();
+        assert_eq!(params.len(), expected_params.len());
+        assert_eq!(params.is_empty(), expected_params.is_empty());
+        for (name, value) in expected_params {
+            assert_eq!(params.get(name), Some(*value));
+        }
+        assert!(params.iter().eq(expected_params.iter().copied()));
+    }
+}
+
+#[test]
+fn first_route_matches() {
+    let router = Router::new(0)
+        .with_route(&[Method::Get], "/pet/new", 1)
+        .with_route(&[Method::Get], "/pet/{id}", 2);
+    assert_eq!(*router.find(Method::Get, "/pet/new").handler(), 1);
+    assert_eq!(*router.find(Method::Get, "/pet/123").handler(), 2);
+}
+
+#[test]
+#[should_panic = "route pattern must start with `/`"]
+fn invalid_pattern_no_slash() {
+    let _ = Router::new(()).with_route(&[Method::Get], "pet", ());
+}
+
+#[test]
+#[should_panic = "rest parameter must be the last segment in route pattern"]
+fn invalid_pattern_rest_not_last() {
+    let _ = Router::new(()).with_route(&[Method::Get], "/{*rest}/pet", ());
+}
+
+#[test]
+#[should_panic = "invalid segment in route pattern"]
+fn invalid_pattern_segment() {
+    let _ = Router::new(()).with_route(&[Method::Get], "/pet{id}", ());
+}
+
+#[test]
+fn handle() {
+    async fn handler(
+        request: Request<EmptyBody>,
+        params: Params,
+    ) -> Response<OneshotBody<'static>> {
+        match params.get("id") {
+            Some("123") => Response::ok().with_body("Pet".into()),
+            Some(_) => Response::not_found().with_body("Unknown pet".into()),
+            None => {
+                assert_eq!(request.path(), "/other");
+                Response::not_found().with_body("Not found".into())
+            }
+        }
+    }
+
+    let router = Router::new(handler).with_route(&[Method::Get], "/pet/{id}", handler);
+    block_on(async move {
+        let response = router.handle(Request::get("/pet/123".to_owned())).await;
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(response.body(), "Pet");
+        let response = router.handle(Request::get("/pet/456".to_owned())).await;
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+        let response = router.handle(Request::get("/other".to_owned())).await;
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+        assert_eq!(response.body(), "Not found");
+    });
+}
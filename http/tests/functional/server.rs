@@ -11,8 +11,9 @@ use heph::actor::messages::Terminate;
 use heph::rt::{self, Runtime, ThreadLocal};
 use heph::spawn::options::{ActorOptions, Priority};
 use heph::{actor, Actor, ActorRef, NewActor, Supervisor, SupervisorStrategy};
-use heph_http::body::OneshotBody;
+use heph_http::body::{OneshotBody, SharedBody};
 use heph_http::server::{HttpServer, RequestError};
+use heph_http::websocket::{Message, WebSocket};
 use heph_http::{self as http, Header, HeaderName, Headers, Method, StatusCode, Version};
 use httpdate::fmt_http_date;
 
@@ -38,6 +39,18 @@ macro_rules! with_test_server {
     };
 }
 
+#[test]
+fn request_error_into_io_error() {
+    let err = io::Error::from(RequestError::HeadTooLarge);
+    assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+    assert_eq!(err.to_string(), "head too large");
+    let source = err.get_ref().unwrap();
+    assert_eq!(
+        source.downcast_ref::<RequestError>(),
+        Some(&RequestError::HeadTooLarge)
+    );
+}
+
 #[test]
 fn get() {
     with_test_server!(|stream| {
@@ -64,6 +77,21 @@ fn head() {
     });
 }
 
+#[test]
+fn get_shared_body() {
+    with_test_server!(|stream| {
+        stream.write_all(b"GET /shared HTTP/1.1\r\n\r\n").unwrap();
+        let mut headers = Headers::EMPTY;
+        let now = fmt_http_date(SystemTime::now());
+        headers.append(Header::new(HeaderName::DATE, now.as_bytes()));
+        let etag = SharedBody::from("OK").etag();
+        headers.append(Header::new(HeaderName::ETAG, etag.as_bytes()));
+        headers.append(Header::new(HeaderName::CONTENT_LENGTH, b"2"));
+        let body = b"OK";
+        expect_response(&mut stream, Version::Http11, StatusCode::OK, &headers, body);
+    });
+}
+
 #[test]
 fn post() {
     with_test_server!(|stream| {
@@ -79,6 +107,34 @@ fn post() {
     });
 }
 
+#[test]
+fn post_large_body() {
+    with_test_server!(|stream| {
+        const BODY_LENGTH: usize = 64 * 1024;
+        let head = format!(
+            "POST /body-length HTTP/1.1\r\nContent-Length: {}\r\n\r\n",
+            BODY_LENGTH
+        );
+        stream.write_all(head.as_bytes()).unwrap();
+        stream.write_all(&[b'a'; BODY_LENGTH]).unwrap();
+        let mut headers = Headers::EMPTY;
+        let now = fmt_http_date(SystemTime::now());
+        headers.append(Header::new(HeaderName::DATE, now.as_bytes()));
+        headers.append(Header::new(HeaderName::CONTENT_LENGTH, b"5"));
+        let body = b"65536";
+        expect_response(&mut stream, Version::Http11, StatusCode::OK, &headers, body);
+
+        // The connection should be usable for the next request.
+        stream.write_all(b"GET / HTTP/1.1\r\n\r\n").unwrap();
+        let mut headers = Headers::EMPTY;
+        let now = fmt_http_date(SystemTime::now());
+        headers.append(Header::new(HeaderName::DATE, now.as_bytes()));
+        headers.append(Header::new(HeaderName::CONTENT_LENGTH, b"2"));
+        let body = b"OK";
+        expect_response(&mut stream, Version::Http11, StatusCode::OK, &headers, body);
+    });
+}
+
 #[test]
 fn with_request_header() {
     with_test_server!(|stream| {
@@ -359,6 +415,44 @@ fn empty_body_chunked_transfer_encoding() {
     });
 }
 
+#[test]
+fn multiple_chunks_chunked_transfer_encoding() {
+    with_test_server!(|stream| {
+        stream
+            .write_all(b"POST /echo-body HTTP/1.1\r\nTransfer-Encoding: chunked\r\n\r\n")
+            .unwrap();
+        stream
+            .write_all(b"5\r\nHello\r\n6\r\n world\r\n0\r\n")
+            .unwrap();
+        let status = StatusCode::OK;
+        let mut headers = Headers::EMPTY;
+        let now = fmt_http_date(SystemTime::now());
+        headers.append(Header::new(HeaderName::DATE, now.as_bytes()));
+        headers.append(Header::new(HeaderName::CONTENT_LENGTH, b"11"));
+        let body = b"Hello world";
+        expect_response(&mut stream, Version::Http11, status, &headers, body);
+    });
+}
+
+#[test]
+fn incremental_read_chunked_transfer_encoding() {
+    with_test_server!(|stream| {
+        stream
+            .write_all(b"POST /body-length HTTP/1.1\r\nTransfer-Encoding: chunked\r\n\r\n")
+            .unwrap();
+        stream.write_all(b"5\r\nHello\r\n").unwrap();
+        sleep(Duration::from_millis(100));
+        stream.write_all(b"6\r\n world\r\n0\r\n").unwrap();
+        let status = StatusCode::OK;
+        let mut headers = Headers::EMPTY;
+        let now = fmt_http_date(SystemTime::now());
+        headers.append(Header::new(HeaderName::DATE, now.as_bytes()));
+        headers.append(Header::new(HeaderName::CONTENT_LENGTH, b"2"));
+        let body = b"11";
+        expect_response(&mut stream, Version::Http11, status, &headers, body);
+    });
+}
+
 #[test]
 fn deny_invalid_chunk_size() {
     with_test_server!(|stream| {
@@ -499,6 +593,144 @@ fn too_many_header() {
     });
 }
 
+#[test]
+fn http2_prior_knowledge() {
+    with_test_server!(|stream| {
+        stream
+            .write_all(b"PRI * HTTP/2.0\r\n\r\nSM\r\n\r\n")
+            .unwrap();
+        // Empty SETTINGS frame, followed by a GOAWAY frame with the
+        // HTTP_1_1_REQUIRED error code.
+        let expected = [
+            0, 0, 0, 0x4, 0, 0, 0, 0, 0, 0, 0, 8, 0x7, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0xd,
+        ];
+        let mut buf = [0; 26];
+        stream.read_exact(&mut buf).unwrap();
+        assert_eq!(buf, expected);
+        assert_eq!(stream.read(&mut buf).unwrap(), 0);
+    });
+}
+
+#[test]
+fn websocket() {
+    with_test_server!(|stream| {
+        // The first frame is send along with the request.
+        websocket_handshake(&mut stream, &websocket_frame(true, 0x1, b"Hello"));
+        assert_eq!(read_websocket_frame(&mut stream), (0x81, b"Hello".to_vec()));
+
+        // Fragmented binary message, with a ping in the middle.
+        stream
+            .write_all(&websocket_frame(false, 0x2, b"Hello"))
+            .unwrap();
+        stream
+            .write_all(&websocket_frame(true, 0x9, b"ping"))
+            .unwrap();
+        stream
+            .write_all(&websocket_frame(true, 0x0, b" world"))
+            .unwrap();
+        assert_eq!(read_websocket_frame(&mut stream), (0x8A, b"ping".to_vec()));
+        let want = (0x82, b"Hello world".to_vec());
+        assert_eq!(read_websocket_frame(&mut stream), want);
+
+        // Close, the server should echo the status code.
+        stream
+            .write_all(&websocket_frame(true, 0x8, &[0x03, 0xE8, b'b', b'y', b'e']))
+            .unwrap();
+        assert_eq!(read_websocket_frame(&mut stream), (0x88, vec![0x03, 0xE8]));
+        assert_eq!(stream.read(&mut [0; 8]).unwrap(), 0);
+    });
+}
+
+#[test]
+fn websocket_invalid_utf8() {
+    with_test_server!(|stream| {
+        websocket_handshake(&mut stream, &[]);
+        stream
+            .write_all(&websocket_frame(true, 0x1, &[0xFF, 0xFE]))
+            .unwrap();
+        // 1007 Invalid frame payload data.
+        assert_eq!(read_websocket_frame(&mut stream), (0x88, vec![0x03, 0xEF]));
+    });
+}
+
+#[test]
+fn websocket_unmasked_frame() {
+    with_test_server!(|stream| {
+        websocket_handshake(&mut stream, &[]);
+        stream.write_all(&[0x81, 0x02, b'H', b'i']).unwrap();
+        // 1002 Protocol error.
+        assert_eq!(read_websocket_frame(&mut stream), (0x88, vec![0x03, 0xEA]));
+    });
+}
+
+#[test]
+fn websocket_unsupported_version() {
+    with_test_server!(|stream| {
+        stream.write_all(b"GET /websocket HTTP/1.1\r\nUpgrade: websocket\r\nConnection: Upgrade\r\nSec-WebSocket-Key: dGhlIHNhbXBsZSBub25jZQ==\r\nSec-WebSocket-Version: 8\r\n\r\n").unwrap();
+        let mut headers = Headers::EMPTY;
+        let now = fmt_http_date(SystemTime::now());
+        headers.append(Header::new(HeaderName::CONNECTION, b"close"));
+        headers.append(Header::new(HeaderName::SEC_WEBSOCKET_VERSION, b"13"));
+        headers.append(Header::new(HeaderName::DATE, now.as_bytes()));
+        headers.append(Header::new(HeaderName::CONTENT_LENGTH, b"29"));
+        let body = b"unsupported WebSocket version";
+        let status = StatusCode::UPGRADE_REQUIRED;
+        expect_response(&mut stream, Version::Http11, status, &headers, body);
+    });
+}
+
+#[test]
+fn websocket_not_upgrade_request() {
+    with_test_server!(|stream| {
+        stream
+            .write_all(b"GET /websocket HTTP/1.1\r\n\r\n")
+            .unwrap();
+        let mut headers = Headers::EMPTY;
+        let now = fmt_http_date(SystemTime::now());
+        headers.append(Header::new(HeaderName::CONNECTION, b"close"));
+        headers.append(Header::new(HeaderName::DATE, now.as_bytes()));
+        headers.append(Header::new(HeaderName::CONTENT_LENGTH, b"31"));
+        let body = b"not a WebSocket upgrade request";
+        let status = StatusCode::BAD_REQUEST;
+        expect_response(&mut stream, Version::Http11, status, &headers, body);
+    });
+}
+
+/// Upgrade `stream` to a WebSocket connection, sending `frames` along with the
+/// request.
+fn websocket_handshake(stream: &mut TcpStream, frames: &[u8]) {
+    // Example from RFC 6455 section 1.3.
+    let mut request = b"GET /websocket HTTP/1.1\r\nHost: server.example.com\r\nUpgrade: websocket\r\nConnection: keep-alive, Upgrade\r\nSec-WebSocket-Key: dGhlIHNhbXBsZSBub25jZQ==\r\nSec-WebSocket-Version: 13\r\n\r\n".to_vec();
+    request.extend_from_slice(frames);
+    stream.write_all(&request).unwrap();
+
+    let expected = b"HTTP/1.1 101 \r\nUpgrade: websocket\r\nConnection: Upgrade\r\nSec-WebSocket-Accept: s3pPLMBiTxaQ9kYGzzhZRbK+xOo=\r\n\r\n";
+    let mut buf = vec![0; expected.len()];
+    stream.read_exact(&mut buf).unwrap();
+    assert_eq!(buf, expected);
+}
+
+/// Returns a masked WebSocket frame.
+fn websocket_frame(fin: bool, opcode: u8, payload: &[u8]) -> Vec<u8> {
+    const MASK: [u8; 4] = [0x37, 0xFA, 0x21, 0x3D];
+    assert!(payload.len() <= 125);
+    let first = if fin { 0x80 | opcode } else { opcode };
+    let mut frame = vec![first, 0x80 | payload.len() as u8];
+    frame.extend_from_slice(&MASK);
+    frame.extend(payload.iter().enumerate().map(|(i, b)| b ^ MASK[i % 4]));
+    frame
+}
+
+/// Read an unmasked WebSocket frame, returns the first byte and the payload.
+fn read_websocket_frame(stream: &mut TcpStream) -> (u8, Vec<u8>) {
+    let mut head = [0; 2];
+    stream.read_exact(&mut head).unwrap();
+    assert!(head[1] <= 125, "unexpected large or masked frame");
+    let mut payload = vec![0; head[1] as usize];
+    stream.read_exact(&mut payload).unwrap();
+    (head[0], payload)
+}
+
 fn expect_response(
     stream: &mut TcpStream,
     // Expected values:
@@ -648,7 +880,10 @@ fn conn_supervisor(err: io::Error) -> SupervisorStrategy<(heph::net::TcpStream,
 
 /// Routes:
 /// GET / => 200, OK.
+/// GET /shared => 200, OK (using `SharedBody`).
 /// POST /echo-body => 200, $request_body.
+/// POST /body-length => 200, $request_body_length (reading the body in parts).
+/// GET /websocket => upgrade to WebSocket, echoing all messages.
 /// * => 404, Not found.
 async fn http_actor(
     _: actor::Context<!, ThreadLocal>,
@@ -668,6 +903,12 @@ async fn http_actor(
 
                 match (request.method(), request.path()) {
                     (Method::Get | Method::Head, "/") => (StatusCode::OK, "OK".into(), false),
+                    (Method::Get, "/shared") => {
+                        drop(request);
+                        let body = SharedBody::from("OK");
+                        connection.respond(StatusCode::OK, &headers, body).await?;
+                        continue;
+                    }
                     (Method::Post, "/echo-body") => {
                         let body_len = request.body().len();
                         let mut buf = Vec::with_capacity(128);
@@ -681,6 +922,33 @@ async fn http_actor(
                         let body = String::from_utf8(buf).unwrap().into();
                         (StatusCode::OK, body, false)
                     }
+                    (Method::Post, "/body-length") => {
+                        // Read the body in parts, without keeping it in memory.
+                        let mut buf = Vec::with_capacity(1024);
+                        let mut length = 0;
+                        loop {
+                            buf.clear();
+                            match request.body_mut().recv(&mut buf).await? {
+                                0 => break,
+                                n => length += n,
+                            }
+                        }
+                        assert!(request.body().is_empty());
+                        (StatusCode::OK, length.to_string().into(), false)
+                    }
+                    (Method::Get, "/websocket") => {
+                        let (head, _) = request.split();
+                        let res = match connection.upgrade(&head).await {
+                            Ok(websocket) => websocket_echo(websocket).await,
+                            Err(err) => Err(err),
+                        };
+                        // Invalid upgrade requests and protocol errors are
+                        // already handled by `upgrade` and `recv`.
+                        return match res {
+                            Err(err) if err.kind() == io::ErrorKind::InvalidData => Ok(()),
+                            res => res,
+                        };
+                    }
                     _ => (StatusCode::NOT_FOUND, "Not found".into(), false),
                 }
             }
@@ -713,6 +981,17 @@ async fn http_actor(
     }
 }
 
+/// Echo all text and binary messages received on `websocket`.
+async fn websocket_echo(mut websocket: WebSocket) -> io::Result<()> {
+    while let Some(message) = websocket.recv().await? {
+        match message {
+            Message::Text(_) | Message::Binary(_) => websocket.send(&message).await?,
+            Message::Ping(_) | Message::Pong(_) | Message::Close(_) => {}
+        }
+    }
+    Ok(())
+}
+
 #[test]
 fn request_error_proper_status_code() {
     use RequestError::*;
@@ -0,0 +1,149 @@
+//! Procedural macros for [Heph].
+//!
+//! This crate should not be used directly, the macros are re-exported by Heph,
+//! see [`heph::actor`].
+//!
+//! [Heph]: https://docs.rs/heph
+//! [`heph::actor`]: https://docs.rs/heph/latest/heph/attr.actor.html
+
+#![warn(
+    anonymous_parameters,
+    bare_trait_objects,
+    missing_debug_implementations,
+    missing_docs,
+    rust_2018_idioms,
+    trivial_numeric_casts,
+    unused_extern_crates,
+    unused_import_braces,
+    unused_qualifications,
+    unused_results,
+    variant_size_differences
+)]
+
+use proc_macro::TokenStream;
+use proc_macro2::Span;
+use quote::quote;
+use syn::spanned::Spanned;
+use syn::{
+    parse_macro_input, AttributeArgs, FnArg, Ident, ItemFn, Meta, NestedMeta, Pat, PatIdent,
+};
+
+/// Instrument an asynchronous function actor.
+///
+/// See the documentation in Heph for more information.
+#[proc_macro_attribute]
+pub fn actor(args: TokenStream, input: TokenStream) -> TokenStream {
+    let args = parse_macro_input!(args as AttributeArgs);
+    let func = parse_macro_input!(input as ItemFn);
+    match expand_actor(args, func) {
+        Ok(output) => output.into(),
+        Err(err) => err.to_compile_error().into(),
+    }
+}
+
+/// Options of the [`actor`] macro.
+#[derive(Debug, Default)]
+struct Options {
+    /// Log the arguments of the actor (excluding the actor's context).
+    log_args: bool,
+}
+
+impl Options {
+    fn parse(args: AttributeArgs) -> syn::Result<Options> {
+        let mut options = Options::default();
+        for arg in args {
+            match arg {
+                NestedMeta::Meta(Meta::Path(path)) if path.is_ident("log_args") => {
+                    options.log_args = true;
+                }
+                arg => {
+                    return Err(syn::Error::new(
+                        arg.span(),
+                        "unknown option, expected `log_args`",
+                    ))
+                }
+            }
+        }
+        Ok(options)
+    }
+}
+
+fn expand_actor(args: AttributeArgs, func: ItemFn) -> syn::Result<proc_macro2::TokenStream> {
+    let options = Options::parse(args)?;
+
+    let sig = &func.sig;
+    if sig.asyncness.is_none() {
+        return Err(syn::Error::new(
+            sig.fn_token.span(),
+            "`heph::actor` can only be used on asynchronous functions",
+        ));
+    }
+    if sig.inputs.is_empty() {
+        return Err(syn::Error::new(
+            sig.paren_token.span,
+            "actor must have at least one argument: the actor's context",
+        ));
+    }
+
+    // The outer function uses its own argument names, the original patterns
+    // are used in the inner function.
+    let mut outer_sig = sig.clone();
+    let mut arg_names = Vec::with_capacity(sig.inputs.len());
+    for (n, input) in outer_sig.inputs.iter_mut().enumerate() {
+        match input {
+            FnArg::Receiver(receiver) => {
+                return Err(syn::Error::new(
+                    receiver.span(),
+                    "`heph::actor` can't be used on methods",
+                ))
+            }
+            FnArg::Typed(arg) => {
+                let name = Ident::new(&format!("__heph_arg{}", n), Span::call_site());
+                arg.pat = Box::new(Pat::Ident(PatIdent {
+                    attrs: Vec::new(),
+                    by_ref: None,
+                    mutability: None,
+                    ident: name.clone(),
+                    subpat: None,
+                }));
+                arg_names.push(name);
+            }
+        }
+    }
+
+    let attrs = &func.attrs;
+    let vis = &func.vis;
+    let body = &func.block;
+    let inner_name = Ident::new("__heph_actor", Span::call_site());
+    let mut inner_sig = sig.clone();
+    inner_sig.ident = inner_name.clone();
+    let actor_name = sig.ident.to_string();
+
+    // Skip the actor's context.
+    let log_start = if options.log_args {
+        let args = &arg_names[1..];
+        let format = format!(
+            "starting actor: actor={}, args=({})",
+            actor_name,
+            vec!["{:?}"; args.len()].join(", ")
+        );
+        quote! { ::heph::log::_private::debug!(#format, #( &#args ),*); }
+    } else {
+        let format = format!("starting actor: actor={}", actor_name);
+        quote! { ::heph::log::_private::debug!(#format); }
+    };
+    let log_stop = format!("actor stopped: actor={}, elapsed={{:?}}", actor_name);
+
+    Ok(quote! {
+        #(#attrs)*
+        #vis #outer_sig {
+            #inner_sig #body
+
+            #log_start
+            let __heph_start = ::std::time::Instant::now();
+            let __heph_result = #inner_name(#( #arg_names ),*).await;
+            ::heph::log::_private::debug!(#log_stop, __heph_start.elapsed());
+            __heph_result
+        }
+    })
+}
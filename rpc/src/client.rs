@@ -0,0 +1,102 @@
+//! Module with the RPC client.
+//!
+//! [`Client`] is a connection to a RPC server that can make calls to any
+//! service. Generally the client types created by the [`rpc_service!`] macro
+//! are used instead, which provide a typed method for each method of the
+//! service.
+//!
+//! Calls are made one at a time, i.e. the client waits for the response of a
+//! call before making the next one. Use multiple clients to make concurrent
+//! calls.
+//!
+//! [`rpc_service!`]: crate::rpc_service
+
+use std::io;
+use std::net::SocketAddr;
+
+use heph::net::TcpStream;
+use heph::{actor, rt};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+use crate::frame::{self, Request, Response};
+use crate::Error;
+
+/// Connection to a RPC server.
+///
+/// See the [module documentation] for more information.
+///
+/// [module documentation]: crate::client
+#[derive(Debug)]
+pub struct Client {
+    stream: TcpStream,
+    /// Buffer used to read and write frames.
+    buf: Vec<u8>,
+    /// Id of the next request.
+    next_id: u64,
+}
+
+impl Client {
+    /// Connect to the RPC server at `address`.
+    pub async fn connect<M, RT>(
+        ctx: &mut actor::Context<M, RT>,
+        address: SocketAddr,
+    ) -> io::Result<Client>
+    where
+        RT: rt::Access,
+    {
+        let stream = TcpStream::connect(ctx, address)?.await?;
+        Ok(Client::from_stream(stream))
+    }
+
+    /// Create a new client from an already connected `stream`.
+    pub const fn from_stream(stream: TcpStream) -> Client {
+        Client {
+            stream,
+            buf: Vec::new(),
+            next_id: 0,
+        }
+    }
+
+    /// Call `method` with `args`, returning the return value of the method.
+    ///
+    /// `args` must be serialised as array, e.g. a tuple, as the server expects
+    /// the arguments of a method in an array.
+    ///
+    /// # Notes
+    ///
+    /// If the future is dropped before completion the connection is in an
+    /// unknown state and the client should no longer be used.
+    pub async fn call<A, R>(&mut self, method: &str, args: A) -> Result<R, Error>
+    where
+        A: Serialize,
+        R: DeserializeOwned,
+    {
+        let id = self.next_id;
+        self.next_id = self.next_id.wrapping_add(1);
+        let request = Request {
+            id,
+            method: method.to_owned(),
+            args: serde_json::to_value(args)?,
+        };
+        frame::write(&mut self.stream, &mut self.buf, &request).await?;
+
+        self.buf.clear();
+        let response: Response = match frame::read(&mut self.stream, &mut self.buf).await? {
+            Some(response) => response,
+            None => return Err(io::Error::from(io::ErrorKind::UnexpectedEof).into()),
+        };
+        if response.id != id {
+            return Err(Error::InvalidResponse);
+        }
+        match response.result {
+            Ok(value) => serde_json::from_value(value).map_err(Error::Serde),
+            Err(msg) => Err(Error::Remote(msg)),
+        }
+    }
+
+    /// Returns the underlying stream.
+    pub fn into_inner(self) -> TcpStream {
+        self.stream
+    }
+}
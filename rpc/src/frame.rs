@@ -0,0 +1,90 @@
+//! Module with the framing of messages.
+
+use std::convert::TryInto;
+use std::io;
+
+use heph::net::TcpStream;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::{Error, MAX_FRAME_SIZE};
+
+/// Size of the length prefix of a frame.
+const LEN_SIZE: usize = 4;
+
+/// Minimum amount of bytes read at a time.
+const MIN_READ_SIZE: usize = 4096;
+
+/// Request send by the client.
+#[derive(Debug, Deserialize, Serialize)]
+pub(crate) struct Request {
+    pub(crate) id: u64,
+    pub(crate) method: String,
+    pub(crate) args: Value,
+}
+
+/// Response to a [`Request`] send by the server.
+#[derive(Debug, Deserialize, Serialize)]
+pub(crate) struct Response {
+    pub(crate) id: u64,
+    /// Return value of the method or the error message.
+    pub(crate) result: Result<Value, String>,
+}
+
+/// Read a single frame from `stream`, using `buf` as buffer.
+///
+/// Returns `None` if the stream was closed without any partial frame in the
+/// buffer.
+pub(crate) async fn read<T>(stream: &mut TcpStream, buf: &mut Vec<u8>) -> Result<Option<T>, Error>
+where
+    T: DeserializeOwned,
+{
+    loop {
+        if buf.len() >= LEN_SIZE {
+            let len = u32::from_be_bytes(buf[..LEN_SIZE].try_into().unwrap()) as usize;
+            if len > MAX_FRAME_SIZE {
+                return Err(Error::FrameTooLarge);
+            }
+
+            let end = LEN_SIZE + len;
+            if buf.len() >= end {
+                let value = serde_json::from_slice(&buf[LEN_SIZE..end]);
+                drop(buf.drain(..end));
+                return value.map(Some).map_err(Error::Serde);
+            }
+            buf.reserve(end - buf.len());
+        }
+
+        if buf.capacity() - buf.len() < MIN_READ_SIZE {
+            buf.reserve(MIN_READ_SIZE);
+        }
+        match stream.recv(&mut *buf).await {
+            Ok(0) if buf.is_empty() => return Ok(None),
+            Ok(0) => return Err(io::Error::from(io::ErrorKind::UnexpectedEof).into()),
+            Ok(_) => {}
+            Err(ref err) if err.kind() == io::ErrorKind::Interrupted => {}
+            Err(err) => return Err(err.into()),
+        }
+    }
+}
+
+/// Write `value` as a single frame to `stream`, using `buf` as buffer.
+pub(crate) async fn write<T>(
+    stream: &mut TcpStream,
+    buf: &mut Vec<u8>,
+    value: &T,
+) -> Result<(), Error>
+where
+    T: Serialize,
+{
+    buf.clear();
+    buf.extend_from_slice(&[0; LEN_SIZE]);
+    serde_json::to_writer(&mut *buf, value)?;
+    let len = buf.len() - LEN_SIZE;
+    if len > MAX_FRAME_SIZE {
+        return Err(Error::FrameTooLarge);
+    }
+    buf[..LEN_SIZE].copy_from_slice(&(len as u32).to_be_bytes());
+    stream.send_all(buf).await.map_err(Error::Io)
+}
@@ -0,0 +1,286 @@
+//! Remote Procedure Call (RPC) implementation for Heph.
+//!
+//! This crate allows actors on different Heph nodes to call each other over
+//! TCP. The protocol is defined using services, created with the
+//! [`rpc_service!`] macro. The macro creates three types:
+//!
+//!  * a trait that needs to be implemented by the server,
+//!  * a client with a method for each method of the trait, returning a future
+//!    with the result of the call, see [`client`], and
+//!  * a wrapper type around an implementation of the trait that implements
+//!    [`Service`], which can be used by the server actor, see [`server`].
+//!
+//! # Protocol
+//!
+//! Requests and responses are send in frames. Each frame starts with the
+//! length of the frame (excluding the length itself) as 32 bit unsigned
+//! integer in big-endian, followed by the JSON encoded message. Frames can be
+//! at most [`MAX_FRAME_SIZE`] bytes.
+//!
+//! A request contains an id, the name of the method and the arguments to the
+//! method as JSON array. A response contains the id of the request and either
+//! the return value of the method or an error message, e.g. if the server
+//! doesn't know the method.
+//!
+//! # Supervision
+//!
+//! Both the server and client side of a connection return an [`Error`] if the
+//! connection fails, which is returned to the supervisor of the actor. For the
+//! server this is the supervisor passed to [`TcpServer::setup`], which should
+//! generally log the error and stop the actor (the client will reconnect). For
+//! clients a supervisor created using [`restart_supervisor!`], using the
+//! address of the server as argument, can be used to reconnect.
+//!
+//! [`TcpServer::setup`]: heph::net::TcpServer::setup
+//! [`restart_supervisor!`]: heph::restart_supervisor
+//!
+//! # Examples
+//!
+//! ```
+//! #![feature(never_type)]
+//!
+//! use std::net::SocketAddr;
+//!
+//! use heph::actor;
+//! use heph::net::TcpStream;
+//! use heph::rt::ThreadLocal;
+//! use heph_rpc::client::Client;
+//! use heph_rpc::{rpc_service, server, Error};
+//!
+//! rpc_service! {
+//!     /// Our calculator service.
+//!     pub trait Calculator {
+//!         /// Add two numbers.
+//!         fn add(&mut self, a: i64, b: i64) -> i64;
+//!         /// Negate a number.
+//!         fn negate(&mut self, n: i64) -> i64;
+//!     }
+//!
+//!     /// Client for the [`Calculator`] service.
+//!     pub struct CalculatorClient;
+//!
+//!     /// Server for the [`Calculator`] service.
+//!     pub struct CalculatorServer;
+//! }
+//!
+//! /// Implementation of our calculator service.
+//! struct Calc;
+//!
+//! impl Calculator for Calc {
+//!     fn add(&mut self, a: i64, b: i64) -> i64 {
+//!         a + b
+//!     }
+//!
+//!     fn negate(&mut self, n: i64) -> i64 {
+//!         -n
+//!     }
+//! }
+//!
+//! /// Actor handling a single connection, start by a `TcpServer`.
+//! async fn conn_actor(_: actor::Context<!, ThreadLocal>, stream: TcpStream, _: SocketAddr) -> Result<(), Error> {
+//!     server::serve(stream, CalculatorServer(Calc)).await
+//! }
+//!
+//! /// Actor calling the calculator service.
+//! async fn client_actor(mut ctx: actor::Context<!, ThreadLocal>, address: SocketAddr) -> Result<(), Error> {
+//!     let client = Client::connect(&mut ctx, address).await?;
+//!     let mut calculator = CalculatorClient::new(client);
+//!     let sum = calculator.add(1, 2).await?;
+//!     assert_eq!(sum, 3);
+//!     Ok(())
+//! }
+//! # drop((conn_actor, client_actor));
+//! ```
+
+#![warn(
+    anonymous_parameters,
+    bare_trait_objects,
+    missing_debug_implementations,
+    missing_docs,
+    rust_2018_idioms,
+    trivial_numeric_casts,
+    unused_extern_crates,
+    unused_import_braces,
+    unused_qualifications,
+    unused_results,
+    variant_size_differences
+)]
+
+use std::{fmt, io};
+
+use serde_json::Value;
+
+pub mod client;
+mod frame;
+pub mod server;
+
+#[doc(no_inline)]
+pub use client::Client;
+
+/// Maximum size of a single frame, excluding the length prefix.
+pub const MAX_FRAME_SIZE: usize = 1 << 24;
+
+/// Implementation of a service, called by the server for each request.
+///
+/// This is implemented by the server type created by the [`rpc_service!`]
+/// macro.
+pub trait Service {
+    /// Call `method` with the JSON encoded `args`, returning the JSON encoded
+    /// return value.
+    fn call(&mut self, method: &str, args: Value) -> Result<Value, Error>;
+}
+
+/// Macro to define a service.
+///
+/// The macro expects a trait defining the service, followed by the name of the
+/// client and server types to create. The methods of the trait must take
+/// `&mut self` and must have a return type, use `()` if the method doesn't
+/// return anything. The arguments and return type must implement
+/// [`Serialize`] and [`Deserialize`].
+///
+/// The client type has a method for each method of the trait, with the same
+/// arguments, that returns a future with a `Result<T, Error>`, where `T` is
+/// the return type of the method in the trait.
+///
+/// The server type is a wrapper around an implementation of the trait that
+/// implements [`Service`], see [`server::serve`].
+///
+/// See the [crate documentation] for an example.
+///
+/// [`Serialize`]: serde::Serialize
+/// [`Deserialize`]: serde::Deserialize
+/// [crate documentation]: crate
+#[macro_export]
+macro_rules! rpc_service {
+    (
+        $( #[$trait_meta: meta] )*
+        $vis: vis trait $name: ident {
+            $(
+                $( #[$method_meta: meta] )*
+                fn $method: ident (&mut self $(, $arg: ident : $arg_ty: ty )* $(,)? ) -> $ret: ty;
+            )*
+        }
+
+        $( #[$client_meta: meta] )*
+        $client_vis: vis struct $client: ident;
+
+        $( #[$server_meta: meta] )*
+        $server_vis: vis struct $server: ident;
+    ) => {
+        $( #[$trait_meta] )*
+        $vis trait $name {
+            $(
+                $( #[$method_meta] )*
+                fn $method(&mut self $(, $arg: $arg_ty )*) -> $ret;
+            )*
+        }
+
+        $( #[$client_meta] )*
+        #[derive(Debug)]
+        $client_vis struct $client {
+            client: $crate::client::Client,
+        }
+
+        impl $client {
+            /// Create a new client using the connection of `client`.
+            $client_vis const fn new(client: $crate::client::Client) -> $client {
+                $client { client }
+            }
+
+            /// Returns the underlying client.
+            $client_vis fn into_inner(self) -> $crate::client::Client {
+                self.client
+            }
+
+            $(
+                $( #[$method_meta] )*
+                $client_vis async fn $method(&mut self $(, $arg: $arg_ty )*) -> ::std::result::Result<$ret, $crate::Error> {
+                    self.client.call(::std::stringify!($method), ($( $arg, )*)).await
+                }
+            )*
+        }
+
+        $( #[$server_meta] )*
+        #[derive(Clone, Debug)]
+        $server_vis struct $server<S>(pub S);
+
+        impl<S: $name> $crate::Service for $server<S> {
+            fn call(
+                &mut self,
+                method: &::std::primitive::str,
+                args: $crate::__private::Value,
+            ) -> ::std::result::Result<$crate::__private::Value, $crate::Error> {
+                match method {
+                    $(
+                        ::std::stringify!($method) => {
+                            let ($( $arg, )*): ($( $arg_ty, )*) = $crate::__private::from_value(args)?;
+                            let result = self.0.$method($( $arg ),*);
+                            ::std::result::Result::Ok($crate::__private::to_value(result)?)
+                        }
+                    )*
+                    _ => ::std::result::Result::Err($crate::Error::UnknownMethod(method.to_owned())),
+                }
+            }
+        }
+    };
+}
+
+/// Private module used by the [`rpc_service!`] macro.
+#[doc(hidden)]
+pub mod __private {
+    pub use serde_json::{from_value, to_value, Value};
+}
+
+/// Error returned by RPC calls and connections.
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum Error {
+    /// I/O error, e.g. the connection was closed.
+    Io(io::Error),
+    /// Error encoding or decoding a message.
+    Serde(serde_json::Error),
+    /// Received a frame larger than [`MAX_FRAME_SIZE`].
+    FrameTooLarge,
+    /// Received a response with an id that doesn't match the request.
+    InvalidResponse,
+    /// The service doesn't have the method with the given name.
+    UnknownMethod(String),
+    /// The server returned an error handling the call.
+    Remote(String),
+}
+
+impl From<io::Error> for Error {
+    fn from(err: io::Error) -> Error {
+        Error::Io(err)
+    }
+}
+
+impl From<serde_json::Error> for Error {
+    fn from(err: serde_json::Error) -> Error {
+        Error::Serde(err)
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        use Error::*;
+        match self {
+            Io(err) => write!(f, "I/O error: {}", err),
+            Serde(err) => write!(f, "error encoding/decoding message: {}", err),
+            FrameTooLarge => f.write_str("frame too large"),
+            InvalidResponse => f.write_str("invalid response"),
+            UnknownMethod(method) => write!(f, "unknown method: {}", method),
+            Remote(msg) => write!(f, "remote error: {}", msg),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Io(err) => Some(err),
+            Error::Serde(err) => Some(err),
+            _ => None,
+        }
+    }
+}
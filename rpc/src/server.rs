@@ -0,0 +1,42 @@
+//! Module with the RPC server.
+//!
+//! The server side uses a [`TcpServer`] to accept connections, starting an
+//! actor for each connection. That actor can use [`serve`] to handle all
+//! requests on the connection using a [`Service`], usually the server type
+//! created by the [`rpc_service!`] macro. See the [crate documentation] for an
+//! example.
+//!
+//! [`TcpServer`]: heph::net::TcpServer
+//! [`rpc_service!`]: crate::rpc_service
+//! [crate documentation]: crate
+
+use heph::net::TcpStream;
+use log::debug;
+
+use crate::frame::{self, Request, Response};
+use crate::{Error, Service};
+
+/// Handle all requests on `stream` using `service`.
+///
+/// Returns once the connection is closed by the client, or if an error occurs
+/// reading or writing a frame. Errors returned by `service` are send to the
+/// client and don't stop the connection.
+pub async fn serve<S>(mut stream: TcpStream, mut service: S) -> Result<(), Error>
+where
+    S: Service,
+{
+    let mut read_buf = Vec::new();
+    let mut write_buf = Vec::new();
+    while let Some(request) = frame::read::<Request>(&mut stream, &mut read_buf).await? {
+        let result = service.call(&request.method, request.args);
+        if let Err(err) = &result {
+            debug!("RPC call to '{}' failed: {}", request.method, err);
+        }
+        let response = Response {
+            id: request.id,
+            result: result.map_err(|err| err.to_string()),
+        };
+        frame::write(&mut stream, &mut write_buf, &response).await?;
+    }
+    Ok(())
+}
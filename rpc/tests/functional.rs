@@ -0,0 +1,8 @@
+//! Functional tests.
+
+#![feature(never_type)]
+
+#[path = "functional"] // rustfmt can't find the files.
+mod functional {
+    mod service;
+}
@@ -0,0 +1,147 @@
+use std::net::SocketAddr;
+use std::time::Duration;
+
+use heph::actor;
+use heph::actor::messages::Terminate;
+use heph::net::tcp::server;
+use heph::net::{TcpServer, TcpStream};
+use heph::rt::ThreadLocal;
+use heph::spawn::ActorOptions;
+use heph::test::{join, try_spawn_local, PanicSupervisor};
+use heph::ActorRef;
+use heph_rpc::{rpc_service, Client, Error, Service};
+use serde_json::json;
+
+rpc_service! {
+    /// Test service.
+    pub trait Calculator {
+        /// Add two numbers.
+        fn add(&mut self, a: i64, b: i64) -> i64;
+        /// Negate a number.
+        fn negate(&mut self, n: i64) -> i64;
+        /// Returns the number of calls made.
+        fn calls(&mut self) -> usize;
+        /// Divide `a` by `b`.
+        fn divide(&mut self, a: i64, b: i64) -> Result<i64, String>;
+    }
+
+    /// Test client.
+    pub struct CalculatorClient;
+
+    /// Test server.
+    pub struct CalculatorServer;
+}
+
+#[derive(Clone, Default)]
+struct Calc {
+    calls: usize,
+}
+
+impl Calculator for Calc {
+    fn add(&mut self, a: i64, b: i64) -> i64 {
+        self.calls += 1;
+        a + b
+    }
+
+    fn negate(&mut self, n: i64) -> i64 {
+        self.calls += 1;
+        -n
+    }
+
+    fn calls(&mut self) -> usize {
+        self.calls
+    }
+
+    fn divide(&mut self, a: i64, b: i64) -> Result<i64, String> {
+        self.calls += 1;
+        a.checked_div(b)
+            .ok_or_else(|| "division by zero".to_owned())
+    }
+}
+
+#[test]
+fn service_call() {
+    let mut service = CalculatorServer(Calc::default());
+    assert_eq!(service.call("add", json!([1, 2])).unwrap(), json!(3));
+    assert_eq!(service.call("negate", json!([1])).unwrap(), json!(-1));
+    assert_eq!(service.call("calls", json!(null)).unwrap(), json!(2));
+    assert_eq!(
+        service.call("divide", json!([1, 0])).unwrap(),
+        json!({ "Err": "division by zero" })
+    );
+}
+
+#[test]
+fn service_call_errors() {
+    let mut service = CalculatorServer(Calc::default());
+    match service.call("unknown", json!([])) {
+        Err(Error::UnknownMethod(method)) => assert_eq!(method, "unknown"),
+        res => panic!("unexpected result: {:?}", res),
+    }
+    match service.call("add", json!(["1", 2])) {
+        Err(Error::Serde(_)) => {}
+        res => panic!("unexpected result: {:?}", res),
+    }
+}
+
+async fn conn_actor(
+    _: actor::Context<!, ThreadLocal>,
+    stream: TcpStream,
+    _: SocketAddr,
+) -> Result<(), Error> {
+    heph_rpc::server::serve(stream, CalculatorServer(Calc::default())).await
+}
+
+async fn client_actor(
+    mut ctx: actor::Context<!, ThreadLocal>,
+    address: SocketAddr,
+    server_ref: ActorRef<server::Message>,
+) -> Result<(), Error> {
+    let client = Client::connect(&mut ctx, address).await?;
+    let mut calculator = CalculatorClient::new(client);
+    assert_eq!(calculator.add(1, 2).await?, 3);
+    assert_eq!(calculator.negate(10).await?, -10);
+    assert_eq!(calculator.divide(10, 2).await?, Ok(5));
+    assert_eq!(
+        calculator.divide(10, 0).await?,
+        Err("division by zero".to_owned())
+    );
+    assert_eq!(calculator.calls().await?, 4);
+
+    let mut client = calculator.into_inner();
+    match client.call::<_, ()>("unknown", ()).await {
+        Err(Error::Remote(msg)) => assert_eq!(msg, "unknown method: unknown"),
+        res => panic!("unexpected result: {:?}", res),
+    }
+    // The connection should still be usable after an error.
+    assert_eq!(client.call::<_, i64>("add", (3, 4)).await?, 7);
+
+    server_ref.send(Terminate).await.unwrap();
+    Ok(())
+}
+
+#[test]
+fn client_server() {
+    let conn_actor = conn_actor as fn(_, _, _) -> _;
+    let server = TcpServer::setup(
+        "127.0.0.1:0".parse().unwrap(),
+        |err| panic!("unexpected error: {}", err),
+        conn_actor,
+        ActorOptions::default(),
+    )
+    .unwrap();
+    let address = server.local_addr();
+    let server_ref = try_spawn_local(PanicSupervisor, server, (), ActorOptions::default()).unwrap();
+
+    let client_actor = client_actor as fn(_, _, _) -> _;
+    let client_ref = try_spawn_local(
+        PanicSupervisor,
+        client_actor,
+        (address, server_ref.clone()),
+        ActorOptions::default(),
+    )
+    .unwrap();
+
+    join(&client_ref, Duration::from_secs(1)).unwrap();
+    join(&server_ref, Duration::from_secs(1)).unwrap();
+}
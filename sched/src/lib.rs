@@ -0,0 +1,119 @@
+//! The scheduling core of Heph.
+//!
+//! This crate contains the scheduling building blocks used by [Heph]'s runtime,
+//! without the runtime itself: it has no event sources, I/O, timers or actors.
+//! This allows the scheduling core to be driven by other event sources, for
+//! example on embedded systems with their own interrupt-based event loop.
+//!
+//! The crate has the following parts:
+//!
+//! - [`Priority`]: the priority of a process, used as weight for its runtime.
+//! - [`fair_runtime`]: determines the *fair* runtime of a process, i.e. its
+//!   actual runtime weighted by its priority.
+//! - [`RunQueue`]: holds the processes that are ready to run and determines
+//!   the order in which they run, see its documentation for details.
+//! - [`Schedulable`]: trait that must be implemented by the processes
+//!   added to the [`RunQueue`].
+//!
+//! Heph's own schedulers are build on top of this crate.
+//!
+//! [Heph]: https://docs.rs/heph
+//!
+//! # Examples
+//!
+//! ```
+//! use std::pin::Pin;
+//! use std::time::{Duration, Instant};
+//!
+//! use heph_sched::{fair_runtime, Priority, RunQueue, Schedulable};
+//!
+//! /// Our own process type.
+//! struct Task {
+//!     name: &'static str,
+//!     priority: Priority,
+//!     unaccounted_runtime: Duration,
+//! }
+//!
+//! impl Schedulable for Task {
+//!     fn priority(&self) -> Priority {
+//!         self.priority
+//!     }
+//!
+//!     fn deadline(&self) -> Option<Instant> {
+//!         None
+//!     }
+//!
+//!     fn take_unaccounted_runtime(mut self: Pin<&mut Self>) -> Duration {
+//!         std::mem::take(&mut self.unaccounted_runtime)
+//!     }
+//! }
+//!
+//! let mut run_queue = RunQueue::empty();
+//! for (name, priority) in [("low", Priority::LOW), ("high", Priority::HIGH)] {
+//!     let task = Task { name, priority, unaccounted_runtime: Duration::ZERO };
+//!     run_queue.add(Box::pin(task));
+//! }
+//!
+//! // Our event loop, the higher priority task runs first.
+//! let mut order = Vec::new();
+//! while let Some(mut task) = run_queue.remove() {
+//!     let start = Instant::now();
+//!     // Run the task...
+//!     order.push(task.name);
+//!     let elapsed = start.elapsed();
+//!     let priority = task.priority;
+//!     task.unaccounted_runtime += fair_runtime(elapsed, priority);
+//!     // ...and add it back to the queue once it's ready to run again.
+//! }
+//! assert_eq!(order, ["high", "low"]);
+//! ```
+
+#![feature(const_option)]
+#![warn(
+    anonymous_parameters,
+    bare_trait_objects,
+    missing_debug_implementations,
+    missing_docs,
+    rust_2018_idioms,
+    trivial_numeric_casts,
+    unused_extern_crates,
+    unused_import_braces,
+    unused_qualifications,
+    unused_results,
+    variant_size_differences
+)]
+
+use std::pin::Pin;
+use std::time::{Duration, Instant};
+
+mod priority;
+mod run_queue;
+
+pub use priority::Priority;
+pub use run_queue::{RunQueue, MIN_CHARGE, QUANTUM};
+
+/// Process that can be scheduled using a [`RunQueue`].
+pub trait Schedulable {
+    /// Returns the priority of the process.
+    fn priority(&self) -> Priority;
+
+    /// Returns the deadline of the process' current work, if any.
+    ///
+    /// Processes with a deadline are always run before any process without
+    /// one, earliest deadline first.
+    fn deadline(&self) -> Option<Instant>;
+
+    /// Returns the fair runtime (see [`fair_runtime`]) used by the process
+    /// since the last call to this function. Used by the [`RunQueue`] to
+    /// determine when to move on to the next priority.
+    fn take_unaccounted_runtime(self: Pin<&mut Self>) -> Duration;
+}
+
+/// Returns the fair runtime for a run of `elapsed` time of a process with
+/// `priority`.
+///
+/// The fair runtime is the actual runtime weighted by the [`Priority`], i.e.
+/// processes with a lower priority are charged more for the same runtime.
+pub fn fair_runtime(elapsed: Duration, priority: Priority) -> Duration {
+    elapsed * priority
+}
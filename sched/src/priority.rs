@@ -0,0 +1,145 @@
+//! Module containing the [`Priority`] type.
+
+use std::cmp::Ordering;
+use std::num::NonZeroU8;
+use std::ops::Mul;
+use std::time::Duration;
+
+/// Priority for a process, e.g. an actor, in the scheduler.
+///
+/// Processes with a higher priority will be scheduled to run more often and
+/// quicker (after they return [`Poll::Pending`]) then processes with a lower
+/// priority.
+///
+/// [`Poll::Pending`]: std::task::Poll::Pending
+///
+/// Internally the priority is a weight, the time a process runs is multiplied
+/// by it when deciding which process to run next, see [`fair_runtime`]. A
+/// *lower* weight means a *higher* priority. Next to the presets
+/// ([`Priority::HIGH`], [`Priority::NORMAL`] and [`Priority::LOW`]) custom
+/// priorities can be created using [`Priority::new`], allowing processes to be
+/// finely ordered relative to each other.
+///
+/// [`fair_runtime`]: crate::fair_runtime
+///
+/// # Examples
+///
+/// ```
+/// use heph_sched::Priority;
+///
+/// // Slightly higher priority than `Priority::HIGH`.
+/// let priority = Priority::new(Priority::HIGH.weight() - 1);
+/// assert!(priority > Priority::HIGH);
+/// ```
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+#[repr(transparent)]
+pub struct Priority(NonZeroU8);
+
+impl Priority {
+    /// Highest possible priority, a weight of 1.
+    pub const HIGHEST: Priority = Priority(NonZeroU8::new(1).unwrap());
+
+    /// Lowest possible priority, a weight of 255.
+    pub const LOWEST: Priority = Priority(NonZeroU8::new(u8::MAX).unwrap());
+
+    /// Low priority.
+    ///
+    /// Other processes have priority over this process.
+    pub const LOW: Priority = Priority(NonZeroU8::new(15).unwrap());
+
+    /// Normal priority.
+    ///
+    /// Most processes should run at this priority, hence its also the default
+    /// priority.
+    pub const NORMAL: Priority = Priority(NonZeroU8::new(10).unwrap());
+
+    /// High priority.
+    ///
+    /// Takes priority over other processes.
+    pub const HIGH: Priority = Priority(NonZeroU8::new(5).unwrap());
+
+    /// Create a custom priority with `weight`.
+    ///
+    /// A lower weight means a higher priority. For reference the presets have
+    /// the following weights: [`Priority::HIGH`] 5, [`Priority::NORMAL`] 10
+    /// and [`Priority::LOW`] 15.
+    ///
+    /// # Panics
+    ///
+    /// This will panic if `weight` is zero.
+    pub const fn new(weight: u8) -> Priority {
+        match NonZeroU8::new(weight) {
+            Some(weight) => Priority(weight),
+            None => panic!("Can't use a priority weight of zero"),
+        }
+    }
+
+    /// Returns the weight of the priority, see [`Priority::new`].
+    pub const fn weight(self) -> u8 {
+        self.0.get()
+    }
+}
+
+impl Default for Priority {
+    fn default() -> Priority {
+        Priority::NORMAL
+    }
+}
+
+impl Ord for Priority {
+    fn cmp(&self, other: &Self) -> Ordering {
+        other.0.cmp(&self.0)
+    }
+}
+
+impl PartialOrd for Priority {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+/// Implementation detail, please ignore.
+#[doc(hidden)]
+impl Mul<Priority> for Duration {
+    type Output = Duration;
+
+    fn mul(self, rhs: Priority) -> Duration {
+        self * u32::from(rhs.0.get())
+    }
+}
+
+#[test]
+fn priority_duration_multiplication() {
+    let duration = Duration::from_millis(1);
+    let high = duration * Priority::HIGH;
+    let normal = duration * Priority::NORMAL;
+    let low = duration * Priority::LOW;
+
+    assert!(high < normal);
+    assert!(normal < low);
+    assert!(high < low);
+}
+
+#[test]
+fn priority_custom() {
+    assert_eq!(Priority::new(5), Priority::HIGH);
+    assert_eq!(Priority::new(10), Priority::NORMAL);
+    assert_eq!(Priority::new(15), Priority::LOW);
+    assert_eq!(Priority::new(1), Priority::HIGHEST);
+    assert_eq!(Priority::new(255), Priority::LOWEST);
+
+    let higher = Priority::new(4);
+    assert!(higher > Priority::HIGH);
+    assert!(Priority::HIGHEST > higher);
+    let between = Priority::new(7);
+    assert!(between < Priority::HIGH);
+    assert!(between > Priority::NORMAL);
+    assert!(Priority::LOWEST < Priority::LOW);
+    assert_eq!(between.weight(), 7);
+}
+
+#[test]
+#[should_panic(expected = "Can't use a priority weight of zero")]
+fn priority_zero_weight() {
+    let _ = Priority::new(0);
+}
@@ -0,0 +1,344 @@
+//! Module with the [`RunQueue`].
+
+use std::cmp::Ordering;
+use std::collections::{BinaryHeap, VecDeque};
+use std::fmt;
+use std::pin::Pin;
+use std::time::{Duration, Instant};
+
+use crate::Schedulable;
+
+/// Fair runtime a priority is allowed to use in a single round.
+pub const QUANTUM: Duration = Duration::from_millis(10);
+
+/// Minimum fair runtime charged for removing a process, ensures a priority
+/// can't be served forever by processes that haven't run yet.
+pub const MIN_CHARGE: Duration = Duration::from_micros(10);
+
+/// Number of priority levels, one per possible [`Priority`] weight.
+///
+/// [`Priority`]: crate::Priority
+const LEVELS: usize = u8::MAX as usize + 1;
+
+/// Processes that are ready to run.
+///
+/// Instead of ordering all processes by their fair runtime, which makes adding
+/// a process an `O(log n)` operation, it uses a FIFO queue per priority. Both
+/// adding and removing a process are `O(1)` operations.
+///
+/// # Fairness
+///
+/// The priorities are served using deficit round robin. When the run queue
+/// starts serving a priority it's given a quantum ([`QUANTUM`]) of fair
+/// runtime. Every process removed from the queue is charged the fair runtime
+/// it used since it was last removed (at least [`MIN_CHARGE`]). Once the
+/// quantum is used up the next priority is served. As the fair runtime is the
+/// actual runtime multiplied by the [`Priority`] weight, processes with a
+/// higher priority get more actual runtime per round.
+///
+/// Within a single priority processes are run in the order in which they
+/// became ready.
+///
+/// # Deadlines
+///
+/// Processes with a deadline (see [`Schedulable::deadline`]) are always run
+/// before any process without one, earliest deadline first. These are kept in
+/// a separate binary heap.
+///
+/// [`Priority`]: crate::Priority
+pub struct RunQueue<P: ?Sized> {
+    /// Processes with a deadline, earliest deadline first.
+    deadlines: BinaryHeap<ByDeadline<P>>,
+    /// Processes without a deadline, indexed by [`Priority::weight`].
+    ///
+    /// [`Priority::weight`]: crate::Priority::weight
+    levels: Box<[Level<P>]>,
+    /// Bitmap of the `levels` that contain processes.
+    non_empty: [u64; LEVELS / 64],
+    /// Index into `levels` of the priority currently being served.
+    current: usize,
+    /// Total number of processes in the queue.
+    len: usize,
+}
+
+/// FIFO queue for processes with the same [`Priority`].
+///
+/// [`Priority`]: crate::Priority
+struct Level<P: ?Sized> {
+    processes: VecDeque<Pin<Box<P>>>,
+    /// Fair runtime left in the current round.
+    deficit: Duration,
+}
+
+/// Process with a deadline, ordered by earliest deadline first.
+struct ByDeadline<P: ?Sized> {
+    deadline: Instant,
+    process: Pin<Box<P>>,
+}
+
+impl<P: Schedulable + ?Sized> RunQueue<P> {
+    /// Returns an empty `RunQueue`.
+    pub fn empty() -> RunQueue<P> {
+        RunQueue {
+            deadlines: BinaryHeap::new(),
+            levels: (0..LEVELS)
+                .map(|_| Level {
+                    processes: VecDeque::new(),
+                    deficit: Duration::ZERO,
+                })
+                .collect(),
+            non_empty: [0; LEVELS / 64],
+            current: 0,
+            len: 0,
+        }
+    }
+
+    /// Returns the number of processes in the queue.
+    pub const fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns `true` if the queue contains no processes.
+    pub const fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Returns `true` if the queue contains any process.
+    pub const fn has_process(&self) -> bool {
+        self.len != 0
+    }
+
+    /// Add `process` to the queue.
+    pub fn add(&mut self, process: Pin<Box<P>>) {
+        self.len += 1;
+        if let Some(deadline) = process.deadline() {
+            self.deadlines.push(ByDeadline { deadline, process });
+            return;
+        }
+
+        let level = process.priority().weight() as usize;
+        self.levels[level].processes.push_back(process);
+        self.non_empty[level / 64] |= 1 << (level % 64);
+    }
+
+    /// Remove the next process to run from the queue.
+    pub fn remove(&mut self) -> Option<Pin<Box<P>>> {
+        if let Some(ByDeadline { process, .. }) = self.deadlines.pop() {
+            self.len -= 1;
+            return Some(process);
+        }
+
+        let mut level = self.current;
+        if !self.is_non_empty(level) || self.levels[level].deficit.is_zero() {
+            // Current priority used up its quantum (or is empty), move on to
+            // the next one.
+            level = self.next_level(level)?;
+            self.current = level;
+            self.levels[level].deficit += QUANTUM;
+        }
+
+        let queue = &mut self.levels[level];
+        // `is_non_empty` and `next_level` ensure the level has processes.
+        let mut process = queue.processes.pop_front().unwrap();
+        let min_charge = MIN_CHARGE * process.priority();
+        let charge = process.as_mut().take_unaccounted_runtime().max(min_charge);
+        queue.deficit = queue.deficit.saturating_sub(charge);
+        if queue.processes.is_empty() {
+            queue.deficit = Duration::ZERO;
+            self.non_empty[level / 64] &= !(1 << (level % 64));
+        }
+        self.len -= 1;
+        Some(process)
+    }
+
+    /// Returns all processes in the queue, in no particular order.
+    pub fn iter(&self) -> impl Iterator<Item = Pin<&P>> + '_ {
+        self.deadlines.iter().map(|p| p.process.as_ref()).chain(
+            self.levels
+                .iter()
+                .flat_map(|level| level.processes.iter().map(Pin::as_ref)),
+        )
+    }
+
+    /// Returns `true` if `level` contains processes.
+    fn is_non_empty(&self, level: usize) -> bool {
+        self.non_empty[level / 64] & (1 << (level % 64)) != 0
+    }
+
+    /// Returns the first non-empty level after `level`, wrapping around to the
+    /// start (possibly returning `level` itself).
+    fn next_level(&self, level: usize) -> Option<usize> {
+        let start = level + 1;
+        let words = self.non_empty.iter().copied().enumerate();
+        // Mask the levels up to and including `level` in the first word.
+        let after = words.clone().skip(start / 64).map(|(word, bits)| {
+            if word == start / 64 {
+                (word, bits & (u64::MAX << (start % 64)))
+            } else {
+                (word, bits)
+            }
+        });
+        after
+            .chain(words) // Wrap around.
+            .find(|(_, bits)| *bits != 0)
+            .map(|(word, bits)| word * 64 + bits.trailing_zeros() as usize)
+    }
+}
+
+impl<P: ?Sized> fmt::Debug for RunQueue<P> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("RunQueue")
+            .field("deadlines", &self.deadlines.len())
+            .field("current", &self.current)
+            .field("len", &self.len)
+            .finish()
+    }
+}
+
+impl<P: ?Sized> Eq for ByDeadline<P> {}
+
+impl<P: ?Sized> PartialEq for ByDeadline<P> {
+    fn eq(&self, other: &Self) -> bool {
+        self.deadline == other.deadline
+    }
+}
+
+impl<P: ?Sized> Ord for ByDeadline<P> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        // `BinaryHeap` is a max-heap, so reverse the order to get the earliest
+        // deadline first.
+        other.deadline.cmp(&self.deadline)
+    }
+}
+
+impl<P: ?Sized> PartialOrd for ByDeadline<P> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::mem::take;
+    use std::pin::Pin;
+    use std::time::{Duration, Instant};
+
+    use crate::{Priority, Schedulable};
+
+    use super::QUANTUM;
+
+    type RunQueue = super::RunQueue<TestProcess>;
+
+    struct TestProcess {
+        id: usize,
+        priority: Priority,
+        deadline: Option<Instant>,
+        unaccounted_runtime: Duration,
+    }
+
+    impl Schedulable for TestProcess {
+        fn priority(&self) -> Priority {
+            self.priority
+        }
+
+        fn deadline(&self) -> Option<Instant> {
+            self.deadline
+        }
+
+        fn take_unaccounted_runtime(mut self: Pin<&mut Self>) -> Duration {
+            take(&mut self.unaccounted_runtime)
+        }
+    }
+
+    fn test_process(id: usize, priority: Priority) -> Pin<Box<TestProcess>> {
+        Box::pin(TestProcess {
+            id,
+            priority,
+            deadline: None,
+            unaccounted_runtime: Duration::ZERO,
+        })
+    }
+
+    fn remove_id(run_queue: &mut RunQueue) -> usize {
+        run_queue.remove().expect("missing process").id
+    }
+
+    #[test]
+    fn empty() {
+        let mut run_queue = RunQueue::empty();
+        assert!(!run_queue.has_process());
+        assert!(run_queue.is_empty());
+        assert_eq!(run_queue.len(), 0);
+        assert!(run_queue.remove().is_none());
+    }
+
+    #[test]
+    fn fifo() {
+        let mut run_queue = RunQueue::empty();
+        for id in 0..10 {
+            run_queue.add(test_process(id, Priority::NORMAL));
+        }
+        assert_eq!(run_queue.len(), 10);
+        for id in 0..10 {
+            assert_eq!(remove_id(&mut run_queue), id);
+        }
+        assert!(!run_queue.has_process());
+    }
+
+    #[test]
+    fn priority_order() {
+        let mut run_queue = RunQueue::empty();
+        run_queue.add(test_process(0, Priority::LOW));
+        run_queue.add(test_process(1, Priority::NORMAL));
+        run_queue.add(test_process(2, Priority::HIGH));
+        assert_eq!(remove_id(&mut run_queue), 2);
+        assert_eq!(remove_id(&mut run_queue), 1);
+        assert_eq!(remove_id(&mut run_queue), 0);
+        assert!(run_queue.remove().is_none());
+    }
+
+    #[test]
+    fn runtime_accounting() {
+        let mut run_queue = RunQueue::empty();
+        let mut high = test_process(0, Priority::HIGH);
+        // Used up the quantum of its priority.
+        high.unaccounted_runtime = QUANTUM;
+        run_queue.add(high);
+        run_queue.add(test_process(1, Priority::HIGH));
+        run_queue.add(test_process(2, Priority::NORMAL));
+
+        assert_eq!(remove_id(&mut run_queue), 0);
+        // High priority used up its quantum, so the normal priority process
+        // should run next.
+        assert_eq!(remove_id(&mut run_queue), 2);
+        assert_eq!(remove_id(&mut run_queue), 1);
+        assert!(run_queue.remove().is_none());
+    }
+
+    #[test]
+    fn deadlines_first() {
+        let mut run_queue = RunQueue::empty();
+        run_queue.add(test_process(0, Priority::HIGH));
+        let now = Instant::now();
+        for (id, offset) in [(1, 20), (2, 10)] {
+            let mut process = test_process(id, Priority::LOW);
+            process.deadline = Some(now + Duration::from_millis(offset));
+            run_queue.add(process);
+        }
+        assert_eq!(run_queue.len(), 3);
+        assert_eq!(remove_id(&mut run_queue), 2);
+        assert_eq!(remove_id(&mut run_queue), 1);
+        assert_eq!(remove_id(&mut run_queue), 0);
+        assert!(run_queue.remove().is_none());
+    }
+
+    #[test]
+    fn iter() {
+        let mut run_queue = RunQueue::empty();
+        run_queue.add(test_process(0, Priority::LOW));
+        run_queue.add(test_process(1, Priority::HIGH));
+        let mut ids: Vec<usize> = run_queue.iter().map(|process| process.id).collect();
+        ids.sort_unstable();
+        assert_eq!(ids, [0, 1]);
+    }
+}
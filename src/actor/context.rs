@@ -2,12 +2,25 @@
 
 use std::fmt;
 use std::future::Future;
+#[cfg(feature = "record")]
+use std::io;
+#[cfg(feature = "record")]
+use std::path::Path;
 use std::pin::Pin;
+use std::stream::Stream;
 use std::task::{self, Poll};
+use std::time::{Duration, Instant};
 
-use heph_inbox::{self as inbox, Receiver, RecvValue};
+use heph_inbox as inbox;
 
+use crate::actor::inbox::{Receiver, RecvValue};
+#[cfg(feature = "record")]
+use crate::actor::record::Recorder;
+use crate::actor::NewActor;
 use crate::actor_ref::ActorRef;
+use crate::rt::{self, ProcessId, WaitReady};
+use crate::spawn::{ActorOptions, AddActorError, PrivateSpawn, Spawn};
+use crate::supervisor::Supervisor;
 
 /// The context in which an actor is executed.
 ///
@@ -50,7 +63,11 @@ impl<M, RT> Context<M, RT> {
     /// actor wants to wait until a message is received [`receive_next`] can be
     /// used, which returns a `Future<Output = M>`.
     ///
+    /// Messages send using [`ActorRef::send_priority`] are returned before any
+    /// other message.
+    ///
     /// [`receive_next`]: Context::receive_next
+    /// [`ActorRef::send_priority`]: crate::actor_ref::ActorRef::send_priority
     ///
     /// # Examples
     ///
@@ -72,12 +89,19 @@ impl<M, RT> Context<M, RT> {
     /// # drop(greeter_actor);
     /// ```
     pub fn try_receive_next(&mut self) -> Result<M, RecvError> {
-        self.inbox.try_recv().map_err(RecvError::from)
+        let msg = self.inbox.try_recv().map_err(RecvError::from)?;
+        rt::message_received();
+        Ok(msg)
     }
 
     /// Receive the next message.
     ///
     /// This returns a [`Future`] that will complete once a message is ready.
+    /// Same as [`try_receive_next`] messages send using
+    /// [`ActorRef::send_priority`] are returned first.
+    ///
+    /// [`try_receive_next`]: Context::try_receive_next
+    /// [`ActorRef::send_priority`]: crate::actor_ref::ActorRef::send_priority
     ///
     /// # Examples
     ///
@@ -132,11 +156,486 @@ impl<M, RT> Context<M, RT> {
         }
     }
 
+    /// Returns a [`Stream`] of the messages send to this actor.
+    ///
+    /// This is the same as calling [`receive_next`] in a loop, but allows the
+    /// inbox to be used where a `Stream` is expected, e.g. to combine it with
+    /// other streams. The stream ends once [`receive_next`] would return
+    /// [`NoMessages`].
+    ///
+    /// [`receive_next`]: Context::receive_next
+    ///
+    /// # Examples
+    ///
+    /// An actor that prints all messages it receives.
+    ///
+    /// ```
+    /// use heph::actor;
+    /// use heph::rt::ThreadLocal;
+    /// use heph::util::next;
+    ///
+    /// async fn print_actor(mut ctx: actor::Context<String, ThreadLocal>) {
+    ///     let mut messages = ctx.messages();
+    ///     while let Some(msg) = next(&mut messages).await {
+    ///         println!("Got a message: {}", msg);
+    ///     }
+    /// }
+    ///
+    /// # // Use the `print_actor` function to silence dead code warning.
+    /// # drop(print_actor);
+    /// ```
+    pub fn messages<'ctx>(&'ctx mut self) -> Messages<'ctx, M, RT> {
+        Messages { ctx: self }
+    }
+
+    /// Peek at the next message, without receiving it.
+    ///
+    /// This returns a [`Future`] that will complete once a message is ready,
+    /// returning a reference to the message. The message is returned by the
+    /// next call to [`receive_next`] or [`try_receive_next`], even if a
+    /// message is send using [`ActorRef::send_priority`] in the meantime.
+    ///
+    /// [`receive_next`]: Context::receive_next
+    /// [`try_receive_next`]: Context::try_receive_next
+    /// [`ActorRef::send_priority`]: crate::actor_ref::ActorRef::send_priority
+    ///
+    /// # Examples
+    ///
+    /// An actor that only starts processing once it receives a message.
+    ///
+    /// ```
+    /// use heph::actor;
+    /// use heph::rt::ThreadLocal;
+    ///
+    /// async fn lazy_actor(mut ctx: actor::Context<String, ThreadLocal>) {
+    ///     if let Ok(msg) = ctx.peek_next().await {
+    ///         println!("First message will be: {}", msg);
+    ///     }
+    ///     // Start expensive processing...
+    ///     while let Ok(msg) = ctx.receive_next().await {
+    ///         println!("Got a message: {}", msg);
+    ///     }
+    /// }
+    ///
+    /// # // Use the `lazy_actor` function to silence dead code warning.
+    /// # drop(lazy_actor);
+    /// ```
+    pub fn peek_next<'ctx>(&'ctx mut self) -> PeekMessage<'ctx, M> {
+        PeekMessage {
+            inbox: Some(&mut self.inbox),
+        }
+    }
+
+    /// Receive the first message for which `predicate` returns `true`.
+    ///
+    /// This returns a [`Future`] that will complete once a matching message is
+    /// ready. Messages are checked in the order in which [`receive_next`]
+    /// would return them. Messages that don't match remain queued and are
+    /// received, in order, by the next call to [`receive_next`] or
+    /// `receive_match`. This allows an actor to defer certain messages until
+    /// it's ready to process them, e.g. in a state machine.
+    ///
+    /// The skipped messages are moved out of the inbox into a deferred queue.
+    /// They still count towards the inbox watermarks (see
+    /// [`ActorRef::watch_inbox`]), but not towards the inbox's capacity. They
+    /// are dropped if the actor is restarted by its supervisor.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`ReceiveMatchError::NoMessages`] if no matching message is
+    /// available and no references to the actor exists.
+    ///
+    /// To bound the memory used by skipped messages at most
+    /// [`DEFERRED_CAPACITY`] messages are deferred. Once this limit is reached
+    /// this returns [`ReceiveMatchError::DeferredFull`], the deferred messages
+    /// can then be received using [`receive_next`].
+    ///
+    /// [`receive_next`]: Context::receive_next
+    /// [`DEFERRED_CAPACITY`]: crate::actor::DEFERRED_CAPACITY
+    /// [`ActorRef::watch_inbox`]: crate::ActorRef::watch_inbox
+    ///
+    /// # Examples
+    ///
+    /// A connection actor that defers sending data until it's connected.
+    ///
+    /// ```
+    /// use heph::actor;
+    /// use heph::rt::ThreadLocal;
+    ///
+    /// enum Message {
+    ///     Connected,
+    ///     Send(String),
+    /// }
+    ///
+    /// async fn connection_actor(mut ctx: actor::Context<Message, ThreadLocal>) {
+    ///     // Wait until we're connected, keeping any `Send` message queued.
+    ///     let is_connected = |msg: &Message| matches!(msg, Message::Connected);
+    ///     if ctx.receive_match(is_connected).await.is_err() {
+    ///         return;
+    ///     }
+    ///
+    ///     // Now process the deferred (and any new) messages.
+    ///     while let Ok(msg) = ctx.receive_next().await {
+    ///         if let Message::Send(data) = msg {
+    ///             println!("Sending: {}", data);
+    ///         }
+    ///     }
+    /// }
+    ///
+    /// # // Use the `connection_actor` function to silence dead code warning.
+    /// # drop(connection_actor);
+    /// ```
+    pub fn receive_match<'ctx, F>(&'ctx mut self, predicate: F) -> ReceiveMatch<'ctx, M, F>
+    where
+        F: FnMut(&M) -> bool + Unpin,
+    {
+        ReceiveMatch {
+            inbox: &mut self.inbox,
+            predicate,
+        }
+    }
+
+    /// Returns the sequence number of the last message received, or `None` if
+    /// no message was received yet.
+    ///
+    /// Every message is assigned a sequence number when it's send to the
+    /// actor. The sequence numbers are unique per actor (inbox) and increase
+    /// monotonically, they're also logged (at trace level) when a message is
+    /// send and received. This makes it possible to track down lost or
+    /// duplicated messages in the logs and traces.
+    ///
+    /// Note that messages are not always received in the order of their
+    /// sequence numbers, e.g. messages send using
+    /// [`ActorRef::send_priority`] are received before any other message and
+    /// messages send concurrently can be added to the inbox in a different
+    /// order. Sequence numbers are also skipped if sending a message fails.
+    ///
+    /// [`ActorRef::send_priority`]: crate::actor_ref::ActorRef::send_priority
+    pub fn message_seq(&self) -> Option<u64> {
+        self.inbox.last_seq()
+    }
+
+    /// Record all messages received by this actor to the file at `path`.
+    ///
+    /// All messages received after this call, using either
+    /// [`try_receive_next`] or [`receive_next`], are written to the file as
+    /// JSON, one message per line. If the file already exists it's truncated.
+    /// The recording can be replayed using [`test::replay`] to reproduce bugs
+    /// found in production.
+    ///
+    /// Failing to record a message is logged, but doesn't stop the message
+    /// from being received.
+    ///
+    /// [`try_receive_next`]: Context::try_receive_next
+    /// [`receive_next`]: Context::receive_next
+    /// [`test::replay`]: crate::test::replay
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use heph::actor;
+    /// use heph::rt::ThreadLocal;
+    ///
+    /// async fn actor(mut ctx: actor::Context<String, ThreadLocal>) {
+    ///     let path = std::env::temp_dir().join("heph_recorded_actor.json");
+    ///     if let Err(err) = ctx.record_messages(path) {
+    ///         eprintln!("failed to start recording messages: {}", err);
+    ///     }
+    ///
+    ///     while let Ok(msg) = ctx.receive_next().await {
+    ///         println!("Got a message: {}", msg);
+    ///     }
+    /// }
+    ///
+    /// # // Use the `actor` function to silence dead code warning.
+    /// # drop(actor);
+    /// ```
+    #[cfg(feature = "record")]
+    #[doc(cfg(feature = "record"))]
+    pub fn record_messages<P>(&mut self, path: P) -> io::Result<()>
+    where
+        P: AsRef<Path>,
+        M: serde::Serialize,
+    {
+        let recorder = Recorder::create(path)?;
+        self.inbox.record_messages(recorder);
+        Ok(())
+    }
+
     /// Returns a reference to this actor.
     pub fn actor_ref(&self) -> ActorRef<M> {
         ActorRef::local(self.inbox.new_sender())
     }
 
+    /// Returns the process id of this actor.
+    ///
+    /// This is the same id as used in the logs, traces and process
+    /// statistics, see [`ProcessId`]. The process id remains the same when
+    /// the actor is restarted.
+    pub fn pid(&self) -> ProcessId
+    where
+        RT: rt::Access,
+    {
+        self.rt.pid()
+    }
+
+    /// Spawn a child actor.
+    ///
+    /// This works the same as [`Spawn::try_spawn`], but links the new actor
+    /// to this actor as child. Once this (parent) actor stops, or is
+    /// restarted, all its children are stopped as well. The child's
+    /// supervisor can escalate failures to the parent's supervisor using
+    /// [`SupervisorStrategy::Escalate`].
+    ///
+    /// [`SupervisorStrategy::Escalate`]: crate::supervisor::SupervisorStrategy::Escalate
+    pub fn try_spawn_child<S, NA, RT2>(
+        &mut self,
+        supervisor: S,
+        new_actor: NA,
+        arg: NA::Argument,
+        options: ActorOptions,
+    ) -> Result<ActorRef<NA::Message>, NA::Error>
+    where
+        RT: Spawn<S, NA, RT2>,
+        S: Supervisor<NA>,
+        NA: NewActor<RuntimeAccess = RT2>,
+    {
+        let parent = &self.inbox;
+        let link_child = |ctx: &mut Context<NA::Message, RT2>| {
+            parent.link_child(&ctx.inbox);
+            Ok(arg)
+        };
+        self.rt
+            .try_spawn_setup(supervisor, new_actor, link_child, options)
+            .map_err(|err| match err {
+                AddActorError::NewActor(err) => err,
+                AddActorError::<_, !>::ArgFn(_) => unreachable!(),
+            })
+    }
+
+    /// Spawn a child actor.
+    ///
+    /// This is a convenience method for `NewActor` implementations that never
+    /// return an error, such as asynchronous functions. See
+    /// [`Context::try_spawn_child`] for more information.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use heph::actor;
+    /// use heph::rt::ThreadLocal;
+    /// use heph::spawn::ActorOptions;
+    /// use heph::supervisor::NoSupervisor;
+    ///
+    /// async fn parent(mut ctx: actor::Context<(), ThreadLocal>) {
+    ///     // Once `parent` stops, `child` is stopped as well.
+    ///     let child = child as fn(_) -> _;
+    ///     let child_ref = ctx.spawn_child(NoSupervisor, child, (), ActorOptions::default());
+    ///     let _ = child_ref.try_send("Hello child!".to_owned());
+    /// }
+    ///
+    /// async fn child(mut ctx: actor::Context<String, ThreadLocal>) {
+    ///     while let Ok(msg) = ctx.receive_next().await {
+    ///         println!("Got a message: {}", msg);
+    ///     }
+    /// }
+    ///
+    /// # // Use the `parent` function to silence dead code warning.
+    /// # drop(parent);
+    /// ```
+    pub fn spawn_child<S, NA, RT2>(
+        &mut self,
+        supervisor: S,
+        new_actor: NA,
+        arg: NA::Argument,
+        options: ActorOptions,
+    ) -> ActorRef<NA::Message>
+    where
+        RT: Spawn<S, NA, RT2>,
+        S: Supervisor<NA>,
+        NA: NewActor<Error = !, RuntimeAccess = RT2>,
+    {
+        self.try_spawn_child(supervisor, new_actor, arg, options)
+            .unwrap_or_else(|_: !| unreachable!())
+    }
+
+    /// Set the `deadline` for the actor's current work.
+    ///
+    /// If earliest-deadline-first scheduling is enabled (see
+    /// [`rt::Setup::enable_deadline_scheduling`]) the actor will be scheduled
+    /// before all processes with a later deadline and all processes without a
+    /// deadline. Otherwise this does nothing.
+    ///
+    /// The deadline is applied once the actor returns control to the
+    /// scheduler and remains set until it's changed or cleared using
+    /// [`Context::clear_deadline`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::time::{Duration, Instant};
+    ///
+    /// use heph::actor;
+    /// use heph::rt::ThreadLocal;
+    ///
+    /// async fn frame_actor(mut ctx: actor::Context<Vec<u8>, ThreadLocal>) {
+    ///     while let Ok(frame) = ctx.receive_next().await {
+    ///         // Frames must be processed within 16 milliseconds, the
+    ///         // deadline applies whenever the actor awaits while processing it.
+    ///         ctx.set_deadline(Instant::now() + Duration::from_millis(16));
+    ///         // Process the frame...
+    ///         # drop(frame);
+    ///     }
+    /// }
+    ///
+    /// # // Use the `frame_actor` function to silence dead code warning.
+    /// # drop(frame_actor);
+    /// ```
+    pub fn set_deadline(&mut self, deadline: Instant) {
+        rt::set_deadline(Some(deadline));
+    }
+
+    /// Clear the deadline set by [`Context::set_deadline`].
+    pub fn clear_deadline(&mut self) {
+        rt::set_deadline(None);
+    }
+
+    /// Report that the actor is ready, e.g. to serve requests.
+    ///
+    /// The runtime is only considered ready once all actors spawned with
+    /// [`ActorOptions::require_readiness`] reported they're ready, see
+    /// [`RuntimeRef::is_ready`]. Reporting more than once, or reporting for an
+    /// actor that isn't required to, does nothing.
+    ///
+    /// [`RuntimeRef::is_ready`]: crate::rt::RuntimeRef::is_ready
+    pub fn report_ready(&mut self)
+    where
+        RT: rt::Access,
+    {
+        self.rt.report_ready();
+    }
+
+    /// Wait until the runtime is ready, for at most `timeout`.
+    ///
+    /// See [`Context::report_ready`] for when the runtime is ready.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # #![feature(never_type)]
+    /// use std::time::Duration;
+    ///
+    /// use heph::actor;
+    /// use heph::rt::ThreadLocal;
+    ///
+    /// async fn actor(mut ctx: actor::Context<!, ThreadLocal>) {
+    ///     let timeout = Duration::from_secs(10);
+    ///     match ctx.wait_ready(timeout).await {
+    ///         Ok(()) => println!("runtime is ready"),
+    ///         Err(_) => println!("runtime not ready after {:?}", timeout),
+    ///     }
+    /// }
+    /// # drop(actor); // Silence dead code warnings.
+    /// ```
+    pub fn wait_ready(&mut self, timeout: Duration) -> WaitReady<RT>
+    where
+        RT: rt::Access + Clone,
+    {
+        WaitReady::new(self.rt.clone(), Instant::now() + timeout)
+    }
+
+    /// Subscribe to the `topic` on the runtime's event bus.
+    ///
+    /// All messages of type `T` published to `topic` are send to this actor.
+    /// The actor is automatically unsubscribed once it stops. See the [`bus`]
+    /// module for more information.
+    ///
+    /// [`bus`]: crate::bus
+    pub fn subscribe<T>(&mut self, topic: &str)
+    where
+        RT: rt::Access,
+        M: From<T> + Send + 'static,
+        T: Send + 'static,
+    {
+        self.rt.bus().subscribe(topic, self.actor_ref().map());
+    }
+
+    /// Unsubscribe from `topic` on the runtime's event bus.
+    ///
+    /// Returns `true` if the actor was subscribed to `topic` for messages of
+    /// type `T`.
+    pub fn unsubscribe<T>(&mut self, topic: &str) -> bool
+    where
+        RT: rt::Access,
+        M: From<T> + Send + 'static,
+        T: Send + 'static,
+    {
+        self.rt
+            .bus()
+            .unsubscribe::<T>(topic, &self.actor_ref().map())
+    }
+
+    /// Publish `msg` to all actors subscribed to `topic` on the runtime's
+    /// event bus.
+    ///
+    /// Returns the number of actors the message was send to, see
+    /// [`Bus::publish`].
+    ///
+    /// [`Bus::publish`]: crate::bus::Bus::publish
+    pub fn publish<T>(&mut self, topic: &str, msg: T) -> usize
+    where
+        RT: rt::Access,
+        T: Clone + Send + 'static,
+    {
+        self.rt.bus().publish(topic, msg)
+    }
+
+    /// Register `resource` with the runtime.
+    ///
+    /// Registered resources are owned by the runtime and dropped (in reverse
+    /// order of registration) once the actor stops, or once it fails, **before**
+    /// the supervisor's restart creates a new actor. This guarantees that
+    /// cleanup, e.g. removing a lock file or releasing a lease, happens before
+    /// the restarted actor attempts to acquire the resource again, which isn't
+    /// the case for values owned by the actor itself.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::fs;
+    /// use std::io;
+    ///
+    /// use heph::actor;
+    /// use heph::rt::ThreadLocal;
+    ///
+    /// /// Lock file that is removed once dropped.
+    /// struct LockFile(&'static str);
+    ///
+    /// impl Drop for LockFile {
+    ///     fn drop(&mut self) {
+    ///         let _ = fs::remove_file(self.0);
+    ///     }
+    /// }
+    ///
+    /// async fn actor(mut ctx: actor::Context<(), ThreadLocal>) -> io::Result<()> {
+    ///     // If a previous instance of this actor failed its lock file is
+    ///     // already removed.
+    ///     let path = "/tmp/heph_actor.lock";
+    ///     let _ = fs::OpenOptions::new().write(true).create_new(true).open(path)?;
+    ///     ctx.register_resource(LockFile(path));
+    ///
+    ///     // Do work that requires the lock...
+    ///     Ok(())
+    /// }
+    /// # drop(actor); // Silence dead code warnings.
+    /// ```
+    pub fn register_resource<R>(&mut self, resource: R)
+    where
+        R: Send + 'static,
+    {
+        self.inbox.register_resource(Box::new(resource));
+    }
+
     /// Get access to the runtime this actor is running in.
     pub fn runtime(&mut self) -> &mut RT {
         &mut self.rt
@@ -170,6 +669,43 @@ impl RecvError {
     }
 }
 
+impl fmt::Display for RecvError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            RecvError::Empty => "inbox is empty",
+            RecvError::Disconnected => "inbox is empty and all actor references are disconnected",
+        })
+    }
+}
+
+impl std::error::Error for RecvError {}
+
+/// Error returned by [`actor::Context::receive_match`].
+///
+/// [`actor::Context::receive_match`]: crate::actor::Context::receive_match
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum ReceiveMatchError {
+    /// No matching message is available and no references to the actor
+    /// exists, see [`NoMessages`].
+    NoMessages,
+    /// The maximum number of messages, [`DEFERRED_CAPACITY`], are already
+    /// deferred.
+    ///
+    /// [`DEFERRED_CAPACITY`]: crate::actor::DEFERRED_CAPACITY
+    DeferredFull,
+}
+
+impl fmt::Display for ReceiveMatchError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            ReceiveMatchError::NoMessages => "no messages in inbox",
+            ReceiveMatchError::DeferredFull => "maximum number of messages deferred",
+        })
+    }
+}
+
+impl std::error::Error for ReceiveMatchError {}
+
 /// Future to receive a single message.
 ///
 /// The implementation behind and [`actor::Context::receive_next`].
@@ -185,9 +721,109 @@ impl<'ctx, M> Future for ReceiveMessage<'ctx, M> {
     type Output = Result<M, NoMessages>;
 
     fn poll(mut self: Pin<&mut Self>, ctx: &mut task::Context<'_>) -> Poll<Self::Output> {
-        Pin::new(&mut self.recv)
+        Pin::new(&mut self.recv).poll(ctx).map(|r| match r {
+            Some(msg) => {
+                rt::message_received();
+                Ok(msg)
+            }
+            None => Err(NoMessages),
+        })
+    }
+}
+
+/// Future to peek at the next message.
+///
+/// The implementation behind [`actor::Context::peek_next`].
+///
+/// [`actor::Context::peek_next`]: crate::actor::Context::peek_next
+#[derive(Debug)]
+#[must_use = "futures do nothing unless you `.await` or poll them"]
+pub struct PeekMessage<'ctx, M> {
+    /// `None` once the future is completed.
+    inbox: Option<&'ctx mut Receiver<M>>,
+}
+
+impl<'ctx, M> Future for PeekMessage<'ctx, M> {
+    type Output = Result<&'ctx M, NoMessages>;
+
+    fn poll(mut self: Pin<&mut Self>, ctx: &mut task::Context<'_>) -> Poll<Self::Output> {
+        let inbox = self
+            .inbox
+            .take()
+            .expect("polled `PeekMessage` after completion");
+        match inbox.poll_peek(ctx) {
+            Poll::Ready(true) => {
+                let inbox: &'ctx Receiver<M> = inbox;
+                Poll::Ready(inbox.peeked().ok_or(NoMessages))
+            }
+            Poll::Ready(false) => Poll::Ready(Err(NoMessages)),
+            Poll::Pending => {
+                self.inbox = Some(inbox);
+                Poll::Pending
+            }
+        }
+    }
+}
+
+/// Future to receive a single message matching a predicate.
+///
+/// The implementation behind [`actor::Context::receive_match`].
+///
+/// [`actor::Context::receive_match`]: crate::actor::Context::receive_match
+#[must_use = "futures do nothing unless you `.await` or poll them"]
+pub struct ReceiveMatch<'ctx, M, F> {
+    inbox: &'ctx mut Receiver<M>,
+    predicate: F,
+}
+
+impl<'ctx, M, F> Future for ReceiveMatch<'ctx, M, F>
+where
+    F: FnMut(&M) -> bool + Unpin,
+{
+    type Output = Result<M, ReceiveMatchError>;
+
+    fn poll(mut self: Pin<&mut Self>, ctx: &mut task::Context<'_>) -> Poll<Self::Output> {
+        let this = &mut *self;
+        this.inbox
+            .poll_recv_match(&mut this.predicate, ctx)
+            .map(|r| {
+                if r.is_ok() {
+                    rt::message_received();
+                }
+                r
+            })
+    }
+}
+
+impl<'ctx, M, F> fmt::Debug for ReceiveMatch<'ctx, M, F>
+where
+    M: fmt::Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ReceiveMatch")
+            .field("inbox", &self.inbox)
+            .finish()
+    }
+}
+
+/// [`Stream`] of messages send to an actor.
+///
+/// The implementation behind [`actor::Context::messages`].
+///
+/// [`actor::Context::messages`]: crate::actor::Context::messages
+#[derive(Debug)]
+#[must_use = "streams do nothing unless polled"]
+pub struct Messages<'ctx, M, RT> {
+    ctx: &'ctx mut Context<M, RT>,
+}
+
+impl<'ctx, M, RT> Stream for Messages<'ctx, M, RT> {
+    type Item = M;
+
+    fn poll_next(mut self: Pin<&mut Self>, ctx: &mut task::Context<'_>) -> Poll<Option<M>> {
+        Pin::new(&mut self.ctx.receive_next())
             .poll(ctx)
-            .map(|r| r.ok_or(NoMessages))
+            .map(Result::ok)
     }
 }
 
@@ -201,3 +837,5 @@ impl fmt::Display for NoMessages {
         f.write_str("no messages in inbox")
     }
 }
+
+impl std::error::Error for NoMessages {}
@@ -0,0 +1,1139 @@
+//! Module containing the inbox of an actor.
+//!
+//! This wraps the inbox provided by `heph_inbox`, adding a priority lane to it.
+//! Messages send to the priority lane, using [`ActorRef::send_priority`], are
+//! received before any message in the regular inbox. This allows control
+//! messages, e.g. telling the actor to stop, to jump ahead of a long backlog
+//! of regular messages.
+//!
+//! [`ActorRef::send_priority`]: crate::actor_ref::ActorRef::send_priority
+//!
+//! It also keeps track of the lifecycle of the actor: the actors watching it
+//! (see [`ActorRef::watch`]) and its parent and children (see
+//! [`actor::Context::spawn_child`]). And the number of messages in the inbox,
+//! notifying actors watching the inbox (see [`ActorRef::watch_inbox`]).
+//!
+//! [`ActorRef::watch`]: crate::actor_ref::ActorRef::watch
+//! [`actor::Context::spawn_child`]: crate::actor::Context::spawn_child
+//! [`ActorRef::watch_inbox`]: crate::actor_ref::ActorRef::watch_inbox
+//!
+//! The resources registered by the actor (see
+//! [`actor::Context::register_resource`]) are also kept here, so that they can
+//! be dropped by the runtime before the actor is restarted.
+//!
+//! [`actor::Context::register_resource`]: crate::actor::Context::register_resource
+//!
+//! Finally every message is assigned a sequence number when it's send, see
+//! [`actor::Context::message_seq`]. The sequence number is unique per inbox
+//! and increases monotonically, it's logged (at trace level) when the message
+//! is send and received, giving lost or duplicated messages an identifier
+//! that can be found in the logs.
+//!
+//! [`actor::Context::message_seq`]: crate::actor::Context::message_seq
+//!
+//! The receiving side supports selective receive (see
+//! [`actor::Context::receive_match`]) and peeking at the next message (see
+//! [`actor::Context::peek_next`]). Messages skipped by a selective receive are
+//! moved out of the inbox into a deferred queue, which is received from before
+//! the inbox, keeping the messages in order. The deferred queue holds at most
+//! [`DEFERRED_CAPACITY`] messages, which are still counted as in the inbox by
+//! the inbox watermarks.
+//!
+//! [`actor::Context::receive_match`]: crate::actor::Context::receive_match
+//! [`actor::Context::peek_next`]: crate::actor::Context::peek_next
+
+use std::any::Any;
+use std::collections::VecDeque;
+use std::fmt;
+use std::future::Future;
+use std::mem;
+use std::pin::Pin;
+use std::sync::atomic::{AtomicBool, AtomicIsize, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::task::{self, Poll};
+
+use heph_inbox::{self as inbox, ReceiverConnected};
+use log::trace;
+
+use crate::actor::messages::{ActorStopped, InboxWatermark, StopReason};
+#[cfg(feature = "record")]
+use crate::actor::record::Recorder;
+use crate::actor::ReceiveMatchError;
+use crate::actor_ref::ActorRef;
+
+/// Maximum number of messages in the priority lane.
+///
+/// The priority lane is meant for a small number of control messages, not as
+/// a way to bypass the regular inbox.
+pub(crate) const PRIORITY_CAPACITY: usize = 8;
+
+/// Maximum number of messages deferred by a selective receive.
+///
+/// See [`actor::Context::receive_match`].
+///
+/// [`actor::Context::receive_match`]: crate::actor::Context::receive_match
+pub const DEFERRED_CAPACITY: usize = 64;
+
+/// Message with the sequence number assigned to it when it was send.
+#[derive(Debug)]
+pub(crate) struct Envelope<M> {
+    seq: u64,
+    msg: M,
+}
+
+/// See [`inbox::Join`].
+pub(crate) type Join<'s, M> = inbox::Join<'s, Envelope<M>>;
+
+/// Manager of the actor's inbox, see [`inbox::Manager`].
+pub(crate) struct Manager<M> {
+    manager: inbox::Manager<Envelope<M>>,
+    shared: Arc<Shared<M>>,
+}
+
+impl<M> Manager<M> {
+    /// Create a small bounded channel with a priority lane.
+    ///
+    /// See [`inbox::Manager::new_small_channel`].
+    pub(crate) fn new_small_channel() -> (Manager<M>, Sender<M>, Receiver<M>) {
+        let (manager, sender, receiver) = inbox::Manager::new_small_channel();
+        let shared = Arc::new(Shared {
+            id: sender.id(),
+            priority: PriorityLane::new(),
+            lifecycle: Arc::new(Lifecycle::new()),
+            watermarks: Watermarks::new(),
+            resources: Resources::new(),
+            next_seq: AtomicU64::new(0),
+            #[cfg(feature = "record")]
+            recorder: Mutex::new(None),
+        });
+        let sender = Sender {
+            sender,
+            shared: shared.clone(),
+        };
+        let receiver = Receiver {
+            receiver,
+            shared: shared.clone(),
+            last_seq: None,
+            peeked: None,
+            deferred: VecDeque::new(),
+        };
+        (Manager { manager, shared }, sender, receiver)
+    }
+
+    /// Create a new [`Receiver`], see [`inbox::Manager::new_receiver`].
+    pub(crate) fn new_receiver(&self) -> Result<Receiver<M>, ReceiverConnected> {
+        self.manager.new_receiver().map(|receiver| Receiver {
+            receiver,
+            shared: self.shared.clone(),
+            last_seq: None,
+            peeked: None,
+            deferred: VecDeque::new(),
+        })
+    }
+
+    /// Mark the actor as stopped because of `reason`, notifying all watchers
+    /// and stopping all child actors.
+    ///
+    /// Only the first call has an effect.
+    pub(crate) fn stopped(&self, reason: StopReason) {
+        self.shared.lifecycle.stopped(reason);
+        self.shared.resources.drop_all();
+    }
+
+    /// Drop all resources registered by the actor, used when the actor failed
+    /// and before it's restarted.
+    pub(crate) fn drop_resources(&self) {
+        self.shared.resources.drop_all();
+    }
+
+    /// Stop all child actors, used when the actor is restarted.
+    pub(crate) fn stop_children(&self) {
+        self.shared.lifecycle.stop_children();
+    }
+
+    /// Returns `true` if the actor should stop, because its parent stopped or
+    /// because [`ActorRef::stop`] was called.
+    ///
+    /// [`ActorRef::stop`]: crate::actor_ref::ActorRef::stop
+    pub(crate) fn stop_requested(&self) -> bool {
+        self.shared.lifecycle.stop.load(Ordering::SeqCst)
+    }
+
+    /// Returns `true` if a child actor escalated its failure to this actor,
+    /// resetting it.
+    pub(crate) fn take_escalation(&self) -> bool {
+        self.shared
+            .lifecycle
+            .escalated
+            .swap(false, Ordering::SeqCst)
+    }
+
+    /// Escalate a failure to the parent actor, see
+    /// [`SupervisorStrategy::Escalate`]. Returns `false` if the actor has no
+    /// parent.
+    ///
+    /// [`SupervisorStrategy::Escalate`]: crate::supervisor::SupervisorStrategy::Escalate
+    pub(crate) fn escalate(&self) -> bool {
+        self.shared.lifecycle.escalate()
+    }
+
+    /// Link the actor of `child` as child to this actor, see
+    /// [`Receiver::link_child`].
+    #[cfg(test)]
+    pub(crate) fn link_child<CM>(&self, child: &Manager<CM>) {
+        Lifecycle::link(&self.shared.lifecycle, &child.shared.lifecycle);
+    }
+
+    /// Set the waker used to wake the actor's process if it's asked to stop or
+    /// one of its children escalates a failure.
+    ///
+    /// Returns `true` if a stop or escalation is pending.
+    pub(crate) fn register_lifecycle_waker(&self, waker: &task::Waker) -> bool {
+        self.shared.lifecycle.register_waker(waker)
+    }
+}
+
+/// Sending side of the actor's inbox, see [`inbox::Sender`].
+pub(crate) struct Sender<M> {
+    sender: inbox::Sender<Envelope<M>>,
+    shared: Arc<Shared<M>>,
+}
+
+impl<M> Sender<M> {
+    /// See [`inbox::Sender::try_send`].
+    pub(crate) fn try_send(&self, msg: M) -> Result<(), inbox::SendError<M>> {
+        let envelope = self.shared.envelope(msg);
+        let seq = envelope.seq;
+        match self.sender.try_send(envelope) {
+            Ok(()) => {
+                trace!("sent message: inbox={:?}, seq={}", self.shared.id, seq);
+                self.shared.watermarks.enqueued();
+                Ok(())
+            }
+            Err(inbox::SendError::Full(envelope)) => {
+                trace!(
+                    "failed to send message, inbox full: inbox={:?}, seq={}",
+                    self.shared.id,
+                    seq
+                );
+                Err(inbox::SendError::Full(envelope.msg))
+            }
+            Err(inbox::SendError::Disconnected(envelope)) => {
+                trace!(
+                    "failed to send message, inbox disconnected: inbox={:?}, seq={}",
+                    self.shared.id,
+                    seq
+                );
+                Err(inbox::SendError::Disconnected(envelope.msg))
+            }
+        }
+    }
+
+    /// Attempt to send `msg` using the priority lane.
+    ///
+    /// Returns the message if the receiver is disconnected or if the priority
+    /// lane is full.
+    pub(crate) fn try_send_priority(&self, msg: M) -> Result<(), M> {
+        if !self.sender.is_connected() {
+            return Err(msg);
+        }
+        let envelope = self.shared.envelope(msg);
+        let seq = envelope.seq;
+        match self.shared.priority.try_send(envelope) {
+            Ok(()) => {
+                trace!(
+                    "sent priority message: inbox={:?}, seq={}",
+                    self.shared.id,
+                    seq
+                );
+                Ok(())
+            }
+            Err(envelope) => {
+                trace!(
+                    "failed to send priority message, lane full: inbox={:?}, seq={}",
+                    self.shared.id,
+                    seq
+                );
+                Err(envelope.msg)
+            }
+        }
+    }
+
+    /// Add `watcher` to the actor's watchers, see [`ActorRef::watch`].
+    ///
+    /// [`ActorRef::watch`]: crate::actor_ref::ActorRef::watch
+    pub(crate) fn add_watcher(&self, watcher: ActorRef<ActorStopped>) {
+        self.shared.lifecycle.add_watcher(watcher);
+    }
+
+    /// Add `watcher` to the inbox's watchers, see [`ActorRef::watch_inbox`].
+    ///
+    /// [`ActorRef::watch_inbox`]: crate::actor_ref::ActorRef::watch_inbox
+    pub(crate) fn add_inbox_watcher(
+        &self,
+        watcher: ActorRef<InboxWatermark>,
+        low: usize,
+        high: usize,
+    ) {
+        self.shared.watermarks.add_watcher(watcher, low, high);
+    }
+
+    /// See [`inbox::Sender::send`].
+    pub(crate) fn send<'s>(&'s self, msg: M) -> SendValue<'s, M> {
+        let envelope = self.shared.envelope(msg);
+        SendValue {
+            seq: envelope.seq,
+            id: self.shared.id,
+            send: self.sender.send(envelope),
+            watermarks: &self.shared.watermarks,
+        }
+    }
+
+    /// Ask the actor to stop, see [`ActorRef::stop`].
+    ///
+    /// [`ActorRef::stop`]: crate::actor_ref::ActorRef::stop
+    pub(crate) fn stop(&self) {
+        self.shared.lifecycle.request_stop();
+        // Wake the actor in case it's waiting for a message, which is the only
+        // way to wake a synchronous actor.
+        self.shared.priority.wake();
+    }
+
+    /// See [`inbox::Sender::join`].
+    pub(crate) fn join<'s>(&'s self) -> Join<'s, M> {
+        self.sender.join()
+    }
+
+    /// See [`inbox::Sender::is_connected`].
+    pub(crate) fn is_connected(&self) -> bool {
+        self.sender.is_connected()
+    }
+
+    /// See [`inbox::Sender::id`].
+    pub(crate) fn id(&self) -> inbox::Id {
+        self.sender.id()
+    }
+}
+
+/// [`Future`] behind [`Sender::send`].
+pub(crate) struct SendValue<'s, M> {
+    /// Id of the inbox and sequence number of the message, for logging.
+    id: inbox::Id,
+    seq: u64,
+    send: inbox::SendValue<'s, Envelope<M>>,
+    watermarks: &'s Watermarks,
+}
+
+impl<'s, M> Future for SendValue<'s, M> {
+    type Output = <inbox::SendValue<'s, Envelope<M>> as Future>::Output;
+
+    fn poll(self: Pin<&mut Self>, ctx: &mut task::Context<'_>) -> Poll<Self::Output> {
+        // Safety: we're not moving `send` so this is safe.
+        let this = unsafe { self.get_unchecked_mut() };
+        match unsafe { Pin::new_unchecked(&mut this.send) }.poll(ctx) {
+            Poll::Ready(Ok(())) => {
+                trace!("sent message: inbox={:?}, seq={}", this.id, this.seq);
+                this.watermarks.enqueued();
+                Poll::Ready(Ok(()))
+            }
+            poll => poll,
+        }
+    }
+}
+
+impl<M> Clone for Sender<M> {
+    fn clone(&self) -> Sender<M> {
+        Sender {
+            sender: self.sender.clone(),
+            shared: self.shared.clone(),
+        }
+    }
+}
+
+/// Receiving side of the actor's inbox, see [`inbox::Receiver`].
+#[derive(Debug)]
+pub(crate) struct Receiver<M> {
+    receiver: inbox::Receiver<Envelope<M>>,
+    shared: Arc<Shared<M>>,
+    /// Sequence number of the last received message.
+    last_seq: Option<u64>,
+    /// Message returned by [`Receiver::peeked`], received before any other
+    /// message.
+    peeked: Option<Envelope<M>>,
+    /// Messages skipped by [`Receiver::poll_recv_match`], received after the
+    /// priority lane but before the inbox.
+    deferred: VecDeque<Envelope<M>>,
+}
+
+impl<M> Receiver<M> {
+    /// Attempt to receive a message, first checking the priority lane.
+    ///
+    /// See [`inbox::Receiver::try_recv`].
+    ///
+    /// Once the actor is asked to stop this always returns
+    /// [`inbox::RecvError::Disconnected`].
+    pub(crate) fn try_recv(&mut self) -> Result<M, inbox::RecvError> {
+        if self.shared.lifecycle.stop.load(Ordering::SeqCst) {
+            return Err(inbox::RecvError::Disconnected);
+        }
+        let envelope = match self.next_buffered(None) {
+            Some(envelope) => envelope,
+            None => {
+                let envelope = self.receiver.try_recv()?;
+                self.shared.watermarks.dequeued();
+                envelope
+            }
+        };
+        Ok(self.shared.received(envelope, &mut self.last_seq))
+    }
+
+    /// Receive a message, first checking the priority lane.
+    ///
+    /// See [`inbox::Receiver::recv`].
+    pub(crate) fn recv<'r>(&'r mut self) -> RecvValue<'r, M> {
+        RecvValue { receiver: self }
+    }
+
+    /// Poll for the next message, first checking the priority lane.
+    ///
+    /// Returns `None` if the inbox is empty and disconnected or if the actor
+    /// is asked to stop.
+    fn poll_recv(&mut self, ctx: &mut task::Context<'_>) -> Poll<Option<M>> {
+        match self.poll_next_envelope(ctx) {
+            Poll::Ready(Some(envelope)) => {
+                Poll::Ready(Some(self.shared.received(envelope, &mut self.last_seq)))
+            }
+            Poll::Ready(None) => Poll::Ready(None),
+            Poll::Pending => Poll::Pending,
+        }
+    }
+
+    /// Poll until a message is available to peek at, see [`Receiver::peeked`].
+    ///
+    /// Returns `false` if the inbox is empty and disconnected or if the actor
+    /// is asked to stop.
+    pub(crate) fn poll_peek(&mut self, ctx: &mut task::Context<'_>) -> Poll<bool> {
+        if self.shared.lifecycle.stop.load(Ordering::SeqCst) {
+            return Poll::Ready(false);
+        } else if self.peeked.is_some() {
+            return Poll::Ready(true);
+        }
+        match self.poll_next_envelope(ctx) {
+            Poll::Ready(Some(envelope)) => {
+                self.peeked = Some(envelope);
+                Poll::Ready(true)
+            }
+            Poll::Ready(None) => Poll::Ready(false),
+            Poll::Pending => Poll::Pending,
+        }
+    }
+
+    /// Returns the message [`Receiver::poll_peek`] made available, if any.
+    ///
+    /// This message is received before any other message.
+    pub(crate) fn peeked(&self) -> Option<&M> {
+        self.peeked.as_ref().map(|envelope| &envelope.msg)
+    }
+
+    /// Poll for the first message for which `predicate` returns `true`.
+    ///
+    /// Messages are checked in the same order as they are received. Messages
+    /// taken out of the inbox for which `predicate` returns `false` are
+    /// deferred, they are received (in order) before any message still in the
+    /// inbox.
+    ///
+    /// Returns [`ReceiveMatchError::NoMessages`] if no matching message is
+    /// available and the inbox is disconnected or if the actor is asked to
+    /// stop. Returns [`ReceiveMatchError::DeferredFull`] if
+    /// [`DEFERRED_CAPACITY`] messages are already deferred.
+    pub(crate) fn poll_recv_match<F>(
+        &mut self,
+        predicate: &mut F,
+        ctx: &mut task::Context<'_>,
+    ) -> Poll<Result<M, ReceiveMatchError>>
+    where
+        F: FnMut(&M) -> bool,
+    {
+        if self.shared.lifecycle.stop.load(Ordering::SeqCst) {
+            return Poll::Ready(Err(ReceiveMatchError::NoMessages));
+        }
+
+        let peeked_matches = matches!(&self.peeked, Some(envelope) if predicate(&envelope.msg));
+        let envelope = if peeked_matches {
+            self.peeked.take()
+        } else {
+            // NOTE: the waker must be registered before checking the inbox,
+            // otherwise we could miss a wake-up.
+            match self.shared.priority.try_recv_match(predicate, ctx.waker()) {
+                Some(envelope) => Some(envelope),
+                None => {
+                    let idx = self
+                        .deferred
+                        .iter()
+                        .position(|envelope| predicate(&envelope.msg));
+                    idx.and_then(|idx| self.remove_deferred(idx))
+                }
+            }
+        };
+        if let Some(envelope) = envelope {
+            return Poll::Ready(Ok(self.shared.received(envelope, &mut self.last_seq)));
+        }
+
+        loop {
+            if self.deferred.len() >= DEFERRED_CAPACITY {
+                trace!("deferred messages at capacity: inbox={:?}", self.shared.id);
+                return Poll::Ready(Err(ReceiveMatchError::DeferredFull));
+            }
+
+            match self.poll_inbox(ctx) {
+                Poll::Ready(Some(envelope)) if predicate(&envelope.msg) => {
+                    self.shared.watermarks.dequeued();
+                    let msg = self.shared.received(envelope, &mut self.last_seq);
+                    return Poll::Ready(Ok(msg));
+                }
+                // NOTE: deferred messages are still counted by the watermarks,
+                // see `remove_deferred`.
+                Poll::Ready(Some(envelope)) => {
+                    trace!(
+                        "deferred message: inbox={:?}, seq={}",
+                        self.shared.id,
+                        envelope.seq
+                    );
+                    self.deferred.push_back(envelope);
+                }
+                Poll::Ready(None) => return Poll::Ready(Err(ReceiveMatchError::NoMessages)),
+                Poll::Pending => return Poll::Pending,
+            }
+        }
+    }
+
+    /// Poll for the next message, without unwrapping the envelope.
+    fn poll_next_envelope(&mut self, ctx: &mut task::Context<'_>) -> Poll<Option<Envelope<M>>> {
+        if self.shared.lifecycle.stop.load(Ordering::SeqCst) {
+            return Poll::Ready(None);
+        }
+        // NOTE: the waker must be registered before checking the inbox,
+        // otherwise we could miss a wake-up.
+        match self.next_buffered(Some(ctx.waker())) {
+            Some(envelope) => Poll::Ready(Some(envelope)),
+            None => match self.poll_inbox(ctx) {
+                Poll::Ready(Some(envelope)) => {
+                    self.shared.watermarks.dequeued();
+                    Poll::Ready(Some(envelope))
+                }
+                poll => poll,
+            },
+        }
+    }
+
+    /// Poll for the next message in the (regular) inbox, ignoring the
+    /// priority lane and deferred messages.
+    ///
+    /// # Notes
+    ///
+    /// The caller must update the watermarks once the message is dequeued.
+    fn poll_inbox(&mut self, ctx: &mut task::Context<'_>) -> Poll<Option<Envelope<M>>> {
+        let mut registered = false;
+        loop {
+            match self.receiver.try_recv() {
+                Ok(envelope) => return Poll::Ready(Some(envelope)),
+                Err(inbox::RecvError::Empty) if !registered => {
+                    // Register our waker and try again, otherwise we could
+                    // miss a wake-up.
+                    let _ = self.receiver.register_waker(ctx.waker());
+                    registered = true;
+                }
+                Err(inbox::RecvError::Empty) => return Poll::Pending,
+                Err(inbox::RecvError::Disconnected) => return Poll::Ready(None),
+            }
+        }
+    }
+
+    /// Returns the next message that isn't in the inbox: the peeked message,
+    /// the first message in the priority lane or the first deferred message.
+    ///
+    /// If `waker` is provided it's registered with the priority lane in case
+    /// it's empty.
+    fn next_buffered(&mut self, waker: Option<&task::Waker>) -> Option<Envelope<M>> {
+        if let Some(envelope) = self.peeked.take() {
+            return Some(envelope);
+        }
+        let envelope = match waker {
+            Some(waker) => self.shared.priority.try_recv_or_register(waker),
+            None => self.shared.priority.try_recv(),
+        };
+        envelope.or_else(|| self.remove_deferred(0))
+    }
+
+    /// Remove the deferred message at `idx`, if any.
+    ///
+    /// Deferred messages are counted as in the inbox by the watermarks until
+    /// they're removed from the deferred queue.
+    fn remove_deferred(&mut self, idx: usize) -> Option<Envelope<M>> {
+        let envelope = self.deferred.remove(idx)?;
+        self.shared.watermarks.dequeued();
+        Some(envelope)
+    }
+
+    /// Returns the sequence number of the last received message, see
+    /// [`actor::Context::message_seq`].
+    ///
+    /// [`actor::Context::message_seq`]: crate::actor::Context::message_seq
+    pub(crate) const fn last_seq(&self) -> Option<u64> {
+        self.last_seq
+    }
+
+    /// Record all messages received from now on using `recorder`, see
+    /// [`actor::Context::record_messages`].
+    ///
+    /// [`actor::Context::record_messages`]: crate::actor::Context::record_messages
+    #[cfg(feature = "record")]
+    pub(crate) fn record_messages(&self, recorder: Recorder<M>) {
+        *self.shared.recorder.lock().unwrap() = Some(recorder);
+    }
+
+    /// Register `resource`, see [`actor::Context::register_resource`].
+    ///
+    /// [`actor::Context::register_resource`]: crate::actor::Context::register_resource
+    pub(crate) fn register_resource(&self, resource: Box<dyn Any + Send>) {
+        self.shared.resources.register(resource);
+    }
+
+    /// Create a new [`Sender`], see [`inbox::Receiver::new_sender`].
+    pub(crate) fn new_sender(&self) -> Sender<M> {
+        Sender {
+            sender: self.receiver.new_sender(),
+            shared: self.shared.clone(),
+        }
+    }
+
+    /// Link the actor of `child` as child to this actor, see
+    /// [`actor::Context::spawn_child`].
+    ///
+    /// [`actor::Context::spawn_child`]: crate::actor::Context::spawn_child
+    pub(crate) fn link_child<CM>(&self, child: &Receiver<CM>) {
+        Lifecycle::link(&self.shared.lifecycle, &child.shared.lifecycle);
+    }
+
+    /// Set the waker of the inbox and the priority lane to `waker`.
+    ///
+    /// See [`inbox::Receiver::register_waker`].
+    pub(crate) fn register_waker(&mut self, waker: &task::Waker) -> bool {
+        self.shared.priority.register_waker(waker);
+        self.receiver.register_waker(waker)
+    }
+}
+
+impl<M> Drop for Receiver<M> {
+    fn drop(&mut self) {
+        // Deferred messages are dropped with the receiver, e.g. when the actor
+        // is restarted, so they're no longer in the inbox.
+        while self.remove_deferred(0).is_some() {}
+    }
+}
+
+/// [`Future`] behind [`Receiver::recv`].
+#[derive(Debug)]
+pub(crate) struct RecvValue<'r, M> {
+    receiver: &'r mut Receiver<M>,
+}
+
+impl<'r, M> Future for RecvValue<'r, M> {
+    type Output = Option<M>;
+
+    fn poll(mut self: Pin<&mut Self>, ctx: &mut task::Context<'_>) -> Poll<Self::Output> {
+        self.receiver.poll_recv(ctx)
+    }
+}
+
+/// State shared between the [`Manager`], [`Sender`]s and [`Receiver`].
+#[derive(Debug)]
+struct Shared<M> {
+    /// Id of the inbox, for logging.
+    id: inbox::Id,
+    priority: PriorityLane<M>,
+    lifecycle: Arc<Lifecycle>,
+    watermarks: Watermarks,
+    resources: Resources,
+    /// Sequence number assigned to the next message send.
+    next_seq: AtomicU64,
+    /// Records the received messages, see [`Receiver::record_messages`].
+    #[cfg(feature = "record")]
+    recorder: Mutex<Option<Recorder<M>>>,
+}
+
+impl<M> Shared<M> {
+    /// Wrap `msg` in an envelope, assigning it the next sequence number.
+    fn envelope(&self, msg: M) -> Envelope<M> {
+        let seq = self.next_seq.fetch_add(1, Ordering::Relaxed);
+        Envelope { seq, msg }
+    }
+
+    /// Unwrap a received `envelope`, setting `last_seq` to its sequence
+    /// number.
+    fn received(&self, envelope: Envelope<M>, last_seq: &mut Option<u64>) -> M {
+        trace!(
+            "received message: inbox={:?}, seq={}",
+            self.id,
+            envelope.seq
+        );
+        *last_seq = Some(envelope.seq);
+        #[cfg(feature = "record")]
+        self.record(&envelope.msg);
+        envelope.msg
+    }
+
+    /// Record `msg`, if the actor is recording its messages.
+    #[cfg(feature = "record")]
+    fn record(&self, msg: &M) {
+        if let Some(recorder) = &mut *self.recorder.lock().unwrap() {
+            recorder.record(msg);
+        }
+    }
+}
+
+/// Resources registered by the actor, see
+/// [`actor::Context::register_resource`].
+///
+/// [`actor::Context::register_resource`]: crate::actor::Context::register_resource
+struct Resources {
+    resources: Mutex<Vec<Box<dyn Any + Send>>>,
+}
+
+impl Resources {
+    const fn new() -> Resources {
+        Resources {
+            resources: Mutex::new(Vec::new()),
+        }
+    }
+
+    fn register(&self, resource: Box<dyn Any + Send>) {
+        self.resources.lock().unwrap().push(resource);
+    }
+
+    /// Drop all resources, in reverse order of registration.
+    fn drop_all(&self) {
+        // NOTE: drop the resources outside of the lock.
+        let resources = mem::take(&mut *self.resources.lock().unwrap());
+        for resource in resources.into_iter().rev() {
+            drop(resource);
+        }
+    }
+}
+
+impl fmt::Debug for Resources {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Resources")
+            .field("len", &self.resources.lock().unwrap().len())
+            .finish()
+    }
+}
+
+/// Number of messages in the (regular) inbox and the actors watching it, see
+/// [`ActorRef::watch_inbox`].
+///
+/// [`ActorRef::watch_inbox`]: crate::actor_ref::ActorRef::watch_inbox
+#[derive(Debug)]
+struct Watermarks {
+    /// Number of messages in the inbox.
+    ///
+    /// # Notes
+    ///
+    /// This is updated after a message is sent or received, so it can
+    /// (briefly) be negative if a message is received before the sender
+    /// updated the length.
+    len: AtomicIsize,
+    /// Set once the first watcher is added, to avoid locking `watchers` if
+    /// there are no watchers.
+    has_watchers: AtomicBool,
+    watchers: Mutex<Vec<WatermarkWatcher>>,
+}
+
+#[derive(Debug)]
+struct WatermarkWatcher {
+    watcher: ActorRef<InboxWatermark>,
+    low: usize,
+    high: usize,
+    /// `true` if the number of messages reached `high` and hasn't dropped to
+    /// `low` since.
+    above: bool,
+}
+
+impl Watermarks {
+    const fn new() -> Watermarks {
+        Watermarks {
+            len: AtomicIsize::new(0),
+            has_watchers: AtomicBool::new(false),
+            watchers: Mutex::new(Vec::new()),
+        }
+    }
+
+    /// Add `watcher`, notifying it directly if the inbox is already at or
+    /// above the `high` watermark.
+    fn add_watcher(&self, watcher: ActorRef<InboxWatermark>, low: usize, high: usize) {
+        let msg = {
+            let mut watchers = self.watchers.lock().unwrap();
+            let mut w = WatermarkWatcher {
+                watcher: watcher.clone(),
+                low,
+                high,
+                above: false,
+            };
+            let msg = w.update(self.len());
+            watchers.push(w);
+            self.has_watchers.store(true, Ordering::Release);
+            msg
+        };
+        // NOTE: send outside of the lock.
+        if let Some(msg) = msg {
+            let _ = watcher.try_send(msg);
+        }
+    }
+
+    /// Returns the number of messages in the inbox.
+    fn len(&self) -> usize {
+        // NOTE: the length can be negative, see `Watermarks.len`.
+        self.len.load(Ordering::Acquire).max(0) as usize
+    }
+
+    /// A message was added to the inbox.
+    fn enqueued(&self) {
+        let _ = self.len.fetch_add(1, Ordering::AcqRel);
+        self.notify();
+    }
+
+    /// A message was removed from the inbox.
+    fn dequeued(&self) {
+        let _ = self.len.fetch_sub(1, Ordering::AcqRel);
+        self.notify();
+    }
+
+    /// Notify the watchers of which a watermark was crossed.
+    fn notify(&self) {
+        if !self.has_watchers.load(Ordering::Acquire) {
+            return;
+        }
+        let notifications: Vec<(ActorRef<InboxWatermark>, InboxWatermark)> = {
+            let mut watchers = self.watchers.lock().unwrap();
+            // Remove watchers that stopped.
+            watchers.retain(|watcher| watcher.watcher.is_connected());
+            // NOTE: read the length while holding the lock so that all changes
+            // are seen in order.
+            let len = self.len();
+            watchers
+                .iter_mut()
+                .filter_map(|watcher| {
+                    let msg = watcher.update(len)?;
+                    Some((watcher.watcher.clone(), msg))
+                })
+                .collect()
+        };
+        // NOTE: send outside of the lock.
+        for (watcher, msg) in notifications {
+            // The message is lost if the watcher's inbox is full.
+            let _ = watcher.try_send(msg);
+        }
+    }
+}
+
+impl WatermarkWatcher {
+    /// Update the watcher with the current length of the inbox, returning the
+    /// message to send to the watcher if a watermark was crossed.
+    fn update(&mut self, len: usize) -> Option<InboxWatermark> {
+        if !self.above && len >= self.high {
+            self.above = true;
+            Some(InboxWatermark::High)
+        } else if self.above && len <= self.low {
+            self.above = false;
+            Some(InboxWatermark::Low)
+        } else {
+            None
+        }
+    }
+}
+
+/// Priority lane of an actor's inbox.
+#[derive(Debug)]
+struct PriorityLane<M> {
+    inner: Mutex<PriorityLaneInner<M>>,
+}
+
+#[derive(Debug)]
+struct PriorityLaneInner<M> {
+    messages: VecDeque<Envelope<M>>,
+    /// Waker of the actor, if any.
+    waker: Option<task::Waker>,
+}
+
+impl<M> PriorityLane<M> {
+    const fn new() -> PriorityLane<M> {
+        PriorityLane {
+            inner: Mutex::new(PriorityLaneInner {
+                messages: VecDeque::new(),
+                waker: None,
+            }),
+        }
+    }
+
+    /// Add `msg` to the lane, waking the actor. Returns the message if the
+    /// lane is full.
+    fn try_send(&self, msg: Envelope<M>) -> Result<(), Envelope<M>> {
+        let waker = {
+            let mut inner = self.inner.lock().unwrap();
+            if inner.messages.len() >= PRIORITY_CAPACITY {
+                return Err(msg);
+            }
+            inner.messages.push_back(msg);
+            inner.waker.clone()
+        };
+        // NOTE: wake outside of the lock.
+        if let Some(waker) = waker {
+            waker.wake();
+        }
+        Ok(())
+    }
+
+    /// Remove the first message from the lane, if any.
+    fn try_recv(&self) -> Option<Envelope<M>> {
+        self.inner.lock().unwrap().messages.pop_front()
+    }
+
+    /// Remove the first message for which `predicate` returns `true`, or set
+    /// the waker in case no such message is available.
+    fn try_recv_match<F>(&self, predicate: &mut F, waker: &task::Waker) -> Option<Envelope<M>>
+    where
+        F: FnMut(&M) -> bool,
+    {
+        let mut inner = self.inner.lock().unwrap();
+        match inner.messages.iter().position(|msg| predicate(&msg.msg)) {
+            Some(idx) => inner.messages.remove(idx),
+            None => {
+                set_waker(&mut inner.waker, waker);
+                None
+            }
+        }
+    }
+
+    /// Same as [`PriorityLane::try_recv`], but also sets the waker in case no
+    /// message is available.
+    fn try_recv_or_register(&self, waker: &task::Waker) -> Option<Envelope<M>> {
+        let mut inner = self.inner.lock().unwrap();
+        match inner.messages.pop_front() {
+            Some(msg) => Some(msg),
+            None => {
+                set_waker(&mut inner.waker, waker);
+                None
+            }
+        }
+    }
+
+    /// Set the waker of the actor.
+    fn register_waker(&self, waker: &task::Waker) {
+        set_waker(&mut self.inner.lock().unwrap().waker, waker);
+    }
+
+    /// Wake the actor, if it's waiting for a message.
+    fn wake(&self) {
+        let waker = self.inner.lock().unwrap().waker.clone();
+        if let Some(waker) = waker {
+            waker.wake();
+        }
+    }
+}
+
+/// Lifecycle of an actor.
+///
+/// This keeps track of the actors watching this actor (see
+/// [`ActorRef::watch`]) and the parent and children of the actor (see
+/// [`actor::Context::spawn_child`]).
+///
+/// [`ActorRef::watch`]: crate::actor_ref::ActorRef::watch
+/// [`actor::Context::spawn_child`]: crate::actor::Context::spawn_child
+#[derive(Debug)]
+struct Lifecycle {
+    /// Set once the actor is linked to a parent or child actor.
+    linked: AtomicBool,
+    /// Set if the actor should stop, because its parent stopped or because
+    /// [`ActorRef::stop`] was called.
+    ///
+    /// [`ActorRef::stop`]: crate::actor_ref::ActorRef::stop
+    stop: AtomicBool,
+    /// Set once the waker of the actor's process is set, see
+    /// [`Lifecycle::register_waker`].
+    has_waker: AtomicBool,
+    /// Set if a child actor escalated its failure to this actor.
+    escalated: AtomicBool,
+    state: Mutex<LifecycleState>,
+}
+
+#[derive(Debug)]
+struct LifecycleState {
+    /// Reason why the actor stopped, `None` while it's still running.
+    stopped: Option<StopReason>,
+    /// Actors to notify once this actor stops.
+    watchers: Vec<ActorRef<ActorStopped>>,
+    /// Parent actor, if any.
+    parent: Option<Arc<Lifecycle>>,
+    /// Child actors, stopped once this actor stops.
+    children: Vec<Arc<Lifecycle>>,
+    /// Waker for the actor's process.
+    waker: Option<task::Waker>,
+}
+
+impl Lifecycle {
+    const fn new() -> Lifecycle {
+        Lifecycle {
+            linked: AtomicBool::new(false),
+            stop: AtomicBool::new(false),
+            has_waker: AtomicBool::new(false),
+            escalated: AtomicBool::new(false),
+            state: Mutex::new(LifecycleState {
+                stopped: None,
+                watchers: Vec::new(),
+                parent: None,
+                children: Vec::new(),
+                waker: None,
+            }),
+        }
+    }
+
+    /// Link `child` to `parent`.
+    fn link(parent: &Arc<Lifecycle>, child: &Arc<Lifecycle>) {
+        child.state.lock().unwrap().parent = Some(parent.clone());
+        child.linked.store(true, Ordering::SeqCst);
+        let parent_stopped = {
+            let mut state = parent.state.lock().unwrap();
+            if state.stopped.is_none() {
+                state.children.push(child.clone());
+            }
+            state.stopped.is_some()
+        };
+        parent.linked.store(true, Ordering::SeqCst);
+        if parent_stopped {
+            child.request_stop();
+        }
+    }
+
+    /// Add `watcher`, or notify it directly if the actor already stopped.
+    fn add_watcher(&self, watcher: ActorRef<ActorStopped>) {
+        let reason = {
+            let mut state = self.state.lock().unwrap();
+            match state.stopped {
+                Some(reason) => reason,
+                None => {
+                    state.watchers.push(watcher);
+                    return;
+                }
+            }
+        };
+        // NOTE: send outside of the lock.
+        let _ = watcher.try_send(ActorStopped { reason });
+    }
+
+    /// Mark the actor as stopped, notifying all watchers and stopping all
+    /// children. Only the first call has an effect.
+    fn stopped(self: &Arc<Self>, reason: StopReason) {
+        let (watchers, parent, children) = {
+            let mut state = self.state.lock().unwrap();
+            if state.stopped.is_some() {
+                return;
+            }
+            state.stopped = Some(reason);
+            state.waker = None;
+            (
+                mem::take(&mut state.watchers),
+                state.parent.take(),
+                mem::take(&mut state.children),
+            )
+        };
+        // NOTE: send and stop outside of the lock.
+        for watcher in watchers {
+            // The watcher might have stopped itself, which we can ignore.
+            let _ = watcher.try_send(ActorStopped { reason });
+        }
+        for child in children {
+            child.request_stop();
+        }
+        if let Some(parent) = parent {
+            parent.remove_child(self);
+        }
+    }
+
+    /// Stop all children of the actor.
+    fn stop_children(&self) {
+        let children = mem::take(&mut self.state.lock().unwrap().children);
+        for child in children {
+            child.request_stop();
+        }
+    }
+
+    /// Remove `child` from the actor's children.
+    fn remove_child(&self, child: &Arc<Lifecycle>) {
+        let mut state = self.state.lock().unwrap();
+        state.children.retain(|c| !Arc::ptr_eq(c, child));
+    }
+
+    /// Ask the actor to stop, waking its process.
+    fn request_stop(&self) {
+        let waker = {
+            let state = self.state.lock().unwrap();
+            if state.stopped.is_some() {
+                return;
+            }
+            self.stop.store(true, Ordering::SeqCst);
+            state.waker.clone()
+        };
+        if let Some(waker) = waker {
+            waker.wake();
+        }
+    }
+
+    /// Escalate a failure to the parent actor. Returns `false` if the actor
+    /// has no parent.
+    fn escalate(&self) -> bool {
+        let parent = self.state.lock().unwrap().parent.clone();
+        match parent {
+            Some(parent) => {
+                let waker = {
+                    let state = parent.state.lock().unwrap();
+                    parent.escalated.store(true, Ordering::SeqCst);
+                    state.waker.clone()
+                };
+                if let Some(waker) = waker {
+                    waker.wake();
+                }
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Set the waker of the actor's process.
+    ///
+    /// The waker is always set once, so that the actor can be stopped using
+    /// [`ActorRef::stop`]. After that it's only updated if the actor is linked
+    /// to another actor, to avoid locking on every run.
+    ///
+    /// Returns `true` if a stop or escalation is pending.
+    ///
+    /// [`ActorRef::stop`]: crate::actor_ref::ActorRef::stop
+    fn register_waker(&self, waker: &task::Waker) -> bool {
+        if !self.linked.load(Ordering::SeqCst) && self.has_waker.load(Ordering::SeqCst) {
+            return self.stop.load(Ordering::SeqCst);
+        }
+        let mut state = self.state.lock().unwrap();
+        if state.stopped.is_none() {
+            set_waker(&mut state.waker, waker);
+            self.has_waker.store(true, Ordering::SeqCst);
+        }
+        self.stop.load(Ordering::SeqCst) || self.escalated.load(Ordering::SeqCst)
+    }
+}
+
+/// Replace `current` waker with `waker`, if the two don't wake the same task.
+fn set_waker(current: &mut Option<task::Waker>, waker: &task::Waker) {
+    match current {
+        Some(current) if current.will_wake(waker) => {}
+        _ => *current = Some(waker.clone()),
+    }
+}
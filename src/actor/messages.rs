@@ -96,6 +96,49 @@ pub struct Cancel<Id = ()>(pub Id);
 #[derive(Copy, Clone, Debug, Eq, PartialEq, Ord, PartialOrd)]
 pub struct Terminate;
 
+/// Message send to watchers of an actor once the actor stopped.
+///
+/// See [`ActorRef::watch`] to watch an actor.
+///
+/// [`ActorRef::watch`]: crate::actor_ref::ActorRef::watch
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct ActorStopped {
+    /// Reason why the actor stopped.
+    pub reason: StopReason,
+}
+
+/// Message sent to watchers of an actor's inbox once the number of messages in
+/// the inbox crosses a watermark.
+///
+/// See [`ActorRef::watch_inbox`] to watch an actor's inbox.
+///
+/// [`ActorRef::watch_inbox`]: crate::actor_ref::ActorRef::watch_inbox
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum InboxWatermark {
+    /// The number of messages in the inbox reached the high watermark.
+    High,
+    /// The number of messages in the inbox dropped to the low watermark, after
+    /// reaching the high watermark.
+    Low,
+}
+
+/// Reason why an actor stopped, see [`ActorStopped`].
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum StopReason {
+    /// The actor completed successfully.
+    Completed,
+    /// The actor returned an error and wasn't restarted by its supervisor.
+    Failed,
+    /// The actor panicked.
+    Panicked,
+    /// The actor was stopped before completing, e.g. because its parent
+    /// actor stopped (see [`actor::Context::spawn_child`]) or because the
+    /// runtime stopped.
+    ///
+    /// [`actor::Context::spawn_child`]: crate::actor::Context::spawn_child
+    Terminated,
+}
+
 /// Macro to implement [`From`] for an enum message type.
 ///
 /// # Examples
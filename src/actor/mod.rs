@@ -140,14 +140,21 @@ use std::pin::Pin;
 use std::task::{self, Poll};
 
 mod context;
+pub(crate) mod inbox;
 pub mod messages;
+#[cfg(feature = "record")]
+pub(crate) mod record;
 mod sync;
 #[cfg(test)]
 mod tests;
 
 #[doc(inline)]
-pub use context::{Context, NoMessages, ReceiveMessage, RecvError};
-#[cfg(any(test, feature = "test"))]
+pub use context::{
+    Context, Messages, NoMessages, PeekMessage, ReceiveMatch, ReceiveMatchError, ReceiveMessage,
+    RecvError,
+};
+#[doc(inline)]
+pub use inbox::DEFERRED_CAPACITY;
 pub(crate) use sync::SyncWaker;
 #[doc(inline)]
 pub use sync::{SyncActor, SyncContext};
@@ -265,6 +272,27 @@ pub trait NewActor {
         arg: Self::Argument,
     ) -> Result<Self::Actor, Self::Error>;
 
+    /// Validate the argument(s) passed to the actor.
+    ///
+    /// This is called before [`new`] whenever an actor is spawned or restarted.
+    /// Returning an error here fails spawning the actor synchronously, i.e.
+    /// [`try_spawn_local`] returns the error, rather than spawning an actor
+    /// that fails on its first poll. When restarting an actor the error is
+    /// passed to the supervisor's [`decide_on_restart_error`] method.
+    ///
+    /// This can be used to check for invalid configuration, such as addresses
+    /// that can't be reached, before creating the actor.
+    ///
+    /// Defaults to accepting all arguments.
+    ///
+    /// [`new`]: NewActor::new
+    /// [`try_spawn_local`]: crate::RuntimeRef::try_spawn_local
+    /// [`decide_on_restart_error`]: crate::supervisor::Supervisor::decide_on_restart_error
+    fn validate(&self, arg: &Self::Argument) -> Result<(), Self::Error> {
+        let _ = arg;
+        Ok(())
+    }
+
     /// Wrap the `NewActor` to change the arguments its accepts.
     ///
     /// This can be used when additional arguments are needed to be passed to an
@@ -383,6 +411,72 @@ pub trait NewActor {
         }
     }
 
+    /// Wrap the `NewActor` so that its supervisor can replace it with a
+    /// different actor implementation, `replacement`.
+    ///
+    /// The replacement uses the same process id and inbox as the original
+    /// actor, which means that actor references to the actor keep working.
+    /// This can be used to switch to, for example, a degraded-mode actor when
+    /// the primary implementation keeps failing.
+    ///
+    /// The argument of the returned `NewActor` is a [`ReplaceableArg`], which
+    /// determines which actor is created. A supervisor can replace the actor
+    /// using [`SupervisorStrategy::replace`].
+    ///
+    /// [`SupervisorStrategy::replace`]: crate::supervisor::SupervisorStrategy::replace
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use heph::actor::{self, NewActor, ReplaceableArg};
+    /// use heph::rt::ThreadLocal;
+    /// use heph::spawn::ActorOptions;
+    /// use heph::supervisor::SupervisorStrategy;
+    /// use heph::test::{join, try_spawn_local};
+    /// # use std::time::Duration;
+    ///
+    /// /// Our primary actor, which always fails.
+    /// async fn primary_actor(_: actor::Context<String, ThreadLocal>) -> Result<(), ()> {
+    ///     Err(())
+    /// }
+    ///
+    /// /// Actor running in degraded mode.
+    /// async fn degraded_actor(mut ctx: actor::Context<String, ThreadLocal>) -> Result<(), ()> {
+    ///     if let Ok(msg) = ctx.receive_next().await {
+    ///         println!("Got a message (in degraded mode): {}", msg);
+    ///     }
+    ///     Ok(())
+    /// }
+    ///
+    /// let new_actor = (primary_actor as fn(_) -> _).with_replacement(degraded_actor as fn(_) -> _);
+    ///
+    /// // Restart the primary actor twice before replacing it.
+    /// let mut restarts = 0;
+    /// let supervisor = move |()| {
+    ///     restarts += 1;
+    ///     if restarts <= 2 {
+    ///         SupervisorStrategy::Restart(ReplaceableArg::Primary(()))
+    ///     } else {
+    ///         SupervisorStrategy::replace(())
+    ///     }
+    /// };
+    ///
+    /// let arg = ReplaceableArg::Primary(());
+    /// let actor_ref = try_spawn_local(supervisor, new_actor, arg, ActorOptions::default()).unwrap();
+    /// actor_ref.try_send("Hello world".to_owned()).unwrap();
+    /// # join(&actor_ref, Duration::from_secs(1)).unwrap();
+    /// ```
+    fn with_replacement<R>(self, replacement: R) -> Replaceable<Self, R>
+    where
+        Self: Sized,
+        R: NewActor<Message = Self::Message, RuntimeAccess = Self::RuntimeAccess>,
+    {
+        Replaceable {
+            primary: self,
+            replacement,
+        }
+    }
+
     /// Returns the name of the actor.
     ///
     /// The default implementation creates the name based on the type name of
@@ -437,6 +531,8 @@ where
         arg: Self::Argument,
     ) -> Result<Self::Actor, Self::Error> {
         let arg = (self.map)(arg);
+        // We can only validate the argument once it's mapped.
+        self.new_actor.validate(&arg)?;
         self.new_actor.new(ctx, arg)
     }
 
@@ -445,6 +541,92 @@ where
     }
 }
 
+/// See [`NewActor::with_replacement`].
+#[derive(Clone, Debug)]
+pub struct Replaceable<NA, R> {
+    primary: NA,
+    replacement: R,
+}
+
+/// Argument for [`Replaceable`], determining which actor is created.
+///
+/// See [`NewActor::with_replacement`].
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum ReplaceableArg<A, R> {
+    /// Create the primary actor with the argument.
+    Primary(A),
+    /// Create the replacement actor with the argument.
+    Replacement(R),
+}
+
+impl<NA, R> NewActor for Replaceable<NA, R>
+where
+    NA: NewActor,
+    R: NewActor<Message = NA::Message, Error = NA::Error, RuntimeAccess = NA::RuntimeAccess>,
+    R::Actor: Actor<Error = <NA::Actor as Actor>::Error>,
+{
+    type Message = NA::Message;
+    type Argument = ReplaceableArg<NA::Argument, R::Argument>;
+    type Actor = ReplaceableActor<NA::Actor, R::Actor>;
+    type Error = NA::Error;
+    type RuntimeAccess = NA::RuntimeAccess;
+
+    fn new(
+        &mut self,
+        ctx: Context<Self::Message, Self::RuntimeAccess>,
+        arg: Self::Argument,
+    ) -> Result<Self::Actor, Self::Error> {
+        let actor = match arg {
+            ReplaceableArg::Primary(arg) => Inner::Primary(self.primary.new(ctx, arg)?),
+            ReplaceableArg::Replacement(arg) => Inner::Replacement(self.replacement.new(ctx, arg)?),
+        };
+        Ok(ReplaceableActor { actor })
+    }
+
+    fn validate(&self, arg: &Self::Argument) -> Result<(), Self::Error> {
+        match arg {
+            ReplaceableArg::Primary(arg) => self.primary.validate(arg),
+            ReplaceableArg::Replacement(arg) => self.replacement.validate(arg),
+        }
+    }
+
+    fn name(&self) -> &'static str {
+        self.primary.name()
+    }
+}
+
+/// The [`Actor`] created by [`Replaceable`], either the primary or the
+/// replacement actor.
+#[derive(Debug)]
+pub struct ReplaceableActor<A, R> {
+    actor: Inner<A, R>,
+}
+
+#[derive(Debug)]
+enum Inner<A, R> {
+    Primary(A),
+    Replacement(R),
+}
+
+impl<A, R> Actor for ReplaceableActor<A, R>
+where
+    A: Actor,
+    R: Actor<Error = A::Error>,
+{
+    type Error = A::Error;
+
+    fn try_poll(
+        self: Pin<&mut Self>,
+        ctx: &mut task::Context<'_>,
+    ) -> Poll<Result<(), Self::Error>> {
+        // SAFETY: not moving the actor.
+        match unsafe { &mut Pin::get_unchecked_mut(self).actor } {
+            Inner::Primary(actor) => unsafe { Pin::new_unchecked(actor) }.try_poll(ctx),
+            Inner::Replacement(actor) => unsafe { Pin::new_unchecked(actor) }.try_poll(ctx),
+        }
+    }
+}
+
 /// Macro to implement the [`NewActor`] trait on function pointers.
 macro_rules! impl_new_actor {
     (
@@ -0,0 +1,77 @@
+//! Module with the message recorder, see [`actor::Context::record_messages`].
+//!
+//! Messages are recorded as JSON, one message per line.
+//!
+//! [`actor::Context::record_messages`]: crate::actor::Context::record_messages
+
+use std::fmt;
+use std::fs::File;
+use std::io::{self, Write};
+use std::path::Path;
+
+use log::warn;
+use serde::Serialize;
+
+/// Records all messages received by an actor to a file.
+pub(crate) struct Recorder<M> {
+    file: File,
+    /// Writes a message to `file`. This is a function pointer so that we don't
+    /// require `M: Serialize` everywhere the recorder is used.
+    write: fn(&mut File, &M) -> io::Result<()>,
+}
+
+impl<M> Recorder<M> {
+    /// Create a new `Recorder` writing to the file at `path`. If the file
+    /// already exists it will be truncated.
+    pub(crate) fn create<P>(path: P) -> io::Result<Recorder<M>>
+    where
+        P: AsRef<Path>,
+        M: Serialize,
+    {
+        File::create(path).map(|file| Recorder {
+            file,
+            write: write_message::<M>,
+        })
+    }
+
+    /// Record `msg`.
+    ///
+    /// Errors are logged, but otherwise ignored as we don't want to fail
+    /// receiving messages because recording them failed.
+    pub(crate) fn record(&mut self, msg: &M) {
+        if let Err(err) = (self.write)(&mut self.file, msg) {
+            warn!("failed to record message: {}", err);
+        }
+    }
+}
+
+fn write_message<M: Serialize>(file: &mut File, msg: &M) -> io::Result<()> {
+    let mut buf = serde_json::to_vec(msg)?;
+    buf.push(b'\n');
+    // Single write call so that a crash doesn't leave a partial message.
+    file.write_all(&buf)
+}
+
+impl<M> fmt::Debug for Recorder<M> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Recorder")
+            .field("file", &self.file)
+            .finish()
+    }
+}
+
+/// Read all messages recorded by a [`Recorder`] from the file at `path`.
+#[cfg(any(test, feature = "test"))]
+pub(crate) fn read_messages<M, P>(path: P) -> io::Result<Vec<M>>
+where
+    M: serde::de::DeserializeOwned,
+    P: AsRef<Path>,
+{
+    use std::io::{BufRead, BufReader};
+
+    let file = BufReader::new(File::open(path)?);
+    file.lines()
+        .filter(|line| !matches!(line, Ok(line) if line.is_empty()))
+        .map(|line| Ok(serde_json::from_str(&line?)?))
+        .collect()
+}
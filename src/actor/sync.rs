@@ -8,8 +8,7 @@ use std::thread::{self, Thread};
 #[cfg(any(test, feature = "test"))]
 use std::time::{Duration, Instant};
 
-use heph_inbox::Receiver;
-
+use crate::actor::inbox::Receiver;
 use crate::actor::{NoMessages, RecvError};
 use crate::trace::{self, Trace};
 
@@ -216,6 +215,16 @@ impl<M> SyncContext<M> {
         waker.block_on(self.inbox.recv()).ok_or(NoMessages)
     }
 
+    /// Returns the sequence number of the last message received, or `None` if
+    /// no message was received yet.
+    ///
+    /// See [`actor::Context::message_seq`] for more information.
+    ///
+    /// [`actor::Context::message_seq`]: crate::actor::Context::message_seq
+    pub fn message_seq(&self) -> Option<u64> {
+        self.inbox.last_seq()
+    }
+
     /// Block on a [`Future`] waiting for it's completion.
     ///
     /// # Limitations
@@ -6,17 +6,34 @@
 //! # Sending messages
 //!
 //! The primary function of actor references is sending messages. This can be
-//! done by using the [`send`] or [`try_send`] methods. These methods don't
-//! block, even the remote actor reference, but the methods don't provided a lot
-//! of guarantees. It doesn't even guarantee the order in which the messages
-//! arrive. What [`send`] does is asynchronously add the message to the queue of
-//! messages for the actor.
+//! done by using the [`try_send`] or [`send`] methods, which differ in what
+//! happens if the actor's inbox is full:
 //!
-//! In case of thread-local actor reference this can be done directly. But for
-//! thread-safe actor references the message must first be send across thread
-//! bounds before being added to the actor's message queue. Remote actor
-//! references even need to send this message across a network, a lot can go
-//! wrong here.
+//!  * [`try_send`] never waits, it returns an error if the actor's inbox is
+//!    full or if the actor is no longer running.
+//!  * [`send`] returns a [`Future`] that waits until the actor's inbox has
+//!    capacity for the message. It only returns an error if the actor is no
+//!    longer running.
+//!
+//! These semantics are the same for all actor references, whether the actor
+//! is thread-local, thread-safe or synchronous, and for mapped actor
+//! references (see [`ActorRef::map`]). Mapped actor references that fail to
+//! convert the message (see [`ActorRef::try_map`]) return an error for both
+//! methods. Generic code can thus rely on these semantics, regardless of the
+//! kind of actor reference it receives.
+//!
+//! Neither method guarantees that the message is handled by the actor, only
+//! that it's added to the actor's inbox. The actor could stop before
+//! receiving it.
+//!
+//! Actor references can be send across threads (if the message type is
+//! [`Send`]), this includes references to thread-local actors. The actor's
+//! inbox is shared between threads, a message send from another thread is
+//! added to it directly and the actor is woken by notifying the worker thread
+//! it runs on. So a thread-safe actor can send messages to a thread-local actor
+//! running on a different worker thread without any additional setup. Remote
+//! actor references even need to send this message across a network, a lot can
+//! go wrong here.
 //!
 //! If guarantees are needed that a message is received or processed the
 //! receiving actor should send back an acknowledgment that the message is
@@ -24,6 +41,7 @@
 //!
 //! [`send`]: ActorRef::send
 //! [`try_send`]: ActorRef::try_send
+//! [`Future`]: std::future::Future
 //!
 //! This example shows a simple actor that prints all the messages it receives.
 //!
@@ -115,11 +133,17 @@ use std::sync::atomic::{AtomicUsize, Ordering};
 use std::sync::Arc;
 use std::task::{self, Poll};
 
-use heph_inbox::{self as inbox, Sender};
+use heph_inbox as inbox;
 
+use crate::actor::inbox::{self as actor_inbox, Sender};
+use crate::actor::messages::{ActorStopped, InboxWatermark};
+
+pub mod receipt;
 pub mod rpc;
 #[doc(no_inline)]
-pub use rpc::{Rpc, RpcError, RpcMessage, RpcResponse};
+pub use receipt::{Delivered, ReceiptKind, Receipted, SendReceipt};
+#[doc(no_inline)]
+pub use rpc::{Caller, Canceled, Rpc, RpcError, RpcMessage, RpcResponse};
 
 /// Actor reference.
 ///
@@ -158,9 +182,13 @@ impl<M> ActorRef<M> {
         }
     }
 
-    /// Send a message to the actor.
+    /// Send a message to the actor, waiting for capacity in the actor's inbox.
     ///
-    /// See [Sending messages] and [`ActorRef::try_send`] for more details.
+    /// The returned [`Future`] completes once the message is added to the
+    /// actor's inbox, it returns an error if the actor is no longer running.
+    /// Use [`ActorRef::try_send`] to send a message without waiting.
+    ///
+    /// See [Sending messages] for more details.
     ///
     /// [Sending messages]: index.html#sending-messages
     ///
@@ -176,6 +204,14 @@ impl<M> ActorRef<M> {
         Msg: Into<M>,
     {
         use ActorRefKind::*;
+        #[cfg(any(test, feature = "test"))]
+        if crate::test::should_lose_msg() {
+            log::debug!("dropping message on purpose");
+            return SendValue {
+                kind: SendValueKind::Mapped(Box::pin(std::future::ready(Ok(())))),
+            };
+        }
+
         let msg = msg.into();
         SendValue {
             kind: match &self.kind {
@@ -185,12 +221,49 @@ impl<M> ActorRef<M> {
         }
     }
 
-    /// Attempt to send a message to the actor.
+    /// Send a message to the actor, skipping ahead of all other messages.
     ///
-    /// Some types of actor references can detect errors in sending a message,
-    /// however not all actor references can. This means that even if this
-    /// methods returns `Ok` it does **not** mean that the message is guaranteed
-    /// to be delivered to or handled by the actor.
+    /// This adds the message to the priority lane of the actor's inbox. Messages
+    /// in the priority lane are received before any message send using
+    /// [`ActorRef::send`] or [`ActorRef::try_send`], making it useful for control
+    /// messages, e.g. telling the actor to stop, which should not wait on a long
+    /// backlog of regular messages.
+    ///
+    /// Messages send using this method are received in the order in which they
+    /// are send, relative to other priority messages.
+    ///
+    /// # Notes
+    ///
+    /// The priority lane has a small, fixed capacity (of 8 messages) and is
+    /// meant for control messages only. If the priority lane is full, or the
+    /// actor is no longer running, this returns an error.
+    ///
+    /// Unlike [`ActorRef::send`] this doesn't return a [`Future`], as the
+    /// priority lane never waits for capacity to become available.
+    pub fn send_priority<Msg>(&self, msg: Msg) -> Result<(), SendError>
+    where
+        Msg: Into<M>,
+    {
+        use ActorRefKind::*;
+        #[cfg(any(test, feature = "test"))]
+        if crate::test::should_lose_msg() {
+            log::debug!("dropping message on purpose");
+            return Ok(());
+        }
+
+        let msg = msg.into();
+        match &self.kind {
+            Local(sender) => sender.try_send_priority(msg).map_err(|_| SendError),
+            Mapped(actor_ref) => actor_ref.mapped_send_priority(msg),
+        }
+    }
+
+    /// Attempt to send a message to the actor, without waiting.
+    ///
+    /// Returns an error if the actor's inbox is full or if the actor is no
+    /// longer running. Use [`ActorRef::send`] to wait for capacity in the
+    /// actor's inbox instead. Even if this methods returns `Ok` it does **not**
+    /// mean that the message is guaranteed to be handled by the actor.
     ///
     /// See [Sending messages] for more details.
     ///
@@ -229,6 +302,25 @@ impl<M> ActorRef<M> {
         Rpc::new(self, request)
     }
 
+    /// Send a message to the actor, returning a receipt once the message is
+    /// delivered.
+    ///
+    /// This returns a [`SendReceipt`] [`Future`] that resolves once the
+    /// message is added to the actor's inbox, or once the actor received the
+    /// message, depending on `kind`. Unlike an [RPC] the receiving actor
+    /// doesn't need to respond.
+    ///
+    /// See the [`receipt`] module for more details.
+    ///
+    /// [`Future`]: std::future::Future
+    /// [RPC]: ActorRef::rpc
+    pub fn send_with_receipt<'r, Msg>(&'r self, msg: Msg, kind: ReceiptKind) -> SendReceipt<'r, M>
+    where
+        M: From<Receipted<Msg>>,
+    {
+        SendReceipt::new(self, msg, kind)
+    }
+
     /// Change the message type of the actor reference.
     ///
     /// Before sending the message this will first change the message into a
@@ -245,6 +337,35 @@ impl<M> ActorRef<M> {
     ///
     /// Prefer to clone an existing mapped `ActorRef` over creating a new one as
     /// that can reuse the allocation mentioned above.
+    ///
+    /// # Examples
+    ///
+    /// Handing out a narrower message interface to other components, without
+    /// exposing the actor's full message type.
+    ///
+    /// ```
+    /// use heph::actor_ref::ActorRef;
+    ///
+    /// /// All messages our actor can handle.
+    /// enum Message {
+    ///     Add(usize),
+    ///     Reset,
+    /// }
+    ///
+    /// /// The only message other components are allowed to send.
+    /// struct Add(usize);
+    ///
+    /// impl From<Add> for Message {
+    ///     fn from(msg: Add) -> Message {
+    ///         Message::Add(msg.0)
+    ///     }
+    /// }
+    ///
+    /// fn public_ref(actor_ref: ActorRef<Message>) -> ActorRef<Add> {
+    ///     actor_ref.map()
+    /// }
+    /// # let _ = public_ref;
+    /// ```
     pub fn map<Msg>(self) -> ActorRef<Msg>
     where
         M: From<Msg> + 'static,
@@ -354,6 +475,161 @@ impl<M> ActorRef<M> {
         }
     }
 
+    /// Stop the actor.
+    ///
+    /// This asks the actor to stop, which works even if the actor doesn't
+    /// handle any messages, e.g. because it's stuck in a loop waiting on
+    /// something else. The actor is polled one last time, in which receiving a
+    /// message returns an error (as if all actor references were dropped),
+    /// giving it a chance to stop gracefully. After that the actor is dropped,
+    /// even if it didn't complete. The actor isn't restarted by its
+    /// supervisor. Actors [watching] the actor receive
+    /// [`StopReason::Terminated`].
+    ///
+    /// Synchronous actors are not dropped, but receiving a message will
+    /// return an error once this is called.
+    ///
+    /// [watching]: ActorRef::watch
+    /// [`StopReason::Terminated`]: crate::actor::messages::StopReason::Terminated
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use heph::actor_ref::ActorRef;
+    ///
+    /// fn stop_worker(worker: ActorRef<String>) {
+    ///     // Regardless of what the worker is doing, it will stop.
+    ///     worker.stop();
+    /// }
+    /// # drop(stop_worker);
+    /// ```
+    pub fn stop(&self) {
+        use ActorRefKind::*;
+        match &self.kind {
+            Local(sender) => sender.stop(),
+            Mapped(actor_ref) => actor_ref.stop(),
+        }
+    }
+
+    /// Watch the actor behind `other`.
+    ///
+    /// Once the other actor stops, either because it completed, returned an
+    /// error (and wasn't restarted by its supervisor) or panicked, an
+    /// [`ActorStopped`] message is send to this actor. If the other actor
+    /// already stopped the message is send immediately.
+    ///
+    /// Restarts of the other actor by its supervisor are not reported, only
+    /// the final stop.
+    ///
+    /// # Notes
+    ///
+    /// The [`ActorStopped`] message doesn't identify the stopped actor. To
+    /// watch multiple actors use [`ActorRef::map_fn`] to include an
+    /// identifier, before calling `watch`.
+    ///
+    /// The message is send using [`ActorRef::try_send`], so it's lost if
+    /// this actor's inbox is full at the time.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use heph::actor::messages::ActorStopped;
+    /// use heph::actor_ref::ActorRef;
+    /// # use heph::from_message;
+    ///
+    /// enum Message {
+    ///     Stopped(ActorStopped),
+    ///     // Other messages...
+    /// }
+    /// # from_message!(Message::Stopped(ActorStopped));
+    ///
+    /// fn watch_worker(watcher: &ActorRef<Message>, worker: &ActorRef<String>) {
+    ///     // Once the worker stops `watcher` receives a
+    ///     // `Message::Stopped` message.
+    ///     watcher.watch(worker);
+    /// }
+    /// # drop(watch_worker);
+    /// ```
+    pub fn watch<Msg>(&self, other: &ActorRef<Msg>)
+    where
+        M: From<ActorStopped> + 'static,
+    {
+        other.add_watcher(self.clone().map());
+    }
+
+    /// Add `watcher` to the watchers of the actor, see [`ActorRef::watch`].
+    fn add_watcher(&self, watcher: ActorRef<ActorStopped>) {
+        use ActorRefKind::*;
+        match &self.kind {
+            Local(sender) => sender.add_watcher(watcher),
+            Mapped(actor_ref) => actor_ref.add_watcher(watcher),
+        }
+    }
+
+    /// Watch the inbox of the actor of `other`, sending this actor an
+    /// [`InboxWatermark`] message once the number of messages in its inbox
+    /// crosses a watermark.
+    ///
+    /// Once the number of messages reaches `high` this actor receives
+    /// [`InboxWatermark::High`]. Once it drops to `low` afterwards this actor
+    /// receives [`InboxWatermark::Low`]. This allows a producing actor to
+    /// throttle itself before the inbox of the consuming actor is full.
+    ///
+    /// # Notes
+    ///
+    /// Only the regular inbox is watched, messages sent using
+    /// [`ActorRef::send_priority`] are not counted.
+    ///
+    /// The messages are sent using [`ActorRef::try_send`], so they're lost if
+    /// this actor's inbox is full at the time.
+    ///
+    /// # Panics
+    ///
+    /// This panics if `low` is not smaller than `high`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use heph::actor::messages::InboxWatermark;
+    /// use heph::actor_ref::ActorRef;
+    /// # use heph::from_message;
+    ///
+    /// enum Message {
+    ///     Watermark(InboxWatermark),
+    ///     // Other messages...
+    /// }
+    /// # from_message!(Message::Watermark(InboxWatermark));
+    ///
+    /// fn watch_consumer(producer: &ActorRef<Message>, consumer: &ActorRef<String>) {
+    ///     // Once the consumer has 6 or more messages in its inbox `producer`
+    ///     // receives a `Message::Watermark(InboxWatermark::High)` message.
+    ///     // Once the inbox drops to 2 messages it receives a
+    ///     // `Message::Watermark(InboxWatermark::Low)` message.
+    ///     producer.watch_inbox(consumer, 2, 6);
+    /// }
+    /// # drop(watch_consumer);
+    /// ```
+    pub fn watch_inbox<Msg>(&self, other: &ActorRef<Msg>, low: usize, high: usize)
+    where
+        M: From<InboxWatermark> + 'static,
+    {
+        assert!(
+            low < high,
+            "low watermark must be smaller than the high watermark"
+        );
+        other.add_inbox_watcher(self.clone().map(), low, high);
+    }
+
+    /// Add `watcher` to the watchers of the actor's inbox, see
+    /// [`ActorRef::watch_inbox`].
+    fn add_inbox_watcher(&self, watcher: ActorRef<InboxWatermark>, low: usize, high: usize) {
+        use ActorRefKind::*;
+        match &self.kind {
+            Local(sender) => sender.add_inbox_watcher(watcher, low, high),
+            Mapped(actor_ref) => actor_ref.add_inbox_watcher(watcher, low, high),
+        }
+    }
+
     /// Returns `true` if the actor to which this reference sends to is still
     /// connected.
     ///
@@ -417,6 +693,9 @@ trait MappedActorRef<M> {
     /// Same as [`ActorRef::try_send`] but converts the message first.
     fn try_mapped_send(&self, msg: M) -> Result<(), SendError>;
 
+    /// Same as [`ActorRef::send_priority`] but converts the message first.
+    fn mapped_send_priority(&self, msg: M) -> Result<(), SendError>;
+
     fn mapped_send<'r, 'fut>(
         &'r self,
         msg: M,
@@ -429,6 +708,15 @@ trait MappedActorRef<M> {
     where
         'r: 'fut;
 
+    /// See [`ActorRef::watch`].
+    fn add_watcher(&self, watcher: ActorRef<ActorStopped>);
+
+    /// See [`ActorRef::watch_inbox`].
+    fn add_inbox_watcher(&self, watcher: ActorRef<InboxWatermark>, low: usize, high: usize);
+
+    /// See [`ActorRef::stop`].
+    fn stop(&self);
+
     fn is_connected(&self) -> bool;
 
     fn id(&self) -> inbox::Id;
@@ -444,6 +732,12 @@ where
             .and_then(|msg| self.try_send(msg))
     }
 
+    fn mapped_send_priority(&self, msg: Msg) -> Result<(), SendError> {
+        M::try_from(msg)
+            .map_err(|_| SendError)
+            .and_then(|msg| self.send_priority(msg))
+    }
+
     fn mapped_send<'r, 'fut>(
         &'r self,
         msg: Msg,
@@ -466,6 +760,18 @@ where
         Box::pin(self.join())
     }
 
+    fn add_watcher(&self, watcher: ActorRef<ActorStopped>) {
+        self.add_watcher(watcher);
+    }
+
+    fn add_inbox_watcher(&self, watcher: ActorRef<InboxWatermark>, low: usize, high: usize) {
+        self.add_inbox_watcher(watcher, low, high);
+    }
+
+    fn stop(&self) {
+        self.stop();
+    }
+
     fn is_connected(&self) -> bool {
         self.is_connected()
     }
@@ -492,6 +798,13 @@ where
         }
     }
 
+    fn mapped_send_priority(&self, msg: Msg) -> Result<(), SendError> {
+        match (self.map)(msg) {
+            Ok(msg) => self.actor_ref.send_priority(msg),
+            Err(..) => Err(SendError),
+        }
+    }
+
     fn mapped_send<'r, 'fut>(
         &'r self,
         msg: Msg,
@@ -514,6 +827,18 @@ where
         Box::pin(self.actor_ref.join())
     }
 
+    fn add_watcher(&self, watcher: ActorRef<ActorStopped>) {
+        self.actor_ref.add_watcher(watcher);
+    }
+
+    fn add_inbox_watcher(&self, watcher: ActorRef<InboxWatermark>, low: usize, high: usize) {
+        self.actor_ref.add_inbox_watcher(watcher, low, high);
+    }
+
+    fn stop(&self) {
+        self.actor_ref.stop();
+    }
+
     fn is_connected(&self) -> bool {
         self.actor_ref.is_connected()
     }
@@ -554,7 +879,7 @@ pub struct SendValue<'r, M> {
 }
 
 enum SendValueKind<'r, M> {
-    Local(inbox::SendValue<'r, M>),
+    Local(actor_inbox::SendValue<'r, M>),
     Mapped(Pin<Box<dyn Future<Output = Result<(), SendError>> + 'r>>),
 }
 
@@ -617,7 +942,7 @@ pub struct Join<'r, M> {
 }
 
 enum JoinKind<'r, M> {
-    Local(inbox::Join<'r, M>),
+    Local(actor_inbox::Join<'r, M>),
     Mapped(Pin<Box<dyn Future<Output = ()> + 'r>>),
 }
 
@@ -766,14 +1091,49 @@ impl<M> ActorGroup<M> {
     /// `Msg` it might be worthwhile to call `msg.into()` before calling this
     /// method.
     ///
-    /// This only returns an error if the group is empty, otherwise this will
-    /// always return `Ok(())`.
+    /// When delivering to one actor the actors are selected in a round-robin
+    /// fashion. If sending to the selected actor fails, e.g. because it's no
+    /// longer running, the message is sent to the next actor in the group.
+    /// Use [`ActorGroup::remove_disconnected`] to remove actors that are no
+    /// longer running from the group.
+    ///
+    /// When delivering to all actors this only returns an error if the group
+    /// is empty, otherwise this will always return `Ok(())`. When delivering
+    /// to one actor this returns an error if the message couldn't be sent to
+    /// any of the actors.
     ///
     /// See [Sending messages] for more details.
     ///
     /// [`try_send`]: ActorRef::try_send
     /// [Sending messages]: index.html#sending-messages
     pub fn try_send<Msg>(&self, msg: Msg, delivery: Delivery) -> Result<(), SendError>
+    where
+        Msg: Into<M> + Clone,
+    {
+        self.try_send_using(msg, delivery, ActorRef::try_send)
+    }
+
+    /// Attempts to send a message to the priority lane of the actors in the
+    /// group.
+    ///
+    /// This works the same way as [`ActorGroup::try_send`], but uses
+    /// [`ActorRef::send_priority`] to send the message, making it useful for
+    /// control messages.
+    pub fn try_send_priority<Msg>(&self, msg: Msg, delivery: Delivery) -> Result<(), SendError>
+    where
+        Msg: Into<M> + Clone,
+    {
+        self.try_send_using(msg, delivery, ActorRef::send_priority)
+    }
+
+    /// Implementation of [`ActorGroup::try_send`] and
+    /// [`ActorGroup::try_send_priority`].
+    fn try_send_using<Msg>(
+        &self,
+        msg: Msg,
+        delivery: Delivery,
+        send: fn(&ActorRef<M>, Msg) -> Result<(), SendError>,
+    ) -> Result<(), SendError>
     where
         Msg: Into<M> + Clone,
     {
@@ -784,17 +1144,24 @@ impl<M> ActorGroup<M> {
         match delivery {
             Delivery::ToAll => {
                 for actor_ref in &self.actor_refs {
-                    let _ = actor_ref.try_send(msg.clone());
+                    let _ = send(actor_ref, msg.clone());
                 }
                 Ok(())
             }
             Delivery::ToOne => {
                 // Safety: this needs to sync itself.
                 // NOTE: this wraps around on overflow.
-                let idx = self.send_next.fetch_add(1, Ordering::AcqRel) % self.actor_refs.len();
-                let actor_ref = &self.actor_refs[idx];
-                // TODO: try to send it to another actor on send failure?
-                actor_ref.try_send(msg)
+                let start = self.send_next.fetch_add(1, Ordering::AcqRel);
+                let len = self.actor_refs.len();
+                // If sending fails, e.g. because the actor stopped, we try the
+                // next actor in the group.
+                for n in 0..len {
+                    let actor_ref = &self.actor_refs[start.wrapping_add(n) % len];
+                    if send(actor_ref, msg.clone()).is_ok() {
+                        return Ok(());
+                    }
+                }
+                Err(SendError)
             }
         }
     }
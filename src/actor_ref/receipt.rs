@@ -0,0 +1,203 @@
+//! Types related to send receipts, see [`ActorRef::send_with_receipt`].
+//!
+//! A send receipt gives the sending actor visibility into the delivery of a
+//! message, without requiring a response like [RPC] does. Depending on the
+//! [`ReceiptKind`] the receipt is given once the message is added to the
+//! actor's inbox ([`ReceiptKind::Enqueued`]) or once the receiving actor
+//! received the message ([`ReceiptKind::Received`]).
+//!
+//! [RPC]: crate::actor_ref::rpc
+//!
+//! To support send receipts the receiving actor needs to implement
+//! [`From`]`<`[`Receipted`]`<Msg>>`, where `Msg` is the type of the message. To
+//! acknowledge that the message was received the actor must call
+//! [`Receipted::into_inner`]. If the `Receipted` message is dropped before that
+//! the message is considered not delivered.
+//!
+//! # Examples
+//!
+//! ```
+//! # #![feature(never_type)]
+//! #
+//! use heph::actor;
+//! use heph::actor_ref::{ActorRef, ReceiptKind, Receipted};
+//! use heph::rt::{self, ThreadLocal};
+//!
+//! /// Message type for [`logger`].
+//! struct Log(Receipted<String>);
+//!
+//! /// Required to support send receipts.
+//! impl From<Receipted<String>> for Log {
+//!     fn from(msg: Receipted<String>) -> Log {
+//!         Log(msg)
+//!     }
+//! }
+//!
+//! /// Receiving actor.
+//! async fn logger(mut ctx: actor::Context<Log, ThreadLocal>) {
+//!     while let Ok(Log(msg)) = ctx.receive_next().await {
+//!         // This acknowledges the receipt of the message.
+//!         let line = msg.into_inner();
+//!         println!("{}", line);
+//!     }
+//! }
+//!
+//! /// Sending actor.
+//! async fn sender(_: actor::Context<!, ThreadLocal>, actor_ref: ActorRef<Log>) {
+//!     let msg = "Hello world".to_owned();
+//!     let receipt = actor_ref.send_with_receipt(msg, ReceiptKind::Received).await;
+//! #   assert!(receipt.is_ok());
+//!     match receipt {
+//!         Ok(_) => println!("message was received"),
+//!         Err(err) => eprintln!("message wasn't delivered: {}", err),
+//!     }
+//! }
+//!
+//! # fn main() -> Result<(), rt::Error> {
+//! #    use heph::rt::Runtime;
+//! #    use heph::spawn::ActorOptions;
+//! #    use heph::supervisor::NoSupervisor;
+//! #    let mut runtime = Runtime::new()?;
+//! #    runtime.run_on_workers(|mut runtime_ref| -> Result<(), !> {
+//! #        let logger = logger as fn(_) -> _;
+//! #        let actor_ref = runtime_ref.spawn_local(NoSupervisor, logger, (), ActorOptions::default());
+//! #
+//! #        let sender = sender as fn(_, _) -> _;
+//! #        runtime_ref.spawn_local(NoSupervisor, sender, actor_ref, ActorOptions::default());
+//! #        Ok(())
+//! #    })?;
+//! #    runtime.start()
+//! # }
+//! ```
+
+use std::fmt;
+use std::future::Future;
+use std::pin::Pin;
+use std::task::{self, Poll};
+
+use heph_inbox::oneshot::{new_oneshot, RecvOnce, Sender};
+
+use crate::actor_ref::{ActorRef, SendError, SendValue};
+
+/// When to give a send receipt, see [`ActorRef::send_with_receipt`].
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum ReceiptKind {
+    /// Once the message is added to the actor's inbox.
+    Enqueued,
+    /// Once the actor received the message, see [`Receipted::into_inner`].
+    Received,
+}
+
+/// Receipt of a delivered message, returned by [`SendReceipt`].
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct Delivered;
+
+/// [`Future`] behind [`ActorRef::send_with_receipt`].
+#[must_use = "futures do nothing unless you `.await` or poll them"]
+pub struct SendReceipt<'r, M> {
+    send: Option<SendValue<'r, M>>,
+    /// `None` if the receipt is given once the message is enqueued.
+    recv: Option<RecvOnce<()>>,
+}
+
+impl<'r, M> SendReceipt<'r, M> {
+    /// Create a new `SendReceipt`.
+    pub(super) fn new<Msg>(
+        actor_ref: &'r ActorRef<M>,
+        msg: Msg,
+        kind: ReceiptKind,
+    ) -> SendReceipt<'r, M>
+    where
+        M: From<Receipted<Msg>>,
+    {
+        let (receipt, recv) = match kind {
+            ReceiptKind::Enqueued => (None, None),
+            ReceiptKind::Received => {
+                let (sender, receiver) = new_oneshot();
+                (Some(sender), Some(receiver.recv_once()))
+            }
+        };
+        let send = actor_ref.send(Receipted { msg, receipt });
+        SendReceipt {
+            send: Some(send),
+            recv,
+        }
+    }
+}
+
+impl<'r, M> Future for SendReceipt<'r, M> {
+    type Output = Result<Delivered, SendError>;
+
+    #[track_caller]
+    fn poll(mut self: Pin<&mut Self>, ctx: &mut task::Context<'_>) -> Poll<Self::Output> {
+        // Safety: we're not moving `send` so this is safe.
+        let send = unsafe { self.as_mut().map_unchecked_mut(|s| &mut s.send) }.as_pin_mut();
+        if let Some(send) = send {
+            match send.poll(ctx) {
+                Poll::Ready(Ok(())) => {}
+                Poll::Ready(Err(err)) => return Poll::Ready(Err(err)),
+                Poll::Pending => return Poll::Pending,
+            }
+            // Don't take this branch again.
+            // Safety: we're not moving `send` so this is safe.
+            unsafe { self.as_mut().map_unchecked_mut(|s| &mut s.send) }.set(None);
+        }
+
+        // Safety: we're not moving `recv` so this is safe.
+        match unsafe { self.map_unchecked_mut(|s| &mut s.recv) }.as_pin_mut() {
+            Some(recv) => match recv.poll(ctx) {
+                Poll::Ready(Some(())) => Poll::Ready(Ok(Delivered)),
+                // Message was dropped before it was received.
+                Poll::Ready(None) => Poll::Ready(Err(SendError)),
+                Poll::Pending => Poll::Pending,
+            },
+            // Message was enqueued.
+            None => Poll::Ready(Ok(Delivered)),
+        }
+    }
+}
+
+impl<'r, M> fmt::Debug for SendReceipt<'r, M> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SendReceipt")
+            .field("send", &self.send)
+            .field("recv", &self.recv)
+            .finish()
+    }
+}
+
+/// Message sent using [`ActorRef::send_with_receipt`].
+///
+/// Use [`Receipted::into_inner`] to get the message, acknowledging its
+/// receipt.
+pub struct Receipted<Msg> {
+    msg: Msg,
+    /// `None` if no receipt is required (or it was already given).
+    receipt: Option<Sender<()>>,
+}
+
+impl<Msg> Receipted<Msg> {
+    /// Returns a reference to the message, **without** acknowledging its
+    /// receipt.
+    pub const fn get_ref(&self) -> &Msg {
+        &self.msg
+    }
+
+    /// Returns the message, acknowledging its receipt.
+    pub fn into_inner(mut self) -> Msg {
+        if let Some(receipt) = self.receipt.take() {
+            // If the sender is no longer interested that's fine.
+            let _ = receipt.try_send(());
+        }
+        self.msg
+    }
+}
+
+impl<Msg> fmt::Debug for Receipted<Msg>
+where
+    Msg: fmt::Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Receipted").field("msg", &self.msg).finish()
+    }
+}
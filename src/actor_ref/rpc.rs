@@ -16,6 +16,16 @@
 //!
 //! [`from_message`]: crate::from_message
 //!
+//! The receiving actor can get information about the actor that made the call
+//! using [`RpcMessage::caller`], such as its name and deadline. This can be
+//! used to implement per caller quotas or to skip requests of which the
+//! caller's deadline already passed.
+//!
+//! If the caller is no longer interested in the response, i.e. it dropped the
+//! [`Rpc`] future, e.g. due to a timeout, the RPC is canceled. The receiving
+//! actor can check this using [`RpcResponse::is_canceled`], or wait for it
+//! using [`RpcResponse::canceled`], to stop expensive work early.
+//!
 //! # Examples
 //!
 //! Using RPC to communicate with another actor.
@@ -160,11 +170,15 @@ use std::error::Error;
 use std::fmt;
 use std::future::Future;
 use std::pin::Pin;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
 use std::task::{self, Poll};
+use std::time::Instant;
 
 use heph_inbox::oneshot::{new_oneshot, RecvOnce, Sender};
 
 use crate::actor_ref::{ActorRef, SendError, SendValue};
+use crate::rt::{self, ProcessId};
 
 /// [`Future`] that resolves to a Remote Procedure Call (RPC) response.
 ///
@@ -174,6 +188,7 @@ use crate::actor_ref::{ActorRef, SendError, SendValue};
 pub struct Rpc<'r, M, Res> {
     send: Option<SendValue<'r, M>>,
     recv: RecvOnce<Res>,
+    cancel: Arc<Cancel>,
 }
 
 impl<'r, M, Res> Rpc<'r, M, Res> {
@@ -183,12 +198,21 @@ impl<'r, M, Res> Rpc<'r, M, Res> {
         M: From<RpcMessage<Req, Res>>,
     {
         let (sender, receiver) = new_oneshot();
-        let response = RpcResponse { sender };
+        let cancel = Arc::new(Cancel {
+            canceled: AtomicBool::new(false),
+            waker: Mutex::new(None),
+        });
+        let response = RpcResponse {
+            sender,
+            caller: rt::current_caller(),
+            cancel: cancel.clone(),
+        };
         let msg = RpcMessage { request, response };
         let send = actor_ref.send(msg);
         Rpc {
             send: Some(send),
             recv: receiver.recv_once(),
+            cancel,
         }
     }
 }
@@ -220,6 +244,37 @@ impl<'r, M, Res> Future for Rpc<'r, M, Res> {
     }
 }
 
+impl<'r, M, Res> Drop for Rpc<'r, M, Res> {
+    fn drop(&mut self) {
+        // NOTE: if we already received the response the receiving actor no
+        // longer has the `RpcResponse`, so it won't see the cancellation.
+        self.cancel.cancel();
+    }
+}
+
+/// Cancellation state shared between [`Rpc`] and [`RpcResponse`].
+#[derive(Debug)]
+struct Cancel {
+    /// Set to `true` once the [`Rpc`] is dropped.
+    canceled: AtomicBool,
+    /// Waker registered by the [`Canceled`] future.
+    waker: Mutex<Option<task::Waker>>,
+}
+
+impl Cancel {
+    /// Mark the RPC as canceled, waking the [`Canceled`] future (if any).
+    fn cancel(&self) {
+        self.canceled.store(true, Ordering::Release);
+        if let Some(waker) = self.waker.lock().unwrap().take() {
+            waker.wake();
+        }
+    }
+
+    fn is_canceled(&self) -> bool {
+        self.canceled.load(Ordering::Acquire)
+    }
+}
+
 /// Error returned by [`Rpc`].
 #[derive(Copy, Clone, Debug, Eq, PartialEq)]
 pub enum RpcError {
@@ -288,12 +343,24 @@ impl<Req, Res> RpcMessage<Req, Res> {
             Ok(())
         }
     }
+
+    /// Returns information about the caller, see [`Caller`].
+    ///
+    /// Returns `None` if the RPC wasn't made from within an actor or future
+    /// running on the [`Runtime`], e.g. from a synchronous actor or a test.
+    ///
+    /// [`Runtime`]: crate::Runtime
+    pub const fn caller(&self) -> Option<&Caller> {
+        self.response.caller()
+    }
 }
 
 /// Structure to respond to an [`Rpc`] request.
 #[derive(Debug)]
 pub struct RpcResponse<Res> {
     sender: Sender<Res>,
+    caller: Option<Caller>,
+    cancel: Arc<Cancel>,
 }
 
 impl<Res> RpcResponse<Res> {
@@ -312,4 +379,106 @@ impl<Res> RpcResponse<Res> {
     pub fn is_connected(&self) -> bool {
         self.sender.is_connected()
     }
+
+    /// Returns information about the caller, see [`RpcMessage::caller`].
+    pub const fn caller(&self) -> Option<&Caller> {
+        self.caller.as_ref()
+    }
+
+    /// Returns `true` if the RPC was canceled, i.e. the caller dropped the
+    /// [`Rpc`] future and is no longer interested in the response.
+    pub fn is_canceled(&self) -> bool {
+        self.cancel.is_canceled()
+    }
+
+    /// Returns a [`Future`] that completes once the RPC is canceled, see
+    /// [`RpcResponse::is_canceled`].
+    ///
+    /// This can be used to stop (expensive) work once the caller is no longer
+    /// interested in the response.
+    pub fn canceled<'r>(&'r self) -> Canceled<'r, Res> {
+        Canceled { response: self }
+    }
+}
+
+/// [`Future`] that completes once an RPC is canceled.
+///
+/// Created by [`RpcResponse::canceled`].
+#[derive(Debug)]
+#[must_use = "futures do nothing unless you `.await` or poll them"]
+pub struct Canceled<'r, Res> {
+    response: &'r RpcResponse<Res>,
+}
+
+impl<'r, Res> Future for Canceled<'r, Res> {
+    type Output = ();
+
+    fn poll(self: Pin<&mut Self>, ctx: &mut task::Context<'_>) -> Poll<Self::Output> {
+        let cancel = &*self.response.cancel;
+        if cancel.is_canceled() {
+            return Poll::Ready(());
+        }
+        *cancel.waker.lock().unwrap() = Some(ctx.waker().clone());
+        // Check again in case the RPC was canceled while we were registering
+        // our waker.
+        if cancel.is_canceled() {
+            Poll::Ready(())
+        } else {
+            Poll::Pending
+        }
+    }
+}
+
+/// Information about the process that made an RPC.
+///
+/// See [`RpcMessage::caller`].
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct Caller {
+    pid: ProcessId,
+    name: &'static str,
+    deadline: Option<Instant>,
+}
+
+impl Caller {
+    pub(crate) const fn new(
+        pid: ProcessId,
+        name: &'static str,
+        deadline: Option<Instant>,
+    ) -> Caller {
+        Caller {
+            pid,
+            name,
+            deadline,
+        }
+    }
+
+    /// Returns the process id of the caller.
+    pub const fn pid(&self) -> ProcessId {
+        self.pid
+    }
+
+    /// Returns the name of the caller, e.g. the name of the actor.
+    pub const fn name(&self) -> &'static str {
+        self.name
+    }
+
+    /// Returns the deadline of the caller at the time it made the call, if
+    /// any.
+    ///
+    /// This is only set if deadline scheduling is enabled, see
+    /// [`actor::Context::set_deadline`].
+    ///
+    /// [`actor::Context::set_deadline`]: crate::actor::Context::set_deadline
+    pub const fn deadline(&self) -> Option<Instant> {
+        self.deadline
+    }
+
+    /// Returns `true` if the deadline of the caller has passed, `false` if it
+    /// hasn't or if the caller doesn't have a deadline.
+    ///
+    /// A caller is unlikely to be interested in a response after its deadline
+    /// has passed, so the request could be skipped.
+    pub fn deadline_passed(&self) -> bool {
+        matches!(self.deadline, Some(deadline) if deadline <= Instant::now())
+    }
 }
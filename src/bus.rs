@@ -0,0 +1,195 @@
+//! Topic based publish-subscribe event bus.
+//!
+//! The [`Bus`] allows actors to publish messages to a topic, without knowing
+//! which actors are interested in them. Actors subscribe to a topic using
+//! [`actor::Context::subscribe`] and receive all messages published to that
+//! topic in their inbox. Messages are published using
+//! [`actor::Context::publish`], or using the `Bus` directly, which is returned
+//! by [`RuntimeRef::bus`] and [`ThreadSafe::bus`].
+//!
+//! Topics are typed, i.e. subscribing to the `"events"` topic with a message
+//! type `A` doesn't receive messages of type `B` published to the same
+//! `"events"` topic.
+//!
+//! Every runtime has a single bus, shared between all worker threads, so
+//! messages are delivered to both thread-local and thread-safe actors.
+//!
+//! Actors are automatically unsubscribed once they stop, i.e. once all their
+//! messages can no longer be received. Actors that are restarted by their
+//! supervisor remain subscribed.
+//!
+//! [`actor::Context::subscribe`]: crate::actor::Context::subscribe
+//! [`actor::Context::publish`]: crate::actor::Context::publish
+//! [`RuntimeRef::bus`]: crate::rt::RuntimeRef::bus
+//! [`ThreadSafe::bus`]: crate::rt::ThreadSafe::bus
+//!
+//! # Examples
+//!
+//! ```
+//! #![feature(never_type)]
+//!
+//! use heph::actor;
+//! use heph::rt::ThreadLocal;
+//!
+//! /// Message published to the `"temperature"` topic.
+//! #[derive(Clone)]
+//! struct Temperature(f32);
+//!
+//! /// Actor that publishes temperature measurements.
+//! async fn sensor(mut ctx: actor::Context<!, ThreadLocal>) {
+//!     let measurement = Temperature(21.5);
+//!     let subscribers = ctx.publish("temperature", measurement);
+//!     println!("send measurement to {} subscribers", subscribers);
+//! }
+//!
+//! /// Actor that prints all temperature measurements.
+//! async fn display(mut ctx: actor::Context<Temperature, ThreadLocal>) {
+//!     ctx.subscribe::<Temperature>("temperature");
+//!     while let Ok(Temperature(temperature)) = ctx.receive_next().await {
+//!         println!("temperature: {}°C", temperature);
+//!     }
+//! }
+//! # drop((sensor, display)); // Silence dead code warnings.
+//! ```
+
+use std::any::{Any, TypeId};
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::Mutex;
+
+use log::trace;
+
+use crate::actor_ref::ActorRef;
+
+/// Topic based publish-subscribe event bus.
+///
+/// See the [module documentation] for more information.
+///
+/// [module documentation]: crate::bus
+pub struct Bus {
+    /// Topics per message type, [`TypeId`] of `T` -> [`Topics<T>`].
+    topics: Mutex<HashMap<TypeId, Box<dyn Any + Send>>>,
+}
+
+/// Subscribers of a single message type, per topic.
+type Topics<T> = HashMap<String, Vec<ActorRef<T>>>;
+
+impl Bus {
+    /// Create a new empty `Bus`.
+    pub(crate) fn new() -> Bus {
+        Bus {
+            topics: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Subscribe `subscriber` to `topic`.
+    ///
+    /// All messages of type `T` published to `topic` are send to
+    /// `subscriber`. Subscribing the same actor twice has no effect.
+    pub fn subscribe<T>(&self, topic: &str, subscriber: ActorRef<T>)
+    where
+        T: Send + 'static,
+    {
+        self.with_topics(|topics: &mut Topics<T>| {
+            let subscribers = topics.entry(topic.to_owned()).or_default();
+            // Good moment to remove the actors that stopped.
+            subscribers.retain(ActorRef::is_connected);
+            if !subscribers.iter().any(|s| s.sends_to(&subscriber)) {
+                subscribers.push(subscriber);
+            }
+        });
+    }
+
+    /// Unsubscribe `subscriber` from `topic`.
+    ///
+    /// Returns `true` if `subscriber` was subscribed to `topic`.
+    pub fn unsubscribe<T>(&self, topic: &str, subscriber: &ActorRef<T>) -> bool
+    where
+        T: Send + 'static,
+    {
+        self.with_topics(|topics: &mut Topics<T>| match topics.get_mut(topic) {
+            Some(subscribers) => {
+                let n = subscribers.len();
+                subscribers.retain(|s| !s.sends_to(subscriber));
+                let removed = subscribers.len() != n;
+                if subscribers.is_empty() {
+                    let _ = topics.remove(topic);
+                }
+                removed
+            }
+            None => false,
+        })
+    }
+
+    /// Publish `msg` to all actors subscribed to `topic`.
+    ///
+    /// Sending the message to the subscribers doesn't block, if an actor's
+    /// inbox is full it won't receive the message. Returns the number of
+    /// actors the message was send to.
+    pub fn publish<T>(&self, topic: &str, msg: T) -> usize
+    where
+        T: Clone + Send + 'static,
+    {
+        self.with_topics(|topics: &mut Topics<T>| {
+            let subscribers = match topics.get_mut(topic) {
+                Some(subscribers) => subscribers,
+                None => return 0,
+            };
+            subscribers.retain(ActorRef::is_connected);
+            if subscribers.is_empty() {
+                let _ = topics.remove(topic);
+                return 0;
+            }
+
+            let mut send = 0;
+            // Don't clone the message for the last subscriber.
+            let (last, rest) = subscribers.split_last().unwrap();
+            for subscriber in rest {
+                if subscriber.try_send(msg.clone()).is_ok() {
+                    send += 1;
+                } else {
+                    trace!("failed to publish message: topic={}", topic);
+                }
+            }
+            if last.try_send(msg).is_ok() {
+                send += 1;
+            } else {
+                trace!("failed to publish message: topic={}", topic);
+            }
+            send
+        })
+    }
+
+    /// Returns the number of actors subscribed to `topic` for messages of type
+    /// `T`.
+    pub fn subscribers<T>(&self, topic: &str) -> usize
+    where
+        T: Send + 'static,
+    {
+        self.with_topics(|topics: &mut Topics<T>| {
+            topics.get(topic).map_or(0, |subscribers| {
+                subscribers.iter().filter(|s| s.is_connected()).count()
+            })
+        })
+    }
+
+    /// Call `f` with the topics for message type `T`.
+    fn with_topics<T, F, R>(&self, f: F) -> R
+    where
+        T: Send + 'static,
+        F: FnOnce(&mut Topics<T>) -> R,
+    {
+        let mut all_topics = self.topics.lock().unwrap();
+        let topics = all_topics
+            .entry(TypeId::of::<T>())
+            .or_insert_with(|| Box::new(Topics::<T>::new()));
+        // We only insert `Topics<T>` for the `TypeId` of `T`.
+        f(topics.downcast_mut().unwrap())
+    }
+}
+
+impl fmt::Debug for Bus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("Bus")
+    }
+}
@@ -47,7 +47,7 @@ pub trait Bytes {
 
     /// Returns `true` if the buffer has spare capacity.
     fn has_spare_capacity(&self) -> bool {
-        self.spare_capacity() == 0
+        self.spare_capacity() != 0
     }
 
     /// Update the length of the byte slice, marking `n` bytes as initialised.
@@ -242,11 +242,12 @@ impl<'a> DerefMut for MaybeUninitSlice<'a> {
 
 /// Trait to make easier to work with uninitialised buffers using vectored I/O.
 ///
-/// This trait is implemented for arrays and tuples. When all of buffers are
-/// *homogeneous*, i.e. of the same type, the array implementation is the
-/// easiest to use along side with the [`Bytes`] trait. If however the buffers
-/// are *heterogeneous*, i.e. of different types, the tuple implementation can
-/// be used. See the examples below.
+/// This trait is implemented for arrays, vectors and tuples. When all of
+/// buffers are *homogeneous*, i.e. of the same type, the array implementation
+/// is the easiest to use along side with the [`Bytes`] trait. If the number of
+/// buffers isn't known at compile time the vector implementation can be used.
+/// If however the buffers are *heterogeneous*, i.e. of different types, the
+/// tuple implementation can be used. See the examples below.
 ///
 /// # Examples
 ///
@@ -391,7 +392,7 @@ pub trait BytesVectored {
 
     /// Returns `true` if (one of) the buffers has spare capacity.
     fn has_spare_capacity(&self) -> bool {
-        self.spare_capacity() == 0
+        self.spare_capacity() != 0
     }
 
     /// Update the length of the buffers in the slice.
@@ -486,6 +487,44 @@ where
     }
 }
 
+/// The implementation for `Vec<B>` can be used for a variable number of
+/// buffers, e.g. a rope of `Vec<u8>` chunks. Note however that calling
+/// [`as_bufs`] allocates, as the number of buffers isn't known up front.
+///
+/// [`as_bufs`]: BytesVectored::as_bufs
+impl<B> BytesVectored for Vec<B>
+where
+    B: Bytes,
+{
+    type Bufs<'b> = Vec<MaybeUninitSlice<'b>>;
+
+    fn as_bufs<'b>(&'b mut self) -> Self::Bufs<'b> {
+        self.iter_mut()
+            .map(|buf| MaybeUninitSlice::new(buf.as_bytes()))
+            .collect()
+    }
+
+    fn spare_capacity(&self) -> usize {
+        self.iter().map(Bytes::spare_capacity).sum()
+    }
+
+    fn has_spare_capacity(&self) -> bool {
+        self.iter().any(Bytes::has_spare_capacity)
+    }
+
+    unsafe fn update_lengths(&mut self, n: usize) {
+        let mut left = n;
+        for buf in self.iter_mut() {
+            let n = min(left, buf.spare_capacity());
+            buf.update_length(n);
+            left -= n;
+            if left == 0 {
+                return;
+            }
+        }
+    }
+}
+
 macro_rules! impl_vectored_bytes_tuple {
     ( $N: tt : $( $t: ident $idx: tt ),+ ) => {
         impl<$( $t ),+> BytesVectored for ( $( $t ),+ )
@@ -0,0 +1,292 @@
+//! Runtime managed configuration.
+//!
+//! Configuration values are stored in the runtime, one value per type, so all
+//! actors have access to the same configuration. A configuration value is
+//! loaded at startup using [`Runtime::load_config`], which runs the provided
+//! loader function once and keeps it around to reload the configuration
+//! later. The current value can be retrieved using [`RuntimeRef::config`] (or
+//! [`ThreadSafe::config`]).
+//!
+//! Reloading the configuration is done using [`RuntimeRef::reload_config`],
+//! which runs all loaders again, or by setting a new value directly using
+//! [`RuntimeRef::update_config`]. In both cases a [`ConfigUpdated`] message is
+//! published on the runtime's [event bus] to the [`TOPIC`] topic. Actors that
+//! want to handle configuration changes can subscribe to it using
+//! [`actor::Context::subscribe`].
+//!
+//! [`Runtime::load_config`]: crate::rt::Runtime::load_config
+//! [`RuntimeRef::config`]: crate::rt::RuntimeRef::config
+//! [`ThreadSafe::config`]: crate::rt::ThreadSafe::config
+//! [`RuntimeRef::reload_config`]: crate::rt::RuntimeRef::reload_config
+//! [`RuntimeRef::update_config`]: crate::rt::RuntimeRef::update_config
+//! [event bus]: crate::bus
+//! [`actor::Context::subscribe`]: crate::actor::Context::subscribe
+//!
+//! # Notes
+//!
+//! The runtime doesn't reload the configuration by itself. A common pattern is
+//! to reload it once the process receives a signal, see the example below.
+//!
+//! # Examples
+//!
+//! ```
+//! #![feature(never_type)]
+//!
+//! use heph::actor;
+//! use heph::config::{self, ConfigUpdated};
+//! use heph::rt::{self, Runtime, RuntimeRef, Signal, ThreadLocal};
+//! use heph::spawn::ActorOptions;
+//! use heph::supervisor::NoSupervisor;
+//!
+//! /// Our application's configuration.
+//! #[derive(Debug)]
+//! struct Config {
+//!     greeting: String,
+//! }
+//!
+//! fn load_config() -> Result<Config, String> {
+//!     // Read the configuration from a file, environment variables, etc.
+//!     Ok(Config { greeting: "Hello".to_owned() })
+//! }
+//!
+//! fn main() -> Result<(), rt::Error> {
+//!     let mut runtime = Runtime::new()?;
+//!     runtime.load_config(load_config)?;
+//!     runtime.run_on_workers(setup)?;
+//!     # if false { // Don't actually start the runtime, the actors never stop.
+//!     runtime.start()
+//!     # } else { Ok(()) }
+//! }
+//!
+//! fn setup(mut runtime_ref: RuntimeRef) -> Result<(), !> {
+//!     let actor = greeter as fn(_) -> _;
+//!     runtime_ref.spawn_local(NoSupervisor, actor, (), ActorOptions::default());
+//!     let actor = reloader as fn(_) -> _;
+//!     let actor_ref = runtime_ref.spawn_local(NoSupervisor, actor, (), ActorOptions::default());
+//!     runtime_ref.receive_signals(actor_ref);
+//!     Ok(())
+//! }
+//!
+//! /// Actor that uses the configuration.
+//! async fn greeter(mut ctx: actor::Context<ConfigUpdated<Config>, ThreadLocal>) {
+//!     ctx.subscribe::<ConfigUpdated<Config>>(config::TOPIC);
+//!     let config = ctx.runtime().config::<Config>().unwrap();
+//!     println!("{} world", config.greeting);
+//!
+//!     while let Ok(update) = ctx.receive_next().await {
+//!         println!("{} world", update.config().greeting);
+//!     }
+//! }
+//!
+//! /// Actor that reloads the configuration on `SIGUSR1`.
+//! async fn reloader(mut ctx: actor::Context<Signal, ThreadLocal>) {
+//!     while let Ok(signal) = ctx.receive_next().await {
+//!         if let Signal::User1 = signal {
+//!             if let Err(err) = ctx.runtime().reload_config() {
+//!                 log::warn!("{}", err);
+//!             }
+//!         }
+//!     }
+//! }
+//! ```
+
+use std::any::{type_name, Any, TypeId};
+use std::collections::HashMap;
+use std::error::Error;
+use std::fmt;
+use std::sync::{Arc, RwLock};
+
+use log::debug;
+
+use crate::bus::Bus;
+
+/// Topic on the runtime's [event bus] to which [`ConfigUpdated`] messages are
+/// published.
+///
+/// [event bus]: crate::bus
+pub const TOPIC: &str = "heph.config";
+
+/// Message published when the configuration of type `T` is updated.
+///
+/// See the [module documentation] for more information.
+///
+/// [module documentation]: crate::config
+pub struct ConfigUpdated<T> {
+    config: Arc<T>,
+}
+
+impl<T> ConfigUpdated<T> {
+    /// Returns the new configuration.
+    pub fn config(&self) -> &Arc<T> {
+        &self.config
+    }
+
+    /// Returns the new configuration.
+    pub fn into_inner(self) -> Arc<T> {
+        self.config
+    }
+}
+
+impl<T> Clone for ConfigUpdated<T> {
+    fn clone(&self) -> ConfigUpdated<T> {
+        ConfigUpdated {
+            config: self.config.clone(),
+        }
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for ConfigUpdated<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ConfigUpdated")
+            .field("config", &self.config)
+            .finish()
+    }
+}
+
+/// Loader of a configuration value, see [`Configs::load`].
+type Loader = Arc<dyn Fn(&Configs, &Bus) -> Result<(), String> + Send + Sync>;
+
+/// Configuration values of the runtime.
+pub(crate) struct Configs {
+    /// Configuration per type, [`TypeId`] of `T` -> [`Entry`].
+    entries: RwLock<HashMap<TypeId, Entry>>,
+}
+
+/// A single configuration value.
+struct Entry {
+    /// `Arc<T>`.
+    value: Arc<dyn Any + Send + Sync>,
+    /// Used to reload the value, if any.
+    loader: Option<Loader>,
+}
+
+impl Configs {
+    /// Create a new empty `Configs`.
+    pub(crate) fn new() -> Configs {
+        Configs {
+            entries: RwLock::new(HashMap::new()),
+        }
+    }
+
+    /// Returns the current configuration of type `T`, if any.
+    pub(crate) fn get<T>(&self) -> Option<Arc<T>>
+    where
+        T: Send + Sync + 'static,
+    {
+        let entries = self.entries.read().unwrap();
+        let value = entries.get(&TypeId::of::<T>())?.value.clone();
+        // We only insert `Arc<T>` for the `TypeId` of `T`.
+        Some(value.downcast().unwrap())
+    }
+
+    /// Set the configuration of type `T` to `value` and publish a
+    /// [`ConfigUpdated`] message on the `bus`.
+    ///
+    /// Returns the number of actors the update was send to.
+    pub(crate) fn update<T>(&self, bus: &Bus, value: T) -> usize
+    where
+        T: Send + Sync + 'static,
+    {
+        let config = Arc::new(value);
+        {
+            let mut entries = self.entries.write().unwrap();
+            let value = config.clone();
+            let _ = entries
+                .entry(TypeId::of::<T>())
+                .and_modify(|entry| entry.value = value.clone())
+                .or_insert(Entry {
+                    value,
+                    loader: None,
+                });
+        }
+        debug!("updated configuration: type={}", type_name::<T>());
+        bus.publish(TOPIC, ConfigUpdated { config })
+    }
+
+    /// Load the configuration of type `T` using `loader`, keeping the loader
+    /// to reload the configuration, see [`Configs::reload`].
+    pub(crate) fn load<T, F, E>(&self, bus: &Bus, loader: F) -> Result<(), E>
+    where
+        T: Send + Sync + 'static,
+        F: Fn() -> Result<T, E> + Send + Sync + 'static,
+        E: ToString,
+    {
+        let _ = self.update(bus, loader()?);
+        let loader: Loader = Arc::new(move |configs, bus| match loader() {
+            Ok(value) => {
+                let _ = configs.update(bus, value);
+                Ok(())
+            }
+            Err(err) => Err(err.to_string()),
+        });
+        let mut entries = self.entries.write().unwrap();
+        // `update` above always adds the entry.
+        entries.get_mut(&TypeId::of::<T>()).unwrap().loader = Some(loader);
+        Ok(())
+    }
+
+    /// Reload all configuration values that have a loader.
+    ///
+    /// If a loader fails the old configuration is kept, all other loaders
+    /// are still run.
+    pub(crate) fn reload(&self, bus: &Bus) -> Result<(), ReloadError> {
+        // Don't hold the lock while running the loaders, they call `update`.
+        let loaders: Vec<Loader> = self
+            .entries
+            .read()
+            .unwrap()
+            .values()
+            .filter_map(|entry| entry.loader.clone())
+            .collect();
+        let mut errors = Vec::new();
+        for loader in loaders {
+            if let Err(err) = loader(self, bus) {
+                errors.push(err);
+            }
+        }
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(ReloadError { errors })
+        }
+    }
+}
+
+impl fmt::Debug for Configs {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("Configs")
+    }
+}
+
+/// Error returned when reloading the configuration fails.
+///
+/// See [`RuntimeRef::reload_config`].
+///
+/// [`RuntimeRef::reload_config`]: crate::rt::RuntimeRef::reload_config
+#[derive(Debug)]
+pub struct ReloadError {
+    /// Errors returned by the loaders.
+    errors: Vec<String>,
+}
+
+impl ReloadError {
+    /// Returns the errors returned by the loaders that failed.
+    pub fn errors(&self) -> &[String] {
+        &self.errors
+    }
+}
+
+impl fmt::Display for ReloadError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("failed to reload configuration: ")?;
+        for (n, err) in self.errors.iter().enumerate() {
+            if n != 0 {
+                f.write_str(", ")?;
+            }
+            f.write_str(err)?;
+        }
+        Ok(())
+    }
+}
+
+impl Error for ReloadError {}
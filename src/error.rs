@@ -0,0 +1,89 @@
+//! Error types used in Heph.
+//!
+//! This module collects the error types returned by Heph in a single place.
+//! All of them implement [`std::error::Error`], including
+//! [`std::error::Error::source`] where the error is caused by another error,
+//! so they can be used as source in application defined error types.
+//!
+//! Errors that can only be caused by an I/O error, such as the error returned
+//! by a [`TcpServer`] that can't fail to create new actors
+//! ([`TcpServerError<!>`]), can be converted into [`io::Error`] using the
+//! `From` trait (and thus the `?` operator).
+//!
+//! [`TcpServer`]: crate::net::TcpServer
+//! [`io::Error`]: std::io::Error
+//!
+//! # Examples
+//!
+//! Using Heph's errors in an application error type.
+//!
+//! ```
+//! #![feature(never_type)]
+//!
+//! use std::{fmt, io};
+//!
+//! use heph::error::{RuntimeError, TcpServerError};
+//!
+//! /// Error for our application.
+//! #[derive(Debug)]
+//! enum AppError {
+//!     Runtime(RuntimeError),
+//!     Io(io::Error),
+//! }
+//!
+//! impl From<RuntimeError> for AppError {
+//!     fn from(err: RuntimeError) -> AppError {
+//!         AppError::Runtime(err)
+//!     }
+//! }
+//!
+//! impl From<io::Error> for AppError {
+//!     fn from(err: io::Error) -> AppError {
+//!         AppError::Io(err)
+//!     }
+//! }
+//!
+//! // Creating a new actor for a connection can't fail, so the `TcpServer`
+//! // error is always an I/O error.
+//! impl From<TcpServerError<!>> for AppError {
+//!     fn from(err: TcpServerError<!>) -> AppError {
+//!         AppError::Io(err.into())
+//!     }
+//! }
+//!
+//! impl fmt::Display for AppError {
+//!     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+//!         match self {
+//!             AppError::Runtime(err) => err.fmt(f),
+//!             AppError::Io(err) => err.fmt(f),
+//!         }
+//!     }
+//! }
+//!
+//! impl std::error::Error for AppError {
+//!     fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+//!         match self {
+//!             AppError::Runtime(err) => Some(err),
+//!             AppError::Io(err) => Some(err),
+//!         }
+//!     }
+//! }
+//!
+//! fn main() -> Result<(), AppError> {
+//!     let runtime = heph::Runtime::new()?;
+//!     // Add actors etc.
+//!     runtime.start()?;
+//!     Ok(())
+//! }
+//! ```
+
+#[doc(no_inline)]
+pub use crate::actor::{NoMessages, ReceiveMatchError, RecvError};
+#[doc(no_inline)]
+pub use crate::actor_ref::{RpcError, SendError};
+#[doc(no_inline)]
+pub use crate::net::tcp::server::Error as TcpServerError;
+#[doc(no_inline)]
+pub use crate::rt::{Error as RuntimeError, ErrorKind as RuntimeErrorKind, JoinError};
+#[doc(no_inline)]
+pub use crate::trace::convert::ParseError as TraceParseError;
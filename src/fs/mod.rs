@@ -0,0 +1,481 @@
+//! Asynchronous file system operations.
+//!
+//! Most operating systems don't support non-blocking file I/O, so instead the
+//! operations in this module are run on a pool of threads dedicated to blocking
+//! operations. This means actors can do file I/O without blocking the worker
+//! thread it's running on, and thus without stalling all other actors on the
+//! same thread.
+//!
+//! All operations return an [`Operation`], a [`Future`] that wakes the actor
+//! once the operation is complete. As the operations are run on another thread
+//! they need to own their data, e.g. [`File::read`] takes the buffer to read
+//! into and returns it once the read is done.
+//!
+//! Other blocking operations, e.g. calls into a blocking library, can be run on
+//! the same thread pool using [`spawn_blocking`].
+//!
+//! # Notes
+//!
+//! Dropping an `Operation` of a file system operation before it's complete
+//! does **not** cancel it, the operation will run to completion but its result
+//! is dropped. Operations started using [`spawn_blocking`] however are
+//! cancelled, see [`BlockingCtx`].
+//!
+//! # File system changes
+//!
+//! Files and directories can be watched for changes using [`Watch`], which
+//! uses inotify or kqueue and does not use the blocking thread pool.
+//!
+//! # Examples
+//!
+//! ```
+//! # #![feature(never_type)]
+//! use std::io;
+//!
+//! use heph::fs::{self, File};
+//! use heph::{actor, rt};
+//!
+//! async fn actor<RT>(_: actor::Context<!, RT>) -> io::Result<()>
+//!     where RT: rt::Access,
+//! {
+//!     # let path = std::env::temp_dir().join("heph_fs_doc_example.txt");
+//!     # fs::write(path.clone(), b"Hello, world!".to_vec()).await?;
+//!     // Open a file.
+//!     let file = File::open(path).await?;
+//!     // Read the entire file into a buffer.
+//!     let buf = file.read_to_end(Vec::new()).await?;
+//!     # assert_eq!(buf, b"Hello, world!");
+//!     println!("read {} bytes", buf.len());
+//!     Ok(())
+//! }
+//! #
+//! # let actor_ref = heph::test::try_spawn(
+//! #     heph::test::PanicSupervisor,
+//! #     actor as fn(_) -> _,
+//! #     (),
+//! #     heph::spawn::ActorOptions::default(),
+//! # ).unwrap();
+//! # heph::test::join(&actor_ref, std::time::Duration::from_secs(1)).unwrap();
+//! ```
+
+use std::future::Future;
+use std::io::{self, Read, Seek, SeekFrom, Write};
+use std::lazy::SyncLazy;
+use std::os::unix::io::{AsRawFd, RawFd};
+use std::panic::{catch_unwind, AssertUnwindSafe};
+use std::path::PathBuf;
+use std::pin::Pin;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::task::{self, Poll};
+use std::{fmt, fs, thread};
+
+use crossbeam_channel::{unbounded, Receiver, Sender};
+use heph_inbox::oneshot::{new_oneshot, RecvOnce};
+use log::{debug, error, warn};
+
+pub mod watch;
+
+#[doc(no_inline)]
+pub use watch::Watch;
+
+/// Number of threads in the blocking thread pool.
+const POOL_SIZE: usize = 4;
+
+/// Operation to run on the blocking thread pool.
+type Job = Box<dyn FnOnce() + Send + 'static>;
+
+/// Sending side of the blocking thread pool, lazily started on first use.
+static POOL: SyncLazy<Sender<Job>> = SyncLazy::new(|| {
+    let (sender, receiver) = unbounded();
+    for n in 0..POOL_SIZE {
+        let receiver = receiver.clone();
+        let res = thread::Builder::new()
+            .name(format!("Heph blocking I/O {}", n))
+            .spawn(move || run_jobs(receiver));
+        if let Err(err) = res {
+            // If none of the threads can be started all operations will return
+            // an error, see `Operation::poll`.
+            warn!("failed to start blocking I/O thread: {}", err);
+        }
+    }
+    sender
+});
+
+/// Run the jobs sent to the blocking thread pool.
+fn run_jobs(receiver: Receiver<Job>) {
+    while let Ok(job) = receiver.recv() {
+        // NOTE: if `job` panics the result is never sent, which causes the
+        // `Operation` to return an error.
+        if catch_unwind(AssertUnwindSafe(job)).is_err() {
+            error!("blocking I/O operation panicked");
+        }
+    }
+}
+
+/// Run blocking `op` on the blocking thread pool.
+pub(crate) fn run<F, T>(op: F) -> Operation<T>
+where
+    F: FnOnce() -> io::Result<T> + Send + 'static,
+    T: Send + 'static,
+{
+    let (sender, receiver) = new_oneshot();
+    let job = Box::new(move || {
+        // If the `Operation` is dropped we don't care about the result.
+        let _ = sender.try_send(op());
+    });
+    // If the pool is stopped the job is dropped, which also drops the sender
+    // and causes the `Operation` to return an error.
+    let _ = POOL.send(job);
+    Operation {
+        recv: receiver.recv_once(),
+        cancelled: None,
+    }
+}
+
+/// Run blocking `op` on the blocking thread pool.
+///
+/// This can be used to run blocking operations, e.g. calls into a library that
+/// only has a blocking API, without blocking the worker thread the actor is
+/// running on. The returned [`Operation`] returns the result of `op` once it's
+/// complete, or an error if `op` panicked.
+///
+/// Dropping the `Operation` cancels the operation, e.g. when the actor that
+/// started it is stopped. If `op` hasn't started yet it will not be run at
+/// all. If it's already running it should check [`BlockingCtx::is_cancelled`]
+/// periodically and return early if the operation was cancelled.
+///
+/// # Examples
+///
+/// ```
+/// use heph::fs::spawn_blocking;
+/// # use heph::test::block_on;
+///
+/// # block_on(async {
+/// let sum = spawn_blocking(|ctx| {
+///     let mut sum = 0_u64;
+///     for n in 0..1_000_000 {
+///         // Stop early if we're no longer interested in the result.
+///         if n % 1000 == 0 && ctx.is_cancelled() {
+///             break;
+///         }
+///         sum += n;
+///     }
+///     sum
+/// })
+/// .await
+/// .unwrap();
+/// assert_eq!(sum, 499_999_500_000);
+/// # });
+/// ```
+pub fn spawn_blocking<F, T>(op: F) -> Operation<T>
+where
+    F: FnOnce(&BlockingCtx) -> T + Send + 'static,
+    T: Send + 'static,
+{
+    let (sender, receiver) = new_oneshot();
+    let cancelled = Arc::new(AtomicBool::new(false));
+    let ctx = BlockingCtx {
+        cancelled: cancelled.clone(),
+    };
+    let job = Box::new(move || {
+        if ctx.is_cancelled() {
+            debug!("skipping cancelled blocking operation");
+            return;
+        }
+        // If the `Operation` is dropped we don't care about the result.
+        let _ = sender.try_send(Ok(op(&ctx)));
+    });
+    // If the pool is stopped the job is dropped, which also drops the sender
+    // and causes the `Operation` to return an error.
+    let _ = POOL.send(job);
+    Operation {
+        recv: receiver.recv_once(),
+        cancelled: Some(cancelled),
+    }
+}
+
+/// Context of an operation started using [`spawn_blocking`].
+#[derive(Debug)]
+pub struct BlockingCtx {
+    /// Set once the [`Operation`] is dropped.
+    cancelled: Arc<AtomicBool>,
+}
+
+impl BlockingCtx {
+    /// Returns `true` if the operation was cancelled, i.e. if the
+    /// [`Operation`] was dropped.
+    ///
+    /// Long running operations should check this periodically and return
+    /// early if the operation was cancelled, as no one is interested in the
+    /// result anymore.
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::Relaxed)
+    }
+}
+
+/// [`Future`] representing an operation on the blocking thread pool, e.g. a
+/// file system operation.
+///
+/// See the [module documentation] for more information.
+///
+/// [module documentation]: crate::fs
+#[must_use = "futures do nothing unless you `.await` or poll them"]
+pub struct Operation<T> {
+    recv: RecvOnce<io::Result<T>>,
+    /// Cancellation flag of operations started by [`spawn_blocking`], see
+    /// [`BlockingCtx`].
+    cancelled: Option<Arc<AtomicBool>>,
+}
+
+impl<T> Future for Operation<T> {
+    type Output = io::Result<T>;
+
+    fn poll(self: Pin<&mut Self>, ctx: &mut task::Context<'_>) -> Poll<Self::Output> {
+        // Safety: we're not moving `recv` so this is safe.
+        match unsafe { self.map_unchecked_mut(|s| &mut s.recv) }.poll(ctx) {
+            Poll::Ready(Some(result)) => Poll::Ready(result),
+            Poll::Ready(None) => Poll::Ready(Err(io::Error::new(
+                io::ErrorKind::Other,
+                "failed to run blocking I/O operation",
+            ))),
+            Poll::Pending => Poll::Pending,
+        }
+    }
+}
+
+impl<T> Drop for Operation<T> {
+    fn drop(&mut self) {
+        if let Some(cancelled) = &self.cancelled {
+            // NOTE: if the operation is already complete this has no effect.
+            cancelled.store(true, Ordering::Relaxed);
+        }
+    }
+}
+
+impl<T> fmt::Debug for Operation<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("fs::Operation").finish()
+    }
+}
+
+/// An open file.
+///
+/// This is an asynchronous version of [`std::fs::File`]. The file can be
+/// cloned cheaply, all clones refer to the same underlying file (including
+/// the file cursor).
+///
+/// The file can be sent using `sendfile(2)`, see [`TcpStream::send_file`].
+///
+/// [`TcpStream::send_file`]: crate::net::TcpStream::send_file
+#[derive(Clone, Debug)]
+pub struct File {
+    file: Arc<fs::File>,
+}
+
+impl File {
+    /// Open a file in read-only mode.
+    ///
+    /// See [`std::fs::File::open`].
+    pub fn open<P>(path: P) -> Operation<File>
+    where
+        P: Into<PathBuf>,
+    {
+        let path = path.into();
+        run(move || fs::File::open(path).map(File::from_std))
+    }
+
+    /// Open a file in write-only mode, creating it if it doesn't exist and
+    /// truncating it if it does.
+    ///
+    /// See [`std::fs::File::create`].
+    pub fn create<P>(path: P) -> Operation<File>
+    where
+        P: Into<PathBuf>,
+    {
+        let path = path.into();
+        run(move || fs::File::create(path).map(File::from_std))
+    }
+
+    /// Open a file with the options set in `options`.
+    ///
+    /// See [`std::fs::OpenOptions::open`].
+    pub fn open_with<P>(path: P, options: fs::OpenOptions) -> Operation<File>
+    where
+        P: Into<PathBuf>,
+    {
+        let path = path.into();
+        run(move || options.open(path).map(File::from_std))
+    }
+
+    /// Create a `File` from a [`std::fs::File`].
+    pub fn from_std(file: fs::File) -> File {
+        File {
+            file: Arc::new(file),
+        }
+    }
+
+    /// Read bytes from the file into the spare capacity of `buf`, returning
+    /// the buffer once the read is done.
+    ///
+    /// The bytes read are appended to `buf`, thus the number of bytes read is
+    /// the difference in length of the buffer. If no bytes are read the end of
+    /// the file is reached, or the buffer has no spare capacity.
+    pub fn read(&self, mut buf: Vec<u8>) -> Operation<Vec<u8>> {
+        let file = self.file.clone();
+        run(move || {
+            let len = buf.len();
+            buf.resize(buf.capacity(), 0);
+            loop {
+                match (&*file).read(&mut buf[len..]) {
+                    Ok(n) => {
+                        buf.truncate(len + n);
+                        return Ok(buf);
+                    }
+                    Err(ref err) if err.kind() == io::ErrorKind::Interrupted => continue,
+                    Err(err) => return Err(err),
+                }
+            }
+        })
+    }
+
+    /// Read all bytes until the end of the file, appending them to `buf`.
+    ///
+    /// See [`std::io::Read::read_to_end`].
+    pub fn read_to_end(&self, mut buf: Vec<u8>) -> Operation<Vec<u8>> {
+        let file = self.file.clone();
+        run(move || (&*file).read_to_end(&mut buf).map(|_| buf))
+    }
+
+    /// Write bytes from `buf` into the file, returning the number of bytes
+    /// written.
+    ///
+    /// See [`std::io::Write::write`].
+    pub fn write(&self, buf: Vec<u8>) -> Operation<usize> {
+        let file = self.file.clone();
+        run(move || (&*file).write(&buf))
+    }
+
+    /// Write all bytes in `buf` into the file.
+    ///
+    /// See [`std::io::Write::write_all`].
+    pub fn write_all(&self, buf: Vec<u8>) -> Operation<()> {
+        let file = self.file.clone();
+        run(move || (&*file).write_all(&buf))
+    }
+
+    /// Seek to an offset in the file, returning the new position from the
+    /// start of the file.
+    ///
+    /// See [`std::io::Seek::seek`].
+    pub fn seek(&self, pos: SeekFrom) -> Operation<u64> {
+        let file = self.file.clone();
+        run(move || (&*file).seek(pos))
+    }
+
+    /// Returns the metadata of the file.
+    ///
+    /// See [`std::fs::File::metadata`].
+    pub fn metadata(&self) -> Operation<fs::Metadata> {
+        let file = self.file.clone();
+        run(move || file.metadata())
+    }
+
+    /// Truncate or extend the file to `size` bytes.
+    ///
+    /// See [`std::fs::File::set_len`].
+    pub fn set_len(&self, size: u64) -> Operation<()> {
+        let file = self.file.clone();
+        run(move || file.set_len(size))
+    }
+
+    /// Sync all data and metadata of the file to disk.
+    ///
+    /// See [`std::fs::File::sync_all`].
+    pub fn sync_all(&self) -> Operation<()> {
+        let file = self.file.clone();
+        run(move || file.sync_all())
+    }
+
+    /// Sync all data of the file to disk, but not necessarily the metadata.
+    ///
+    /// See [`std::fs::File::sync_data`].
+    pub fn sync_data(&self) -> Operation<()> {
+        let file = self.file.clone();
+        run(move || file.sync_data())
+    }
+}
+
+impl AsRawFd for File {
+    fn as_raw_fd(&self) -> RawFd {
+        self.file.as_raw_fd()
+    }
+}
+
+/// Read the entire contents of the file at `path`.
+///
+/// See [`std::fs::read`].
+pub fn read<P>(path: P) -> Operation<Vec<u8>>
+where
+    P: Into<PathBuf>,
+{
+    let path = path.into();
+    run(move || fs::read(path))
+}
+
+/// Read the entire contents of the file at `path` into a string.
+///
+/// See [`std::fs::read_to_string`].
+pub fn read_to_string<P>(path: P) -> Operation<String>
+where
+    P: Into<PathBuf>,
+{
+    let path = path.into();
+    run(move || fs::read_to_string(path))
+}
+
+/// Write `contents` to the file at `path`, creating it if it doesn't exist and
+/// replacing its contents if it does.
+///
+/// See [`std::fs::write`].
+pub fn write<P>(path: P, contents: Vec<u8>) -> Operation<()>
+where
+    P: Into<PathBuf>,
+{
+    let path = path.into();
+    run(move || fs::write(path, contents))
+}
+
+/// Returns the metadata of the file or directory at `path`.
+///
+/// See [`std::fs::metadata`].
+pub fn metadata<P>(path: P) -> Operation<fs::Metadata>
+where
+    P: Into<PathBuf>,
+{
+    let path = path.into();
+    run(move || fs::metadata(path))
+}
+
+/// Remove the file at `path`.
+///
+/// See [`std::fs::remove_file`].
+pub fn remove_file<P>(path: P) -> Operation<()>
+where
+    P: Into<PathBuf>,
+{
+    let path = path.into();
+    run(move || fs::remove_file(path))
+}
+
+/// Create the directory at `path`, and all of its parent directories if they
+/// don't exist.
+///
+/// See [`std::fs::create_dir_all`].
+pub fn create_dir_all<P>(path: P) -> Operation<()>
+where
+    P: Into<PathBuf>,
+{
+    let path = path.into();
+    run(move || fs::create_dir_all(path))
+}
@@ -0,0 +1,439 @@
+//! Module with [`Watch`], file system change notifications.
+
+use std::collections::VecDeque;
+use std::ffi::{CString, OsString};
+use std::fs::File;
+use std::future::Future;
+use std::io;
+use std::os::unix::ffi::OsStrExt;
+use std::os::unix::io::{AsRawFd, FromRawFd, RawFd};
+use std::path::Path;
+use std::pin::Pin;
+use std::task::{self, Poll};
+
+#[cfg(any(target_os = "freebsd", target_os = "macos"))]
+use std::collections::HashMap;
+
+use mio::unix::SourceFd;
+use mio::Interest;
+
+use crate::{actor, rt};
+
+/// Watch files and directories for changes.
+///
+/// This uses `inotify(7)` on Linux and `kqueue(2)` on FreeBSD and macOS. The
+/// watch is registered with the runtime of the actor that creates it, waiting
+/// for events using [`Watch::next`] doesn't block the worker thread. This is
+/// useful for e.g. an actor that reloads its configuration once the
+/// configuration file changes.
+///
+/// Paths are watched using [`Watch::add`], which returns a [`WatchId`] to
+/// match the returned [`Event`]s to the watched path.
+///
+/// # Notes
+///
+/// The events returned differ per OS. On Linux watching a directory returns
+/// events for the files in the directory, including the [name] of the file,
+/// using kqueue only the directory itself is watched (creating or removing a
+/// file in it is a [`EventKind::Modify`] event of the directory).
+///
+/// The `Watch` type is [bound] to an actor. See the [`actor::Bound`] trait
+/// for more information.
+///
+/// [name]: Event::name
+/// [bound]: crate::actor::Bound
+/// [`actor::Bound`]: crate::actor::Bound
+///
+/// # Examples
+///
+/// An actor that reloads its configuration once it changes.
+///
+/// ```
+/// # #![feature(never_type)]
+/// use std::io;
+///
+/// use heph::fs::Watch;
+/// use heph::{actor, rt};
+///
+/// async fn actor<RT>(mut ctx: actor::Context<!, RT>) -> io::Result<()>
+///     where RT: rt::Access,
+/// {
+///     let mut watch = Watch::new(&mut ctx)?;
+///     let config_id = watch.add("/etc/my_app.conf")?;
+///
+///     loop {
+///         let event = watch.next().await?;
+///         if event.id() == config_id {
+///             println!("reloading configuration after {:?}", event.kind());
+///             // Reload configuration...
+///         }
+///     }
+/// }
+/// #
+/// # drop(actor::<heph::rt::ThreadLocal>); // Silence dead code warnings.
+/// ```
+#[derive(Debug)]
+pub struct Watch {
+    /// inotify or kqueue file descriptor.
+    fd: File,
+    /// Events read, but not yet returned.
+    events: VecDeque<Event>,
+    /// Files opened for the watches, by file descriptor.
+    #[cfg(any(target_os = "freebsd", target_os = "macos"))]
+    files: HashMap<RawFd, File>,
+}
+
+/// Identifier of a watched path, see [`Watch::add`].
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+pub struct WatchId(RawFd);
+
+/// Event returned by [`Watch::next`].
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Event {
+    id: WatchId,
+    kind: EventKind,
+    name: Option<OsString>,
+}
+
+impl Event {
+    /// Returns the id of the watch the event is for.
+    pub const fn id(&self) -> WatchId {
+        self.id
+    }
+
+    /// Returns the kind of change.
+    pub const fn kind(&self) -> EventKind {
+        self.kind
+    }
+
+    /// Name of the file, if the event is for a file in a watched directory.
+    ///
+    /// Only supported on Linux, always `None` on other OSs.
+    pub fn name(&self) -> Option<&Path> {
+        self.name.as_deref().map(Path::new)
+    }
+}
+
+/// The kind of change of an [`Event`].
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+#[non_exhaustive]
+pub enum EventKind {
+    /// Contents were modified.
+    Modify,
+    /// Metadata, e.g. permissions, were changed.
+    Metadata,
+    /// File was created in a watched directory.
+    ///
+    /// Only supported on Linux.
+    Create,
+    /// File was deleted.
+    Delete,
+    /// File was moved or renamed.
+    Rename,
+}
+
+impl Watch {
+    /// Create a new `Watch`, watching nothing.
+    pub fn new<M, RT>(ctx: &mut actor::Context<M, RT>) -> io::Result<Watch>
+    where
+        RT: rt::Access,
+    {
+        let fd = sys::new()?;
+        // Safety: just created the file descriptor, so we own it.
+        let fd = unsafe { File::from_raw_fd(fd) };
+        ctx.runtime()
+            .register(&mut SourceFd(&fd.as_raw_fd()), Interest::READABLE)?;
+        Ok(Watch {
+            fd,
+            events: VecDeque::new(),
+            #[cfg(any(target_os = "freebsd", target_os = "macos"))]
+            files: HashMap::new(),
+        })
+    }
+
+    /// Watch `path` for changes.
+    ///
+    /// On Linux adding the same path twice returns the same [`WatchId`].
+    pub fn add<P>(&mut self, path: P) -> io::Result<WatchId>
+    where
+        P: AsRef<Path>,
+    {
+        let path = CString::new(path.as_ref().as_os_str().as_bytes())
+            .map_err(|err| io::Error::new(io::ErrorKind::InvalidInput, err))?;
+        self.add_path(&path)
+    }
+
+    /// Stop watching the path with `id`.
+    pub fn remove(&mut self, id: WatchId) -> io::Result<()> {
+        self.remove_id(id)
+    }
+
+    /// Attempt to return the next event.
+    ///
+    /// If no events are available this will return an error with the [kind]
+    /// set to [`ErrorKind::WouldBlock`]. Most users should prefer to use
+    /// [`Watch::next`].
+    ///
+    /// [kind]: io::Error::kind
+    /// [`ErrorKind::WouldBlock`]: io::ErrorKind::WouldBlock
+    pub fn try_next(&mut self) -> io::Result<Event> {
+        loop {
+            if let Some(event) = self.events.pop_front() {
+                return Ok(event);
+            }
+            self.read_events()?;
+        }
+    }
+
+    /// Returns the next event.
+    pub fn next<'w>(&'w mut self) -> Next<'w> {
+        Next { watch: self }
+    }
+}
+
+/// The [`Future`] behind [`Watch::next`].
+#[derive(Debug)]
+#[must_use = "futures do nothing unless you `.await` or poll them"]
+pub struct Next<'w> {
+    watch: &'w mut Watch,
+}
+
+impl<'w> Future for Next<'w> {
+    type Output = io::Result<Event>;
+
+    fn poll(self: Pin<&mut Self>, _: &mut task::Context<'_>) -> Poll<Self::Output> {
+        let Next { watch } = Pin::into_inner(self);
+        try_io!(watch.try_next())
+    }
+}
+
+impl<RT: rt::Access> actor::Bound<RT> for Watch {
+    type Error = io::Error;
+
+    fn bind_to<M>(&mut self, ctx: &mut actor::Context<M, RT>) -> io::Result<()> {
+        ctx.runtime()
+            .reregister(&mut SourceFd(&self.fd.as_raw_fd()), Interest::READABLE)
+    }
+}
+
+#[cfg(target_os = "linux")]
+mod sys {
+    use std::ffi::{CStr, OsString};
+    use std::io::{self, Read};
+    use std::mem::size_of;
+    use std::os::unix::ffi::OsStringExt;
+    use std::os::unix::io::{AsRawFd, RawFd};
+    use std::ptr;
+
+    use super::{Event, EventKind, Watch, WatchId};
+
+    /// Size of the buffer used to read events, enough for at least a single
+    /// event with the maximum file name length.
+    const BUF_SIZE: usize = 4096;
+
+    /// Events we watch for.
+    const MASK: u32 = libc::IN_MODIFY
+        | libc::IN_ATTRIB
+        | libc::IN_CREATE
+        | libc::IN_DELETE
+        | libc::IN_DELETE_SELF
+        | libc::IN_MOVE
+        | libc::IN_MOVE_SELF;
+
+    pub(super) fn new() -> io::Result<RawFd> {
+        match unsafe { libc::inotify_init1(libc::IN_NONBLOCK | libc::IN_CLOEXEC) } {
+            -1 => Err(io::Error::last_os_error()),
+            fd => Ok(fd),
+        }
+    }
+
+    impl Watch {
+        pub(super) fn add_path(&mut self, path: &CStr) -> io::Result<WatchId> {
+            let fd = self.fd.as_raw_fd();
+            match unsafe { libc::inotify_add_watch(fd, path.as_ptr(), MASK) } {
+                -1 => Err(io::Error::last_os_error()),
+                wd => Ok(WatchId(wd)),
+            }
+        }
+
+        pub(super) fn remove_id(&mut self, id: WatchId) -> io::Result<()> {
+            match unsafe { libc::inotify_rm_watch(self.fd.as_raw_fd(), id.0) } {
+                -1 => Err(io::Error::last_os_error()),
+                _ => Ok(()),
+            }
+        }
+
+        pub(super) fn read_events(&mut self) -> io::Result<()> {
+            let mut buf = [0; BUF_SIZE];
+            let n = self.fd.read(&mut buf)?;
+            let mut buf = &buf[..n];
+            while buf.len() >= size_of::<libc::inotify_event>() {
+                // Safety: the kernel only writes complete events and we
+                // checked the size above.
+                let event: libc::inotify_event =
+                    unsafe { ptr::read_unaligned(buf.as_ptr().cast()) };
+                let name_start = size_of::<libc::inotify_event>();
+                let name_end = name_start + event.len as usize;
+                let name = &buf[name_start..name_end];
+                buf = &buf[name_end..];
+
+                let kind = match event_kind(event.mask) {
+                    Some(kind) => kind,
+                    // E.g. `IN_IGNORED`.
+                    None => continue,
+                };
+                // The name is padded with NUL bytes.
+                let name_len = name.iter().position(|b| *b == 0).unwrap_or(name.len());
+                let name = if name_len == 0 {
+                    None
+                } else {
+                    Some(OsString::from_vec(name[..name_len].to_vec()))
+                };
+                self.events.push_back(Event {
+                    id: WatchId(event.wd),
+                    kind,
+                    name,
+                });
+            }
+            Ok(())
+        }
+    }
+
+    fn event_kind(mask: u32) -> Option<EventKind> {
+        if mask & libc::IN_MODIFY != 0 {
+            Some(EventKind::Modify)
+        } else if mask & libc::IN_ATTRIB != 0 {
+            Some(EventKind::Metadata)
+        } else if mask & libc::IN_CREATE != 0 {
+            Some(EventKind::Create)
+        } else if mask & (libc::IN_DELETE | libc::IN_DELETE_SELF) != 0 {
+            Some(EventKind::Delete)
+        } else if mask & (libc::IN_MOVE | libc::IN_MOVE_SELF) != 0 {
+            Some(EventKind::Rename)
+        } else {
+            None
+        }
+    }
+}
+
+#[cfg(any(target_os = "freebsd", target_os = "macos"))]
+mod sys {
+    use std::ffi::CStr;
+    use std::fs::File;
+    use std::io;
+    use std::mem::MaybeUninit;
+    use std::os::unix::io::{AsRawFd, FromRawFd, RawFd};
+    use std::ptr;
+
+    use super::{Event, EventKind, Watch, WatchId};
+
+    /// Maximum number of events read at once.
+    const MAX_EVENTS: usize = 32;
+
+    /// Events we watch for.
+    const FFLAGS: u32 = libc::NOTE_WRITE
+        | libc::NOTE_EXTEND
+        | libc::NOTE_ATTRIB
+        | libc::NOTE_DELETE
+        | libc::NOTE_RENAME;
+
+    /// Flags used to open the watched files, only used for the notifications.
+    #[cfg(target_os = "freebsd")]
+    const OPEN_FLAGS: libc::c_int = libc::O_RDONLY | libc::O_CLOEXEC;
+    #[cfg(target_os = "macos")]
+    const OPEN_FLAGS: libc::c_int = libc::O_EVTONLY | libc::O_CLOEXEC;
+
+    pub(super) fn new() -> io::Result<RawFd> {
+        let fd = unsafe { libc::kqueue() };
+        if fd == -1 || unsafe { libc::fcntl(fd, libc::F_SETFD, libc::FD_CLOEXEC) } == -1 {
+            return Err(io::Error::last_os_error());
+        }
+        Ok(fd)
+    }
+
+    impl Watch {
+        pub(super) fn add_path(&mut self, path: &CStr) -> io::Result<WatchId> {
+            let fd = unsafe { libc::open(path.as_ptr(), OPEN_FLAGS) };
+            if fd == -1 {
+                return Err(io::Error::last_os_error());
+            }
+            // Safety: just opened the file, so we own the file descriptor.
+            let file = unsafe { File::from_raw_fd(fd) };
+
+            // Not all fields are available on all OSs, so we zero the struct.
+            let mut change: libc::kevent = unsafe { MaybeUninit::zeroed().assume_init() };
+            change.ident = fd as libc::uintptr_t;
+            change.filter = libc::EVFILT_VNODE;
+            change.flags = libc::EV_ADD | libc::EV_CLEAR;
+            change.fflags = FFLAGS;
+            let res = unsafe {
+                libc::kevent(
+                    self.fd.as_raw_fd(),
+                    &change,
+                    1,
+                    ptr::null_mut(),
+                    0,
+                    ptr::null(),
+                )
+            };
+            if res == -1 {
+                return Err(io::Error::last_os_error());
+            }
+            let _ = self.files.insert(fd, file);
+            Ok(WatchId(fd))
+        }
+
+        pub(super) fn remove_id(&mut self, id: WatchId) -> io::Result<()> {
+            // Closing the file removes it from the kqueue.
+            match self.files.remove(&id.0) {
+                Some(_) => Ok(()),
+                None => Err(io::Error::from_raw_os_error(libc::EINVAL)),
+            }
+        }
+
+        pub(super) fn read_events(&mut self) -> io::Result<()> {
+            let mut events: [libc::kevent; MAX_EVENTS] =
+                unsafe { MaybeUninit::zeroed().assume_init() };
+            // Don't block.
+            let timeout = libc::timespec {
+                tv_sec: 0,
+                tv_nsec: 0,
+            };
+            let n = unsafe {
+                libc::kevent(
+                    self.fd.as_raw_fd(),
+                    ptr::null(),
+                    0,
+                    events.as_mut_ptr(),
+                    MAX_EVENTS as libc::c_int,
+                    &timeout,
+                )
+            };
+            match n {
+                -1 => return Err(io::Error::last_os_error()),
+                0 => return Err(io::ErrorKind::WouldBlock.into()),
+                _ => {}
+            }
+
+            for event in &events[..n as usize] {
+                let id = WatchId(event.ident as RawFd);
+                let kinds = [
+                    (libc::NOTE_WRITE | libc::NOTE_EXTEND, EventKind::Modify),
+                    (libc::NOTE_ATTRIB, EventKind::Metadata),
+                    (libc::NOTE_DELETE, EventKind::Delete),
+                    (libc::NOTE_RENAME, EventKind::Rename),
+                ];
+                for (fflags, kind) in kinds {
+                    if event.fflags & fflags != 0 {
+                        self.events.push_back(Event {
+                            id,
+                            kind,
+                            name: None,
+                        });
+                    }
+                }
+            }
+            Ok(())
+        }
+    }
+}
@@ -0,0 +1,242 @@
+//! Module with I/O utilities.
+//!
+//! [`from_blocking`] allows actors to read from a blocking [`Read`]er, for
+//! example a serial port or a reader provided by a third-party SDK, without
+//! blocking the worker thread. The reads are run on the same pool of threads as
+//! used by the [`fs`] module, the read bytes are returned in chunks by the
+//! [`FromBlocking`] stream.
+//!
+//! [`fs`]: crate::fs
+//!
+//! # Back-pressure
+//!
+//! [`FromBlocking`] reads ahead at most [`FromBlocking::max_buffered`] chunks.
+//! Once that many chunks are buffered no new reads are started until the actor
+//! received a chunk from the stream. This means a slow actor will not cause the
+//! buffer to grow unbounded.
+//!
+//! # Examples
+//!
+//! ```
+//! # #![feature(never_type)]
+//! use std::io::{self, Read};
+//!
+//! use heph::io::from_blocking;
+//! use heph::util::next;
+//! use heph::{actor, rt};
+//!
+//! async fn actor<RT>(_: actor::Context<!, RT>) -> io::Result<()>
+//!     where RT: rt::Access,
+//! {
+//!     // In a real application this would be a reader that blocks, for example
+//!     // a serial port.
+//!     let reader = io::repeat(b'a').take(64);
+//!
+//!     let mut chunks = from_blocking(reader).chunk_size(16);
+//!     while let Some(chunk) = next(&mut chunks).await {
+//!         let chunk = chunk?;
+//!         # assert_eq!(chunk, [b'a'; 16]);
+//!         println!("read {} bytes", chunk.len());
+//!     }
+//!     Ok(())
+//! }
+//! #
+//! # let actor_ref = heph::test::try_spawn(
+//! #     heph::test::PanicSupervisor,
+//! #     actor as fn(_) -> _,
+//! #     (),
+//! #     heph::spawn::ActorOptions::default(),
+//! # ).unwrap();
+//! # heph::test::join(&actor_ref, std::time::Duration::from_secs(1)).unwrap();
+//! ```
+
+use std::collections::VecDeque;
+use std::fmt;
+use std::future::Future;
+use std::io::{self, Read};
+use std::pin::Pin;
+use std::stream::Stream;
+use std::task::{self, Poll};
+
+use crate::fs::{self, Operation};
+
+/// Default size of the chunks read by [`FromBlocking`].
+pub const DEFAULT_CHUNK_SIZE: usize = 8 * 1024;
+
+/// Default maximum number of chunks buffered by [`FromBlocking`].
+pub const DEFAULT_MAX_BUFFERED: usize = 4;
+
+/// Read from the blocking `reader` on a separate thread, returning the read
+/// bytes in chunks.
+///
+/// See the [module documentation] for more information.
+///
+/// [module documentation]: crate::io
+pub fn from_blocking<R>(reader: R) -> FromBlocking<R>
+where
+    R: Read + Send + 'static,
+{
+    FromBlocking {
+        reader: Some(reader),
+        read: None,
+        chunks: VecDeque::new(),
+        error: None,
+        done: false,
+        chunk_size: DEFAULT_CHUNK_SIZE,
+        max_buffered: DEFAULT_MAX_BUFFERED,
+    }
+}
+
+/// [`Stream`] behind [`from_blocking`].
+///
+/// Returns the bytes read in chunks of at most [`FromBlocking::chunk_size`]
+/// bytes. Errors returned by the reader are returned by the stream, after which
+/// it can be polled again to continue reading. The stream ends once the reader
+/// returns zero bytes.
+#[must_use = "streams do nothing unless polled"]
+pub struct FromBlocking<R> {
+    /// `None` while a read is in progress (or if the read operation failed).
+    reader: Option<R>,
+    /// Read in progress, returns the reader once done.
+    read: Option<Pin<Box<Operation<(R, io::Result<Vec<u8>>)>>>>,
+    /// Chunks read, but not yet returned.
+    chunks: VecDeque<Vec<u8>>,
+    /// Error returned by the reader, returned after all chunks.
+    error: Option<io::Error>,
+    /// Reader returned zero bytes, or the read operation failed.
+    done: bool,
+    chunk_size: usize,
+    max_buffered: usize,
+}
+
+impl<R> FromBlocking<R> {
+    /// Set the maximum size of a single chunk, defaults to
+    /// [`DEFAULT_CHUNK_SIZE`].
+    ///
+    /// # Panics
+    ///
+    /// Panics if `size` is zero.
+    pub fn chunk_size(mut self, size: usize) -> Self {
+        assert!(size != 0, "chunk size can't be zero");
+        self.chunk_size = size;
+        self
+    }
+
+    /// Set the maximum number of chunks to read ahead, defaults to
+    /// [`DEFAULT_MAX_BUFFERED`].
+    ///
+    /// # Panics
+    ///
+    /// Panics if `max` is zero.
+    pub fn max_buffered(mut self, max: usize) -> Self {
+        assert!(max != 0, "maximum number of buffered chunks can't be zero");
+        self.max_buffered = max;
+        self
+    }
+
+    /// Returns the number of chunks read, but not yet returned by the stream.
+    pub fn buffered(&self) -> usize {
+        self.chunks.len()
+    }
+}
+
+impl<R> FromBlocking<R>
+where
+    R: Read + Send + 'static,
+{
+    /// Start reading the next chunk, if there is room in the buffer and no
+    /// read is in progress.
+    fn start_read(&mut self) {
+        if self.read.is_some()
+            || self.done
+            || self.error.is_some()
+            || self.chunks.len() >= self.max_buffered
+        {
+            return;
+        }
+
+        if let Some(mut reader) = self.reader.take() {
+            let size = self.chunk_size;
+            let read = fs::run(move || {
+                let result = read_chunk(&mut reader, size);
+                Ok((reader, result))
+            });
+            self.read = Some(Box::pin(read));
+        }
+    }
+}
+
+/// Read a single chunk of at most `size` bytes from `reader`.
+fn read_chunk<R: Read>(reader: &mut R, size: usize) -> io::Result<Vec<u8>> {
+    let mut buf = vec![0; size];
+    loop {
+        match reader.read(&mut buf) {
+            Ok(n) => {
+                buf.truncate(n);
+                return Ok(buf);
+            }
+            Err(ref err) if err.kind() == io::ErrorKind::Interrupted => continue,
+            Err(err) => return Err(err),
+        }
+    }
+}
+
+impl<R> Stream for FromBlocking<R>
+where
+    R: Read + Send + 'static,
+{
+    type Item = io::Result<Vec<u8>>;
+
+    fn poll_next(self: Pin<&mut Self>, ctx: &mut task::Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = Pin::get_mut(self);
+        loop {
+            this.start_read();
+            let read = match this.read.as_mut() {
+                Some(read) => read,
+                None => break,
+            };
+            match read.as_mut().poll(ctx) {
+                Poll::Ready(Ok((reader, result))) => {
+                    this.read = None;
+                    this.reader = Some(reader);
+                    match result {
+                        Ok(chunk) if chunk.is_empty() => this.done = true,
+                        Ok(chunk) => this.chunks.push_back(chunk),
+                        Err(err) => this.error = Some(err),
+                    }
+                }
+                Poll::Ready(Err(err)) => {
+                    // The read operation failed, we lost the reader.
+                    this.read = None;
+                    this.error = Some(err);
+                    this.done = true;
+                }
+                Poll::Pending => break,
+            }
+        }
+
+        if let Some(chunk) = this.chunks.pop_front() {
+            // NOTE: the next call will start a new read now that there is room
+            // in the buffer.
+            Poll::Ready(Some(Ok(chunk)))
+        } else if let Some(err) = this.error.take() {
+            Poll::Ready(Some(Err(err)))
+        } else if this.done {
+            Poll::Ready(None)
+        } else {
+            Poll::Pending
+        }
+    }
+}
+
+impl<R> fmt::Debug for FromBlocking<R> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("FromBlocking")
+            .field("buffered", &self.chunks.len())
+            .field("reading", &self.read.is_some())
+            .field("done", &self.done)
+            .field("chunk_size", &self.chunk_size)
+            .field("max_buffered", &self.max_buffered)
+            .finish()
+    }
+}
@@ -61,8 +61,15 @@
 //!
 //! ## Features
 //!
-//! This crate has a single optional feature: `test`. This feature will enable
-//! the `test` module which adds testing facilities.
+//! This crate has the following optional features:
+//!
+//! * `test`: enables the `test` module which adds testing facilities.
+//! * `record`: enables recording the messages received by an actor, see
+//!   [`actor::Context::record_messages`]. Recordings can be replayed using
+//!   `test::replay` (requires the `test` feature as well).
+//! * `raw-socket`: enables the `net::raw` module which adds an ICMP socket.
+//!   Note that raw sockets require additional privileges, e.g. the
+//!   `CAP_NET_RAW` capability on Linux.
 
 #![feature(
     arc_new_cyclic,
@@ -85,11 +92,11 @@
     maybe_uninit_uninit_array,
     never_type,
     new_uninit,
+    once_cell,
     result_into_ok_or_err,
     stmt_expr_attributes,
     vec_spare_capacity
 )]
-#![cfg_attr(any(test, feature = "test"), feature(once_cell))]
 #![warn(
     anonymous_parameters,
     bare_trait_objects,
@@ -138,12 +145,21 @@ macro_rules! try_io {
 
 pub mod actor;
 pub mod actor_ref;
+pub mod bus;
 pub mod bytes;
+pub mod config;
+pub mod error;
+pub mod fs;
+pub mod io;
 pub mod log;
+pub mod metrics;
 pub mod net;
 pub mod pipe;
+pub mod process;
 pub mod quick_start;
 pub mod rt;
+pub mod serial;
+pub mod shutdown;
 pub mod spawn;
 pub mod supervisor;
 #[cfg(any(test, feature = "test"))]
@@ -154,6 +170,48 @@ pub mod trace;
 #[doc(hidden)]
 pub mod util;
 
+/// Instrument an asynchronous function actor.
+///
+/// This attribute macro wraps an `async fn` actor to log (at debug level) when
+/// the actor starts and stops, including how long it ran. Using
+/// `#[heph::actor(log_args)]` the arguments of the actor, excluding the actor's
+/// context (the first argument), are logged as well, which requires them to
+/// implement [`fmt::Debug`].
+///
+/// The macro doesn't change the signature of the function, so it can still be
+/// used as [`NewActor`] in the same way as a function without the attribute.
+///
+/// The other per-actor instrumentation is already done by the runtime for all
+/// actors: the number of received messages and the runtime are tracked in the
+/// [process statistics], every run of an actor is recorded in the [trace
+/// log] and panics can be converted into supervisor decisions using
+/// [`ActorOptions::catch_panics`].
+///
+/// [`fmt::Debug`]: std::fmt::Debug
+/// [process statistics]: crate::rt::RuntimeRef::process_stats
+/// [trace log]: crate::trace
+///
+/// # Examples
+///
+/// ```
+/// #![feature(never_type)]
+///
+/// use heph::actor;
+/// use heph::rt::ThreadLocal;
+///
+/// #[heph::actor(log_args)]
+/// async fn greeter_actor(mut ctx: actor::Context<String, ThreadLocal>, greeting: &'static str) {
+///     while let Ok(name) = ctx.receive_next().await {
+///         println!("{} {}", greeting, name);
+///     }
+/// }
+///
+/// // The function can be used as `NewActor` like any other actor.
+/// let new_actor = greeter_actor as fn(_, _) -> _;
+/// # drop(new_actor); // Silence dead code warnings.
+/// ```
+pub use heph_macros::actor;
+
 #[doc(no_inline)]
 pub use actor::{Actor, NewActor};
 #[doc(no_inline)]
@@ -46,10 +46,12 @@
 
 #[doc(hidden)]
 pub mod _private {
-    //! Private module to support the [`restart_supervisor!`] macro.
+    //! Private module to support the [`restart_supervisor!`] and [`actor`]
+    //! macros.
     //!
     //! [`restart_supervisor!`]: crate::restart_supervisor
+    //! [`actor`]: macro@crate::actor
 
     #[doc(no_inline)]
-    pub use log::warn;
+    pub use log::{debug, warn};
 }
@@ -0,0 +1,255 @@
+//! Module with the metrics exporter.
+//!
+//! The [`http_exporter`] actor serves the metrics of the runtime over HTTP in
+//! the [Prometheus text exposition format], allowing them to be scraped by
+//! Prometheus (or any other monitoring system that understands the format).
+//! The metrics are served at the `/metrics` path.
+//!
+//! The easiest way to run the exporter is using
+//! [`rt::Setup::enable_metrics_exporter`], but it can also be spawned like any
+//! other thread-safe actor.
+//!
+//! [Prometheus text exposition format]: https://prometheus.io/docs/instrumenting/exposition_formats/#text-based-format
+//! [`rt::Setup::enable_metrics_exporter`]: crate::rt::Setup::enable_metrics_exporter
+//!
+//! # Exported metrics
+//!
+//! | Name                              | Type    | Description                                                |
+//! |-----------------------------------|---------|------------------------------------------------------------|
+//! | `heph_uptime_seconds`             | gauge   | Time since the runtime was started.                        |
+//! | `heph_cpu_seconds_total`          | counter | CPU time used by the process.                              |
+//! | `heph_worker_threads`             | gauge   | Number of worker threads.                                  |
+//! | `heph_shared_ready_processes`     | gauge   | Number of thread-safe processes ready to run.              |
+//! | `heph_shared_inactive_processes`  | gauge   | Number of inactive thread-safe processes.                  |
+//! | `heph_shared_timers`              | gauge   | Number of timers of thread-safe processes.                 |
+//! | `heph_worker_ready_processes`     | gauge   | Number of thread-local processes ready to run, per worker. |
+//! | `heph_worker_processes_run_total` | counter | Number of processes run, per worker.                       |
+//!
+//! The per worker metrics have a `worker` label set to the id of the worker
+//! thread. These metrics are updated by the worker threads once per iteration
+//! of their event loop, so they can be slightly out of date.
+//!
+//! # Examples
+//!
+//! ```
+//! use heph::rt::{self, Runtime};
+//!
+//! # fn main() -> Result<(), rt::Error> {
+//! let address = "127.0.0.1:0".parse().unwrap();
+//! let runtime = Runtime::setup()
+//!     .enable_metrics_exporter(address)
+//!     .build()?;
+//! # drop(runtime);
+//! // Start the runtime as normal.
+//! # Ok(())
+//! # }
+//! ```
+
+use std::fmt::{self, Write};
+use std::io;
+use std::net::SocketAddr;
+use std::time::Duration;
+
+use log::{debug, error, warn};
+
+use crate::actor::messages::Terminate;
+use crate::actor::{self, NoMessages};
+use crate::net::{TcpListener, TcpStream};
+use crate::rt::{cpu_usage, shared, ThreadSafe};
+use crate::supervisor::SupervisorStrategy;
+use crate::timer::Deadline;
+use crate::util::either;
+
+/// Maximum size of a request head, larger requests are rejected.
+const MAX_REQUEST_SIZE: usize = 4096;
+
+/// Timeout for reading a request and writing the response.
+const TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Content-Type of the Prometheus text exposition format.
+const CONTENT_TYPE: &str = "text/plain; version=0.0.4; charset=utf-8";
+
+/// Actor that serves the runtime metrics over HTTP on `address`.
+///
+/// Connections are handled one at a time, each connection can make a single
+/// request. The actor stops when it receives a [`Terminate`] message.
+///
+/// See the [module documentation] for more information.
+///
+/// [module documentation]: crate::metrics
+///
+/// # Examples
+///
+/// Spawning the exporter manually.
+///
+/// ```
+/// use heph::metrics;
+/// use heph::rt::{self, Runtime};
+/// use heph::spawn::ActorOptions;
+/// use heph::supervisor::SupervisorStrategy;
+/// # use heph::actor::messages::Terminate;
+///
+/// # fn main() -> Result<(), rt::Error> {
+/// let mut runtime = Runtime::new()?;
+///
+/// let address = "127.0.0.1:0".parse().unwrap();
+/// let exporter = metrics::http_exporter as fn(_, _) -> _;
+/// let actor_ref = runtime.spawn(supervisor, exporter, address, ActorOptions::default());
+/// # actor_ref.try_send(Terminate).unwrap();
+///
+/// runtime.start()
+/// # }
+///
+/// fn supervisor(err: std::io::Error) -> SupervisorStrategy<std::net::SocketAddr> {
+///     eprintln!("metrics exporter failed: {}", err);
+///     SupervisorStrategy::Stop
+/// }
+/// ```
+pub async fn http_exporter(
+    mut ctx: actor::Context<Terminate, ThreadSafe>,
+    address: SocketAddr,
+) -> io::Result<()> {
+    let mut listener = TcpListener::bind(&mut ctx, address)?;
+    debug!("metrics exporter listening on {}", listener.local_addr()?);
+
+    // Once all actor references are dropped we can't receive any more
+    // messages, but we keep serving metrics.
+    let mut can_receive = true;
+    loop {
+        let accept = if can_receive {
+            match either(ctx.receive_next(), listener.accept()).await {
+                Ok(Ok(Terminate)) => return Ok(()),
+                Ok(Err(NoMessages)) => {
+                    can_receive = false;
+                    continue;
+                }
+                Err(accept) => accept,
+            }
+        } else {
+            listener.accept().await
+        };
+
+        let (unbound_stream, peer_address) = match accept {
+            Ok(accepted) => accepted,
+            Err(err) => {
+                warn!("metrics exporter failed to accept connection: {}", err);
+                continue;
+            }
+        };
+        let mut stream = unbound_stream.bind_to(&mut ctx)?;
+        let rt = ctx.runtime().clone();
+        let handle = handle_connection(&mut stream, &rt);
+        if let Err(err) = Deadline::after(&mut ctx, TIMEOUT, handle).await {
+            warn!(
+                "metrics exporter failed to handle request: {}: remote_address={}",
+                err, peer_address
+            );
+        }
+    }
+}
+
+/// Supervisor used by [`rt::Setup::enable_metrics_exporter`], which logs the
+/// error and stops the exporter.
+///
+/// [`rt::Setup::enable_metrics_exporter`]: crate::rt::Setup::enable_metrics_exporter
+pub(crate) fn supervisor(err: io::Error) -> SupervisorStrategy<SocketAddr> {
+    error!("metrics exporter failed: {}", err);
+    SupervisorStrategy::Stop
+}
+
+/// Handle a single request on `stream`.
+async fn handle_connection(stream: &mut TcpStream, rt: &ThreadSafe) -> io::Result<()> {
+    let mut buf = Vec::with_capacity(MAX_REQUEST_SIZE);
+    // Read until the end of the request head, we don't care about the body.
+    while !buf.windows(4).any(|w| w == b"\r\n\r\n") {
+        if buf.len() >= MAX_REQUEST_SIZE {
+            return send_response(stream, "431 Request Header Fields Too Large", b"").await;
+        }
+        if stream.recv(&mut buf).await? == 0 {
+            // Connection closed before the entire request was send.
+            return Ok(());
+        }
+    }
+
+    let request_line = buf.split(|b| *b == b'\r').next().unwrap_or_default();
+    let mut parts = request_line.split(|b| *b == b' ');
+    let method = parts.next().unwrap_or_default();
+    let path = parts.next().unwrap_or_default();
+    let path = path.split(|b| *b == b'?').next().unwrap_or_default();
+
+    if path != b"/metrics" {
+        send_response(stream, "404 Not Found", b"").await
+    } else if method == b"GET" || method == b"HEAD" {
+        let mut body = String::new();
+        // Writing to a `String` can't fail.
+        write_metrics(&mut body, rt.shared_internals()).unwrap();
+        let head = response_head("200 OK", body.len());
+        stream.send_all(head.as_bytes()).await?;
+        if method == b"GET" {
+            stream.send_all(body.as_bytes()).await?;
+        }
+        Ok(())
+    } else {
+        send_response(stream, "405 Method Not Allowed", b"").await
+    }
+}
+
+/// Send a response with `status` and `body`.
+async fn send_response(stream: &mut TcpStream, status: &str, body: &[u8]) -> io::Result<()> {
+    let head = response_head(status, body.len());
+    stream.send_all(head.as_bytes()).await?;
+    stream.send_all(body).await
+}
+
+/// Returns the head of a response with `status` and a body of `body_length`
+/// bytes.
+fn response_head(status: &str, body_length: usize) -> String {
+    format!(
+        "HTTP/1.1 {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+        status, CONTENT_TYPE, body_length
+    )
+}
+
+/// Write the metrics of the runtime in the Prometheus text exposition format
+/// to `buf`.
+fn write_metrics(buf: &mut String, internals: &shared::RuntimeInternals) -> fmt::Result {
+    let shared = internals.metrics();
+    let workers = internals.worker_metrics();
+    let cpu_time = cpu_usage(libc::CLOCK_PROCESS_CPUTIME_ID);
+
+    #[rustfmt::skip]
+    let metrics: [(&str, &str, &str, &dyn fmt::Display); 6] = [
+        ("heph_uptime_seconds", "gauge", "Time since the runtime was started.", &internals.uptime().as_secs_f64()),
+        ("heph_cpu_seconds_total", "counter", "CPU time used by the process.", &cpu_time.as_secs_f64()),
+        ("heph_worker_threads", "gauge", "Number of worker threads.", &workers.len()),
+        ("heph_shared_ready_processes", "gauge", "Number of thread-safe processes ready to run.", &shared.scheduler.ready),
+        ("heph_shared_inactive_processes", "gauge", "Number of inactive thread-safe processes.", &shared.scheduler.inactive),
+        ("heph_shared_timers", "gauge", "Number of timers of thread-safe processes.", &shared.timers.timers),
+    ];
+    for (name, kind, help, value) in metrics {
+        write_header(buf, name, kind, help)?;
+        writeln!(buf, "{} {}", name, value)?;
+    }
+
+    let name = "heph_worker_ready_processes";
+    let help = "Number of thread-local processes ready to run.";
+    write_header(buf, name, "gauge", help)?;
+    for (id, worker) in (1..).zip(workers) {
+        let ready = worker.ready_processes();
+        writeln!(buf, "{}{{worker=\"{}\"}} {}", name, id, ready)?;
+    }
+
+    let name = "heph_worker_processes_run_total";
+    write_header(buf, name, "counter", "Number of processes run.")?;
+    for (id, worker) in (1..).zip(workers) {
+        let run = worker.processes_run();
+        writeln!(buf, "{}{{worker=\"{}\"}} {}", name, id, run)?;
+    }
+    Ok(())
+}
+
+/// Write the `HELP` and `TYPE` lines for metric `name`.
+fn write_header(buf: &mut String, name: &str, kind: &str, help: &str) -> fmt::Result {
+    writeln!(buf, "# HELP {} {}", name, help)?;
+    writeln!(buf, "# TYPE {} {}", name, kind)
+}
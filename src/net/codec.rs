@@ -0,0 +1,497 @@
+//! Module with codecs to split a stream of bytes into frames.
+//!
+//! Most protocols send messages, or frames, over a byte stream such as a TCP
+//! connection. The [`Decoder`] trait defines how to split the received bytes
+//! into frames and the [`Encoder`] trait how to write frames as bytes. The
+//! following codecs are provided:
+//!
+//!  * [`LengthDelimited`]: frames prefixed with their length,
+//!  * [`Lines`]: newline-delimited (UTF-8) lines, and
+//!  * [`FixedSize`]: frames of a fixed size.
+//!
+//! [`Framed`] combines a codec with a [`TcpStream`], implementing [`Stream`]
+//! to receive frames and providing [`Framed::send`] to send them.
+//!
+//! # Examples
+//!
+//! An actor that echos all lines it receives.
+//!
+//! ```
+//! #![feature(never_type)]
+//!
+//! use std::io;
+//! use std::net::SocketAddr;
+//!
+//! use heph::actor;
+//! use heph::net::codec::{Framed, Lines};
+//! use heph::net::TcpStream;
+//! use heph::rt::ThreadLocal;
+//!
+//! async fn actor(mut ctx: actor::Context<!, ThreadLocal>, address: SocketAddr) -> io::Result<()> {
+//!     let stream = TcpStream::connect(&mut ctx, address)?.await?;
+//!     let mut framed = Framed::new(stream, Lines::new());
+//!
+//!     while let Some(line) = framed.next().await {
+//!         let line = line?;
+//!         framed.send(line).await?;
+//!     }
+//!     Ok(())
+//! }
+//! #
+//! # drop(actor); // Silent dead code warnings.
+//! ```
+
+use std::borrow::BorrowMut;
+use std::io;
+use std::pin::Pin;
+use std::stream::Stream;
+use std::task::{self, Poll};
+
+use crate::net::TcpStream;
+use crate::util::{next, Next};
+
+/// Default capacity of the read buffer of [`Framed`], 8 KB.
+const DEFAULT_BUF_SIZE: usize = 8 * 1024;
+
+/// Trait to decode frames from bytes.
+pub trait Decoder {
+    /// Type of the decoded frames.
+    type Item;
+    /// Error returned when decoding fails.
+    ///
+    /// This also needs to support I/O errors as it's returned by [`Framed`].
+    type Error: From<io::Error>;
+
+    /// Attempt to decode a single frame from the start of `buf`.
+    ///
+    /// Returns the frame and the number of bytes of `buf` used by it, or
+    /// `None` if `buf` doesn't contain a complete frame yet.
+    fn decode(&mut self, buf: &[u8]) -> Result<Option<(Self::Item, usize)>, Self::Error>;
+
+    /// Decode the remaining bytes once the end of the stream is reached.
+    ///
+    /// The default implementation calls [`Decoder::decode`] and returns an
+    /// [`UnexpectedEof`] error if `buf` still contains a partial frame.
+    ///
+    /// [`UnexpectedEof`]: io::ErrorKind::UnexpectedEof
+    fn decode_eof(&mut self, buf: &[u8]) -> Result<Option<(Self::Item, usize)>, Self::Error> {
+        match self.decode(buf)? {
+            Some(frame) => Ok(Some(frame)),
+            None if buf.is_empty() => Ok(None),
+            None => Err(io::Error::from(io::ErrorKind::UnexpectedEof).into()),
+        }
+    }
+}
+
+/// Trait to encode frames of type `Item` into bytes.
+pub trait Encoder<Item> {
+    /// Error returned when encoding fails.
+    ///
+    /// This also needs to support I/O errors as it's returned by [`Framed`].
+    type Error: From<io::Error>;
+
+    /// Encode `item`, appending it to `buf`.
+    fn encode(&mut self, item: Item, buf: &mut Vec<u8>) -> Result<(), Self::Error>;
+}
+
+/// Codec for frames prefixed with their length.
+///
+/// The length is encoded as 32 bit unsigned integer in big-endian and doesn't
+/// include the length itself. Frames larger than the maximum length (8 MB by
+/// default) are refused with an [`InvalidData`] error.
+///
+/// [`InvalidData`]: io::ErrorKind::InvalidData
+#[derive(Copy, Clone, Debug)]
+pub struct LengthDelimited {
+    max_length: usize,
+}
+
+impl LengthDelimited {
+    /// Size of the length prefix.
+    const LEN_SIZE: usize = 4;
+
+    /// Create a new `LengthDelimited` codec.
+    pub const fn new() -> LengthDelimited {
+        LengthDelimited {
+            max_length: 8 * 1024 * 1024,
+        }
+    }
+
+    /// Set the maximum length of a frame, excluding the length prefix.
+    pub const fn with_max_length(mut self, max_length: usize) -> LengthDelimited {
+        self.max_length = max_length;
+        self
+    }
+
+    /// Returns the maximum length of a frame.
+    pub const fn max_length(&self) -> usize {
+        self.max_length
+    }
+}
+
+impl Default for LengthDelimited {
+    fn default() -> LengthDelimited {
+        LengthDelimited::new()
+    }
+}
+
+impl Decoder for LengthDelimited {
+    type Item = Vec<u8>;
+    type Error = io::Error;
+
+    fn decode(&mut self, buf: &[u8]) -> io::Result<Option<(Vec<u8>, usize)>> {
+        if buf.len() < Self::LEN_SIZE {
+            return Ok(None);
+        }
+        let mut len = [0; Self::LEN_SIZE];
+        len.copy_from_slice(&buf[..Self::LEN_SIZE]);
+        let len = u32::from_be_bytes(len) as usize;
+        if len > self.max_length {
+            return Err(too_long());
+        }
+
+        let end = Self::LEN_SIZE + len;
+        if buf.len() < end {
+            return Ok(None);
+        }
+        Ok(Some((buf[Self::LEN_SIZE..end].to_vec(), end)))
+    }
+}
+
+impl<B> Encoder<B> for LengthDelimited
+where
+    B: AsRef<[u8]>,
+{
+    type Error = io::Error;
+
+    fn encode(&mut self, item: B, buf: &mut Vec<u8>) -> io::Result<()> {
+        let item = item.as_ref();
+        if item.len() > self.max_length || item.len() > u32::MAX as usize {
+            return Err(too_long());
+        }
+        buf.extend_from_slice(&(item.len() as u32).to_be_bytes());
+        buf.extend_from_slice(item);
+        Ok(())
+    }
+}
+
+/// Codec for newline-delimited lines.
+///
+/// Lines are decoded as [`String`], without the newline (`\n`) or carriage
+/// return and newline (`\r\n`). Lines that are not valid UTF-8 or longer than
+/// the maximum length (64 KB by default) are refused with an [`InvalidData`]
+/// error. The last line of the stream doesn't need to end with a newline.
+///
+/// When encoding a newline is added to each line.
+///
+/// [`InvalidData`]: io::ErrorKind::InvalidData
+#[derive(Copy, Clone, Debug)]
+pub struct Lines {
+    max_length: usize,
+}
+
+impl Lines {
+    /// Create a new `Lines` codec.
+    pub const fn new() -> Lines {
+        Lines {
+            max_length: 64 * 1024,
+        }
+    }
+
+    /// Set the maximum length of a line, excluding the newline.
+    pub const fn with_max_length(mut self, max_length: usize) -> Lines {
+        self.max_length = max_length;
+        self
+    }
+
+    /// Returns the maximum length of a line.
+    pub const fn max_length(&self) -> usize {
+        self.max_length
+    }
+
+    /// Convert `line` to a `String`, removing the carriage return (if any).
+    fn to_line(line: &[u8]) -> io::Result<String> {
+        let line = line.strip_suffix(b"\r").unwrap_or(line);
+        match std::str::from_utf8(line) {
+            Ok(line) => Ok(line.to_owned()),
+            Err(err) => Err(io::Error::new(io::ErrorKind::InvalidData, err)),
+        }
+    }
+}
+
+impl Default for Lines {
+    fn default() -> Lines {
+        Lines::new()
+    }
+}
+
+impl Decoder for Lines {
+    type Item = String;
+    type Error = io::Error;
+
+    fn decode(&mut self, buf: &[u8]) -> io::Result<Option<(String, usize)>> {
+        match buf.iter().position(|b| *b == b'\n') {
+            Some(idx) if idx > self.max_length => Err(too_long()),
+            Some(idx) => Lines::to_line(&buf[..idx]).map(|line| Some((line, idx + 1))),
+            None if buf.len() > self.max_length => Err(too_long()),
+            None => Ok(None),
+        }
+    }
+
+    fn decode_eof(&mut self, buf: &[u8]) -> io::Result<Option<(String, usize)>> {
+        match self.decode(buf)? {
+            Some(line) => Ok(Some(line)),
+            None if buf.is_empty() => Ok(None),
+            // Last line without a newline.
+            None => Lines::to_line(buf).map(|line| Some((line, buf.len()))),
+        }
+    }
+}
+
+impl<S> Encoder<S> for Lines
+where
+    S: AsRef<str>,
+{
+    type Error = io::Error;
+
+    fn encode(&mut self, item: S, buf: &mut Vec<u8>) -> io::Result<()> {
+        let item = item.as_ref();
+        if item.len() > self.max_length {
+            return Err(too_long());
+        }
+        buf.extend_from_slice(item.as_bytes());
+        buf.push(b'\n');
+        Ok(())
+    }
+}
+
+/// Codec for frames of a fixed size.
+///
+/// Encoding a frame of a different size returns an [`InvalidInput`] error.
+///
+/// [`InvalidInput`]: io::ErrorKind::InvalidInput
+#[derive(Copy, Clone, Debug)]
+pub struct FixedSize {
+    size: usize,
+}
+
+impl FixedSize {
+    /// Create a new `FixedSize` codec for frames of `size` bytes.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `size` is zero.
+    pub fn new(size: usize) -> FixedSize {
+        assert!(size != 0, "frame size can't be zero");
+        FixedSize { size }
+    }
+
+    /// Returns the size of the frames.
+    pub const fn size(&self) -> usize {
+        self.size
+    }
+}
+
+impl Decoder for FixedSize {
+    type Item = Vec<u8>;
+    type Error = io::Error;
+
+    fn decode(&mut self, buf: &[u8]) -> io::Result<Option<(Vec<u8>, usize)>> {
+        if buf.len() < self.size {
+            Ok(None)
+        } else {
+            Ok(Some((buf[..self.size].to_vec(), self.size)))
+        }
+    }
+}
+
+impl<B> Encoder<B> for FixedSize
+where
+    B: AsRef<[u8]>,
+{
+    type Error = io::Error;
+
+    fn encode(&mut self, item: B, buf: &mut Vec<u8>) -> io::Result<()> {
+        let item = item.as_ref();
+        if item.len() == self.size {
+            buf.extend_from_slice(item);
+            Ok(())
+        } else {
+            Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "frame has an invalid size",
+            ))
+        }
+    }
+}
+
+/// Returns an error for a frame that is too long.
+fn too_long() -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, "frame too long")
+}
+
+/// A [`TcpStream`] combined with a codec `C` to receive and send frames.
+///
+/// Frames are received using the [`Stream`] implementation, or
+/// [`Framed::next`], using the [`Decoder`] implementation of `C`. The stream
+/// ends once the connection is closed by the peer.
+///
+/// Frames are send using [`Framed::send`], or [`Framed::feed`] and
+/// [`Framed::flush`] to send multiple frames at once, using the [`Encoder`]
+/// implementation of `C`.
+///
+/// The stream `S` can be an owned `TcpStream` or a mutable reference to one.
+///
+/// See the [module documentation] for an example.
+///
+/// [module documentation]: crate::net::codec
+#[derive(Debug)]
+pub struct Framed<S, C> {
+    stream: S,
+    codec: C,
+    read_buf: Vec<u8>,
+    /// Number of bytes in `read_buf` that are already decoded.
+    read_pos: usize,
+    write_buf: Vec<u8>,
+    /// Whether or not we've reached the end of the stream.
+    eof: bool,
+}
+
+impl<S, C> Framed<S, C>
+where
+    S: BorrowMut<TcpStream>,
+{
+    /// Create a new `Framed` using `codec` to decode and encode frames.
+    pub fn new(stream: S, codec: C) -> Framed<S, C> {
+        Framed {
+            stream,
+            codec,
+            read_buf: Vec::with_capacity(DEFAULT_BUF_SIZE),
+            read_pos: 0,
+            write_buf: Vec::new(),
+            eof: false,
+        }
+    }
+
+    /// Returns a reference to the codec.
+    pub const fn codec(&self) -> &C {
+        &self.codec
+    }
+
+    /// Returns a mutable reference to the codec.
+    pub fn codec_mut(&mut self) -> &mut C {
+        &mut self.codec
+    }
+
+    /// Returns a reference to the underlying stream.
+    pub fn get_ref(&self) -> &TcpStream {
+        self.stream.borrow()
+    }
+
+    /// Returns a mutable reference to the underlying stream.
+    ///
+    /// # Notes
+    ///
+    /// Reading from or writing to the stream directly will mess up the order
+    /// of the data with respect to the buffered data.
+    pub fn get_mut(&mut self) -> &mut TcpStream {
+        self.stream.borrow_mut()
+    }
+
+    /// Returns the underlying stream and codec.
+    ///
+    /// # Notes
+    ///
+    /// Any buffered data is lost, call [`Framed::flush`] to send the buffered
+    /// frames first.
+    pub fn into_inner(self) -> (S, C) {
+        (self.stream, self.codec)
+    }
+
+    /// Returns a [`Future`] that receives the next frame.
+    ///
+    /// [`Future`]: std::future::Future
+    pub fn next(&mut self) -> Next<&mut Self>
+    where
+        C: Decoder + Unpin,
+        S: Unpin,
+    {
+        next(self)
+    }
+
+    /// Encode `item` into the write buffer, without sending it.
+    ///
+    /// Use [`Framed::flush`] to send the buffered frames.
+    pub fn feed<Item>(&mut self, item: Item) -> Result<(), C::Error>
+    where
+        C: Encoder<Item>,
+    {
+        self.codec.encode(item, &mut self.write_buf)
+    }
+
+    /// Send all buffered frames.
+    pub async fn flush(&mut self) -> io::Result<()> {
+        if self.write_buf.is_empty() {
+            return Ok(());
+        }
+        let res = self.stream.borrow_mut().send_all(&self.write_buf).await;
+        self.write_buf.clear();
+        res
+    }
+
+    /// Send `item`, and any previously buffered frames.
+    pub async fn send<Item>(&mut self, item: Item) -> Result<(), C::Error>
+    where
+        C: Encoder<Item>,
+    {
+        self.feed(item)?;
+        self.flush().await.map_err(Into::into)
+    }
+}
+
+impl<S, C> Stream for Framed<S, C>
+where
+    S: BorrowMut<TcpStream> + Unpin,
+    C: Decoder + Unpin,
+{
+    type Item = Result<C::Item, C::Error>;
+
+    fn poll_next(self: Pin<&mut Self>, _: &mut task::Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = Pin::into_inner(self);
+        loop {
+            let buf = &this.read_buf[this.read_pos..];
+            let res = if this.eof {
+                this.codec.decode_eof(buf)
+            } else {
+                this.codec.decode(buf)
+            };
+            match res {
+                Ok(Some((item, n))) => {
+                    this.read_pos += n;
+                    return Poll::Ready(Some(Ok(item)));
+                }
+                Ok(None) if this.eof => return Poll::Ready(None),
+                Ok(None) => {}
+                Err(err) => return Poll::Ready(Some(Err(err))),
+            }
+
+            // Need more data, first make room in the buffer by removing the
+            // already decoded bytes.
+            if this.read_pos != 0 {
+                drop(this.read_buf.drain(..this.read_pos));
+                this.read_pos = 0;
+            }
+            if this.read_buf.len() == this.read_buf.capacity() {
+                this.read_buf.reserve(DEFAULT_BUF_SIZE);
+            }
+
+            match this.stream.borrow_mut().try_recv(&mut this.read_buf) {
+                Ok(0) => this.eof = true,
+                Ok(_) => {}
+                Err(ref err) if err.kind() == io::ErrorKind::WouldBlock => return Poll::Pending,
+                Err(ref err) if err.kind() == io::ErrorKind::Interrupted => {}
+                Err(err) => return Poll::Ready(Some(Err(err.into()))),
+            }
+        }
+    }
+}
@@ -9,11 +9,21 @@
 //! * [User Datagram Protocol] (UDP) only provides a single socket type:
 //!   * [`UdpSocket`].
 //!
+//! To split the bytes received from a stream into frames, and the reverse, the
+//! [codec] module provides the [`Framed`] type and some common codecs.
+//!
+//! Furthermore the [raw socket] module, enabled by the `raw-socket` feature,
+//! provides an [`IcmpSocket`] to send ICMP echo requests, i.e. ping hosts.
+//!
 //! [Transmission Control Protocol]: crate::net::tcp
 //! [TCP stream]: crate::net::TcpStream
 //! [TCP listening socket]: crate::net::TcpListener
 //! [TCP server]: crate::net::TcpServer
 //! [User Datagram Protocol]: crate::net::udp
+//! [codec]: crate::net::codec
+//! [`Framed`]: crate::net::codec::Framed
+//! [raw socket]: crate::net::raw
+//! [`IcmpSocket`]: crate::net::raw::IcmpSocket
 //!
 //! # I/O with Heph's socket
 //!
@@ -49,11 +59,15 @@ use std::net::SocketAddr;
 
 use socket2::SockAddr;
 
+pub mod codec;
+#[cfg(feature = "raw-socket")]
+#[doc(cfg(feature = "raw-socket"))]
+pub mod raw;
 pub mod tcp;
 pub mod udp;
 
 #[doc(no_inline)]
-pub use tcp::{TcpListener, TcpServer, TcpStream};
+pub use tcp::{BufferedStream, TcpListener, TcpServer, TcpStream};
 #[doc(no_inline)]
 pub use udp::UdpSocket;
 /// Convert a `socket2:::SockAddr` into a `std::net::SocketAddr`.
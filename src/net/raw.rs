@@ -0,0 +1,428 @@
+//! Raw socket related types.
+//!
+//! See [`IcmpSocket`].
+//!
+//! # Notes
+//!
+//! This module requires the `raw-socket` feature. Creating raw sockets requires
+//! additional privileges, on Linux the process needs the `CAP_NET_RAW`
+//! capability.
+
+use std::fmt;
+use std::future::Future;
+use std::io;
+use std::net::{IpAddr, SocketAddr};
+use std::os::unix::io::AsRawFd;
+use std::pin::Pin;
+use std::task::{self, Poll};
+
+use mio::unix::SourceFd;
+use mio::Interest;
+use socket2::{Domain, Protocol, Socket, Type};
+
+use crate::net::convert_address;
+use crate::{actor, rt};
+
+/// ICMPv4 echo request type.
+const ICMPV4_ECHO_REQUEST: u8 = 8;
+/// ICMPv4 echo reply type.
+const ICMPV4_ECHO_REPLY: u8 = 0;
+/// ICMPv6 echo request type.
+const ICMPV6_ECHO_REQUEST: u8 = 128;
+/// ICMPv6 echo reply type.
+const ICMPV6_ECHO_REPLY: u8 = 129;
+
+/// Size of the ICMP echo header: type, code, checksum, identifier and
+/// sequence number.
+const ECHO_HEADER_LEN: usize = 8;
+
+/// Size of the buffer used to receive packets.
+const RECV_BUF_SIZE: usize = 4096;
+
+/// An Internet Control Message Protocol (ICMP) socket.
+///
+/// The socket can be used to send ICMP echo requests and receive echo replies,
+/// i.e. ping other hosts. This is useful to create actors that check the
+/// health of other hosts.
+///
+/// The socket uses a raw socket, which means the process requires the
+/// `CAP_NET_RAW` capability (on Linux). See the [module documentation].
+///
+/// [module documentation]: crate::net::raw
+///
+/// # Notes
+///
+/// Raw ICMP sockets receive all ICMP packets send to the host, including
+/// replies to echo requests send by other sockets or processes. Use the
+/// identifier and sequence number of the [`EchoReply`] to match replies to the
+/// requests send.
+///
+/// # Examples
+///
+/// ```
+/// #![feature(never_type)]
+///
+/// use std::io;
+/// use std::net::IpAddr;
+///
+/// use heph::actor;
+/// use heph::net::raw::IcmpSocket;
+/// use heph::rt::ThreadLocal;
+///
+/// /// Actor that pings `target` once.
+/// async fn health_checker(mut ctx: actor::Context<!, ThreadLocal>, target: IpAddr) -> io::Result<()> {
+///     let mut socket = IcmpSocket::bind(&mut ctx, "0.0.0.0".parse().unwrap())?;
+///     let identifier = 1234;
+///     socket.send_echo(target, identifier, 1, b"ping").await?;
+///     loop {
+///         let reply = socket.recv_echo().await?;
+///         if reply.source() == target && reply.identifier() == identifier {
+///             println!("host {} is up", target);
+///             return Ok(());
+///         }
+///     }
+/// }
+/// # drop(health_checker); // Silence dead code warnings.
+/// ```
+pub struct IcmpSocket {
+    socket: Socket,
+    /// `true` if this is an ICMPv6 socket.
+    is_ipv6: bool,
+    /// Buffer used to receive packets.
+    buf: Vec<u8>,
+}
+
+impl IcmpSocket {
+    /// Create an ICMP socket bound to the `local` address.
+    ///
+    /// Binding to an IPv4 address creates an ICMPv4 socket, binding to an IPv6
+    /// address an ICMPv6 socket. Use the unspecified address (e.g. `0.0.0.0`)
+    /// to send and receive on all interfaces.
+    ///
+    /// # Notes
+    ///
+    /// The ICMP socket is also [bound] to the actor that owns the
+    /// `actor::Context`, which means the actor will be run every time the
+    /// socket is ready to be read from or write to.
+    ///
+    /// [bound]: crate::actor::Bound
+    pub fn bind<M, RT>(ctx: &mut actor::Context<M, RT>, local: IpAddr) -> io::Result<IcmpSocket>
+    where
+        RT: rt::Access,
+    {
+        let (domain, protocol) = match local {
+            IpAddr::V4(_) => (Domain::IPV4, Protocol::ICMPV4),
+            IpAddr::V6(_) => (Domain::IPV6, Protocol::ICMPV6),
+        };
+        let socket = Socket::new(domain, Type::RAW, Some(protocol))?;
+        socket.set_nonblocking(true)?;
+        socket.bind(&SocketAddr::new(local, 0).into())?;
+        let interest = Interest::READABLE | Interest::WRITABLE;
+        ctx.runtime()
+            .register(&mut SourceFd(&socket.as_raw_fd()), interest)?;
+        Ok(IcmpSocket {
+            socket,
+            is_ipv6: local.is_ipv6(),
+            buf: Vec::with_capacity(RECV_BUF_SIZE),
+        })
+    }
+
+    /// Attempt to send an echo request to the `target` address.
+    ///
+    /// If the request currently can't be send this will return an error with
+    /// the [kind] set to [`ErrorKind::WouldBlock`]. Most users should prefer to
+    /// use [`IcmpSocket::send_echo`].
+    ///
+    /// [kind]: io::Error::kind
+    /// [`ErrorKind::WouldBlock`]: io::ErrorKind::WouldBlock
+    pub fn try_send_echo(
+        &mut self,
+        target: IpAddr,
+        identifier: u16,
+        sequence: u16,
+        payload: &[u8],
+    ) -> io::Result<()> {
+        if target.is_ipv6() != self.is_ipv6 {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "target address family doesn't match the socket's",
+            ));
+        }
+        let packet = echo_request(self.is_ipv6, identifier, sequence, payload);
+        let target = SocketAddr::new(target, 0).into();
+        self.socket.send_to(&packet, &target).map(|_| ())
+    }
+
+    /// Send an echo request to the `target` address. Returns a [`Future`]
+    /// that on success returns `io::Result<()>`.
+    pub fn send_echo<'a, 'b>(
+        &'a mut self,
+        target: IpAddr,
+        identifier: u16,
+        sequence: u16,
+        payload: &'b [u8],
+    ) -> SendEcho<'a, 'b> {
+        SendEcho {
+            socket: self,
+            target,
+            identifier,
+            sequence,
+            payload,
+        }
+    }
+
+    /// Attempt to receive an echo reply.
+    ///
+    /// All other ICMP packets received are ignored. If no reply can currently
+    /// be received this will return an error with the [kind] set to
+    /// [`ErrorKind::WouldBlock`]. Most users should prefer to use
+    /// [`IcmpSocket::recv_echo`].
+    ///
+    /// [kind]: io::Error::kind
+    /// [`ErrorKind::WouldBlock`]: io::ErrorKind::WouldBlock
+    pub fn try_recv_echo(&mut self) -> io::Result<EchoReply> {
+        loop {
+            self.buf.clear();
+            let (n, address) = self.socket.recv_from(self.buf.spare_capacity_mut())?;
+            // Safety: just read the bytes.
+            unsafe { self.buf.set_len(n) }
+            let source = convert_address(address)?.ip();
+            if let Some(reply) = parse_echo_reply(self.is_ipv6, source, &self.buf) {
+                return Ok(reply);
+            }
+            // Not an echo reply, try the next packet.
+        }
+    }
+
+    /// Receive an echo reply. Returns a [`Future`] that on success returns
+    /// the [`EchoReply`].
+    pub fn recv_echo(&mut self) -> RecvEcho<'_> {
+        RecvEcho { socket: self }
+    }
+
+    /// Get the value of the `SO_ERROR` option on this socket.
+    ///
+    /// This will retrieve the stored error in the underlying socket, clearing
+    /// the field in the process. This can be useful for checking errors between
+    /// calls.
+    pub fn take_error(&mut self) -> io::Result<Option<io::Error>> {
+        self.socket.take_error()
+    }
+}
+
+/// Create an echo request packet.
+fn echo_request(is_ipv6: bool, identifier: u16, sequence: u16, payload: &[u8]) -> Vec<u8> {
+    let kind = if is_ipv6 {
+        ICMPV6_ECHO_REQUEST
+    } else {
+        ICMPV4_ECHO_REQUEST
+    };
+    let mut packet = Vec::with_capacity(ECHO_HEADER_LEN + payload.len());
+    packet.extend_from_slice(&[kind, 0, 0, 0]);
+    packet.extend_from_slice(&identifier.to_be_bytes());
+    packet.extend_from_slice(&sequence.to_be_bytes());
+    packet.extend_from_slice(payload);
+    // For ICMPv6 the kernel calculates the checksum, as it includes the IPv6
+    // pseudo header.
+    if !is_ipv6 {
+        let checksum = checksum(&packet);
+        packet[2..4].copy_from_slice(&checksum.to_be_bytes());
+    }
+    packet
+}
+
+/// Parse an echo reply from `packet`, returns `None` if the packet is not an
+/// echo reply.
+fn parse_echo_reply(is_ipv6: bool, source: IpAddr, packet: &[u8]) -> Option<EchoReply> {
+    let (packet, kind) = if is_ipv6 {
+        (packet, ICMPV6_ECHO_REPLY)
+    } else {
+        // Raw ICMPv4 sockets also receive the IPv4 header.
+        let header_len = usize::from(packet.first()? & 0x0f) * 4;
+        (packet.get(header_len..)?, ICMPV4_ECHO_REPLY)
+    };
+    if packet.len() < ECHO_HEADER_LEN || packet[0] != kind || packet[1] != 0 {
+        return None;
+    }
+    Some(EchoReply {
+        source,
+        identifier: u16::from_be_bytes([packet[4], packet[5]]),
+        sequence: u16::from_be_bytes([packet[6], packet[7]]),
+        payload: packet[ECHO_HEADER_LEN..].to_vec(),
+    })
+}
+
+/// Calculate the internet checksum (RFC 1071) of `data`.
+fn checksum(data: &[u8]) -> u16 {
+    let mut sum: u32 = data
+        .chunks(2)
+        .map(|chunk| match *chunk {
+            [a, b] => u32::from(u16::from_be_bytes([a, b])),
+            [a] => u32::from(u16::from_be_bytes([a, 0])),
+            _ => unreachable!(),
+        })
+        .sum();
+    while (sum >> 16) != 0 {
+        sum = (sum & 0xffff) + (sum >> 16);
+    }
+    !(sum as u16)
+}
+
+impl fmt::Debug for IcmpSocket {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.socket.fmt(f)
+    }
+}
+
+impl<RT: rt::Access> actor::Bound<RT> for IcmpSocket {
+    type Error = io::Error;
+
+    fn bind_to<M>(&mut self, ctx: &mut actor::Context<M, RT>) -> io::Result<()> {
+        let interest = Interest::READABLE | Interest::WRITABLE;
+        ctx.runtime()
+            .reregister(&mut SourceFd(&self.socket.as_raw_fd()), interest)
+    }
+}
+
+/// An ICMP echo reply, received by [`IcmpSocket::recv_echo`].
+#[derive(Clone, Debug)]
+pub struct EchoReply {
+    source: IpAddr,
+    identifier: u16,
+    sequence: u16,
+    payload: Vec<u8>,
+}
+
+impl EchoReply {
+    /// Returns the address of the host that send the reply.
+    pub const fn source(&self) -> IpAddr {
+        self.source
+    }
+
+    /// Returns the identifier of the echo request.
+    pub const fn identifier(&self) -> u16 {
+        self.identifier
+    }
+
+    /// Returns the sequence number of the echo request.
+    pub const fn sequence(&self) -> u16 {
+        self.sequence
+    }
+
+    /// Returns the payload of the echo request.
+    pub fn payload(&self) -> &[u8] {
+        &self.payload
+    }
+}
+
+/// The [`Future`] behind [`IcmpSocket::send_echo`].
+#[derive(Debug)]
+#[must_use = "futures do nothing unless you `.await` or poll them"]
+pub struct SendEcho<'a, 'b> {
+    socket: &'a mut IcmpSocket,
+    target: IpAddr,
+    identifier: u16,
+    sequence: u16,
+    payload: &'b [u8],
+}
+
+impl<'a, 'b> Future for SendEcho<'a, 'b> {
+    type Output = io::Result<()>;
+
+    fn poll(self: Pin<&mut Self>, _: &mut task::Context<'_>) -> Poll<Self::Output> {
+        let SendEcho {
+            socket,
+            target,
+            identifier,
+            sequence,
+            payload,
+        } = Pin::into_inner(self);
+        try_io!(socket.try_send_echo(*target, *identifier, *sequence, *payload))
+    }
+}
+
+/// The [`Future`] behind [`IcmpSocket::recv_echo`].
+#[derive(Debug)]
+#[must_use = "futures do nothing unless you `.await` or poll them"]
+pub struct RecvEcho<'a> {
+    socket: &'a mut IcmpSocket,
+}
+
+impl<'a> Future for RecvEcho<'a> {
+    type Output = io::Result<EchoReply>;
+
+    fn poll(self: Pin<&mut Self>, _: &mut task::Context<'_>) -> Poll<Self::Output> {
+        try_io!(self.socket.try_recv_echo())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+
+    use super::{checksum, echo_request, parse_echo_reply};
+
+    #[test]
+    fn checksum_rfc1071() {
+        // Example from RFC 1071, section 3.
+        let data = [0x00, 0x01, 0xf2, 0x03, 0xf4, 0xf5, 0xf6, 0xf7];
+        assert_eq!(checksum(&data), !0xddf2);
+        // Odd number of bytes.
+        assert_eq!(checksum(&[0xff]), !0xff00);
+    }
+
+    #[test]
+    fn echo_request_ipv4() {
+        let packet = echo_request(false, 0x1234, 1, b"ping");
+        assert_eq!(packet[..2], [8, 0]);
+        assert_eq!(packet[4..8], [0x12, 0x34, 0x00, 0x01]);
+        assert_eq!(&packet[8..], b"ping");
+        // Checksum of a packet, including its checksum, must be zero.
+        assert_eq!(checksum(&packet), 0);
+    }
+
+    #[test]
+    fn echo_request_ipv6() {
+        let packet = echo_request(true, 0x1234, 1, b"ping");
+        // Checksum is left to the kernel.
+        assert_eq!(packet[..4], [128, 0, 0, 0]);
+        assert_eq!(packet[4..8], [0x12, 0x34, 0x00, 0x01]);
+        assert_eq!(&packet[8..], b"ping");
+    }
+
+    #[test]
+    fn parse_ipv4_echo_reply() {
+        let source = IpAddr::V4(Ipv4Addr::LOCALHOST);
+        // Minimal IPv4 header (IHL of 5) followed by the reply.
+        let mut packet = vec![0x45];
+        packet.resize(20, 0);
+        packet.extend_from_slice(&[0, 0, 0, 0, 0x12, 0x34, 0x00, 0x02]);
+        packet.extend_from_slice(b"pong");
+
+        let reply = parse_echo_reply(false, source, &packet).unwrap();
+        assert_eq!(reply.source(), source);
+        assert_eq!(reply.identifier(), 0x1234);
+        assert_eq!(reply.sequence(), 2);
+        assert_eq!(reply.payload(), b"pong");
+
+        // Echo request, not a reply.
+        packet[20] = 8;
+        assert!(parse_echo_reply(false, source, &packet).is_none());
+        // Too short.
+        assert!(parse_echo_reply(false, source, &packet[..24]).is_none());
+    }
+
+    #[test]
+    fn parse_ipv6_echo_reply() {
+        let source = IpAddr::V6(Ipv6Addr::LOCALHOST);
+        let packet = [129, 0, 0, 0, 0x12, 0x34, 0x00, 0x03];
+        let reply = parse_echo_reply(true, source, &packet).unwrap();
+        assert_eq!(reply.identifier(), 0x1234);
+        assert_eq!(reply.sequence(), 3);
+        assert!(reply.payload().is_empty());
+
+        // Destination unreachable.
+        let packet = [1, 0, 0, 0, 0, 0, 0, 0];
+        assert!(parse_echo_reply(true, source, &packet).is_none());
+    }
+}
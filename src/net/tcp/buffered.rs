@@ -0,0 +1,272 @@
+//! Module with [`BufferedStream`].
+
+use std::io;
+use std::net::SocketAddr;
+use std::time::Duration;
+
+use crate::net::TcpStream;
+use crate::{actor, rt};
+
+/// Default capacity of the read and write buffers, 8 KB.
+const DEFAULT_BUF_SIZE: usize = 8 * 1024;
+
+/// A [`TcpStream`] with buffered reading and writing.
+///
+/// Most protocols are easier to implement on top of a buffered stream, e.g.
+/// reading a line at a time using [`read_line`]. This provides the buffering
+/// once, so that every actor doesn't have to implement its own.
+///
+/// Reading is done using [`fill_buf`] and [`consume`], or the convenience
+/// functions [`read_until`] and [`read_line`] build on top of them. Only if
+/// the read buffer is empty is more data received from the stream, waiting
+/// for the stream to become readable if no data is available.
+///
+/// Data written using [`send_all`] is buffered and only send once the write
+/// buffer is full or when [`flush`] is called. Buffered data is **not**
+/// flushed when the `BufferedStream` is dropped, [`flush`] must be called
+/// explicitly.
+///
+/// A `BufferedStream` can be created using [`TcpStream::buffered`].
+///
+/// [`read_line`]: BufferedStream::read_line
+/// [`fill_buf`]: BufferedStream::fill_buf
+/// [`consume`]: BufferedStream::consume
+/// [`read_until`]: BufferedStream::read_until
+/// [`send_all`]: BufferedStream::send_all
+/// [`flush`]: BufferedStream::flush
+///
+/// # Examples
+///
+/// An actor that echos all lines it receives.
+///
+/// ```
+/// #![feature(never_type)]
+///
+/// use std::io;
+/// use std::net::SocketAddr;
+///
+/// use heph::actor;
+/// use heph::net::TcpStream;
+/// use heph::rt::ThreadLocal;
+///
+/// async fn actor(mut ctx: actor::Context<!, ThreadLocal>, address: SocketAddr) -> io::Result<()> {
+///     let stream = TcpStream::connect(&mut ctx, address)?.await?;
+///     let mut stream = stream.buffered();
+///
+///     let mut line = String::new();
+///     while stream.read_line(&mut line).await? != 0 {
+///         stream.send_all(line.as_bytes()).await?;
+///         stream.flush().await?;
+///         line.clear();
+///     }
+///     Ok(())
+/// }
+/// #
+/// # drop(actor); // Silent dead code warnings.
+/// ```
+#[derive(Debug)]
+pub struct BufferedStream {
+    stream: TcpStream,
+    read_buf: Vec<u8>,
+    /// Number of bytes in `read_buf` that are already consumed.
+    read_pos: usize,
+    write_buf: Vec<u8>,
+}
+
+impl BufferedStream {
+    /// Create a new `BufferedStream` with the default buffer capacities (8 KB
+    /// each).
+    pub fn new(stream: TcpStream) -> BufferedStream {
+        BufferedStream::with_capacity(DEFAULT_BUF_SIZE, DEFAULT_BUF_SIZE, stream)
+    }
+
+    /// Create a new `BufferedStream` with a read buffer of `read_capacity`
+    /// bytes and a write buffer of `write_capacity` bytes.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `read_capacity` is zero.
+    pub fn with_capacity(
+        read_capacity: usize,
+        write_capacity: usize,
+        stream: TcpStream,
+    ) -> BufferedStream {
+        assert!(read_capacity != 0, "read capacity can't be zero");
+        BufferedStream {
+            stream,
+            read_buf: Vec::with_capacity(read_capacity),
+            read_pos: 0,
+            write_buf: Vec::with_capacity(write_capacity),
+        }
+    }
+
+    /// Returns a reference to the underlying stream.
+    pub const fn get_ref(&self) -> &TcpStream {
+        &self.stream
+    }
+
+    /// Returns a mutable reference to the underlying stream.
+    ///
+    /// # Notes
+    ///
+    /// Reading from or writing to the stream directly will mess up the order
+    /// of the data with respect to the buffered data.
+    pub fn get_mut(&mut self) -> &mut TcpStream {
+        &mut self.stream
+    }
+
+    /// Returns the underlying stream.
+    ///
+    /// # Notes
+    ///
+    /// Any buffered data is lost, call [`BufferedStream::flush`] to send the
+    /// buffered data first.
+    pub fn into_inner(self) -> TcpStream {
+        self.stream
+    }
+
+    /// Returns the socket address of the remote peer of this TCP connection.
+    pub fn peer_addr(&mut self) -> io::Result<SocketAddr> {
+        self.stream.peer_addr()
+    }
+
+    /// Returns the socket address of the local half of this TCP connection.
+    pub fn local_addr(&mut self) -> io::Result<SocketAddr> {
+        self.stream.local_addr()
+    }
+
+    /// Returns the bytes in the read buffer, without receiving any more bytes
+    /// from the stream.
+    pub fn buffer(&self) -> &[u8] {
+        &self.read_buf[self.read_pos..]
+    }
+
+    /// Returns the contents of the read buffer, receiving more bytes from the
+    /// stream if the buffer is empty.
+    ///
+    /// Returns an empty slice if the peer closed its writing side of the
+    /// connection. The returned bytes must be marked as read using
+    /// [`BufferedStream::consume`].
+    pub async fn fill_buf(&mut self) -> io::Result<&[u8]> {
+        if self.read_pos >= self.read_buf.len() {
+            self.read_buf.clear();
+            self.read_pos = 0;
+            let _ = self.stream.recv(&mut self.read_buf).await?;
+        }
+        Ok(self.buffer())
+    }
+
+    /// Mark `n` bytes of the read buffer as read, see
+    /// [`BufferedStream::fill_buf`].
+    pub fn consume(&mut self, n: usize) {
+        self.read_pos = (self.read_pos + n).min(self.read_buf.len());
+    }
+
+    /// Read all bytes into `buf` until the delimiter `byte` or the end of the
+    /// stream is reached.
+    ///
+    /// If found the delimiter is included in `buf`. Returns the number of
+    /// bytes read, zero if the end of the stream was already reached.
+    pub async fn read_until(&mut self, byte: u8, buf: &mut Vec<u8>) -> io::Result<usize> {
+        let mut read = 0;
+        loop {
+            let (done, used) = {
+                let available = self.fill_buf().await?;
+                match available.iter().position(|b| *b == byte) {
+                    Some(idx) => {
+                        buf.extend_from_slice(&available[..=idx]);
+                        (true, idx + 1)
+                    }
+                    None => {
+                        buf.extend_from_slice(available);
+                        (available.is_empty(), available.len())
+                    }
+                }
+            };
+            self.consume(used);
+            read += used;
+            if done {
+                return Ok(read);
+            }
+        }
+    }
+
+    /// Read all bytes until a newline (the `0xA` byte) or the end of the
+    /// stream is reached, appending them to `buf`.
+    ///
+    /// If found the newline is included in `buf`. Returns the number of bytes
+    /// read, zero if the end of the stream was already reached.
+    ///
+    /// # Errors
+    ///
+    /// If the read bytes are not valid UTF-8 this returns an error of kind
+    /// [`io::ErrorKind::InvalidData`], the bytes are still consumed but not
+    /// added to `buf`.
+    pub async fn read_line(&mut self, buf: &mut String) -> io::Result<usize> {
+        let mut bytes = Vec::new();
+        let read = self.read_until(b'\n', &mut bytes).await?;
+        match String::from_utf8(bytes) {
+            Ok(line) => {
+                buf.push_str(&line);
+                Ok(read)
+            }
+            Err(_) => Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "stream did not contain valid UTF-8",
+            )),
+        }
+    }
+
+    /// Write all bytes in `buf` to the write buffer.
+    ///
+    /// If the write buffer doesn't have enough capacity left the buffered
+    /// bytes are send first. If `buf` itself is larger than the write buffer
+    /// it's send directly, without copying it into the buffer.
+    ///
+    /// Use [`BufferedStream::flush`] to send the buffered bytes.
+    pub async fn send_all(&mut self, buf: &[u8]) -> io::Result<()> {
+        if self.write_buf.len() + buf.len() > self.write_buf.capacity() {
+            self.flush().await?;
+        }
+        if buf.len() >= self.write_buf.capacity() {
+            self.stream.send_all(buf).await
+        } else {
+            self.write_buf.extend_from_slice(buf);
+            Ok(())
+        }
+    }
+
+    /// Send all bytes in the write buffer.
+    pub async fn flush(&mut self) -> io::Result<()> {
+        if !self.write_buf.is_empty() {
+            self.stream.send_all(&self.write_buf).await?;
+            self.write_buf.clear();
+        }
+        Ok(())
+    }
+
+    /// Send all bytes in the write buffer and gracefully close the
+    /// connection.
+    ///
+    /// Any bytes in the read buffer are discarded. See
+    /// [`TcpStream::close_graceful`] for more information.
+    pub async fn close_graceful<M, RT>(
+        mut self,
+        ctx: &mut actor::Context<M, RT>,
+        timeout: Duration,
+    ) -> io::Result<()>
+    where
+        RT: rt::Access + Clone,
+    {
+        self.flush().await?;
+        self.stream.close_graceful(ctx, timeout).await
+    }
+}
+
+impl<RT: rt::Access> actor::Bound<RT> for BufferedStream {
+    type Error = io::Error;
+
+    fn bind_to<M>(&mut self, ctx: &mut actor::Context<M, RT>) -> io::Result<()> {
+        actor::Bound::<RT>::bind_to(&mut self.stream, ctx)
+    }
+}
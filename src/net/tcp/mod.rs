@@ -3,16 +3,24 @@
 //! Three main types are provided:
 //!
 //!  * [`TcpListener`] listens for incoming connections.
-//!  * [`TcpStream`] represents a single TCP connection.
+//!  * [`TcpStream`] represents a single TCP connection, which can be buffered
+//!    using [`BufferedStream`].
 //!  * [`TcpServer`] is an [`Actor`] that listens for incoming connections and
 //!    starts a new actor for each.
 //!
 //! [`Actor`]: crate::actor::Actor
+//!
+//! Outgoing connections can be made through a SOCKS5 or HTTP proxy, see the
+//! [`proxy`] module.
 
+pub mod buffered;
 pub mod listener;
+pub mod proxy;
 pub mod server;
 pub mod stream;
 
+#[doc(no_inline)]
+pub use buffered::BufferedStream;
 #[doc(no_inline)]
 pub use listener::TcpListener;
 #[doc(no_inline)]
@@ -19,6 +19,7 @@ use crate::actor::messages::Terminate;
 use crate::actor::{self, Actor, NewActor};
 use crate::net::TcpStream;
 use crate::rt::{self, PrivateAccess, Signal};
+use crate::spawn::options::Priority;
 use crate::spawn::{ActorOptions, AddActorError, PrivateSpawn, Spawn};
 use crate::supervisor::Supervisor;
 
@@ -31,6 +32,15 @@ pub struct Setup<S, NA> {
     /// All fields are in an `Arc` to allow `Setup` to cheaply be cloned and
     /// still be `Send` and `Sync` for use in the setup function of `Runtime`.
     inner: Arc<SetupInner<S, NA>>,
+    /// Whether or not to keep the listener alive across restarts, see
+    /// [`Setup::with_listener_reuse`].
+    reuse_listener: bool,
+    /// Listener used by the last `TcpServer` created by this `Setup`, only set
+    /// if `reuse_listener` is `true`.
+    listener: Option<Arc<TcpListener>>,
+    /// Function to determine the priority of the actor of each connection, see
+    /// [`Setup::with_connection_priority`].
+    connection_priority: Option<fn(SocketAddr) -> Priority>,
 }
 
 #[derive(Debug)]
@@ -53,6 +63,36 @@ impl<S, NA> Setup<S, NA> {
     pub fn local_addr(&self) -> SocketAddr {
         self.inner.address
     }
+
+    /// Keep the listening socket alive when the [`TcpServer`] is restarted.
+    ///
+    /// By default a restarted `TcpServer` creates and binds a new listening
+    /// socket, dropping all connections in the listen queue of the old socket
+    /// (and possibly failing to bind the address). With this option the
+    /// `TcpServer` keeps using the original listening socket instead,
+    /// preserving the listen queue.
+    ///
+    /// # Notes
+    ///
+    /// Each clone of the `Setup` has its own listening socket, the socket is
+    /// only shared between the restarts of a single `TcpServer` actor.
+    pub fn with_listener_reuse(mut self) -> Self {
+        self.reuse_listener = true;
+        self
+    }
+
+    /// Determine the priority of the actor started for each connection based
+    /// on the address of the peer.
+    ///
+    /// This overwrites the priority set in the options passed to
+    /// [`TcpServer::setup`]. It can be used to give connections from trusted
+    /// peers, for example a load balancer performing health checks, a higher
+    /// priority so they're run before the connections of other clients when
+    /// the worker thread is busy.
+    pub fn with_connection_priority(mut self, priority: fn(SocketAddr) -> Priority) -> Self {
+        self.connection_priority = Some(priority);
+        self
+    }
 }
 
 impl<S, NA> NewActor for Setup<S, NA>
@@ -73,9 +113,21 @@ where
         _: Self::Argument,
     ) -> Result<Self::Actor, Self::Error> {
         let this = &*self.inner;
-        let socket = new_listener(this.address, 1024)?;
-        let mut listener = unsafe { TcpListener::from_raw_fd(socket.into_raw_fd()) };
-        ctx.runtime().register(&mut listener, Interest::READABLE)?;
+        let listener = match &self.listener {
+            // NOTE: the restarted actor uses the same process id, so the
+            // listener is still registered with the correct token.
+            Some(listener) => listener.clone(),
+            None => {
+                let socket = new_listener(this.address, 1024)?;
+                let mut listener = unsafe { TcpListener::from_raw_fd(socket.into_raw_fd()) };
+                ctx.runtime().register(&mut listener, Interest::READABLE)?;
+                let listener = Arc::new(listener);
+                if self.reuse_listener {
+                    self.listener = Some(listener.clone());
+                }
+                listener
+            }
+        };
         Ok(TcpServer {
             ctx,
             set_waker: false,
@@ -83,6 +135,7 @@ where
             supervisor: this.supervisor.clone(),
             new_actor: this.new_actor.clone(),
             options: this.options.clone(),
+            connection_priority: self.connection_priority,
         })
     }
 }
@@ -118,6 +171,10 @@ impl<S, NA> Clone for Setup<S, NA> {
     fn clone(&self) -> Setup<S, NA> {
         Setup {
             inner: self.inner.clone(),
+            reuse_listener: self.reuse_listener,
+            // Each clone creates its own listener.
+            listener: None,
+            connection_priority: self.connection_priority,
         }
     }
 }
@@ -141,6 +198,12 @@ impl<S, NA> Clone for Setup<S, NA> {
 /// see "Example 2 my ip" (in the examples directory of the source code) for an
 /// example of that.
 ///
+/// Shutdown messages should be send using [`ActorRef::send_priority`], which
+/// ensures they are handled before any other pending messages. Process
+/// signals are always send this way by the runtime.
+///
+/// [`ActorRef::send_priority`]: crate::actor_ref::ActorRef::send_priority
+///
 /// # Examples
 ///
 /// The following example is a TCP server that writes "Hello World" to the
@@ -184,7 +247,7 @@ impl<S, NA> Clone for Setup<S, NA> {
 ///     let options = ActorOptions::default().with_priority(Priority::LOW);
 ///     # let actor_ref =
 ///     runtime_ref.try_spawn_local(ServerSupervisor, server, (), options)?;
-///     # actor_ref.try_send(Terminate).unwrap();
+///     # actor_ref.send_priority(Terminate).unwrap();
 ///
 ///     Ok(())
 /// }
@@ -273,8 +336,9 @@ impl<S, NA> Clone for Setup<S, NA> {
 ///     let server_ref = runtime_ref.try_spawn_local(ServerSupervisor, server, (), options)?;
 ///
 ///     // Because the server is just another actor we can send it messages. Here
-///     // we'll send it a terminate message so it will gracefully shutdown.
-///     server_ref.try_send(Terminate).unwrap();
+///     // we'll send it a terminate message so it will gracefully shutdown. We
+///     // use the priority lane to ensure it's handled as soon as possible.
+///     server_ref.send_priority(Terminate).unwrap();
 ///
 ///     Ok(())
 /// }
@@ -355,7 +419,7 @@ impl<S, NA> Clone for Setup<S, NA> {
 ///     # let actor_ref =
 ///     runtime.try_spawn(ServerSupervisor, server, (), options)
 ///         .map_err(rt::Error::setup)?;
-///     # actor_ref.try_send(Terminate).unwrap();
+///     # actor_ref.send_priority(Terminate).unwrap();
 ///
 ///     runtime.start()
 /// }
@@ -415,14 +479,17 @@ pub struct TcpServer<S, NA: NewActor> {
     ctx: actor::Context<Message, NA::RuntimeAccess>,
     /// Whether or not we set the waker for the inbox.
     set_waker: bool,
-    /// The underlying TCP listener, backed by Mio.
-    listener: TcpListener,
+    /// The underlying TCP listener, backed by Mio. Shared with [`Setup`] if
+    /// the listener is reused, see [`Setup::with_listener_reuse`].
+    listener: Arc<TcpListener>,
     /// Supervisor for all actors created by `NewActor`.
     supervisor: S,
     /// `NewActor` used to create an actor for each connection.
     new_actor: NA,
     /// Options used to spawn the actor.
     options: ActorOptions,
+    /// See [`Setup::with_connection_priority`].
+    connection_priority: Option<fn(SocketAddr) -> Priority>,
 }
 
 impl<S, NA> TcpServer<S, NA>
@@ -471,6 +538,9 @@ where
                     new_actor,
                     options,
                 }),
+                reuse_listener: false,
+                listener: None,
+                connection_priority: None,
             })
         })
     }
@@ -499,7 +569,9 @@ where
             this.set_waker = true
         }
 
-        // See if we need to shutdown.
+        // See if we need to shutdown. Shutdown messages are normally send
+        // using the priority lane, which is checked first by
+        // `try_receive_next`.
         //
         // We don't return immediately here because we're using `SO_REUSEPORT`,
         // which on most OSes causes each listener (file descriptor) to have
@@ -533,11 +605,15 @@ where
                 }
                 Ok((stream, addr))
             };
+            let options = match this.connection_priority {
+                Some(priority) => this.options.clone().with_priority(priority(addr)),
+                None => this.options.clone(),
+            };
             let res = this.ctx.try_spawn_setup(
                 this.supervisor.clone(),
                 this.new_actor.clone(),
                 setup_actor,
-                this.options.clone(),
+                options,
             );
             if let Err(err) = res {
                 return Poll::Ready(Err(err.into()));
@@ -557,6 +633,9 @@ where
 ///
 /// The message implements [`From`]`<`[`Terminate`]`>` and
 /// [`TryFrom`]`<`[`Signal`]`>` for the message, allowing for graceful shutdown.
+/// It's recommended to send these messages using [`ActorRef::send_priority`].
+///
+/// [`ActorRef::send_priority`]: crate::actor_ref::ActorRef::send_priority
 #[derive(Debug)]
 pub struct Message {
     // Allow for future expansion.
@@ -602,6 +681,17 @@ impl<E> From<AddActorError<E, io::Error>> for Error<E> {
     }
 }
 
+/// Converts the error into the underlying [`io::Error`], possible because
+/// creating a new actor can't fail.
+impl From<Error<!>> for io::Error {
+    fn from(err: Error<!>) -> io::Error {
+        match err {
+            Error::Accept(err) => err,
+            Error::NewActor(err) => err,
+        }
+    }
+}
+
 impl<E: fmt::Display> fmt::Display for Error<E> {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         use Error::*;
@@ -611,3 +701,12 @@ impl<E: fmt::Display> fmt::Display for Error<E> {
         }
     }
 }
+
+impl<E: std::error::Error + 'static> std::error::Error for Error<E> {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Accept(ref err) => Some(err),
+            Error::NewActor(ref err) => Some(err),
+        }
+    }
+}
@@ -5,12 +5,15 @@
 // that once Mio uses Socket2 and supports all the methods we need, Mio's
 // tracking issue: https://github.com/tokio-rs/mio/issues/1381.
 
+use std::collections::VecDeque;
+use std::fmt;
 use std::future::Future;
 use std::io::{self, IoSlice};
-use std::net::{Shutdown, SocketAddr};
+use std::net::{Shutdown, SocketAddr, ToSocketAddrs};
 use std::num::NonZeroUsize;
 use std::pin::Pin;
 use std::task::{self, Poll};
+use std::time::{Duration, Instant};
 
 #[cfg(target_os = "linux")]
 use log::warn;
@@ -19,8 +22,15 @@ use mio::{net, Interest};
 use socket2::SockRef;
 
 use crate::bytes::{Bytes, BytesVectored, MaybeUninitSlice};
+use crate::net::tcp::proxy::ProxyConfig;
+use crate::net::tcp::BufferedStream;
+use crate::timer::Deadline;
 use crate::{actor, rt};
 
+/// Size of the buffer used to discard the received data in
+/// [`TcpStream::close_graceful`].
+const DRAIN_BUF_SIZE: usize = 4 * 1024;
+
 /// A non-blocking TCP stream between a local socket and a remote socket.
 ///
 /// # Examples
@@ -78,6 +88,103 @@ impl TcpStream {
         })
     }
 
+    /// Create a new TCP stream connecting to one of the `addresses`, returning
+    /// the first stream that is connected.
+    ///
+    /// This implements the connection attempt part of "Happy Eyeballs" ([RFC
+    /// 8305]). The addresses are sorted to alternate between address families
+    /// (IPv6 and IPv4), starting with the family of the first address.
+    /// Connection attempts are started one at a time, each one
+    /// [`CONNECTION_ATTEMPT_DELAY`] after the previous one, or immediately if
+    /// the previous attempt failed. Multiple attempts can be in progress at
+    /// the same time, the first attempt that successfully connects is
+    /// returned and all other attempts are canceled. If all attempts fail the
+    /// error of the last attempt is returned.
+    ///
+    /// [RFC 8305]: https://datatracker.ietf.org/doc/html/rfc8305
+    ///
+    /// # Notes
+    ///
+    /// Resolving a host name, e.g. when passing `"example.com:80"` as
+    /// `addresses`, is done using [`ToSocketAddrs`], which **blocks** the
+    /// worker thread. Prefer to pass already resolved addresses, e.g. a slice
+    /// of [`SocketAddr`].
+    ///
+    /// Like [`TcpStream::connect`] the stream is also [bound] to the actor.
+    ///
+    /// [bound]: crate::actor::Bound
+    pub fn connect_any<M, RT, A>(
+        ctx: &mut actor::Context<M, RT>,
+        addresses: A,
+    ) -> io::Result<ConnectAny<RT>>
+    where
+        RT: rt::Access + Clone,
+        A: ToSocketAddrs,
+    {
+        let addresses = sort_addresses(addresses.to_socket_addrs()?);
+        if addresses.is_empty() {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "no addresses to connect to",
+            ));
+        }
+        Ok(ConnectAny {
+            rt: ctx.runtime().clone(),
+            addresses,
+            attempts: Vec::new(),
+            next_attempt: None,
+            last_error: None,
+        })
+    }
+
+    /// Create a new TCP stream connecting to `host`:`port` using `options`.
+    ///
+    /// If a proxy is set using [`ConnectOptions::via_proxy`] this connects to
+    /// the proxy and asks it to connect to `host`. The host name is resolved by
+    /// the proxy. The returned stream is connected to `host` through the proxy.
+    ///
+    /// # Notes
+    ///
+    /// Without a proxy the host name is resolved using [`ToSocketAddrs`], which
+    /// **blocks** the worker thread, and connects to the first address. Prefer
+    /// to pass an IP address as `host`, or use [`TcpStream::connect_any`].
+    ///
+    /// Like [`TcpStream::connect`] the stream is also [bound] to the actor.
+    ///
+    /// [bound]: crate::actor::Bound
+    pub fn connect_to<M, RT>(
+        ctx: &mut actor::Context<M, RT>,
+        host: &str,
+        port: u16,
+        options: ConnectOptions,
+    ) -> io::Result<ConnectTo>
+    where
+        RT: rt::Access,
+    {
+        match options.proxy {
+            Some(proxy) => {
+                let connect = TcpStream::connect(ctx, proxy.address())?;
+                let host = host.to_owned();
+                Ok(ConnectTo {
+                    future: Box::pin(async move {
+                        let mut stream = connect.await?;
+                        proxy.handshake(&mut stream, &host, port).await?;
+                        Ok(stream)
+                    }),
+                })
+            }
+            None => {
+                let address = (host, port).to_socket_addrs()?.next().ok_or_else(|| {
+                    io::Error::new(io::ErrorKind::InvalidInput, "no addresses to connect to")
+                })?;
+                let connect = TcpStream::connect(ctx, address)?;
+                Ok(ConnectTo {
+                    future: Box::pin(connect),
+                })
+            }
+        }
+    }
+
     /// Returns the socket address of the remote peer of this TCP connection.
     pub fn peer_addr(&mut self) -> io::Result<SocketAddr> {
         self.socket.peer_addr()
@@ -88,6 +195,11 @@ impl TcpStream {
         self.socket.local_addr()
     }
 
+    /// Wrap the stream in a [`BufferedStream`], buffering reads and writes.
+    pub fn buffered(self) -> BufferedStream {
+        BufferedStream::new(self)
+    }
+
     /// Set the CPU affinity to `cpu`.
     ///
     /// On Linux this uses `SO_INCOMING_CPU`.
@@ -469,7 +581,10 @@ impl TcpStream {
     /// Send the `file` out this stream.
     ///
     /// What kind of files are support depends on the OS and is determined by
-    /// the [`FileSend`] trait. All OSs at least support regular files.
+    /// the [`FileSend`] trait. All OSs at least support regular files, both
+    /// [`std::fs::File`] and [`heph::fs::File`].
+    ///
+    /// [`heph::fs::File`]: crate::fs::File
     ///
     /// The `offset` is the offset into the `file` from which to start copying.
     /// The `length` is the amount of bytes to copy, or if `None` this send the
@@ -534,6 +649,48 @@ impl TcpStream {
         self.socket.shutdown(how)
     }
 
+    /// Gracefully close the connection.
+    ///
+    /// Dropping a `TcpStream` closes the connection immediately, if the peer
+    /// still sends data after that (e.g. the remainder of a request) the OS
+    /// responds with a reset, which could cause the peer to lose the data we
+    /// send it before closing. This method instead does the following:
+    ///
+    /// 1. Shuts down the writing side of the connection, signalling the end of
+    ///    the stream to the peer. All data send before is still delivered.
+    /// 2. Reads, and discards, all data from the peer until it closes its
+    ///    writing side as well.
+    /// 3. Closes the connection.
+    ///
+    /// If the peer doesn't close its side of the connection within `timeout`
+    /// an error of kind [`io::ErrorKind::TimedOut`] is returned, the
+    /// connection is closed in any case.
+    ///
+    /// Use [`BufferedStream::close_graceful`] to also send any buffered data.
+    pub async fn close_graceful<M, RT>(
+        mut self,
+        ctx: &mut actor::Context<M, RT>,
+        timeout: Duration,
+    ) -> io::Result<()>
+    where
+        RT: rt::Access + Clone,
+    {
+        self.shutdown(Shutdown::Write)?;
+        let drain = async {
+            let mut buf = Vec::with_capacity(DRAIN_BUF_SIZE);
+            loop {
+                buf.clear();
+                match self.recv(&mut buf).await {
+                    Ok(0) => return Ok(()),
+                    Ok(_) => continue,
+                    Err(err) => return Err(err),
+                }
+            }
+        };
+        Deadline::after(ctx, timeout, drain).await
+        // Dropping `self` closes the connection.
+    }
+
     /// Get the value of the `SO_ERROR` option on this socket.
     ///
     /// This will retrieve the stored error in the underlying socket, clearing
@@ -622,6 +779,217 @@ impl Future for Connect {
     }
 }
 
+/// Delay between starting connection attempts in [`TcpStream::connect_any`].
+///
+/// This is the recommended value of the "Connection Attempt Delay" in [RFC
+/// 8305].
+///
+/// [RFC 8305]: https://datatracker.ietf.org/doc/html/rfc8305#section-5
+pub const CONNECTION_ATTEMPT_DELAY: Duration = Duration::from_millis(250);
+
+/// Sort `addresses` so that the address families (IPv6 and IPv4) alternate,
+/// starting with the family of the first address, as per [RFC 8305 section 4].
+///
+/// [RFC 8305 section 4]: https://datatracker.ietf.org/doc/html/rfc8305#section-4
+fn sort_addresses<I>(addresses: I) -> VecDeque<SocketAddr>
+where
+    I: Iterator<Item = SocketAddr>,
+{
+    let mut preferred = Vec::new();
+    let mut other = Vec::new();
+    for address in addresses {
+        match preferred.first() {
+            Some(first) if first.is_ipv6() != address.is_ipv6() => other.push(address),
+            _ => preferred.push(address),
+        }
+    }
+
+    let mut sorted = VecDeque::with_capacity(preferred.len() + other.len());
+    let mut preferred = preferred.into_iter();
+    let mut other = other.into_iter();
+    loop {
+        match (preferred.next(), other.next()) {
+            (None, None) => break,
+            (p, o) => sorted.extend(p.into_iter().chain(o)),
+        }
+    }
+    sorted
+}
+
+/// Options for [`TcpStream::connect_to`].
+///
+/// # Examples
+///
+/// Connecting through a SOCKS5 proxy.
+///
+/// ```
+/// #![feature(never_type)]
+///
+/// use std::io;
+///
+/// use heph::actor;
+/// use heph::net::tcp::proxy::ProxyConfig;
+/// use heph::net::tcp::stream::{ConnectOptions, TcpStream};
+/// use heph::rt::ThreadLocal;
+///
+/// async fn actor(mut ctx: actor::Context<!, ThreadLocal>) -> io::Result<()> {
+///     let proxy = ProxyConfig::socks5("127.0.0.1:1080".parse().unwrap());
+///     let options = ConnectOptions::default().via_proxy(proxy);
+///     let mut stream = TcpStream::connect_to(&mut ctx, "example.com", 80, options)?.await?;
+///     stream.send_all(b"GET / HTTP/1.1\r\nHost: example.com\r\n\r\n").await
+/// }
+/// #
+/// # drop(actor); // Silent dead code warnings.
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct ConnectOptions {
+    proxy: Option<ProxyConfig>,
+}
+
+impl ConnectOptions {
+    /// Returns the proxy set in the options, if any.
+    pub const fn proxy(&self) -> Option<&ProxyConfig> {
+        self.proxy.as_ref()
+    }
+
+    /// Connect through `proxy`.
+    pub fn via_proxy(mut self, proxy: ProxyConfig) -> Self {
+        self.proxy = Some(proxy);
+        self
+    }
+}
+
+/// The [`Future`] behind [`TcpStream::connect_to`].
+#[must_use = "futures do nothing unless you `.await` or poll them"]
+pub struct ConnectTo {
+    future: Pin<Box<dyn Future<Output = io::Result<TcpStream>> + Send>>,
+}
+
+impl Future for ConnectTo {
+    type Output = io::Result<TcpStream>;
+
+    fn poll(mut self: Pin<&mut Self>, ctx: &mut task::Context<'_>) -> Poll<Self::Output> {
+        self.future.as_mut().poll(ctx)
+    }
+}
+
+impl fmt::Debug for ConnectTo {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ConnectTo").finish()
+    }
+}
+
+/// The [`Future`] behind [`TcpStream::connect_any`].
+#[derive(Debug)]
+#[must_use = "futures do nothing unless you `.await` or poll them"]
+pub struct ConnectAny<RT: rt::Access> {
+    rt: RT,
+    /// Addresses not yet attempted, in order.
+    addresses: VecDeque<SocketAddr>,
+    /// Connection attempts in progress.
+    attempts: Vec<Connect>,
+    /// Time at which to start the next connection attempt, also registered as
+    /// deadline with the runtime.
+    next_attempt: Option<Instant>,
+    /// Error of the last failed attempt.
+    last_error: Option<io::Error>,
+}
+
+impl<RT: rt::Access> ConnectAny<RT> {
+    /// Start a connection attempt to the next address.
+    fn start_attempt(&mut self) {
+        while let Some(address) = self.addresses.pop_front() {
+            let res = net::TcpStream::connect(address).and_then(|mut socket| {
+                self.rt
+                    .register(&mut socket, Interest::READABLE | Interest::WRITABLE)?;
+                Ok(socket)
+            });
+            match res {
+                Ok(socket) => {
+                    self.attempts.push(Connect {
+                        socket: Some(socket),
+                        #[cfg(target_os = "linux")]
+                        cpu_affinity: self.rt.cpu(),
+                    });
+                    // Start the next attempt after a delay, unless this attempt
+                    // fails before that.
+                    let next_attempt = Instant::now() + CONNECTION_ATTEMPT_DELAY;
+                    self.set_next_attempt(Some(next_attempt));
+                    return;
+                }
+                // Failed to start, try the next address.
+                Err(err) => self.last_error = Some(err),
+            }
+        }
+        self.set_next_attempt(None);
+    }
+
+    /// Set the time to start the next attempt, replacing the deadline in the
+    /// runtime.
+    fn set_next_attempt(&mut self, next_attempt: Option<Instant>) {
+        if let Some(deadline) = self.next_attempt.take() {
+            self.rt.remove_deadline(deadline);
+        }
+        if let Some(deadline) = next_attempt {
+            self.rt.add_deadline(deadline);
+        }
+        self.next_attempt = next_attempt;
+    }
+}
+
+impl<RT: rt::Access> Future for ConnectAny<RT> {
+    type Output = io::Result<TcpStream>;
+
+    fn poll(self: Pin<&mut Self>, ctx: &mut task::Context<'_>) -> Poll<Self::Output> {
+        // Safety: not moving any of the fields.
+        let this = unsafe { Pin::get_unchecked_mut(self) };
+        loop {
+            let mut i = 0;
+            while i < this.attempts.len() {
+                match Pin::new(&mut this.attempts[i]).poll(ctx) {
+                    Poll::Ready(Ok(stream)) => {
+                        // Dropping the other attempts cancels them.
+                        this.attempts.clear();
+                        this.set_next_attempt(None);
+                        return Poll::Ready(Ok(stream));
+                    }
+                    Poll::Ready(Err(err)) => {
+                        drop(this.attempts.swap_remove(i));
+                        this.last_error = Some(err);
+                    }
+                    Poll::Pending => i += 1,
+                }
+            }
+
+            let start_next = this.attempts.is_empty()
+                || this
+                    .next_attempt
+                    .map_or(false, |next_attempt| next_attempt <= Instant::now());
+            if start_next && !this.addresses.is_empty() {
+                this.start_attempt();
+                // Poll the new attempt, it could have connected immediately.
+                continue;
+            }
+
+            return if this.attempts.is_empty() {
+                this.set_next_attempt(None);
+                let err = this.last_error.take().unwrap_or_else(|| {
+                    io::Error::new(io::ErrorKind::Other, "failed to connect to any address")
+                });
+                Poll::Ready(Err(err))
+            } else {
+                Poll::Pending
+            };
+        }
+    }
+}
+
+impl<RT: rt::Access> Drop for ConnectAny<RT> {
+    fn drop(&mut self) {
+        self.set_next_attempt(None);
+    }
+}
+
 /// The [`Future`] behind [`TcpStream::send`].
 #[derive(Debug)]
 #[must_use = "futures do nothing unless you `.await` or poll them"]
@@ -949,6 +1317,10 @@ mod private {
     impl super::FileSend for File {}
 
     impl PrivateFileSend for File {}
+
+    impl super::FileSend for crate::fs::File {}
+
+    impl PrivateFileSend for crate::fs::File {}
 }
 
 impl<RT: rt::Access> actor::Bound<RT> for TcpStream {
@@ -11,8 +11,14 @@ use std::fmt;
 use std::future::Future;
 use std::io::{self, IoSlice};
 use std::marker::PhantomData;
-use std::net::SocketAddr;
+#[cfg(target_os = "linux")]
+use std::mem::{size_of, MaybeUninit};
+use std::net::{Ipv4Addr, Ipv6Addr, SocketAddr};
+#[cfg(target_os = "linux")]
+use std::os::unix::io::AsRawFd;
 use std::pin::Pin;
+#[cfg(target_os = "linux")]
+use std::ptr;
 use std::task::{self, Poll};
 
 #[cfg(target_os = "linux")]
@@ -197,6 +203,241 @@ impl<M> UdpSocket<M> {
     pub fn take_error(&mut self) -> io::Result<Option<io::Error>> {
         self.socket.take_error()
     }
+
+    /// Sets the value for the `IP_TTL` option on this socket.
+    pub fn set_ttl(&mut self, ttl: u32) -> io::Result<()> {
+        self.socket.set_ttl(ttl)
+    }
+
+    /// Gets the value of the `IP_TTL` option for this socket.
+    pub fn ttl(&mut self) -> io::Result<u32> {
+        self.socket.ttl()
+    }
+
+    /// Sets the value of the `SO_BROADCAST` option for this socket.
+    ///
+    /// When enabled, this socket is allowed to send packets to a broadcast
+    /// address.
+    pub fn set_broadcast(&mut self, broadcast: bool) -> io::Result<()> {
+        self.socket.set_broadcast(broadcast)
+    }
+
+    /// Gets the value of the `SO_BROADCAST` option for this socket.
+    pub fn broadcast(&mut self) -> io::Result<bool> {
+        self.socket.broadcast()
+    }
+
+    /// Join the IPv4 multicast group `multiaddr` using the interface with
+    /// address `interface`, or any interface if `interface` is
+    /// [`Ipv4Addr::UNSPECIFIED`].
+    ///
+    /// This uses the `IP_ADD_MEMBERSHIP` option.
+    pub fn join_multicast_v4(
+        &mut self,
+        multiaddr: Ipv4Addr,
+        interface: Ipv4Addr,
+    ) -> io::Result<()> {
+        self.socket.join_multicast_v4(&multiaddr, &interface)
+    }
+
+    /// Leave the IPv4 multicast group `multiaddr`, see
+    /// [`UdpSocket::join_multicast_v4`].
+    ///
+    /// This uses the `IP_DROP_MEMBERSHIP` option.
+    pub fn leave_multicast_v4(
+        &mut self,
+        multiaddr: Ipv4Addr,
+        interface: Ipv4Addr,
+    ) -> io::Result<()> {
+        self.socket.leave_multicast_v4(&multiaddr, &interface)
+    }
+
+    /// Join the IPv6 multicast group `multiaddr` using the interface with
+    /// index `interface`, or any interface if `interface` is `0`.
+    ///
+    /// This uses the `IPV6_ADD_MEMBERSHIP` option.
+    pub fn join_multicast_v6(&mut self, multiaddr: Ipv6Addr, interface: u32) -> io::Result<()> {
+        self.socket.join_multicast_v6(&multiaddr, interface)
+    }
+
+    /// Leave the IPv6 multicast group `multiaddr`, see
+    /// [`UdpSocket::join_multicast_v6`].
+    ///
+    /// This uses the `IPV6_DROP_MEMBERSHIP` option.
+    pub fn leave_multicast_v6(&mut self, multiaddr: Ipv6Addr, interface: u32) -> io::Result<()> {
+        self.socket.leave_multicast_v6(&multiaddr, interface)
+    }
+
+    /// Sets the value of the `IP_MULTICAST_LOOP` option for this socket.
+    ///
+    /// If enabled, multicast packets send by this socket are looped back to
+    /// the local socket.
+    pub fn set_multicast_loop_v4(&mut self, multicast_loop: bool) -> io::Result<()> {
+        self.socket.set_multicast_loop_v4(multicast_loop)
+    }
+
+    /// Gets the value of the `IP_MULTICAST_LOOP` option for this socket.
+    pub fn multicast_loop_v4(&mut self) -> io::Result<bool> {
+        self.socket.multicast_loop_v4()
+    }
+
+    /// Sets the value of the `IP_MULTICAST_TTL` option for this socket.
+    ///
+    /// This is the time-to-live of multicast packets send by this socket,
+    /// which defaults to 1 so that the packets don't leave the local network.
+    pub fn set_multicast_ttl_v4(&mut self, ttl: u32) -> io::Result<()> {
+        self.socket.set_multicast_ttl_v4(ttl)
+    }
+
+    /// Gets the value of the `IP_MULTICAST_TTL` option for this socket.
+    pub fn multicast_ttl_v4(&mut self) -> io::Result<u32> {
+        self.socket.multicast_ttl_v4()
+    }
+
+    /// Sets the value of the `IPV6_MULTICAST_LOOP` option for this socket.
+    ///
+    /// If enabled, multicast packets send by this socket are looped back to
+    /// the local socket.
+    pub fn set_multicast_loop_v6(&mut self, multicast_loop: bool) -> io::Result<()> {
+        self.socket.set_multicast_loop_v6(multicast_loop)
+    }
+
+    /// Gets the value of the `IPV6_MULTICAST_LOOP` option for this socket.
+    pub fn multicast_loop_v6(&mut self) -> io::Result<bool> {
+        self.socket.multicast_loop_v6()
+    }
+
+    /// Enable, or disable, receiving extended errors on the socket's error
+    /// queue.
+    ///
+    /// This sets the `IP_RECVERR` (or `IPV6_RECVERR`) option. Once enabled
+    /// errors, such as ICMP destination unreachable or packet too big
+    /// messages, are queued on the socket and can be read using
+    /// [`UdpSocket::recv_error`].
+    #[cfg(target_os = "linux")]
+    pub fn set_recv_error(&mut self, enable: bool) -> io::Result<()> {
+        let (level, name) = if self.socket.local_addr()?.is_ipv4() {
+            (libc::IPPROTO_IP, libc::IP_RECVERR)
+        } else {
+            (libc::IPPROTO_IPV6, libc::IPV6_RECVERR)
+        };
+        set_int_option(&self.socket, level, name, enable.into())
+    }
+
+    /// Set the "Don't Fragment" (DF) bit on all outgoing packets.
+    ///
+    /// When enabled packets larger than the path MTU are not fragmented, but
+    /// dropped and reported with an `EMSGSIZE` error instead (see
+    /// [`UdpSocket::recv_error`]). This is required for path MTU discovery in
+    /// protocols such as QUIC.
+    ///
+    /// This sets the `IP_MTU_DISCOVER` option to `IP_PMTUDISC_DO` (or
+    /// `IP_PMTUDISC_DONT` if disabled) for IPv4 sockets and the
+    /// `IPV6_DONTFRAG` option for IPv6 sockets.
+    #[cfg(target_os = "linux")]
+    pub fn set_dontfrag(&mut self, dontfrag: bool) -> io::Result<()> {
+        if self.socket.local_addr()?.is_ipv4() {
+            let value = if dontfrag {
+                libc::IP_PMTUDISC_DO
+            } else {
+                libc::IP_PMTUDISC_DONT
+            };
+            set_int_option(&self.socket, libc::IPPROTO_IP, libc::IP_MTU_DISCOVER, value)
+        } else {
+            let value = dontfrag.into();
+            set_int_option(&self.socket, libc::IPPROTO_IPV6, libc::IPV6_DONTFRAG, value)
+        }
+    }
+
+    /// Returns the maximum number of segments that can be send in a single
+    /// call using generic segmentation offload (GSO), see
+    /// [`UdpSocket::set_segment_size`].
+    ///
+    /// Returns `1` if GSO is not supported.
+    #[cfg(target_os = "linux")]
+    pub fn max_gso_segments(&mut self) -> usize {
+        // There is no option to retrieve the maximum number of segments, so
+        // we determine if GSO is supported by trying to get the option.
+        match get_int_option(&self.socket, libc::SOL_UDP, UDP_SEGMENT) {
+            Ok(_) => UDP_MAX_SEGMENTS,
+            Err(_) => 1,
+        }
+    }
+
+    /// Sets the segment size used in generic segmentation offload (GSO).
+    ///
+    /// Once set the buffer passed to the send methods is split by the kernel
+    /// (or network card) into multiple packets of `size` bytes (the last one
+    /// may be smaller), allowing up to [`max_gso_segments`] packets to be
+    /// send in a single call. Set to `0` to disable GSO.
+    ///
+    /// This sets the `UDP_SEGMENT` option.
+    ///
+    /// [`max_gso_segments`]: UdpSocket::max_gso_segments
+    #[cfg(target_os = "linux")]
+    pub fn set_segment_size(&mut self, size: u16) -> io::Result<()> {
+        set_int_option(&self.socket, libc::SOL_UDP, UDP_SEGMENT, size.into())
+    }
+
+    /// Attempt to read an error from the socket's error queue.
+    ///
+    /// If the error queue is empty this will return an error with the [kind]
+    /// set to [`ErrorKind::WouldBlock`]. Most users should prefer to use
+    /// [`UdpSocket::recv_error`].
+    ///
+    /// [kind]: io::Error::kind
+    /// [`ErrorKind::WouldBlock`]: io::ErrorKind::WouldBlock
+    #[cfg(target_os = "linux")]
+    pub fn try_recv_error(&mut self) -> io::Result<QueuedError> {
+        // Large enough for a `sock_extended_err` and the offender's address.
+        let mut control: [MaybeUninit<u64>; 32] = [MaybeUninit::uninit(); 32];
+        // Not all fields are public, so we zero the struct.
+        let mut msg: libc::msghdr = unsafe { MaybeUninit::zeroed().assume_init() };
+        msg.msg_control = control.as_mut_ptr().cast();
+        msg.msg_controllen = size_of::<[u64; 32]>() as _;
+        // NOTE: we don't read the (partial) packet that caused the error.
+        let res = unsafe { libc::recvmsg(self.socket.as_raw_fd(), &mut msg, libc::MSG_ERRQUEUE) };
+        if res == -1 {
+            return Err(io::Error::last_os_error());
+        }
+
+        // Safety: the kernel initialised the control messages.
+        let mut cmsg = unsafe { libc::CMSG_FIRSTHDR(&msg) };
+        while !cmsg.is_null() {
+            let (level, kind, len) =
+                unsafe { ((*cmsg).cmsg_level, (*cmsg).cmsg_type, (*cmsg).cmsg_len) };
+            if (level == libc::IPPROTO_IP && kind == libc::IP_RECVERR)
+                || (level == libc::IPPROTO_IPV6 && kind == libc::IPV6_RECVERR)
+            {
+                let data = unsafe { libc::CMSG_DATA(cmsg) };
+                let data_len = len as usize - (data as usize - cmsg as usize);
+                return unsafe { QueuedError::from_raw(data, data_len) };
+            }
+            cmsg = unsafe { libc::CMSG_NXTHDR(&msg, cmsg) };
+        }
+        Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            "missing extended error in error queue message",
+        ))
+    }
+
+    /// Read an error from the socket's error queue. Returns a [`Future`] that
+    /// on success returns the error (`io::Result<QueuedError>`).
+    ///
+    /// Receiving errors must be enabled first using
+    /// [`UdpSocket::set_recv_error`].
+    ///
+    /// # Notes
+    ///
+    /// Only a single error is kept in the `SO_ERROR` option (see
+    /// [`UdpSocket::take_error`]), while the error queue can hold multiple
+    /// errors, including the address of the host that reported it. On
+    /// connected sockets the error is also returned by the next send or
+    /// receive call.
+    #[cfg(target_os = "linux")]
+    pub fn recv_error(&mut self) -> RecvError<'_, M> {
+        RecvError { socket: self }
+    }
 }
 
 impl UdpSocket<Unconnected> {
@@ -528,6 +769,23 @@ where
 }
 
 impl UdpSocket<Connected> {
+    /// Returns the path MTU, as currently known by the kernel, to the peer.
+    ///
+    /// Combine this with [`UdpSocket::set_dontfrag`] to discover the path
+    /// MTU.
+    ///
+    /// This uses the `IP_MTU` (or `IPV6_MTU`) option.
+    #[cfg(target_os = "linux")]
+    pub fn path_mtu(&mut self) -> io::Result<u32> {
+        let (level, name) = if self.socket.local_addr()?.is_ipv4() {
+            (libc::IPPROTO_IP, libc::IP_MTU)
+        } else {
+            (libc::IPPROTO_IPV6, libc::IPV6_MTU)
+        };
+        #[allow(clippy::cast_sign_loss)] // MTU is never negative.
+        get_int_option(&self.socket, level, name).map(|mtu| mtu as u32)
+    }
+
     /// Attempt to send data to the peer.
     ///
     /// If the buffer currently can't be send this will return an error with the
@@ -831,6 +1089,215 @@ where
     }
 }
 
+/// The [`Future`] behind [`UdpSocket::recv_error`].
+#[cfg(target_os = "linux")]
+#[derive(Debug)]
+#[must_use = "futures do nothing unless you `.await` or poll them"]
+pub struct RecvError<'a, M> {
+    socket: &'a mut UdpSocket<M>,
+}
+
+#[cfg(target_os = "linux")]
+impl<'a, M> Future for RecvError<'a, M> {
+    type Output = io::Result<QueuedError>;
+
+    fn poll(self: Pin<&mut Self>, _: &mut task::Context<'_>) -> Poll<Self::Output> {
+        let RecvError { socket } = Pin::into_inner(self);
+        try_io!(socket.try_recv_error())
+    }
+}
+
+/// Error read from the socket's error queue, see [`UdpSocket::recv_error`].
+#[cfg(target_os = "linux")]
+#[derive(Debug)]
+pub struct QueuedError {
+    error: io::Error,
+    origin: ErrorOrigin,
+    icmp_type: u8,
+    icmp_code: u8,
+    info: u32,
+    offender: Option<SocketAddr>,
+}
+
+#[cfg(target_os = "linux")]
+impl QueuedError {
+    /// Create a `QueuedError` from a `sock_extended_err`, followed by the
+    /// offender's address.
+    ///
+    /// # Safety
+    ///
+    /// `data` must be valid for reads of `len` bytes.
+    unsafe fn from_raw(data: *const libc::c_uchar, len: usize) -> io::Result<QueuedError> {
+        if len < size_of::<libc::sock_extended_err>() {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "extended error too small",
+            ));
+        }
+        let err: libc::sock_extended_err = ptr::read_unaligned(data.cast());
+        let origin = match err.ee_origin {
+            libc::SO_EE_ORIGIN_LOCAL => ErrorOrigin::Local,
+            libc::SO_EE_ORIGIN_ICMP => ErrorOrigin::Icmp,
+            libc::SO_EE_ORIGIN_ICMP6 => ErrorOrigin::Icmp6,
+            _ => ErrorOrigin::Other,
+        };
+
+        let offender = libc::SO_EE_OFFENDER(data.cast()) as *const u8;
+        let offender_len = len - size_of::<libc::sock_extended_err>();
+        let offender = if offender_len >= size_of::<libc::sa_family_t>() {
+            let family: libc::sa_family_t = ptr::read_unaligned(offender.cast());
+            let address_len = match libc::c_int::from(family) {
+                libc::AF_INET => size_of::<libc::sockaddr_in>(),
+                libc::AF_INET6 => size_of::<libc::sockaddr_in6>(),
+                // `AF_UNSPEC` is used if there is no offender.
+                _ => 0,
+            };
+            if address_len != 0 && offender_len >= address_len {
+                let (_, address) = SockAddr::init(|storage, len| {
+                    ptr::copy_nonoverlapping(offender, storage.cast(), address_len);
+                    *len = address_len as libc::socklen_t;
+                    Ok(())
+                })?;
+                address.as_socket()
+            } else {
+                None
+            }
+        } else {
+            None
+        };
+
+        Ok(QueuedError {
+            error: io::Error::from_raw_os_error(err.ee_errno as i32),
+            origin,
+            icmp_type: err.ee_type,
+            icmp_code: err.ee_code,
+            info: err.ee_info,
+            offender,
+        })
+    }
+
+    /// Returns the error, e.g. [`io::ErrorKind::ConnectionRefused`] for an
+    /// ICMP port unreachable message.
+    pub fn error(&self) -> &io::Error {
+        &self.error
+    }
+
+    /// Returns the error, consuming `self`.
+    pub fn into_error(self) -> io::Error {
+        self.error
+    }
+
+    /// Returns the origin of the error.
+    pub fn origin(&self) -> ErrorOrigin {
+        self.origin
+    }
+
+    /// Returns the ICMP type, only valid if the [origin] is [`ErrorOrigin::Icmp`]
+    /// or [`ErrorOrigin::Icmp6`].
+    ///
+    /// [origin]: QueuedError::origin
+    pub fn icmp_type(&self) -> u8 {
+        self.icmp_type
+    }
+
+    /// Returns the ICMP code, only valid if the [origin] is [`ErrorOrigin::Icmp`]
+    /// or [`ErrorOrigin::Icmp6`].
+    ///
+    /// [origin]: QueuedError::origin
+    pub fn icmp_code(&self) -> u8 {
+        self.icmp_code
+    }
+
+    /// Returns the path MTU if the error is caused by a packet that was too
+    /// big (`EMSGSIZE`), e.g. by a ICMP fragmentation needed message.
+    pub fn mtu(&self) -> Option<u32> {
+        match self.error.raw_os_error() {
+            Some(libc::EMSGSIZE) => Some(self.info),
+            _ => None,
+        }
+    }
+
+    /// Returns the address of the host that caused the error, if known. For
+    /// ICMP errors this is the host that send the ICMP message, which is not
+    /// necessarily the destination of the packet.
+    pub fn offender(&self) -> Option<SocketAddr> {
+        self.offender
+    }
+}
+
+/// Origin of a [`QueuedError`].
+#[cfg(target_os = "linux")]
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+#[non_exhaustive]
+pub enum ErrorOrigin {
+    /// Error generated by the local network stack.
+    Local,
+    /// ICMP message, e.g. destination unreachable.
+    Icmp,
+    /// ICMPv6 message, e.g. packet too big.
+    Icmp6,
+    /// Other origin.
+    Other,
+}
+
+/// `UDP_SEGMENT` socket option, from `linux/udp.h`.
+#[cfg(target_os = "linux")]
+const UDP_SEGMENT: libc::c_int = 103;
+
+/// Maximum number of segments in a single GSO send, `UDP_MAX_SEGMENTS` from
+/// `linux/udp.h`.
+#[cfg(target_os = "linux")]
+const UDP_MAX_SEGMENTS: usize = 64;
+
+/// Set socket option `name` at `level` to `value`.
+#[cfg(target_os = "linux")]
+fn set_int_option(
+    socket: &net::UdpSocket,
+    level: libc::c_int,
+    name: libc::c_int,
+    value: libc::c_int,
+) -> io::Result<()> {
+    let res = unsafe {
+        libc::setsockopt(
+            socket.as_raw_fd(),
+            level,
+            name,
+            (&value as *const libc::c_int).cast(),
+            size_of::<libc::c_int>() as libc::socklen_t,
+        )
+    };
+    if res == -1 {
+        Err(io::Error::last_os_error())
+    } else {
+        Ok(())
+    }
+}
+
+/// Get socket option `name` at `level`.
+#[cfg(target_os = "linux")]
+fn get_int_option(
+    socket: &net::UdpSocket,
+    level: libc::c_int,
+    name: libc::c_int,
+) -> io::Result<libc::c_int> {
+    let mut value: libc::c_int = 0;
+    let mut len = size_of::<libc::c_int>() as libc::socklen_t;
+    let res = unsafe {
+        libc::getsockopt(
+            socket.as_raw_fd(),
+            level,
+            name,
+            (&mut value as *mut libc::c_int).cast(),
+            &mut len,
+        )
+    };
+    if res == -1 {
+        Err(io::Error::last_os_error())
+    } else {
+        Ok(value)
+    }
+}
+
 impl<M> fmt::Debug for UdpSocket<M> {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         self.socket.fmt(f)
@@ -6,9 +6,11 @@
 //! In addition to creating a new pipe it's also possible to create a pipe from
 //! a process' standard I/O when [spawning another process]. For this use
 //! [`Sender::from_child_stdin`], [`Receiver::from_child_stdout`] and
-//! [`Receiver::from_child_stderr`] methods. See the example below.
+//! [`Receiver::from_child_stderr`] methods. See the example below, or use
+//! [`process::spawn`] which does this for all piped standard I/O.
 //!
 //! [spawning another process]: std::process::Command
+//! [`process::spawn`]: crate::process::spawn
 //!
 //! # Notes
 //!
@@ -0,0 +1,153 @@
+//! Spawning and supervising child processes.
+//!
+//! Not to be confused with Heph's own processes, see the [`rt`] module, this
+//! module is about spawning other programs as a child process of the runtime.
+//!
+//! A child process is spawned using [`spawn`], which returns a [`Child`]. The
+//! standard in, out and error of the child that are configured as piped (using
+//! [`Stdio::piped`]) are converted into non-blocking [`pipe::Sender`] and
+//! [`pipe::Receiver`]s, registered with the actor's runtime. This allows an
+//! actor to stream the output of a child process without blocking the worker
+//! thread. Waiting for the child process to exit is done on the same pool of
+//! threads used by the [`fs`] module, see [`Child::wait`].
+//!
+//! [`rt`]: crate::rt
+//! [`Stdio::piped`]: std::process::Stdio::piped
+//! [`fs`]: crate::fs
+//!
+//! # Examples
+//!
+//! ```
+//! # #![feature(never_type)]
+//! use std::io;
+//! use std::process::{Command, Stdio};
+//!
+//! use heph::{actor, process, rt};
+//!
+//! async fn actor<RT>(mut ctx: actor::Context<!, RT>) -> io::Result<()>
+//!     where RT: rt::Access,
+//! {
+//!     let mut command = Command::new("echo");
+//!     command.arg("Hello, world!").stdout(Stdio::piped());
+//!     let mut child = process::spawn(&mut ctx, &mut command)?;
+//!
+//!     // Read all output of the child process.
+//!     let mut stdout = child.stdout.take().unwrap();
+//!     let mut output = Vec::with_capacity(64);
+//!     while stdout.read(&mut output).await? != 0 {}
+//!     assert_eq!(output, b"Hello, world!\n");
+//!
+//!     // Wait for the process to exit.
+//!     let status = child.wait().await?;
+//!     assert!(status.success());
+//!     Ok(())
+//! }
+//! #
+//! # let actor_ref = heph::test::try_spawn(
+//! #     heph::test::PanicSupervisor,
+//! #     actor as fn(_) -> _,
+//! #     (),
+//! #     heph::spawn::ActorOptions::default(),
+//! # ).unwrap();
+//! # heph::test::join(&actor_ref, std::time::Duration::from_secs(1)).unwrap();
+//! ```
+
+use std::io;
+use std::process::{Command, ExitStatus};
+
+use crate::fs::{self, Operation};
+use crate::{actor, pipe, rt};
+
+/// Spawn `command` as a child process.
+///
+/// The standard in, out and error of the process that are [piped] are
+/// registered with the runtime of `ctx`, see [`Child`].
+///
+/// [piped]: std::process::Stdio::piped
+pub fn spawn<M, RT>(ctx: &mut actor::Context<M, RT>, command: &mut Command) -> io::Result<Child>
+where
+    RT: rt::Access,
+{
+    let mut inner = command.spawn()?;
+    let stdin = match inner.stdin.take() {
+        Some(stdin) => Some(pipe::Sender::from_child_stdin(ctx, stdin)?),
+        None => None,
+    };
+    let stdout = match inner.stdout.take() {
+        Some(stdout) => Some(pipe::Receiver::from_child_stdout(ctx, stdout)?),
+        None => None,
+    };
+    let stderr = match inner.stderr.take() {
+        Some(stderr) => Some(pipe::Receiver::from_child_stderr(ctx, stderr)?),
+        None => None,
+    };
+    Ok(Child {
+        stdin,
+        stdout,
+        stderr,
+        inner,
+    })
+}
+
+/// A child process, spawned using [`spawn`].
+///
+/// This is the asynchronous version of [`std::process::Child`], the standard
+/// I/O handles are non-blocking [`pipe`]s and waiting for the process to exit
+/// doesn't block the actor.
+///
+/// # Notes
+///
+/// Just like [`std::process::Child`] the child process is **not** killed or
+/// waited on when the `Child` is dropped.
+///
+/// The standard I/O handles are [bound] to the actor that spawned the process.
+///
+/// [bound]: crate::actor::Bound
+#[derive(Debug)]
+pub struct Child {
+    /// Handle to the standard input of the child process, if it was piped.
+    pub stdin: Option<pipe::Sender>,
+    /// Handle to the standard output of the child process, if it was piped.
+    pub stdout: Option<pipe::Receiver>,
+    /// Handle to the standard error of the child process, if it was piped.
+    pub stderr: Option<pipe::Receiver>,
+    inner: std::process::Child,
+}
+
+impl Child {
+    /// Returns the OS-assigned process identifier of the child process.
+    pub fn id(&self) -> u32 {
+        self.inner.id()
+    }
+
+    /// Kill the child process, using `SIGKILL`.
+    ///
+    /// See [`std::process::Child::kill`].
+    pub fn kill(&mut self) -> io::Result<()> {
+        self.inner.kill()
+    }
+
+    /// Returns the exit status of the child process, if it has exited,
+    /// without blocking.
+    ///
+    /// See [`std::process::Child::try_wait`].
+    pub fn try_wait(&mut self) -> io::Result<Option<ExitStatus>> {
+        self.inner.try_wait()
+    }
+
+    /// Wait for the child process to exit, returning its exit status.
+    ///
+    /// Standard input is closed before waiting, to prevent a deadlock where
+    /// the child waits for more input. The remaining standard I/O handles are
+    /// dropped, take them out of the `Child` to read all output first.
+    ///
+    /// Waiting is done on the blocking thread pool, see the [`fs`] module.
+    /// Because of this the `Child` is consumed, use [`Child::kill`] before
+    /// waiting to stop the process.
+    ///
+    /// [`fs`]: crate::fs
+    pub fn wait(self) -> Operation<ExitStatus> {
+        let Child { mut inner, .. } = self;
+        fs::run(move || inner.wait())
+    }
+}
@@ -13,8 +13,10 @@ use mio::{event, Interest};
 
 use crate::actor::{self, NewActor};
 use crate::actor_ref::ActorRef;
+use crate::bus::Bus;
+use crate::config::ReloadError;
 use crate::rt::process::ProcessId;
-use crate::rt::{shared, RuntimeRef};
+use crate::rt::{shared, JoinHandle, RuntimeRef};
 use crate::spawn::{ActorOptions, AddActorError, FutureOptions, PrivateSpawn, Spawn};
 use crate::supervisor::Supervisor;
 use crate::trace::{self, Trace};
@@ -64,6 +66,16 @@ pub trait PrivateAccess {
     /// Returns the CPU the thread is bound to, if any.
     fn cpu(&self) -> Option<usize>;
 
+    /// Report the process as ready, see [`actor::Context::report_ready`].
+    fn report_ready(&mut self);
+
+    /// Returns `true` if the runtime is ready, otherwise `waker` is woken once
+    /// it is.
+    fn poll_ready(&self, waker: &task::Waker) -> bool;
+
+    /// Returns the event bus of the runtime.
+    fn bus(&self) -> &Bus;
+
     /// Start timing an event if tracing is enabled, see [`trace::start`].
     fn start_trace(&self) -> Option<trace::EventTiming>;
 
@@ -162,6 +174,18 @@ impl PrivateAccess for ThreadLocal {
         self.rt.cpu()
     }
 
+    fn report_ready(&mut self) {
+        self.rt.internals.shared.readiness().report_ready(self.pid);
+    }
+
+    fn poll_ready(&self, waker: &task::Waker) -> bool {
+        self.rt.internals.shared.readiness().poll_ready(waker)
+    }
+
+    fn bus(&self) -> &Bus {
+        self.rt.internals.shared.bus()
+    }
+
     fn start_trace(&self) -> Option<trace::EventTiming> {
         self.rt.start_trace()
     }
@@ -268,12 +292,63 @@ impl ThreadSafe {
     /// Spawn a thread-safe [`Future`].
     ///
     /// See [`RuntimeRef::spawn_future`] for more documentation.
-    pub fn spawn_future<Fut>(&mut self, future: Fut, options: FutureOptions)
+    pub fn spawn_future<Fut>(
+        &mut self,
+        future: Fut,
+        options: FutureOptions,
+    ) -> JoinHandle<Fut::Output>
     where
-        Fut: Future<Output = ()> + Send + Sync + 'static,
+        Fut: Future + Send + Sync + 'static,
+        Fut::Output: Send + 'static,
     {
         self.rt.spawn_future(future, options)
     }
+
+    /// Returns `true` if the runtime is ready.
+    ///
+    /// See [`RuntimeRef::is_ready`] for more documentation.
+    pub fn is_ready(&self) -> bool {
+        self.rt.readiness().is_ready()
+    }
+
+    /// Returns the event bus of the runtime.
+    ///
+    /// See [`RuntimeRef::bus`] for more documentation.
+    pub fn bus(&self) -> &Bus {
+        self.rt.bus()
+    }
+
+    /// Returns the current configuration of type `T`, if any.
+    ///
+    /// See [`RuntimeRef::config`] for more documentation.
+    pub fn config<T>(&self) -> Option<Arc<T>>
+    where
+        T: Send + Sync + 'static,
+    {
+        self.rt.config().get()
+    }
+
+    /// Update the configuration of type `T` to `config`.
+    ///
+    /// See [`RuntimeRef::update_config`] for more documentation.
+    pub fn update_config<T>(&mut self, config: T) -> usize
+    where
+        T: Send + Sync + 'static,
+    {
+        self.rt.config().update(self.rt.bus(), config)
+    }
+
+    /// Reload all configuration values.
+    ///
+    /// See [`RuntimeRef::reload_config`] for more documentation.
+    pub fn reload_config(&mut self) -> Result<(), ReloadError> {
+        self.rt.config().reload(self.rt.bus())
+    }
+
+    /// Returns the shared runtime internals.
+    pub(crate) fn shared_internals(&self) -> &shared::RuntimeInternals {
+        &self.rt
+    }
 }
 
 impl Access for ThreadSafe {}
@@ -321,6 +396,18 @@ impl PrivateAccess for ThreadSafe {
         None
     }
 
+    fn report_ready(&mut self) {
+        self.rt.readiness().report_ready(self.pid);
+    }
+
+    fn poll_ready(&self, waker: &task::Waker) -> bool {
+        self.rt.readiness().poll_ready(waker)
+    }
+
+    fn bus(&self) -> &Bus {
+        self.rt.bus()
+    }
+
     fn start_trace(&self) -> Option<trace::EventTiming> {
         self.rt.start_trace()
     }
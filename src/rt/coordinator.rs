@@ -19,6 +19,7 @@ use crate::rt::thread_waker::ThreadWaker;
 use crate::rt::{
     self, cpu_usage, shared, Signal, SyncWorker, Worker, SYNC_WORKER_ID_END, SYNC_WORKER_ID_START,
 };
+use crate::spawn::ActorOptions;
 use crate::trace;
 
 /// Token used to receive process signals.
@@ -94,13 +95,17 @@ impl Coordinator {
         app_name: Box<str>,
         worker_wakers: Box<[&'static ThreadWaker]>,
         trace_log: Option<Arc<trace::SharedLog>>,
+        default_actor_options: ActorOptions,
+        process_stats: bool,
     ) -> io::Result<Coordinator> {
         let poll = Poll::new()?;
         // NOTE: on Linux this MUST be created before starting the worker
         // threads.
         let signals = setup_signals(poll.registry())?;
 
-        let setup = shared::RuntimeInternals::setup()?;
+        let setup = shared::RuntimeInternals::setup()?
+            .with_default_actor_options(default_actor_options)
+            .with_process_stats(process_stats);
         let internals = Arc::new_cyclic(|shared_internals| {
             let waker_id = waker::init(shared_internals.clone());
             setup.complete(waker_id, worker_wakers, trace_log)
@@ -186,7 +191,7 @@ impl Coordinator {
                 match event.token() {
                     SIGNAL => {
                         let timing = trace::start(&trace_log);
-                        let log_metrics =
+                        let relayed =
                             relay_signals(&mut self.signals, &mut workers, &mut signal_refs);
                         trace::finish_rt(
                             trace_log.as_mut(),
@@ -195,7 +200,18 @@ impl Coordinator {
                             &[],
                         );
 
-                        if log_metrics {
+                        if relayed.dump_schedulers {
+                            let timing = trace::start(&trace_log);
+                            self.dump_schedulers(&workers);
+                            trace::finish_rt(
+                                trace_log.as_mut(),
+                                timing,
+                                "Dumping scheduler state",
+                                &[],
+                            );
+                        }
+
+                        if relayed.log_metrics {
                             let timing = trace::start(&trace_log);
                             let metrics =
                                 self.metrics(&workers, &sync_workers, &signal_refs, &trace_log);
@@ -240,6 +256,28 @@ impl Coordinator {
         }
     }
 
+    /// Log the state of the shared scheduler and the process running on each
+    /// worker thread. The worker threads log the state of their own schedulers,
+    /// see [`Signal::Quit`].
+    fn dump_schedulers(&self, workers: &[Worker]) {
+        info!(target: "dump", "shared scheduler dump: {:?}", self.internals.metrics());
+        for worker in workers {
+            match worker.running_process() {
+                Some(pid) => info!(
+                    target: "dump",
+                    "worker thread running process: worker={}, pid={}",
+                    worker.id(),
+                    pid
+                ),
+                None => info!(
+                    target: "dump",
+                    "worker thread not running a process: worker={}",
+                    worker.id()
+                ),
+            }
+        }
+    }
+
     /// Gather metrics about the coordinator and runtime.
     fn metrics<'c, 'l>(
         &'c self,
@@ -450,29 +488,41 @@ fn check_sync_worker_alive(sync_workers: &mut Vec<SyncWorker>) -> Result<(), rt:
     sync_workers
         .drain_filter(|sync_worker| !sync_worker.is_alive())
         .try_for_each(|sync_worker| {
-            debug!("sync actor worker thread stopped: id={}", sync_worker.id());
-            sync_worker.join().map_err(rt::Error::sync_actor_panic)
+            let id = sync_worker.id();
+            debug!("sync actor worker thread stopped: id={}", id);
+            sync_worker
+                .join()
+                .map_err(|err| rt::Error::sync_actor_panic(id, err))
         })
 }
 
+/// Actions to take after relaying signals, see [`relay_signals`].
+#[derive(Copy, Clone, Debug, Default)]
+struct RelayedSignals {
+    /// Received `SIGUSR2`, which is used to get metrics from the runtime.
+    log_metrics: bool,
+    /// Received `SIGQUIT`, which is used to dump the state of the schedulers.
+    dump_schedulers: bool,
+}
+
 /// Relay all signals received from `signals` to the `workers` and
 /// `signal_refs`.
-/// Returns a bool indicating we received `SIGUSR2`, which is used to get
-/// metrics from the runtime. If this returns `true` call `log_metrics`.
 fn relay_signals(
     signals: &mut Signals,
     workers: &mut [Worker],
     signal_refs: &mut ActorGroup<Signal>,
-) -> bool {
+) -> RelayedSignals {
     signal_refs.remove_disconnected();
 
-    let mut log_metrics = false;
+    let mut relayed = RelayedSignals::default();
     loop {
         match signals.receive() {
             Ok(Some(signal)) => {
                 let signal = Signal::from_mio(signal);
-                if let Signal::User2 = signal {
-                    log_metrics = true;
+                match signal {
+                    Signal::User2 => relayed.log_metrics = true,
+                    Signal::Quit => relayed.dump_schedulers = true,
+                    _ => {}
                 }
 
                 debug!(
@@ -500,7 +550,9 @@ fn relay_signals(
                 if !signal_refs.is_empty() {
                     // Safety: only returns an error if the group is empty, so
                     // this `unwrap` is safe.
-                    signal_refs.try_send(signal, Delivery::ToAll).unwrap();
+                    signal_refs
+                        .try_send_priority(signal, Delivery::ToAll)
+                        .unwrap();
                 }
             }
             Ok(None) => break,
@@ -510,7 +562,7 @@ fn relay_signals(
             }
         }
     }
-    log_metrics
+    relayed
 }
 
 /// Handle an `event` for a worker.
@@ -520,10 +572,11 @@ fn handle_worker_event(workers: &mut Vec<Worker>, event: &Event) -> Result<(), r
             // Receiving end of the pipe is dropped, which means the worker has
             // shut down.
             let worker = workers.remove(i);
-            debug!("worker thread stopped: id={}", worker.id());
+            let id = worker.id();
+            debug!("worker thread stopped: id={}", id);
             worker
                 .join()
-                .map_err(rt::Error::worker_panic)
+                .map_err(|err| rt::Error::worker_panic(id, err))
                 .and_then(|res| res)?;
         }
     }
@@ -540,8 +593,11 @@ fn handle_sync_worker_event(
             // Receiving end of the pipe is dropped, which means the sync worker
             // has shut down.
             let sync_worker = sync_workers.remove(i);
-            debug!("sync actor worker thread stopped: id={}", sync_worker.id());
-            sync_worker.join().map_err(rt::Error::sync_actor_panic)?;
+            let id = sync_worker.id();
+            debug!("sync actor worker thread stopped: id={}", id);
+            sync_worker
+                .join()
+                .map_err(|err| rt::Error::sync_actor_panic(id, err))?;
         }
     }
     Ok(())
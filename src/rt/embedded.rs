@@ -0,0 +1,176 @@
+//! Module with the embeddable runtime.
+
+use std::cell::Cell;
+use std::future::Future;
+use std::io;
+use std::num::NonZeroUsize;
+use std::rc::Rc;
+use std::sync::Arc;
+
+use crate::rt::local::{self, Control};
+use crate::rt::shared::waker;
+use crate::rt::{self, shared, worker, Error, RuntimeRef};
+use crate::spawn::FutureOptions;
+
+/// Worker id used by the [`EmbeddedRuntime`], as if it's the first worker
+/// thread.
+const ID: usize = 1;
+
+/// Single-threaded runtime that is driven by the user.
+///
+/// Where [`Runtime`] takes ownership of the main thread and runs all actors on
+/// worker threads it starts itself, an `EmbeddedRuntime` runs all its actors
+/// on the thread that created it, and only when asked to. This allows Heph's
+/// actors to be hosted inside another event loop, for example a GUI event loop
+/// or a game loop, that owns the main thread.
+///
+/// There are two ways to drive the runtime:
+///
+/// - [`poll_once`] runs a single iteration of the event loop without ever
+///   blocking, meant to be called for example once per frame in a game loop.
+/// - [`run_until`] runs the event loop until a future completes, blocking the
+///   thread while waiting for events.
+///
+/// Actors and futures can be spawned using the [`RuntimeRef`] returned by
+/// [`runtime_ref`]. Thread-safe actors and futures are run on this thread as
+/// well, as there are no other worker threads.
+///
+/// # Notes
+///
+/// Every `EmbeddedRuntime` uses one of the 128 slots for waking threads, which
+/// are shared with the worker threads of a [`Runtime`] and are never reused.
+///
+/// Process signals are not handled by an `EmbeddedRuntime`, that is up to the
+/// host application.
+///
+/// Run statistics of processes are always collected, see
+/// [`RuntimeRef::process_stats`].
+///
+/// [`Runtime`]: rt::Runtime
+/// [`RuntimeRef::process_stats`]: rt::RuntimeRef::process_stats
+/// [`poll_once`]: EmbeddedRuntime::poll_once
+/// [`run_until`]: EmbeddedRuntime::run_until
+/// [`runtime_ref`]: EmbeddedRuntime::runtime_ref
+///
+/// # Examples
+///
+/// ```
+/// use heph::rt::{self, EmbeddedRuntime};
+/// use heph::spawn::FutureOptions;
+///
+/// fn main() -> Result<(), rt::Error> {
+///     let mut runtime = EmbeddedRuntime::new()?;
+///
+///     // Spawn a future, actors can be spawned in the same way.
+///     let future = async { println!("Hello from the embedded runtime") };
+///     runtime
+///         .runtime_ref()
+///         .spawn_local_future(future, FutureOptions::default());
+///
+///     // The host's event loop, e.g. a game loop.
+///     loop {
+///         // Do the host's work, e.g. render a frame.
+///
+///         // Run the processes that are ready, without blocking.
+///         if !runtime.poll_once()? {
+///             // No more processes left to run.
+///             break;
+///         }
+///     }
+///
+///     // Or block until a future is done.
+///     let answer = runtime.run_until(async { 42 })?;
+///     assert_eq!(answer, 42);
+///     Ok(())
+/// }
+/// ```
+#[derive(Debug)]
+pub struct EmbeddedRuntime {
+    runtime: local::Runtime,
+    runtime_ref: RuntimeRef,
+    /// Sending side of the local runtime's communication channel. Not used,
+    /// but dropping it would disconnect the channel.
+    _channel: rt::channel::Sender<Control>,
+}
+
+impl EmbeddedRuntime {
+    /// Create a new `EmbeddedRuntime` on the current thread.
+    #[allow(clippy::new_without_default)]
+    pub fn new() -> Result<EmbeddedRuntime, Error> {
+        let (setup, thread_waker) =
+            worker::setup(NonZeroUsize::new(ID).unwrap()).map_err(init_error)?;
+
+        let shared_setup = shared::RuntimeInternals::setup()
+            .map_err(init_error)?
+            .with_process_stats(true);
+        let shared_internals = Arc::new_cyclic(|shared_internals| {
+            let waker_id = waker::init(shared_internals.clone());
+            let worker_wakers = vec![thread_waker].into_boxed_slice();
+            shared_setup.complete(waker_id, worker_wakers, None)
+        });
+
+        let (channel, receiver) = rt::channel::new().map_err(init_error)?;
+        let runtime = setup
+            .into_runtime(receiver, shared_internals)
+            .map_err(init_error)?;
+        let runtime_ref = runtime.create_ref();
+        Ok(EmbeddedRuntime {
+            runtime,
+            runtime_ref,
+            _channel: channel,
+        })
+    }
+
+    /// Returns a reference to the runtime, which can be used to spawn actors
+    /// and futures.
+    pub fn runtime_ref(&mut self) -> &mut RuntimeRef {
+        &mut self.runtime_ref
+    }
+
+    /// Run a single iteration of the event loop.
+    ///
+    /// This runs a batch of processes that are ready to run and then checks
+    /// all event sources (e.g. I/O, timers and wake-ups) to schedule processes
+    /// for the next call. This never blocks waiting for events.
+    ///
+    /// Returns `true` if there are processes left, which may or may not be
+    /// ready to run, or `false` if all processes have completed.
+    pub fn poll_once(&mut self) -> Result<bool, Error> {
+        self.runtime
+            .poll_once(&mut self.runtime_ref)
+            .map_err(|err| Error::worker(ID, err))
+    }
+
+    /// Run the event loop until `future` is completed, returning its output.
+    ///
+    /// `future` is run as thread-local future. All other processes are run as
+    /// well, but unlike [`rt::Runtime::start`] this doesn't wait for them to
+    /// complete. If the `future` never completes this blocks forever.
+    pub fn run_until<Fut>(&mut self, future: Fut) -> Result<Fut::Output, Error>
+    where
+        Fut: Future + 'static,
+        Fut::Output: 'static,
+    {
+        let output = Rc::new(Cell::new(None));
+        let future_output = output.clone();
+        self.runtime_ref.spawn_local_future(
+            async move { future_output.set(Some(future.await)) },
+            FutureOptions::default(),
+        );
+
+        let mut result = None;
+        self.runtime
+            .run_event_loop_until(&mut self.runtime_ref, || {
+                result = output.take();
+                result.is_some()
+            })
+            .map_err(|err| Error::worker(ID, err))?;
+        // `run_event_loop_until` only returns once we have the output.
+        Ok(result.unwrap())
+    }
+}
+
+/// Error returned when initialising the [`EmbeddedRuntime`] fails.
+fn init_error(err: io::Error) -> Error {
+    Error::worker(ID, local::Error::Init(err))
+}
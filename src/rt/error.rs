@@ -7,6 +7,10 @@ use crate::rt::{coordinator, worker};
 
 /// Error returned by running a [`Runtime`].
 ///
+/// Use [`Error::kind`] to determine what kind of failure occurred, and
+/// [`Error::worker_id`] to determine on which thread. The underlying error, if
+/// any, is available using [`std::error::Error::source`].
+///
 /// [`Runtime`]: crate::rt::Runtime
 pub struct Error {
     inner: ErrorInner,
@@ -27,14 +31,37 @@ enum ErrorInner {
     /// Error starting worker thread.
     StartWorker(io::Error),
     /// Error in a worker thread.
-    Worker(worker::Error),
+    Worker(usize, worker::Error),
     /// Panic in a worker thread.
-    WorkerPanic(StringError),
+    WorkerPanic(usize, StringError),
 
     /// Error starting synchronous actor thread.
     StartSyncActor(io::Error),
     /// Panic in a synchronous actor thread.
-    SyncActorPanic(StringError),
+    SyncActorPanic(usize, StringError),
+}
+
+/// The kind of [`Error`], see [`Error::kind`].
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+#[non_exhaustive]
+pub enum ErrorKind {
+    /// Error setting up the runtime, e.g. starting a thread or a user-defined
+    /// setup error (see [`Error::setup`]).
+    Setup,
+    /// Error polling for events, e.g. in a worker or the coordinator thread.
+    Poll,
+    /// A worker thread, or a thread running a synchronous actor, panicked.
+    WorkerPanic,
+    /// A function run on a worker thread, see [`Runtime::run_on_workers`],
+    /// returned an error.
+    ///
+    /// [`Runtime::run_on_workers`]: crate::rt::Runtime::run_on_workers
+    UserFunction,
+    /// The process received a signal that should stop it, but no actor could
+    /// receive it. See [`Runtime::receive_signals`].
+    ///
+    /// [`Runtime::receive_signals`]: crate::rt::Runtime::receive_signals
+    Interrupted,
 }
 
 impl Error {
@@ -54,6 +81,41 @@ impl Error {
         }
     }
 
+    /// Returns the kind of error.
+    pub fn kind(&self) -> ErrorKind {
+        use ErrorInner::*;
+        match self.inner {
+            Setup(..) | SetupTrace(..) | InitCoordinator(..) | StartWorker(..)
+            | StartSyncActor(..) => ErrorKind::Setup,
+            Coordinator(ref err) => match err {
+                coordinator::Error::Polling(..) => ErrorKind::Poll,
+                coordinator::Error::RegisteringWorkers(..)
+                | coordinator::Error::RegisteringSyncActors(..)
+                | coordinator::Error::SendingStartSignal(..)
+                | coordinator::Error::SendingFunc(..) => ErrorKind::Setup,
+            },
+            Worker(_, ref err) => match err {
+                worker::Error::Init(..) => ErrorKind::Setup,
+                worker::Error::Polling(..) | worker::Error::RecvMsg(..) => ErrorKind::Poll,
+                worker::Error::ProcessInterrupted => ErrorKind::Interrupted,
+                worker::Error::UserFunction(..) => ErrorKind::UserFunction,
+            },
+            WorkerPanic(..) | SyncActorPanic(..) => ErrorKind::WorkerPanic,
+        }
+    }
+
+    /// Returns the id of the worker thread, or thread running a synchronous
+    /// actor, in which the error occurred. Returns `None` if the error didn't
+    /// occur in a specific thread, e.g. in the coordinator or when starting
+    /// the runtime.
+    pub fn worker_id(&self) -> Option<usize> {
+        use ErrorInner::*;
+        match self.inner {
+            Worker(id, _) | WorkerPanic(id, _) | SyncActorPanic(id, _) => Some(id),
+            _ => None,
+        }
+    }
+
     pub(super) const fn setup_trace(err: io::Error) -> Error {
         Error {
             inner: ErrorInner::SetupTrace(err),
@@ -78,16 +140,16 @@ impl Error {
         }
     }
 
-    pub(super) const fn worker(err: worker::Error) -> Error {
+    pub(super) const fn worker(id: usize, err: worker::Error) -> Error {
         Error {
-            inner: ErrorInner::Worker(err),
+            inner: ErrorInner::Worker(id, err),
         }
     }
 
-    pub(super) fn worker_panic(err: Box<dyn Any + Send + 'static>) -> Error {
+    pub(super) fn worker_panic(id: usize, err: Box<dyn Any + Send + 'static>) -> Error {
         let msg = convert_panic(err);
         Error {
-            inner: ErrorInner::WorkerPanic(msg),
+            inner: ErrorInner::WorkerPanic(id, msg),
         }
     }
 
@@ -97,10 +159,10 @@ impl Error {
         }
     }
 
-    pub(super) fn sync_actor_panic(err: Box<dyn Any + Send + 'static>) -> Error {
+    pub(super) fn sync_actor_panic(id: usize, err: Box<dyn Any + Send + 'static>) -> Error {
         let msg = convert_panic(err);
         Error {
-            inner: ErrorInner::SyncActorPanic(msg),
+            inner: ErrorInner::SyncActorPanic(id, msg),
         }
     }
 }
@@ -154,18 +216,23 @@ impl fmt::Display for Error {
             StartWorker(ref err) => {
                 write!(f, "{}: error starting worker thread: {}", Self::DESC, err)
             }
-            Worker(ref err) => write!(f, "{}: error in worker thread: {}", Self::DESC, err),
-            WorkerPanic(ref msg) => write!(f, "{}: panic in worker thread: {}", Self::DESC, msg),
+            Worker(id, ref err) => {
+                write!(f, "{}: error in worker thread {}: {}", Self::DESC, id, err)
+            }
+            WorkerPanic(id, ref msg) => {
+                write!(f, "{}: panic in worker thread {}: {}", Self::DESC, id, msg)
+            }
             StartSyncActor(ref err) => write!(
                 f,
                 "{}: error starting synchronous actor: {}",
                 Self::DESC,
                 err
             ),
-            SyncActorPanic(ref msg) => write!(
+            SyncActorPanic(id, ref msg) => write!(
                 f,
-                "{}: panic in synchronous actor thread: {}",
+                "{}: panic in synchronous actor thread {}: {}",
                 Self::DESC,
+                id,
                 msg
             ),
         }
@@ -182,9 +249,9 @@ impl std::error::Error for Error {
             | StartWorker(ref err)
             | StartSyncActor(ref err) => Some(err),
             Coordinator(ref err) => Some(err),
-            Worker(ref err) => Some(err),
+            Worker(_, ref err) => Some(err),
             // All `StringError`.
-            Setup(ref err) | WorkerPanic(ref err) | SyncActorPanic(ref err) => Some(err),
+            Setup(ref err) | WorkerPanic(_, ref err) | SyncActorPanic(_, ref err) => Some(err),
         }
     }
 }
@@ -0,0 +1,239 @@
+//! Module with the [`JoinHandle`] returned when spawning a future.
+
+use std::error::Error;
+use std::fmt;
+use std::future::Future;
+use std::mem::replace;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+use std::task::{self, Poll, Waker};
+
+/// Create a new [`JoinHandle`] and the [`Completion`] used by the process
+/// running the future.
+pub(crate) fn new<T>() -> (Completion<T>, JoinHandle<T>) {
+    let shared = Arc::new(Mutex::new(State::Running {
+        task_waker: None,
+        join_waker: None,
+    }));
+    let completion = Completion {
+        shared: shared.clone(),
+    };
+    (completion, JoinHandle { shared })
+}
+
+/// State shared between the [`JoinHandle`] and the [`Completion`].
+enum State<T> {
+    /// Future is still running.
+    Running {
+        /// Waker of the process running the future, used to wake it when the
+        /// future is aborted.
+        task_waker: Option<Waker>,
+        /// Waker of the task awaiting the [`JoinHandle`].
+        join_waker: Option<Waker>,
+    },
+    /// Future completed with the output.
+    Done(T),
+    /// Future was aborted using [`JoinHandle::abort`].
+    Aborted,
+    /// Future was dropped before completing.
+    Dropped,
+    /// Output was returned by the [`JoinHandle`].
+    Taken,
+}
+
+/// Handle to a spawned future.
+///
+/// This is returned when spawning a future, e.g. by
+/// [`RuntimeRef::spawn_local_future`]. The `JoinHandle` can be awaited to
+/// retrieve the output of the future, or used to [abort] it.
+///
+/// Dropping the `JoinHandle` detaches it from the future: the future will
+/// keep running, but its output is dropped once it completes.
+///
+/// [`RuntimeRef::spawn_local_future`]: crate::rt::RuntimeRef::spawn_local_future
+/// [abort]: JoinHandle::abort
+///
+/// # Examples
+///
+/// ```
+/// use heph::rt::{self, EmbeddedRuntime};
+/// use heph::spawn::FutureOptions;
+///
+/// fn main() -> Result<(), rt::Error> {
+///     let mut runtime = EmbeddedRuntime::new()?;
+///     let handle = runtime
+///         .runtime_ref()
+///         .spawn_local_future(async { 1 + 1 }, FutureOptions::default());
+///
+///     let output = runtime.run_until(handle)?;
+///     assert_eq!(output, Ok(2));
+///     Ok(())
+/// }
+/// ```
+pub struct JoinHandle<T> {
+    shared: Arc<Mutex<State<T>>>,
+}
+
+impl<T> JoinHandle<T> {
+    /// Abort the future.
+    ///
+    /// The future is dropped the next time it's scheduled, without being
+    /// polled again. Awaiting the `JoinHandle` after calling this returns
+    /// [`JoinError::Aborted`], unless the future already completed.
+    pub fn abort(&self) {
+        let mut state = self.shared.lock().unwrap();
+        if let State::Running { .. } = &*state {
+            let old_state = replace(&mut *state, State::Aborted);
+            drop(state);
+            if let State::Running {
+                task_waker,
+                join_waker,
+            } = old_state
+            {
+                task_waker
+                    .into_iter()
+                    .chain(join_waker)
+                    .for_each(Waker::wake);
+            }
+        }
+    }
+
+    /// Returns `true` if the future is no longer running, i.e. it completed,
+    /// was aborted or was dropped.
+    pub fn is_finished(&self) -> bool {
+        !matches!(*self.shared.lock().unwrap(), State::Running { .. })
+    }
+
+    /// Returns the output of the future without waiting, `None` if the future
+    /// is still running.
+    pub(crate) fn into_output(self) -> Option<Result<T, JoinError>> {
+        let mut state = self.shared.lock().unwrap();
+        match replace(&mut *state, State::Taken) {
+            State::Running {
+                task_waker,
+                join_waker,
+            } => {
+                *state = State::Running {
+                    task_waker,
+                    join_waker,
+                };
+                None
+            }
+            State::Done(output) => Some(Ok(output)),
+            State::Aborted => Some(Err(JoinError::Aborted)),
+            State::Dropped => Some(Err(JoinError::Dropped)),
+            // `JoinHandle` can't be polled after it's consumed.
+            State::Taken => unreachable!(),
+        }
+    }
+}
+
+impl<T> Future for JoinHandle<T> {
+    type Output = Result<T, JoinError>;
+
+    fn poll(self: Pin<&mut Self>, ctx: &mut task::Context<'_>) -> Poll<Self::Output> {
+        let mut state = self.shared.lock().unwrap();
+        match replace(&mut *state, State::Taken) {
+            State::Running {
+                task_waker,
+                join_waker: _,
+            } => {
+                *state = State::Running {
+                    task_waker,
+                    join_waker: Some(ctx.waker().clone()),
+                };
+                Poll::Pending
+            }
+            State::Done(output) => Poll::Ready(Ok(output)),
+            State::Aborted => Poll::Ready(Err(JoinError::Aborted)),
+            State::Dropped => Poll::Ready(Err(JoinError::Dropped)),
+            State::Taken => {
+                drop(state);
+                panic!("polled `JoinHandle` after completion")
+            }
+        }
+    }
+}
+
+impl<T> fmt::Debug for JoinHandle<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("JoinHandle")
+            .field("finished", &self.is_finished())
+            .finish()
+    }
+}
+
+/// Error returned by [`JoinHandle`] if the future didn't complete.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum JoinError {
+    /// The future was aborted using [`JoinHandle::abort`].
+    Aborted,
+    /// The future was dropped before it completed, e.g. because it panicked
+    /// or because the runtime was stopped.
+    Dropped,
+}
+
+impl fmt::Display for JoinError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            JoinError::Aborted => "future was aborted",
+            JoinError::Dropped => "future was dropped before completing",
+        })
+    }
+}
+
+impl Error for JoinError {}
+
+/// The process side of a [`JoinHandle`], used to set the output of the
+/// future.
+pub(crate) struct Completion<T> {
+    shared: Arc<Mutex<State<T>>>,
+}
+
+impl<T> Completion<T> {
+    /// Returns `true` if the future was aborted. Otherwise `waker` is used to
+    /// wake the process if the future gets aborted.
+    pub(crate) fn is_aborted(&self, waker: &Waker) -> bool {
+        match &mut *self.shared.lock().unwrap() {
+            State::Running { task_waker, .. } => {
+                if task_waker.is_none() {
+                    *task_waker = Some(waker.clone());
+                }
+                false
+            }
+            // Aborted, or otherwise no longer running.
+            _ => true,
+        }
+    }
+
+    /// Complete the future with `output`, waking the task awaiting the
+    /// [`JoinHandle`] (if any).
+    pub(crate) fn complete(&self, output: T) {
+        self.finish(State::Done(output))
+    }
+
+    fn finish(&self, new_state: State<T>) {
+        let mut state = self.shared.lock().unwrap();
+        if let State::Running { join_waker, .. } = &mut *state {
+            let join_waker = join_waker.take();
+            *state = new_state;
+            drop(state);
+            if let Some(waker) = join_waker {
+                waker.wake();
+            }
+        }
+    }
+}
+
+impl<T> Drop for Completion<T> {
+    fn drop(&mut self) {
+        // No-op if the future completed or was aborted.
+        self.finish(State::Dropped)
+    }
+}
+
+impl<T> fmt::Debug for Completion<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("Completion")
+    }
+}
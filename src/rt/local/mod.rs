@@ -1,8 +1,10 @@
 //! Module with shared runtime internals.
 
 use std::cell::{RefCell, RefMut};
+use std::hint::spin_loop;
 use std::num::NonZeroUsize;
 use std::rc::Rc;
+use std::sync::atomic::{AtomicUsize, Ordering};
 use std::sync::Arc;
 use std::time::{Duration, Instant};
 use std::{fmt, io};
@@ -13,23 +15,38 @@ use mio::{Events, Poll, Token};
 
 use crate::actor_ref::{ActorGroup, Delivery, SendError};
 use crate::rt::error::StringError;
-use crate::rt::process::ProcessId;
-use crate::rt::process::ProcessResult;
+use crate::rt::process::{self, ProcessId, ProcessResult};
 use crate::rt::{self, cpu_usage, shared, RuntimeRef, Signal, WakerId};
 use crate::trace;
 
 mod scheduler;
 mod timers;
 
-use scheduler::Scheduler;
+pub(super) use scheduler::Scheduler;
 use timers::Timers;
 
-/// Number of processes to run in between calls to poll.
-///
-/// This number is chosen arbitrarily, if you can improve it please do.
-// TODO: find a good balance between polling, polling user space events only and
-// running processes.
-const RUN_POLL_RATIO: usize = 32;
+/// Configuration of the event loop, set using [`rt::Setup`].
+#[derive(Copy, Clone, Debug)]
+pub(crate) struct EventLoopConfig {
+    /// See [`rt::Setup::event_capacity`].
+    pub(crate) event_capacity: usize,
+    /// See [`rt::Setup::busy_poll`].
+    pub(crate) busy_poll: Duration,
+    /// See [`rt::Setup::max_processes_per_poll`].
+    pub(crate) max_processes_per_poll: usize,
+}
+
+impl EventLoopConfig {
+    /// Default configuration.
+    ///
+    /// The number of processes to run in between calls to poll is chosen
+    /// arbitrarily, if you can improve it please do.
+    pub(crate) const DEFAULT: EventLoopConfig = EventLoopConfig {
+        event_capacity: 128,
+        busy_poll: Duration::ZERO,
+        max_processes_per_poll: 32,
+    };
+}
 
 /// Token used to indicate user space events have happened.
 pub(super) const WAKER: Token = Token(usize::MAX);
@@ -48,6 +65,10 @@ pub(crate) struct Runtime {
     internals: Rc<RuntimeInternals>,
     /// Mio events container.
     events: Events,
+    /// See [`rt::Setup::busy_poll`].
+    busy_poll: Duration,
+    /// See [`rt::Setup::max_processes_per_poll`].
+    max_processes_per_poll: usize,
     /// Receiving side of the channel for waker events, see the [`rt::waker`]
     /// module for the implementation.
     waker_events: Receiver<ProcessId>,
@@ -93,8 +114,12 @@ impl Runtime {
         waker_events: Receiver<ProcessId>,
         mut channel: rt::channel::Receiver<Control>,
         shared_internals: Arc<shared::RuntimeInternals>,
+        running: Arc<AtomicUsize>,
         trace_log: Option<trace::Log>,
         cpu: Option<usize>,
+        deadline_scheduling: bool,
+        time_slice: Option<Duration>,
+        config: EventLoopConfig,
     ) -> io::Result<Runtime> {
         // Register the shared poll intance.
         shared_internals.register_worker_poll(poll.registry(), SHARED_POLL)?;
@@ -102,10 +127,22 @@ impl Runtime {
         channel.register(poll.registry(), COMMS)?;
 
         // Finally create all the runtime internals.
-        let internals = RuntimeInternals::new(id, shared_internals, waker_id, poll, cpu, trace_log);
+        let internals = RuntimeInternals::new(
+            id,
+            shared_internals,
+            running,
+            waker_id,
+            poll,
+            cpu,
+            deadline_scheduling,
+            time_slice,
+            trace_log,
+        );
         Ok(Runtime {
             internals: Rc::new(internals),
-            events: Events::with_capacity(128),
+            events: Events::with_capacity(config.event_capacity),
+            busy_poll: config.busy_poll,
+            max_processes_per_poll: config.max_processes_per_poll,
             waker_events,
             channel,
             started: false,
@@ -131,10 +168,23 @@ impl Runtime {
         channel.register(poll.registry(), COMMS)?;
 
         let id = NonZeroUsize::new(usize::MAX).unwrap();
-        let internals = RuntimeInternals::new(id, shared_internals, waker_id, poll, None, None);
+        let running = Arc::new(AtomicUsize::new(0));
+        let internals = RuntimeInternals::new(
+            id,
+            shared_internals,
+            running,
+            waker_id,
+            poll,
+            None,
+            false,
+            None,
+            None,
+        );
         Ok(Runtime {
             internals: Rc::new(internals),
             events: Events::with_capacity(1),
+            busy_poll: Duration::ZERO,
+            max_processes_per_poll: EventLoopConfig::DEFAULT.max_processes_per_poll,
             waker_events,
             channel,
             started: false,
@@ -162,30 +212,73 @@ impl Runtime {
         loop {
             // We first run the processes and only poll after to ensure that we
             // return if there are no processes to run.
-            trace!("running processes");
-            let mut n = 0;
-            while n < RUN_POLL_RATIO {
-                if !self.run_local_process(&mut runtime_ref) {
-                    break;
-                }
-                n += 1;
-            }
-            while n < RUN_POLL_RATIO {
-                if !self.run_shared_process(&mut runtime_ref) {
-                    break;
-                }
-                n += 1;
-            }
+            self.run_processes(&mut runtime_ref);
 
             if self.started && !self.has_process() {
                 debug!("no processes to run, stopping runtime");
                 return Ok(());
             }
 
-            self.schedule_processes()?;
+            self.schedule_processes(true)?;
         }
     }
 
+    /// Run the event loop until `done` returns `true`, which is checked after
+    /// each batch of processes is run.
+    ///
+    /// Used by [`rt::EmbeddedRuntime::run_until`].
+    pub(super) fn run_event_loop_until<F>(
+        &mut self,
+        runtime_ref: &mut RuntimeRef,
+        mut done: F,
+    ) -> Result<(), Error>
+    where
+        F: FnMut() -> bool,
+    {
+        loop {
+            self.run_processes(runtime_ref);
+            if done() {
+                return Ok(());
+            }
+            self.schedule_processes(true)?;
+        }
+    }
+
+    /// Run a single iteration of the event loop without blocking. Returns
+    /// `true` if there are processes left, `false` otherwise.
+    ///
+    /// Used by [`rt::EmbeddedRuntime::poll_once`].
+    pub(super) fn poll_once(&mut self, runtime_ref: &mut RuntimeRef) -> Result<bool, Error> {
+        self.run_processes(runtime_ref);
+        self.schedule_processes(false)?;
+        Ok(self.has_process())
+    }
+
+    /// Run up to [`rt::Setup::max_processes_per_poll`] processes, first local
+    /// then shared processes. Returns the number of processes run.
+    fn run_processes(&mut self, runtime_ref: &mut RuntimeRef) -> usize {
+        trace!("running processes");
+        let mut n = 0;
+        while n < self.max_processes_per_poll {
+            if !self.run_local_process(runtime_ref) {
+                break;
+            }
+            n += 1;
+        }
+        while n < self.max_processes_per_poll {
+            if !self.run_shared_process(runtime_ref) {
+                break;
+            }
+            n += 1;
+        }
+
+        let ready_processes = self.internals.scheduler.borrow().ready_processes();
+        self.internals
+            .shared
+            .publish_worker_metrics(self.internals.id, ready_processes, n);
+        n
+    }
+
     /// Attempts to run a single local process. Returns `true` if it ran a
     /// process, `false` otherwise.
     fn run_local_process(&mut self, runtime_ref: &mut RuntimeRef) -> bool {
@@ -196,12 +289,33 @@ impl Runtime {
                     let timing = trace::start(&*self.internals.trace_log.borrow());
                     let pid = process.as_ref().id();
                     let name = process.as_ref().name();
-                    match process.as_mut().run(runtime_ref) {
-                        ProcessResult::Complete => {}
+                    let message_type = process.as_ref().message_type();
+                    if timing.is_some() {
+                        trace::reset_allocations();
+                    }
+                    self.internals.running.store(pid.0, Ordering::Relaxed);
+                    let start = Instant::now();
+                    let result = process.as_mut().run(runtime_ref);
+                    let elapsed = start.elapsed();
+                    self.internals.running.store(0, Ordering::Relaxed);
+                    let messages = process::take_messages_received();
+                    self.internals
+                        .scheduler
+                        .borrow_mut()
+                        .record_run(pid, start, elapsed, messages);
+                    match result {
+                        ProcessResult::Complete => {
+                            self.internals.scheduler.borrow_mut().complete(process);
+                        }
                         ProcessResult::Pending => {
+                            // Run the process again once its CPU quota allows.
+                            if let Some(until) = process.as_mut().throttle_timer() {
+                                self.internals.timers.borrow_mut().add(pid, until);
+                            }
                             self.internals.scheduler.borrow_mut().add_process(process);
                         }
                     }
+                    self.trace_allocations(timing.clone(), pid, name, message_type);
                     trace::finish_rt(
                         self.internals.trace_log.borrow_mut().as_mut(),
                         timing,
@@ -225,14 +339,32 @@ impl Runtime {
                     let timing = trace::start(&*self.internals.trace_log.borrow());
                     let pid = process.as_ref().id();
                     let name = process.as_ref().name();
-                    match process.as_mut().run(runtime_ref) {
+                    let message_type = process.as_ref().message_type();
+                    if timing.is_some() {
+                        trace::reset_allocations();
+                    }
+                    self.internals.running.store(pid.0, Ordering::Relaxed);
+                    let start = Instant::now();
+                    let result = process.as_mut().run(runtime_ref);
+                    let elapsed = start.elapsed();
+                    self.internals.running.store(0, Ordering::Relaxed);
+                    let messages = process::take_messages_received();
+                    self.internals
+                        .shared
+                        .record_run(pid, start, elapsed, messages);
+                    match result {
                         ProcessResult::Complete => {
                             self.internals.shared.complete(process);
                         }
                         ProcessResult::Pending => {
+                            // Run the process again once its CPU quota allows.
+                            if let Some(until) = process.as_mut().throttle_timer() {
+                                self.internals.shared.add_deadline(pid, until);
+                            }
                             self.internals.shared.add_process(process);
                         }
                     }
+                    self.trace_allocations(timing.clone(), pid, name, message_type);
                     trace::finish_rt(
                         self.internals.trace_log.borrow_mut().as_mut(),
                         timing,
@@ -246,6 +378,35 @@ impl Runtime {
         }
     }
 
+    /// Add an "Allocation spike" trace event if the process that just ran
+    /// allocated more than the thresholds set in [`trace::TraceAllocator`].
+    fn trace_allocations(
+        &self,
+        timing: Option<trace::EventTiming>,
+        pid: ProcessId,
+        name: &'static str,
+        message_type: Option<&'static str>,
+    ) {
+        if timing.is_none() {
+            return;
+        }
+        if let Some(allocations) = trace::allocation_spike() {
+            let message_type = message_type.unwrap_or("");
+            trace::finish_rt(
+                self.internals.trace_log.borrow_mut().as_mut(),
+                timing,
+                "Allocation spike",
+                &[
+                    ("id", &pid.0),
+                    ("name", &name),
+                    ("message_type", &message_type),
+                    ("bytes", &allocations.bytes),
+                    ("allocations", &allocations.count),
+                ],
+            );
+        }
+    }
+
     /// Returns `true` if there are processes in either the local or shared
     /// schedulers.
     fn has_process(&self) -> bool {
@@ -255,12 +416,16 @@ impl Runtime {
     /// Schedule processes.
     ///
     /// This polls all event subsystems and schedules processes based on them.
-    fn schedule_processes(&mut self) -> Result<(), Error> {
+    /// If `block` is `false` polling for OS events never blocks. Otherwise it
+    /// blocks until the next (local or shared) timer expires, or indefinitely
+    /// if there are no timers, but never if any process is ready to run. See
+    /// [`Runtime::determine_timeout`].
+    fn schedule_processes(&mut self, block: bool) -> Result<(), Error> {
         trace!("polling event sources to schedule processes");
         let timing = trace::start(&*self.internals.trace_log.borrow());
 
         // Schedule local and shared processes based on various event sources.
-        let (mut local_amount, check_shared_poll) = self.schedule_from_os_events()?;
+        let (mut local_amount, check_shared_poll) = self.schedule_from_os_events(block)?;
         let mut shared_amount = if check_shared_poll {
             self.schedule_from_shared_os_events()
                 .map_err(Error::Polling)?
@@ -295,9 +460,9 @@ impl Runtime {
     ///
     /// Returns the amount of processes marked as active and a boolean
     /// indicating whether or not the shared timers should be checked.
-    fn schedule_from_os_events(&mut self) -> Result<(usize, bool), Error> {
+    fn schedule_from_os_events(&mut self, block: bool) -> Result<(usize, bool), Error> {
         // Start with polling for OS events.
-        self.poll_os().map_err(Error::Polling)?;
+        self.poll_os(block).map_err(Error::Polling)?;
 
         // Based on the OS event scheduler thread-local processes.
         let timing = trace::start(&*self.internals.trace_log.borrow());
@@ -461,9 +626,28 @@ impl Runtime {
     /// Poll for OS events, filling `self.events`.
     ///
     /// Returns a boolean indicating if the shared timers should be checked.
-    fn poll_os(&mut self) -> io::Result<()> {
+    fn poll_os(&mut self, block: bool) -> io::Result<()> {
         let timing = trace::start(&*self.internals.trace_log.borrow());
-        let mut timeout = self.determine_timeout();
+        let mut timeout = if block {
+            self.determine_timeout()
+        } else {
+            Some(Duration::ZERO)
+        };
+
+        // Before blocking, busy poll if configured to do so.
+        if !self.busy_poll.is_zero() && timeout.map_or(true, |t| !t.is_zero()) {
+            if self.busy_poll(timeout)? {
+                trace::finish_rt(
+                    self.internals.trace_log.borrow_mut().as_mut(),
+                    timing,
+                    "Busy polling for OS events",
+                    &[],
+                );
+                return Ok(());
+            }
+            // Timers might have expired while we were busy polling.
+            timeout = self.determine_timeout();
+        }
 
         // Only mark ourselves as polling if the timeout is non zero.
         let marked_polling = if timeout.map_or(true, |t| !t.is_zero()) {
@@ -512,6 +696,34 @@ impl Runtime {
         res
     }
 
+    /// Poll for OS events without blocking, for at most the busy poll duration
+    /// or `timeout`, whichever is shorter.
+    ///
+    /// Returns `true` if we got any OS events or processes are ready to run,
+    /// `false` if we should block.
+    fn busy_poll(&mut self, timeout: Option<Duration>) -> io::Result<bool> {
+        let duration = timeout.map_or(self.busy_poll, |t| t.min(self.busy_poll));
+        trace!("busy polling OS events: duration={:?}", duration);
+        let end = Instant::now() + duration;
+        loop {
+            self.internals
+                .poll
+                .borrow_mut()
+                .poll(&mut self.events, Some(Duration::ZERO))?;
+            // NOTE: we're not marked as polling, so user space events are not
+            // send to `poll`, we have to check for them ourselves.
+            if !self.events.is_empty()
+                || !self.waker_events.is_empty()
+                || self.internals.shared.has_ready_process()
+            {
+                return Ok(true);
+            } else if Instant::now() >= end {
+                return Ok(false);
+            }
+            spin_loop();
+        }
+    }
+
     /// Determine the timeout to be used in polling.
     fn determine_timeout(&self) -> Option<Duration> {
         if self.internals.scheduler.borrow().has_ready_process()
@@ -556,6 +768,18 @@ impl Runtime {
             match msg {
                 Control::Started => self.started = true,
                 Control::Signal(signal) => {
+                    if let Signal::Quit = signal {
+                        let timing = trace::start(&*self.internals.trace_log.borrow());
+                        let dump = self.internals.dump();
+                        info!(target: "dump", "scheduler dump: {:?}", dump);
+                        trace::finish_rt(
+                            self.internals.trace_log.borrow_mut().as_mut(),
+                            timing,
+                            "Dumping scheduler state",
+                            &[],
+                        );
+                    }
+
                     if let Signal::User2 = signal {
                         let timing = trace::start(&*self.internals.trace_log.borrow());
                         let metrics = self.internals.metrics();
@@ -590,7 +814,7 @@ impl Runtime {
 
         let mut receivers = self.internals.signal_receivers.borrow_mut();
         receivers.remove_disconnected();
-        let res = match receivers.try_send(signal, Delivery::ToAll) {
+        let res = match receivers.try_send_priority(signal, Delivery::ToAll) {
             Ok(()) => Ok(()),
             Err(SendError) if signal.should_stop() => Err(Error::ProcessInterrupted),
             Err(SendError) => Ok(()),
@@ -697,6 +921,10 @@ pub(super) struct RuntimeInternals {
     id: NonZeroUsize,
     /// Runtime internals shared between coordinator and worker threads.
     pub(super) shared: Arc<shared::RuntimeInternals>,
+    /// Pid of the process that is currently running, or 0 if no process is
+    /// running. Shared with the coordinator so it can determine what process
+    /// is blocking the worker thread.
+    running: Arc<AtomicUsize>,
     /// Waker id used to create a `Waker` for thread-local actors.
     pub(super) waker_id: WakerId,
     /// Scheduler for thread-local actors.
@@ -709,6 +937,12 @@ pub(super) struct RuntimeInternals {
     pub(super) signal_receivers: RefCell<ActorGroup<Signal>>,
     /// CPU affinity of the worker thread, or `None` if not set.
     pub(super) cpu: Option<usize>,
+    /// Whether or not earliest-deadline-first scheduling is enabled, see
+    /// [`rt::Setup::enable_deadline_scheduling`].
+    pub(super) deadline_scheduling: bool,
+    /// Maximum time a process should run before returning, see
+    /// [`rt::Setup::time_slice`].
+    pub(super) time_slice: Option<Duration>,
     /// Log used for tracing, `None` is tracing is disabled.
     pub(super) trace_log: RefCell<Option<trace::Log>>,
 }
@@ -726,29 +960,55 @@ pub(crate) struct Metrics {
     trace_log: Option<trace::Metrics>,
 }
 
+/// Dump of the state of [`RuntimeInternals`].
+#[derive(Debug)]
+#[allow(dead_code)] // https://github.com/rust-lang/rust/issues/88900.
+pub(crate) struct Dump {
+    id: NonZeroUsize,
+    scheduler: scheduler::Dump,
+    timers: timers::Metrics,
+}
+
 impl RuntimeInternals {
     /// Create a local runtime internals.
+    #[allow(clippy::too_many_arguments)]
     pub(super) fn new(
         id: NonZeroUsize,
         shared_internals: Arc<shared::RuntimeInternals>,
+        running: Arc<AtomicUsize>,
         waker_id: WakerId,
         poll: Poll,
         cpu: Option<usize>,
+        deadline_scheduling: bool,
+        time_slice: Option<Duration>,
         trace_log: Option<trace::Log>,
     ) -> RuntimeInternals {
+        let process_stats = shared_internals.process_stats_enabled();
         RuntimeInternals {
             id,
             shared: shared_internals,
+            running,
             waker_id,
-            scheduler: RefCell::new(Scheduler::new()),
+            scheduler: RefCell::new(Scheduler::new(process_stats)),
             poll: RefCell::new(poll),
             timers: RefCell::new(Timers::new()),
             signal_receivers: RefCell::new(ActorGroup::empty()),
             cpu,
+            deadline_scheduling,
+            time_slice,
             trace_log: RefCell::new(trace_log),
         }
     }
 
+    /// Dump the state of the schedulers of the runtime internals.
+    fn dump(&self) -> Dump {
+        Dump {
+            id: self.id,
+            scheduler: self.scheduler.borrow().dump(),
+            timers: self.timers.borrow_mut().metrics(),
+        }
+    }
+
     /// Gather metrics about the runtime internals.
     fn metrics(&self) -> Metrics {
         let cpu_time = cpu_usage(libc::CLOCK_THREAD_CPUTIME_ID);
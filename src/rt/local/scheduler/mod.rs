@@ -4,17 +4,19 @@
 //!
 //! [`RuntimeRef::try_spawn_local`]: crate::rt::RuntimeRef::try_spawn_local
 
-use std::collections::BinaryHeap;
+use std::collections::HashMap;
 use std::future::Future;
 use std::mem::MaybeUninit;
 use std::pin::Pin;
+use std::time::{Duration, Instant};
 
-use heph_inbox::Manager;
 use log::{debug, trace};
 
+use crate::actor::inbox::Manager;
 use crate::actor::NewActor;
-use crate::rt::process::{self, ActorProcess, FutureProcess, ProcessId};
-use crate::rt::{ptr_as_usize, ThreadLocal};
+use crate::rt::process::{self, ActorProcess, FutureProcess, ProcessId, ProcessStats};
+use crate::rt::run_queue::RunQueue;
+use crate::rt::{ptr_as_usize, JoinHandle, ThreadLocal};
 use crate::spawn::options::Priority;
 use crate::supervisor::Supervisor;
 
@@ -29,9 +31,14 @@ type ProcessData = process::ProcessData<dyn process::Process>;
 #[derive(Debug)]
 pub(crate) struct Scheduler {
     /// Processes that are ready to run.
-    ready: BinaryHeap<Pin<Box<ProcessData>>>,
+    ready: RunQueue<dyn process::Process>,
     /// Processes that are not ready to run.
     inactive: Inactive,
+    /// Run statistics of all processes, `None` if process statistics are
+    /// disabled, see [`rt::Setup::enable_process_stats`].
+    ///
+    /// [`rt::Setup::enable_process_stats`]: crate::rt::Setup::enable_process_stats
+    stats: Option<HashMap<ProcessId, ProcessStats>>,
 }
 
 /// Metrics for [`Scheduler`].
@@ -42,12 +49,24 @@ pub(crate) struct Metrics {
     inactive: usize,
 }
 
+/// State of the [`Scheduler`], see [`Scheduler::dump`].
+#[derive(Debug)]
+#[allow(dead_code)] // https://github.com/rust-lang/rust/issues/88900.
+pub(crate) struct Dump {
+    /// Pid and name of the processes that are ready to run, in no particular
+    /// order.
+    ready: Vec<(ProcessId, &'static str)>,
+    inactive: usize,
+}
+
 impl Scheduler {
-    /// Create a new `Scheduler`.
-    pub(crate) fn new() -> Scheduler {
+    /// Create a new `Scheduler`, collecting run statistics of all processes if
+    /// `process_stats` is `true`.
+    pub(crate) fn new(process_stats: bool) -> Scheduler {
         Scheduler {
-            ready: BinaryHeap::new(),
+            ready: RunQueue::empty(),
             inactive: Inactive::empty(),
+            stats: process_stats.then(HashMap::new),
         }
     }
 
@@ -59,6 +78,18 @@ impl Scheduler {
         }
     }
 
+    /// Dump the state of the scheduler, used to debug stuck processes.
+    pub(crate) fn dump(&self) -> Dump {
+        Dump {
+            ready: self
+                .ready
+                .iter()
+                .map(|process| (process.id(), process.name()))
+                .collect(),
+            inactive: self.inactive.len(),
+        }
+    }
+
     /// Returns `true` if the scheduler has any processes (in any state),
     /// `false` otherwise.
     pub(crate) fn has_process(&self) -> bool {
@@ -68,7 +99,12 @@ impl Scheduler {
     /// Returns `true` if the scheduler has any processes that are ready to run,
     /// `false` otherwise.
     pub(crate) fn has_ready_process(&self) -> bool {
-        !self.ready.is_empty()
+        self.ready.has_process()
+    }
+
+    /// Returns the number of processes that are ready to run.
+    pub(crate) fn ready_processes(&self) -> usize {
+        self.ready.len()
     }
 
     /// Add an actor to the scheduler.
@@ -76,22 +112,43 @@ impl Scheduler {
         AddActor {
             scheduler: self,
             alloc: Box::new_uninit(),
+            cpu_quota: None,
+            name: None,
+            catch_panics: false,
         }
     }
 
-    pub(crate) fn add_future<Fut>(&mut self, future: Fut, priority: Priority)
+    pub(crate) fn add_future<Fut>(
+        &mut self,
+        future: Fut,
+        priority: Priority,
+    ) -> JoinHandle<Fut::Output>
     where
-        Fut: Future<Output = ()> + 'static,
+        Fut: Future + 'static,
+        Fut::Output: 'static,
     {
-        let process = Box::pin(ProcessData::new(
-            priority,
-            Box::pin(FutureProcess::<Fut, ThreadLocal>::new(future)),
-        ));
-        debug!(
-            "spawning thread-local future: pid={}",
-            process.as_ref().id()
-        );
-        self.ready.push(process)
+        let (process, handle) = FutureProcess::<Fut, ThreadLocal>::new(future);
+        let process = Box::pin(ProcessData::new(priority, Box::pin(process)));
+        let pid = process.as_ref().id();
+        debug!("spawning thread-local future: pid={}", pid);
+        self.insert_stats(pid, process.as_ref().name());
+        self.ready.add(process);
+        handle
+    }
+
+    /// Add a new custom `process`, see [`RuntimeRef::add_process`].
+    ///
+    /// [`RuntimeRef::add_process`]: crate::rt::RuntimeRef::add_process
+    pub(crate) fn add_new_process<P>(&mut self, process: P, priority: Priority) -> ProcessId
+    where
+        P: process::Process + 'static,
+    {
+        let process = Box::pin(ProcessData::new(priority, Box::pin(process)));
+        let pid = process.as_ref().id();
+        debug!("adding thread-local process: pid={}", pid);
+        self.insert_stats(pid, process.as_ref().name());
+        self.ready.add(process);
+        pid
     }
 
     /// Mark the process, with `pid`, as ready to run.
@@ -102,13 +159,13 @@ impl Scheduler {
     pub(crate) fn mark_ready(&mut self, pid: ProcessId) {
         trace!("marking process as ready: pid={}", pid);
         if let Some(process) = self.inactive.remove(pid) {
-            self.ready.push(process)
+            self.ready.add(process)
         }
     }
 
     /// Returns the next ready process.
     pub(crate) fn next_process(&mut self) -> Option<Pin<Box<ProcessData>>> {
-        self.ready.pop()
+        self.ready.remove()
     }
 
     /// Add back a process that was previously removed via
@@ -116,6 +173,50 @@ impl Scheduler {
     pub(crate) fn add_process(&mut self, process: Pin<Box<ProcessData>>) {
         self.inactive.add(process);
     }
+
+    /// Mark `process` as complete, removing it from the scheduler.
+    pub(crate) fn complete(&mut self, process: Pin<Box<ProcessData>>) {
+        let pid = process.as_ref().id();
+        trace!("removing process: pid={}", pid);
+        if let Some(stats) = self.stats.as_mut() {
+            let _ = stats.remove(&pid);
+        }
+    }
+
+    /// Record a single run of the process with `pid`, see
+    /// [`ProcessStats::record_run`].
+    pub(crate) fn record_run(
+        &mut self,
+        pid: ProcessId,
+        start: Instant,
+        elapsed: Duration,
+        messages: u64,
+    ) {
+        if let Some(stats) = self.stats.as_mut().and_then(|stats| stats.get_mut(&pid)) {
+            stats.record_run(start, elapsed, messages);
+        }
+    }
+
+    /// Returns the run statistics of the process with `pid`, if any.
+    pub(crate) fn process_stats(&self, pid: ProcessId) -> Option<ProcessStats> {
+        self.stats
+            .as_ref()
+            .and_then(|stats| stats.get(&pid).copied())
+    }
+
+    /// Returns the run statistics of all processes.
+    pub(crate) fn all_process_stats(&self) -> impl Iterator<Item = (ProcessId, ProcessStats)> + '_ {
+        self.stats
+            .iter()
+            .flatten()
+            .map(|(pid, stats)| (*pid, *stats))
+    }
+
+    fn insert_stats(&mut self, pid: ProcessId, name: &'static str) {
+        if let Some(stats) = self.stats.as_mut() {
+            let _ = stats.insert(pid, ProcessStats::new(name));
+        }
+    }
 }
 
 /// A handle to add a process to the scheduler.
@@ -126,6 +227,12 @@ pub(crate) struct AddActor<'s> {
     scheduler: &'s mut Scheduler,
     /// Already allocated `ProcessData`, used to determine the `ProcessId`.
     alloc: Box<MaybeUninit<ProcessData>>,
+    /// CPU quota of the actor, see [`AddActor::with_cpu_quota`].
+    cpu_quota: Option<Duration>,
+    /// Name of the actor, see [`AddActor::with_name`].
+    name: Option<&'static str>,
+    /// Whether to catch panics, see [`AddActor::with_catch_panics`].
+    catch_panics: bool,
 }
 
 impl<'s> AddActor<'s> {
@@ -134,6 +241,31 @@ impl<'s> AddActor<'s> {
         ProcessId(ptr_as_usize(&*self.alloc as *const _))
     }
 
+    /// Set the CPU quota of the actor, see [`ActorOptions::with_cpu_quota`].
+    ///
+    /// [`ActorOptions::with_cpu_quota`]: crate::spawn::ActorOptions::with_cpu_quota
+    pub(crate) fn with_cpu_quota(mut self, cpu_quota: Option<Duration>) -> Self {
+        self.cpu_quota = cpu_quota;
+        self
+    }
+
+    /// Set the name of the actor, see [`ActorOptions::with_name`].
+    ///
+    /// [`ActorOptions::with_name`]: crate::spawn::ActorOptions::with_name
+    pub(crate) fn with_name(mut self, name: Option<&'static str>) -> Self {
+        self.name = name;
+        self
+    }
+
+    /// Set whether to catch panics of the actor, see
+    /// [`ActorOptions::catch_panics`].
+    ///
+    /// [`ActorOptions::catch_panics`]: crate::spawn::ActorOptions::catch_panics
+    pub(crate) fn with_catch_panics(mut self, catch_panics: bool) -> Self {
+        self.catch_panics = catch_panics;
+        self
+    }
+
     /// Add a new inactive actor to the scheduler.
     pub(crate) fn add<S, NA>(
         self,
@@ -153,19 +285,26 @@ impl<'s> AddActor<'s> {
         );
         let process = ProcessData::new(
             priority,
-            Box::pin(ActorProcess::new(supervisor, new_actor, actor, inbox)),
-        );
+            Box::pin(
+                ActorProcess::new(supervisor, new_actor, actor, inbox)
+                    .with_name(self.name)
+                    .with_catch_panics(self.catch_panics),
+            ),
+        )
+        .with_cpu_quota(self.cpu_quota);
         let AddActor {
             scheduler,
             mut alloc,
+            ..
         } = self;
         let process: Pin<_> = unsafe {
             let _ = alloc.write(process);
             // Safe because we write into the allocation above.
             alloc.assume_init().into()
         };
+        scheduler.insert_stats(process.as_ref().id(), process.as_ref().name());
         if is_ready {
-            scheduler.ready.push(process)
+            scheduler.ready.add(process)
         } else {
             scheduler.inactive.add(process);
         }
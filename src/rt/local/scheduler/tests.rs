@@ -5,6 +5,7 @@ use std::future::{pending, Pending};
 use std::mem;
 use std::pin::Pin;
 use std::rc::Rc;
+use std::time::{Duration, Instant};
 
 use crate::actor::{self, NewActor};
 use crate::rt::local::scheduler::{ProcessData, Scheduler};
@@ -20,7 +21,7 @@ fn assert_size<T>(expected: usize) {
 
 #[test]
 fn size_assertions() {
-    assert_size::<ProcessData>(40);
+    assert_size::<ProcessData>(64);
 }
 
 #[derive(Debug)]
@@ -38,7 +39,7 @@ impl Process for NopTestProcess {
 
 #[test]
 fn has_process() {
-    let mut scheduler = Scheduler::new();
+    let mut scheduler = Scheduler::new(false);
     assert!(!scheduler.has_process());
     assert!(!scheduler.has_ready_process());
 
@@ -55,7 +56,7 @@ async fn simple_actor(_: actor::Context<!, ThreadLocal>) {}
 
 #[test]
 fn add_actor() {
-    let mut scheduler = Scheduler::new();
+    let mut scheduler = Scheduler::new(false);
 
     let actor_entry = scheduler.add_actor();
     let new_actor = simple_actor as fn(_) -> _;
@@ -74,7 +75,7 @@ fn add_actor() {
 
 #[test]
 fn mark_ready() {
-    let mut scheduler = Scheduler::new();
+    let mut scheduler = Scheduler::new(false);
 
     // Incorrect (outdated) pid should be ok.
     scheduler.mark_ready(ProcessId(1));
@@ -97,9 +98,42 @@ fn mark_ready() {
     assert!(scheduler.has_ready_process());
 }
 
+#[test]
+fn dump() {
+    let mut scheduler = Scheduler::new(false);
+    let dump = scheduler.dump();
+    assert!(dump.ready.is_empty());
+    assert_eq!(dump.inactive, 0);
+
+    let actor_entry = scheduler.add_actor();
+    let pid = actor_entry.pid();
+    let new_actor = simple_actor as fn(_) -> _;
+    let (actor, inbox, _) = init_local_actor_with_inbox(new_actor, ()).unwrap();
+    actor_entry.add(
+        Priority::NORMAL,
+        NoSupervisor,
+        new_actor,
+        actor,
+        inbox,
+        false,
+    );
+    let process: Pin<Box<ProcessData>> = Box::pin(ProcessData::new(
+        Priority::default(),
+        Box::pin(NopTestProcess),
+    ));
+    scheduler.add_process(process);
+    scheduler.mark_ready(pid);
+
+    let dump = scheduler.dump();
+    assert_eq!(dump.ready.len(), 1);
+    assert_eq!(dump.ready[0].0, pid);
+    assert!(dump.ready[0].1.ends_with("simple_actor"));
+    assert_eq!(dump.inactive, 1);
+}
+
 #[test]
 fn next_process() {
-    let mut scheduler = Scheduler::new();
+    let mut scheduler = Scheduler::new(false);
 
     let actor_entry = scheduler.add_actor();
     let pid = actor_entry.pid();
@@ -126,7 +160,7 @@ fn next_process() {
 
 #[test]
 fn next_process_order() {
-    let mut scheduler = Scheduler::new();
+    let mut scheduler = Scheduler::new(false);
 
     let new_actor = simple_actor as fn(_) -> _;
     // Actor 1.
@@ -175,7 +209,7 @@ fn next_process_order() {
 
 #[test]
 fn add_process() {
-    let mut scheduler = Scheduler::new();
+    let mut scheduler = Scheduler::new(false);
 
     let actor_entry = scheduler.add_actor();
     let pid = actor_entry.pid();
@@ -203,7 +237,7 @@ fn add_process() {
 
 #[test]
 fn add_process_marked_ready() {
-    let mut scheduler = Scheduler::new();
+    let mut scheduler = Scheduler::new(false);
 
     let actor_entry = scheduler.add_actor();
     let pid = actor_entry.pid();
@@ -230,6 +264,66 @@ fn add_process_marked_ready() {
     assert_eq!(process.as_ref().id(), pid);
 }
 
+#[test]
+fn process_stats() {
+    let mut scheduler = Scheduler::new(true);
+
+    let actor_entry = scheduler.add_actor();
+    let pid = actor_entry.pid();
+    let new_actor = simple_actor as fn(_) -> _;
+    let (actor, inbox, _) = init_local_actor_with_inbox(new_actor, ()).unwrap();
+    actor_entry.add(
+        Priority::NORMAL,
+        NoSupervisor,
+        new_actor,
+        actor,
+        inbox,
+        true,
+    );
+
+    let stats = scheduler.process_stats(pid).unwrap();
+    assert_eq!(stats.name(), "simple_actor");
+    assert_eq!(stats.polls(), 0);
+    assert!(stats.last_run().is_none());
+
+    let start = Instant::now();
+    scheduler.record_run(pid, start, Duration::from_millis(1), 2);
+    scheduler.record_run(pid, start, Duration::from_millis(2), 0);
+    let stats = scheduler.process_stats(pid).unwrap();
+    assert_eq!(stats.runtime(), Duration::from_millis(3));
+    assert_eq!(stats.polls(), 2);
+    assert_eq!(stats.messages(), 2);
+    assert_eq!(stats.last_run(), Some(start));
+    assert_eq!(scheduler.all_process_stats().count(), 1);
+
+    let process = scheduler.next_process().unwrap();
+    scheduler.complete(process);
+    assert!(scheduler.process_stats(pid).is_none());
+    assert_eq!(scheduler.all_process_stats().count(), 0);
+}
+
+#[test]
+fn process_stats_disabled() {
+    let mut scheduler = Scheduler::new(false);
+
+    let actor_entry = scheduler.add_actor();
+    let pid = actor_entry.pid();
+    let new_actor = simple_actor as fn(_) -> _;
+    let (actor, inbox, _) = init_local_actor_with_inbox(new_actor, ()).unwrap();
+    actor_entry.add(
+        Priority::NORMAL,
+        NoSupervisor,
+        new_actor,
+        actor,
+        inbox,
+        true,
+    );
+
+    scheduler.record_run(pid, Instant::now(), Duration::from_millis(1), 1);
+    assert!(scheduler.process_stats(pid).is_none());
+    assert_eq!(scheduler.all_process_stats().count(), 0);
+}
+
 #[test]
 fn scheduler_run_order() {
     async fn order_actor(
@@ -240,7 +334,7 @@ fn scheduler_run_order() {
         order.borrow_mut().push(id);
     }
 
-    let mut scheduler = Scheduler::new();
+    let mut scheduler = Scheduler::new(false);
     let mut runtime_ref = test::runtime();
 
     // The order in which the processes have been run.
@@ -294,7 +388,7 @@ impl NewActor for TestAssertUnmovedNewActor {
 
 #[test]
 fn assert_actor_process_unmoved() {
-    let mut scheduler = Scheduler::new();
+    let mut scheduler = Scheduler::new(false);
     let mut runtime_ref = test::runtime();
 
     let (actor, inbox, _) = init_local_actor_with_inbox(TestAssertUnmovedNewActor, ()).unwrap();
@@ -336,7 +430,7 @@ fn assert_actor_process_unmoved() {
 
 #[test]
 fn assert_future_process_unmoved() {
-    let mut scheduler = Scheduler::new();
+    let mut scheduler = Scheduler::new(false);
     let mut runtime_ref = test::runtime();
 
     let future = AssertUnmoved::new(pending());
@@ -1,10 +1,12 @@
 //! Module with the local timers implementation.
 //!
-//! Also see the shared timers implementation.
+//! Also see the shared timers implementation and the types shared by both in
+//! `rt::timers`.
 
-use std::cmp::{min, Ordering};
+use std::cmp::min;
 use std::time::{Duration, Instant};
 
+use crate::rt::timers::{find_timer, Timer};
 use crate::rt::ProcessId;
 
 #[cfg(test)]
@@ -316,7 +318,7 @@ fn remove_timer<T>(timers: &mut Vec<Timer<T>>, timer: Timer<T>)
 where
     Timer<T>: Ord + Copy,
 {
-    if let Ok(idx) = timers.binary_search(&timer) {
+    if let Ok(idx) = find_timer(timers, &timer) {
         let _ = timers.remove(idx);
     }
 }
@@ -326,7 +328,7 @@ fn change_timer<T>(timers: &mut Vec<Timer<T>>, timer: Timer<T>, new_pid: Process
 where
     Timer<T>: Ord + Copy,
 {
-    match timers.binary_search(&timer) {
+    match find_timer(timers, &timer) {
         Ok(idx) => timers[idx].pid = new_pid,
         #[rustfmt::skip]
         Err(idx) => timers.insert(idx, Timer { pid: new_pid, deadline: timer.deadline }),
@@ -403,39 +405,6 @@ impl CachedInstant {
     }
 }
 
-/// A timer.
-///
-/// # Notes
-///
-/// The [`Ord`] implementation is in reverse order, i.e. the deadline to expire
-/// first will have the highest ordering value. Furthermore the ordering is only
-/// done base on the deadline, the process id is ignored in ordering. This
-/// allows `change_timer` to not worry about order when changing the process id
-/// of a timer.
-#[derive(Copy, Clone, Debug, Eq, PartialEq)]
-struct Timer<T> {
-    pid: ProcessId,
-    deadline: T,
-}
-
-impl<T> Ord for Timer<T>
-where
-    T: Ord,
-{
-    fn cmp(&self, other: &Self) -> Ordering {
-        other.deadline.cmp(&self.deadline)
-    }
-}
-
-impl<T> PartialOrd for Timer<T>
-where
-    T: Ord,
-{
-    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
-        Some(self.cmp(other))
-    }
-}
-
 /// Returns all timers that have passed (since the iterator was created).
 #[derive(Debug)]
 pub(crate) struct Deadlines<'t> {
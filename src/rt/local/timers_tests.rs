@@ -108,6 +108,24 @@ fn remove_deadline() {
     assert_eq!(timers.remove_next(deadline), None);
 }
 
+#[test]
+fn remove_deadline_same_deadline_other_pid() {
+    let mut timers = Timers::new();
+    let deadline = timers.epoch + Duration::from_millis(10);
+    timers.add(PID, deadline);
+    timers.add(PID2, deadline);
+    // Should only remove the timer for `PID2`.
+    timers.remove(PID2, deadline);
+    assert_eq!(timers.remove_next(deadline), Some(PID));
+    assert_eq!(timers.remove_next(deadline), None);
+
+    timers.add(PID, deadline);
+    timers.add(PID2, deadline);
+    timers.remove(PID, deadline);
+    assert_eq!(timers.remove_next(deadline), Some(PID2));
+    assert_eq!(timers.remove_next(deadline), None);
+}
+
 #[test]
 fn remove_never_added_deadline() {
     let mut timers = Timers::new();
@@ -229,6 +247,24 @@ fn change_deadline() {
     assert_eq!(timers.remove_next(deadline), None);
 }
 
+#[test]
+fn change_deadline_same_deadline_other_pid() {
+    const PID3: ProcessId = ProcessId(300);
+    let mut timers = Timers::new();
+    let deadline = timers.epoch + Duration::from_millis(10);
+    timers.add(PID, deadline);
+    timers.add(PID2, deadline);
+    // Should only change the timer for `PID2`.
+    timers.change(PID2, deadline, PID3);
+    let mut got = vec![
+        timers.remove_next(deadline).unwrap(),
+        timers.remove_next(deadline).unwrap(),
+    ];
+    got.sort_unstable();
+    assert_eq!(got, vec![PID, PID3]);
+    assert_eq!(timers.remove_next(deadline), None);
+}
+
 #[test]
 fn changing_never_added_deadline_adds_it() {
     let mut timers = Timers::new();
@@ -39,6 +39,13 @@
 //! Finally after configurating the runtime and spawning actors the runtime can
 //! be [`start`]ed, which runs all actors and waits for them to complete.
 //!
+//! ## Embedding the runtime
+//!
+//! If the main thread is owned by something else, for example a GUI event loop
+//! or a game loop, [`EmbeddedRuntime`] can be used instead of [`Runtime`]. It
+//! runs all actors on the current thread and is driven manually using
+//! [`EmbeddedRuntime::poll_once`] or [`EmbeddedRuntime::run_until`].
+//!
 //! [`setup`]: Runtime::setup
 //! [`new`]: Runtime::new
 //! [`num_threads`]: Setup::num_threads
@@ -123,12 +130,14 @@ use std::time::Duration;
 use std::time::Instant;
 use std::{io, task};
 
-use heph_inbox as inbox;
 use log::{debug, trace, warn};
 use mio::{event, Interest, Token};
 
+use crate::actor::inbox::Manager;
 use crate::actor::{self, NewActor, SyncActor};
 use crate::actor_ref::{ActorGroup, ActorRef};
+use crate::bus::Bus;
+use crate::config::ReloadError;
 use crate::spawn::{
     ActorOptions, AddActorError, FutureOptions, PrivateSpawn, Spawn, SyncActorOptions,
 };
@@ -138,22 +147,31 @@ use crate::trace;
 pub(crate) mod access;
 pub(crate) mod channel;
 mod coordinator;
+mod embedded;
 mod error;
+mod join_handle;
 pub(crate) mod local;
 mod process;
+mod readiness;
+mod run_queue;
 mod setup;
 pub(crate) mod shared;
 mod signal;
 pub(crate) mod sync_worker;
 pub(crate) mod thread_waker;
+mod timers;
 pub(crate) mod waker;
 pub(crate) mod worker;
 
 pub(crate) use access::PrivateAccess;
-pub(crate) use process::ProcessId;
+pub(crate) use process::{current_caller, message_received, set_deadline};
 
 pub use access::{Access, ThreadLocal, ThreadSafe};
-pub use error::Error;
+pub use embedded::EmbeddedRuntime;
+pub use error::{Error, ErrorKind};
+pub use join_handle::{JoinError, JoinHandle};
+pub use process::{Process, ProcessId, ProcessResult, ProcessStats};
+pub use readiness::WaitReady;
 pub use setup::Setup;
 pub use signal::Signal;
 
@@ -313,9 +331,14 @@ impl Runtime {
     /// Spawn a thread-safe [`Future`].
     ///
     /// See [`RuntimeRef::spawn_future`] for more documentation.
-    pub fn spawn_future<Fut>(&mut self, future: Fut, options: FutureOptions)
+    pub fn spawn_future<Fut>(
+        &mut self,
+        future: Fut,
+        options: FutureOptions,
+    ) -> JoinHandle<Fut::Output>
     where
-        Fut: Future<Output = ()> + Send + Sync + 'static,
+        Fut: Future + Send + Sync + 'static,
+        Fut::Output: Send + 'static,
     {
         self.coordinator
             .shared_internals()
@@ -355,6 +378,129 @@ impl Runtime {
         self.signals.add(actor_ref);
     }
 
+    /// Load the configuration of type `T` using `loader`.
+    ///
+    /// The `loader` is called once now, if it fails its error is returned.
+    /// It's called again each time the configuration is reloaded, see
+    /// [`RuntimeRef::reload_config`]. The configuration can be retrieved using
+    /// [`RuntimeRef::config`], see the [`config`] module for more information.
+    ///
+    /// [`config`]: crate::config
+    pub fn load_config<T, F, E>(&mut self, loader: F) -> Result<(), Error>
+    where
+        T: Send + Sync + 'static,
+        F: Fn() -> Result<T, E> + Send + Sync + 'static,
+        E: ToString,
+    {
+        let shared = self.coordinator.shared_internals();
+        shared
+            .config()
+            .load(shared.bus(), loader)
+            .map_err(Error::setup)
+    }
+
+    /// Run the runtime until `future` completes, returning its output.
+    ///
+    /// `future` is spawned as thread-safe future (see
+    /// [`Runtime::spawn_future`]) after which the runtime is started (see
+    /// [`Runtime::start`]). This makes it easy to write request-style
+    /// applications, e.g. a client that connects to a server, does its work
+    /// and returns, using `future` as root of all the work.
+    ///
+    /// # Notes
+    ///
+    /// Just like [`Runtime::start`] this waits until all actors and futures
+    /// have finished, not just `future`. So any actors spawned by `future` (or
+    /// before calling this) should stop once `future` completes.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use heph::rt::{self, Runtime};
+    ///
+    /// fn main() -> Result<(), rt::Error> {
+    ///     let runtime = Runtime::new()?;
+    ///     let answer = runtime.block_on(async {
+    ///         // Connect to a server, send a request, etc.
+    ///         42
+    ///     })?;
+    ///     assert_eq!(answer, 42);
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn block_on<Fut>(mut self, future: Fut) -> Result<Fut::Output, Error>
+    where
+        Fut: Future + Send + Sync + 'static,
+        Fut::Output: Send + 'static,
+    {
+        let handle = self.spawn_future(future, FutureOptions::default());
+        self.start()?;
+        // The runtime only stops without an error once all processes are
+        // completed, including our future. If the future panicked the worker
+        // thread running it panicked as well, which is returned above.
+        match handle.into_output() {
+            Some(Ok(output)) => Ok(output),
+            Some(Err(_)) | None => unreachable!("future didn't complete in `Runtime::block_on`"),
+        }
+    }
+
+    /// Run the runtime until the "main" actor, created using `new_actor`,
+    /// stops.
+    ///
+    /// The main actor is spawned as a thread-safe actor (see
+    /// [`Runtime::try_spawn`]) after which the runtime is run using
+    /// [`Runtime::block_on`], waiting for the main actor to stop. This is
+    /// useful for CLI-style programs and tests in which a single actor drives
+    /// all the work.
+    ///
+    /// # Notes
+    ///
+    /// Actors don't return a value, only an error which is handled by the
+    /// `supervisor`. To get a value out of the runtime use
+    /// [`Runtime::block_on`] and e.g. an [RPC] to an actor.
+    ///
+    /// Just like [`Runtime::block_on`] this waits until all actors and futures
+    /// have finished, not just the main actor.
+    ///
+    /// [RPC]: crate::actor_ref::rpc
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// #![feature(never_type)]
+    ///
+    /// use heph::actor;
+    /// use heph::rt::{self, Runtime, ThreadSafe};
+    /// use heph::spawn::ActorOptions;
+    /// use heph::supervisor::NoSupervisor;
+    ///
+    /// fn main() -> Result<(), rt::Error> {
+    ///     let runtime = Runtime::new()?;
+    ///     let actor = main_actor as fn(_, _) -> _;
+    ///     runtime.block_on_actor(NoSupervisor, actor, "World", ActorOptions::default())
+    /// }
+    ///
+    /// async fn main_actor(_: actor::Context<!, ThreadSafe>, name: &'static str) {
+    ///     println!("Hello {}", name);
+    /// }
+    /// ```
+    pub fn block_on_actor<S, NA>(
+        mut self,
+        supervisor: S,
+        new_actor: NA,
+        arg: NA::Argument,
+        options: ActorOptions,
+    ) -> Result<(), Error>
+    where
+        S: Supervisor<NA> + Send + Sync + 'static,
+        NA: NewActor<Error = !, RuntimeAccess = ThreadSafe> + Sync + Send + 'static,
+        NA::Actor: Send + Sync + 'static,
+        NA::Message: Send,
+    {
+        let actor_ref = self.spawn(supervisor, new_actor, arg, options);
+        self.block_on(async move { actor_ref.join().await })
+    }
+
     /// Run the runtime.
     ///
     /// This will wait until all spawned workers have finished, which happens
@@ -490,15 +636,152 @@ impl RuntimeRef {
         Spawn::spawn(self, supervisor, new_actor, arg, options)
     }
 
+    /// Attempt to spawn a pool of `size` thread-safe actors.
+    ///
+    /// This spawns an actor for each argument in `args`, up to `size` actors.
+    /// All actors use a clone of `supervisor`, `new_actor` and `options`. Use
+    /// [`iter::repeat`] to use the same argument for all actors.
+    ///
+    /// The returned [`ActorGroup`] can be used to send messages to the actors
+    /// in the pool, using [`Delivery::ToOne`] the messages are load balanced
+    /// across the actors in a round-robin fashion.
+    ///
+    /// If spawning an actor fails the error is returned, actors already
+    /// spawned keep running.
+    ///
+    /// [`iter::repeat`]: std::iter::repeat
+    /// [`Delivery::ToOne`]: crate::actor_ref::Delivery::ToOne
+    pub fn try_spawn_pool<S, NA, I>(
+        &mut self,
+        supervisor: S,
+        new_actor: NA,
+        args: I,
+        size: usize,
+        options: ActorOptions,
+    ) -> Result<ActorGroup<NA::Message>, NA::Error>
+    where
+        S: Supervisor<NA> + Clone + Send + Sync + 'static,
+        NA: NewActor<RuntimeAccess = ThreadSafe> + Clone + Sync + Send + 'static,
+        NA::Actor: Send + Sync + 'static,
+        NA::Message: Send,
+        I: IntoIterator<Item = NA::Argument>,
+    {
+        let mut group = ActorGroup::empty();
+        for arg in args.into_iter().take(size) {
+            let actor_ref =
+                self.try_spawn(supervisor.clone(), new_actor.clone(), arg, options.clone())?;
+            group.add(actor_ref);
+        }
+        Ok(group)
+    }
+
+    /// Spawn a pool of `size` thread-safe actors.
+    ///
+    /// See [`RuntimeRef::try_spawn_pool`] for more information.
+    pub fn spawn_pool<S, NA, I>(
+        &mut self,
+        supervisor: S,
+        new_actor: NA,
+        args: I,
+        size: usize,
+        options: ActorOptions,
+    ) -> ActorGroup<NA::Message>
+    where
+        S: Supervisor<NA> + Clone + Send + Sync + 'static,
+        NA: NewActor<Error = !, RuntimeAccess = ThreadSafe> + Clone + Sync + Send + 'static,
+        NA::Actor: Send + Sync + 'static,
+        NA::Message: Send,
+        I: IntoIterator<Item = NA::Argument>,
+    {
+        self.try_spawn_pool(supervisor, new_actor, args, size, options)
+            .unwrap_or_else(|_: !| unreachable!())
+    }
+
+    /// Attempt to spawn a batch of thread-local actors.
+    ///
+    /// This spawns an actor for each argument in `args`, all using a clone of
+    /// `supervisor`, `new_actor` and `options`. Compared to calling
+    /// [`RuntimeRef::try_spawn_local`] in a loop this only sets up spawning
+    /// once, e.g. it borrows the scheduler once for the entire batch, making
+    /// it cheaper to spawn thousands of actors, e.g. when starting a server.
+    ///
+    /// # Notes
+    ///
+    /// Because the scheduler is borrowed for the entire batch, actors can't
+    /// spawn other thread-local actors while being created (i.e. in
+    /// [`NewActor::new`]).
+    ///
+    /// If spawning an actor fails the error is returned, actors already
+    /// spawned keep running.
+    pub fn try_spawn_local_batch<S, NA, I>(
+        &mut self,
+        supervisor: S,
+        new_actor: NA,
+        args: I,
+        options: ActorOptions,
+    ) -> Result<Vec<ActorRef<NA::Message>>, NA::Error>
+    where
+        S: Supervisor<NA> + Clone + 'static,
+        NA: NewActor<RuntimeAccess = ThreadLocal> + Clone + 'static,
+        NA::Actor: 'static,
+        I: IntoIterator<Item = NA::Argument>,
+    {
+        let options = options.inherit(self.internals.shared.default_actor_options());
+        let args = args.into_iter();
+        let mut actor_refs = Vec::with_capacity(args.size_hint().0);
+        let mut scheduler = self.internals.scheduler.borrow_mut();
+        for arg in args {
+            let supervisor = supervisor.clone();
+            let new_actor = new_actor.clone();
+            let actor_ref = self
+                .spawn_local_with(&mut scheduler, supervisor, new_actor, |_| Ok(arg), &options)
+                .map_err(|err| match err {
+                    AddActorError::NewActor(err) => err,
+                    AddActorError::<_, !>::ArgFn(_) => unreachable!(),
+                })?;
+            actor_refs.push(actor_ref);
+        }
+        Ok(actor_refs)
+    }
+
+    /// Spawn a batch of thread-local actors.
+    ///
+    /// See [`RuntimeRef::try_spawn_local_batch`] for more information.
+    pub fn spawn_local_batch<S, NA, I>(
+        &mut self,
+        supervisor: S,
+        new_actor: NA,
+        args: I,
+        options: ActorOptions,
+    ) -> Vec<ActorRef<NA::Message>>
+    where
+        S: Supervisor<NA> + Clone + 'static,
+        NA: NewActor<Error = !, RuntimeAccess = ThreadLocal> + Clone + 'static,
+        NA::Actor: 'static,
+        I: IntoIterator<Item = NA::Argument>,
+    {
+        self.try_spawn_local_batch(supervisor, new_actor, args, options)
+            .unwrap_or_else(|_: !| unreachable!())
+    }
+
     /// Spawn a thread-local [`Future`].
     ///
     /// Similar to thread-local actors this will only run on a single thread.
     /// See the discussion of thread-local vs. thread-safe actors in the
     /// [`actor`] module for additional information.
+    ///
+    /// Returns a [`JoinHandle`] that can be used to retrieve the output of
+    /// the future, or to abort it. Dropping the handle doesn't stop the
+    /// future.
     #[allow(clippy::needless_pass_by_value)]
-    pub fn spawn_local_future<Fut>(&mut self, future: Fut, options: FutureOptions)
+    pub fn spawn_local_future<Fut>(
+        &mut self,
+        future: Fut,
+        options: FutureOptions,
+    ) -> JoinHandle<Fut::Output>
     where
-        Fut: Future<Output = ()> + 'static,
+        Fut: Future + 'static,
+        Fut::Output: 'static,
     {
         self.internals
             .scheduler
@@ -506,14 +789,41 @@ impl RuntimeRef {
             .add_future(future, options.priority())
     }
 
+    /// Add a custom thread-local [`Process`].
+    ///
+    /// This is a low-level API to integrate state machines that don't fit the
+    /// actor or [`Future`] model, see the [`Process`] trait for more
+    /// information. Only the priority of the `options` is used. The process is
+    /// marked as ready to run, so it will be run at least once.
+    ///
+    /// Returns the [`ProcessId`] of the added process.
+    pub fn add_process<P>(&mut self, process: P, options: FutureOptions) -> ProcessId
+    where
+        P: Process + 'static,
+    {
+        self.internals
+            .scheduler
+            .borrow_mut()
+            .add_new_process(process, options.priority())
+    }
+
     /// Spawn a thread-safe [`Future`].
     ///
     /// Similar to thread-safe actors this can run on any of the workers
     /// threads. See the discussion of thread-local vs. thread-safe actors in
     /// the [`actor`] module for additional information.
-    pub fn spawn_future<Fut>(&mut self, future: Fut, options: FutureOptions)
+    ///
+    /// Returns a [`JoinHandle`] that can be used to retrieve the output of
+    /// the future, or to abort it. Dropping the handle doesn't stop the
+    /// future.
+    pub fn spawn_future<Fut>(
+        &mut self,
+        future: Fut,
+        options: FutureOptions,
+    ) -> JoinHandle<Fut::Output>
     where
-        Fut: Future<Output = ()> + Send + Sync + 'static,
+        Fut: Future + Send + Sync + 'static,
+        Fut::Output: Send + 'static,
     {
         self.internals.shared.spawn_future(future, options)
     }
@@ -531,8 +841,115 @@ impl RuntimeRef {
             .add_unique(actor_ref)
     }
 
+    /// Returns the number of thread-local processes that are ready to run on
+    /// this worker thread, i.e. the length of its run queue.
+    ///
+    /// This can be used as an indication of the load of the worker thread.
+    /// Note that this doesn't include thread-safe processes, which are shared
+    /// between all worker threads.
+    pub fn ready_processes(&self) -> usize {
+        self.internals.scheduler.borrow().ready_processes()
+    }
+
+    /// Returns `true` if the runtime is ready, i.e. all actors spawned with
+    /// [`ActorOptions::require_readiness`] reported they're ready using
+    /// [`actor::Context::report_ready`].
+    ///
+    /// This can be used by health endpoints to distinguish between an
+    /// application that is started and one that is ready to serve requests.
+    /// See [`actor::Context::wait_ready`] to wait for the runtime to become
+    /// ready.
+    pub fn is_ready(&self) -> bool {
+        self.internals.shared.readiness().is_ready()
+    }
+
+    /// Returns the event bus of the runtime.
+    ///
+    /// The bus is shared by all actors in the runtime, see the [`bus`] module
+    /// for more information.
+    ///
+    /// [`bus`]: crate::bus
+    pub fn bus(&self) -> &Bus {
+        self.internals.shared.bus()
+    }
+
+    /// Returns the current configuration of type `T`, if any.
+    ///
+    /// See the [`config`] module for more information.
+    ///
+    /// [`config`]: crate::config
+    pub fn config<T>(&self) -> Option<Arc<T>>
+    where
+        T: Send + Sync + 'static,
+    {
+        self.internals.shared.config().get()
+    }
+
+    /// Update the configuration of type `T` to `config`.
+    ///
+    /// This publishes a [`ConfigUpdated`] message to all subscribed actors,
+    /// returning the number of actors the message was send to. See the
+    /// [`config`] module for more information.
+    ///
+    /// [`ConfigUpdated`]: crate::config::ConfigUpdated
+    /// [`config`]: crate::config
+    pub fn update_config<T>(&mut self, config: T) -> usize
+    where
+        T: Send + Sync + 'static,
+    {
+        let shared = &self.internals.shared;
+        shared.config().update(shared.bus(), config)
+    }
+
+    /// Reload all configuration values loaded using [`Runtime::load_config`].
+    ///
+    /// For each reloaded configuration a [`ConfigUpdated`] message is
+    /// published. If a loader fails the configuration is not changed. See the
+    /// [`config`] module for more information.
+    ///
+    /// [`ConfigUpdated`]: crate::config::ConfigUpdated
+    /// [`config`]: crate::config
+    pub fn reload_config(&mut self) -> Result<(), ReloadError> {
+        let shared = &self.internals.shared;
+        shared.config().reload(shared.bus())
+    }
+
+    /// Returns the run statistics of the process with `pid`, e.g. an actor.
+    ///
+    /// This can be the pid of a thread-local process running on this worker
+    /// thread or of any thread-safe process. Returns `None` if the process
+    /// doesn't exist (anymore) or if process statistics are not enabled, see
+    /// [`Setup::enable_process_stats`].
+    ///
+    /// The statistics of a process are updated after each time it's run, so
+    /// calling this from within the process itself doesn't include the
+    /// current run.
+    pub fn process_stats(&self, pid: ProcessId) -> Option<ProcessStats> {
+        let stats = self.internals.scheduler.borrow().process_stats(pid);
+        stats.or_else(|| self.internals.shared.process_stats(pid))
+    }
+
+    /// Returns the run statistics of all thread-local processes running on
+    /// this worker thread and all thread-safe processes, in no particular
+    /// order.
+    ///
+    /// See [`RuntimeRef::process_stats`] for more information.
+    pub fn all_process_stats(&self) -> impl Iterator<Item = (ProcessId, ProcessStats)> {
+        let mut stats: Vec<_> = self
+            .internals
+            .scheduler
+            .borrow()
+            .all_process_stats()
+            .collect();
+        stats.extend(self.internals.shared.all_process_stats());
+        stats.into_iter()
+    }
+
     /// Register an `event::Source`, see [`mio::Registry::register`].
-    pub(crate) fn register<S>(
+    ///
+    /// Using a [`ProcessId`] as `token` wakes the process with that id once
+    /// the source is ready, see [`Process`].
+    pub fn register<S>(
         &mut self,
         source: &mut S,
         token: Token,
@@ -549,7 +966,7 @@ impl RuntimeRef {
     }
 
     /// Reregister an `event::Source`, see [`mio::Registry::reregister`].
-    pub(crate) fn reregister<S>(
+    pub fn reregister<S>(
         &mut self,
         source: &mut S,
         token: Token,
@@ -565,12 +982,9 @@ impl RuntimeRef {
             .reregister(source, token, interest)
     }
 
-    /// Get a clone of the sending end of the notification channel.
-    ///
-    /// # Notes
-    ///
-    /// Prefer `new_waker` if possible, only use `task::Waker` for `Future`s.
-    pub(crate) fn new_local_task_waker(&self, pid: ProcessId) -> task::Waker {
+    /// Returns a [`task::Waker`] that wakes the thread-local process with
+    /// `pid`, see [`Process`].
+    pub fn new_local_task_waker(&self, pid: ProcessId) -> task::Waker {
         waker::new(self.internals.waker_id, pid)
     }
 
@@ -615,6 +1029,69 @@ impl RuntimeRef {
         self.internals.cpu
     }
 
+    /// Returns `true` if earliest-deadline-first scheduling is enabled.
+    pub(crate) fn deadline_scheduling(&self) -> bool {
+        self.internals.deadline_scheduling
+    }
+
+    /// Returns the maximum time a process should run before returning, if
+    /// set.
+    pub(crate) fn time_slice(&self) -> Option<Duration> {
+        self.internals.time_slice
+    }
+
+    /// Spawn a thread-local actor using the already borrowed `scheduler`.
+    ///
+    /// `options` must already inherit the runtime's default actor options.
+    fn spawn_local_with<S, NA, ArgFn, E>(
+        &self,
+        scheduler: &mut local::Scheduler,
+        supervisor: S,
+        mut new_actor: NA,
+        arg_fn: ArgFn,
+        options: &ActorOptions,
+    ) -> Result<ActorRef<NA::Message>, AddActorError<NA::Error, E>>
+    where
+        S: Supervisor<NA> + 'static,
+        NA: NewActor<RuntimeAccess = ThreadLocal> + 'static,
+        NA::Actor: 'static,
+        ArgFn: FnOnce(&mut actor::Context<NA::Message, ThreadLocal>) -> Result<NA::Argument, E>,
+    {
+        // Setup adding a new process to the scheduler.
+        let actor_entry = scheduler
+            .add_actor()
+            .with_cpu_quota(options.cpu_quota())
+            .with_name(options.name())
+            .with_catch_panics(options.catches_panics());
+        let pid = actor_entry.pid();
+        let name = options.name().unwrap_or_else(|| new_actor.name());
+        debug!("spawning thread-local actor: pid={}, name={}", pid, name);
+        if options.readiness_required() {
+            self.internals.shared.readiness().require(pid);
+        }
+
+        // Create our actor context and our actor with it.
+        let (manager, sender, receiver) = Manager::new_small_channel();
+        let actor_ref = ActorRef::local(sender);
+        let mut ctx = actor::Context::new(receiver, ThreadLocal::new(pid, self.clone()));
+        // Create our actor argument, running any setup required by the caller.
+        let arg = arg_fn(&mut ctx).map_err(AddActorError::ArgFn)?;
+        new_actor.validate(&arg).map_err(AddActorError::NewActor)?;
+        let actor = new_actor.new(ctx, arg).map_err(AddActorError::NewActor)?;
+
+        // Add the actor to the scheduler.
+        actor_entry.add(
+            options.priority(),
+            supervisor,
+            new_actor,
+            actor,
+            manager,
+            options.is_ready(),
+        );
+
+        Ok(actor_ref)
+    }
+
     fn start_trace(&self) -> Option<trace::EventTiming> {
         trace::start(&*self.internals.trace_log.borrow())
     }
@@ -653,39 +1130,16 @@ where
     fn try_spawn_setup<ArgFn, E>(
         &mut self,
         supervisor: S,
-        mut new_actor: NA,
+        new_actor: NA,
         arg_fn: ArgFn,
         options: ActorOptions,
     ) -> Result<ActorRef<NA::Message>, AddActorError<NA::Error, E>>
     where
         ArgFn: FnOnce(&mut actor::Context<NA::Message, ThreadLocal>) -> Result<NA::Argument, E>,
     {
-        // Setup adding a new process to the scheduler.
+        let options = options.inherit(self.internals.shared.default_actor_options());
         let mut scheduler = self.internals.scheduler.borrow_mut();
-        let actor_entry = scheduler.add_actor();
-        let pid = actor_entry.pid();
-        let name = new_actor.name();
-        debug!("spawning thread-local actor: pid={}, name={}", pid, name);
-
-        // Create our actor context and our actor with it.
-        let (manager, sender, receiver) = inbox::Manager::new_small_channel();
-        let actor_ref = ActorRef::local(sender);
-        let mut ctx = actor::Context::new(receiver, ThreadLocal::new(pid, self.clone()));
-        // Create our actor argument, running any setup required by the caller.
-        let arg = arg_fn(&mut ctx).map_err(AddActorError::ArgFn)?;
-        let actor = new_actor.new(ctx, arg).map_err(AddActorError::NewActor)?;
-
-        // Add the actor to the scheduler.
-        actor_entry.add(
-            options.priority(),
-            supervisor,
-            new_actor,
-            actor,
-            manager,
-            options.is_ready(),
-        );
-
-        Ok(actor_ref)
+        self.spawn_local_with(&mut scheduler, supervisor, new_actor, arg_fn, &options)
     }
 }
 
@@ -721,7 +1175,7 @@ where
     }
 }
 
-fn cpu_usage(clock_id: libc::clockid_t) -> Duration {
+pub(crate) fn cpu_usage(clock_id: libc::clockid_t) -> Duration {
     let mut duration = libc::timespec {
         tv_sec: 0,
         tv_nsec: 0,
@@ -1,16 +1,23 @@
 //! Module containing the implementation of the [`Process`] trait for
 //! [`Actor`]s.
 
+use std::any::{type_name, Any};
+use std::panic::{self, AssertUnwindSafe};
 use std::pin::Pin;
 use std::task::{self, Poll};
+use std::thread;
+use std::time::Instant;
 
-use heph_inbox::{Manager, Receiver};
+use log::error;
 
+use crate::actor::inbox::{Manager, Receiver};
+use crate::actor::messages::StopReason;
 use crate::actor::{self, Actor, NewActor};
 use crate::rt::access::PrivateAccess;
 use crate::rt::process::{Process, ProcessId, ProcessResult};
 use crate::rt::{self, RuntimeRef, ThreadLocal, ThreadSafe};
 use crate::supervisor::{Supervisor, SupervisorStrategy};
+use crate::trace;
 
 /// A process that represent an [`Actor`].
 pub(crate) struct ActorProcess<S, NA: NewActor> {
@@ -24,6 +31,12 @@ pub(crate) struct ActorProcess<S, NA: NewActor> {
     inbox: Manager<NA::Message>,
     /// The running actor.
     actor: NA::Actor,
+    /// Delayed restart of the actor, see [`SupervisorStrategy::RestartAfter`].
+    restart: Option<(Instant, NA::Argument)>,
+    /// Name of the actor, overwriting [`NewActor::name`].
+    name: Option<&'static str>,
+    /// Catch panics of the actor, passing them to the supervisor.
+    catch_panics: bool,
 }
 
 impl<S, NA> ActorProcess<S, NA>
@@ -44,9 +57,29 @@ where
             new_actor,
             inbox,
             actor,
+            restart: None,
+            name: None,
+            catch_panics: false,
         }
     }
 
+    /// Set the name of the actor, see [`ActorOptions::with_name`].
+    ///
+    /// [`ActorOptions::with_name`]: crate::spawn::ActorOptions::with_name
+    pub(crate) fn with_name(mut self, name: Option<&'static str>) -> Self {
+        self.name = name;
+        self
+    }
+
+    /// Set whether to catch panics of the actor, see
+    /// [`ActorOptions::catch_panics`].
+    ///
+    /// [`ActorOptions::catch_panics`]: crate::spawn::ActorOptions::catch_panics
+    pub(crate) fn with_catch_panics(mut self, catch_panics: bool) -> Self {
+        self.catch_panics = catch_panics;
+        self
+    }
+
     /// Returns `Ok(ProcessResult::Pending)` if the actor was successfully
     /// restarted, `Ok(ProcessResult::Complete)` if the actor wasn't restarted
     /// or an error if the actor failed to restart.
@@ -56,12 +89,14 @@ where
         pid: ProcessId,
         err: <NA::Actor as Actor>::Error,
     ) -> Result<ProcessResult, NA::Error> {
-        match self.supervisor.decide(err) {
-            SupervisorStrategy::Restart(arg) => self
-                .create_new_actor(runtime_ref, pid, arg)
-                .map(|()| ProcessResult::Pending),
-            SupervisorStrategy::Stop => Ok(ProcessResult::Complete),
-        }
+        let strategy = self.decide(
+            runtime_ref,
+            pid,
+            "error",
+            type_name::<<NA::Actor as Actor>::Error>(),
+            |supervisor| supervisor.decide(err),
+        );
+        self.handle_strategy(runtime_ref, pid, strategy)
     }
 
     /// Same as `handle_actor_error` but handles [`NewActor::Error`]s instead.
@@ -71,11 +106,107 @@ where
         pid: ProcessId,
         err: NA::Error,
     ) -> Result<ProcessResult, NA::Error> {
-        match self.supervisor.decide_on_restart_error(err) {
+        let strategy = self.decide(
+            runtime_ref,
+            pid,
+            "restart error",
+            type_name::<NA::Error>(),
+            |supervisor| supervisor.decide_on_restart_error(err),
+        );
+        self.handle_strategy(runtime_ref, pid, strategy)
+    }
+
+    /// Same as `handle_actor_error` but handles a failure escalated by a child
+    /// actor instead.
+    fn handle_escalation(
+        &mut self,
+        runtime_ref: &mut RuntimeRef,
+        pid: ProcessId,
+    ) -> Result<ProcessResult, NA::Error> {
+        let strategy = self.decide(runtime_ref, pid, "escalation", "", |supervisor| {
+            supervisor.decide_on_escalation()
+        });
+        self.handle_strategy(runtime_ref, pid, strategy)
+    }
+
+    /// Let the supervisor decide what to do with the failed actor.
+    ///
+    /// If tracing is enabled this adds a "Supervising actor" trace event,
+    /// including the error captured by [`trace::capture_error`] (if any).
+    fn decide<F>(
+        &mut self,
+        runtime_ref: &mut RuntimeRef,
+        pid: ProcessId,
+        cause: &'static str,
+        error_type: &'static str,
+        decide: F,
+    ) -> SupervisorStrategy<NA::Argument>
+    where
+        F: FnOnce(&mut S) -> SupervisorStrategy<NA::Argument>,
+    {
+        let timing = NA::RuntimeAccess::start_trace(runtime_ref);
+        if timing.is_none() {
+            return decide(&mut self.supervisor);
+        }
+
+        trace::start_error_capture();
+        let strategy = decide(&mut self.supervisor);
+        let error = trace::take_captured_error();
+        let decision = match &strategy {
+            SupervisorStrategy::Restart(_) => "restart",
+            SupervisorStrategy::RestartAfter(..) => "restart after",
+            SupervisorStrategy::Stop => "stop",
+            SupervisorStrategy::Escalate => "escalate",
+        };
+        let name = match self.name {
+            Some(name) => name,
+            None => self.new_actor.name(),
+        };
+        NA::RuntimeAccess::finish_trace(
+            runtime_ref,
+            timing,
+            pid,
+            "Supervising actor",
+            &[
+                ("name", &name),
+                ("cause", &cause),
+                ("error_type", &error_type),
+                ("error", &error),
+                ("decision", &decision),
+            ],
+        );
+        strategy
+    }
+
+    /// Apply the supervisor's `strategy`.
+    fn handle_strategy(
+        &mut self,
+        runtime_ref: &mut RuntimeRef,
+        pid: ProcessId,
+        strategy: SupervisorStrategy<NA::Argument>,
+    ) -> Result<ProcessResult, NA::Error> {
+        // The resources of the failed actor are dropped before it's restarted
+        // (or stopped), see `actor::Context::register_resource`.
+        self.inbox.drop_resources();
+        match strategy {
             SupervisorStrategy::Restart(arg) => self
                 .create_new_actor(runtime_ref, pid, arg)
                 .map(|()| ProcessResult::Pending),
+            SupervisorStrategy::RestartAfter(arg, delay) => {
+                // The actor is restarted once the deadline has passed, see
+                // `Process::run`.
+                let deadline = Instant::now() + delay;
+                NA::RuntimeAccess::add_deadline(runtime_ref, pid, deadline);
+                self.restart = Some((deadline, arg));
+                Ok(ProcessResult::Pending)
+            }
             SupervisorStrategy::Stop => Ok(ProcessResult::Complete),
+            SupervisorStrategy::Escalate => {
+                // If the actor doesn't have a parent this is the same as
+                // stopping it.
+                let _ = self.inbox.escalate();
+                Ok(ProcessResult::Complete)
+            }
         }
     }
 
@@ -86,6 +217,12 @@ where
         pid: ProcessId,
         arg: NA::Argument,
     ) -> Result<(), NA::Error> {
+        self.new_actor.validate(&arg)?;
+        // The children of the old actor are stopped, the new actor is expected
+        // to spawn its own.
+        self.inbox.stop_children();
+        // This replaces any delayed restart.
+        self.restart = None;
         let receiver = self.inbox.new_receiver().expect(
             "failed to create new receiver for actor's inbox. Was the `actor::Context` leaked?",
         );
@@ -105,47 +242,204 @@ where
     NA::RuntimeAccess: rt::Access + RuntimeSupport,
 {
     fn name(&self) -> &'static str {
-        self.new_actor.name()
+        match self.name {
+            Some(name) => name,
+            None => self.new_actor.name(),
+        }
+    }
+
+    fn message_type(&self) -> Option<&'static str> {
+        Some(type_name::<NA::Message>())
     }
 
     fn run(self: Pin<&mut Self>, runtime_ref: &mut RuntimeRef, pid: ProcessId) -> ProcessResult {
         // This is safe because we're not moving the actor.
         let this = unsafe { Pin::get_unchecked_mut(self) };
+        let waker = NA::RuntimeAccess::new_task_waker(runtime_ref, pid);
+
+        // Handle the parent or child actors, see `actor::Context::spawn_child`.
+        if this.inbox.register_lifecycle_waker(&waker) {
+            if this.inbox.stop_requested() {
+                // The actor was asked to stop, either because its parent
+                // stopped or using `ActorRef::stop`. We give it one last poll,
+                // in which receiving a message returns an error, to stop
+                // gracefully before it's dropped. A failed actor waiting on a
+                // restart can't be polled.
+                if this.restart.is_none() {
+                    if let Err(panic) = this.poll_actor(&waker) {
+                        error!(
+                            "actor panicked while stopping: pid={}, name={}, message={}",
+                            pid,
+                            this.name(),
+                            panic_message(&*panic)
+                        );
+                    }
+                }
+                this.inbox.stopped(StopReason::Terminated);
+                return ProcessResult::Complete;
+            }
+            if this.inbox.take_escalation() {
+                let res = this.handle_escalation(runtime_ref, pid);
+                return this.handle_result(runtime_ref, pid, res);
+            }
+        }
+
+        // Handle a delayed restart, see `SupervisorStrategy::RestartAfter`.
+        match this.restart.take() {
+            Some((deadline, arg)) if deadline <= Instant::now() => {
+                let res = this
+                    .create_new_actor(runtime_ref, pid, arg)
+                    .map(|()| ProcessResult::Pending);
+                return this.handle_result(runtime_ref, pid, res);
+            }
+            Some(restart) => {
+                // Not yet time to restart the actor, the failed actor must not
+                // be run again.
+                this.restart = Some(restart);
+                return ProcessResult::Pending;
+            }
+            None => {}
+        }
+
+        let poll = match this.poll_actor(&waker) {
+            Ok(poll) => poll,
+            Err(panic) => return this.handle_panic(runtime_ref, pid, panic),
+        };
+        match poll {
+            Poll::Ready(Ok(())) => {
+                this.inbox.stopped(StopReason::Completed);
+                ProcessResult::Complete
+            }
+            Poll::Ready(Err(err)) => {
+                let res = this.handle_actor_error(runtime_ref, pid, err);
+                this.handle_result(runtime_ref, pid, res)
+            }
+            Poll::Pending => {
+                // The actor could have spawned a child actor while running, in
+                // which case we need to register our waker.
+                if this.inbox.register_lifecycle_waker(&waker) {
+                    waker.wake_by_ref();
+                }
+                ProcessResult::Pending
+            }
+        }
+    }
+}
+
+impl<S, NA> ActorProcess<S, NA>
+where
+    S: Supervisor<NA>,
+    NA: NewActor,
+    NA::RuntimeAccess: rt::Access + RuntimeSupport,
+{
+    /// Poll the actor, catching a panic if enabled.
+    fn poll_actor(
+        &mut self,
+        waker: &task::Waker,
+    ) -> Result<Poll<Result<(), <NA::Actor as Actor>::Error>>, Box<dyn Any + Send + 'static>> {
         // The actor need to be called with `Pin`. So we're undoing the previous
         // operation, still ensuring that the actor is not moved.
-        let mut actor = unsafe { Pin::new_unchecked(&mut this.actor) };
+        let mut actor = unsafe { Pin::new_unchecked(&mut self.actor) };
+        let mut task_ctx = task::Context::from_waker(waker);
+        if self.catch_panics {
+            panic::catch_unwind(AssertUnwindSafe(|| actor.as_mut().try_poll(&mut task_ctx)))
+        } else {
+            Ok(actor.as_mut().try_poll(&mut task_ctx))
+        }
+    }
 
-        let waker = NA::RuntimeAccess::new_task_waker(runtime_ref, pid);
-        let mut task_ctx = task::Context::from_waker(&waker);
-        match actor.as_mut().try_poll(&mut task_ctx) {
-            Poll::Ready(Ok(())) => ProcessResult::Complete,
-            Poll::Ready(Err(err)) => match this.handle_actor_error(runtime_ref, pid, err) {
+    /// Handle a `panic` of the actor, caught in `Process::run`.
+    fn handle_panic(
+        &mut self,
+        runtime_ref: &mut RuntimeRef,
+        pid: ProcessId,
+        panic: Box<dyn Any + Send + 'static>,
+    ) -> ProcessResult {
+        error!(
+            "actor panicked: pid={}, name={}, message={}",
+            pid,
+            self.name(),
+            panic_message(&*panic)
+        );
+        let strategy = self.decide(runtime_ref, pid, "panic", "", |supervisor| {
+            // Supervisors don't get the panic message, so we capture it here.
+            trace::capture_error(&panic_message(&*panic));
+            supervisor.decide_on_panic(panic)
+        });
+        match self.handle_strategy(runtime_ref, pid, strategy) {
+            // Actor wasn't restarted.
+            Ok(ProcessResult::Complete) => {
+                self.inbox.stopped(StopReason::Panicked);
+                ProcessResult::Complete
+            }
+            res => self.handle_result(runtime_ref, pid, res),
+        }
+    }
+
+    /// Handle the result of a supervisor's decision, see `handle_actor_error`.
+    fn handle_result(
+        &mut self,
+        runtime_ref: &mut RuntimeRef,
+        pid: ProcessId,
+        res: Result<ProcessResult, NA::Error>,
+    ) -> ProcessResult {
+        match res {
+            Ok(ProcessResult::Pending) => {
+                // Run the actor just in case progress can be made already,
+                // this required because we use edge triggers for I/O.
+                unsafe { Pin::new_unchecked(self) }.run(runtime_ref, pid)
+            }
+            // Actor wasn't restarted.
+            Ok(ProcessResult::Complete) => {
+                self.inbox.stopped(StopReason::Failed);
+                ProcessResult::Complete
+            }
+            Err(err) => match self.handle_restart_error(runtime_ref, pid, err) {
                 Ok(ProcessResult::Pending) => {
-                    // Run the actor just in case progress can be made already,
-                    // this required because we use edge triggers for I/O.
-                    unsafe { Pin::new_unchecked(this) }.run(runtime_ref, pid)
+                    // Run the actor, same reason as above.
+                    unsafe { Pin::new_unchecked(self) }.run(runtime_ref, pid)
                 }
                 // Actor wasn't restarted.
-                Ok(ProcessResult::Complete) => ProcessResult::Complete,
-                Err(err) => match this.handle_restart_error(runtime_ref, pid, err) {
-                    Ok(ProcessResult::Pending) => {
-                        // Run the actor, same reason as above.
-                        unsafe { Pin::new_unchecked(this) }.run(runtime_ref, pid)
-                    }
-                    // Actor wasn't restarted.
-                    Ok(ProcessResult::Complete) => ProcessResult::Complete,
-                    Err(err) => {
-                        // Let the supervisor know.
-                        this.supervisor.second_restart_error(err);
-                        ProcessResult::Complete
-                    }
-                },
+                Ok(ProcessResult::Complete) => {
+                    self.inbox.stopped(StopReason::Failed);
+                    ProcessResult::Complete
+                }
+                Err(err) => {
+                    // Let the supervisor know.
+                    self.supervisor.second_restart_error(err);
+                    self.inbox.stopped(StopReason::Failed);
+                    ProcessResult::Complete
+                }
             },
-            Poll::Pending => ProcessResult::Pending,
         }
     }
 }
 
+impl<S, NA: NewActor> Drop for ActorProcess<S, NA> {
+    fn drop(&mut self) {
+        // If the actor didn't stop normally (see `Process::run`) it was either
+        // dropped while panicking or before it could complete, e.g. when the
+        // runtime stops.
+        let reason = if thread::panicking() {
+            StopReason::Panicked
+        } else {
+            StopReason::Terminated
+        };
+        self.inbox.stopped(reason);
+    }
+}
+
+/// Returns the message of a `panic` payload.
+fn panic_message(panic: &(dyn Any + Send + 'static)) -> &str {
+    match panic.downcast_ref::<&'static str>() {
+        Some(msg) => msg,
+        None => match panic.downcast_ref::<String>() {
+            Some(msg) => msg,
+            None => "<unknown>",
+        },
+    }
+}
+
 /// Trait to support different kind of runtime access, e.g. [`ThreadSafe`] and
 /// [`ThreadLocal`], within the same implementation of [`ActorProcess`].
 pub(crate) trait RuntimeSupport {
@@ -157,6 +451,21 @@ pub(crate) trait RuntimeSupport {
     ) -> actor::Context<M, Self>
     where
         Self: Sized;
+
+    /// Add a `deadline` for the process with `pid`.
+    fn add_deadline(runtime_ref: &mut RuntimeRef, pid: ProcessId, deadline: Instant);
+
+    /// Start timing a trace event, if tracing is enabled.
+    fn start_trace(runtime_ref: &mut RuntimeRef) -> Option<trace::EventTiming>;
+
+    /// Finish a trace event for the process with `pid`.
+    fn finish_trace(
+        runtime_ref: &mut RuntimeRef,
+        timing: Option<trace::EventTiming>,
+        pid: ProcessId,
+        description: &str,
+        attributes: &[(&str, &dyn trace::AttributeValue)],
+    );
 }
 
 impl RuntimeSupport for ThreadLocal {
@@ -167,6 +476,24 @@ impl RuntimeSupport for ThreadLocal {
     ) -> actor::Context<M, ThreadLocal> {
         actor::Context::new(inbox, ThreadLocal::new(pid, runtime_ref.clone()))
     }
+
+    fn add_deadline(runtime_ref: &mut RuntimeRef, pid: ProcessId, deadline: Instant) {
+        ThreadLocal::new(pid, runtime_ref.clone()).add_deadline(deadline);
+    }
+
+    fn start_trace(runtime_ref: &mut RuntimeRef) -> Option<trace::EventTiming> {
+        runtime_ref.start_trace()
+    }
+
+    fn finish_trace(
+        runtime_ref: &mut RuntimeRef,
+        timing: Option<trace::EventTiming>,
+        pid: ProcessId,
+        description: &str,
+        attributes: &[(&str, &dyn trace::AttributeValue)],
+    ) {
+        runtime_ref.finish_trace(timing, pid, description, attributes)
+    }
 }
 
 impl RuntimeSupport for ThreadSafe {
@@ -177,4 +504,25 @@ impl RuntimeSupport for ThreadSafe {
     ) -> actor::Context<M, ThreadSafe> {
         actor::Context::new(inbox, ThreadSafe::new(pid, runtime_ref.clone_shared()))
     }
+
+    fn add_deadline(runtime_ref: &mut RuntimeRef, pid: ProcessId, deadline: Instant) {
+        ThreadSafe::new(pid, runtime_ref.clone_shared()).add_deadline(deadline);
+    }
+
+    fn start_trace(runtime_ref: &mut RuntimeRef) -> Option<trace::EventTiming> {
+        runtime_ref.internals.shared.start_trace()
+    }
+
+    fn finish_trace(
+        runtime_ref: &mut RuntimeRef,
+        timing: Option<trace::EventTiming>,
+        pid: ProcessId,
+        description: &str,
+        attributes: &[(&str, &dyn trace::AttributeValue)],
+    ) {
+        runtime_ref
+            .internals
+            .shared
+            .finish_trace(timing, pid, description, attributes)
+    }
 }
@@ -6,29 +6,36 @@ use std::marker::PhantomData;
 use std::pin::Pin;
 use std::task::{self, Poll};
 
+use crate::rt::join_handle::{self, Completion, JoinHandle};
 use crate::rt::process::{Process, ProcessId, ProcessResult};
 use crate::rt::{self, RuntimeRef};
 
 /// A process that represent a [`Future`].
-pub(crate) struct FutureProcess<Fut, RT> {
+pub(crate) struct FutureProcess<Fut: Future, RT> {
     future: Fut,
+    /// Used to pass the output of the future to its [`JoinHandle`].
+    completion: Completion<Fut::Output>,
     /// We need to know whether we need to create thread-local or thread-safe
     /// waker.
     _phantom: PhantomData<RT>,
 }
 
-impl<Fut, RT> FutureProcess<Fut, RT> {
-    pub(crate) const fn new(future: Fut) -> FutureProcess<Fut, RT> {
-        FutureProcess {
+impl<Fut: Future, RT> FutureProcess<Fut, RT> {
+    /// Returns the process and the [`JoinHandle`] to the `future`.
+    pub(crate) fn new(future: Fut) -> (FutureProcess<Fut, RT>, JoinHandle<Fut::Output>) {
+        let (completion, handle) = join_handle::new();
+        let process = FutureProcess {
             future,
+            completion,
             _phantom: PhantomData,
-        }
+        };
+        (process, handle)
     }
 }
 
 impl<Fut, RT> Process for FutureProcess<Fut, RT>
 where
-    Fut: Future<Output = ()>,
+    Fut: Future,
     RT: rt::Access,
 {
     fn name(&self) -> &'static str {
@@ -37,12 +44,21 @@ where
 
     fn run(self: Pin<&mut Self>, runtime_ref: &mut RuntimeRef, pid: ProcessId) -> ProcessResult {
         // This is safe because we're not moving the future.
-        let future = unsafe { Pin::map_unchecked_mut(self, |this| &mut this.future) };
+        let this = unsafe { Pin::get_unchecked_mut(self) };
+        let future = unsafe { Pin::new_unchecked(&mut this.future) };
 
         let waker = RT::new_task_waker(runtime_ref, pid);
+        if this.completion.is_aborted(&waker) {
+            // Completing the process drops the future.
+            return ProcessResult::Complete;
+        }
+
         let mut task_ctx = task::Context::from_waker(&waker);
         match Future::poll(future, &mut task_ctx) {
-            Poll::Ready(()) => ProcessResult::Complete,
+            Poll::Ready(output) => {
+                this.completion.complete(output);
+                ProcessResult::Complete
+            }
             Poll::Pending => ProcessResult::Pending,
         }
     }
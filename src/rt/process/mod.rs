@@ -1,33 +1,60 @@
 //! Module containing the `Process` trait, related types and implementations.
 
-use std::cmp::Ordering;
+use std::cell::Cell;
 use std::fmt;
+use std::mem::take;
+use std::num::ParseIntError;
 use std::pin::Pin;
+use std::str::FromStr;
+use std::thread;
 use std::time::{Duration, Instant};
 
-use log::trace;
+use log::{error, trace, warn};
 use mio::Token;
 
+use crate::actor_ref::rpc::Caller;
 use crate::rt::RuntimeRef;
 use crate::spawn::options::Priority;
 
 mod actor;
 mod future;
+mod stats;
 #[cfg(test)]
 mod tests;
 
 pub(crate) use actor::ActorProcess;
 pub(crate) use future::FutureProcess;
+pub use stats::ProcessStats;
 
 /// Process id, or pid for short, is an identifier for a process in an
 /// [`Runtime`].
 ///
-/// This can only be created by one of the schedulers and should be seen as an
-/// opaque type for the rest of the crate. For convince this can converted from
-/// and into an [`Token`] as used by Mio.
+/// Process ids are created by the schedulers and are unique within a
+/// [`Runtime`] while the process is running, an id can be reused once the
+/// process completes. An actor can get its own process id using
+/// [`actor::Context::pid`].
+///
+/// The process id is formatted as a decimal number, e.g. `123`. It's used in
+/// this format in the logs (as `pid=123`), in the traces (as the `pid` of an
+/// event) and in the [process statistics], so the data from all these sources
+/// can be joined. It can be parsed from the same format using [`FromStr`] and,
+/// if the `serde` feature is enabled (e.g. via the `record` feature),
+/// (de)serialised as a number.
+///
+/// For convince this can converted from and into an [`Token`] as used by Mio.
 ///
 /// [`Runtime`]: crate::Runtime
-// NOTE: public because it used in the `RuntimeAccess` trait.
+/// [`actor::Context::pid`]: crate::actor::Context::pid
+/// [process statistics]: crate::rt::RuntimeRef::process_stats
+///
+/// # Examples
+///
+/// ```
+/// use heph::rt::ProcessId;
+///
+/// let pid: ProcessId = "123".parse().unwrap();
+/// assert_eq!(pid.to_string(), "123");
+/// ```
 #[derive(Copy, Clone, Eq, PartialEq, Hash, Ord, PartialOrd)]
 #[repr(transparent)]
 pub struct ProcessId(pub(crate) usize);
@@ -56,21 +83,115 @@ impl fmt::Display for ProcessId {
     }
 }
 
+impl FromStr for ProcessId {
+    type Err = ParseIntError;
+
+    fn from_str(s: &str) -> Result<ProcessId, ParseIntError> {
+        s.parse().map(ProcessId)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl serde::Serialize for ProcessId {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        serializer.serialize_u64(self.0 as u64)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for ProcessId {
+    fn deserialize<D>(deserializer: D) -> Result<ProcessId, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        usize::deserialize(deserializer).map(ProcessId)
+    }
+}
+
 /// The trait that represents a process.
 ///
-/// This currently has a single implementation:
-/// - `ActorProcess`, which wraps an `Actor` to implement this trait.
-pub(crate) trait Process {
+/// A process is the unit of work run by the schedulers of the runtime. Actors
+/// and futures are run as processes, but it's also possible to implement this
+/// trait to run a custom state machine, e.g. an event pump of a C library,
+/// without having to fake an actor. Such a process is added to the runtime
+/// using [`RuntimeRef::add_process`].
+///
+/// Just like actors, processes get their own [`ProcessId`], can have a
+/// priority (see [`FutureOptions::with_priority`]) and are accounted for in the
+/// [process statistics].
+///
+/// [`FutureOptions::with_priority`]: crate::spawn::FutureOptions::with_priority
+/// [process statistics]: RuntimeRef::process_stats
+///
+/// # Waking a process
+///
+/// Once a process returns [`ProcessResult::Pending`] it will not be run again
+/// until it's woken. This can be done using a [`task::Waker`] created by
+/// [`RuntimeRef::new_local_task_waker`], or by registering an [`event::Source`]
+/// with the process' id as token using [`RuntimeRef::register`].
+///
+/// [`task::Waker`]: std::task::Waker
+/// [`event::Source`]: mio::event::Source
+///
+/// # Examples
+///
+/// ```
+/// use std::pin::Pin;
+///
+/// use heph::rt::{self, EmbeddedRuntime, Process, ProcessId, ProcessResult, RuntimeRef};
+/// use heph::spawn::FutureOptions;
+///
+/// /// Process that runs three times, waking itself each time.
+/// struct Countdown(usize);
+///
+/// impl Process for Countdown {
+///     fn name(&self) -> &'static str {
+///         "Countdown"
+///     }
+///
+///     fn run(mut self: Pin<&mut Self>, runtime_ref: &mut RuntimeRef, pid: ProcessId) -> ProcessResult {
+///         self.0 -= 1;
+///         if self.0 == 0 {
+///             ProcessResult::Complete
+///         } else {
+///             // Make sure we're run again.
+///             runtime_ref.new_local_task_waker(pid).wake();
+///             ProcessResult::Pending
+///         }
+///     }
+/// }
+///
+/// fn main() -> Result<(), rt::Error> {
+///     let mut runtime = EmbeddedRuntime::new()?;
+///     let pid = runtime.runtime_ref().add_process(Countdown(3), FutureOptions::default());
+///     println!("added process: pid={}", pid);
+///     while runtime.poll_once()? {}
+///     Ok(())
+/// }
+/// ```
+pub trait Process {
     /// Return the name of this process, used in logging.
     fn name(&self) -> &'static str;
 
+    /// Return the name of the type of message the process receives, if any,
+    /// used in tracing.
+    fn message_type(&self) -> Option<&'static str> {
+        None
+    }
+
     /// Run the process.
     ///
-    /// Once the process returns `ProcessResult::Complete` it will be removed
+    /// Once the process returns [`ProcessResult::Complete`] it will be removed
     /// from the scheduler and will no longer run.
     ///
-    /// If it returns `ProcessResult::Pending` it will be considered inactive
-    /// and the process itself must make sure its gets scheduled again.
+    /// If it returns [`ProcessResult::Pending`] it will be considered inactive
+    /// and the process itself must make sure its gets scheduled again, see
+    /// [Waking a process].
+    ///
+    /// [Waking a process]: Process#waking-a-process
     fn run(self: Pin<&mut Self>, runtime_ref: &mut RuntimeRef, pid: ProcessId) -> ProcessResult;
 }
 
@@ -79,7 +200,7 @@ pub(crate) trait Process {
 /// See [`Process::run`].
 #[must_use]
 #[derive(Copy, Clone, Debug, Eq, PartialEq)]
-pub(crate) enum ProcessResult {
+pub enum ProcessResult {
     /// The process is complete.
     ///
     /// Similar to [`Poll::Ready`].
@@ -96,6 +217,97 @@ pub(crate) enum ProcessResult {
     Pending,
 }
 
+thread_local! {
+    /// Deadline set by the currently running process, if any. The outer
+    /// `Option` is `None` if the deadline wasn't changed, the inner `Option`
+    /// is the new deadline (`None` meaning the deadline was cleared).
+    ///
+    /// See [`set_deadline`] and [`ProcessData::run`].
+    static NEW_DEADLINE: Cell<Option<Option<Instant>>> = Cell::new(None);
+}
+
+thread_local! {
+    /// Number of messages received by the currently running process.
+    ///
+    /// See [`message_received`] and [`ProcessData::run`].
+    static MESSAGES_RECEIVED: Cell<u64> = Cell::new(0);
+}
+
+thread_local! {
+    /// The currently running process, if any.
+    ///
+    /// See [`current_caller`] and [`ProcessData::run`].
+    static CURRENT: Cell<Option<Current>> = Cell::new(None);
+}
+
+/// Information about the currently running process, see [`CURRENT`].
+#[derive(Copy, Clone)]
+struct Current {
+    pid: ProcessId,
+    name: &'static str,
+    /// Deadline of the process at the start of the run.
+    deadline: Option<Instant>,
+    /// Whether or not deadline scheduling is enabled.
+    deadline_scheduling: bool,
+}
+
+/// Guard that resets [`CURRENT`] once the process is done running, logging
+/// the process if it panicked.
+struct RunGuard {
+    pid: ProcessId,
+    name: &'static str,
+}
+
+impl Drop for RunGuard {
+    fn drop(&mut self) {
+        CURRENT.with(|current| current.set(None));
+        if thread::panicking() {
+            error!("process panicked: pid={}, name={}", self.pid, self.name);
+        }
+    }
+}
+
+/// Returns information about the currently running process as [`Caller`], or
+/// `None` if no process is running, e.g. when called from a synchronous actor.
+pub(crate) fn current_caller() -> Option<Caller> {
+    CURRENT.with(Cell::get).map(|current| {
+        // The process could have changed its deadline while running.
+        let deadline = match NEW_DEADLINE.with(Cell::get) {
+            Some(deadline) if current.deadline_scheduling => deadline,
+            _ => current.deadline,
+        };
+        Caller::new(current.pid, current.name, deadline)
+    })
+}
+
+/// Set (or clear if `None`) the deadline of the currently running process.
+///
+/// This only has an effect if deadline scheduling is enabled, see
+/// [`rt::Setup::enable_deadline_scheduling`].
+///
+/// [`rt::Setup::enable_deadline_scheduling`]: crate::rt::Setup::enable_deadline_scheduling
+pub(crate) fn set_deadline(deadline: Option<Instant>) {
+    NEW_DEADLINE.with(|new_deadline| new_deadline.set(Some(deadline)));
+}
+
+/// Record that the currently running process received a message, used in
+/// [`ProcessStats::messages`].
+pub(crate) fn message_received() {
+    MESSAGES_RECEIVED.with(|messages| messages.set(messages.get() + 1));
+}
+
+/// Returns the number of messages received by the last process that ran, see
+/// [`message_received`].
+pub(crate) fn take_messages_received() -> u64 {
+    MESSAGES_RECEIVED.with(Cell::take)
+}
+
+/// Length of the accounting window used for CPU quotas, see
+/// [`ActorOptions::with_cpu_quota`].
+///
+/// [`ActorOptions::with_cpu_quota`]: crate::spawn::ActorOptions::with_cpu_quota
+const QUOTA_WINDOW: Duration = Duration::from_secs(1);
+
 /// Data related to a process.
 ///
 /// # Notes
@@ -103,12 +315,19 @@ pub(crate) enum ProcessResult {
 /// `PartialEq` and `Eq` are implemented based on the id of the process
 /// (`ProcessId`).
 ///
-/// `PartialOrd` and `Ord` however are implemented based on runtime and
-/// priority.
+/// The order in which processes run is determined by the run queue, see
+/// [`heph_sched::Schedulable`].
 pub(crate) struct ProcessData<P: ?Sized> {
     priority: Priority,
-    /// Fair runtime of the process, which is `actual runtime * priority`.
-    fair_runtime: Duration,
+    /// Fair runtime not yet accounted for by the run queue, see
+    /// [`heph_sched::Schedulable::take_unaccounted_runtime`].
+    unaccounted_runtime: Duration,
+    /// Deadline of the process' current work, only set if deadline scheduling
+    /// is enabled.
+    deadline: Option<Instant>,
+    /// CPU quota of the process, if any. Boxed as most processes don't have
+    /// one.
+    cpu_quota: Option<Box<CpuQuota>>,
     process: Pin<Box<P>>,
 }
 
@@ -116,14 +335,45 @@ impl<P: ?Sized> ProcessData<P> {
     pub(crate) const fn new(priority: Priority, process: Pin<Box<P>>) -> ProcessData<P> {
         ProcessData {
             priority,
-            fair_runtime: Duration::ZERO,
+            unaccounted_runtime: Duration::ZERO,
+            deadline: None,
+            cpu_quota: None,
             process,
         }
     }
 
+    /// Set the CPU quota of the process, see
+    /// [`ActorOptions::with_cpu_quota`].
+    ///
+    /// [`ActorOptions::with_cpu_quota`]: crate::spawn::ActorOptions::with_cpu_quota
+    pub(crate) fn with_cpu_quota(mut self, quota: Option<Duration>) -> ProcessData<P> {
+        self.cpu_quota = quota.map(|quota| Box::new(CpuQuota::new(quota)));
+        self
+    }
+
+    /// Returns the time until which the process is suspended because it has
+    /// exceeded its CPU quota, or `None` if it's allowed to run.
     #[cfg(test)]
-    pub(crate) fn set_fair_runtime(&mut self, fair_runtime: Duration) {
-        self.fair_runtime = fair_runtime;
+    pub(crate) fn throttled_until(self: Pin<&Self>) -> Option<Instant> {
+        self.cpu_quota
+            .as_ref()
+            .and_then(|cpu_quota| cpu_quota.throttled_until(Instant::now()))
+    }
+
+    /// Returns the time at which to run the process again if it's suspended
+    /// because it has exceeded its CPU quota.
+    ///
+    /// This only returns the time once per suspension, so that the caller adds
+    /// a single timer for it, rather than one per run of the process.
+    pub(crate) fn throttle_timer(mut self: Pin<&mut Self>) -> Option<Instant> {
+        self.cpu_quota
+            .as_mut()
+            .and_then(|cpu_quota| cpu_quota.throttle_timer(Instant::now()))
+    }
+
+    /// Returns the priority of the process.
+    pub(crate) fn priority(self: Pin<&Self>) -> Priority {
+        self.priority
     }
 
     /// Returns the process identifier, or pid for short.
@@ -143,6 +393,11 @@ impl<P: Process + ?Sized> ProcessData<P> {
         self.process.name()
     }
 
+    /// Returns the name of the message type of the process, if any.
+    pub(crate) fn message_type(self: Pin<&Self>) -> Option<&'static str> {
+        self.process.message_type()
+    }
+
     /// Run the process.
     ///
     /// Returns the completion state of the process.
@@ -151,11 +406,68 @@ impl<P: Process + ?Sized> ProcessData<P> {
         let name = self.process.name();
         trace!("running process: pid={}, name={}", pid, name);
 
+        // Reset the deadline in case a previous process set it outside of
+        // `ProcessData::run`, e.g. in a test.
+        NEW_DEADLINE.with(|new_deadline| new_deadline.set(None));
+        MESSAGES_RECEIVED.with(|messages| messages.set(0));
+
         let start = Instant::now();
+        if let Some(until) = self
+            .cpu_quota
+            .as_ref()
+            .and_then(|q| q.throttled_until(start))
+        {
+            // The caller is responsible for scheduling the process again once
+            // the quota allows it, see `ProcessData::throttled_until`.
+            trace!(
+                "not running process, CPU quota exceeded: pid={}, name={}, until={:?}",
+                pid,
+                name,
+                until
+            );
+            return ProcessResult::Pending;
+        }
+
+        CURRENT.with(|current| {
+            current.set(Some(Current {
+                pid,
+                name,
+                deadline: self.deadline,
+                deadline_scheduling: runtime_ref.deadline_scheduling(),
+            }))
+        });
+        let guard = RunGuard { pid, name };
         let result = self.process.as_mut().run(runtime_ref, pid);
+        drop(guard);
         let elapsed = start.elapsed();
-        let fair_elapsed = elapsed * self.priority;
-        self.fair_runtime += fair_elapsed;
+        let time_slice = runtime_ref.time_slice();
+        if let Some(time_slice) = time_slice.filter(|time_slice| elapsed > *time_slice) {
+            warn!(
+                "process ran longer than time slice: pid={}, name={}, elapsed_time={:?}, time_slice={:?}",
+                pid, name, elapsed, time_slice
+            );
+        }
+        let fair_time = fair_elapsed(elapsed, self.priority, time_slice);
+        self.unaccounted_runtime += fair_time;
+        if let Some(cpu_quota) = self.cpu_quota.as_mut() {
+            cpu_quota.add_runtime(start, elapsed);
+        }
+
+        if let Some(deadline) = NEW_DEADLINE.with(Cell::take) {
+            if runtime_ref.deadline_scheduling() {
+                trace!(
+                    "setting process deadline: pid={}, deadline={:?}",
+                    pid,
+                    deadline
+                );
+                self.deadline = deadline;
+            } else {
+                trace!(
+                    "ignoring process deadline, deadline scheduling is disabled: pid={}",
+                    pid
+                );
+            }
+        }
 
         trace!(
             "finished running process: pid={}, name={}, elapsed_time={:?}, result={:?}",
@@ -169,26 +481,40 @@ impl<P: Process + ?Sized> ProcessData<P> {
     }
 }
 
-impl<P: ?Sized> Eq for ProcessData<P> {}
+/// Returns the fair runtime for a run of `elapsed` time.
+///
+/// If the process ran longer than `time_slice` the time over the slice is
+/// counted double, see [`rt::Setup::time_slice`].
+///
+/// [`rt::Setup::time_slice`]: crate::rt::Setup::time_slice
+fn fair_elapsed(elapsed: Duration, priority: Priority, time_slice: Option<Duration>) -> Duration {
+    let overrun = match time_slice {
+        Some(time_slice) => elapsed.saturating_sub(time_slice),
+        None => Duration::ZERO,
+    };
+    heph_sched::fair_runtime(elapsed + overrun, priority)
+}
 
-impl<P: ?Sized> PartialEq for ProcessData<P> {
-    fn eq(&self, other: &Self) -> bool {
-        // FIXME: is this correct?
-        Pin::new(self).id() == Pin::new(other).id()
+impl<P: ?Sized> heph_sched::Schedulable for ProcessData<P> {
+    fn priority(&self) -> Priority {
+        self.priority
     }
-}
 
-impl<P: ?Sized> Ord for ProcessData<P> {
-    fn cmp(&self, other: &Self) -> Ordering {
-        (other.fair_runtime)
-            .cmp(&(self.fair_runtime))
-            .then_with(|| self.priority.cmp(&other.priority))
+    fn deadline(&self) -> Option<Instant> {
+        self.deadline
+    }
+
+    fn take_unaccounted_runtime(mut self: Pin<&mut Self>) -> Duration {
+        take(&mut self.unaccounted_runtime)
     }
 }
 
-impl<P: ?Sized> PartialOrd for ProcessData<P> {
-    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
-        Some(self.cmp(other))
+impl<P: ?Sized> Eq for ProcessData<P> {}
+
+impl<P: ?Sized> PartialEq for ProcessData<P> {
+    fn eq(&self, other: &Self) -> bool {
+        // FIXME: is this correct?
+        Pin::new(self).id() == Pin::new(other).id()
     }
 }
 
@@ -199,7 +525,70 @@ impl<P: Process + ?Sized> fmt::Debug for ProcessData<P> {
             .field("id", &Pin::new(self).id())
             .field("name", &self.process.name())
             .field("priority", &self.priority)
-            .field("fair_runtime", &self.fair_runtime)
+            .field("unaccounted_runtime", &self.unaccounted_runtime)
+            .field("deadline", &self.deadline)
+            .field("cpu_quota", &self.cpu_quota)
             .finish()
     }
 }
+
+/// CPU quota of a process, see [`ActorOptions::with_cpu_quota`].
+///
+/// [`ActorOptions::with_cpu_quota`]: crate::spawn::ActorOptions::with_cpu_quota
+#[derive(Debug)]
+struct CpuQuota {
+    /// Maximum runtime per accounting window.
+    quota: Duration,
+    /// Start of the current accounting window.
+    window_start: Instant,
+    /// Runtime used in the current accounting window.
+    used: Duration,
+    /// End of the accounting window for which a timer was returned by
+    /// [`CpuQuota::throttle_timer`].
+    timer: Option<Instant>,
+}
+
+impl CpuQuota {
+    fn new(quota: Duration) -> CpuQuota {
+        CpuQuota {
+            quota,
+            window_start: Instant::now(),
+            used: Duration::ZERO,
+            timer: None,
+        }
+    }
+
+    /// Returns the end of the current accounting window if the quota is used
+    /// up, `None` otherwise.
+    fn throttled_until(&self, now: Instant) -> Option<Instant> {
+        let window_end = self.window_start + QUOTA_WINDOW;
+        if self.used >= self.quota && now < window_end {
+            Some(window_end)
+        } else {
+            None
+        }
+    }
+
+    /// Same as [`CpuQuota::throttled_until`], but only returns the end of a
+    /// window once.
+    fn throttle_timer(&mut self, now: Instant) -> Option<Instant> {
+        let until = self.throttled_until(now)?;
+        if self.timer == Some(until) {
+            // Timer already added.
+            None
+        } else {
+            self.timer = Some(until);
+            Some(until)
+        }
+    }
+
+    /// Add `elapsed` runtime, of a run started at `start`, to the quota.
+    fn add_runtime(&mut self, start: Instant, elapsed: Duration) {
+        if start >= self.window_start + QUOTA_WINDOW {
+            // Start a new accounting window.
+            self.window_start = start;
+            self.used = Duration::ZERO;
+        }
+        self.used += elapsed;
+    }
+}
@@ -0,0 +1,69 @@
+//! Module containing the run statistics of processes.
+
+use std::time::{Duration, Instant};
+
+/// Run statistics of a single process, e.g. an actor.
+///
+/// Returned by [`RuntimeRef::process_stats`] and
+/// [`RuntimeRef::all_process_stats`]. The statistics are a snapshot, they're
+/// not updated after they're returned. Statistics are only collected if
+/// enabled, see [`Setup::enable_process_stats`].
+///
+/// [`Setup::enable_process_stats`]: crate::rt::Setup::enable_process_stats
+/// [`RuntimeRef::process_stats`]: crate::rt::RuntimeRef::process_stats
+/// [`RuntimeRef::all_process_stats`]: crate::rt::RuntimeRef::all_process_stats
+#[derive(Copy, Clone, Debug)]
+pub struct ProcessStats {
+    name: &'static str,
+    runtime: Duration,
+    polls: u64,
+    messages: u64,
+    last_run: Option<Instant>,
+}
+
+impl ProcessStats {
+    /// Create new statistics for a process with `name`.
+    pub(crate) const fn new(name: &'static str) -> ProcessStats {
+        ProcessStats {
+            name,
+            runtime: Duration::ZERO,
+            polls: 0,
+            messages: 0,
+            last_run: None,
+        }
+    }
+
+    /// Record a single run of the process.
+    pub(crate) fn record_run(&mut self, start: Instant, elapsed: Duration, messages: u64) {
+        self.runtime += elapsed;
+        self.polls += 1;
+        self.messages += messages;
+        self.last_run = Some(start);
+    }
+
+    /// Returns the name of the process, e.g. the name of the actor.
+    pub const fn name(&self) -> &'static str {
+        self.name
+    }
+
+    /// Returns the total time the process has run.
+    pub const fn runtime(&self) -> Duration {
+        self.runtime
+    }
+
+    /// Returns the number of times the process was run (polled).
+    pub const fn polls(&self) -> u64 {
+        self.polls
+    }
+
+    /// Returns the number of messages the actor received, always zero for
+    /// futures.
+    pub const fn messages(&self) -> u64 {
+        self.messages
+    }
+
+    /// Returns the time the process was last run, or `None` if it never ran.
+    pub const fn last_run(&self) -> Option<Instant> {
+        self.last_run
+    }
+}
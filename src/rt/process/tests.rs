@@ -1,24 +1,29 @@
 //! Tests for the process module.
 
-use std::cmp::Ordering;
+use std::any::Any;
 use std::future::{pending, Pending};
 use std::mem::size_of;
 use std::pin::Pin;
 use std::sync::atomic::{self, AtomicBool};
 use std::sync::Arc;
+use std::task::Poll;
 use std::thread::sleep;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 
+use heph_sched::Schedulable;
 use mio::Token;
 
+use crate::actor::messages::{ActorStopped, StopReason};
 use crate::actor::{self, Actor, NewActor};
 use crate::rt::process::{
-    ActorProcess, FutureProcess, Process, ProcessData, ProcessId, ProcessResult,
+    fair_elapsed, ActorProcess, FutureProcess, Process, ProcessData, ProcessId, ProcessResult,
 };
-use crate::rt::{RuntimeRef, ThreadLocal, ThreadSafe};
+use crate::rt::{self, RuntimeRef, ThreadLocal, ThreadSafe};
 use crate::spawn::options::Priority;
 use crate::supervisor::{NoSupervisor, Supervisor, SupervisorStrategy};
-use crate::test::{self, init_local_actor_with_inbox, AssertUnmoved, TEST_PID};
+use crate::test::{
+    self, init_local_actor, init_local_actor_with_inbox, poll_actor, AssertUnmoved, TEST_PID,
+};
 
 #[test]
 fn pid() {
@@ -30,6 +35,22 @@ fn pid() {
     assert_eq!(ProcessId(0).to_string(), "0");
     assert_eq!(ProcessId(100).to_string(), "100");
     assert_eq!(ProcessId(8000).to_string(), "8000");
+
+    assert_eq!("0".parse(), Ok(ProcessId(0)));
+    assert_eq!("8000".parse(), Ok(ProcessId(8000)));
+    assert!("".parse::<ProcessId>().is_err());
+    assert!("-1".parse::<ProcessId>().is_err());
+    assert!("pid".parse::<ProcessId>().is_err());
+}
+
+#[test]
+#[cfg(feature = "record")]
+fn pid_serde() {
+    let pid = ProcessId(123);
+    let json = serde_json::to_string(&pid).unwrap();
+    assert_eq!(json, "123");
+    let got: ProcessId = serde_json::from_str(&json).unwrap();
+    assert_eq!(got, pid);
 }
 
 #[test]
@@ -51,7 +72,7 @@ fn assert_size<T>(expected: usize) {
 fn size_assertions() {
     assert_size::<ProcessId>(8);
     assert_size::<Priority>(1);
-    assert_size::<ProcessData<Box<dyn Process>>>(32);
+    assert_size::<ProcessData<Box<dyn Process>>>(56);
 }
 
 #[derive(Debug)]
@@ -89,42 +110,51 @@ fn process_data_equality() {
 }
 
 #[test]
-fn process_data_ordering() {
-    let mut process1 = ProcessData::new(Priority::HIGH, Box::pin(NopTestProcess));
-    let mut process2 = ProcessData::new(Priority::NORMAL, Box::pin(NopTestProcess));
-    let mut process3 = ProcessData::new(Priority::LOW, Box::pin(NopTestProcess));
-
-    // Ordering only on runtime and priority.
-    assert_eq!(process1.cmp(&process1), Ordering::Equal);
-    assert_eq!(process1.cmp(&process2), Ordering::Greater);
-    assert_eq!(process1.cmp(&process3), Ordering::Greater);
-
-    assert_eq!(process2.cmp(&process1), Ordering::Less);
-    assert_eq!(process2.cmp(&process2), Ordering::Equal);
-    assert_eq!(process2.cmp(&process3), Ordering::Greater);
+fn process_data_schedulable() {
+    let mut process = Box::pin(ProcessData::new(Priority::HIGH, Box::pin(NopTestProcess)));
+    assert_eq!(Schedulable::priority(&*process), Priority::HIGH);
+    assert_eq!(process.deadline(), None);
+
+    let deadline = Instant::now();
+    process.deadline = Some(deadline);
+    assert_eq!(process.deadline(), Some(deadline));
+
+    // The unaccounted runtime is only returned once.
+    process.unaccounted_runtime = Duration::from_millis(10);
+    assert_eq!(
+        process.as_mut().take_unaccounted_runtime(),
+        Duration::from_millis(10)
+    );
+    assert_eq!(process.as_mut().take_unaccounted_runtime(), Duration::ZERO);
+}
 
-    assert_eq!(process3.cmp(&process1), Ordering::Less);
-    assert_eq!(process3.cmp(&process2), Ordering::Less);
-    assert_eq!(process3.cmp(&process3), Ordering::Equal);
+#[derive(Debug)]
+struct DeadlineProcess;
 
-    let duration = Duration::from_millis(0);
-    process1.fair_runtime = duration;
-    process2.fair_runtime = duration;
-    process3.fair_runtime = duration;
+impl Process for DeadlineProcess {
+    fn name(&self) -> &'static str {
+        "DeadlineProcess"
+    }
 
-    // If all the "fair runtimes" are equal we only compare based on the
-    // priority.
-    assert_eq!(process1.cmp(&process1), Ordering::Equal);
-    assert_eq!(process1.cmp(&process2), Ordering::Greater);
-    assert_eq!(process1.cmp(&process3), Ordering::Greater);
+    fn run(self: Pin<&mut Self>, _: &mut RuntimeRef, _: ProcessId) -> ProcessResult {
+        rt::set_deadline(Some(Instant::now()));
+        ProcessResult::Pending
+    }
+}
 
-    assert_eq!(process2.cmp(&process1), Ordering::Less);
-    assert_eq!(process2.cmp(&process2), Ordering::Equal);
-    assert_eq!(process2.cmp(&process3), Ordering::Greater);
+#[test]
+fn process_data_deadline_scheduling_disabled() {
+    let mut process = Box::pin(ProcessData::new(
+        Priority::NORMAL,
+        Box::pin(DeadlineProcess),
+    ));
 
-    assert_eq!(process3.cmp(&process1), Ordering::Less);
-    assert_eq!(process3.cmp(&process2), Ordering::Less);
-    assert_eq!(process3.cmp(&process3), Ordering::Equal);
+    // Deadline scheduling is disabled in the test runtime, so the deadline
+    // should be ignored.
+    let mut runtime_ref = test::runtime();
+    let res = process.as_mut().run(&mut runtime_ref);
+    assert_eq!(res, ProcessResult::Pending);
+    assert_eq!(process.deadline, None);
 }
 
 #[derive(Debug)]
@@ -149,13 +179,60 @@ fn process_data_runtime_increase() {
         Priority::HIGH,
         Box::pin(SleepyProcess(SLEEP_TIME)),
     ));
-    process.fair_runtime = Duration::from_millis(10);
+    process.unaccounted_runtime = Duration::from_millis(10);
 
     // Runtime must increase after running.
     let mut runtime_ref = test::runtime();
     let res = process.as_mut().run(&mut runtime_ref);
     assert_eq!(res, ProcessResult::Pending);
-    assert!(process.fair_runtime >= SLEEP_TIME);
+    assert!(process.unaccounted_runtime >= SLEEP_TIME);
+}
+
+#[test]
+fn process_data_fair_elapsed_time_slice() {
+    const MS: Duration = Duration::from_millis(1);
+    const SLICE: Option<Duration> = Some(Duration::from_millis(10));
+    let tests = [
+        (10 * MS, Priority::NORMAL, None, 10 * MS * Priority::NORMAL),
+        (10 * MS, Priority::HIGH, None, 10 * MS * Priority::HIGH),
+        // Within the time slice.
+        (10 * MS, Priority::NORMAL, SLICE, 10 * MS * Priority::NORMAL),
+        // Over the time slice, the overrun is counted double.
+        (15 * MS, Priority::NORMAL, SLICE, 20 * MS * Priority::NORMAL),
+        (15 * MS, Priority::HIGH, SLICE, 20 * MS * Priority::HIGH),
+    ];
+    for (elapsed, priority, time_slice, expected) in tests {
+        assert_eq!(fair_elapsed(elapsed, priority, time_slice), expected);
+    }
+}
+
+#[test]
+fn process_data_cpu_quota() {
+    const SLEEP_TIME: Duration = Duration::from_millis(10);
+
+    let mut process = Box::pin(
+        ProcessData::new(Priority::NORMAL, Box::pin(SleepyProcess(SLEEP_TIME)))
+            .with_cpu_quota(Some(Duration::from_millis(1))),
+    );
+    assert_eq!(process.as_ref().throttled_until(), None);
+
+    // Running the process once uses up the entire quota.
+    let mut runtime_ref = test::runtime();
+    let res = process.as_mut().run(&mut runtime_ref);
+    assert_eq!(res, ProcessResult::Pending);
+    let unaccounted_runtime = process.unaccounted_runtime;
+    assert!(unaccounted_runtime >= SLEEP_TIME);
+    let until = process.as_ref().throttled_until().unwrap();
+    assert!(until > Instant::now());
+    // Timer should only be returned once.
+    assert_eq!(process.as_mut().throttle_timer(), Some(until));
+
+    // While throttled the process shouldn't run.
+    let res = process.as_mut().run(&mut runtime_ref);
+    assert_eq!(res, ProcessResult::Pending);
+    assert_eq!(process.unaccounted_runtime, unaccounted_runtime);
+    assert_eq!(process.as_ref().throttled_until(), Some(until));
+    assert_eq!(process.as_mut().throttle_timer(), None);
 }
 
 async fn ok_actor(mut ctx: actor::Context<(), ThreadLocal>) {
@@ -184,6 +261,19 @@ fn actor_process() {
     assert_eq!(res, ProcessResult::Complete);
 }
 
+#[test]
+fn actor_process_name() {
+    let new_actor = ok_actor as fn(_) -> _;
+    let (actor, inbox, _) = init_local_actor_with_inbox(new_actor, ()).unwrap();
+    let process = ActorProcess::new(NoSupervisor, new_actor, actor, inbox);
+    assert_eq!(process.name(), "ok_actor");
+
+    let (actor, inbox, _) = init_local_actor_with_inbox(new_actor, ()).unwrap();
+    let process =
+        ActorProcess::new(NoSupervisor, new_actor, actor, inbox).with_name(Some("my_actor"));
+    assert_eq!(process.name(), "my_actor");
+}
+
 async fn error_actor(mut ctx: actor::Context<(), ThreadLocal>, fail: bool) -> Result<(), ()> {
     if fail {
         Err(())
@@ -209,6 +299,155 @@ fn erroneous_actor_process() {
     assert_eq!(res, ProcessResult::Complete);
 }
 
+async fn watcher_actor(mut ctx: actor::Context<ActorStopped, ThreadLocal>, reason: StopReason) {
+    assert_eq!(ctx.receive_next().await, Ok(ActorStopped { reason }));
+}
+
+#[test]
+fn actor_process_watchers() {
+    let new_actor = ok_actor as fn(_) -> _;
+    let (actor, inbox, actor_ref) = init_local_actor_with_inbox(new_actor, ()).unwrap();
+    let mut process = Box::pin(ActorProcess::new(NoSupervisor, new_actor, actor, inbox));
+
+    let watcher_actor = watcher_actor as fn(_, _) -> _;
+    let (watcher, watcher_ref) = init_local_actor(watcher_actor, StopReason::Completed).unwrap();
+    let mut watcher = Box::pin(watcher);
+    watcher_ref.watch(&actor_ref);
+
+    let mut runtime_ref = test::runtime();
+    let res = process.as_mut().run(&mut runtime_ref, TEST_PID);
+    assert_eq!(res, ProcessResult::Pending);
+    // Actor is still running.
+    assert_eq!(poll_actor(watcher.as_mut()), Poll::Pending);
+
+    actor_ref.try_send(()).unwrap();
+    let res = process.as_mut().run(&mut runtime_ref, TEST_PID);
+    assert_eq!(res, ProcessResult::Complete);
+    assert_eq!(poll_actor(watcher.as_mut()), Poll::Ready(Ok(())));
+}
+
+#[test]
+fn erroneous_actor_process_watchers() {
+    let new_actor = error_actor as fn(_, _) -> _;
+    let (actor, inbox, actor_ref) = init_local_actor_with_inbox(new_actor, true).unwrap();
+    let process = ActorProcess::new(|_| SupervisorStrategy::Stop, new_actor, actor, inbox);
+    let mut process = Box::pin(process);
+
+    let watcher_actor = watcher_actor as fn(_, _) -> _;
+    let (watcher, watcher_ref) = init_local_actor(watcher_actor, StopReason::Failed).unwrap();
+    let mut watcher = Box::pin(watcher);
+    watcher_ref.watch(&actor_ref);
+
+    let mut runtime_ref = test::runtime();
+    let res = process.as_mut().run(&mut runtime_ref, TEST_PID);
+    assert_eq!(res, ProcessResult::Complete);
+    assert_eq!(poll_actor(watcher.as_mut()), Poll::Ready(Ok(())));
+}
+
+#[test]
+fn dropped_actor_process_watchers() {
+    let new_actor = ok_actor as fn(_) -> _;
+    let (actor, inbox, actor_ref) = init_local_actor_with_inbox(new_actor, ()).unwrap();
+    let process = ActorProcess::new(NoSupervisor, new_actor, actor, inbox);
+
+    let watcher_actor = watcher_actor as fn(_, _) -> _;
+    let (watcher, watcher_ref) = init_local_actor(watcher_actor, StopReason::Terminated).unwrap();
+    let mut watcher = Box::pin(watcher);
+    watcher_ref.watch(&actor_ref);
+
+    drop(process);
+    assert_eq!(poll_actor(watcher.as_mut()), Poll::Ready(Ok(())));
+}
+
+#[test]
+fn stopped_parent_actor_process_stops_children() {
+    let new_actor = ok_actor as fn(_) -> _;
+    let (parent_actor, parent_inbox, parent_ref) =
+        init_local_actor_with_inbox(new_actor, ()).unwrap();
+    let (actor, child_inbox, child_ref) = init_local_actor_with_inbox(new_actor, ()).unwrap();
+    parent_inbox.link_child(&child_inbox);
+    let parent = ActorProcess::new(NoSupervisor, new_actor, parent_actor, parent_inbox);
+    let mut parent = Box::pin(parent);
+    let child = ActorProcess::new(NoSupervisor, new_actor, actor, child_inbox);
+    let mut child = Box::pin(child);
+
+    let watcher_actor = watcher_actor as fn(_, _) -> _;
+    let (watcher, watcher_ref) = init_local_actor(watcher_actor, StopReason::Terminated).unwrap();
+    let mut watcher = Box::pin(watcher);
+    watcher_ref.watch(&child_ref);
+
+    let mut runtime_ref = test::runtime();
+    let res = child.as_mut().run(&mut runtime_ref, TEST_PID);
+    assert_eq!(res, ProcessResult::Pending);
+
+    // Once the parent stops the child should stop as well, without receiving
+    // a message.
+    parent_ref.try_send(()).unwrap();
+    let res = parent.as_mut().run(&mut runtime_ref, TEST_PID);
+    assert_eq!(res, ProcessResult::Complete);
+    let res = child.as_mut().run(&mut runtime_ref, TEST_PID);
+    assert_eq!(res, ProcessResult::Complete);
+    assert_eq!(poll_actor(watcher.as_mut()), Poll::Ready(Ok(())));
+}
+
+#[test]
+fn escalate_to_parent_actor_process() {
+    struct TestSupervisor(Arc<AtomicBool>);
+
+    impl<NA> Supervisor<NA> for TestSupervisor
+    where
+        NA: NewActor,
+    {
+        fn decide(&mut self, _: <NA::Actor as Actor>::Error) -> SupervisorStrategy<NA::Argument> {
+            unreachable!("test call to decide in ActorProcess");
+        }
+
+        fn decide_on_restart_error(&mut self, _: NA::Error) -> SupervisorStrategy<NA::Argument> {
+            unreachable!("test call to decide_on_restart_error in ActorProcess");
+        }
+
+        fn second_restart_error(&mut self, _: NA::Error) {
+            unreachable!("test call to second_restart_error in ActorProcess");
+        }
+
+        fn decide_on_escalation(&mut self) -> SupervisorStrategy<NA::Argument> {
+            self.0.store(true, atomic::Ordering::SeqCst);
+            SupervisorStrategy::Stop
+        }
+    }
+
+    let supervisor_called = Arc::new(AtomicBool::new(false));
+    let supervisor = TestSupervisor(Arc::clone(&supervisor_called));
+
+    let new_parent = ok_actor as fn(_) -> _;
+    let (actor, parent_inbox, _) = init_local_actor_with_inbox(new_parent, ()).unwrap();
+    let new_child = error_actor as fn(_, _) -> _;
+    let (child_actor, child_inbox, _) = init_local_actor_with_inbox(new_child, true).unwrap();
+    parent_inbox.link_child(&child_inbox);
+
+    let parent = ActorProcess::new(supervisor, new_parent, actor, parent_inbox);
+    let mut parent = Box::pin(parent);
+    let child = ActorProcess::new(
+        |_| SupervisorStrategy::Escalate,
+        new_child,
+        child_actor,
+        child_inbox,
+    );
+    let mut child = Box::pin(child);
+
+    let mut runtime_ref = test::runtime();
+    let res = parent.as_mut().run(&mut runtime_ref, TEST_PID);
+    assert_eq!(res, ProcessResult::Pending);
+    assert!(!supervisor_called.load(atomic::Ordering::SeqCst));
+
+    // The child fails, escalating the error to the parent's supervisor.
+    let res = child.as_mut().run(&mut runtime_ref, TEST_PID);
+    assert_eq!(res, ProcessResult::Complete);
+    let res = parent.as_mut().run(&mut runtime_ref, TEST_PID);
+    assert_eq!(res, ProcessResult::Complete);
+    assert!(supervisor_called.load(atomic::Ordering::SeqCst));
+}
+
 #[test]
 fn restarting_erroneous_actor_process() {
     struct TestSupervisor(Arc<AtomicBool>);
@@ -258,6 +497,170 @@ fn restarting_erroneous_actor_process() {
     assert_eq!(res, ProcessResult::Complete);
 }
 
+#[test]
+fn delayed_restart_erroneous_actor_process() {
+    const DELAY: Duration = Duration::from_millis(20);
+
+    let new_actor = error_actor as fn(_, _) -> _;
+    let (actor, inbox, actor_ref) = init_local_actor_with_inbox(new_actor, true).unwrap();
+    let supervisor = |_: ()| SupervisorStrategy::RestartAfter(false, DELAY);
+    let process = ActorProcess::new(supervisor, new_actor, actor, inbox);
+    let mut process: Pin<Box<dyn Process>> = Box::pin(process);
+
+    // The actor returns an error, but shouldn't be restarted yet.
+    let mut runtime_ref = test::runtime();
+    let start = Instant::now();
+    let res = process.as_mut().run(&mut runtime_ref, ProcessId(0));
+    assert_eq!(res, ProcessResult::Pending);
+
+    // Receiving a message should not restart (or run) the actor.
+    actor_ref.try_send(()).unwrap();
+    let res = process.as_mut().run(&mut runtime_ref, ProcessId(0));
+    assert_eq!(res, ProcessResult::Pending);
+
+    // After the delay the actor should be restarted and receive the message.
+    sleep(DELAY);
+    let res = process.as_mut().run(&mut runtime_ref, ProcessId(0));
+    assert_eq!(res, ProcessResult::Complete);
+    assert!(start.elapsed() >= DELAY);
+}
+
+/// Sets the flag once dropped.
+struct DropFlag(Arc<AtomicBool>);
+
+impl Drop for DropFlag {
+    fn drop(&mut self) {
+        self.0.store(true, atomic::Ordering::SeqCst);
+    }
+}
+
+async fn resource_actor(
+    mut ctx: actor::Context<(), ThreadLocal>,
+    dropped: Arc<AtomicBool>,
+    fail: bool,
+) -> Result<(), ()> {
+    if fail {
+        ctx.register_resource(DropFlag(dropped));
+        return Err(());
+    }
+    // The resource of the failed actor must be dropped before we're restarted.
+    assert!(dropped.load(atomic::Ordering::SeqCst));
+    Ok(())
+}
+
+#[test]
+fn registered_resources_dropped_before_restart() {
+    let dropped = Arc::new(AtomicBool::new(false));
+    let new_actor = resource_actor as fn(_, _, _) -> _;
+    let arg = (dropped.clone(), true);
+    let (actor, inbox, _) = init_local_actor_with_inbox(new_actor, arg).unwrap();
+    let supervisor_dropped = dropped.clone();
+    let supervisor = move |()| {
+        // Not yet dropped when the supervisor is called.
+        assert!(!supervisor_dropped.load(atomic::Ordering::SeqCst));
+        SupervisorStrategy::Restart((supervisor_dropped.clone(), false))
+    };
+    let process = ActorProcess::new(supervisor, new_actor, actor, inbox);
+    let mut process: Pin<Box<dyn Process>> = Box::pin(process);
+
+    let mut runtime_ref = test::runtime();
+    let res = process.as_mut().run(&mut runtime_ref, ProcessId(0));
+    assert_eq!(res, ProcessResult::Complete);
+    assert!(dropped.load(atomic::Ordering::SeqCst));
+}
+
+#[test]
+fn registered_resources_dropped_on_stop() {
+    let dropped = Arc::new(AtomicBool::new(false));
+    let new_actor = resource_actor as fn(_, _, _) -> _;
+    let arg = (dropped.clone(), true);
+    let (actor, inbox, _) = init_local_actor_with_inbox(new_actor, arg).unwrap();
+    let process = ActorProcess::new(|()| SupervisorStrategy::Stop, new_actor, actor, inbox);
+    let mut process: Pin<Box<dyn Process>> = Box::pin(process);
+
+    let mut runtime_ref = test::runtime();
+    let res = process.as_mut().run(&mut runtime_ref, ProcessId(0));
+    assert_eq!(res, ProcessResult::Complete);
+    assert!(dropped.load(atomic::Ordering::SeqCst));
+}
+
+async fn panic_actor(mut ctx: actor::Context<(), ThreadLocal>, panic: bool) {
+    if panic {
+        panic!("oops");
+    }
+    assert_eq!(ctx.receive_next().await, Ok(()));
+}
+
+#[test]
+fn restarting_panicking_actor_process() {
+    struct TestSupervisor(Arc<AtomicBool>);
+
+    impl<NA> Supervisor<NA> for TestSupervisor
+    where
+        NA: NewActor<Argument = bool>,
+    {
+        fn decide(&mut self, _: <NA::Actor as Actor>::Error) -> SupervisorStrategy<NA::Argument> {
+            unreachable!("test call to decide in ActorProcess");
+        }
+
+        fn decide_on_restart_error(&mut self, _: NA::Error) -> SupervisorStrategy<NA::Argument> {
+            unreachable!("test call to decide_on_restart_error in ActorProcess");
+        }
+
+        fn second_restart_error(&mut self, _: NA::Error) {
+            unreachable!("test call to second_restart_error in ActorProcess");
+        }
+
+        fn decide_on_panic(
+            &mut self,
+            panic: Box<dyn Any + Send + 'static>,
+        ) -> SupervisorStrategy<NA::Argument> {
+            assert_eq!(panic.downcast_ref::<&'static str>(), Some(&"oops"));
+            self.0.store(true, atomic::Ordering::SeqCst);
+            SupervisorStrategy::Restart(false)
+        }
+    }
+
+    let new_actor = panic_actor as fn(_, _) -> _;
+    let (actor, inbox, actor_ref) = init_local_actor_with_inbox(new_actor, true).unwrap();
+
+    let supervisor_called = Arc::new(AtomicBool::new(false));
+    let supervisor = TestSupervisor(Arc::clone(&supervisor_called));
+
+    let process = ActorProcess::new(supervisor, new_actor, actor, inbox).with_catch_panics(true);
+    let mut process: Pin<Box<dyn Process>> = Box::pin(process);
+
+    // The actor panics, the supervisor restarts it, after which the actor
+    // waits for a message.
+    let mut runtime_ref = test::runtime();
+    let res = process.as_mut().run(&mut runtime_ref, ProcessId(0));
+    assert_eq!(res, ProcessResult::Pending);
+    assert!(supervisor_called.load(atomic::Ordering::SeqCst));
+
+    actor_ref.try_send(()).unwrap();
+    let res = process.as_mut().run(&mut runtime_ref, ProcessId(0));
+    assert_eq!(res, ProcessResult::Complete);
+}
+
+#[test]
+fn stopping_panicking_actor_process() {
+    let new_actor = panic_actor as fn(_, _) -> _;
+    let (actor, inbox, actor_ref) = init_local_actor_with_inbox(new_actor, true).unwrap();
+    let process = ActorProcess::new(NoSupervisor, new_actor, actor, inbox).with_catch_panics(true);
+    let mut process = Box::pin(process);
+
+    let watcher_actor = watcher_actor as fn(_, _) -> _;
+    let (watcher, watcher_ref) = init_local_actor(watcher_actor, StopReason::Panicked).unwrap();
+    let mut watcher = Box::pin(watcher);
+    watcher_ref.watch(&actor_ref);
+
+    // By default the supervisor stops the actor.
+    let mut runtime_ref = test::runtime();
+    let res = process.as_mut().run(&mut runtime_ref, TEST_PID);
+    assert_eq!(res, ProcessResult::Complete);
+    assert_eq!(poll_actor(watcher.as_mut()), Poll::Ready(Ok(())));
+}
+
 struct TestAssertUnmovedNewActor;
 
 impl NewActor for TestAssertUnmovedNewActor {
@@ -295,7 +698,7 @@ fn actor_process_assert_actor_unmoved() {
 
 #[test]
 fn future_process_thread_local_assert_future_unmoved() {
-    let process = FutureProcess::<_, ThreadLocal>::new(AssertUnmoved::new(pending()));
+    let (process, _) = FutureProcess::<_, ThreadLocal>::new(AssertUnmoved::new(pending()));
     let mut process: Pin<Box<dyn Process>> = Box::pin(process);
 
     // All we do is run it a couple of times, it should panic if the actor is
@@ -311,7 +714,7 @@ fn future_process_thread_local_assert_future_unmoved() {
 
 #[test]
 fn future_process_thread_safe_assert_future_unmoved() {
-    let process = FutureProcess::<_, ThreadSafe>::new(AssertUnmoved::new(pending()));
+    let (process, _) = FutureProcess::<_, ThreadSafe>::new(AssertUnmoved::new(pending()));
     let mut process: Pin<Box<dyn Process>> = Box::pin(process);
 
     // All we do is run it a couple of times, it should panic if the actor is
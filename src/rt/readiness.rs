@@ -0,0 +1,117 @@
+//! Module with the readiness state of the runtime.
+//!
+//! Actors spawned with [`ActorOptions::require_readiness`] must report they're
+//! ready using [`actor::Context::report_ready`]. Until all of these actors did
+//! so the runtime isn't considered ready, see [`RuntimeRef::is_ready`] and
+//! [`actor::Context::wait_ready`].
+//!
+//! [`ActorOptions::require_readiness`]: crate::spawn::ActorOptions::require_readiness
+//! [`actor::Context::report_ready`]: crate::actor::Context::report_ready
+//! [`RuntimeRef::is_ready`]: crate::rt::RuntimeRef::is_ready
+//! [`actor::Context::wait_ready`]: crate::actor::Context::wait_ready
+
+use std::collections::HashSet;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Mutex;
+use std::task::{self, Poll};
+use std::time::Instant;
+
+use log::debug;
+
+use crate::rt::{self, ProcessId};
+use crate::timer::DeadlinePassed;
+
+/// Readiness state of the runtime.
+#[derive(Debug, Default)]
+pub(crate) struct Readiness {
+    /// Processes that didn't yet report they're ready.
+    pending: Mutex<HashSet<ProcessId>>,
+    /// Wakers of the [`WaitReady`] futures waiting for the runtime to become
+    /// ready.
+    wakers: Mutex<Vec<task::Waker>>,
+}
+
+impl Readiness {
+    /// Require the process with `pid` to report it's ready.
+    pub(crate) fn require(&self, pid: ProcessId) {
+        let _ = self.pending.lock().unwrap().insert(pid);
+    }
+
+    /// Mark the process with `pid` as ready.
+    ///
+    /// Does nothing if the process isn't required to report it's ready or
+    /// already did so.
+    pub(crate) fn report_ready(&self, pid: ProcessId) {
+        let is_ready = {
+            let mut pending = self.pending.lock().unwrap();
+            pending.remove(&pid) && pending.is_empty()
+        };
+        if is_ready {
+            debug!("runtime is ready");
+            for waker in self.wakers.lock().unwrap().drain(..) {
+                waker.wake();
+            }
+        }
+    }
+
+    /// Returns `true` if all processes required to report they're ready did
+    /// so.
+    pub(crate) fn is_ready(&self) -> bool {
+        self.pending.lock().unwrap().is_empty()
+    }
+
+    /// Returns `true` if the runtime is ready, otherwise `waker` is woken once
+    /// it is.
+    pub(crate) fn poll_ready(&self, waker: &task::Waker) -> bool {
+        // NOTE: need to hold the lock on `pending` while registering the waker
+        // so we don't miss a wake-up from `report_ready`.
+        let pending = self.pending.lock().unwrap();
+        if pending.is_empty() {
+            return true;
+        }
+        let mut wakers = self.wakers.lock().unwrap();
+        if !wakers.iter().any(|w| w.will_wake(waker)) {
+            wakers.push(waker.clone());
+        }
+        false
+    }
+}
+
+/// [`Future`] behind [`actor::Context::wait_ready`].
+///
+/// [`actor::Context::wait_ready`]: crate::actor::Context::wait_ready
+#[derive(Debug)]
+#[must_use = "futures do nothing unless you `.await` or poll them"]
+pub struct WaitReady<RT: rt::Access> {
+    deadline: Instant,
+    rt: RT,
+}
+
+impl<RT: rt::Access> WaitReady<RT> {
+    /// Create a new `WaitReady` future.
+    pub(crate) fn new(mut rt: RT, deadline: Instant) -> WaitReady<RT> {
+        rt.add_deadline(deadline);
+        WaitReady { deadline, rt }
+    }
+}
+
+impl<RT: rt::Access> Future for WaitReady<RT> {
+    type Output = Result<(), DeadlinePassed>;
+
+    fn poll(self: Pin<&mut Self>, ctx: &mut task::Context<'_>) -> Poll<Self::Output> {
+        if self.rt.poll_ready(ctx.waker()) {
+            Poll::Ready(Ok(()))
+        } else if self.deadline <= Instant::now() {
+            Poll::Ready(Err(DeadlinePassed))
+        } else {
+            Poll::Pending
+        }
+    }
+}
+
+impl<RT: rt::Access> Drop for WaitReady<RT> {
+    fn drop(&mut self) {
+        self.rt.remove_deadline(self.deadline);
+    }
+}
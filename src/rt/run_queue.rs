@@ -0,0 +1,13 @@
+//! Module with the run queue used by the schedulers.
+//!
+//! The run queue is implemented in the `heph-sched` crate, see
+//! [`heph_sched::RunQueue`] for how the next process to run is selected.
+//! Processes with a deadline (see [`rt::Setup::enable_deadline_scheduling`])
+//! are always run before any process without one.
+//!
+//! [`rt::Setup::enable_deadline_scheduling`]: crate::rt::Setup::enable_deadline_scheduling
+
+use crate::rt::process::ProcessData;
+
+/// Processes that are ready to run.
+pub(crate) type RunQueue<P> = heph_sched::RunQueue<ProcessData<P>>;
@@ -2,15 +2,20 @@
 //!
 //! [`rt::Setup`]: Setup
 
+use std::net::SocketAddr;
 use std::num::NonZeroUsize;
 use std::path::Path;
+use std::time::Duration;
 use std::{env, io, thread};
 
 use log::{debug, warn};
 
 use crate::actor_ref::ActorGroup;
+use crate::metrics;
 use crate::rt::coordinator::Coordinator;
+use crate::rt::local::EventLoopConfig;
 use crate::rt::{worker, Error, Runtime, Worker, MAX_THREADS};
+use crate::spawn::ActorOptions;
 use crate::trace;
 
 /// Setup a [`Runtime`].
@@ -27,8 +32,20 @@ pub struct Setup {
     threads: usize,
     /// Whether or not to automatically set CPU affinity.
     auto_cpu_affinity: bool,
+    /// Whether or not to use earliest-deadline-first scheduling.
+    deadline_scheduling: bool,
+    /// Whether or not to collect run statistics of processes.
+    process_stats: bool,
+    /// See [`Setup::time_slice`].
+    time_slice: Option<Duration>,
+    /// See [`Setup::default_actor_options`].
+    default_actor_options: ActorOptions,
+    /// Configuration of the event loop of the worker threads.
+    event_loop: EventLoopConfig,
     /// Optional trace log.
     trace_log: Option<trace::CoordinatorLog>,
+    /// Address to run the metrics exporter on, if any.
+    metrics_exporter: Option<SocketAddr>,
 }
 
 impl Setup {
@@ -38,7 +55,13 @@ impl Setup {
             name: None,
             threads: 1,
             auto_cpu_affinity: false,
+            deadline_scheduling: false,
+            process_stats: false,
+            time_slice: None,
+            default_actor_options: ActorOptions::DEFAULT,
+            event_loop: EventLoopConfig::DEFAULT,
             trace_log: None,
+            metrics_exporter: None,
         }
     }
 
@@ -126,6 +149,144 @@ impl Setup {
         self
     }
 
+    /// Enable earliest-deadline-first (EDF) scheduling.
+    ///
+    /// By default processes are scheduled based on their fair runtime, that is
+    /// the time spent running multiplied by their [`Priority`]. With this
+    /// enabled actors can declare a deadline for their current work using
+    /// [`actor::Context::set_deadline`]. Processes with a deadline are run
+    /// before any process without one, earliest deadline first. Processes
+    /// without a deadline are still scheduled based on their fair runtime.
+    ///
+    /// This is useful for soft-realtime workloads, where some work must be
+    /// done before a certain point in time.
+    ///
+    /// [`Priority`]: crate::spawn::options::Priority
+    /// [`actor::Context::set_deadline`]: crate::actor::Context::set_deadline
+    ///
+    /// # Notes
+    ///
+    /// Processes with a deadline always run before processes without one, an
+    /// actor that never clears its deadline (using
+    /// [`actor::Context::clear_deadline`]) can starve all other processes.
+    ///
+    /// [`actor::Context::clear_deadline`]: crate::actor::Context::clear_deadline
+    pub const fn enable_deadline_scheduling(mut self) -> Self {
+        self.deadline_scheduling = true;
+        self
+    }
+
+    /// Collect run statistics of all processes.
+    ///
+    /// By default no statistics are collected and
+    /// [`RuntimeRef::process_stats`] and [`RuntimeRef::all_process_stats`]
+    /// don't return any statistics. With this enabled the statistics of a
+    /// process are updated each time it's run, which adds a small cost to
+    /// running processes.
+    ///
+    /// [`RuntimeRef::process_stats`]: crate::rt::RuntimeRef::process_stats
+    /// [`RuntimeRef::all_process_stats`]: crate::rt::RuntimeRef::all_process_stats
+    pub const fn enable_process_stats(mut self) -> Self {
+        self.process_stats = true;
+        self
+    }
+
+    /// Set the maximum time a process should run before returning control to
+    /// the runtime.
+    ///
+    /// Processes are run cooperatively, a process that runs for a long time
+    /// (e.g. by doing blocking I/O or a lot of computation in a single poll)
+    /// blocks all other processes on the same worker thread. When a process
+    /// runs longer than `time_slice` a warning is logged and the process is
+    /// deprioritised: the time it ran past `time_slice` is counted double in
+    /// its fair runtime, moving it further back in the run queue.
+    ///
+    /// Defaults to no time slice, in which case long running processes are
+    /// only deprioritised based on their fair runtime.
+    ///
+    /// # Notes
+    ///
+    /// The runtime can't preempt processes, this only changes how the process
+    /// is scheduled *after* it returns.
+    pub const fn time_slice(mut self, time_slice: Duration) -> Self {
+        self.time_slice = Some(time_slice);
+        self
+    }
+
+    /// Set the default options for all actors spawned in the runtime.
+    ///
+    /// The priority, CPU quota and catching of panics set in `options` are
+    /// inherited by all actors, both thread-local and thread-safe, that don't
+    /// set the option themselves when being spawned. Other options, such as
+    /// the name, are not inherited.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::time::Duration;
+    ///
+    /// use heph::rt::{self, Runtime};
+    /// use heph::spawn::options::{ActorOptions, Priority};
+    ///
+    /// fn main() -> Result<(), rt::Error> {
+    ///     // By default all actors catch panics and use at most 100
+    ///     // milliseconds of runtime per second.
+    ///     let defaults = ActorOptions::default()
+    ///         .catch_panics()
+    ///         .with_cpu_quota(Duration::from_millis(100));
+    ///     let mut runtime = Runtime::setup().default_actor_options(defaults).build()?;
+    ///
+    ///     // This actor has a high priority, but still inherits the CPU quota and
+    ///     // catches panics.
+    ///     let options = ActorOptions::default().with_priority(Priority::HIGH);
+    ///     # drop(options);
+    ///     # runtime.start()?;
+    ///     Ok(())
+    /// }
+    /// ```
+    pub const fn default_actor_options(mut self, options: ActorOptions) -> Self {
+        self.default_actor_options = options;
+        self
+    }
+
+    /// Set the maximum number of OS events a worker thread handles per poll.
+    ///
+    /// This is the capacity of the buffer the events are stored in, allocated
+    /// once per worker thread. Events that don't fit in the buffer are returned
+    /// in the next poll. Defaults to 128.
+    pub const fn event_capacity(mut self, capacity: usize) -> Self {
+        assert!(capacity != 0, "Can't use an event capacity of zero");
+        self.event_loop.event_capacity = capacity;
+        self
+    }
+
+    /// Busy poll for OS events for at most `duration` before blocking.
+    ///
+    /// When a worker thread has no processes ready to run it blocks while
+    /// polling for OS events, e.g. a readable socket. With this set the worker
+    /// thread first polls in a busy loop for `duration`, which reduces the
+    /// latency of handling events as the thread doesn't have to be woken up
+    /// by the OS, at the cost of using more CPU time.
+    ///
+    /// Defaults to no busy polling.
+    pub const fn busy_poll(mut self, duration: Duration) -> Self {
+        self.event_loop.busy_poll = duration;
+        self
+    }
+
+    /// Set the maximum number of processes a worker thread runs in between
+    /// polling for events.
+    ///
+    /// A lower number means events are handled sooner, making the runtime
+    /// more responsive, but also means more time is spent polling rather than
+    /// running processes. Note that if no processes are ready to run the
+    /// worker thread will poll sooner. Defaults to 32.
+    pub const fn max_processes_per_poll(mut self, n: usize) -> Self {
+        assert!(n != 0, "Can't run zero processes in between polls");
+        self.event_loop.max_processes_per_poll = n;
+        self
+    }
+
     /// Generate a trace of the runtime, writing it to the file specified by
     /// `path`.
     ///
@@ -143,13 +304,32 @@ impl Setup {
         }
     }
 
+    /// Serve the metrics of the runtime over HTTP on `address`, in the
+    /// Prometheus text exposition format.
+    ///
+    /// This spawns the [`metrics::http_exporter`] actor as thread-safe actor
+    /// once the runtime is build. See the [`mod@metrics`] module for the
+    /// exported metrics.
+    ///
+    /// # Notes
+    ///
+    /// The exporter runs until the runtime is stopped, this means the runtime
+    /// will not stop on its own once all other actors are done. It will still
+    /// stop on process signals, see [`Signal`].
+    ///
+    /// [`Signal`]: crate::rt::Signal
+    pub const fn enable_metrics_exporter(mut self, address: SocketAddr) -> Self {
+        self.metrics_exporter = Some(address);
+        self
+    }
+
     /// Build the runtime.
     ///
     /// This will spawn a number of worker threads (see [`Setup::num_threads`])
     /// to run all the actors.
     pub fn build(self) -> Result<Runtime, Error> {
         #[rustfmt::skip]
-        let Setup { name, threads, auto_cpu_affinity, mut trace_log } = self;
+        let Setup { name, threads, auto_cpu_affinity, deadline_scheduling, process_stats, time_slice, default_actor_options, event_loop, mut trace_log, metrics_exporter } = self;
         let name = name.unwrap_or_else(default_app_name).into_boxed_str();
         debug!(
             "building Heph runtime: name={}, worker_threads={}",
@@ -171,8 +351,14 @@ impl Setup {
         // Create the coordinator to oversee all workers.
         let thread_wakers = thread_wakers.into_boxed_slice();
         let shared_trace_log = trace_log.as_ref().map(trace::CoordinatorLog::clone_shared);
-        let coordinator = Coordinator::init(name, thread_wakers, shared_trace_log)
-            .map_err(Error::init_coordinator)?;
+        let coordinator = Coordinator::init(
+            name,
+            thread_wakers,
+            shared_trace_log,
+            default_actor_options,
+            process_stats,
+        )
+        .map_err(Error::init_coordinator)?;
 
         // Spawn the worker threads.
         let workers = worker_setups
@@ -185,6 +371,9 @@ impl Setup {
                 worker_setup.start(
                     coordinator.shared_internals().clone(),
                     auto_cpu_affinity,
+                    deadline_scheduling,
+                    time_slice,
+                    event_loop,
                     trace_log,
                 )
             })
@@ -198,13 +387,21 @@ impl Setup {
             &[("amount", &threads)],
         );
 
-        Ok(Runtime {
+        let mut runtime = Runtime {
             coordinator,
             workers,
             sync_actors: Vec::new(),
             signals: ActorGroup::empty(),
             trace_log,
-        })
+        };
+
+        if let Some(address) = metrics_exporter {
+            let exporter = metrics::http_exporter as fn(_, _) -> _;
+            let supervisor = metrics::supervisor as fn(_) -> _;
+            let _ = runtime.spawn(supervisor, exporter, address, ActorOptions::default());
+        }
+
+        Ok(runtime)
     }
 }
 
@@ -2,22 +2,26 @@
 
 use std::cmp::min;
 use std::future::Future;
+use std::num::NonZeroUsize;
 use std::os::unix::io::AsRawFd;
 use std::pin::Pin;
-use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
 use std::sync::{Arc, Mutex, TryLockError};
 use std::time::{Duration, Instant};
 use std::{io, task};
 
-use heph_inbox as inbox;
 use log::{debug, error, trace};
 use mio::unix::SourceFd;
 use mio::{event, Events, Interest, Poll, Registry, Token};
 
+use crate::actor::inbox::Manager;
 use crate::actor::{self, NewActor};
 use crate::actor_ref::ActorRef;
+use crate::bus::Bus;
+use crate::config::Configs;
+use crate::rt::readiness::Readiness;
 use crate::rt::thread_waker::ThreadWaker;
-use crate::rt::{ProcessId, ThreadSafe};
+use crate::rt::{JoinHandle, ProcessId, ProcessStats, ThreadSafe};
 use crate::spawn::{ActorOptions, AddActorError, FutureOptions};
 use crate::supervisor::Supervisor;
 use crate::trace;
@@ -43,9 +47,29 @@ use waker::WakerId;
 pub(crate) struct RuntimeSetup {
     poll: Poll,
     registry: Registry,
+    default_actor_options: ActorOptions,
+    process_stats: bool,
 }
 
 impl RuntimeSetup {
+    /// Set the default options for all actors, see
+    /// [`Setup::default_actor_options`].
+    ///
+    /// [`Setup::default_actor_options`]: crate::rt::Setup::default_actor_options
+    pub(crate) fn with_default_actor_options(mut self, options: ActorOptions) -> RuntimeSetup {
+        self.default_actor_options = options;
+        self
+    }
+
+    /// Collect run statistics of processes, see
+    /// [`Setup::enable_process_stats`].
+    ///
+    /// [`Setup::enable_process_stats`]: crate::rt::Setup::enable_process_stats
+    pub(crate) fn with_process_stats(mut self, enabled: bool) -> RuntimeSetup {
+        self.process_stats = enabled;
+        self
+    }
+
     /// Complete the runtime setup.
     pub(crate) fn complete(
         self,
@@ -55,14 +79,24 @@ impl RuntimeSetup {
     ) -> RuntimeInternals {
         // Needed by `RuntimeInternals::wake_workers`.
         debug_assert!(worker_wakers.len() >= 1);
+        let worker_metrics = worker_wakers
+            .iter()
+            .map(|_| WorkerMetrics::default())
+            .collect();
         RuntimeInternals {
             shared_id,
+            start: Instant::now(),
+            worker_metrics,
             worker_wakers,
             wake_worker_idx: AtomicUsize::new(0),
             poll: Mutex::new(self.poll),
             registry: self.registry,
-            scheduler: Scheduler::new(),
+            scheduler: Scheduler::new(self.process_stats),
             timers: Timers::new(),
+            readiness: Readiness::default(),
+            bus: Bus::new(),
+            config: Configs::new(),
+            default_actor_options: self.default_actor_options,
             trace_log,
         }
     }
@@ -73,6 +107,11 @@ impl RuntimeSetup {
 pub(crate) struct RuntimeInternals {
     /// Waker id used to create [`task::Waker`]s for thread-safe actors.
     shared_id: WakerId,
+    /// Start time of the runtime, used to calculate the uptime.
+    start: Instant,
+    /// Metrics published by the workers, indexed by worker id minus one, see
+    /// [`RuntimeInternals::publish_worker_metrics`].
+    worker_metrics: Box<[WorkerMetrics]>,
     /// Thread wakers for all the workers.
     worker_wakers: Box<[&'static ThreadWaker]>,
     /// Index into `worker_wakers` to wake next, see
@@ -87,6 +126,14 @@ pub(crate) struct RuntimeInternals {
     scheduler: Scheduler,
     /// Timers for thread-safe actors.
     timers: Timers,
+    /// Readiness state of the runtime, shared by all actors.
+    readiness: Readiness,
+    /// Event bus of the runtime, shared by all actors.
+    bus: Bus,
+    /// Configuration of the runtime, shared by all actors.
+    config: Configs,
+    /// Default options for actors, inherited by all spawned actors.
+    default_actor_options: ActorOptions,
     /// Shared trace log.
     ///
     /// # Notes
@@ -100,8 +147,30 @@ pub(crate) struct RuntimeInternals {
 #[derive(Debug)]
 #[allow(dead_code)] // https://github.com/rust-lang/rust/issues/88900.
 pub(crate) struct Metrics {
-    scheduler: scheduler::Metrics,
-    timers: timers::Metrics,
+    pub(crate) scheduler: scheduler::Metrics,
+    pub(crate) timers: timers::Metrics,
+}
+
+/// Metrics published by a worker thread, see
+/// [`RuntimeInternals::publish_worker_metrics`].
+#[derive(Debug, Default)]
+pub(crate) struct WorkerMetrics {
+    /// Number of thread-local processes ready to run.
+    ready_processes: AtomicUsize,
+    /// Total number of processes (thread-local and thread-safe) run.
+    processes_run: AtomicU64,
+}
+
+impl WorkerMetrics {
+    /// Returns the number of thread-local processes ready to run.
+    pub(crate) fn ready_processes(&self) -> usize {
+        self.ready_processes.load(Ordering::Relaxed)
+    }
+
+    /// Returns the total number of processes run.
+    pub(crate) fn processes_run(&self) -> u64 {
+        self.processes_run.load(Ordering::Relaxed)
+    }
 }
 
 impl RuntimeInternals {
@@ -109,7 +178,33 @@ impl RuntimeInternals {
     pub(crate) fn setup() -> io::Result<RuntimeSetup> {
         let poll = Poll::new()?;
         let registry = poll.registry().try_clone()?;
-        Ok(RuntimeSetup { poll, registry })
+        Ok(RuntimeSetup {
+            poll,
+            registry,
+            default_actor_options: ActorOptions::default(),
+            process_stats: false,
+        })
+    }
+
+    /// Returns the readiness state of the runtime.
+    pub(crate) const fn readiness(&self) -> &Readiness {
+        &self.readiness
+    }
+
+    /// Returns the event bus of the runtime.
+    pub(crate) const fn bus(&self) -> &Bus {
+        &self.bus
+    }
+
+    /// Returns the configuration of the runtime.
+    pub(crate) const fn config(&self) -> &Configs {
+        &self.config
+    }
+
+    /// Returns the default options for actors, see
+    /// [`ActorOptions::inherit`].
+    pub(crate) const fn default_actor_options(&self) -> &ActorOptions {
+        &self.default_actor_options
     }
 
     /// Gather metrics about the shared runtime state.
@@ -120,6 +215,37 @@ impl RuntimeInternals {
         }
     }
 
+    /// Returns the time since the runtime was started.
+    pub(crate) fn uptime(&self) -> Duration {
+        self.start.elapsed()
+    }
+
+    /// Returns the metrics published by the workers, the first being worker
+    /// one.
+    pub(crate) fn worker_metrics(&self) -> &[WorkerMetrics] {
+        &self.worker_metrics
+    }
+
+    /// Publish the metrics of worker with `id`, which has `ready_processes`
+    /// thread-local processes ready to run and ran `processes_run` processes
+    /// since the last call.
+    pub(crate) fn publish_worker_metrics(
+        &self,
+        id: NonZeroUsize,
+        ready_processes: usize,
+        processes_run: usize,
+    ) {
+        // NOTE: the test runtime uses an id that is out of bounds.
+        if let Some(metrics) = self.worker_metrics.get(id.get() - 1) {
+            metrics
+                .ready_processes
+                .store(ready_processes, Ordering::Relaxed);
+            let _ = metrics
+                .processes_run
+                .fetch_add(processes_run as u64, Ordering::Relaxed);
+        }
+    }
+
     /// Returns a new [`task::Waker`] for the thread-safe actor with `pid`.
     pub(crate) fn new_task_waker(&self, pid: ProcessId) -> task::Waker {
         waker::new(self.shared_id, pid)
@@ -227,17 +353,27 @@ impl RuntimeInternals {
         NA::Actor: Send + Sync + 'static,
         NA::Message: Send,
     {
+        let options = options.inherit(&self.default_actor_options);
         // Setup adding a new process to the scheduler.
-        let actor_entry = self.scheduler.add_actor();
+        let actor_entry = self
+            .scheduler
+            .add_actor()
+            .with_cpu_quota(options.cpu_quota())
+            .with_name(options.name())
+            .with_catch_panics(options.catches_panics());
         let pid = actor_entry.pid();
-        let name = new_actor.name();
+        let name = options.name().unwrap_or_else(|| new_actor.name());
         debug!("spawning thread-safe actor: pid={}, name={}", pid, name);
+        if options.readiness_required() {
+            self.readiness.require(pid);
+        }
 
         // Create our actor context and our actor with it.
-        let (manager, sender, receiver) = inbox::Manager::new_small_channel();
+        let (manager, sender, receiver) = Manager::new_small_channel();
         let actor_ref = ActorRef::local(sender);
         let mut ctx = actor::Context::new(receiver, ThreadSafe::new(pid, self.clone()));
         let arg = arg_fn(&mut ctx).map_err(AddActorError::ArgFn)?;
+        new_actor.validate(&arg).map_err(AddActorError::NewActor)?;
         let actor = new_actor.new(ctx, arg).map_err(AddActorError::NewActor)?;
 
         // Add the actor to the scheduler.
@@ -255,9 +391,14 @@ impl RuntimeInternals {
 
     /// Spawn a thread-safe `future`.
     #[allow(clippy::needless_pass_by_value)]
-    pub(crate) fn spawn_future<Fut>(&self, future: Fut, options: FutureOptions)
+    pub(crate) fn spawn_future<Fut>(
+        &self,
+        future: Fut,
+        options: FutureOptions,
+    ) -> JoinHandle<Fut::Output>
     where
-        Fut: Future<Output = ()> + Send + Sync + 'static,
+        Fut: Future + Send + Sync + 'static,
+        Fut::Output: Send + 'static,
     {
         self.scheduler.add_future(future, options.priority())
     }
@@ -323,6 +464,32 @@ impl RuntimeInternals {
         self.scheduler.complete(process);
     }
 
+    /// See [`Scheduler::process_stats_enabled`].
+    pub(crate) const fn process_stats_enabled(&self) -> bool {
+        self.scheduler.process_stats_enabled()
+    }
+
+    /// See [`Scheduler::record_run`].
+    pub(crate) fn record_run(
+        &self,
+        pid: ProcessId,
+        start: Instant,
+        elapsed: Duration,
+        messages: u64,
+    ) {
+        self.scheduler.record_run(pid, start, elapsed, messages);
+    }
+
+    /// See [`Scheduler::process_stats`].
+    pub(crate) fn process_stats(&self, pid: ProcessId) -> Option<ProcessStats> {
+        self.scheduler.process_stats(pid)
+    }
+
+    /// See [`Scheduler::all_process_stats`].
+    pub(crate) fn all_process_stats(&self) -> Vec<(ProcessId, ProcessStats)> {
+        self.scheduler.all_process_stats()
+    }
+
     pub(crate) fn start_trace(&self) -> Option<trace::EventTiming> {
         trace::start(&self.trace_log.as_deref())
     }
@@ -4,16 +4,19 @@
 //!
 //! [`RuntimeRef::try_spawn`]: crate::rt::RuntimeRef::try_spawn
 
+use std::collections::HashMap;
 use std::future::Future;
 use std::mem::MaybeUninit;
 use std::pin::Pin;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
 
-use heph_inbox::Manager;
 use log::{debug, trace};
 
+use crate::actor::inbox::Manager;
 use crate::actor::NewActor;
-use crate::rt::process::{self, ActorProcess, FutureProcess, Process, ProcessId};
-use crate::rt::{ptr_as_usize, ThreadSafe};
+use crate::rt::process::{self, ActorProcess, FutureProcess, Process, ProcessId, ProcessStats};
+use crate::rt::{ptr_as_usize, JoinHandle, ThreadSafe};
 use crate::spawn::options::Priority;
 use crate::supervisor::Supervisor;
 
@@ -83,7 +86,8 @@ pub(super) type ProcessData = process::ProcessData<dyn Process + Send + Sync>;
 ///
 /// A worker thread can by first removing a process from the `Scheduler` by
 /// calling [`Scheduler::remove`]. The scheduler will check if the [`RunQueue`]
-/// is non-empty and returns the highest priority process that is ready to run.
+/// is non-empty and returns the next process to run, see
+/// [`heph_sched::RunQueue`] for how it's selected.
 ///
 /// If `remove` returns `Some(process)` the process must be run. Depending on
 /// the result of the process it should be added back the schduler using
@@ -100,22 +104,29 @@ pub(super) struct Scheduler {
     ready: RunQueue,
     /// Inactive processes that are not ready to run.
     inactive: Inactive,
+    /// Run statistics of all processes, `None` if process statistics are
+    /// disabled, see [`rt::Setup::enable_process_stats`].
+    ///
+    /// [`rt::Setup::enable_process_stats`]: crate::rt::Setup::enable_process_stats
+    stats: Option<Mutex<HashMap<ProcessId, ProcessStats>>>,
 }
 
 /// Metrics for [`Scheduler`].
 #[derive(Debug)]
 #[allow(dead_code)] // https://github.com/rust-lang/rust/issues/88900.
 pub(crate) struct Metrics {
-    ready: usize,
-    inactive: usize,
+    pub(crate) ready: usize,
+    pub(crate) inactive: usize,
 }
 
 impl Scheduler {
-    /// Create a new `Scheduler`.
-    pub(super) fn new() -> Scheduler {
+    /// Create a new `Scheduler`, collecting run statistics of all processes if
+    /// `process_stats` is `true`.
+    pub(super) fn new(process_stats: bool) -> Scheduler {
         Scheduler {
             ready: RunQueue::empty(),
             inactive: Inactive::empty(),
+            stats: process_stats.then(|| Mutex::new(HashMap::new())),
         }
     }
 
@@ -153,19 +164,24 @@ impl Scheduler {
         AddActor {
             scheduler: self,
             alloc: Box::new_uninit(),
+            cpu_quota: None,
+            name: None,
+            catch_panics: false,
         }
     }
 
-    pub(super) fn add_future<Fut>(&self, future: Fut, priority: Priority)
+    pub(super) fn add_future<Fut>(&self, future: Fut, priority: Priority) -> JoinHandle<Fut::Output>
     where
-        Fut: Future<Output = ()> + Send + Sync + 'static,
+        Fut: Future + Send + Sync + 'static,
+        Fut::Output: Send + 'static,
     {
-        let process = Box::pin(ProcessData::new(
-            priority,
-            Box::pin(FutureProcess::<Fut, ThreadSafe>::new(future)),
-        ));
-        debug!("spawning thread-safe future: pid={}", process.as_ref().id());
-        self.ready.add(process)
+        let (process, handle) = FutureProcess::<Fut, ThreadSafe>::new(future);
+        let process = Box::pin(ProcessData::new(priority, Box::pin(process)));
+        let pid = process.as_ref().id();
+        debug!("spawning thread-safe future: pid={}", pid);
+        self.insert_stats(pid, process.as_ref().name());
+        self.ready.add(process);
+        handle
     }
 
     /// Mark the process, with `pid`, as ready to run.
@@ -198,12 +214,59 @@ impl Scheduler {
     }
 
     /// Mark `process` as complete, removing it from the scheduler.
-    #[allow(clippy::unused_self)] // See NOTE below.
     pub(super) fn complete(&self, process: Pin<Box<ProcessData>>) {
         let pid = process.as_ref().id();
         trace!("removing process: pid={}", pid);
+        if let Some(stats) = self.stats.as_ref() {
+            let _ = stats.lock().unwrap().remove(&pid);
+        }
         self.inactive.complete(process);
     }
+
+    /// Returns `true` if run statistics of processes are collected.
+    pub(super) const fn process_stats_enabled(&self) -> bool {
+        self.stats.is_some()
+    }
+
+    /// Record a single run of the process with `pid`, see
+    /// [`ProcessStats::record_run`].
+    pub(super) fn record_run(
+        &self,
+        pid: ProcessId,
+        start: Instant,
+        elapsed: Duration,
+        messages: u64,
+    ) {
+        if let Some(stats) = self.stats.as_ref() {
+            if let Some(stats) = stats.lock().unwrap().get_mut(&pid) {
+                stats.record_run(start, elapsed, messages);
+            }
+        }
+    }
+
+    /// Returns the run statistics of the process with `pid`, if any.
+    pub(super) fn process_stats(&self, pid: ProcessId) -> Option<ProcessStats> {
+        let stats = self.stats.as_ref()?;
+        let stats = stats.lock().unwrap();
+        stats.get(&pid).copied()
+    }
+
+    /// Returns the run statistics of all processes.
+    pub(super) fn all_process_stats(&self) -> Vec<(ProcessId, ProcessStats)> {
+        match self.stats.as_ref() {
+            Some(stats) => {
+                let stats = stats.lock().unwrap();
+                stats.iter().map(|(pid, stats)| (*pid, *stats)).collect()
+            }
+            None => Vec::new(),
+        }
+    }
+
+    fn insert_stats(&self, pid: ProcessId, name: &'static str) {
+        if let Some(stats) = self.stats.as_ref() {
+            let _ = stats.lock().unwrap().insert(pid, ProcessStats::new(name));
+        }
+    }
 }
 
 /// A handle to add a process to the scheduler.
@@ -214,6 +277,12 @@ pub(super) struct AddActor<'s> {
     scheduler: &'s Scheduler,
     /// Already allocated `ProcessData`, used to determine the `ProcessId`.
     alloc: Box<MaybeUninit<ProcessData>>,
+    /// CPU quota of the actor, see [`AddActor::with_cpu_quota`].
+    cpu_quota: Option<Duration>,
+    /// Name of the actor, see [`AddActor::with_name`].
+    name: Option<&'static str>,
+    /// Whether to catch panics, see [`AddActor::with_catch_panics`].
+    catch_panics: bool,
 }
 
 impl<'s> AddActor<'s> {
@@ -222,6 +291,31 @@ impl<'s> AddActor<'s> {
         ProcessId(ptr_as_usize(&*self.alloc as *const _))
     }
 
+    /// Set the CPU quota of the actor, see [`ActorOptions::with_cpu_quota`].
+    ///
+    /// [`ActorOptions::with_cpu_quota`]: crate::spawn::ActorOptions::with_cpu_quota
+    pub(super) fn with_cpu_quota(mut self, cpu_quota: Option<Duration>) -> Self {
+        self.cpu_quota = cpu_quota;
+        self
+    }
+
+    /// Set the name of the actor, see [`ActorOptions::with_name`].
+    ///
+    /// [`ActorOptions::with_name`]: crate::spawn::ActorOptions::with_name
+    pub(super) fn with_name(mut self, name: Option<&'static str>) -> Self {
+        self.name = name;
+        self
+    }
+
+    /// Set whether to catch panics of the actor, see
+    /// [`ActorOptions::catch_panics`].
+    ///
+    /// [`ActorOptions::catch_panics`]: crate::spawn::ActorOptions::catch_panics
+    pub(super) fn with_catch_panics(mut self, catch_panics: bool) -> Self {
+        self.catch_panics = catch_panics;
+        self
+    }
+
     /// Add a new thread-safe actor to the scheduler.
     pub(super) fn add<S, NA>(
         self,
@@ -244,17 +338,24 @@ impl<'s> AddActor<'s> {
 
         let process = ProcessData::new(
             priority,
-            Box::pin(ActorProcess::new(supervisor, new_actor, actor, inbox)),
-        );
+            Box::pin(
+                ActorProcess::new(supervisor, new_actor, actor, inbox)
+                    .with_name(self.name)
+                    .with_catch_panics(self.catch_panics),
+            ),
+        )
+        .with_cpu_quota(self.cpu_quota);
         let AddActor {
             scheduler,
             mut alloc,
+            ..
         } = self;
         let process: Pin<_> = unsafe {
             let _ = alloc.write(process);
             // Safe because we write into the allocation above.
             alloc.assume_init().into()
         };
+        scheduler.insert_stats(process.as_ref().id(), process.as_ref().name());
 
         if is_ready {
             scheduler.ready.add(process);
@@ -17,7 +17,7 @@ fn assert_size<T>(expected: usize) {
 
 #[test]
 fn size_assertions() {
-    assert_size::<ProcessData>(40);
+    assert_size::<ProcessData>(64);
 }
 
 #[test]
@@ -30,7 +30,7 @@ async fn simple_actor(_: actor::Context<!, ThreadSafe>) {}
 
 #[test]
 fn adding_actor() {
-    let scheduler = Scheduler::new();
+    let scheduler = Scheduler::new(false);
 
     // Shouldn't run any process yet, since none are added.
     assert!(!scheduler.has_process());
@@ -87,7 +87,7 @@ fn adding_actor() {
 
 #[test]
 fn marking_unknown_pid_as_ready() {
-    let scheduler = Scheduler::new();
+    let scheduler = Scheduler::new(false);
 
     assert!(!scheduler.has_process());
     assert!(!scheduler.has_ready_process());
@@ -110,7 +110,7 @@ fn scheduler_run_order() {
         order.lock().unwrap().push(id);
     }
 
-    let scheduler = Scheduler::new();
+    let scheduler = Scheduler::new(false);
     let mut runtime_ref = test::runtime();
 
     // The order in which the processes have been run.
@@ -163,7 +163,7 @@ impl NewActor for TestAssertUnmovedNewActor {
 
 #[test]
 fn assert_actor_process_unmoved() {
-    let scheduler = Scheduler::new();
+    let scheduler = Scheduler::new(false);
     let mut runtime_ref = test::runtime();
 
     let (actor, inbox, _) = init_actor_with_inbox(TestAssertUnmovedNewActor, ()).unwrap();
@@ -206,7 +206,7 @@ fn assert_actor_process_unmoved() {
 
 #[test]
 fn assert_future_process_unmoved() {
-    let scheduler = Scheduler::new();
+    let scheduler = Scheduler::new(false);
     let mut runtime_ref = test::runtime();
 
     let future = AssertUnmoved::new(pending());
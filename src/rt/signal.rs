@@ -18,9 +18,14 @@ use std::fmt;
 /// limitations and differences. Any manually spawned threads spawned after
 /// calling build should not get a process signal.
 ///
+/// Process signals are send using the priority lane of the actor's inbox (see
+/// [`ActorRef::send_priority`]), which means they are received before any
+/// regular message already in the actor's inbox.
+///
 /// The runtime will only attempt to send the process signal to the actor once.
-/// If the message can't be send it's **not** retried. Ensure that the inbox of
-/// the actor has enough room to receive the message.
+/// If the message can't be send it's **not** retried.
+///
+/// [`ActorRef::send_priority`]: crate::actor_ref::ActorRef::send_priority
 ///
 /// [`rt::Setup::build`]: crate::rt::Setup::build
 #[derive(Copy, Clone, Debug, Eq, PartialEq)]
@@ -48,6 +53,13 @@ pub enum Signal {
     /// perform a core dump.
     ///
     /// Corresponds to POSIX signal `SIGQUIT`.
+    ///
+    /// # Notes
+    ///
+    /// The runtime will log the state of its schedulers, e.g. the processes
+    /// ready to run and the process currently running on each worker thread,
+    /// when it receives this signal. This can be used to determine why the
+    /// application got stuck.
     Quit,
     /// User-defined signal 1.
     ///
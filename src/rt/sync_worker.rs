@@ -1,13 +1,17 @@
 //! Synchronous actor thread code.
 
 use std::io::{self, Write};
+use std::task;
 use std::thread;
+use std::time::{Duration, Instant};
 
-use heph_inbox::{self as inbox, ReceiverConnected};
+use heph_inbox::ReceiverConnected;
 use log::trace;
 use mio::{unix, Interest, Registry, Token};
 
-use crate::actor::{SyncActor, SyncContext};
+use crate::actor::inbox::Manager;
+use crate::actor::messages::StopReason;
+use crate::actor::{SyncActor, SyncContext, SyncWaker};
 use crate::actor_ref::ActorRef;
 use crate::spawn::options::SyncActorOptions;
 use crate::supervisor::{SupervisorStrategy, SyncSupervisor};
@@ -41,7 +45,7 @@ impl SyncWorker {
         A::Argument: Send + 'static,
     {
         unix::pipe::new().and_then(|(sender, receiver)| {
-            let (manager, send, _) = inbox::Manager::new_small_channel();
+            let (manager, send, _) = Manager::new_small_channel();
             let actor_ref = ActorRef::local(send);
             let thread_name = options
                 .take_name()
@@ -93,7 +97,7 @@ fn main<S, A>(
     mut supervisor: S,
     actor: A,
     mut arg: A::Argument,
-    inbox: inbox::Manager<A::Message>,
+    inbox: Manager<A::Message>,
     receiver: unix::pipe::Receiver,
     mut trace_log: Option<trace::Log>,
 ) where
@@ -103,7 +107,7 @@ fn main<S, A>(
     let thread = thread::current();
     let name = thread.name().unwrap();
     trace!("running synchronous actor: pid={}, name='{}'", id, name);
-    loop {
+    let reason = loop {
         let timing = trace::start(&trace_log);
         let receiver = inbox.new_receiver().unwrap_or_else(inbox_failure);
         let ctx = SyncContext::new(receiver, trace_log.clone());
@@ -119,7 +123,7 @@ fn main<S, A>(
         trace::finish_rt(trace_log.as_mut(), timing, "running synchronous actor", &[]);
 
         match res {
-            Ok(()) => break,
+            Ok(()) => break StopReason::Completed,
             Err(err) => {
                 let timing = trace::start(&trace_log);
                 match supervisor.decide(err) {
@@ -133,21 +137,47 @@ fn main<S, A>(
                             &[],
                         );
                     }
-                    SupervisorStrategy::Stop => {
+                    SupervisorStrategy::RestartAfter(new_arg, delay) => {
+                        trace!(
+                            "restarting synchronous actor after {:?}: pid={}, name='{}'",
+                            delay,
+                            id,
+                            name
+                        );
+                        if !wait_for_restart(&inbox, delay) {
+                            trace::finish_rt(
+                                trace_log.as_mut(),
+                                timing,
+                                "stopping synchronous actor",
+                                &[],
+                            );
+                            break StopReason::Terminated;
+                        }
+                        arg = new_arg;
+                        trace::finish_rt(
+                            trace_log.as_mut(),
+                            timing,
+                            "restarting synchronous actor",
+                            &[],
+                        );
+                    }
+                    // Synchronous actors don't have a parent to escalate to.
+                    SupervisorStrategy::Stop | SupervisorStrategy::Escalate => {
                         trace::finish_rt(
                             trace_log.as_mut(),
                             timing,
                             "stopping synchronous actor",
                             &[],
                         );
-                        break;
+                        break StopReason::Failed;
                     }
                 }
             }
         }
-    }
+    };
 
     trace!("stopping synchronous actor: pid={}, name='{}'", id, name);
+    inbox.stopped(reason);
     // First drop all values as this might take an arbitrary time.
     drop(actor);
     drop(supervisor);
@@ -161,3 +191,28 @@ fn main<S, A>(
 fn inbox_failure<T>(_: ReceiverConnected) -> T {
     panic!("failed to create new receiver for synchronous actor's inbox. Was the `SyncContext` leaked?");
 }
+
+/// Wait `delay` before restarting the actor.
+///
+/// Synchronous actors have their own thread, so we can simply park it until
+/// it's time to restart. Returns `false` if the actor was asked to stop (see
+/// [`ActorRef::stop`]) in the meantime, in which case it shouldn't be
+/// restarted.
+fn wait_for_restart<M>(inbox: &Manager<M>, delay: Duration) -> bool {
+    let deadline = Instant::now() + delay;
+    // Wake us if the actor is asked to stop.
+    let waker = task::Waker::from(SyncWaker::new());
+    let _ = inbox.register_lifecycle_waker(&waker);
+    loop {
+        if inbox.stop_requested() {
+            return false;
+        }
+        let now = Instant::now();
+        if now >= deadline {
+            return true;
+        }
+        // NOTE: we can be unparked early, e.g. by a message being send to the
+        // actor, so we need to check the deadline again.
+        thread::park_timeout(deadline - now);
+    }
+}
@@ -0,0 +1,65 @@
+//! Module with the types shared by the local and shared timers
+//! implementations.
+
+use std::cmp::Ordering;
+
+use crate::rt::ProcessId;
+
+/// A timer.
+///
+/// # Notes
+///
+/// The [`Ord`] implementation is in reverse order, i.e. the deadline to expire
+/// first will have the highest ordering value. Furthermore the ordering is only
+/// done base on the deadline, the process id is ignored in ordering. This
+/// allows `change_timer` to not worry about order when changing the process id
+/// of a timer.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub(super) struct Timer<T> {
+    pub(super) pid: ProcessId,
+    pub(super) deadline: T,
+}
+
+impl<T> Ord for Timer<T>
+where
+    T: Ord,
+{
+    fn cmp(&self, other: &Self) -> Ordering {
+        other.deadline.cmp(&self.deadline)
+    }
+}
+
+impl<T> PartialOrd for Timer<T>
+where
+    T: Ord,
+{
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+/// Returns the index of `timer` in `timers`, matching both the deadline and the
+/// process id. If `timer` is not found this returns the index at which it can
+/// be inserted.
+///
+/// Using `binary_search` alone is not enough as it only compares the deadline
+/// (see [`Timer`]), which means it could return a timer of another process with
+/// the same deadline.
+pub(super) fn find_timer<T>(timers: &[Timer<T>], timer: &Timer<T>) -> Result<usize, usize>
+where
+    Timer<T>: Ord,
+    T: Eq,
+{
+    let idx = timers.binary_search(timer)?;
+    // All timers with the same deadline are next to each other.
+    let start = timers[..idx]
+        .iter()
+        .rposition(|t| t.deadline != timer.deadline)
+        .map_or(0, |i| i + 1);
+    timers[start..]
+        .iter()
+        .take_while(|t| t.deadline == timer.deadline)
+        .position(|t| t.pid == timer.pid)
+        .map(|i| start + i)
+        .ok_or(idx)
+}
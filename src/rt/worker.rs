@@ -1,13 +1,15 @@
 //! Worker thread code.
 
 use std::num::NonZeroUsize;
+use std::sync::atomic::{AtomicUsize, Ordering};
 use std::sync::Arc;
+use std::time::Duration;
 use std::{io, thread};
 
 use crossbeam_channel::{self, Receiver};
 use mio::{Poll, Registry, Token};
 
-use crate::rt::local::{Control, Runtime, WAKER};
+use crate::rt::local::{Control, EventLoopConfig, Runtime, WAKER};
 use crate::rt::thread_waker::ThreadWaker;
 use crate::rt::waker::WakerId;
 use crate::rt::{self, shared, ProcessId, RuntimeRef, Signal};
@@ -26,6 +28,8 @@ pub(super) struct WorkerSetup {
     waker_id: WakerId,
     /// Receiving side of the channel for `Waker` events.
     waker_events: Receiver<ProcessId>,
+    /// See [`Worker::running_process`].
+    running: Arc<AtomicUsize>,
 }
 
 /// Setup a new worker thread.
@@ -45,6 +49,7 @@ pub(super) fn setup(id: NonZeroUsize) -> io::Result<(WorkerSetup, &'static Threa
         poll,
         waker_id,
         waker_events,
+        running: Arc::new(AtomicUsize::new(0)),
     };
     Ok((setup, thread_waker))
 }
@@ -55,12 +60,16 @@ impl WorkerSetup {
         self,
         shared_internals: Arc<shared::RuntimeInternals>,
         auto_cpu_affinity: bool,
+        deadline_scheduling: bool,
+        time_slice: Option<Duration>,
+        event_loop: EventLoopConfig,
         trace_log: Option<trace::Log>,
     ) -> io::Result<Worker> {
         rt::channel::new().and_then(|(channel, receiver)| {
-            // Copy id to move into `Worker`, `self` moves into the spawned
-            // thread.
+            // Copy id and running to move into `Worker`, `self` moves into the
+            // spawned thread.
             let id = self.id;
+            let running = self.running.clone();
             thread::Builder::new()
                 .name(format!("Worker {}", id))
                 .spawn(move || {
@@ -69,6 +78,9 @@ impl WorkerSetup {
                         receiver,
                         shared_internals,
                         auto_cpu_affinity,
+                        deadline_scheduling,
+                        time_slice,
+                        event_loop,
                         trace_log,
                     )
                 })
@@ -76,10 +88,36 @@ impl WorkerSetup {
                     id,
                     channel,
                     handle,
+                    running,
                 })
         })
     }
 
+    /// Create the local runtime on the current thread, rather than starting a
+    /// new worker thread.
+    ///
+    /// Used by [`rt::EmbeddedRuntime`].
+    pub(super) fn into_runtime(
+        self,
+        receiver: rt::channel::Receiver<Control>,
+        shared_internals: Arc<shared::RuntimeInternals>,
+    ) -> io::Result<Runtime> {
+        Runtime::new(
+            self.id,
+            self.poll,
+            self.waker_id,
+            self.waker_events,
+            receiver,
+            shared_internals,
+            self.running,
+            None,
+            None,
+            false,
+            None,
+            EventLoopConfig::DEFAULT,
+        )
+    }
+
     /// Return the worker's id.
     pub(super) const fn id(&self) -> usize {
         self.id.get()
@@ -95,6 +133,8 @@ pub(super) struct Worker {
     channel: rt::channel::Sender<Control>,
     /// Handle for the actual thread.
     handle: thread::JoinHandle<Result<(), rt::Error>>,
+    /// Pid of the process currently running on the worker thread, or 0.
+    running: Arc<AtomicUsize>,
 }
 
 impl Worker {
@@ -103,6 +143,20 @@ impl Worker {
         self.id.get()
     }
 
+    /// Returns the pid of the process currently running on the worker thread,
+    /// if any.
+    ///
+    /// # Notes
+    ///
+    /// This is only a snapshot, by the time this returns the process could
+    /// have finished running.
+    pub(super) fn running_process(&self) -> Option<ProcessId> {
+        match self.running.load(Ordering::Relaxed) {
+            0 => None,
+            pid => Some(ProcessId(pid)),
+        }
+    }
+
     /// Registers the channel used to communicate with the thread. Uses the
     /// [`Worker::id`] as [`Token`].
     pub(super) fn register(&mut self, registry: &Registry) -> io::Result<()> {
@@ -134,14 +188,19 @@ impl Worker {
 }
 
 /// The main function of a worker thread.
+#[allow(clippy::too_many_arguments)]
 fn main(
     setup: WorkerSetup,
     receiver: rt::channel::Receiver<Control>,
     shared_internals: Arc<shared::RuntimeInternals>,
     auto_cpu_affinity: bool,
+    deadline_scheduling: bool,
+    time_slice: Option<Duration>,
+    event_loop: EventLoopConfig,
     trace_log: Option<trace::Log>,
 ) -> Result<(), rt::Error> {
     let timing = trace::start(&trace_log);
+    let id = setup.id.get();
 
     let cpu = if auto_cpu_affinity {
         set_cpu_affinity(setup.id)
@@ -156,10 +215,14 @@ fn main(
         setup.waker_events,
         receiver,
         shared_internals,
+        setup.running,
         trace_log,
         cpu,
+        deadline_scheduling,
+        time_slice,
+        event_loop,
     )
-    .map_err(|err| rt::Error::worker(Error::Init(err)))?;
+    .map_err(|err| rt::Error::worker(id, Error::Init(err)))?;
 
     trace::finish_rt(
         (&mut *runtime.trace_log()).as_mut(),
@@ -169,7 +232,9 @@ fn main(
     );
 
     // All setup is done, so we're ready to run the event loop.
-    runtime.run_event_loop().map_err(rt::Error::worker)
+    runtime
+        .run_event_loop()
+        .map_err(|err| rt::Error::worker(id, err))
 }
 
 /// Set thread's CPU affinity.
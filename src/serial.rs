@@ -0,0 +1,518 @@
+//! Serial ports and other character devices.
+//!
+//! To open a serial port use [`Device::open`], which configures the port
+//! using [`Config`] (baud rate, data bits, parity, etc.) and puts the terminal
+//! in raw mode. Other character devices, that don't need to be configured, can
+//! be used with [`Device::from_file`].
+//!
+//! # Notes
+//!
+//! The [`Device`] type is [bound] to an actor. See the [`actor::Bound`] trait
+//! for more information.
+//!
+//! [bound]: crate::actor::Bound
+//! [`actor::Bound`]: crate::actor::Bound
+//!
+//! # Examples
+//!
+//! Reading from a serial port.
+//!
+//! ```no_run
+//! # #![feature(never_type)]
+//! use std::io;
+//!
+//! use heph::serial::{Config, Device, Parity};
+//! use heph::{actor, rt};
+//!
+//! async fn actor<RT>(mut ctx: actor::Context<!, RT>) -> io::Result<()>
+//!     where RT: rt::Access,
+//! {
+//!     let config = Config::default()
+//!         .with_baud_rate(115_200)
+//!         .with_parity(Parity::Even);
+//!     let mut device = Device::open(&mut ctx, "/dev/ttyUSB0", &config)?;
+//!
+//!     device.write_all(b"AT\r\n").await?;
+//!     let mut buf = Vec::with_capacity(128);
+//!     let n = device.read(&mut buf).await?;
+//!     println!("read {} bytes: {:?}", n, buf);
+//!     Ok(())
+//! }
+//! #
+//! # drop(actor::<heph::rt::ThreadLocal>); // Silence dead code warnings.
+//! ```
+
+use std::fs::{File, OpenOptions};
+use std::future::Future;
+use std::io;
+use std::mem::MaybeUninit;
+use std::os::unix::fs::OpenOptionsExt;
+use std::os::unix::io::{AsRawFd, RawFd};
+use std::path::Path;
+use std::pin::Pin;
+use std::task::{self, Poll};
+
+use mio::unix::SourceFd;
+use mio::Interest;
+
+use crate::bytes::Bytes;
+use crate::{actor, rt};
+
+/// Configuration of a serial port.
+///
+/// Defaults to 9600 baud, 8 data bits, no parity, 1 stop bit and no flow
+/// control (often written as "9600 8N1").
+///
+/// # Examples
+///
+/// ```
+/// use heph::serial::{Config, FlowControl};
+///
+/// let config = Config::default()
+///     .with_baud_rate(115_200)
+///     .with_flow_control(FlowControl::Hardware);
+/// # drop(config); // Silence unused variable warning.
+/// ```
+#[derive(Clone, Debug)]
+pub struct Config {
+    baud_rate: u32,
+    data_bits: u8,
+    parity: Parity,
+    stop_bits: u8,
+    flow_control: FlowControl,
+}
+
+impl Config {
+    /// Set the baud rate, e.g. `9600` or `115_200`.
+    ///
+    /// Only the standard baud rates are supported, using a different rate will
+    /// cause [`Device::open`] and [`Device::configure`] to return an error.
+    pub const fn with_baud_rate(mut self, baud_rate: u32) -> Self {
+        self.baud_rate = baud_rate;
+        self
+    }
+
+    /// Set the number of data bits per character.
+    ///
+    /// # Panics
+    ///
+    /// This will panic if `data_bits` is not in the range `5..=8`.
+    pub const fn with_data_bits(mut self, data_bits: u8) -> Self {
+        assert!(
+            matches!(data_bits, 5..=8),
+            "Data bits must be between 5 and 8"
+        );
+        self.data_bits = data_bits;
+        self
+    }
+
+    /// Set the parity checking.
+    pub const fn with_parity(mut self, parity: Parity) -> Self {
+        self.parity = parity;
+        self
+    }
+
+    /// Set the number of stop bits.
+    ///
+    /// # Panics
+    ///
+    /// This will panic if `stop_bits` is not 1 or 2.
+    pub const fn with_stop_bits(mut self, stop_bits: u8) -> Self {
+        assert!(stop_bits == 1 || stop_bits == 2, "Stop bits must be 1 or 2");
+        self.stop_bits = stop_bits;
+        self
+    }
+
+    /// Set the flow control.
+    pub const fn with_flow_control(mut self, flow_control: FlowControl) -> Self {
+        self.flow_control = flow_control;
+        self
+    }
+
+    /// Apply the configuration to the terminal `fd`.
+    fn apply(&self, fd: RawFd) -> io::Result<()> {
+        let speed = match baud_rate_speed(self.baud_rate) {
+            Some(speed) => speed,
+            None => {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    "unsupported baud rate",
+                ))
+            }
+        };
+
+        let mut termios = MaybeUninit::uninit();
+        if unsafe { libc::tcgetattr(fd, termios.as_mut_ptr()) } == -1 {
+            return Err(io::Error::last_os_error());
+        }
+        // SAFETY: initialised by the call to `tcgetattr` above.
+        let mut termios = unsafe { termios.assume_init() };
+
+        // Disables echo, line editing, signal characters, etc.
+        unsafe { libc::cfmakeraw(&mut termios) };
+        termios.c_cflag |= libc::CREAD | libc::CLOCAL;
+
+        termios.c_cflag &= !libc::CSIZE;
+        termios.c_cflag |= match self.data_bits {
+            5 => libc::CS5,
+            6 => libc::CS6,
+            7 => libc::CS7,
+            _ => libc::CS8,
+        };
+
+        termios.c_cflag &= !(libc::PARENB | libc::PARODD);
+        match self.parity {
+            Parity::None => {}
+            Parity::Even => termios.c_cflag |= libc::PARENB,
+            Parity::Odd => termios.c_cflag |= libc::PARENB | libc::PARODD,
+        }
+
+        if self.stop_bits == 2 {
+            termios.c_cflag |= libc::CSTOPB;
+        } else {
+            termios.c_cflag &= !libc::CSTOPB;
+        }
+
+        termios.c_cflag &= !libc::CRTSCTS;
+        termios.c_iflag &= !(libc::IXON | libc::IXOFF | libc::IXANY);
+        match self.flow_control {
+            FlowControl::None => {}
+            FlowControl::Hardware => termios.c_cflag |= libc::CRTSCTS,
+            FlowControl::Software => termios.c_iflag |= libc::IXON | libc::IXOFF,
+        }
+
+        // Return from reads as soon as a single byte is available.
+        termios.c_cc[libc::VMIN] = 1;
+        termios.c_cc[libc::VTIME] = 0;
+
+        if unsafe { libc::cfsetispeed(&mut termios, speed) } == -1
+            || unsafe { libc::cfsetospeed(&mut termios, speed) } == -1
+            || unsafe { libc::tcsetattr(fd, libc::TCSANOW, &termios) } == -1
+        {
+            return Err(io::Error::last_os_error());
+        }
+        Ok(())
+    }
+}
+
+impl Default for Config {
+    fn default() -> Config {
+        Config {
+            baud_rate: 9600,
+            data_bits: 8,
+            parity: Parity::None,
+            stop_bits: 1,
+            flow_control: FlowControl::None,
+        }
+    }
+}
+
+/// Parity checking, see [`Config::with_parity`].
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum Parity {
+    /// No parity bit.
+    None,
+    /// Even parity.
+    Even,
+    /// Odd parity.
+    Odd,
+}
+
+/// Flow control, see [`Config::with_flow_control`].
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum FlowControl {
+    /// No flow control.
+    None,
+    /// Hardware flow control using the RTS and CTS lines.
+    Hardware,
+    /// Software flow control using XON and XOFF characters.
+    Software,
+}
+
+/// Returns the `speed_t` for `baud_rate`, if supported.
+fn baud_rate_speed(baud_rate: u32) -> Option<libc::speed_t> {
+    Some(match baud_rate {
+        50 => libc::B50,
+        75 => libc::B75,
+        110 => libc::B110,
+        134 => libc::B134,
+        150 => libc::B150,
+        200 => libc::B200,
+        300 => libc::B300,
+        600 => libc::B600,
+        1200 => libc::B1200,
+        1800 => libc::B1800,
+        2400 => libc::B2400,
+        4800 => libc::B4800,
+        9600 => libc::B9600,
+        19200 => libc::B19200,
+        38400 => libc::B38400,
+        57600 => libc::B57600,
+        115_200 => libc::B115200,
+        230_400 => libc::B230400,
+        #[cfg(target_os = "linux")]
+        460_800 => libc::B460800,
+        #[cfg(target_os = "linux")]
+        921_600 => libc::B921600,
+        _ => return None,
+    })
+}
+
+/// A serial port or other character device.
+///
+/// Created by calling [`Device::open`] or [`Device::from_file`].
+#[derive(Debug)]
+pub struct Device {
+    file: File,
+}
+
+impl Device {
+    /// Open the serial port at `path`, configuring it using `config`.
+    pub fn open<M, RT, P>(
+        ctx: &mut actor::Context<M, RT>,
+        path: P,
+        config: &Config,
+    ) -> io::Result<Device>
+    where
+        RT: rt::Access,
+        P: AsRef<Path>,
+    {
+        let file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            // Don't make the device the controlling terminal of the process.
+            .custom_flags(libc::O_NOCTTY | libc::O_NONBLOCK)
+            .open(path)?;
+        config.apply(file.as_raw_fd())?;
+        Device::register(ctx, file)
+    }
+
+    /// Convert a [`File`] to a `Device`.
+    ///
+    /// This can be used for character devices that aren't serial ports, or
+    /// for serial ports that are already configured. The file must be opened
+    /// for reading and writing.
+    pub fn from_file<M, RT>(ctx: &mut actor::Context<M, RT>, file: File) -> io::Result<Device>
+    where
+        RT: rt::Access,
+    {
+        let fd = file.as_raw_fd();
+        let flags = unsafe { libc::fcntl(fd, libc::F_GETFL) };
+        if flags == -1 || unsafe { libc::fcntl(fd, libc::F_SETFL, flags | libc::O_NONBLOCK) } == -1
+        {
+            return Err(io::Error::last_os_error());
+        }
+        Device::register(ctx, file)
+    }
+
+    fn register<M, RT>(ctx: &mut actor::Context<M, RT>, file: File) -> io::Result<Device>
+    where
+        RT: rt::Access,
+    {
+        let interest = Interest::READABLE | Interest::WRITABLE;
+        ctx.runtime()
+            .register(&mut SourceFd(&file.as_raw_fd()), interest)?;
+        Ok(Device { file })
+    }
+
+    /// (Re)configure the serial port using `config`.
+    ///
+    /// Returns an error if the device is not a terminal.
+    pub fn configure(&mut self, config: &Config) -> io::Result<()> {
+        config.apply(self.file.as_raw_fd())
+    }
+
+    /// Attempt to read bytes from the device, writing them into `buf`.
+    ///
+    /// If no bytes can currently be read this will return an error with the
+    /// [kind] set to [`ErrorKind::WouldBlock`]. Most users should prefer to use
+    /// [`Device::read`] or [`Device::read_n`].
+    ///
+    /// [kind]: io::Error::kind
+    /// [`ErrorKind::WouldBlock`]: io::ErrorKind::WouldBlock
+    pub fn try_read<B>(&mut self, mut buf: B) -> io::Result<usize>
+    where
+        B: Bytes,
+    {
+        debug_assert!(
+            buf.has_spare_capacity(),
+            "called `Device::try_read` with an empty buffer"
+        );
+        // SAFETY: This is unsound.
+        // However `read(2)` doesn't read any bytes from the buffer, so it
+        // shouldn't invoke any UB.
+        let buf_bytes = unsafe { &mut *(buf.as_bytes() as *mut [MaybeUninit<u8>] as *mut [u8]) };
+        io::Read::read(&mut self.file, buf_bytes).map(|read| {
+            // Safety: just read the bytes.
+            unsafe { buf.update_length(read) }
+            read
+        })
+    }
+
+    /// Read bytes from the device, writing them into `buf`.
+    pub fn read<'a, B>(&'a mut self, buf: B) -> Read<'a, B>
+    where
+        B: Bytes,
+    {
+        Read { device: self, buf }
+    }
+
+    /// Read at least `n` bytes from the device, writing them into `buf`.
+    ///
+    /// This returns a [`Future`] that receives at least `n` bytes from the
+    /// `Device` and writes them into buffer `B`, or returns
+    /// [`io::ErrorKind::UnexpectedEof`] if less then `n` bytes could be read.
+    pub fn read_n<'a, B>(&'a mut self, buf: B, n: usize) -> ReadN<'a, B>
+    where
+        B: Bytes,
+    {
+        debug_assert!(
+            buf.spare_capacity() >= n,
+            "called `Device::read_n` with a buffer smaller then `n`",
+        );
+        ReadN {
+            device: self,
+            buf,
+            left: n,
+        }
+    }
+
+    /// Attempt to write the bytes in `buf` to the device.
+    ///
+    /// If no bytes can currently be written this will return an error with the
+    /// [kind] set to [`ErrorKind::WouldBlock`]. Most users should prefer to use
+    /// [`Device::write`] or [`Device::write_all`].
+    ///
+    /// [kind]: io::Error::kind
+    /// [`ErrorKind::WouldBlock`]: io::ErrorKind::WouldBlock
+    pub fn try_write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        io::Write::write(&mut self.file, buf)
+    }
+
+    /// Write the bytes in `buf` to the device.
+    ///
+    /// Return the number of bytes written. This may we fewer then the length of
+    /// `buf`. To ensure that all bytes are written use [`Device::write_all`].
+    pub fn write<'a, 'b>(&'a mut self, buf: &'b [u8]) -> Write<'a, 'b> {
+        Write { device: self, buf }
+    }
+
+    /// Write the all bytes in `buf` to the device.
+    ///
+    /// If this fails to write all bytes (this happens if a write returns
+    /// `Ok(0)`) this will return [`io::ErrorKind::WriteZero`].
+    pub fn write_all<'a, 'b>(&'a mut self, buf: &'b [u8]) -> WriteAll<'a, 'b> {
+        WriteAll { device: self, buf }
+    }
+}
+
+/// The [`Future`] behind [`Device::read`].
+#[derive(Debug)]
+#[must_use = "futures do nothing unless you `.await` or poll them"]
+pub struct Read<'d, B> {
+    device: &'d mut Device,
+    buf: B,
+}
+
+impl<'d, B> Future for Read<'d, B>
+where
+    B: Bytes + Unpin,
+{
+    type Output = io::Result<usize>;
+
+    fn poll(self: Pin<&mut Self>, _: &mut task::Context<'_>) -> Poll<Self::Output> {
+        let Read { device, buf } = Pin::into_inner(self);
+        try_io!(device.try_read(&mut *buf))
+    }
+}
+
+/// The [`Future`] behind [`Device::read_n`].
+#[derive(Debug)]
+#[must_use = "futures do nothing unless you `.await` or poll them"]
+pub struct ReadN<'d, B> {
+    device: &'d mut Device,
+    buf: B,
+    left: usize,
+}
+
+impl<'d, B> Future for ReadN<'d, B>
+where
+    B: Bytes + Unpin,
+{
+    type Output = io::Result<()>;
+
+    fn poll(self: Pin<&mut Self>, _: &mut task::Context<'_>) -> Poll<Self::Output> {
+        let ReadN { device, buf, left } = Pin::into_inner(self);
+        loop {
+            match device.try_read(&mut *buf) {
+                Ok(0) => return Poll::Ready(Err(io::ErrorKind::UnexpectedEof.into())),
+                Ok(n) if *left <= n => return Poll::Ready(Ok(())),
+                Ok(n) => {
+                    *left -= n;
+                    // Try to read some more bytes.
+                    continue;
+                }
+                Err(ref err) if err.kind() == io::ErrorKind::WouldBlock => break Poll::Pending,
+                Err(ref err) if err.kind() == io::ErrorKind::Interrupted => continue,
+                Err(err) => break Poll::Ready(Err(err)),
+            }
+        }
+    }
+}
+
+/// The [`Future`] behind [`Device::write`].
+#[derive(Debug)]
+#[must_use = "futures do nothing unless you `.await` or poll them"]
+pub struct Write<'a, 'b> {
+    device: &'a mut Device,
+    buf: &'b [u8],
+}
+
+impl<'a, 'b> Future for Write<'a, 'b> {
+    type Output = io::Result<usize>;
+
+    fn poll(self: Pin<&mut Self>, _: &mut task::Context<'_>) -> Poll<Self::Output> {
+        let Write { device, buf } = Pin::into_inner(self);
+        try_io!(device.try_write(*buf))
+    }
+}
+
+/// The [`Future`] behind [`Device::write_all`].
+#[derive(Debug)]
+#[must_use = "futures do nothing unless you `.await` or poll them"]
+pub struct WriteAll<'a, 'b> {
+    device: &'a mut Device,
+    buf: &'b [u8],
+}
+
+impl<'a, 'b> Future for WriteAll<'a, 'b> {
+    type Output = io::Result<()>;
+
+    fn poll(self: Pin<&mut Self>, _: &mut task::Context<'_>) -> Poll<Self::Output> {
+        let WriteAll { device, buf } = Pin::into_inner(self);
+        loop {
+            match device.try_write(*buf) {
+                Ok(0) => return Poll::Ready(Err(io::ErrorKind::WriteZero.into())),
+                Ok(n) if buf.len() <= n => return Poll::Ready(Ok(())),
+                Ok(n) => {
+                    *buf = &buf[n..];
+                    // Try to write some more bytes.
+                    continue;
+                }
+                Err(ref err) if err.kind() == io::ErrorKind::WouldBlock => break Poll::Pending,
+                Err(ref err) if err.kind() == io::ErrorKind::Interrupted => continue,
+                Err(err) => break Poll::Ready(Err(err)),
+            }
+        }
+    }
+}
+
+impl<RT: rt::Access> actor::Bound<RT> for Device {
+    type Error = io::Error;
+
+    fn bind_to<M>(&mut self, ctx: &mut actor::Context<M, RT>) -> io::Result<()> {
+        let interest = Interest::READABLE | Interest::WRITABLE;
+        ctx.runtime()
+            .reregister(&mut SourceFd(&self.file.as_raw_fd()), interest)
+    }
+}
@@ -0,0 +1,191 @@
+//! Graceful shutdown in dependency order.
+//!
+//! When the runtime receives a process signal all actors are informed at the
+//! same time. This means that for example a logger can stop before the HTTP
+//! server has flushed its last log messages to it, losing those messages.
+//!
+//! [`Shutdown`] stops actors in stages instead. Stages are added in the order
+//! in which they are started, i.e. dependencies first, and stopped in reverse
+//! order, i.e. dependents first. Each stage is send a [`Terminate`] message
+//! after which [`Shutdown::run`] waits until all actors in the stage have
+//! stopped, or until the timeout of the stage has passed, before moving on to
+//! the next stage.
+//!
+//! # Examples
+//!
+//! ```
+//! #![feature(never_type)]
+//!
+//! use std::time::Duration;
+//!
+//! use heph::actor::{self, messages::Terminate};
+//! use heph::actor_ref::ActorGroup;
+//! use heph::rt::{Signal, ThreadLocal};
+//! use heph::shutdown::Shutdown;
+//!
+//! /// Actor that stops the other actors once it receives a signal.
+//! async fn shutdown_actor(
+//!     mut ctx: actor::Context<Signal, ThreadLocal>,
+//!     loggers: ActorGroup<Terminate>,
+//!     db_pools: ActorGroup<Terminate>,
+//!     servers: ActorGroup<Terminate>,
+//! ) {
+//!     // Stages are added in startup order.
+//!     let shutdown = Shutdown::new()
+//!         .stage("loggers", loggers, Duration::from_secs(1))
+//!         .stage("database pools", db_pools, Duration::from_secs(5))
+//!         .stage("servers", servers, Duration::from_secs(10));
+//!
+//!     // Wait for a signal to stop.
+//!     let _ = ctx.receive_next().await;
+//!
+//!     // Stops the servers first, followed by the database pools and finally
+//!     // the loggers.
+//!     if let Err(err) = shutdown.run(&mut ctx).await {
+//!         log::warn!("unclean shutdown: {}", err);
+//!     }
+//! }
+//! # drop(shutdown_actor); // Silence dead code warnings.
+//! ```
+
+use std::error::Error;
+use std::fmt;
+use std::time::Duration;
+
+use log::{debug, warn};
+
+use crate::actor::messages::Terminate;
+use crate::actor_ref::{ActorGroup, Delivery};
+use crate::timer::Timer;
+use crate::util::either;
+use crate::{actor, rt};
+
+/// Graceful shutdown of actors in stages.
+///
+/// See the [module documentation] for more information.
+///
+/// [module documentation]: crate::shutdown
+#[derive(Debug)]
+#[must_use = "`Shutdown` does nothing unless `run`"]
+pub struct Shutdown {
+    /// Stages in startup order.
+    stages: Vec<Stage>,
+}
+
+/// Single stage of [`Shutdown`].
+#[derive(Debug)]
+struct Stage {
+    name: &'static str,
+    actors: ActorGroup<Terminate>,
+    timeout: Duration,
+}
+
+impl Shutdown {
+    /// Create a new `Shutdown` without any stages.
+    pub const fn new() -> Shutdown {
+        Shutdown { stages: Vec::new() }
+    }
+
+    /// Add a new stage with `actors`.
+    ///
+    /// Stages must be added in the order in which they are started, i.e. the
+    /// actors in this stage may depend on the actors in all previously added
+    /// stages. When shutting down the stages are stopped in reverse order.
+    ///
+    /// `name` is used in logging and in [`ShutdownError`]. `timeout` is the
+    /// maximum amount of time to wait for the actors in this stage to stop.
+    pub fn stage(
+        mut self,
+        name: &'static str,
+        actors: ActorGroup<Terminate>,
+        timeout: Duration,
+    ) -> Shutdown {
+        self.stages.push(Stage {
+            name,
+            actors,
+            timeout,
+        });
+        self
+    }
+
+    /// Returns the number of stages.
+    pub fn len(&self) -> usize {
+        self.stages.len()
+    }
+
+    /// Returns `true` if no stages have been added.
+    pub fn is_empty(&self) -> bool {
+        self.stages.is_empty()
+    }
+
+    /// Stop all stages in reverse startup order.
+    ///
+    /// For each stage this sends all actors a [`Terminate`] message, using the
+    /// priority lane, and waits until all actors have stopped or the timeout
+    /// of the stage has passed. If the timeout passes a warning is logged and
+    /// the next stage is stopped regardless. Once all stages are stopped this
+    /// returns an error if any of the stages timed out.
+    pub async fn run<M, RT>(&self, ctx: &mut actor::Context<M, RT>) -> Result<(), ShutdownError>
+    where
+        RT: rt::Access + Clone,
+    {
+        let mut timed_out = Vec::new();
+        for stage in self.stages.iter().rev() {
+            debug!("stopping shutdown stage '{}'", stage.name);
+            // NOTE: this only fails if all actors in the stage already stopped
+            // (or the stage is empty), in which case the join below returns
+            // immediately.
+            let _ = stage.actors.try_send_priority(Terminate, Delivery::ToAll);
+            let timer = Timer::after(ctx, stage.timeout);
+            if either(stage.actors.join_all(), timer).await.is_err() {
+                warn!(
+                    "shutdown stage '{}' didn't stop within {:?}, continuing shutdown",
+                    stage.name, stage.timeout
+                );
+                timed_out.push(stage.name);
+            }
+        }
+
+        if timed_out.is_empty() {
+            Ok(())
+        } else {
+            Err(ShutdownError { timed_out })
+        }
+    }
+}
+
+impl Default for Shutdown {
+    fn default() -> Shutdown {
+        Shutdown::new()
+    }
+}
+
+/// Error returned by [`Shutdown::run`].
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ShutdownError {
+    /// Names of the stages that timed out, in shutdown order.
+    timed_out: Vec<&'static str>,
+}
+
+impl ShutdownError {
+    /// Returns the names of the stages that didn't stop within their timeout,
+    /// in the order in which they were stopped.
+    pub fn timed_out(&self) -> &[&'static str] {
+        &self.timed_out
+    }
+}
+
+impl fmt::Display for ShutdownError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("shutdown stages timed out: ")?;
+        for (i, name) in self.timed_out.iter().enumerate() {
+            if i != 0 {
+                f.write_str(", ")?;
+            }
+            f.write_str(name)?;
+        }
+        Ok(())
+    }
+}
+
+impl Error for ShutdownError {}
@@ -4,11 +4,10 @@
 //! [`Actor`]: crate::actor::Actor
 //! [`SyncActor`]: crate::actor::SyncActor
 
-use std::cmp::Ordering;
-use std::num::NonZeroU8;
-use std::ops::Mul;
 use std::time::Duration;
 
+pub use heph_sched::Priority;
+
 /// Options for [spawning] an [`Actor`].
 ///
 /// [spawning]: crate::spawn::Spawn
@@ -33,21 +32,107 @@ use std::time::Duration;
 /// let opts = ActorOptions::default().with_priority(Priority::HIGH);
 /// # drop(opts); // Silence unused variable warning.
 /// ```
+///
+/// Requiring an actor to report it's ready before the runtime is considered
+/// ready.
+///
+/// ```
+/// use heph::spawn::ActorOptions;
+///
+/// let opts = ActorOptions::default().require_readiness();
+/// # drop(opts); // Silence unused variable warning.
+/// ```
+///
+/// Limiting an actor to 100 milliseconds of runtime per second.
+///
+/// ```
+/// use std::time::Duration;
+///
+/// use heph::spawn::ActorOptions;
+///
+/// let opts = ActorOptions::default().with_cpu_quota(Duration::from_millis(100));
+/// # drop(opts); // Silence unused variable warning.
+/// ```
+///
+/// Giving an actor a name used in diagnostics.
+///
+/// ```
+/// use heph::spawn::ActorOptions;
+///
+/// let opts = ActorOptions::default().with_name("session_store");
+/// # drop(opts); // Silence unused variable warning.
+/// ```
+///
+/// Catching panics of an actor, passing them to its supervisor.
+///
+/// ```
+/// use heph::spawn::ActorOptions;
+///
+/// let opts = ActorOptions::default().catch_panics();
+/// # drop(opts); // Silence unused variable warning.
+/// ```
+///
+/// The options can also be created in a `const` context, in which case invalid
+/// options (such as a CPU quota of zero) are a compile time error.
+///
+/// ```
+/// use heph::spawn::options::{ActorOptions, Priority};
+///
+/// const OPTIONS: ActorOptions = ActorOptions::new()
+///     .with_priority(Priority::HIGH)
+///     .with_name("session_store");
+/// # drop(OPTIONS); // Silence unused variable warning.
+/// ```
+///
+/// # Runtime defaults
+///
+/// The priority, CPU quota and catching of panics can also be set for all
+/// actors in a runtime using [`Setup::default_actor_options`]. Options not set
+/// when spawning an actor are inherited from these defaults.
+///
+/// [`Setup::default_actor_options`]: crate::rt::Setup::default_actor_options
 #[derive(Clone, Debug)]
+#[must_use = "options do nothing unless used to spawn an actor"]
 pub struct ActorOptions {
-    priority: Priority,
+    /// `None` means not set, i.e. inherited from the runtime's defaults.
+    priority: Option<Priority>,
     ready: bool,
+    readiness_required: bool,
+    cpu_quota: Option<Duration>,
+    name: Option<&'static str>,
+    /// `None` means not set, i.e. inherited from the runtime's defaults.
+    catch_panics: Option<bool>,
 }
 
 impl ActorOptions {
+    /// Default options, usable in `const` functions.
+    pub(crate) const DEFAULT: ActorOptions = ActorOptions {
+        priority: None,
+        ready: true,
+        readiness_required: false,
+        cpu_quota: None,
+        name: None,
+        catch_panics: None,
+    };
+
+    /// Create the default options.
+    ///
+    /// Same as [`ActorOptions::default`], but usable in a `const` context.
+    pub const fn new() -> ActorOptions {
+        ActorOptions::DEFAULT
+    }
+
     /// Returns the priority set in the options.
     pub const fn priority(&self) -> Priority {
-        self.priority
+        match self.priority {
+            Some(priority) => priority,
+            None => Priority::NORMAL,
+        }
     }
 
     /// Set the scheduling priority.
     pub const fn with_priority(mut self, priority: Priority) -> Self {
-        self.priority = priority;
+        self.priority = Some(priority);
         self
     }
 
@@ -69,104 +154,161 @@ impl ActorOptions {
     /// or write.
     ///
     /// [`TcpStream`]: crate::net::TcpStream
+    ///
+    /// An actor that isn't ready to run when spawned can't be required to
+    /// report it's ready, so marking the actor as not ready clears the
+    /// requirement set by [`require_readiness`].
+    ///
+    /// [`require_readiness`]: ActorOptions::require_readiness
     pub const fn mark_ready(mut self, ready: bool) -> Self {
         self.ready = ready;
+        if !ready {
+            self.readiness_required = false;
+        }
         self
     }
-}
 
-impl Default for ActorOptions {
-    fn default() -> ActorOptions {
-        ActorOptions {
-            priority: Priority::default(),
-            ready: true,
-        }
+    /// Returns `true` if the actor must report it's ready.
+    ///
+    /// See [`require_readiness`] for more information.
+    ///
+    /// [`require_readiness`]: ActorOptions::require_readiness
+    pub const fn readiness_required(&self) -> bool {
+        self.readiness_required
     }
-}
 
-/// Priority for an actor in the scheduler.
-///
-/// Actors with a higher priority will be scheduled to run more often and
-/// quicker (after they return [`Poll::Pending`]) then actors with a lower
-/// priority.
-///
-/// [`Poll::Pending`]: std::task::Poll::Pending
-#[derive(Copy, Clone, Debug, Eq, PartialEq)]
-#[repr(transparent)]
-pub struct Priority(NonZeroU8);
-
-impl Priority {
-    /// Low priority.
+    /// Require the actor to report it's ready, using
+    /// [`actor::Context::report_ready`], before the runtime is considered
+    /// ready.
     ///
-    /// Other actors have priority over this actor.
-    pub const LOW: Priority = Priority(NonZeroU8::new(15).unwrap());
-
-    /// Normal priority.
+    /// This makes "started" and "ready to serve" distinct states, e.g. an
+    /// actor can report it's ready once it loaded its data or connected to
+    /// its database. See [`RuntimeRef::is_ready`] and
+    /// [`actor::Context::wait_ready`].
     ///
-    /// Most actors should run at this priority, hence its also the default
-    /// priority.
-    pub const NORMAL: Priority = Priority(NonZeroU8::new(10).unwrap());
-
-    /// High priority.
+    /// [`actor::Context::report_ready`]: crate::actor::Context::report_ready
+    /// [`RuntimeRef::is_ready`]: crate::rt::RuntimeRef::is_ready
+    /// [`actor::Context::wait_ready`]: crate::actor::Context::wait_ready
     ///
-    /// Takes priority over other actors.
-    pub const HIGH: Priority = Priority(NonZeroU8::new(5).unwrap());
-}
-
-impl Default for Priority {
-    fn default() -> Priority {
-        Priority::NORMAL
+    /// This also marks the actor as ready to run when spawned, see
+    /// [`mark_ready`]. An actor that isn't ready to run would only run, and
+    /// thus be able to report it's ready, once some external event occurs,
+    /// which would make the readiness of the runtime depend on that event.
+    ///
+    /// [`mark_ready`]: ActorOptions::mark_ready
+    pub const fn require_readiness(mut self) -> Self {
+        self.ready = true;
+        self.readiness_required = true;
+        self
     }
-}
 
-impl Ord for Priority {
-    fn cmp(&self, other: &Self) -> Ordering {
-        other.0.cmp(&self.0)
+    /// Returns the CPU quota set in the options, if any.
+    ///
+    /// See [`with_cpu_quota`] for more information.
+    ///
+    /// [`with_cpu_quota`]: ActorOptions::with_cpu_quota
+    pub const fn cpu_quota(&self) -> Option<Duration> {
+        self.cpu_quota
     }
-}
 
-impl PartialOrd for Priority {
-    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
-        other.0.partial_cmp(&self.0)
+    /// Set the CPU quota of the actor, the maximum amount of time the actor
+    /// may run per second.
+    ///
+    /// Once an actor has used up its quota the scheduler will suspend it until
+    /// the next accounting window (of one second) starts. This protects other
+    /// actors, e.g. of other tenants in a multi-tenant service, from an actor
+    /// hogging the worker thread.
+    ///
+    /// By default actors don't have a CPU quota.
+    ///
+    /// # Notes
+    ///
+    /// The scheduler can't interrupt a running actor, so the quota is only
+    /// enforced after the actor returns control (i.e. returns
+    /// [`Poll::Pending`]). An actor can overrun its quota by the time it
+    /// runs in a single poll.
+    ///
+    /// [`Poll::Pending`]: std::task::Poll::Pending
+    ///
+    /// # Panics
+    ///
+    /// This will panic if `quota` is zero.
+    pub const fn with_cpu_quota(mut self, quota: Duration) -> Self {
+        assert!(!quota.is_zero(), "Can't use a CPU quota of zero");
+        self.cpu_quota = Some(quota);
+        self
     }
 
-    fn lt(&self, other: &Self) -> bool {
-        other.0 < self.0
+    /// Returns the name set in the options, if any.
+    ///
+    /// See [`with_name`] for more information.
+    ///
+    /// [`with_name`]: ActorOptions::with_name
+    pub const fn name(&self) -> Option<&'static str> {
+        self.name
     }
 
-    fn le(&self, other: &Self) -> bool {
-        other.0 <= self.0
+    /// Set the name of the actor.
+    ///
+    /// The name is used in diagnostics, such as logs, traces, panic messages
+    /// and [process statistics]. This is useful to tell apart multiple actors
+    /// of the same type.
+    ///
+    /// Defaults to [`NewActor::name`], which is based on the type name of the
+    /// actor.
+    ///
+    /// [process statistics]: crate::rt::RuntimeRef::process_stats
+    /// [`NewActor::name`]: crate::actor::NewActor::name
+    pub const fn with_name(mut self, name: &'static str) -> Self {
+        self.name = Some(name);
+        self
     }
 
-    fn gt(&self, other: &Self) -> bool {
-        other.0 > self.0
+    /// Returns `true` if panics of the actor are caught.
+    ///
+    /// See [`catch_panics`] for more information.
+    ///
+    /// [`catch_panics`]: ActorOptions::catch_panics
+    pub const fn catches_panics(&self) -> bool {
+        matches!(self.catch_panics, Some(true))
     }
 
-    fn ge(&self, other: &Self) -> bool {
-        other.0 >= self.0
+    /// Catch panics of the actor.
+    ///
+    /// By default a panicking actor takes down the worker thread it runs on,
+    /// and with it all other actors running on that thread. With this option
+    /// panics are caught and the panic's payload is passed to the actor's
+    /// supervisor, see [`Supervisor::decide_on_panic`], which decides to
+    /// restart or stop the actor.
+    ///
+    /// # Notes
+    ///
+    /// This only catches panics that unwind, it does nothing if the
+    /// application is compiled with `panic = "abort"`. The panic hook is still
+    /// called for caught panics, by default printing the panic message.
+    ///
+    /// [`Supervisor::decide_on_panic`]: crate::supervisor::Supervisor::decide_on_panic
+    pub const fn catch_panics(mut self) -> Self {
+        self.catch_panics = Some(true);
+        self
     }
-}
-
-/// Implementation detail, please ignore.
-#[doc(hidden)]
-impl Mul<Priority> for Duration {
-    type Output = Duration;
 
-    fn mul(self, rhs: Priority) -> Duration {
-        self * u32::from(rhs.0.get())
+    /// Inherit the options not set in `self` from the runtime's `defaults`,
+    /// see [`Setup::default_actor_options`].
+    ///
+    /// [`Setup::default_actor_options`]: crate::rt::Setup::default_actor_options
+    pub(crate) fn inherit(mut self, defaults: &ActorOptions) -> ActorOptions {
+        self.priority = self.priority.or(defaults.priority);
+        self.cpu_quota = self.cpu_quota.or(defaults.cpu_quota);
+        self.catch_panics = self.catch_panics.or(defaults.catch_panics);
+        self
     }
 }
 
-#[test]
-fn priority_duration_multiplication() {
-    let duration = Duration::from_millis(1);
-    let high = duration * Priority::HIGH;
-    let normal = duration * Priority::NORMAL;
-    let low = duration * Priority::LOW;
-
-    assert!(high < normal);
-    assert!(normal < low);
-    assert!(high < low);
+impl Default for ActorOptions {
+    fn default() -> ActorOptions {
+        ActorOptions::new()
+    }
 }
 
 /// Options for spawning a [`SyncActor`].
@@ -193,11 +335,19 @@ fn priority_duration_multiplication() {
 /// # drop(opts); // Silence unused variable warning.
 /// ```
 #[derive(Debug, Default)]
+#[must_use = "options do nothing unless used to spawn an actor"]
 pub struct SyncActorOptions {
     thread_name: Option<String>,
 }
 
 impl SyncActorOptions {
+    /// Create the default options.
+    ///
+    /// Same as [`SyncActorOptions::default`], but usable in a `const` context.
+    pub const fn new() -> SyncActorOptions {
+        SyncActorOptions { thread_name: None }
+    }
+
     /// Returns the name of the synchronous actor, if any.
     pub fn name(&self) -> Option<&str> {
         self.thread_name.as_deref()
@@ -242,11 +392,21 @@ impl SyncActorOptions {
 /// # drop(opts); // Silence unused variable warning.
 /// ```
 #[derive(Clone, Debug, Default)]
+#[must_use = "options do nothing unless used to spawn a future"]
 pub struct FutureOptions {
     priority: Priority,
 }
 
 impl FutureOptions {
+    /// Create the default options.
+    ///
+    /// Same as [`FutureOptions::default`], but usable in a `const` context.
+    pub const fn new() -> FutureOptions {
+        FutureOptions {
+            priority: Priority::NORMAL,
+        }
+    }
+
     /// Returns the priority set in the options.
     pub const fn priority(&self) -> Priority {
         self.priority
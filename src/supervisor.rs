@@ -28,10 +28,40 @@
 //! new argument can't be provided (think actors started by a [`TcpServer`]). In
 //! those cases the supervisor should still log the error encountered.
 //!
+//! A supervisor can also decide to restart the actor [after a delay], for
+//! example if the actor depends on an external service that is temporarily
+//! unavailable. Restarting it immediately would only hammer the service. See
+//! [`RestartPolicy`] for an exponential back-off strategy.
+//!
 //! [stopped]: crate::supervisor::SupervisorStrategy::Stop
 //! [restarted]: crate::supervisor::SupervisorStrategy::Restart
+//! [after a delay]: crate::supervisor::SupervisorStrategy::RestartAfter
+//! [`RestartPolicy`]: crate::supervisor::RestartPolicy
 //! [`TcpServer`]: crate::net::TcpServer
 //!
+//! # Supervision trees
+//!
+//! Actors spawned using [`actor::Context::spawn_child`] form a tree: once the
+//! parent actor stops, all its child actors are stopped as well. A supervisor
+//! of a child actor can decide to [escalate] a failure to the parent actor, in
+//! which case the parent's supervisor decides what to do (see
+//! [`Supervisor::decide_on_escalation`]), e.g. restarting the parent actor
+//! and with it all its children.
+//!
+//! [`actor::Context::spawn_child`]: crate::actor::Context::spawn_child
+//! [escalate]: crate::supervisor::SupervisorStrategy::Escalate
+//! [`Supervisor::decide_on_escalation`]: crate::supervisor::Supervisor::decide_on_escalation
+//!
+//! # Panics
+//!
+//! By default a panicking actor takes down the worker thread it runs on. Actors
+//! spawned with [`ActorOptions::catch_panics`] instead have their panics
+//! caught, after which their supervisor decides what to do (see
+//! [`Supervisor::decide_on_panic`]).
+//!
+//! [`ActorOptions::catch_panics`]: crate::spawn::ActorOptions::catch_panics
+//! [`Supervisor::decide_on_panic`]: crate::supervisor::Supervisor::decide_on_panic
+//!
 //! # Actors and sync actors
 //!
 //! As actors come in two flavours, [regular/asynchronous actors] and
@@ -94,8 +124,13 @@
 //! }
 //! ```
 
+use std::any::Any;
+use std::convert::TryFrom;
+use std::fmt;
+use std::time::{Duration, Instant};
+
 use crate::actor::SyncActor;
-use crate::actor::{Actor, NewActor};
+use crate::actor::{Actor, NewActor, ReplaceableArg};
 
 /// The supervisor of an [actor].
 ///
@@ -138,6 +173,12 @@ where
     /// To avoid creating such an infinite loop limit the amount times an actor
     /// can be restarted. Or use the [`restart_supervisor!`] macro to
     /// automatically create a supervisor that handles this for you.
+    ///
+    /// To throttle the restarts, e.g. when the actor depends on an external
+    /// service that is temporarily unavailable, use
+    /// [`SupervisorStrategy::RestartAfter`] to restart the actor only after a
+    /// delay. The actor's process is woken up once the delay has passed, not
+    /// blocking any other actors in the meantime.
     fn decide(&mut self, error: <NA::Actor as Actor>::Error) -> SupervisorStrategy<NA::Argument>;
 
     /// Decide what happens when an actor is restarted and the [`NewActor`]
@@ -161,6 +202,38 @@ where
     /// [`decide_on_restart_error`]: Supervisor::decide_on_restart_error
     // TODO: a better name.
     fn second_restart_error(&mut self, error: NA::Error);
+
+    /// Decide what happens to the actor when one of its child actors escalated
+    /// its failure, see [`SupervisorStrategy::Escalate`].
+    ///
+    /// Restarting the actor stops all its current child actors.
+    ///
+    /// Defaults to stopping the actor, which in turn stops all its child
+    /// actors.
+    fn decide_on_escalation(&mut self) -> SupervisorStrategy<NA::Argument> {
+        SupervisorStrategy::Stop
+    }
+
+    /// Decide what happens to the actor that panicked, `panic` is the panic's
+    /// payload (as returned by [`catch_unwind`]).
+    ///
+    /// This is only called for actors spawned with
+    /// [`ActorOptions::catch_panics`], panics in other actors are not caught.
+    ///
+    /// Read the documentation of [`decide`] to avoid an *infinite loop*.
+    ///
+    /// Defaults to stopping the actor.
+    ///
+    /// [`catch_unwind`]: std::panic::catch_unwind
+    /// [`ActorOptions::catch_panics`]: crate::spawn::ActorOptions::catch_panics
+    /// [`decide`]: Supervisor::decide
+    fn decide_on_panic(
+        &mut self,
+        panic: Box<dyn Any + Send + 'static>,
+    ) -> SupervisorStrategy<NA::Argument> {
+        drop(panic);
+        SupervisorStrategy::Stop
+    }
 }
 
 impl<F, NA> Supervisor<NA> for F
@@ -192,8 +265,237 @@ where
 pub enum SupervisorStrategy<Arg> {
     /// Restart the actor with the provided argument `Arg`.
     Restart(Arg),
+    /// Restart the actor with the provided argument `Arg`, after waiting for
+    /// the provided duration.
+    ///
+    /// Until the actor is restarted it won't be run, but it can still receive
+    /// messages. This can be used to throttle restarts of an actor that
+    /// depends on an (external) resource that is unavailable, see
+    /// [`RestartPolicy`].
+    RestartAfter(Arg, Duration),
     /// Stop the actor.
     Stop,
+    /// Stop the actor and escalate the failure to the parent actor, letting
+    /// the parent's supervisor decide what to do (see
+    /// [`Supervisor::decide_on_escalation`]).
+    ///
+    /// Only actors spawned using [`actor::Context::spawn_child`] have a parent
+    /// actor, for all other actors this is the same as [`Stop`].
+    ///
+    /// [`actor::Context::spawn_child`]: crate::actor::Context::spawn_child
+    /// [`Stop`]: SupervisorStrategy::Stop
+    Escalate,
+}
+
+impl<A, R> SupervisorStrategy<ReplaceableArg<A, R>> {
+    /// Replace the actor with the replacement actor, created with the provided
+    /// argument `arg`, see [`NewActor::with_replacement`].
+    ///
+    /// [`NewActor::with_replacement`]: crate::actor::NewActor::with_replacement
+    pub const fn replace(arg: R) -> SupervisorStrategy<ReplaceableArg<A, R>> {
+        SupervisorStrategy::Restart(ReplaceableArg::Replacement(arg))
+    }
+}
+
+/// Policy determining how long to wait before restarting an actor.
+///
+/// By default actors are restarted immediately, which can cause a failing
+/// actor to hammer an (external) service it depends on. Using
+/// [`RestartPolicy::exponential_backoff`] the delay between restarts increases
+/// with each consecutive restart.
+///
+/// This is used by the supervisors created by the [`restart_supervisor!`]
+/// macro, see their `with_restart_policy` method, but can also be used in
+/// manual [`Supervisor`] implementations in combination with
+/// [`SupervisorStrategy::RestartAfter`].
+///
+/// # Examples
+///
+/// ```
+/// use std::convert::TryFrom;
+use std::time::Duration;
+///
+/// use heph::supervisor::RestartPolicy;
+///
+/// let policy = RestartPolicy::exponential_backoff(
+///     Duration::from_millis(100), // Base delay.
+///     2,                          // Multiplier.
+///     Duration::from_secs(1),     // Maximum delay.
+/// );
+/// assert_eq!(policy.delay(0), Duration::from_millis(100));
+/// assert_eq!(policy.delay(1), Duration::from_millis(200));
+/// assert_eq!(policy.delay(2), Duration::from_millis(400));
+/// assert_eq!(policy.delay(3), Duration::from_millis(800));
+/// assert_eq!(policy.delay(4), Duration::from_secs(1));
+/// ```
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct RestartPolicy {
+    /// Delay before the first restart.
+    base_delay: Duration,
+    /// Multiplier applied to the delay for each consecutive restart.
+    multiplier: u32,
+    /// Maximum delay between restarts.
+    max_delay: Duration,
+}
+
+impl RestartPolicy {
+    /// Restart the actor immediately, the default.
+    pub const fn immediate() -> RestartPolicy {
+        RestartPolicy {
+            base_delay: Duration::from_secs(0),
+            multiplier: 1,
+            max_delay: Duration::from_secs(0),
+        }
+    }
+
+    /// Exponential back-off between restarts.
+    ///
+    /// The first restart is delayed by `base_delay`, each consecutive restart
+    /// the delay is multiplied by `multiplier`, up to `max_delay`.
+    pub const fn exponential_backoff(
+        base_delay: Duration,
+        multiplier: u32,
+        max_delay: Duration,
+    ) -> RestartPolicy {
+        RestartPolicy {
+            base_delay,
+            multiplier,
+            max_delay,
+        }
+    }
+
+    /// Returns the delay before the `restart`-th consecutive restart (zero
+    /// indexed).
+    pub fn delay(&self, restart: usize) -> Duration {
+        // `base_delay * multiplier ^ restart`, if that overflows it's larger
+        // than `max_delay`.
+        u32::try_from(restart)
+            .ok()
+            .and_then(|restart| self.multiplier.checked_pow(restart))
+            .and_then(|factor| self.base_delay.checked_mul(factor))
+            .map_or(self.max_delay, |delay| delay.min(self.max_delay))
+    }
+
+    /// Returns the strategy to restart the actor with `arg` for the
+    /// `restart`-th consecutive restart (zero indexed).
+    pub fn strategy<Arg>(&self, arg: Arg, restart: usize) -> SupervisorStrategy<Arg> {
+        let delay = self.delay(restart);
+        if delay == Duration::from_secs(0) {
+            SupervisorStrategy::Restart(arg)
+        } else {
+            SupervisorStrategy::RestartAfter(arg, delay)
+        }
+    }
+}
+
+impl Default for RestartPolicy {
+    fn default() -> RestartPolicy {
+        RestartPolicy::immediate()
+    }
+}
+
+/// Throttle for logging repeated errors.
+///
+/// When an actor fails repeatedly with the same error, e.g. because an
+/// (external) service it depends on is down, logging every error can flood the
+/// logs and slow down the logger. `LogThrottle` collapses the same error (based
+/// on its [`Display`] implementation) within a time window into a single
+/// message, followed by a "last message repeated N times" message once a
+/// different error is logged or the window has elapsed.
+///
+/// This is used by the supervisors created by the [`restart_supervisor!`]
+/// macro, see their `with_log_throttle` method, but can also be used in manual
+/// [`Supervisor`] implementations.
+///
+/// [`Display`]: fmt::Display
+///
+/// # Examples
+///
+/// ```
+/// use std::time::Duration;
+///
+/// use heph::supervisor::LogThrottle;
+///
+/// let mut throttle = LogThrottle::new(Duration::from_secs(10));
+/// // First error is always logged.
+/// assert_eq!(throttle.check("some error"), Some(0));
+/// // Same error within the window is not.
+/// assert_eq!(throttle.check("some error"), None);
+/// assert_eq!(throttle.check("some error"), None);
+/// // A different error is logged, after logging the number of times the
+/// // previous error was repeated.
+/// assert_eq!(throttle.check("other error"), Some(2));
+/// ```
+#[derive(Clone, Debug)]
+pub struct LogThrottle {
+    /// Window in which the same error is only logged once.
+    window: Duration,
+    /// Last logged error and the time it was logged.
+    last: Option<(String, Instant)>,
+    /// Number of times the last error was not logged.
+    repeated: usize,
+}
+
+impl LogThrottle {
+    /// Create a new `LogThrottle` that logs the same error at most once per
+    /// `window`.
+    pub const fn new(window: Duration) -> LogThrottle {
+        LogThrottle {
+            window,
+            last: None,
+            repeated: 0,
+        }
+    }
+
+    /// Don't throttle logging, the default.
+    pub const fn disabled() -> LogThrottle {
+        LogThrottle::new(Duration::from_secs(0))
+    }
+
+    /// Check if `err` should be logged.
+    ///
+    /// Returns `None` if the error should not be logged, because it's the
+    /// same error as the last logged error and the window hasn't elapsed yet.
+    /// Otherwise this returns the number of times the last error was not
+    /// logged, which should be logged (if non-zero) before logging `err`.
+    pub fn check<E>(&mut self, err: &E) -> Option<usize>
+    where
+        E: fmt::Display + ?Sized,
+    {
+        if self.window == Duration::from_secs(0) {
+            return Some(0);
+        }
+
+        let msg = err.to_string();
+        let now = Instant::now();
+        match &self.last {
+            Some((last_msg, logged_at))
+                if *last_msg == msg && now.duration_since(*logged_at) < self.window =>
+            {
+                self.repeated += 1;
+                None
+            }
+            _ => {
+                self.last = Some((msg, now));
+                Some(self.take_repeated())
+            }
+        }
+    }
+
+    /// Returns the number of times the last error was not logged, resetting
+    /// the count.
+    ///
+    /// This should be called before logging a message that is not checked
+    /// using [`LogThrottle::check`], e.g. when the actor is stopped.
+    pub fn take_repeated(&mut self) -> usize {
+        std::mem::replace(&mut self.repeated, 0)
+    }
+}
+
+impl Default for LogThrottle {
+    fn default() -> LogThrottle {
+        LogThrottle::disabled()
+    }
 }
 
 /// Supervisor for [synchronous actors].
@@ -217,6 +519,13 @@ where
     A: SyncActor,
 {
     /// Decide what happens to the actor that returned `error`.
+    ///
+    /// When restarting the actor after a delay, using
+    /// [`SupervisorStrategy::RestartAfter`], the actor's thread is parked
+    /// until the delay has passed. If the actor is asked to stop in the
+    /// meantime (see [`ActorRef::stop`]) it's not restarted.
+    ///
+    /// [`ActorRef::stop`]: crate::actor_ref::ActorRef::stop
     fn decide(&mut self, error: A::Error) -> SupervisorStrategy<A::Argument>;
 }
 
@@ -315,7 +624,12 @@ where
 ///   code).
 ///
 /// The new type can be created using the `new` function, e.g.
-/// `MySupervisor::new(args)`, see the example below.
+/// `MySupervisor::new(args)`, see the example below. By default the actor is
+/// restarted immediately, the `with_restart_policy` method can be used to set
+/// a different [`RestartPolicy`], e.g. to use an exponential back-off between
+/// restarts. By default all errors are logged, the `with_log_throttle` method
+/// can be used to collapse repeated errors within a time window, see
+/// [`LogThrottle`].
 ///
 /// [rust formatting rules]: std::fmt
 ///
@@ -339,6 +653,15 @@ where
 ///
 /// Similar messages will be logged if the actor fails to restart.
 ///
+/// When using a [`LogThrottle`], errors that are the same as the last logged
+/// error (within the window) are not logged. Instead the following message is
+/// logged once a different error is logged, the window elapsed or the actor is
+/// stopped.
+///
+/// ```text
+/// $actor_name failed: last message repeated $n times
+/// ```
+///
 /// # Examples
 ///
 /// The example below shows the simplest usage of the `restart_supervisor`
@@ -349,6 +672,7 @@ where
 /// use std::time::Duration;
 ///
 /// use heph::restart_supervisor;
+/// use heph::supervisor::{LogThrottle, RestartPolicy};
 ///
 /// // Creates the `MySupervisor` type.
 /// restart_supervisor!(
@@ -370,6 +694,22 @@ where
 /// // Create a new supervisor.
 /// let supervisor = MySupervisor::new(true, 23);
 /// # drop(supervisor);
+///
+/// // Create a new supervisor that waits between restarts, starting with 100
+/// // milliseconds and doubling the delay for each consecutive restart.
+/// let policy = RestartPolicy::exponential_backoff(
+///     Duration::from_millis(100),
+///     2,
+///     Duration::from_secs(5),
+/// );
+/// let supervisor = MySupervisor::new(true, 23).with_restart_policy(policy);
+/// # drop(supervisor);
+///
+/// // Create a new supervisor that logs the same error at most once per
+/// // minute.
+/// let supervisor = MySupervisor::new(true, 23)
+///     .with_log_throttle(LogThrottle::new(Duration::from_secs(60)));
+/// # drop(supervisor);
 /// ```
 #[macro_export]
 macro_rules! restart_supervisor {
@@ -446,6 +786,10 @@ macro_rules! __heph_restart_supervisor_impl {
                 last_restart: std::option::Option<std::time::Instant>,
                 /// Arguments used to restart the actor.
                 args: ( $( $arg ),* ),
+                /// Policy used to determine the delay between restarts.
+                restart_policy: $crate::supervisor::RestartPolicy,
+                /// Throttle for logging repeated errors.
+                log_throttle: $crate::supervisor::LogThrottle,
             }
         );
 
@@ -463,6 +807,29 @@ macro_rules! __heph_restart_supervisor_impl {
             $vis const MAX_DURATION: std::time::Duration = $max_duration;
 
             $crate::__heph_restart_supervisor_impl!(impl_new $vis $supervisor_name, ( $( $arg ),* ));
+
+            /// Set the policy used to determine the delay between restarts,
+            /// defaults to restarting immediately.
+            #[allow(dead_code)]
+            $vis fn with_restart_policy(mut self, restart_policy: $crate::supervisor::RestartPolicy) -> Self {
+                self.restart_policy = restart_policy;
+                self
+            }
+
+            /// Set the throttle used to collapse repeated errors in the logs,
+            /// defaults to logging all errors.
+            #[allow(dead_code)]
+            $vis fn with_log_throttle(mut self, log_throttle: $crate::supervisor::LogThrottle) -> Self {
+                self.log_throttle = log_throttle;
+                self
+            }
+
+            /// Returns the strategy to restart the actor, based on the number
+            /// of consecutive restarts.
+            fn restart_strategy(&self) -> $crate::SupervisorStrategy<( $( $arg ),* )> {
+                let restart = Self::MAX_RESTARTS - self.restarts_left - 1;
+                self.restart_policy.strategy(self.args.clone(), restart)
+            }
         }
 
         impl<NA> $crate::supervisor::Supervisor<NA> for $supervisor_name
@@ -476,16 +843,21 @@ macro_rules! __heph_restart_supervisor_impl {
             }
 
             fn decide_on_restart_error(&mut self, err: NA::Error) -> $crate::SupervisorStrategy<NA::Argument> {
+                $crate::trace::capture_error(&err);
                 self.last_restart = Some(std::time::Instant::now());
 
                 if self.restarts_left >= 1 {
                     self.restarts_left -= 1;
-                    $crate::log::_private::warn!(
-                        std::concat!($actor_name, " actor failed to restart, trying again ({}/{} restarts left): {}", $log_extra),
-                        self.restarts_left, $max_restarts, err, $( self.args $(. $log_arg_field )* ),*
-                    );
-                    $crate::SupervisorStrategy::Restart(self.args.clone())
+                    if let Some(repeated) = self.log_throttle.check(&err) {
+                        $crate::__heph_restart_supervisor_impl!(log_repeated $actor_name, repeated);
+                        $crate::log::_private::warn!(
+                            std::concat!($actor_name, " actor failed to restart, trying again ({}/{} restarts left): {}", $log_extra),
+                            self.restarts_left, $max_restarts, err, $( self.args $(. $log_arg_field )* ),*
+                        );
+                    }
+                    self.restart_strategy()
                 } else {
+                    $crate::__heph_restart_supervisor_impl!(log_repeated $actor_name, self.log_throttle.take_repeated());
                     $crate::log::_private::warn!(
                         std::concat!($actor_name, " actor failed to restart, stopping it (no restarts left): {}", $log_extra),
                         err, $( self.args $(. $log_arg_field )* ),*
@@ -495,6 +867,7 @@ macro_rules! __heph_restart_supervisor_impl {
             }
 
             fn second_restart_error(&mut self, err: NA::Error) {
+                $crate::__heph_restart_supervisor_impl!(log_repeated $actor_name, self.log_throttle.take_repeated());
                 $crate::log::_private::warn!(
                     std::concat!($actor_name, " actor failed to restart a second time, stopping it: {}", $log_extra),
                     err, $( self.args $(. $log_arg_field )* ),*
@@ -524,6 +897,7 @@ macro_rules! __heph_restart_supervisor_impl {
         $( args $(. $log_arg_field: tt )* ),*
         $(,)*
     ) => {
+        $crate::trace::capture_error(&$err);
         let now = std::time::Instant::now();
         let last_restart = $self.last_restart.replace(now);
 
@@ -538,12 +912,16 @@ macro_rules! __heph_restart_supervisor_impl {
 
         if $self.restarts_left >= 1 {
             $self.restarts_left -= 1;
-            $crate::log::_private::warn!(
-                std::concat!($actor_name, " failed, restarting it ({}/{} restarts left): {}", $log_extra),
-                $self.restarts_left, $max_restarts, $err, $( $self.args $(. $log_arg_field )* ),*
-            );
-            $crate::SupervisorStrategy::Restart($self.args.clone())
+            if let Some(repeated) = $self.log_throttle.check(&$err) {
+                $crate::__heph_restart_supervisor_impl!(log_repeated $actor_name, repeated);
+                $crate::log::_private::warn!(
+                    std::concat!($actor_name, " failed, restarting it ({}/{} restarts left): {}", $log_extra),
+                    $self.restarts_left, $max_restarts, $err, $( $self.args $(. $log_arg_field )* ),*
+                );
+            }
+            $self.restart_strategy()
         } else {
+            $crate::__heph_restart_supervisor_impl!(log_repeated $actor_name, $self.log_throttle.take_repeated());
             $crate::log::_private::warn!(
                 std::concat!($actor_name, " failed, stopping it (no restarts left): {}", $log_extra),
                 $err, $( $self.args $(. $log_arg_field )* ),*
@@ -552,6 +930,17 @@ macro_rules! __heph_restart_supervisor_impl {
         }
     };
 
+    // Log the number of times the last error was not logged, if any.
+    (log_repeated $actor_name: expr, $repeated: expr) => {
+        let repeated: std::primitive::usize = $repeated;
+        if repeated != 0 {
+            $crate::log::_private::warn!(
+                std::concat!($actor_name, " failed: last message repeated {} times"),
+                repeated
+            );
+        }
+    };
+
     // TODO: DRY the implementations below.
 
     // Unit (`()`) type as argument.
@@ -563,6 +952,8 @@ macro_rules! __heph_restart_supervisor_impl {
                 $supervisor_name {
                     restarts_left: Self::MAX_RESTARTS,
                     last_restart: None,
+                    restart_policy: $crate::supervisor::RestartPolicy::immediate(),
+                    log_throttle: $crate::supervisor::LogThrottle::disabled(),
                     args: (),
                 }
             }
@@ -577,6 +968,8 @@ macro_rules! __heph_restart_supervisor_impl {
                 $supervisor_name {
                     restarts_left: Self::MAX_RESTARTS,
                     last_restart: None,
+                    restart_policy: $crate::supervisor::RestartPolicy::immediate(),
+                    log_throttle: $crate::supervisor::LogThrottle::disabled(),
                     args: (arg),
                 }
             }
@@ -592,6 +985,8 @@ macro_rules! __heph_restart_supervisor_impl {
                 $supervisor_name {
                     restarts_left: Self::MAX_RESTARTS,
                     last_restart: None,
+                    restart_policy: $crate::supervisor::RestartPolicy::immediate(),
+                    log_throttle: $crate::supervisor::LogThrottle::disabled(),
                     args: (arg0, arg1),
                 }
             }
@@ -606,6 +1001,8 @@ macro_rules! __heph_restart_supervisor_impl {
                 $supervisor_name {
                     restarts_left: Self::MAX_RESTARTS,
                     last_restart: None,
+                    restart_policy: $crate::supervisor::RestartPolicy::immediate(),
+                    log_throttle: $crate::supervisor::LogThrottle::disabled(),
                     args: (arg0, arg1, arg2),
                 }
             }
@@ -620,6 +1017,8 @@ macro_rules! __heph_restart_supervisor_impl {
                 $supervisor_name {
                     restarts_left: Self::MAX_RESTARTS,
                     last_restart: None,
+                    restart_policy: $crate::supervisor::RestartPolicy::immediate(),
+                    log_throttle: $crate::supervisor::LogThrottle::disabled(),
                     args: (arg0, arg1, arg2, arg3),
                 }
             }
@@ -634,6 +1033,8 @@ macro_rules! __heph_restart_supervisor_impl {
                 $supervisor_name {
                     restarts_left: Self::MAX_RESTARTS,
                     last_restart: None,
+                    restart_policy: $crate::supervisor::RestartPolicy::immediate(),
+                    log_throttle: $crate::supervisor::LogThrottle::disabled(),
                     args: (arg0, arg1, arg2, arg3, arg4),
                 }
             }
@@ -648,6 +1049,8 @@ macro_rules! __heph_restart_supervisor_impl {
                 $supervisor_name {
                     restarts_left: Self::MAX_RESTARTS,
                     last_restart: None,
+                    restart_policy: $crate::supervisor::RestartPolicy::immediate(),
+                    log_throttle: $crate::supervisor::LogThrottle::disabled(),
                     args: (arg0, arg1, arg2, arg3, arg4, arg5),
                 }
             }
@@ -662,6 +1065,8 @@ macro_rules! __heph_restart_supervisor_impl {
                 $supervisor_name {
                     restarts_left: Self::MAX_RESTARTS,
                     last_restart: None,
+                    restart_policy: $crate::supervisor::RestartPolicy::immediate(),
+                    log_throttle: $crate::supervisor::LogThrottle::disabled(),
                     args,
                 }
             }
@@ -20,6 +20,9 @@
 //!  * Initialising actors:
 //!    * [`init_local_actor`]: initialise a thread-local actor.
 //!    * [`init_actor`]: initialise a thread-safe actor.
+//!  * Replaying recorded messages:
+//!    * `replay`: replay the messages recorded using
+//!      `actor::Context::record_messages` (requires the `record` feature).
 //!  * Polling:
 //!    * [`poll_actor`]: poll an [`Actor`].
 //!    * [`poll_future`]: poll a [`Future`].
@@ -48,6 +51,8 @@
 use std::future::Future;
 use std::lazy::SyncLazy;
 use std::mem::size_of;
+#[cfg(feature = "record")]
+use std::path::Path;
 use std::pin::Pin;
 use std::stream::Stream;
 use std::sync::atomic::{AtomicU8, AtomicUsize, Ordering};
@@ -58,9 +63,11 @@ use std::{fmt, io, slice, thread};
 
 use getrandom::getrandom;
 use heph_inbox::oneshot::new_oneshot;
-use heph_inbox::Manager;
 use log::warn;
 
+use crate::actor::inbox::Manager;
+#[cfg(feature = "record")]
+use crate::actor::record;
 use crate::actor::{self, Actor, NewActor, SyncActor, SyncWaker};
 use crate::actor_ref::{ActorGroup, ActorRef};
 use crate::rt::local::{Control, Runtime};
@@ -387,6 +394,7 @@ where
 {
     let (manager, sender, receiver) = Manager::new_small_channel();
     let ctx = actor::Context::new(receiver, ThreadLocal::new(TEST_PID, runtime()));
+    new_actor.validate(&arg)?;
     let actor = new_actor.new(ctx, arg)?;
     Ok((actor, manager, ActorRef::local(sender)))
 }
@@ -402,6 +410,7 @@ where
 {
     let (manager, sender, receiver) = Manager::new_small_channel();
     let ctx = actor::Context::new(receiver, ThreadSafe::new(TEST_PID, SHARED_INTERNAL.clone()));
+    new_actor.validate(&arg)?;
     let actor = new_actor.new(ctx, arg)?;
     Ok((actor, manager, ActorRef::local(sender)))
 }
@@ -487,6 +496,66 @@ where
     Actor::try_poll(actor, &mut ctx)
 }
 
+/// Replay the messages recorded using [`actor::Context::record_messages`].
+///
+/// This creates a new thread-local actor using `new_actor` and `arg`, and
+/// delivers all messages recorded in the file at `path` to it. The messages
+/// are delivered one at a time, polling the actor after each message (see
+/// [`poll_actor`]). Once all messages are delivered all actor references are
+/// dropped and the actor is polled a final time.
+///
+/// Returns the result of the last poll of the actor. If the actor completes
+/// before all messages are delivered the remaining messages are ignored.
+///
+/// [`actor::Context::record_messages`]: crate::actor::Context::record_messages
+///
+/// # Notes
+///
+/// Wake notifications will be ignored, same as with [`poll_actor`].
+#[cfg(feature = "record")]
+#[doc(cfg(all(feature = "test", feature = "record")))]
+pub fn replay<NA, P>(
+    path: P,
+    mut new_actor: NA,
+    arg: NA::Argument,
+) -> io::Result<Poll<Result<(), <NA::Actor as Actor>::Error>>>
+where
+    NA: NewActor<RuntimeAccess = ThreadLocal>,
+    NA::Message: serde::de::DeserializeOwned,
+    NA::Error: fmt::Display,
+    P: AsRef<Path>,
+{
+    let messages = record::read_messages::<NA::Message, _>(path)?;
+
+    let (_manager, sender, receiver) = Manager::new_small_channel();
+    let ctx = actor::Context::new(receiver, ThreadLocal::new(TEST_PID, runtime()));
+    let actor = new_actor
+        .validate(&arg)
+        .and_then(|()| new_actor.new(ctx, arg))
+        .map_err(|err| {
+            io::Error::new(
+                io::ErrorKind::Other,
+                format!("failed to create actor: {}", err),
+            )
+        })?;
+    let mut actor = Box::pin(actor);
+
+    for msg in messages {
+        if sender.try_send(msg).is_err() {
+            return Err(io::Error::new(
+                io::ErrorKind::Other,
+                "actor stopped receiving messages",
+            ));
+        }
+        if let Poll::Ready(result) = poll_actor(actor.as_mut()) {
+            return Ok(Poll::Ready(result));
+        }
+    }
+
+    drop(sender);
+    Ok(poll_actor(actor.as_mut()))
+}
+
 /// Percentage of messages lost on purpose.
 static MSG_LOSS: AtomicU8 = AtomicU8::new(0);
 
@@ -554,7 +623,7 @@ where
 ///     }
 /// }
 ///
-/// assert_eq!(size_of_actor_val(&(actor as fn(_) -> _)), 88);
+/// assert_eq!(size_of_actor_val(&(actor as fn(_) -> _)), 80);
 /// ```
 pub const fn size_of_actor_val<NA>(_: &NA) -> usize
 where
@@ -37,6 +37,9 @@ impl From<DeadlinePassed> for io::Error {
 /// the deadline has passed. If it returns [`Poll::Pending`] it's not yet
 /// passed.
 ///
+/// Dropping the timer, or calling [`Timer::cancel`], removes the deadline from
+/// the runtime, i.e. the actor will not be woken up by it.
+///
 /// # Examples
 ///
 /// ```
@@ -116,6 +119,24 @@ impl<RT: rt::Access> Timer<RT> {
         self.deadline <= Instant::now()
     }
 
+    /// Reset the timer to a new `deadline`.
+    ///
+    /// This replaces the deadline in the runtime, the previous deadline will
+    /// no longer wake the actor. The deadline may be earlier or later than the
+    /// current deadline, and the timer may have already passed.
+    pub fn reset(&mut self, deadline: Instant) {
+        self.rt.remove_deadline(self.deadline);
+        self.rt.add_deadline(deadline);
+        self.deadline = deadline;
+    }
+
+    /// Cancel the timer, removing the deadline from the runtime.
+    ///
+    /// This is the same as dropping the timer.
+    pub fn cancel(self) {
+        drop(self);
+    }
+
     /// Wrap a future creating a new `Deadline`.
     pub fn wrap<Fut>(self, future: Fut) -> Deadline<Fut, RT> {
         // We don't want to run the destructor as that would remove the
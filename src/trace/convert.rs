@@ -0,0 +1,590 @@
+//! Module to read and convert trace logs.
+//!
+//! [`convert`] converts a trace log, in the format described in the [Trace
+//! Format] design document, into [Chrome's Trace Event Format]. The converted
+//! trace can be opened by [Catapult trace view] and the [Perfetto UI].
+//!
+//! For other uses the trace log can also be read using [`Reader`], which
+//! returns all [`Event`]s in the trace.
+//!
+//! [Trace Format]: https://github.com/Thomasdezeeuw/heph/blob/master/doc/Trace%20Format.md
+//! [Chrome's Trace Event Format]: https://docs.google.com/document/d/1CvAClvFfyA5R-PhYUmn5OOQtYMH4h6I0nSsKchNAySU/preview
+//! [Catapult trace view]: https://chromium.googlesource.com/catapult/+/refs/heads/master/tracing/README.md
+//! [Perfetto UI]: https://ui.perfetto.dev
+//!
+//! # Examples
+//!
+//! Converting a trace log.
+//!
+//! ```no_run
+//! use std::fs::File;
+//! use std::io::BufWriter;
+//!
+//! use heph::trace;
+//!
+//! # fn main() -> Result<(), trace::convert::ParseError> {
+//! let input = File::open("trace.bin.log").map_err(trace::convert::ParseError::Io)?;
+//! let output = File::create("trace.json").map_err(trace::convert::ParseError::Io)?;
+//! trace::convert(input, BufWriter::new(output))?;
+//! # Ok(())
+//! # }
+//! ```
+
+use std::collections::hash_map::{Entry, HashMap};
+use std::convert::TryInto;
+use std::io::{self, Read, Write};
+use std::time::{Duration, SystemTime};
+use std::{fmt, str};
+
+use crate::trace::{EVENT_MAGIC, METADATA_MAGIC};
+
+/// Minimum amount of bytes in the buffer before we read again.
+const MIN_BUF_SIZE: usize = 128;
+
+// TODO: use `cmp::min`, once that stable as constant.
+const MIN_PACKET_SIZE: usize = if MIN_METADATA_PACKET_SIZE < MIN_EVENT_PACKET_SIZE {
+    MIN_METADATA_PACKET_SIZE
+} else {
+    MIN_EVENT_PACKET_SIZE
+};
+const MIN_METADATA_PACKET_SIZE: usize = 10;
+const MIN_EVENT_PACKET_SIZE: usize = 34;
+
+/// Convert the trace log read from `input` into [Chrome's Trace Event Format],
+/// writing it to `output`.
+///
+/// See the [module documentation] for more information.
+///
+/// [Chrome's Trace Event Format]: https://docs.google.com/document/d/1CvAClvFfyA5R-PhYUmn5OOQtYMH4h6I0nSsKchNAySU/preview
+/// [module documentation]: crate::trace::convert
+pub fn convert<R, W>(input: R, mut output: W) -> Result<(), ParseError>
+where
+    R: Read,
+    W: Write,
+{
+    let mut reader = Reader::new(input);
+    output
+        .write_all(b"{\n\t\"displayTimeUnit\": \"ns\",\n\t\"traceEvents\": [\n")
+        .map_err(ParseError::Io)?;
+
+    // Sometimes `Instant` returns a value that is equal to a previously
+    // returned value. Catapult can't really deal with this and creates two
+    // overlapping events, making them both unreadable and unusable.
+    // To fix this change the starting time and duration by a few microseconds
+    // to ensure the two events don't start at the same time.
+    //
+    // Maps `(pid, tid)` -> `timestamp` -> `duration`.
+    let mut times: HashMap<(u32, u64), HashMap<u128, u128>> = HashMap::new();
+
+    let mut first = true;
+    for event in reader.events() {
+        let event = event?;
+
+        let mut timestamp = event
+            .start
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .unwrap_or(Duration::ZERO)
+            .as_micros();
+        let mut duration = event
+            .end
+            .duration_since(event.start)
+            .unwrap_or(Duration::ZERO)
+            .as_micros();
+
+        let process_id = event.stream_id;
+        let thread_id = event.substream_id;
+        loop {
+            let key = (process_id, thread_id);
+            match times.entry(key).or_default().entry(timestamp) {
+                Entry::Vacant(entry) => {
+                    entry.insert(duration);
+                    break;
+                }
+                Entry::Occupied(entry) => {
+                    let other_duration = *entry.get();
+                    if other_duration > duration || timestamp == 0 {
+                        // Other event is the *overlapping* event. Delay the
+                        // start of this event and decrease it's duration.
+                        timestamp += 1;
+                        duration = duration.saturating_sub(1);
+                    } else {
+                        // This is the *overlapping* event, grow it.
+                        timestamp -= 1;
+                        duration += 1;
+                    }
+                }
+            }
+        }
+
+        let mut buf = String::new();
+        buf.push_str(if first { "\t\t{" } else { ",\n\t\t{" });
+        first = false;
+        buf.push_str(&format!(
+            "\"pid\": {}, \"tid\": {}, \"ts\": {}, \"dur\": {}, \"name\": ",
+            process_id, thread_id, timestamp, duration,
+        ));
+        push_json_string(&mut buf, &event.description);
+        if !event.attributes.is_empty() {
+            buf.push_str(", \"args\": {");
+            for (i, (name, value)) in event.attributes.iter().enumerate() {
+                if i != 0 {
+                    buf.push_str(", ");
+                }
+                push_json_string(&mut buf, name);
+                buf.push_str(": ");
+                match value {
+                    Value::Unsigned(value) => buf.push_str(&value.to_string()),
+                    Value::Signed(value) => buf.push_str(&value.to_string()),
+                    // JSON doesn't support NaN or infinity.
+                    Value::Float(value) if value.is_finite() => buf.push_str(&value.to_string()),
+                    Value::Float(value) => push_json_string(&mut buf, &value.to_string()),
+                    Value::String(value) => push_json_string(&mut buf, value),
+                }
+            }
+            buf.push('}');
+        }
+        buf.push_str(", \"ph\": \"X\", \"cat\": \"\"}");
+        output.write_all(buf.as_bytes()).map_err(ParseError::Io)?;
+    }
+
+    output
+        .write_all(b"\n\t]\n}\n")
+        .and_then(|()| output.flush())
+        .map_err(ParseError::Io)
+}
+
+/// Append `value` as JSON string (including quotes) to `buf`.
+fn push_json_string(buf: &mut String, value: &str) {
+    buf.push('"');
+    for c in value.chars() {
+        match c {
+            '"' => buf.push_str("\\\""),
+            '\\' => buf.push_str("\\\\"),
+            '\n' => buf.push_str("\\n"),
+            '\r' => buf.push_str("\\r"),
+            '\t' => buf.push_str("\\t"),
+            c if c.is_control() => buf.push_str(&format!("\\u{:04x}", c as u32)),
+            c => buf.push(c),
+        }
+    }
+    buf.push('"');
+}
+
+/// Reader of a trace log.
+///
+/// Use [`Reader::events`] to iterate over the [`Event`]s in the trace.
+#[derive(Debug)]
+pub struct Reader<R> {
+    reader: R,
+    epoch: SystemTime,
+    // TODO: use VecDeque?
+    buf: Vec<u8>,
+}
+
+impl<R> Reader<R> {
+    /// Create a new `Reader`, reading the trace log from `reader`.
+    pub fn new(reader: R) -> Reader<R> {
+        Reader {
+            reader,
+            epoch: SystemTime::now(),
+            buf: Vec::with_capacity(4096),
+        }
+    }
+
+    /// Returns an iterator over all events in the trace.
+    pub fn events<'r>(&'r mut self) -> Events<'r, R> {
+        Events { reader: self }
+    }
+}
+
+impl<R> Reader<R>
+where
+    R: Read,
+{
+    fn fill_buffer(&mut self) -> io::Result<()> {
+        let original_length = self.buf.len();
+        self.buf.resize(self.buf.capacity(), 0);
+        match self.reader.read(&mut self.buf[original_length..]) {
+            Ok(n) => {
+                self.buf.truncate(original_length + n);
+                Ok(())
+            }
+            Err(err) => {
+                self.buf.truncate(original_length);
+                Err(err)
+            }
+        }
+    }
+}
+
+/// Iterator behind [`Reader::events`].
+// TODO: when hitting error maybe seek until the next magic value and continue
+// from there?
+#[derive(Debug)]
+pub struct Events<'r, R> {
+    reader: &'r mut Reader<R>,
+}
+
+impl<'r, R> Iterator for Events<'r, R>
+where
+    R: Read,
+{
+    type Item = Result<Event, ParseError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let reader = &mut *self.reader;
+        if reader.buf.len() < MIN_BUF_SIZE {
+            if let Err(err) = reader.fill_buffer() {
+                return Some(Err(ParseError::Io(err)));
+            }
+        }
+
+        // Ensure we can read at least one packet.
+        if reader.buf.is_empty() {
+            return None;
+        } else if reader.buf.len() < MIN_PACKET_SIZE {
+            return Some(Err(ParseError::MissingPacketData {
+                got: reader.buf.len(),
+                want: MIN_PACKET_SIZE,
+            }));
+        }
+
+        let (_, magic) = parse_u32(&reader.buf);
+        match magic {
+            METADATA_MAGIC => {
+                if let Err(err) = self.apply_metadata_packet() {
+                    Some(Err(err))
+                } else {
+                    self.next()
+                }
+            }
+            EVENT_MAGIC => Some(self.parse_event_packet()),
+            magic => Some(Err(ParseError::InvalidMagic(magic))),
+        }
+    }
+}
+
+impl<'r, R> Events<'r, R>
+where
+    R: Read,
+{
+    fn apply_metadata_packet(&mut self) -> Result<(), ParseError> {
+        let reader = &mut *self.reader;
+        debug_assert_eq!(reader.buf[0..4], METADATA_MAGIC.to_be_bytes());
+
+        let (left, packet_size) = parse_u32(&reader.buf[4..]);
+        let packet_size = packet_size as usize;
+        if packet_size <= MIN_METADATA_PACKET_SIZE {
+            return Err(ParseError::PacketTooSmall {
+                packet_kind: "metadata",
+                got: packet_size,
+            });
+        } else if reader.buf.len() < packet_size {
+            return Err(ParseError::MissingPacketData {
+                want: packet_size,
+                got: reader.buf.len(),
+            });
+        }
+
+        let (left, option_name) =
+            parse_string(left).map_err(|err| err.into_error("metadata", "option name"))?;
+        match option_name {
+            "epoch" if left.len() < 8 => Err(ParseError::MissingPacketData {
+                got: left.len(),
+                want: 8,
+            }),
+            "epoch" => {
+                let (_, nanos) = parse_u64(left);
+                reader.epoch = SystemTime::UNIX_EPOCH + Duration::from_nanos(nanos);
+                // TODO: check that all bytes according to packet_size are
+                // processed.
+                drop(reader.buf.drain(..packet_size));
+                Ok(())
+            }
+            _ => Err(ParseError::UnknownOption(option_name.to_owned())),
+        }
+    }
+
+    fn parse_event_packet(&mut self) -> Result<Event, ParseError> {
+        let reader = &mut *self.reader;
+        debug_assert_eq!(reader.buf[0..4], EVENT_MAGIC.to_be_bytes());
+
+        let (left, packet_size) = parse_u32(&reader.buf[4..]);
+        let packet_size = packet_size as usize;
+        if packet_size <= MIN_EVENT_PACKET_SIZE {
+            return Err(ParseError::PacketTooSmall {
+                packet_kind: "event",
+                got: packet_size,
+            });
+        } else if reader.buf.len() < packet_size {
+            return Err(ParseError::MissingPacketData {
+                got: reader.buf.len(),
+                want: packet_size,
+            });
+        }
+
+        let (left, stream_id) = parse_u32(&left[..packet_size - 8]);
+        let (left, stream_counter) = parse_u32(left);
+        let (left, substream_id) = parse_u64(left);
+        let (left, start) = parse_timestamp(left, reader.epoch);
+        let (left, end) = parse_timestamp(left, reader.epoch);
+        let (mut left, description) =
+            parse_string(left).map_err(|err| err.into_error("event", "description"))?;
+        let description = description.to_owned();
+
+        let mut attributes = Vec::new();
+        while !left.is_empty() {
+            let (l, attribute_name) =
+                parse_string(left).map_err(|err| err.into_error("event", "attribute name"))?;
+            let (l, attribute_value) = parse_value(l).map_err(|err| match err {
+                ValueParseError::StringParseError(err) => {
+                    err.into_error("event", "attribute value")
+                }
+                ValueParseError::UnknownType(byte) => ParseError::UnknownValueType(byte),
+            })?;
+            left = l;
+            attributes.push((attribute_name.to_owned(), attribute_value));
+        }
+
+        // TODO: check all bytes from packet are read.
+        drop(reader.buf.drain(..packet_size));
+
+        Ok(Event {
+            stream_id,
+            stream_counter,
+            substream_id,
+            start,
+            end,
+            description,
+            attributes,
+        })
+    }
+}
+
+/// Parse a single `u32` from `bytes`.
+///
+/// # Panics
+///
+/// Panics if `bytes` is less than 4 bytes long.
+fn parse_u32(bytes: &[u8]) -> (&[u8], u32) {
+    let n = u32::from_be_bytes(bytes[0..4].try_into().unwrap());
+    (&bytes[4..], n)
+}
+
+/// Parse a single `u64` from `bytes`.
+///
+/// # Panics
+///
+/// Panics if `bytes` is less than 8 bytes long.
+fn parse_u64(bytes: &[u8]) -> (&[u8], u64) {
+    let n = u64::from_be_bytes(bytes[0..8].try_into().unwrap());
+    (&bytes[8..], n)
+}
+
+/// See [`parse_u64`].
+fn parse_i64(bytes: &[u8]) -> (&[u8], i64) {
+    let n = i64::from_be_bytes(bytes[0..8].try_into().unwrap());
+    (&bytes[8..], n)
+}
+
+/// See [`parse_u64`].
+fn parse_f64(bytes: &[u8]) -> (&[u8], f64) {
+    let n = f64::from_be_bytes(bytes[0..8].try_into().unwrap());
+    (&bytes[8..], n)
+}
+
+/// Parse a single timestamp from `bytes`.
+///
+/// # Panics
+///
+/// Panics if `bytes` is less than 8 bytes long.
+fn parse_timestamp(bytes: &[u8], epoch: SystemTime) -> (&[u8], SystemTime) {
+    let (left, nanos) = parse_u64(bytes);
+    let timestamp = epoch + Duration::from_nanos(nanos);
+    (left, timestamp)
+}
+
+/// Parse a single string from `bytes`.
+fn parse_string(bytes: &[u8]) -> Result<(&[u8], &str), StringParseError> {
+    if bytes.len() < 2 {
+        return Err(StringParseError::TooSmall);
+    }
+    let len = u16::from_be_bytes(bytes[0..2].try_into().unwrap()) as usize;
+    if bytes.len() - 2 < len {
+        Err(StringParseError::TooSmall)
+    } else {
+        let (string, left) = bytes[2..].split_at(len);
+        match str::from_utf8(string) {
+            Ok(string) => Ok((left, string)),
+            Err(..) => Err(StringParseError::InvalidUtf8),
+        }
+    }
+}
+
+enum StringParseError {
+    TooSmall,
+    InvalidUtf8,
+}
+
+impl StringParseError {
+    /// Convert the error into a [`ParseError`] for `field` in a packet of
+    /// `packet_kind`.
+    const fn into_error(self, packet_kind: &'static str, field: &'static str) -> ParseError {
+        match self {
+            StringParseError::TooSmall => ParseError::StringTooSmall { packet_kind, field },
+            StringParseError::InvalidUtf8 => ParseError::InvalidString { packet_kind, field },
+        }
+    }
+}
+
+/// Parse a single value from `bytes`.
+fn parse_value(bytes: &[u8]) -> Result<(&[u8], Value), ValueParseError> {
+    match bytes[0] {
+        0b001 => {
+            let (left, value) = parse_u64(&bytes[1..]);
+            Ok((left, Value::Unsigned(value)))
+        }
+        0b010 => {
+            let (left, value) = parse_i64(&bytes[1..]);
+            Ok((left, Value::Signed(value)))
+        }
+        0b011 => {
+            let (left, value) = parse_f64(&bytes[1..]);
+            Ok((left, Value::Float(value)))
+        }
+        0b100 => match parse_string(&bytes[1..]) {
+            Ok((left, value)) => Ok((left, Value::String(value.to_owned()))),
+            Err(err) => Err(ValueParseError::StringParseError(err)),
+        },
+        byte => Err(ValueParseError::UnknownType(byte)),
+        // TODO: parse slice of values.
+    }
+}
+
+enum ValueParseError {
+    StringParseError(StringParseError),
+    UnknownType(u8),
+}
+
+/// Error returned by [`convert`] and [`Reader`].
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum ParseError {
+    /// I/O error reading the trace or writing the output.
+    Io(io::Error),
+    /// Packet is incomplete.
+    MissingPacketData {
+        /// Number of bytes available.
+        got: usize,
+        /// Number of bytes required.
+        want: usize,
+    },
+    /// Packet starts with an invalid magic value.
+    InvalidMagic(u32),
+    /// Packet size is too small.
+    PacketTooSmall {
+        /// Kind of packet, metadata or event.
+        packet_kind: &'static str,
+        /// Size of the packet.
+        got: usize,
+    },
+    /// String in a packet is incomplete.
+    StringTooSmall {
+        /// Kind of packet, metadata or event.
+        packet_kind: &'static str,
+        /// Field of the string.
+        field: &'static str,
+    },
+    /// String in a packet is not valid UTF-8.
+    InvalidString {
+        /// Kind of packet, metadata or event.
+        packet_kind: &'static str,
+        /// Field of the string.
+        field: &'static str,
+    },
+    /// Unknown metadata option.
+    UnknownOption(String),
+    /// Unknown type of attribute value.
+    UnknownValueType(u8),
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        use ParseError::*;
+        match self {
+            Io(err) => write!(f, "error reading trace: {}", err),
+            MissingPacketData { got, want } => write!(
+                f,
+                "missing packet data, want {} bytes, got {} bytes",
+                want, got
+            ),
+            InvalidMagic(got_magic) => {
+                write!(f, "packet has invalid magic value '{:#}'", got_magic)
+            }
+            PacketTooSmall { packet_kind, got } => write!(
+                f,
+                "{} packet size too small, got {} bytes",
+                packet_kind, got
+            ),
+            StringTooSmall { packet_kind, field } => write!(
+                f,
+                "missing string data in {} packet, {} field",
+                packet_kind, field
+            ),
+            InvalidString { packet_kind, field } => write!(
+                f,
+                "invalid string in {} packet, {} field",
+                packet_kind, field
+            ),
+            UnknownOption(option_name) => write!(f, "unknown option name '{}'", option_name),
+            UnknownValueType(byte) => write!(f, "unknown value type byte '{:#}'", byte),
+        }
+    }
+}
+
+impl std::error::Error for ParseError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            ParseError::Io(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+/// Event read from a trace log.
+#[derive(Debug)]
+pub struct Event {
+    /// Id of the stream, see the [`trace`] module for the stream ids used by
+    /// Heph.
+    ///
+    /// [`trace`]: crate::trace
+    pub stream_id: u32,
+    /// Counter of the event within its stream.
+    pub stream_counter: u32,
+    /// Id of the substream, see the [`trace`] module for the substream ids
+    /// used by Heph.
+    ///
+    /// [`trace`]: crate::trace
+    pub substream_id: u64,
+    /// Start time of the event.
+    pub start: SystemTime,
+    /// End time of the event.
+    pub end: SystemTime,
+    /// Description of the event.
+    pub description: String,
+    /// Attributes of the event.
+    pub attributes: Vec<(String, Value)>,
+}
+
+/// Value of an attribute of an [`Event`].
+#[derive(Debug)]
+pub enum Value {
+    /// Unsigned integer.
+    Unsigned(u64),
+    /// Signed integer.
+    Signed(i64),
+    /// Floating point number.
+    Float(f64),
+    /// String.
+    String(String),
+}
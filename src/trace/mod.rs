@@ -42,6 +42,23 @@
 //! [`start_trace`]: Trace::start_trace
 //! [`finish_trace`]: Trace::finish_trace
 //!
+//! ## Allocation Spikes
+//!
+//! By using [`TraceAllocator`] as global allocator the runtime will add an
+//! "Allocation spike" event each time a single run of an actor allocates more
+//! than the configured threshold, either in bytes or in number of allocations.
+//! The event includes the name of the actor and the type of message it
+//! receives, making it easy to attribute allocation spikes to specific actors.
+//!
+//! ## Supervisor Decisions
+//!
+//! Each time a supervisor decides what to do with a failed actor the runtime
+//! adds a "Supervising actor" event. It includes the name of the actor, the
+//! cause (an error, a restart error, a panic or an escalation), the type of the
+//! error, the decision of the supervisor and, if captured using
+//! [`capture_error`], the description of the error. This makes it possible to
+//! link every restart to the error that caused it.
+//!
 //! ## Notes
 //!
 //! You might notice that the `start_trace` doesn't actually return
@@ -58,17 +75,40 @@
 //! design document describes the layout of the trace, found in the `doc`
 //! directory of the repository.
 //!
-//! However as it's a binary format it can be hard to read. So tools are
+//! However as it's a binary format it can be hard to read. So [`convert`] is
 //! provided to convert into [Chrome's Trace Event Format], which can be viewed
-//! using [Catapult]. [Example 8 "Runtime Tracing"] shows a complete example of
-//! this.
+//! using [Catapult] or the [Perfetto UI]. The `convert_trace` tool (in the
+//! `tools` directory of the repository) does the same from the command line.
+//! [Example 8 "Runtime Tracing"] shows a complete example of this.
+//!
+//! To process the trace in other ways it can be read using
+//! [`convert::Reader`].
+//!
+//! ## Streams and substreams
+//!
+//! The trace format splits events into streams and substreams. Heph uses the
+//! following stream ids:
+//!
+//!  * `0`: the coordinator, also used for events of thread-safe actors.
+//!  * `1..`: worker threads, the id is the same as the worker thread's id.
+//!  * `10000..`: synchronous actors, one stream per actor.
+//!
+//! Within a stream the substream id is the [`ProcessId`] of the actor (or
+//! future) that created the event. Events created by the runtime itself use
+//! substream id `0`. Synchronous actors always use substream id `1`, as each
+//! has its own stream.
+//!
+//! [`ProcessId`]: crate::rt::ProcessId
 //!
 //! [Trace Format]: https://github.com/Thomasdezeeuw/heph/blob/master/doc/Trace%20Format.md
 //! [Chrome's Trace Event Format]: https://docs.google.com/document/d/1CvAClvFfyA5R-PhYUmn5OOQtYMH4h6I0nSsKchNAySU/preview
 //! [Catapult]: https://chromium.googlesource.com/catapult/+/refs/heads/master/tracing/README.md
+//! [Perfetto UI]: https://ui.perfetto.dev
 //! [Example 8 "Runtime Tracing"]: https://github.com/Thomasdezeeuw/heph/blob/master/examples/README.md#8-runtime-tracing
 
-use std::cell::RefCell;
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::cell::{Cell, RefCell};
+use std::fmt::{self, Write as _};
 use std::fs::{File, OpenOptions};
 use std::io::{self, Write};
 use std::path::Path;
@@ -78,6 +118,11 @@ use std::time::{Instant, SystemTime};
 
 use log::warn;
 
+pub mod convert;
+
+#[doc(no_inline)]
+pub use convert::convert;
+
 /// Default buffer size, only needs to hold a single trace event.
 const BUF_SIZE: usize = 128;
 
@@ -85,6 +130,12 @@ const BUF_SIZE: usize = 128;
 const COORDINATOR_STREAM_ID: u32 = 0;
 /// Identifier used by the runtime to log events.
 const RT_SUBSTREAM_ID: u64 = 0;
+/// Magic constant at the start of a metadata packet.
+#[allow(clippy::unreadable_literal)]
+const METADATA_MAGIC: u32 = 0x75D11D4D;
+/// Magic constant at the start of an event packet.
+#[allow(clippy::unreadable_literal)]
+const EVENT_MAGIC: u32 = 0xC1FC1FB7;
 
 /// Trace events.
 ///
@@ -290,8 +341,6 @@ impl Log {
 
 /// Write an epoch metadata packet to `buf`.
 fn write_epoch_metadata(buf: &mut Vec<u8>, time: SystemTime) {
-    #[allow(clippy::unreadable_literal)]
-    const MAGIC: u32 = 0x75D11D4D;
     const PACKET_SIZE: u32 = 23;
     // Safety: `OPTION` is small enough to fit it's length in `u16`.
     #[allow(clippy::cast_possible_truncation)]
@@ -307,7 +356,7 @@ fn write_epoch_metadata(buf: &mut Vec<u8>, time: SystemTime) {
         .unwrap()
         .as_nanos() as u64;
 
-    buf.extend_from_slice(&MAGIC.to_be_bytes());
+    buf.extend_from_slice(&METADATA_MAGIC.to_be_bytes());
     buf.extend_from_slice(&PACKET_SIZE.to_be_bytes());
     buf.extend_from_slice(&OPTION_LENGTH.to_be_bytes());
     buf.extend_from_slice(OPTION);
@@ -443,9 +492,6 @@ fn format_event(
     substream_id: u64,
     event: &Event<'_>,
 ) {
-    #[allow(clippy::unreadable_literal)]
-    const MAGIC: u32 = 0xC1FC1FB7;
-
     let start_nanos: u64 = nanos_since_epoch(epoch, event.start);
     let end_nanos: u64 = nanos_since_epoch(epoch, event.end);
     let description: &[u8] = event.description.as_bytes();
@@ -454,7 +500,7 @@ fn format_event(
     let description_len: u16 = description.len() as u16;
 
     buf.clear();
-    buf.extend_from_slice(&MAGIC.to_be_bytes());
+    buf.extend_from_slice(&EVENT_MAGIC.to_be_bytes());
     buf.extend_from_slice(&0_u32.to_be_bytes()); // Written later.
     buf.extend_from_slice(&stream_id.to_be_bytes());
     buf.extend_from_slice(&stream_count.to_be_bytes());
@@ -736,3 +782,248 @@ mod private {
 
     impl<T, const N: usize> super::AttributeValue for [T; N] where T: super::AttributeValue + Default {}
 }
+
+/// Global allocator that tracks the allocations made by actors.
+///
+/// If tracing is enabled and a single run of an actor allocates at least
+/// [`max_bytes`] bytes or makes at least [`max_allocations`] allocations an
+/// "Allocation spike" trace event is added to the trace, see the [`trace`]
+/// module. If no threshold is set no events are added.
+///
+/// [`max_bytes`]: TraceAllocator::max_bytes
+/// [`max_allocations`]: TraceAllocator::max_allocations
+/// [`trace`]: crate::trace
+///
+/// # Examples
+///
+/// ```
+/// use heph::trace::TraceAllocator;
+///
+/// // Add a trace event if an actor allocates 1 MB or makes 1000 allocations
+/// // in a single run.
+/// #[global_allocator]
+/// static ALLOCATOR: TraceAllocator = TraceAllocator::new()
+///     .max_bytes(1024 * 1024)
+///     .max_allocations(1000);
+/// #
+/// # fn main() {}
+/// ```
+#[derive(Debug)]
+pub struct TraceAllocator<A = System> {
+    alloc: A,
+    max_bytes: usize,
+    max_allocations: usize,
+}
+
+impl TraceAllocator<System> {
+    /// Create a new `TraceAllocator` using the [`System`] allocator.
+    pub const fn new() -> TraceAllocator<System> {
+        TraceAllocator::with_allocator(System)
+    }
+}
+
+impl Default for TraceAllocator<System> {
+    fn default() -> TraceAllocator<System> {
+        TraceAllocator::new()
+    }
+}
+
+impl<A> TraceAllocator<A> {
+    /// Create a new `TraceAllocator` using `alloc` to do the actual
+    /// allocating.
+    pub const fn with_allocator(alloc: A) -> TraceAllocator<A> {
+        TraceAllocator {
+            alloc,
+            max_bytes: usize::MAX,
+            max_allocations: usize::MAX,
+        }
+    }
+
+    /// Add a trace event if a single run of an actor allocates at least `max`
+    /// bytes.
+    pub const fn max_bytes(mut self, max: usize) -> TraceAllocator<A> {
+        self.max_bytes = max;
+        self
+    }
+
+    /// Add a trace event if a single run of an actor makes at least `max`
+    /// allocations.
+    pub const fn max_allocations(mut self, max: usize) -> TraceAllocator<A> {
+        self.max_allocations = max;
+        self
+    }
+
+    /// Track an allocation of `size` bytes.
+    fn track(&self, size: usize) {
+        // NOTE: using `try_with` as this can be called while the thread-local
+        // storage is being destroyed.
+        let _ = ALLOCATIONS.try_with(|allocations| {
+            let bytes = allocations.bytes.get().saturating_add(size);
+            let count = allocations.count.get().saturating_add(1);
+            allocations.bytes.set(bytes);
+            allocations.count.set(count);
+            if bytes >= self.max_bytes || count >= self.max_allocations {
+                allocations.spike.set(true);
+            }
+        });
+    }
+}
+
+unsafe impl<A> GlobalAlloc for TraceAllocator<A>
+where
+    A: GlobalAlloc,
+{
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        self.track(layout.size());
+        self.alloc.alloc(layout)
+    }
+
+    unsafe fn alloc_zeroed(&self, layout: Layout) -> *mut u8 {
+        self.track(layout.size());
+        self.alloc.alloc_zeroed(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        self.alloc.dealloc(ptr, layout)
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        self.track(new_size);
+        self.alloc.realloc(ptr, layout, new_size)
+    }
+}
+
+thread_local! {
+    /// Allocations made on this thread since the last call to
+    /// [`reset_allocations`], tracked by [`TraceAllocator`].
+    static ALLOCATIONS: AllocationCounters = AllocationCounters {
+        bytes: Cell::new(0),
+        count: Cell::new(0),
+        spike: Cell::new(false),
+    };
+}
+
+/// Counters used in [`ALLOCATIONS`].
+struct AllocationCounters {
+    bytes: Cell<usize>,
+    count: Cell<usize>,
+    /// Whether or not one of the thresholds of [`TraceAllocator`] was reached.
+    spike: Cell<bool>,
+}
+
+/// Allocations made by a single run of an actor, see [`allocation_spike`].
+#[derive(Copy, Clone, Debug)]
+pub(crate) struct Allocations {
+    /// Total number of bytes allocated.
+    pub(crate) bytes: usize,
+    /// Number of allocations.
+    pub(crate) count: usize,
+}
+
+/// Reset the allocation counters of this thread, call before running an actor.
+pub(crate) fn reset_allocations() {
+    let _ = ALLOCATIONS.try_with(|allocations| {
+        allocations.bytes.set(0);
+        allocations.count.set(0);
+        allocations.spike.set(false);
+    });
+}
+
+/// Returns the allocations made since the last call to [`reset_allocations`]
+/// if they reached one of thresholds of [`TraceAllocator`].
+///
+/// Always returns `None` if [`TraceAllocator`] isn't used as global allocator.
+pub(crate) fn allocation_spike() -> Option<Allocations> {
+    ALLOCATIONS
+        .try_with(|allocations| {
+            allocations.spike.get().then(|| Allocations {
+                bytes: allocations.bytes.get(),
+                count: allocations.count.get(),
+            })
+        })
+        .ok()
+        .flatten()
+}
+
+/// Maximum length, in bytes, of an error captured by [`capture_error`].
+const MAX_ERROR_LEN: usize = 512;
+
+thread_local! {
+    /// Error captured by [`capture_error`], `None` if errors aren't being
+    /// captured, see [`start_error_capture`].
+    static CAPTURED_ERROR: RefCell<Option<String>> = RefCell::new(None);
+}
+
+/// Capture the description of `err` in the trace.
+///
+/// If tracing is enabled the runtime adds a "Supervising actor" trace event
+/// each time a supervisor decides what to do with a failed actor. When this
+/// function is called from within the supervisor the description of `err` is
+/// added to that event as the "error" attribute, making it possible to link
+/// each restart to the error that caused it. The description is limited to
+/// 512 bytes.
+///
+/// The supervisors created by [`restart_supervisor!`] call this for all errors.
+/// Outside of a supervisor, or if tracing is disabled, this does nothing.
+///
+/// [`restart_supervisor!`]: crate::restart_supervisor
+///
+/// # Examples
+///
+/// ```
+/// use heph::actor::{Actor, NewActor};
+/// use heph::supervisor::{Supervisor, SupervisorStrategy};
+/// use heph::trace;
+///
+/// struct MySupervisor;
+///
+/// impl<NA> Supervisor<NA> for MySupervisor
+/// where
+///     NA: NewActor<Argument = ()>,
+///     <NA::Actor as Actor>::Error: std::fmt::Debug,
+/// {
+///     fn decide(&mut self, err: <NA::Actor as Actor>::Error) -> SupervisorStrategy<()> {
+///         // Errors only implementing `Debug` can be captured using
+///         // `format_args!`.
+///         trace::capture_error(&format_args!("{:?}", err));
+///         SupervisorStrategy::Restart(())
+///     }
+///
+///     fn decide_on_restart_error(&mut self, _: NA::Error) -> SupervisorStrategy<()> {
+///         SupervisorStrategy::Stop
+///     }
+///
+///     fn second_restart_error(&mut self, _: NA::Error) {}
+/// }
+/// ```
+pub fn capture_error(err: &dyn fmt::Display) {
+    let _ = CAPTURED_ERROR.try_with(|captured| {
+        if let Some(captured) = &mut *captured.borrow_mut() {
+            captured.clear();
+            let _ = write!(captured, "{}", err);
+            if captured.len() > MAX_ERROR_LEN {
+                let mut len = MAX_ERROR_LEN;
+                while !captured.is_char_boundary(len) {
+                    len -= 1;
+                }
+                captured.truncate(len);
+            }
+        }
+    });
+}
+
+/// Start capturing errors passed to [`capture_error`] on this thread, call
+/// before calling a supervisor.
+pub(crate) fn start_error_capture() {
+    let _ = CAPTURED_ERROR.try_with(|captured| *captured.borrow_mut() = Some(String::new()));
+}
+
+/// Stop capturing errors, returning the error captured since the call to
+/// [`start_error_capture`] (an empty string if no error was captured).
+pub(crate) fn take_captured_error() -> String {
+    CAPTURED_ERROR
+        .try_with(|captured| captured.borrow_mut().take())
+        .ok()
+        .flatten()
+        .unwrap_or_default()
+}
@@ -23,12 +23,21 @@ mod functional {
     mod actor_context;
     mod actor_group;
     mod actor_ref;
+    mod bus;
     mod bytes;
+    mod codec;
+    mod config;
     mod from_message;
+    mod fs;
     mod future;
+    mod io;
+    mod metrics;
     mod pipe;
+    mod process;
     mod restart_supervisor;
     mod runtime;
+    mod serial;
+    mod shutdown;
     mod spawn;
     mod sync_actor;
     mod tcp;
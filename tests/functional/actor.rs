@@ -1,6 +1,12 @@
 //! Tests for the [`Actor`] trait.
 
-use heph::actor::{self, NewActor};
+use std::time::Duration;
+
+use heph::actor::{self, NewActor, ReplaceableArg};
+use heph::rt::ThreadLocal;
+use heph::spawn::ActorOptions;
+use heph::supervisor::SupervisorStrategy;
+use heph::test::{join, try_spawn_local, PanicSupervisor};
 
 #[test]
 fn future_output_result() {
@@ -18,4 +24,49 @@ fn future_output_tuple() {
     is_new_actor(actor as fn(_) -> _);
 }
 
+#[test]
+fn actor_macro() {
+    #[heph::actor(log_args)]
+    async fn actor(
+        mut ctx: actor::Context<usize, ThreadLocal>,
+        (a, b): (usize, usize),
+        expected: usize,
+    ) -> Result<(), String> {
+        let c = ctx.receive_next().await.map_err(|err| err.to_string())?;
+        assert_eq!(a + b + c, expected);
+        Ok(())
+    }
+
+    let actor = actor as fn(_, _, _) -> _;
+    is_new_actor(actor);
+    let actor_ref =
+        try_spawn_local(PanicSupervisor, actor, ((1, 2), 6), ActorOptions::default()).unwrap();
+    actor_ref.try_send(3_usize).unwrap();
+    join(&actor_ref, Duration::from_secs(1)).unwrap();
+}
+
+#[test]
+fn with_replacement() {
+    async fn primary(_: actor::Context<usize, ThreadLocal>) -> Result<(), ()> {
+        Err(())
+    }
+
+    async fn replacement(
+        mut ctx: actor::Context<usize, ThreadLocal>,
+        expected: usize,
+    ) -> Result<(), ()> {
+        let msg = ctx.receive_next().await.map_err(|_| ())?;
+        assert_eq!(msg, expected);
+        Ok(())
+    }
+
+    let new_actor = (primary as fn(_) -> _).with_replacement(replacement as fn(_, _) -> _);
+    is_new_actor(new_actor.clone());
+    let supervisor = |()| SupervisorStrategy::replace(123);
+    let arg = ReplaceableArg::Primary(());
+    let actor_ref = try_spawn_local(supervisor, new_actor, arg, ActorOptions::default()).unwrap();
+    actor_ref.try_send(123_usize).unwrap();
+    join(&actor_ref, Duration::from_secs(1)).unwrap();
+}
+
 fn is_new_actor<NA: NewActor>(_: NA) {}
@@ -1,13 +1,19 @@
 //! Tests for the `actor::Context`.
 
+#[cfg(feature = "record")]
+use std::path::PathBuf;
 use std::pin::Pin;
+#[cfg(feature = "record")]
+use std::sync::{Arc, Mutex};
 use std::task::Poll;
 
-use heph::actor::{self, NoMessages, RecvError};
+use heph::actor::messages::InboxWatermark;
+use heph::actor::{self, NoMessages, ReceiveMatchError, RecvError, DEFERRED_CAPACITY};
 use heph::rt::{Runtime, ThreadLocal, ThreadSafe};
 use heph::spawn::{ActorOptions, Spawn};
 use heph::supervisor::NoSupervisor;
 use heph::test::{init_local_actor, poll_actor};
+use heph::util::next;
 
 use crate::util::{assert_send, assert_sync};
 
@@ -45,6 +51,81 @@ fn local_actor_context() {
     assert_eq!(poll_actor(Pin::as_mut(&mut actor)), Poll::Ready(Ok(())));
 }
 
+async fn message_seq_actor(mut ctx: actor::Context<usize, ThreadLocal>) {
+    assert_eq!(ctx.message_seq(), None);
+    // Messages send before the actor started.
+    assert_eq!(ctx.try_receive_next(), Ok(0));
+    assert_eq!(ctx.message_seq(), Some(0));
+    assert_eq!(ctx.receive_next().await, Ok(1));
+    assert_eq!(ctx.message_seq(), Some(1));
+
+    // Priority messages are received first, but keep their sequence number.
+    let msg = ctx.receive_next().await.unwrap();
+    assert_eq!(msg, 3);
+    assert_eq!(ctx.message_seq(), Some(3));
+    assert_eq!(ctx.try_receive_next(), Ok(2));
+    assert_eq!(ctx.message_seq(), Some(2));
+
+    // Failing to receive a message doesn't change the sequence number.
+    assert_eq!(ctx.try_receive_next(), Err(RecvError::Empty));
+    assert_eq!(ctx.message_seq(), Some(2));
+}
+
+#[test]
+fn message_seq() {
+    let message_seq_actor = message_seq_actor as fn(_) -> _;
+    let (actor, actor_ref) = init_local_actor(message_seq_actor, ()).unwrap();
+    let mut actor = Box::pin(actor);
+
+    actor_ref.try_send(0_usize).unwrap();
+    actor_ref.try_send(1_usize).unwrap();
+    assert_eq!(poll_actor(Pin::as_mut(&mut actor)), Poll::Pending);
+
+    actor_ref.try_send(2_usize).unwrap();
+    actor_ref.send_priority(3_usize).unwrap();
+    assert_eq!(poll_actor(Pin::as_mut(&mut actor)), Poll::Ready(Ok(())));
+}
+
+#[cfg(feature = "record")]
+async fn record_actor(
+    mut ctx: actor::Context<String, ThreadLocal>,
+    record_path: Option<PathBuf>,
+    received: Arc<Mutex<Vec<String>>>,
+) {
+    if let Some(path) = record_path {
+        ctx.record_messages(path).unwrap();
+    }
+    while let Ok(msg) = ctx.receive_next().await {
+        received.lock().unwrap().push(msg);
+    }
+}
+
+#[test]
+#[cfg(feature = "record")]
+fn record_and_replay_messages() {
+    let path = crate::util::temp_file("record_and_replay_messages.json");
+    let record_actor = record_actor as fn(_, _, _) -> _;
+    let expected = vec!["Hello".to_owned(), "World".to_owned()];
+
+    let received = Arc::new(Mutex::new(Vec::new()));
+    let arg = (Some(path.clone()), received.clone());
+    let (actor, actor_ref) = init_local_actor(record_actor, arg).unwrap();
+    let mut actor = Box::pin(actor);
+    assert_eq!(poll_actor(Pin::as_mut(&mut actor)), Poll::Pending);
+    for msg in &expected {
+        actor_ref.try_send(msg.clone()).unwrap();
+        assert_eq!(poll_actor(Pin::as_mut(&mut actor)), Poll::Pending);
+    }
+    drop(actor_ref);
+    assert_eq!(poll_actor(Pin::as_mut(&mut actor)), Poll::Ready(Ok(())));
+    assert_eq!(*received.lock().unwrap(), expected);
+
+    let replayed = Arc::new(Mutex::new(Vec::new()));
+    let result = heph::test::replay(&path, record_actor, (None, replayed.clone())).unwrap();
+    assert_eq!(result, Poll::Ready(Ok(())));
+    assert_eq!(*replayed.lock().unwrap(), expected);
+}
+
 async fn actor_ref_actor(mut ctx: actor::Context<usize, ThreadLocal>) {
     assert_eq!(ctx.receive_next().await, Err(NoMessages));
 
@@ -102,3 +183,155 @@ fn thread_safe_try_spawn() {
     );
     runtime.start().unwrap();
 }
+
+async fn messages_actor(mut ctx: actor::Context<usize, ThreadLocal>) {
+    let mut messages = ctx.messages();
+    assert_eq!(next(&mut messages).await, Some(1));
+    assert_eq!(next(&mut messages).await, Some(2));
+    // Ends once all actor references are dropped.
+    assert_eq!(next(&mut messages).await, None);
+    drop(messages);
+    assert_eq!(ctx.try_receive_next(), Err(RecvError::Disconnected));
+}
+
+#[test]
+fn messages() {
+    let messages_actor = messages_actor as fn(_) -> _;
+    let (actor, actor_ref) = init_local_actor(messages_actor, ()).unwrap();
+    let mut actor = Box::pin(actor);
+
+    assert_eq!(poll_actor(Pin::as_mut(&mut actor)), Poll::Pending);
+    actor_ref.try_send(1_usize).unwrap();
+    assert_eq!(poll_actor(Pin::as_mut(&mut actor)), Poll::Pending);
+    actor_ref.try_send(2_usize).unwrap();
+    assert_eq!(poll_actor(Pin::as_mut(&mut actor)), Poll::Pending);
+
+    drop(actor_ref);
+    assert_eq!(poll_actor(Pin::as_mut(&mut actor)), Poll::Ready(Ok(())));
+}
+
+async fn peek_next_actor(mut ctx: actor::Context<usize, ThreadLocal>) {
+    assert_eq!(ctx.peek_next().await, Ok(&1));
+    // Peeking again returns the same message.
+    assert_eq!(ctx.peek_next().await, Ok(&1));
+    // The peeked message is received before any priority message send after
+    // peeking.
+    ctx.actor_ref().send_priority(3_usize).unwrap();
+    assert_eq!(ctx.receive_next().await, Ok(1));
+    assert_eq!(ctx.peek_next().await, Ok(&3));
+    assert_eq!(ctx.try_receive_next(), Ok(3));
+    assert_eq!(ctx.receive_next().await, Ok(2));
+    assert_eq!(ctx.peek_next().await, Err(NoMessages));
+}
+
+#[test]
+fn peek_next() {
+    let peek_next_actor = peek_next_actor as fn(_) -> _;
+    let (actor, actor_ref) = init_local_actor(peek_next_actor, ()).unwrap();
+    let mut actor = Box::pin(actor);
+
+    assert_eq!(poll_actor(Pin::as_mut(&mut actor)), Poll::Pending);
+    actor_ref.try_send(1_usize).unwrap();
+    actor_ref.try_send(2_usize).unwrap();
+    drop(actor_ref);
+    assert_eq!(poll_actor(Pin::as_mut(&mut actor)), Poll::Ready(Ok(())));
+}
+
+async fn receive_match_actor(mut ctx: actor::Context<usize, ThreadLocal>) {
+    let is_even = |msg: &usize| msg % 2 == 0;
+    assert_eq!(ctx.receive_match(is_even).await, Ok(2));
+    // Deferred messages are received in order, before new messages.
+    assert_eq!(ctx.receive_next().await, Ok(1));
+    assert_eq!(ctx.receive_match(is_even).await, Ok(4));
+    assert_eq!(ctx.try_receive_next(), Ok(3));
+    assert_eq!(ctx.receive_next().await, Ok(5));
+    // No more matching messages.
+    assert_eq!(
+        ctx.receive_match(is_even).await,
+        Err(ReceiveMatchError::NoMessages)
+    );
+    assert_eq!(ctx.receive_next().await, Ok(7));
+}
+
+#[test]
+fn receive_match() {
+    let receive_match_actor = receive_match_actor as fn(_) -> _;
+    let (actor, actor_ref) = init_local_actor(receive_match_actor, ()).unwrap();
+    let mut actor = Box::pin(actor);
+
+    actor_ref.try_send(1_usize).unwrap();
+    assert_eq!(poll_actor(Pin::as_mut(&mut actor)), Poll::Pending);
+    actor_ref.try_send(2_usize).unwrap();
+    actor_ref.try_send(3_usize).unwrap();
+    actor_ref.try_send(4_usize).unwrap();
+    assert_eq!(poll_actor(Pin::as_mut(&mut actor)), Poll::Pending);
+    actor_ref.try_send(5_usize).unwrap();
+    actor_ref.try_send(7_usize).unwrap();
+    drop(actor_ref);
+    assert_eq!(poll_actor(Pin::as_mut(&mut actor)), Poll::Ready(Ok(())));
+}
+
+async fn receive_match_capacity_actor(mut ctx: actor::Context<usize, ThreadLocal>) {
+    let never = |_: &usize| false;
+    // Once the deferred messages are at capacity no more messages are taken
+    // out of the inbox.
+    assert_eq!(
+        ctx.receive_match(never).await,
+        Err(ReceiveMatchError::DeferredFull)
+    );
+    for expected in 0..=DEFERRED_CAPACITY {
+        assert_eq!(ctx.receive_next().await, Ok(expected));
+    }
+}
+
+#[test]
+fn receive_match_deferred_capacity() {
+    let receive_match_capacity_actor = receive_match_capacity_actor as fn(_) -> _;
+    let (actor, actor_ref) = init_local_actor(receive_match_capacity_actor, ()).unwrap();
+    let mut actor = Box::pin(actor);
+
+    for msg in 0..DEFERRED_CAPACITY {
+        actor_ref.try_send(msg).unwrap();
+        assert_eq!(poll_actor(Pin::as_mut(&mut actor)), Poll::Pending);
+    }
+    actor_ref.try_send(DEFERRED_CAPACITY).unwrap();
+    assert_eq!(poll_actor(Pin::as_mut(&mut actor)), Poll::Ready(Ok(())));
+}
+
+async fn receive_match_watermarks_actor(mut ctx: actor::Context<usize, ThreadLocal>) {
+    assert_eq!(ctx.receive_match(|msg: &usize| *msg == 4).await, Ok(4));
+    for expected in 1..=3 {
+        assert_eq!(ctx.receive_next().await, Ok(expected));
+    }
+}
+
+async fn watermark_watcher(mut ctx: actor::Context<InboxWatermark, ThreadLocal>) {
+    assert_eq!(ctx.receive_next().await, Ok(InboxWatermark::High));
+    assert_eq!(ctx.receive_next().await, Ok(InboxWatermark::Low));
+}
+
+#[test]
+fn receive_match_watermarks() {
+    let receive_match_watermarks_actor = receive_match_watermarks_actor as fn(_) -> _;
+    let (actor, actor_ref) = init_local_actor(receive_match_watermarks_actor, ()).unwrap();
+    let mut actor = Box::pin(actor);
+    let watermark_watcher = watermark_watcher as fn(_) -> _;
+    let (watcher, watcher_ref) = init_local_actor(watermark_watcher, ()).unwrap();
+    let mut watcher = Box::pin(watcher);
+    watcher_ref.watch_inbox(&actor_ref, 1, 3);
+
+    // Reaching the high watermark.
+    for msg in 1..=3_usize {
+        actor_ref.try_send(msg).unwrap();
+    }
+    assert_eq!(poll_actor(Pin::as_mut(&mut watcher)), Poll::Pending);
+
+    // Deferred messages still count towards the watermarks.
+    assert_eq!(poll_actor(Pin::as_mut(&mut actor)), Poll::Pending);
+    assert_eq!(poll_actor(Pin::as_mut(&mut watcher)), Poll::Pending);
+
+    // Receiving the deferred messages should cross the low watermark.
+    actor_ref.try_send(4_usize).unwrap();
+    assert_eq!(poll_actor(Pin::as_mut(&mut actor)), Poll::Ready(Ok(())));
+    assert_eq!(poll_actor(Pin::as_mut(&mut watcher)), Poll::Ready(Ok(())));
+}
@@ -33,6 +33,8 @@ fn empty() {
     let group = ActorGroup::<()>::empty();
     assert!(group.try_send((), Delivery::ToAll).is_err());
     assert!(group.try_send((), Delivery::ToOne).is_err());
+    assert!(group.try_send_priority((), Delivery::ToAll).is_err());
+    assert!(group.try_send_priority((), Delivery::ToOne).is_err());
     assert_eq!(group.len(), 0);
     assert!(group.is_empty());
 }
@@ -330,6 +332,25 @@ fn send_delivery_to_all() {
     }
 }
 
+#[test]
+fn send_priority_delivery_to_all() {
+    let mut actors = Vec::new();
+    let mut group = ActorGroup::empty();
+    for _ in 0..10 {
+        let expect_msgs = expect_msgs as fn(_, _) -> _;
+        let (actor, actor_ref) = init_local_actor(expect_msgs, vec![2usize, 1]).unwrap();
+        actors.push(Box::pin(actor));
+        group.add(actor_ref);
+    }
+
+    // The priority message should be received first.
+    assert!(group.try_send(1usize, Delivery::ToAll).is_ok());
+    assert!(group.try_send_priority(2usize, Delivery::ToAll).is_ok());
+    for mut actor in actors {
+        assert_eq!(poll_actor(Pin::as_mut(&mut actor)), Poll::Ready(Ok(())));
+    }
+}
+
 #[test]
 fn send_delivery_to_one() {
     const N: usize = 10;
@@ -350,6 +371,29 @@ fn send_delivery_to_one() {
     }
 }
 
+#[test]
+fn send_delivery_to_one_skips_stopped_actors() {
+    let mut actors = Vec::new();
+    let mut group = ActorGroup::empty();
+    for _ in 0..3 {
+        let expect_msgs = expect_msgs as fn(_, _) -> _;
+        let (actor, actor_ref) = init_local_actor(expect_msgs, vec![123usize]).unwrap();
+        actors.push(Box::pin(actor));
+        group.add(actor_ref);
+    }
+
+    // Stop all but the last actor.
+    let mut actor = actors.pop().unwrap();
+    drop(actors);
+
+    assert!(group.try_send(123usize, Delivery::ToOne).is_ok());
+    assert_eq!(poll_actor(Pin::as_mut(&mut actor)), Poll::Ready(Ok(())));
+
+    // No actors left to send to.
+    drop(actor);
+    assert!(group.try_send(123usize, Delivery::ToOne).is_err());
+}
+
 async fn stop_on_run(ctx: actor::Context<Infallible, ThreadLocal>) {
     drop(ctx);
 }
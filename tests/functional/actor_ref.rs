@@ -5,25 +5,32 @@ use std::fmt;
 use std::num::NonZeroUsize;
 use std::pin::Pin;
 use std::task::Poll;
+use std::time::{Duration, Instant};
 
-use heph::actor_ref::{ActorRef, Join, RpcError, RpcMessage, SendError, SendValue};
+use heph::actor::messages::{ActorStopped, InboxWatermark, StopReason};
+use heph::actor_ref::{
+    ActorRef, Caller, Delivered, Join, ReceiptKind, Receipted, RpcError, RpcMessage, SendError,
+    SendValue,
+};
 use heph::rt::{Runtime, ThreadLocal};
 use heph::spawn::options::Priority;
 use heph::supervisor::NoSupervisor;
-use heph::test::{init_local_actor, poll_actor, poll_future};
+use heph::test::{self, init_local_actor, poll_actor, poll_future};
 use heph::{actor, ActorOptions};
 
 use crate::util::{assert_send, assert_size, assert_sync, pending_once};
 
 /// Default size of the inbox, keep in sync with the inbox crate.
 const INBOX_SIZE: usize = 8;
+/// Size of the priority lane of the inbox.
+const PRIORITY_SIZE: usize = 8;
 
 const MSGS: &[&str] = &["Hello world", "Hello mars", "Hello moon"];
 
 #[test]
 fn size() {
     assert_size::<ActorRef<()>>(24);
-    assert_size::<SendValue<'_, ()>>(48);
+    assert_size::<SendValue<'_, ()>>(56);
     assert_size::<Join<'_, ()>>(40);
 }
 
@@ -183,6 +190,73 @@ fn cloned() {
     assert_eq!(poll_actor(Pin::as_mut(&mut actor)), Poll::Ready(Ok(())));
 }
 
+#[test]
+fn send_priority() {
+    let expected = vec![100, 101, 0, 1, 2];
+    let expect_msgs = expect_msgs as fn(_, _) -> _;
+    let (actor, actor_ref) = init_local_actor(expect_msgs, expected).unwrap();
+    let mut actor = Box::pin(actor);
+
+    for msg in 0..3_usize {
+        actor_ref.try_send(msg).unwrap();
+    }
+    // Priority messages should be received first.
+    actor_ref.send_priority(100_usize).unwrap();
+    actor_ref.send_priority(101_usize).unwrap();
+
+    assert_eq!(poll_actor(Pin::as_mut(&mut actor)), Poll::Ready(Ok(())));
+}
+
+#[test]
+fn send_priority_full() {
+    let expected: Vec<usize> = (0..PRIORITY_SIZE).collect();
+    let expect_msgs = expect_msgs as fn(_, _) -> _;
+    let (actor, actor_ref) = init_local_actor(expect_msgs, expected.clone()).unwrap();
+    let mut actor = Box::pin(actor);
+
+    for msg in expected {
+        actor_ref.send_priority(msg).unwrap();
+    }
+    assert_eq!(actor_ref.send_priority(PRIORITY_SIZE), Err(SendError));
+
+    assert_eq!(poll_actor(Pin::as_mut(&mut actor)), Poll::Ready(Ok(())));
+}
+
+#[test]
+fn send_priority_disconnected() {
+    let expect_msgs = expect_msgs as fn(_, Vec<usize>) -> _;
+    let (actor, actor_ref) = init_local_actor(expect_msgs, Vec::new()).unwrap();
+    drop(actor);
+    assert_eq!(actor_ref.send_priority(1usize), Err(SendError));
+}
+
+#[test]
+fn send_priority_wakes_actor() {
+    let expected = vec![1_usize];
+    let expect_msgs = expect_msgs as fn(_, _) -> _;
+    let (actor, actor_ref) = init_local_actor(expect_msgs, expected).unwrap();
+    let mut actor = Box::pin(actor);
+
+    assert_eq!(poll_actor(Pin::as_mut(&mut actor)), Poll::Pending);
+    actor_ref.send_priority(1_usize).unwrap();
+    assert_eq!(poll_actor(Pin::as_mut(&mut actor)), Poll::Ready(Ok(())));
+}
+
+#[test]
+fn mapped_send_priority() {
+    let expect_msgs = expect_msgs as fn(_, _) -> _;
+    let expected = vec!["priority".to_owned(), "regular".to_owned()];
+    let (actor, actor_ref): (_, ActorRef<String>) =
+        init_local_actor(expect_msgs, expected).unwrap();
+    let mut actor = Box::pin(actor);
+
+    let actor_ref: ActorRef<&str> = actor_ref.map();
+    actor_ref.try_send("regular").unwrap();
+    actor_ref.send_priority("priority").unwrap();
+
+    assert_eq!(poll_actor(Pin::as_mut(&mut actor)), Poll::Ready(Ok(())));
+}
+
 #[test]
 fn mapped() {
     let expect_msgs = expect_msgs as fn(_, _) -> _;
@@ -840,6 +914,44 @@ fn rpc_response_is_connected() {
     );
 }
 
+async fn pong_canceled(mut ctx: actor::Context<RpcTestMessage, ThreadLocal>) {
+    while let Ok(msg) = ctx.receive_next().await {
+        match msg {
+            RpcTestMessage::Ping(RpcMessage { response, .. }) => {
+                assert!(!response.is_canceled());
+                // Wait until the caller is no longer interested.
+                response.canceled().await;
+                assert!(response.is_canceled());
+                assert!(!response.is_connected());
+            }
+            RpcTestMessage::Check => {}
+        }
+    }
+}
+
+#[test]
+fn rpc_response_canceled() {
+    let pong = pong_canceled as fn(_) -> _;
+    let (pong_actor, relay_ref) = init_local_actor(pong, ()).unwrap();
+    let mut pong_actor = Box::pin(pong_actor);
+
+    let ping = ping as fn(_, _) -> _;
+    let (ping_actor, _) = init_local_actor(ping, relay_ref).unwrap();
+    let mut ping_actor = Box::pin(ping_actor);
+
+    // Send RPC requests.
+    assert_eq!(poll_actor(Pin::as_mut(&mut ping_actor)), Poll::Pending);
+    // Wait for the cancellation.
+    assert_eq!(poll_actor(Pin::as_mut(&mut pong_actor)), Poll::Pending);
+    assert_eq!(poll_actor(Pin::as_mut(&mut pong_actor)), Poll::Pending);
+    // Cancel the RPC.
+    drop(ping_actor);
+    assert_eq!(
+        poll_actor(Pin::as_mut(&mut pong_actor)),
+        Poll::Ready(Ok(()))
+    );
+}
+
 #[test]
 fn rpc_error_format() {
     assert_eq!(format!("{}", RpcError::SendError), "unable to send message");
@@ -895,6 +1007,119 @@ fn rpc_waking() {
     runtime.start().unwrap();
 }
 
+async fn caller_server(mut ctx: actor::Context<RpcMessage<(), Option<Caller>>, ThreadLocal>) {
+    while let Ok(msg) = ctx.receive_next().await {
+        let caller = msg.caller().copied();
+        msg.response.respond(caller).unwrap();
+    }
+}
+
+async fn caller_client(
+    mut ctx: actor::Context<!, ThreadLocal>,
+    actor_ref: ActorRef<RpcMessage<(), Option<Caller>>>,
+) {
+    let caller = actor_ref.rpc(()).await.unwrap().unwrap();
+    assert!(caller.name().contains("caller_client"), "{}", caller.name());
+    assert_eq!(caller.deadline(), None);
+    assert!(!caller.deadline_passed());
+
+    let deadline = Instant::now() + Duration::from_secs(10);
+    ctx.set_deadline(deadline);
+    let caller2 = actor_ref.rpc(()).await.unwrap().unwrap();
+    assert_eq!(caller2.pid(), caller.pid());
+    assert_eq!(caller2.deadline(), Some(deadline));
+    assert!(!caller2.deadline_passed());
+}
+
+#[test]
+fn rpc_caller() {
+    let mut runtime = Runtime::setup()
+        .num_threads(1)
+        .enable_deadline_scheduling()
+        .build()
+        .unwrap();
+    runtime
+        .run_on_workers::<_, !>(|mut runtime_ref| {
+            let caller_server = caller_server as fn(_) -> _;
+            let options = ActorOptions::default();
+            let actor_ref = runtime_ref.spawn_local(NoSupervisor, caller_server, (), options);
+
+            let caller_client = caller_client as fn(_, _) -> _;
+            let options = ActorOptions::default();
+            let _ = runtime_ref.spawn_local(NoSupervisor, caller_client, actor_ref, options);
+            Ok(())
+        })
+        .unwrap();
+    runtime.start().unwrap();
+}
+
+#[test]
+fn rpc_caller_outside_runtime() {
+    let caller_server = caller_server as fn(_) -> _;
+    let (server, actor_ref) = init_local_actor(caller_server, ()).unwrap();
+    let mut server = Box::pin(server);
+
+    let mut rpc = Box::pin(actor_ref.rpc(()));
+    assert_eq!(poll_future(Pin::as_mut(&mut rpc)), Poll::Pending);
+    assert_eq!(poll_actor(Pin::as_mut(&mut server)), Poll::Pending);
+    assert_eq!(poll_future(Pin::as_mut(&mut rpc)), Poll::Ready(Ok(None)));
+}
+
+async fn receipt_receiver(mut ctx: actor::Context<Receipted<usize>, ThreadLocal>) {
+    while let Ok(msg) = ctx.receive_next().await {
+        // Only acknowledge even messages.
+        if *msg.get_ref() % 2 == 0 {
+            let _ = msg.into_inner();
+        }
+    }
+}
+
+#[test]
+fn send_with_receipt() {
+    let receiver = receipt_receiver as fn(_) -> _;
+    let (actor, actor_ref) = init_local_actor(receiver, ()).unwrap();
+    let mut actor = Box::pin(actor);
+
+    // Enqueued receipts don't depend on the actor.
+    let mut receipt = Box::pin(actor_ref.send_with_receipt(1, ReceiptKind::Enqueued));
+    assert_eq!(
+        poll_future(Pin::as_mut(&mut receipt)),
+        Poll::Ready(Ok(Delivered))
+    );
+
+    let mut receipt1 = Box::pin(actor_ref.send_with_receipt(2, ReceiptKind::Received));
+    let mut receipt2 = Box::pin(actor_ref.send_with_receipt(3, ReceiptKind::Received));
+    // Messages are enqueued, but not yet received.
+    assert_eq!(poll_future(Pin::as_mut(&mut receipt1)), Poll::Pending);
+    assert_eq!(poll_future(Pin::as_mut(&mut receipt2)), Poll::Pending);
+
+    assert_eq!(poll_actor(Pin::as_mut(&mut actor)), Poll::Pending);
+    assert_eq!(
+        poll_future(Pin::as_mut(&mut receipt1)),
+        Poll::Ready(Ok(Delivered))
+    );
+    // Dropped without acknowledging it.
+    assert_eq!(
+        poll_future(Pin::as_mut(&mut receipt2)),
+        Poll::Ready(Err(SendError))
+    );
+}
+
+#[test]
+fn send_with_receipt_actor_stopped() {
+    let receiver = receipt_receiver as fn(_) -> _;
+    let (actor, actor_ref) = init_local_actor(receiver, ()).unwrap();
+    drop(actor);
+
+    for kind in [ReceiptKind::Enqueued, ReceiptKind::Received] {
+        let mut receipt = Box::pin(actor_ref.send_with_receipt(2, kind));
+        assert_eq!(
+            poll_future(Pin::as_mut(&mut receipt)),
+            Poll::Ready(Err(SendError))
+        );
+    }
+}
+
 async fn stop_on_run(ctx: actor::Context<Infallible, ThreadLocal>) {
     drop(ctx);
 }
@@ -943,3 +1168,100 @@ fn join_before_actor_finished() {
 
     assert_eq!(poll_future(Pin::new(&mut future)), Poll::Ready(()));
 }
+
+#[test]
+fn watch() {
+    let expect_msgs = expect_msgs as fn(_, _) -> _;
+    let actor_ref =
+        test::try_spawn_local(NoSupervisor, expect_msgs, vec![()], ActorOptions::default())
+            .unwrap();
+
+    let expect_stopped = expect_msgs as fn(_, _) -> _;
+    let expected = vec![ActorStopped {
+        reason: StopReason::Completed,
+    }];
+    let (watcher, watcher_ref) = init_local_actor(expect_stopped, expected).unwrap();
+    let mut watcher = Box::pin(watcher);
+    watcher_ref.watch(&actor_ref);
+    assert_eq!(poll_actor(Pin::as_mut(&mut watcher)), Poll::Pending);
+
+    actor_ref.try_send(()).unwrap();
+    test::join(&actor_ref, Duration::from_secs(1)).unwrap();
+    assert_eq!(poll_actor(Pin::as_mut(&mut watcher)), Poll::Ready(Ok(())));
+}
+
+#[test]
+fn watch_stopped_actor() {
+    let stop_on_run = stop_on_run as fn(_) -> _;
+    let actor_ref =
+        test::try_spawn_local(NoSupervisor, stop_on_run, (), ActorOptions::default()).unwrap();
+    test::join(&actor_ref, Duration::from_secs(1)).unwrap();
+
+    // Watching an actor that already stopped should send the message
+    // immediately.
+    let expect_msgs = expect_msgs as fn(_, _) -> _;
+    let expected = vec![ActorStopped {
+        reason: StopReason::Completed,
+    }];
+    let (watcher, watcher_ref) = init_local_actor(expect_msgs, expected).unwrap();
+    let mut watcher = Box::pin(watcher);
+    watcher_ref.watch(&actor_ref.map::<!>());
+    assert_eq!(poll_actor(Pin::as_mut(&mut watcher)), Poll::Ready(Ok(())));
+}
+
+async fn receive_until_stopped(mut ctx: actor::Context<usize, ThreadLocal>) {
+    while ctx.receive_next().await.is_ok() {}
+}
+
+#[test]
+fn stop() {
+    let receive_until_stopped = receive_until_stopped as fn(_) -> _;
+    let (actor, actor_ref) = init_local_actor(receive_until_stopped, ()).unwrap();
+    let mut actor = Box::pin(actor);
+    assert_eq!(poll_actor(Pin::as_mut(&mut actor)), Poll::Pending);
+
+    // Messages are no longer received once the actor is asked to stop.
+    actor_ref.try_send(1_usize).unwrap();
+    actor_ref.stop();
+    assert_eq!(poll_actor(Pin::as_mut(&mut actor)), Poll::Ready(Ok(())));
+}
+
+async fn drain_inbox(mut ctx: actor::Context<usize, ThreadLocal>) {
+    while ctx.try_receive_next().is_ok() {}
+}
+
+#[test]
+fn watch_inbox() {
+    let drain_inbox = drain_inbox as fn(_) -> _;
+    let (actor, actor_ref) = init_local_actor(drain_inbox, ()).unwrap();
+    let mut actor = Box::pin(actor);
+
+    let expect_msgs = expect_msgs as fn(_, _) -> _;
+    let expected = vec![InboxWatermark::High, InboxWatermark::Low];
+    let (watcher, watcher_ref) = init_local_actor(expect_msgs, expected).unwrap();
+    let mut watcher = Box::pin(watcher);
+    watcher_ref.watch_inbox(&actor_ref, 1, 3);
+
+    // Below the high watermark.
+    actor_ref.try_send(1_usize).unwrap();
+    actor_ref.try_send(2_usize).unwrap();
+    assert_eq!(poll_actor(Pin::as_mut(&mut watcher)), Poll::Pending);
+
+    // Reaching the high watermark.
+    actor_ref.try_send(3_usize).unwrap();
+    assert_eq!(poll_actor(Pin::as_mut(&mut watcher)), Poll::Pending);
+
+    // Draining the inbox should cross the low watermark.
+    assert_eq!(poll_actor(Pin::as_mut(&mut actor)), Poll::Ready(Ok(())));
+    assert_eq!(poll_actor(Pin::as_mut(&mut watcher)), Poll::Ready(Ok(())));
+}
+
+#[test]
+#[should_panic = "low watermark must be smaller than the high watermark"]
+fn watch_inbox_invalid_watermarks() {
+    let drain_inbox = drain_inbox as fn(_) -> _;
+    let (_, actor_ref) = init_local_actor(drain_inbox, ()).unwrap();
+    let expect_msgs = expect_msgs as fn(_, _) -> _;
+    let (_, watcher_ref) = init_local_actor(expect_msgs, Vec::<InboxWatermark>::new()).unwrap();
+    watcher_ref.watch_inbox(&actor_ref, 3, 3);
+}
@@ -0,0 +1,79 @@
+//! Tests for the event bus.
+
+use std::pin::Pin;
+use std::task::Poll;
+
+use heph::actor;
+use heph::rt::ThreadLocal;
+use heph::test::{self, init_local_actor, poll_actor};
+
+async fn subscriber(mut ctx: actor::Context<usize, ThreadLocal>, topic: &'static str) {
+    ctx.subscribe::<usize>(topic);
+    assert_eq!(ctx.receive_next().await, Ok(1));
+    assert_eq!(ctx.receive_next().await, Ok(2));
+    assert!(ctx.unsubscribe::<usize>(topic));
+    assert!(!ctx.unsubscribe::<usize>(topic));
+}
+
+#[test]
+fn publish_subscribe() {
+    const TOPIC: &str = "publish_subscribe";
+    let bus_rt = test::runtime();
+    let bus = bus_rt.bus();
+
+    // No subscribers yet.
+    assert_eq!(bus.publish(TOPIC, 0_usize), 0);
+
+    let subscriber = subscriber as fn(_, _) -> _;
+    let (actor, _actor_ref) = init_local_actor(subscriber, TOPIC).unwrap();
+    let mut actor = Box::pin(actor);
+    assert_eq!(poll_actor(Pin::as_mut(&mut actor)), Poll::Pending);
+    assert_eq!(bus.subscribers::<usize>(TOPIC), 1);
+
+    assert_eq!(bus.publish(TOPIC, 1_usize), 1);
+    // Different message type, so the actor isn't subscribed.
+    assert_eq!(bus.publish(TOPIC, "hello"), 0);
+    assert_eq!(poll_actor(Pin::as_mut(&mut actor)), Poll::Pending);
+    assert_eq!(bus.publish(TOPIC, 2_usize), 1);
+    assert_eq!(poll_actor(Pin::as_mut(&mut actor)), Poll::Ready(Ok(())));
+    assert_eq!(bus.subscribers::<usize>(TOPIC), 0);
+}
+
+#[derive(Debug, Eq, PartialEq)]
+enum Message {
+    Event(usize),
+}
+
+impl From<usize> for Message {
+    fn from(event: usize) -> Message {
+        Message::Event(event)
+    }
+}
+
+async fn mapped_subscriber(mut ctx: actor::Context<Message, ThreadLocal>, topic: &'static str) {
+    ctx.subscribe::<usize>(topic);
+    // Subscribing twice has no effect.
+    ctx.subscribe::<usize>(topic);
+    assert_eq!(ctx.receive_next().await, Ok(Message::Event(1)));
+    let _ = ctx.receive_next().await;
+}
+
+#[test]
+fn unsubscribe_stopped_actor() {
+    const TOPIC: &str = "unsubscribe_stopped_actor";
+    let bus_rt = test::runtime();
+    let bus = bus_rt.bus();
+
+    let mapped_subscriber = mapped_subscriber as fn(_, _) -> _;
+    let (actor, _actor_ref) = init_local_actor(mapped_subscriber, TOPIC).unwrap();
+    let mut actor = Box::pin(actor);
+    assert_eq!(poll_actor(Pin::as_mut(&mut actor)), Poll::Pending);
+    assert_eq!(bus.subscribers::<usize>(TOPIC), 1);
+    assert_eq!(bus.publish(TOPIC, 1_usize), 1);
+    assert_eq!(poll_actor(Pin::as_mut(&mut actor)), Poll::Pending);
+
+    // Once the actor is stopped it should be unsubscribed.
+    drop(actor);
+    assert_eq!(bus.subscribers::<usize>(TOPIC), 0);
+    assert_eq!(bus.publish(TOPIC, 2_usize), 0);
+}
@@ -1,6 +1,7 @@
 //! Tests for the [`Bytes`] trait.
 
 use std::cmp::min;
+use std::mem::MaybeUninit;
 use std::ptr;
 
 use heph::bytes::{Bytes, BytesVectored};
@@ -143,6 +144,54 @@ fn vectored_tuple() {
     assert!(!bufs.has_spare_capacity());
 }
 
+#[test]
+fn vectored_vec() {
+    let mut bufs = vec![
+        Vec::<u8>::with_capacity(1),
+        Vec::with_capacity(3),
+        Vec::with_capacity(DATA.len()),
+    ];
+    assert_eq!(bufs.spare_capacity(), 1 + 3 + DATA.len());
+    assert!(bufs.has_spare_capacity());
+    let n = write_bytes_vectored(DATA, &mut bufs);
+    assert_eq!(n, DATA.len());
+    assert_eq!(bufs[0], &DATA[..1]);
+    assert_eq!(bufs[1], &DATA[1..4]);
+    assert_eq!(bufs[2], &DATA[4..]);
+    assert_eq!(bufs.spare_capacity(), 4);
+    assert!(bufs.has_spare_capacity());
+    bufs[2].extend_from_slice(b"aaaa");
+    assert_eq!(bufs.spare_capacity(), 0);
+    assert!(!bufs.has_spare_capacity());
+}
+
+/// Buffer that only implements the required methods of `Bytes`.
+struct MinimalBuf(Vec<u8>);
+
+impl Bytes for MinimalBuf {
+    fn as_bytes(&mut self) -> &mut [MaybeUninit<u8>] {
+        self.0.spare_capacity_mut()
+    }
+
+    fn spare_capacity(&self) -> usize {
+        self.0.capacity() - self.0.len()
+    }
+
+    unsafe fn update_length(&mut self, n: usize) {
+        self.0.set_len(self.0.len() + n);
+    }
+}
+
+#[test]
+fn default_has_spare_capacity() {
+    let mut buf = MinimalBuf(Vec::with_capacity(DATA.len()));
+    assert!(buf.has_spare_capacity());
+    let n = write_bytes(DATA, &mut buf);
+    assert_eq!(n, DATA.len());
+    assert_eq!(buf.0, DATA);
+    assert!(!buf.has_spare_capacity());
+}
+
 #[test]
 fn limited_bytes_vectored() {
     const LIMIT: usize = 5;
@@ -0,0 +1,125 @@
+//! Tests for the `net::codec` module.
+
+use std::io::{self, Read, Write};
+use std::net::{self, SocketAddr};
+use std::time::Duration;
+
+use heph::actor;
+use heph::net::codec::{Decoder, Encoder, FixedSize, Framed, LengthDelimited, Lines};
+use heph::net::TcpStream;
+use heph::rt::ThreadLocal;
+use heph::spawn::ActorOptions;
+use heph::test::{join, try_spawn_local, PanicSupervisor};
+
+use crate::util::any_local_address;
+
+#[test]
+fn length_delimited() {
+    let mut codec = LengthDelimited::new();
+    let mut buf = Vec::new();
+    codec.encode(b"Hello", &mut buf).unwrap();
+    codec.encode(b"", &mut buf).unwrap();
+    assert_eq!(buf, b"\0\0\0\x05Hello\0\0\0\0");
+
+    assert_eq!(codec.decode(&buf[..3]).unwrap(), None);
+    assert_eq!(codec.decode(&buf[..8]).unwrap(), None);
+    assert_eq!(codec.decode(&buf).unwrap(), Some((b"Hello".to_vec(), 9)));
+    assert_eq!(codec.decode(&buf[9..]).unwrap(), Some((Vec::new(), 4)));
+    let err = codec.decode_eof(&buf[..8]).unwrap_err();
+    assert_eq!(err.kind(), io::ErrorKind::UnexpectedEof);
+}
+
+#[test]
+fn length_delimited_max_length() {
+    let mut codec = LengthDelimited::new().with_max_length(4);
+    assert_eq!(codec.max_length(), 4);
+    let err = codec.decode(b"\0\0\0\x05Hello").unwrap_err();
+    assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+    let err = codec.encode(b"Hello", &mut Vec::new()).unwrap_err();
+    assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+}
+
+#[test]
+fn lines() {
+    let mut codec = Lines::new();
+    let mut buf = Vec::new();
+    codec.encode("Hello", &mut buf).unwrap();
+    assert_eq!(buf, b"Hello\n");
+
+    let buf = b"Hello\r\nworld\nEnd";
+    assert_eq!(codec.decode(buf).unwrap(), Some(("Hello".to_owned(), 7)));
+    assert_eq!(
+        codec.decode(&buf[7..]).unwrap(),
+        Some(("world".to_owned(), 6))
+    );
+    assert_eq!(codec.decode(&buf[13..]).unwrap(), None);
+    assert_eq!(
+        codec.decode_eof(&buf[13..]).unwrap(),
+        Some(("End".to_owned(), 3))
+    );
+    assert_eq!(codec.decode_eof(b"").unwrap(), None);
+
+    let err = codec.decode(b"\xff\n").unwrap_err();
+    assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+    let err = Lines::new().with_max_length(2).decode(b"abc").unwrap_err();
+    assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+}
+
+#[test]
+fn fixed_size() {
+    let mut codec = FixedSize::new(2);
+    let mut buf = Vec::new();
+    codec.encode(b"ab", &mut buf).unwrap();
+    assert_eq!(buf, b"ab");
+    let err = codec.encode(b"abc", &mut buf).unwrap_err();
+    assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+
+    assert_eq!(codec.decode(b"a").unwrap(), None);
+    assert_eq!(codec.decode(b"abc").unwrap(), Some((b"ab".to_vec(), 2)));
+}
+
+#[test]
+#[should_panic = "frame size can't be zero"]
+fn fixed_size_zero() {
+    let _ = FixedSize::new(0);
+}
+
+#[test]
+fn framed() {
+    async fn actor(mut ctx: actor::Context<!, ThreadLocal>, address: SocketAddr) -> io::Result<()> {
+        let stream = TcpStream::connect(&mut ctx, address)?.await?;
+        let mut framed = Framed::new(stream, LengthDelimited::new());
+
+        let frame = framed.next().await.unwrap()?;
+        assert_eq!(frame, b"Hello");
+        let frame = framed.next().await.unwrap()?;
+        assert_eq!(frame, b"world");
+
+        framed.feed(b"Hello")?;
+        framed.send(b"back").await?;
+
+        assert!(framed.next().await.is_none());
+        Ok(())
+    }
+
+    let listener = net::TcpListener::bind(any_local_address()).unwrap();
+    let address = listener.local_addr().unwrap();
+
+    let actor = actor as fn(_, _) -> _;
+    let actor_ref =
+        try_spawn_local(PanicSupervisor, actor, address, ActorOptions::default()).unwrap();
+
+    let (mut stream, _) = listener.accept().unwrap();
+    // Split the second frame over multiple writes.
+    stream.write_all(b"\0\0\0\x05Hello\0\0").unwrap();
+    stream.flush().unwrap();
+    std::thread::sleep(Duration::from_millis(10));
+    stream.write_all(b"\0\x05world").unwrap();
+
+    let mut buf = [0; 17];
+    stream.read_exact(&mut buf).unwrap();
+    assert_eq!(&buf, b"\0\0\0\x05Hello\0\0\0\x04back");
+    drop(stream);
+
+    join(&actor_ref, Duration::from_secs(1)).unwrap();
+}
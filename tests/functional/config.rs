@@ -0,0 +1,70 @@
+//! Tests for the runtime managed configuration.
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+use heph::actor;
+use heph::config::{self, ConfigUpdated};
+use heph::rt::{Runtime, ThreadLocal};
+use heph::spawn::ActorOptions;
+use heph::supervisor::NoSupervisor;
+
+#[derive(Debug, Eq, PartialEq)]
+struct Config {
+    version: usize,
+}
+
+#[test]
+fn load_and_reload() {
+    async fn actor(mut ctx: actor::Context<ConfigUpdated<Config>, ThreadLocal>) {
+        ctx.subscribe::<ConfigUpdated<Config>>(config::TOPIC);
+        let config = ctx.runtime().config::<Config>().unwrap();
+        assert_eq!(*config, Config { version: 1 });
+
+        ctx.runtime().reload_config().unwrap();
+        let update = ctx.receive_next().await.unwrap();
+        assert_eq!(**update.config(), Config { version: 2 });
+        assert_eq!(
+            *ctx.runtime().config::<Config>().unwrap(),
+            Config { version: 2 }
+        );
+
+        let n = ctx.runtime().update_config(Config { version: 100 });
+        assert_eq!(n, 1);
+        let update = ctx.receive_next().await.unwrap();
+        assert_eq!(**update.config(), Config { version: 100 });
+
+        // Not loaded.
+        assert!(ctx.runtime().config::<String>().is_none());
+    }
+
+    let loads = Arc::new(AtomicUsize::new(0));
+    let mut runtime = Runtime::setup().num_threads(1).build().unwrap();
+    let l = loads.clone();
+    runtime
+        .load_config(move || -> Result<Config, String> {
+            let version = l.fetch_add(1, Ordering::AcqRel) + 1;
+            Ok(Config { version })
+        })
+        .unwrap();
+    assert_eq!(loads.load(Ordering::Acquire), 1);
+
+    runtime
+        .run_on_workers(|mut runtime_ref| -> Result<(), !> {
+            let actor = actor as fn(_) -> _;
+            let _ = runtime_ref.spawn_local(NoSupervisor, actor, (), ActorOptions::default());
+            Ok(())
+        })
+        .unwrap();
+    runtime.start().unwrap();
+    assert_eq!(loads.load(Ordering::Acquire), 2);
+}
+
+#[test]
+fn failed_load() {
+    let mut runtime = Runtime::setup().num_threads(1).build().unwrap();
+    let err = runtime
+        .load_config(|| -> Result<Config, &'static str> { Err("invalid config") })
+        .unwrap_err();
+    assert!(err.to_string().contains("invalid config"));
+}
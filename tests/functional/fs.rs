@@ -0,0 +1,192 @@
+//! Tests for the `fs` module.
+
+use std::io::{self, SeekFrom};
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{mpsc, Arc};
+use std::thread::sleep;
+use std::time::Duration;
+
+use heph::actor;
+use heph::fs::watch::EventKind;
+use heph::fs::{self, File, Watch};
+use heph::rt::ThreadLocal;
+use heph::spawn::ActorOptions;
+use heph::test::{block_on, join, try_spawn_local, PanicSupervisor};
+
+use crate::util::{assert_send, assert_sync, temp_file};
+
+const DATA: &[u8] = b"Hello, world!";
+
+#[test]
+fn is_send_sync() {
+    assert_send::<fs::Operation<File>>();
+    assert_send::<fs::BlockingCtx>();
+    assert_sync::<fs::BlockingCtx>();
+    assert_send::<File>();
+    assert_sync::<File>();
+}
+
+#[test]
+fn file_write_and_read() {
+    let path = temp_file("fs.file_write_and_read");
+    block_on(async move {
+        let file = File::create(path.clone()).await.unwrap();
+        file.write_all(DATA.to_vec()).await.unwrap();
+        file.sync_all().await.unwrap();
+        assert_eq!(file.metadata().await.unwrap().len(), DATA.len() as u64);
+        drop(file);
+
+        let file = File::open(path).await.unwrap();
+        let buf = file.read_to_end(Vec::new()).await.unwrap();
+        assert_eq!(buf, DATA);
+        // End of file.
+        let buf = file.read(Vec::with_capacity(10)).await.unwrap();
+        assert!(buf.is_empty());
+    });
+}
+
+#[test]
+fn file_read_spare_capacity() {
+    let path = temp_file("fs.file_read_spare_capacity");
+    block_on(async move {
+        fs::write(path.clone(), DATA.to_vec()).await.unwrap();
+        let file = File::open(path).await.unwrap();
+
+        let mut buf = Vec::with_capacity(7);
+        buf.extend_from_slice(b"12");
+        let buf = file.read(buf).await.unwrap();
+        assert_eq!(buf, b"12Hello");
+
+        assert_eq!(file.seek(SeekFrom::Start(7)).await.unwrap(), 7);
+        let buf = file.read(Vec::with_capacity(32)).await.unwrap();
+        assert_eq!(buf, b"world!");
+    });
+}
+
+#[test]
+fn file_set_len() {
+    let path = temp_file("fs.file_set_len");
+    block_on(async move {
+        let file = File::create(path.clone()).await.unwrap();
+        file.write_all(DATA.to_vec()).await.unwrap();
+        file.set_len(5).await.unwrap();
+        file.sync_data().await.unwrap();
+        drop(file);
+
+        assert_eq!(fs::read(path).await.unwrap(), b"Hello");
+    });
+}
+
+#[test]
+fn functions() {
+    let path = temp_file("fs.functions/a/b");
+    block_on(async move {
+        let dir = path.parent().unwrap().to_owned();
+        fs::create_dir_all(dir.clone()).await.unwrap();
+        assert!(fs::metadata(dir).await.unwrap().is_dir());
+
+        fs::write(path.clone(), DATA.to_vec()).await.unwrap();
+        assert_eq!(fs::read(path.clone()).await.unwrap(), DATA);
+        assert_eq!(
+            fs::read_to_string(path.clone()).await.unwrap(),
+            "Hello, world!"
+        );
+        assert!(fs::metadata(path.clone()).await.unwrap().is_file());
+
+        fs::remove_file(path.clone()).await.unwrap();
+        let err = fs::read(path).await.unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::NotFound);
+    });
+}
+
+#[test]
+fn watch_file() {
+    async fn actor(mut ctx: actor::Context<!, ThreadLocal>, path: PathBuf) -> io::Result<()> {
+        let mut watch = Watch::new(&mut ctx)?;
+        let id = watch.add(&path)?;
+
+        // No changes yet.
+        let err = watch.try_next().unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::WouldBlock);
+
+        std::fs::write(&path, DATA)?;
+        let event = watch.next().await?;
+        assert_eq!(event.id(), id);
+        assert_eq!(event.kind(), EventKind::Modify);
+
+        watch.remove(id)?;
+        Ok(())
+    }
+
+    let path = temp_file("fs.watch_file");
+    std::fs::write(&path, b"").unwrap();
+
+    let actor = actor as fn(_, _) -> _;
+    let actor_ref = try_spawn_local(PanicSupervisor, actor, path, ActorOptions::default()).unwrap();
+    join(&actor_ref, Duration::from_secs(1)).unwrap();
+}
+
+#[test]
+fn spawn_blocking() {
+    let result = block_on(fs::spawn_blocking(|ctx| {
+        assert!(!ctx.is_cancelled());
+        1 + 1
+    }));
+    assert_eq!(result.unwrap(), 2);
+}
+
+#[test]
+fn spawn_blocking_cancel_running() {
+    let (sender, receiver) = mpsc::channel();
+    let op = fs::spawn_blocking(move |ctx| {
+        sender.send("started").unwrap();
+        while !ctx.is_cancelled() {
+            sleep(Duration::from_millis(1));
+        }
+        sender.send("cancelled").unwrap();
+    });
+
+    let timeout = Duration::from_secs(1);
+    assert_eq!(receiver.recv_timeout(timeout), Ok("started"));
+    drop(op);
+    assert_eq!(receiver.recv_timeout(timeout), Ok("cancelled"));
+}
+
+#[test]
+fn spawn_blocking_cancel_not_started() {
+    // Occupy all (four) threads in the pool, so that the operation below can't
+    // start before it's cancelled.
+    let (started_sender, started) = mpsc::channel();
+    let release = Arc::new(AtomicBool::new(false));
+    let blockers = (0..4)
+        .map(|_| {
+            let started_sender = started_sender.clone();
+            let release = release.clone();
+            fs::spawn_blocking(move |_| {
+                started_sender.send(()).unwrap();
+                while !release.load(Ordering::Acquire) {
+                    sleep(Duration::from_millis(1));
+                }
+            })
+        })
+        .collect::<Vec<_>>();
+    for _ in 0..blockers.len() {
+        started.recv_timeout(Duration::from_secs(1)).unwrap();
+    }
+
+    let ran = Arc::new(AtomicBool::new(false));
+    let ran2 = ran.clone();
+    drop(fs::spawn_blocking(move |_| {
+        ran2.store(true, Ordering::Release)
+    }));
+
+    release.store(true, Ordering::Release);
+    for blocker in blockers {
+        block_on(blocker).unwrap();
+    }
+    // Run another operation to ensure the cancelled operation was removed
+    // from the queue.
+    block_on(fs::spawn_blocking(|_| ())).unwrap();
+    assert!(!ran.load(Ordering::Acquire));
+}
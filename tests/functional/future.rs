@@ -1,11 +1,13 @@
 //! Tests for spawning [`Future`]s.
 
-use std::future::Future;
+use std::future::{pending, Future};
 use std::pin::Pin;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
 use std::task::{self, Poll};
 
 use heph::actor;
-use heph::rt::{Runtime, ThreadSafe};
+use heph::rt::{EmbeddedRuntime, JoinError, Runtime, ThreadSafe};
 use heph::spawn::{ActorOptions, FutureOptions};
 use heph::supervisor::NoSupervisor;
 use heph::test::poll_future;
@@ -87,3 +89,47 @@ fn thread_safe_waking() {
         .unwrap();
     runtime.start().unwrap();
 }
+
+#[test]
+fn join_handle_output() {
+    let mut runtime = EmbeddedRuntime::new().unwrap();
+    let runtime_ref = runtime.runtime_ref();
+    let local = runtime_ref.spawn_local_future(async { 1 }, FutureOptions::default());
+    let safe = runtime_ref.spawn_future(async { 2 }, FutureOptions::default());
+
+    let output = runtime
+        .run_until(async move { (local.await, safe.await) })
+        .unwrap();
+    assert_eq!(output, (Ok(1), Ok(2)));
+}
+
+#[test]
+fn join_handle_abort() {
+    struct DropGuard(Arc<AtomicBool>);
+
+    impl Drop for DropGuard {
+        fn drop(&mut self) {
+            self.0.store(true, Ordering::SeqCst);
+        }
+    }
+
+    let mut runtime = EmbeddedRuntime::new().unwrap();
+    let dropped = Arc::new(AtomicBool::new(false));
+    let guard = DropGuard(dropped.clone());
+    let future = async move {
+        let _guard = guard;
+        pending::<()>().await
+    };
+    let handle = runtime
+        .runtime_ref()
+        .spawn_local_future(future, FutureOptions::default());
+    assert!(runtime.poll_once().unwrap());
+    assert!(!handle.is_finished());
+
+    handle.abort();
+    assert!(handle.is_finished());
+    assert_eq!(runtime.run_until(handle).unwrap(), Err(JoinError::Aborted));
+    // Run until the aborted future is dropped.
+    while runtime.poll_once().unwrap() {}
+    assert!(dropped.load(Ordering::SeqCst));
+}
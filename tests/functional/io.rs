@@ -0,0 +1,88 @@
+//! Tests for the `io` module.
+
+use std::cmp::min;
+use std::io::{self, Read};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+use heph::io::from_blocking;
+use heph::test::block_on;
+use heph::util::next;
+
+#[test]
+fn from_blocking_reads_all_chunks() {
+    let data: Vec<u8> = (0..=255).cycle().take(1000).collect();
+    let expected = data.clone();
+    block_on(async move {
+        let mut chunks = from_blocking(io::Cursor::new(data)).chunk_size(300);
+        let mut got = Vec::new();
+        while let Some(chunk) = next(&mut chunks).await {
+            let chunk = chunk.unwrap();
+            assert!(chunk.len() <= 300);
+            got.extend_from_slice(&chunk);
+        }
+        assert_eq!(got, expected);
+    });
+}
+
+/// Reader that counts the number of reads.
+struct CountingReader {
+    reads: Arc<AtomicUsize>,
+    left: usize,
+}
+
+impl Read for CountingReader {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let _ = self.reads.fetch_add(1, Ordering::AcqRel);
+        let n = min(buf.len(), self.left);
+        buf[..n].fill(1);
+        self.left -= n;
+        Ok(n)
+    }
+}
+
+#[test]
+fn from_blocking_back_pressure() {
+    let reads = Arc::new(AtomicUsize::new(0));
+    let reader = CountingReader {
+        reads: reads.clone(),
+        left: usize::MAX,
+    };
+    block_on(async move {
+        let mut chunks = from_blocking(reader).chunk_size(10).max_buffered(2);
+        for _ in 0..5 {
+            let chunk = next(&mut chunks).await.unwrap().unwrap();
+            assert_eq!(chunk, [1; 10]);
+            assert!(chunks.buffered() <= 2);
+        }
+        // Returned five chunks, at most two buffered and one read in
+        // progress.
+        assert!(reads.load(Ordering::Acquire) <= 8);
+    });
+}
+
+/// Reader that returns an error on the first read.
+struct ErrorReader(bool);
+
+impl Read for ErrorReader {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if self.0 {
+            Ok(0)
+        } else {
+            self.0 = true;
+            buf[0] = 1;
+            Err(io::Error::new(io::ErrorKind::Other, "oops"))
+        }
+    }
+}
+
+#[test]
+fn from_blocking_error() {
+    block_on(async move {
+        let mut chunks = from_blocking(ErrorReader(false));
+        let err = next(&mut chunks).await.unwrap().unwrap_err();
+        assert_eq!(err.to_string(), "oops");
+        // Can continue reading after an error.
+        assert!(next(&mut chunks).await.is_none());
+    });
+}
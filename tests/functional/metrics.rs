@@ -0,0 +1,72 @@
+//! Tests for the metrics exporter.
+
+use std::io::{self, Read, Write};
+use std::net::{self, SocketAddr};
+use std::thread::{self, sleep};
+use std::time::Duration;
+
+use heph::actor::messages::Terminate;
+use heph::metrics;
+use heph::rt::Runtime;
+use heph::spawn::ActorOptions;
+use heph::supervisor::SupervisorStrategy;
+
+/// Send a request for `path` to `address`, returning the response.
+fn request(address: SocketAddr, path: &str) -> String {
+    let mut tries = 0;
+    let mut stream = loop {
+        match net::TcpStream::connect(address) {
+            Ok(stream) => break stream,
+            // The exporter might not be listening yet.
+            Err(ref err) if err.kind() == io::ErrorKind::ConnectionRefused && tries < 50 => {
+                tries += 1;
+                sleep(Duration::from_millis(10));
+            }
+            Err(err) => panic!("failed to connect to metrics exporter: {}", err),
+        }
+    };
+    let request = format!("GET {} HTTP/1.1\r\nHost: localhost\r\n\r\n", path);
+    stream.write_all(request.as_bytes()).unwrap();
+    let mut response = String::new();
+    stream.read_to_string(&mut response).unwrap();
+    response
+}
+
+#[test]
+fn http_exporter() {
+    fn supervisor(err: io::Error) -> SupervisorStrategy<SocketAddr> {
+        panic!("metrics exporter failed: {}", err);
+    }
+
+    // Get a free port to run the exporter on.
+    let address = net::TcpListener::bind("127.0.0.1:0")
+        .unwrap()
+        .local_addr()
+        .unwrap();
+
+    let mut runtime = Runtime::setup().num_threads(2).build().unwrap();
+    let exporter = metrics::http_exporter as fn(_, _) -> _;
+    let actor_ref = runtime.spawn(supervisor, exporter, address, ActorOptions::default());
+
+    let handle = thread::spawn(move || {
+        let response = request(address, "/metrics");
+        assert!(response.starts_with("HTTP/1.1 200 OK\r\n"), "{}", response);
+        assert!(response.contains("Content-Type: text/plain; version=0.0.4; charset=utf-8\r\n"));
+        assert!(response.contains("# TYPE heph_uptime_seconds gauge\n"));
+        assert!(response.contains("\nheph_worker_threads 2\n"));
+        assert!(response.contains("\nheph_worker_ready_processes{worker=\"1\"} "));
+        assert!(response.contains("\nheph_worker_processes_run_total{worker=\"2\"} "));
+
+        let response = request(address, "/not_found");
+        assert!(
+            response.starts_with("HTTP/1.1 404 Not Found\r\n"),
+            "{}",
+            response
+        );
+
+        actor_ref.try_send(Terminate).unwrap();
+    });
+
+    runtime.start().unwrap();
+    handle.join().unwrap();
+}
@@ -0,0 +1,69 @@
+//! Tests for the child process support.
+
+use std::io;
+use std::process::{Command, Stdio};
+use std::time::Duration;
+
+use heph::spawn::ActorOptions;
+use heph::test::{join, try_spawn_local, PanicSupervisor};
+use heph::{actor, process, rt};
+
+const DATA: &[u8] = b"Hello world";
+
+#[test]
+fn stdin_stdout() {
+    async fn actor<RT>(mut ctx: actor::Context<!, RT>) -> io::Result<()>
+    where
+        RT: rt::Access,
+    {
+        let mut command = Command::new("cat");
+        command
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null());
+        let mut child = process::spawn(&mut ctx, &mut command)?;
+        assert!(child.stderr.is_none());
+
+        let mut stdin = child.stdin.take().unwrap();
+        stdin.write_all(DATA).await?;
+        drop(stdin);
+
+        let mut stdout = child.stdout.take().unwrap();
+        let mut buf = Vec::with_capacity(DATA.len() + 1);
+        while stdout.read(&mut buf).await? != 0 {}
+        assert_eq!(buf, DATA);
+
+        let status = child.wait().await?;
+        assert!(status.success());
+        Ok(())
+    }
+
+    #[allow(trivial_casts)]
+    let actor = actor as fn(_) -> _;
+    let actor_ref = try_spawn_local(PanicSupervisor, actor, (), ActorOptions::default()).unwrap();
+    join(&actor_ref, Duration::from_secs(1)).unwrap();
+}
+
+#[test]
+fn kill() {
+    async fn actor<RT>(mut ctx: actor::Context<!, RT>) -> io::Result<()>
+    where
+        RT: rt::Access,
+    {
+        let mut command = Command::new("sleep");
+        command.arg("10");
+        let mut child = process::spawn(&mut ctx, &mut command)?;
+        assert!(child.stdin.is_none());
+        assert!(child.try_wait()?.is_none());
+
+        child.kill()?;
+        let status = child.wait().await?;
+        assert!(!status.success());
+        Ok(())
+    }
+
+    #[allow(trivial_casts)]
+    let actor = actor as fn(_) -> _;
+    let actor_ref = try_spawn_local(PanicSupervisor, actor, (), ActorOptions::default()).unwrap();
+    join(&actor_ref, Duration::from_secs(1)).unwrap();
+}
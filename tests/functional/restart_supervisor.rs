@@ -11,6 +11,7 @@ use std::thread::sleep;
 use std::time::Duration;
 
 use heph::rt::ThreadSafe;
+use heph::supervisor::{LogThrottle, RestartPolicy};
 use heph::{actor, restart_supervisor, Actor, NewActor, Supervisor, SupervisorStrategy};
 
 // NOTE: keep in sync with the documentation.
@@ -325,3 +326,118 @@ fn decide_on_second_restart_error() {
     let mut supervisor = Supervisor::new(arg);
     decide_for_restart_second(&NEW_ACTOR, &mut supervisor, ERROR2);
 }
+
+#[test]
+fn decide_with_restart_policy() {
+    restart_supervisor!(Supervisor, "my actor", bool, 3, Duration::from_secs(60));
+
+    let arg = true;
+    let policy = RestartPolicy::exponential_backoff(
+        Duration::from_millis(100),
+        2,
+        Duration::from_millis(300),
+    );
+    let mut supervisor = Supervisor::new(arg).with_restart_policy(policy);
+
+    assert_eq!(
+        decide_for(&NEW_ACTOR, &mut supervisor, ERROR1),
+        SupervisorStrategy::RestartAfter(arg, Duration::from_millis(100))
+    );
+    assert_eq!(
+        decide_for_restart(&NEW_ACTOR, &mut supervisor, ERROR2),
+        SupervisorStrategy::RestartAfter(arg, Duration::from_millis(200))
+    );
+    // Maximum delay.
+    assert_eq!(
+        decide_for(&NEW_ACTOR, &mut supervisor, ERROR1),
+        SupervisorStrategy::RestartAfter(arg, Duration::from_millis(300))
+    );
+    assert_eq!(
+        decide_for(&NEW_ACTOR, &mut supervisor, ERROR1),
+        SupervisorStrategy::Stop
+    );
+}
+
+#[test]
+fn decide_with_log_throttle() {
+    restart_supervisor!(Supervisor, "my actor", bool, 3, Duration::from_secs(60));
+
+    let arg = true;
+    let throttle = LogThrottle::new(Duration::from_secs(60));
+    let mut supervisor = Supervisor::new(arg).with_log_throttle(throttle);
+
+    // Throttling the logs should not change the decisions.
+    for _ in 0..3 {
+        assert_eq!(
+            decide_for(&NEW_ACTOR, &mut supervisor, ERROR1),
+            SupervisorStrategy::Restart(arg)
+        );
+    }
+    assert_eq!(
+        decide_for(&NEW_ACTOR, &mut supervisor, ERROR1),
+        SupervisorStrategy::Stop
+    );
+}
+
+#[test]
+fn log_throttle() {
+    let mut throttle = LogThrottle::new(Duration::from_secs(60));
+    assert_eq!(throttle.check(ERROR1), Some(0));
+    assert_eq!(throttle.check(ERROR1), None);
+    assert_eq!(throttle.check(ERROR1), None);
+    assert_eq!(throttle.check(ERROR2), Some(2));
+    assert_eq!(throttle.check(ERROR2), None);
+    assert_eq!(throttle.take_repeated(), 1);
+    assert_eq!(throttle.take_repeated(), 0);
+    assert_eq!(throttle.check(ERROR1), Some(0));
+}
+
+#[test]
+fn log_throttle_window_elapsed() {
+    let mut throttle = LogThrottle::new(Duration::from_millis(10));
+    assert_eq!(throttle.check(ERROR1), Some(0));
+    assert_eq!(throttle.check(ERROR1), None);
+    sleep(Duration::from_millis(10));
+    assert_eq!(throttle.check(ERROR1), Some(1));
+}
+
+#[test]
+fn log_throttle_disabled() {
+    let mut throttle = LogThrottle::default();
+    for _ in 0..3 {
+        assert_eq!(throttle.check(ERROR1), Some(0));
+    }
+    assert_eq!(throttle.take_repeated(), 0);
+}
+
+#[test]
+fn restart_policy() {
+    let policy = RestartPolicy::immediate();
+    assert_eq!(policy, RestartPolicy::default());
+    assert_eq!(policy.delay(0), Duration::from_secs(0));
+    assert_eq!(policy.delay(10), Duration::from_secs(0));
+    assert_eq!(policy.strategy(true, 1), SupervisorStrategy::Restart(true));
+
+    let policy =
+        RestartPolicy::exponential_backoff(Duration::from_secs(1), 3, Duration::from_secs(20));
+    assert_eq!(policy.delay(0), Duration::from_secs(1));
+    assert_eq!(policy.delay(1), Duration::from_secs(3));
+    assert_eq!(policy.delay(2), Duration::from_secs(9));
+    assert_eq!(policy.delay(3), Duration::from_secs(20));
+    assert_eq!(policy.delay(usize::MAX), Duration::from_secs(20));
+
+    // Delays that never reach the maximum delay.
+    let policy =
+        RestartPolicy::exponential_backoff(Duration::from_secs(1), 1, Duration::from_secs(20));
+    assert_eq!(policy.delay(usize::MAX), Duration::from_secs(1));
+    let policy =
+        RestartPolicy::exponential_backoff(Duration::from_secs(1), 0, Duration::from_secs(20));
+    assert_eq!(policy.delay(0), Duration::from_secs(1));
+    assert_eq!(policy.delay(usize::MAX), Duration::from_secs(0));
+    let policy = RestartPolicy::exponential_backoff(Duration::ZERO, 2, Duration::from_secs(20));
+    assert_eq!(policy.delay(usize::MAX), Duration::from_secs(0));
+    assert_eq!(
+        policy.strategy(true, 1),
+        SupervisorStrategy::RestartAfter(true, Duration::from_secs(3))
+    );
+}
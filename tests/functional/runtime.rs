@@ -1,18 +1,28 @@
-use std::future::Future;
+use std::cell::Cell;
+use std::fs::File;
+use std::future::{self, Future};
 use std::io::{self, Write};
+use std::iter;
 use std::marker::PhantomData;
 use std::pin::Pin;
 use std::process::Command;
+use std::rc::Rc;
 use std::sync::atomic::{AtomicUsize, Ordering};
 use std::sync::{Arc, Condvar, Mutex};
 use std::task::{self, Poll};
 use std::thread::{self, sleep};
-use std::time::Duration;
+use std::time::{Duration, Instant};
 
 use heph::actor::{self, Actor, NewActor, SyncContext};
-use heph::rt::{Runtime, ThreadLocal, ThreadSafe};
-use heph::spawn::options::{ActorOptions, Priority, SyncActorOptions};
+use heph::actor_ref::Delivery;
+use heph::rt::{
+    self, EmbeddedRuntime, ErrorKind, Process, ProcessId, ProcessResult, Runtime, RuntimeRef,
+    ThreadLocal, ThreadSafe,
+};
+use heph::spawn::options::{ActorOptions, FutureOptions, Priority, SyncActorOptions};
 use heph::supervisor::{NoSupervisor, Supervisor, SupervisorStrategy};
+use heph::timer::Timer;
+use heph::trace::{self, Trace};
 
 use crate::util::temp_file;
 
@@ -395,6 +405,218 @@ fn tracing() {
     }
 }
 
+#[test]
+fn tracing_convert() {
+    async fn actor(mut ctx: actor::Context<!, ThreadLocal>) {
+        let timing = ctx.start_trace();
+        ctx.finish_trace(timing, "Custom \"event\"", &[("attribute", &"value")]);
+    }
+
+    let trace_path = temp_file("runtime_trace_convert.bin.trace");
+
+    let mut setup = Runtime::setup();
+    setup.enable_tracing(&trace_path).unwrap();
+    let mut runtime = setup.build().unwrap();
+    runtime
+        .run_on_workers(|mut runtime_ref| -> Result<(), !> {
+            let actor = actor as fn(_) -> _;
+            runtime_ref.spawn_local(NoSupervisor, actor, (), ActorOptions::default());
+            Ok(())
+        })
+        .unwrap();
+    runtime.start().unwrap();
+
+    let mut reader = trace::convert::Reader::new(File::open(&trace_path).unwrap());
+    let event = reader
+        .events()
+        .map(Result::unwrap)
+        .find(|event| event.description == "Custom \"event\"")
+        .expect("missing custom trace event");
+    assert_eq!(event.stream_id, 1);
+    assert_ne!(event.substream_id, 0);
+    assert!(event.start <= event.end);
+    match &*event.attributes {
+        [(name, trace::convert::Value::String(value))] => {
+            assert_eq!(name, "attribute");
+            assert_eq!(value, "value");
+        }
+        attributes => panic!("unexpected attributes: {:?}", attributes),
+    }
+
+    let mut output = Vec::new();
+    trace::convert(File::open(&trace_path).unwrap(), &mut output).unwrap();
+    let output = String::from_utf8(output).unwrap();
+    assert!(output.starts_with("{\n\t\"displayTimeUnit\": \"ns\",\n\t\"traceEvents\": [\n"));
+    assert!(
+        output.contains("\"name\": \"Custom \\\"event\\\"\", \"args\": {\"attribute\": \"value\"}")
+    );
+    assert!(output.ends_with("\n\t]\n}\n"));
+}
+
+#[test]
+fn tracing_supervisor_errors() {
+    struct CaptureSupervisor;
+
+    impl<NA> Supervisor<NA> for CaptureSupervisor
+    where
+        NA: NewActor<Argument = bool>,
+        <NA::Actor as Actor>::Error: std::fmt::Display,
+    {
+        fn decide(&mut self, err: <NA::Actor as Actor>::Error) -> SupervisorStrategy<bool> {
+            trace::capture_error(&err);
+            SupervisorStrategy::Restart(false)
+        }
+
+        fn decide_on_restart_error(&mut self, _: NA::Error) -> SupervisorStrategy<bool> {
+            SupervisorStrategy::Stop
+        }
+
+        fn second_restart_error(&mut self, _: NA::Error) {}
+    }
+
+    async fn actor(_: actor::Context<!, ThreadLocal>, fail: bool) -> Result<(), &'static str> {
+        if fail {
+            Err("something went wrong")
+        } else {
+            Ok(())
+        }
+    }
+
+    let trace_path = temp_file("runtime_trace_supervisor_errors.bin.trace");
+
+    let mut setup = Runtime::setup();
+    setup.enable_tracing(&trace_path).unwrap();
+    let mut runtime = setup.build().unwrap();
+    runtime
+        .run_on_workers(|mut runtime_ref| -> Result<(), !> {
+            let actor = actor as fn(_, _) -> _;
+            let options = ActorOptions::default().with_name("failing actor");
+            runtime_ref.spawn_local(CaptureSupervisor, actor, true, options);
+            Ok(())
+        })
+        .unwrap();
+    runtime.start().unwrap();
+
+    let mut reader = trace::convert::Reader::new(File::open(&trace_path).unwrap());
+    let event = reader
+        .events()
+        .map(Result::unwrap)
+        .find(|event| event.description == "Supervising actor")
+        .expect("missing supervisor trace event");
+    let attribute = |name: &str| match event.attributes.iter().find(|(n, _)| n == name) {
+        Some((_, trace::convert::Value::String(value))) => value.clone(),
+        attribute => panic!("unexpected attribute: {:?}", attribute),
+    };
+    assert_eq!(attribute("name"), "failing actor");
+    assert_eq!(attribute("cause"), "error");
+    assert_eq!(attribute("error_type"), "&str");
+    assert_eq!(attribute("error"), "something went wrong");
+    assert_eq!(attribute("decision"), "restart");
+}
+
+#[test]
+fn default_actor_options() {
+    async fn panicking_actor(_: actor::Context<!, ThreadLocal>) {
+        panic!("oops");
+    }
+
+    let defaults = ActorOptions::default().catch_panics();
+    let mut runtime = Runtime::setup()
+        .default_actor_options(defaults)
+        .build()
+        .unwrap();
+    runtime
+        .run_on_workers(|mut runtime_ref| -> Result<(), !> {
+            let actor = panicking_actor as fn(_) -> _;
+            // Should inherit catching panics from the defaults, otherwise the
+            // panic takes down the worker thread.
+            let options = ActorOptions::default().with_priority(Priority::HIGH);
+            runtime_ref.spawn_local(NoSupervisor, actor, (), options);
+            Ok(())
+        })
+        .unwrap();
+    runtime.start().unwrap();
+}
+
+#[test]
+fn event_loop_options() {
+    async fn timer_actor(mut ctx: actor::Context<!, ThreadLocal>) {
+        let start = Instant::now();
+        let _ = Timer::after(&mut ctx, Duration::from_millis(10)).await;
+        assert!(start.elapsed() >= Duration::from_millis(10));
+    }
+
+    let mut runtime = Runtime::setup()
+        .event_capacity(1)
+        .busy_poll(Duration::from_millis(5))
+        .max_processes_per_poll(1)
+        .build()
+        .unwrap();
+    runtime
+        .run_on_workers(|mut runtime_ref| -> Result<(), !> {
+            for _ in 0..4 {
+                let actor = timer_actor as fn(_) -> _;
+                runtime_ref.spawn_local(NoSupervisor, actor, (), ActorOptions::default());
+            }
+            Ok(())
+        })
+        .unwrap();
+    runtime.start().unwrap();
+}
+
+#[test]
+fn new_actor_validate() {
+    #[derive(Copy, Clone)]
+    struct ValidatingNewActor;
+
+    impl NewActor for ValidatingNewActor {
+        type Message = !;
+        type Argument = usize;
+        type Actor = future::Ready<Result<(), !>>;
+        type Error = &'static str;
+        type RuntimeAccess = ThreadLocal;
+
+        fn new(
+            &mut self,
+            _: actor::Context<Self::Message, Self::RuntimeAccess>,
+            arg: Self::Argument,
+        ) -> Result<Self::Actor, Self::Error> {
+            assert_ne!(arg, 0, "called `NewActor::new` with invalid argument");
+            Ok(future::ready(Ok(())))
+        }
+
+        fn validate(&self, arg: &Self::Argument) -> Result<(), Self::Error> {
+            if *arg == 0 {
+                Err("argument can't be zero")
+            } else {
+                Ok(())
+            }
+        }
+    }
+
+    let mut runtime = Runtime::setup().build().unwrap();
+    runtime
+        .run_on_workers(|mut runtime_ref| -> Result<(), !> {
+            let res = runtime_ref.try_spawn_local(
+                NoSupervisor,
+                ValidatingNewActor,
+                0,
+                ActorOptions::default(),
+            );
+            assert_eq!(res.unwrap_err(), "argument can't be zero");
+            let res = runtime_ref.try_spawn_local(
+                NoSupervisor,
+                ValidatingNewActor,
+                1,
+                ActorOptions::default(),
+            );
+            assert!(res.is_ok());
+            Ok(())
+        })
+        .unwrap();
+    runtime.start().unwrap();
+}
+
 #[derive(Clone)] // Needed in setup function.
 struct WaitFuture {
     #[allow(clippy::type_complexity)]
@@ -510,3 +732,420 @@ fn external_thread_wakes_sync_actor() {
     runtime.start().unwrap();
     handle.join().unwrap();
 }
+
+#[test]
+fn restart_after() {
+    const DELAY: Duration = Duration::from_millis(50);
+
+    async fn actor(_: actor::Context<!, ThreadSafe>, attempt: usize) -> Result<(), usize> {
+        if attempt < 2 {
+            Err(attempt)
+        } else {
+            Ok(())
+        }
+    }
+
+    fn supervisor(attempt: usize) -> SupervisorStrategy<usize> {
+        if attempt < 2 {
+            SupervisorStrategy::RestartAfter(attempt + 1, DELAY)
+        } else {
+            SupervisorStrategy::Stop
+        }
+    }
+
+    let start = Instant::now();
+    let mut runtime = Runtime::new().unwrap();
+    let _ = runtime.spawn(
+        supervisor as fn(_) -> _,
+        actor as fn(_, _) -> _,
+        0,
+        ActorOptions::default(),
+    );
+    runtime.start().unwrap();
+    // The actor is restarted twice, each time after `DELAY`.
+    assert!(start.elapsed() >= 2 * DELAY);
+}
+
+#[test]
+fn spawn_pool() {
+    const POOL_SIZE: usize = 4;
+    const MSGS: usize = 16;
+
+    async fn actor(
+        mut ctx: actor::Context<usize, ThreadSafe>,
+        received: Arc<AtomicUsize>,
+    ) -> Result<(), !> {
+        while let Ok(msg) = ctx.receive_next().await {
+            assert!(msg < MSGS);
+            let _ = received.fetch_add(1, Ordering::AcqRel);
+        }
+        Ok(())
+    }
+
+    let received = Arc::new(AtomicUsize::new(0));
+    let mut runtime = Runtime::setup().num_threads(1).build().unwrap();
+    let r = received.clone();
+    runtime
+        .run_on_workers(move |mut runtime_ref| -> Result<(), !> {
+            let group = runtime_ref.spawn_pool(
+                NoSupervisor,
+                actor as fn(_, _) -> _,
+                iter::repeat(r),
+                POOL_SIZE,
+                ActorOptions::default(),
+            );
+            assert_eq!(group.len(), POOL_SIZE);
+            for msg in 0..MSGS {
+                group.try_send(msg, Delivery::ToOne).unwrap();
+            }
+            Ok(())
+        })
+        .unwrap();
+
+    runtime.start().unwrap();
+    assert_eq!(received.load(Ordering::Acquire), MSGS);
+}
+
+#[test]
+fn spawn_local_batch() {
+    const BATCH_SIZE: usize = 100;
+
+    async fn actor(mut ctx: actor::Context<usize, ThreadLocal>, n: usize, sum: Arc<AtomicUsize>) {
+        let msg = ctx.receive_next().await.unwrap();
+        assert_eq!(msg, n);
+        let _ = sum.fetch_add(msg, Ordering::AcqRel);
+    }
+
+    let sum = Arc::new(AtomicUsize::new(0));
+    let mut runtime = Runtime::setup().num_threads(1).build().unwrap();
+    let s = sum.clone();
+    runtime
+        .run_on_workers(move |mut runtime_ref| -> Result<(), !> {
+            let actor_refs = runtime_ref.spawn_local_batch(
+                NoSupervisor,
+                actor as fn(_, _, _) -> _,
+                (0..BATCH_SIZE).map(|n| (n, s.clone())),
+                ActorOptions::default(),
+            );
+            assert_eq!(actor_refs.len(), BATCH_SIZE);
+            for (n, actor_ref) in actor_refs.iter().enumerate() {
+                actor_ref.try_send(n).unwrap();
+            }
+            Ok(())
+        })
+        .unwrap();
+
+    runtime.start().unwrap();
+    assert_eq!(sum.load(Ordering::Acquire), (0..BATCH_SIZE).sum::<usize>());
+}
+
+#[test]
+fn ready_processes() {
+    let mut runtime = Runtime::setup().num_threads(1).build().unwrap();
+    runtime
+        .run_on_workers(|mut runtime_ref| -> Result<(), !> {
+            assert_eq!(runtime_ref.ready_processes(), 0);
+            for n in 1..=3 {
+                runtime_ref.spawn_local_future(async {}, FutureOptions::default());
+                assert_eq!(runtime_ref.ready_processes(), n);
+            }
+            Ok(())
+        })
+        .unwrap();
+    runtime.start().unwrap();
+}
+
+#[test]
+fn readiness() {
+    async fn waiter(mut ctx: actor::Context<!, ThreadLocal>, ready: Arc<AtomicUsize>) {
+        ctx.wait_ready(Duration::from_secs(1)).await.unwrap();
+        assert!(ctx.runtime().is_ready());
+        let _ = ready.fetch_add(1, Ordering::AcqRel);
+    }
+
+    async fn local_actor(mut ctx: actor::Context<!, ThreadLocal>) {
+        assert!(!ctx.runtime().is_ready());
+        ctx.report_ready();
+        // Reporting twice should be fine.
+        ctx.report_ready();
+    }
+
+    async fn thread_safe_actor(mut ctx: actor::Context<!, ThreadSafe>) {
+        ctx.report_ready();
+    }
+
+    let ready = Arc::new(AtomicUsize::new(0));
+    let r = ready.clone();
+    let mut runtime = Runtime::setup().num_threads(1).build().unwrap();
+    runtime
+        .run_on_workers(move |mut runtime_ref| -> Result<(), !> {
+            let options = ActorOptions::default().require_readiness();
+            runtime_ref.spawn_local(
+                NoSupervisor,
+                waiter as fn(_, _) -> _,
+                r,
+                ActorOptions::default(),
+            );
+            runtime_ref.spawn_local(NoSupervisor, local_actor as fn(_) -> _, (), options.clone());
+            runtime_ref.spawn(NoSupervisor, thread_safe_actor as fn(_) -> _, (), options);
+            assert!(!runtime_ref.is_ready());
+            Ok(())
+        })
+        .unwrap();
+    runtime.start().unwrap();
+    assert_eq!(ready.load(Ordering::Acquire), 1);
+}
+
+#[test]
+fn readiness_timeout() {
+    async fn waiter(mut ctx: actor::Context<!, ThreadLocal>, timed_out: Arc<AtomicUsize>) {
+        assert!(ctx.wait_ready(Duration::from_millis(50)).await.is_err());
+        assert!(!ctx.runtime().is_ready());
+        let _ = timed_out.fetch_add(1, Ordering::AcqRel);
+    }
+
+    // Never reports it's ready.
+    async fn never_ready(_: actor::Context<!, ThreadLocal>) {}
+
+    let timed_out = Arc::new(AtomicUsize::new(0));
+    let t = timed_out.clone();
+    let mut runtime = Runtime::setup().num_threads(1).build().unwrap();
+    runtime
+        .run_on_workers(move |mut runtime_ref| -> Result<(), !> {
+            let options = ActorOptions::default().require_readiness();
+            runtime_ref.spawn_local(NoSupervisor, never_ready as fn(_) -> _, (), options);
+            runtime_ref.spawn_local(
+                NoSupervisor,
+                waiter as fn(_, _) -> _,
+                t,
+                ActorOptions::default(),
+            );
+            Ok(())
+        })
+        .unwrap();
+    runtime.start().unwrap();
+    assert_eq!(timed_out.load(Ordering::Acquire), 1);
+}
+
+#[test]
+fn process_stats() {
+    async fn stats_receiver(mut ctx: actor::Context<usize, ThreadLocal>) {
+        // Stop after the fourth message.
+        for _ in 0..4 {
+            let _ = ctx.receive_next().await;
+        }
+    }
+
+    async fn checker(mut ctx: actor::Context<!, ThreadLocal>, checked: Arc<AtomicUsize>) {
+        let actor_ref = ctx.runtime().spawn_local(
+            NoSupervisor,
+            stats_receiver as fn(_) -> _,
+            (),
+            ActorOptions::default(),
+        );
+        for msg in 0..3 {
+            actor_ref.try_send(msg).unwrap();
+        }
+        // Give the receiver some time to process the messages.
+        let _ = Timer::after(&mut ctx, Duration::from_millis(20)).await;
+
+        let (_, stats) = ctx
+            .runtime()
+            .all_process_stats()
+            .find(|(_, stats)| stats.name() == "stats_receiver")
+            .expect("missing receiver stats");
+        assert_eq!(stats.messages(), 3);
+        assert!(stats.polls() >= 1);
+        assert!(stats.last_run().is_some());
+
+        // The actor's own process id can be used to look up its stats.
+        let pid = ctx.pid();
+        let stats = ctx
+            .runtime()
+            .process_stats(pid)
+            .expect("missing checker stats");
+        assert_eq!(stats.name(), "checker");
+        assert_eq!(pid.to_string().parse(), Ok(pid));
+
+        actor_ref.try_send(3_usize).unwrap();
+        let _ = checked.fetch_add(1, Ordering::AcqRel);
+    }
+
+    let checked = Arc::new(AtomicUsize::new(0));
+    let c = checked.clone();
+    let mut runtime = Runtime::setup()
+        .num_threads(1)
+        .enable_process_stats()
+        .build()
+        .unwrap();
+    runtime
+        .run_on_workers(move |mut runtime_ref| -> Result<(), !> {
+            runtime_ref.spawn_local(
+                NoSupervisor,
+                checker as fn(_, _) -> _,
+                c,
+                ActorOptions::default(),
+            );
+            Ok(())
+        })
+        .unwrap();
+    runtime.start().unwrap();
+    assert_eq!(checked.load(Ordering::Acquire), 1);
+}
+
+#[test]
+fn actor_name() {
+    async fn named_actor(mut ctx: actor::Context<!, ThreadLocal>, found: Arc<AtomicUsize>) {
+        let found_name = ctx
+            .runtime()
+            .all_process_stats()
+            .any(|(_, stats)| stats.name() == "my_named_actor");
+        assert!(found_name, "missing named actor");
+        let _ = found.fetch_add(1, Ordering::AcqRel);
+    }
+
+    let found = Arc::new(AtomicUsize::new(0));
+    let f = found.clone();
+    let mut runtime = Runtime::setup()
+        .num_threads(1)
+        .enable_process_stats()
+        .build()
+        .unwrap();
+    runtime
+        .run_on_workers(move |mut runtime_ref| -> Result<(), !> {
+            let options = ActorOptions::default().with_name("my_named_actor");
+            runtime_ref.spawn_local(NoSupervisor, named_actor as fn(_, _) -> _, f, options);
+            Ok(())
+        })
+        .unwrap();
+    runtime.start().unwrap();
+    assert_eq!(found.load(Ordering::Acquire), 1);
+}
+
+#[test]
+fn embedded_runtime() {
+    async fn actor(mut ctx: actor::Context<usize, ThreadLocal>, received: Arc<AtomicUsize>) {
+        let msg = ctx.receive_next().await.unwrap();
+        received.store(msg, Ordering::Release);
+    }
+
+    let received = Arc::new(AtomicUsize::new(0));
+    let mut runtime = EmbeddedRuntime::new().unwrap();
+    let actor_ref = runtime.runtime_ref().spawn_local(
+        NoSupervisor,
+        actor as fn(_, _) -> _,
+        received.clone(),
+        ActorOptions::default(),
+    );
+    actor_ref.try_send(123_usize).unwrap();
+
+    // The host's event loop.
+    let mut iterations = 0;
+    while runtime.poll_once().unwrap() {
+        iterations += 1;
+        assert!(iterations < 100, "embedded runtime didn't run actor");
+    }
+    assert_eq!(received.load(Ordering::Acquire), 123);
+
+    // Requires a wake-up from another thread.
+    let (future, handle) = WaitFuture::new();
+    let output = runtime.run_until(future).unwrap();
+    assert_eq!(output, Ok(()));
+    handle.join().unwrap();
+
+    assert_eq!(runtime.run_until(async { 1 + 1 }).unwrap(), 2);
+    assert!(!runtime.poll_once().unwrap());
+}
+
+#[test]
+fn block_on() {
+    let runtime = Runtime::new().unwrap();
+    // Requires a wake-up from another thread.
+    let (future, handle) = WaitFuture::new();
+    let output = runtime
+        .block_on(async move { future.await.map(|()| 42) })
+        .unwrap();
+    assert_eq!(output, Ok(42));
+    handle.join().unwrap();
+}
+
+#[test]
+fn block_on_actor() {
+    async fn actor(mut ctx: actor::Context<!, ThreadSafe>, done: Arc<AtomicUsize>) {
+        let _ = Timer::after(&mut ctx, Duration::from_millis(10)).await;
+        let _ = done.fetch_add(1, Ordering::AcqRel);
+    }
+
+    let done = Arc::new(AtomicUsize::new(0));
+    let runtime = Runtime::new().unwrap();
+    runtime
+        .block_on_actor(
+            NoSupervisor,
+            actor as fn(_, _) -> _,
+            done.clone(),
+            ActorOptions::default(),
+        )
+        .unwrap();
+    assert_eq!(done.load(Ordering::Acquire), 1);
+}
+
+#[test]
+fn custom_process() {
+    /// Process that runs `n` times, waking itself after each run.
+    struct Countdown {
+        n: usize,
+        runs: Rc<Cell<usize>>,
+    }
+
+    impl Process for Countdown {
+        fn name(&self) -> &'static str {
+            "Countdown"
+        }
+
+        fn run(
+            mut self: Pin<&mut Self>,
+            runtime_ref: &mut RuntimeRef,
+            pid: ProcessId,
+        ) -> ProcessResult {
+            self.runs.set(self.runs.get() + 1);
+            self.n -= 1;
+            if self.n == 0 {
+                ProcessResult::Complete
+            } else {
+                runtime_ref.new_local_task_waker(pid).wake();
+                ProcessResult::Pending
+            }
+        }
+    }
+
+    let runs = Rc::new(Cell::new(0));
+    let mut runtime = EmbeddedRuntime::new().unwrap();
+    let process = Countdown {
+        n: 3,
+        runs: runs.clone(),
+    };
+    let options = FutureOptions::default().with_priority(Priority::HIGH);
+    let pid = runtime.runtime_ref().add_process(process, options);
+    let stats = runtime.runtime_ref().process_stats(pid).unwrap();
+    assert_eq!(stats.name(), "Countdown");
+
+    while runtime.poll_once().unwrap() {}
+    assert_eq!(runs.get(), 3);
+    assert!(runtime.runtime_ref().process_stats(pid).is_none());
+}
+
+#[test]
+fn error_kind() {
+    let err = rt::Error::setup("oops");
+    assert_eq!(err.kind(), ErrorKind::Setup);
+    assert_eq!(err.worker_id(), None);
+
+    let mut runtime = Runtime::setup().num_threads(1).build().unwrap();
+    runtime
+        .run_on_workers(|_| -> Result<(), &'static str> { Err("oops") })
+        .unwrap();
+    let err = runtime.start().unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::UserFunction);
+    assert_eq!(err.worker_id(), Some(1));
+    assert!(std::error::Error::source(&err).is_some());
+}
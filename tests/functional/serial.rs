@@ -0,0 +1,94 @@
+//! Tests for the serial module.
+
+use std::ffi::CStr;
+use std::fs::File;
+use std::io::{self, Read, Write};
+use std::os::unix::io::FromRawFd;
+use std::path::PathBuf;
+use std::time::Duration;
+
+use heph::actor;
+use heph::serial::{Config, Device, Parity};
+use heph::spawn::ActorOptions;
+use heph::test::{join, try_spawn_local, PanicSupervisor};
+
+const DATA: &[u8] = b"Hello world";
+
+/// Open a new pseudo-terminal, returning the controlling side and the path to
+/// the terminal device.
+fn open_pty() -> (File, PathBuf) {
+    let fd = unsafe { libc::posix_openpt(libc::O_RDWR | libc::O_NOCTTY) };
+    assert!(
+        fd != -1,
+        "failed to open pty: {}",
+        io::Error::last_os_error()
+    );
+    let controller = unsafe { File::from_raw_fd(fd) };
+    assert!(unsafe { libc::grantpt(fd) } != -1);
+    assert!(unsafe { libc::unlockpt(fd) } != -1);
+    let name = unsafe { libc::ptsname(fd) };
+    assert!(!name.is_null());
+    let path = unsafe { CStr::from_ptr(name) }.to_str().unwrap().into();
+    (controller, path)
+}
+
+#[test]
+fn smoke() {
+    async fn actor(
+        mut ctx: actor::Context<!, heph::rt::ThreadLocal>,
+        path: PathBuf,
+    ) -> io::Result<()> {
+        let config = Config::default()
+            .with_baud_rate(115_200)
+            .with_parity(Parity::Even);
+        let mut device = Device::open(&mut ctx, path, &config)?;
+
+        device.write_all(DATA).await?;
+
+        let mut buf = Vec::with_capacity(DATA.len() + 1);
+        device.read_n(&mut buf, DATA.len()).await?;
+        assert_eq!(buf, DATA);
+        Ok(())
+    }
+
+    let (mut controller, path) = open_pty();
+
+    #[allow(trivial_casts)]
+    let actor = actor as fn(_, _) -> _;
+    let actor_ref = try_spawn_local(PanicSupervisor, actor, path, ActorOptions::default()).unwrap();
+
+    // The actor writes first, so the terminal is configured (e.g. echo is
+    // disabled) before we write to it.
+    let mut buf = [0; DATA.len()];
+    controller.read_exact(&mut buf).unwrap();
+    assert_eq!(buf, DATA);
+    controller.write_all(DATA).unwrap();
+
+    join(&actor_ref, Duration::from_secs(1)).unwrap();
+}
+
+#[test]
+fn unsupported_baud_rate() {
+    async fn actor(
+        mut ctx: actor::Context<!, heph::rt::ThreadLocal>,
+        path: PathBuf,
+    ) -> io::Result<()> {
+        let config = Config::default().with_baud_rate(12345);
+        let err = Device::open(&mut ctx, path, &config).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+        Ok(())
+    }
+
+    let (_controller, path) = open_pty();
+
+    #[allow(trivial_casts)]
+    let actor = actor as fn(_, _) -> _;
+    let actor_ref = try_spawn_local(PanicSupervisor, actor, path, ActorOptions::default()).unwrap();
+    join(&actor_ref, Duration::from_secs(1)).unwrap();
+}
+
+#[test]
+#[should_panic(expected = "Data bits must be between 5 and 8")]
+fn invalid_data_bits() {
+    let _ = Config::default().with_data_bits(9);
+}
@@ -0,0 +1,109 @@
+//! Tests for the shutdown module.
+
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use heph::actor::{self, messages::Terminate};
+use heph::actor_ref::ActorGroup;
+use heph::rt::ThreadLocal;
+use heph::shutdown::Shutdown;
+use heph::spawn::ActorOptions;
+use heph::supervisor::NoSupervisor;
+use heph::test::{join, try_spawn_local};
+use heph::timer::Timer;
+use heph::ActorRef;
+
+const TIMEOUT: Duration = Duration::from_secs(1);
+
+type Stopped = Arc<Mutex<Vec<usize>>>;
+
+async fn stage_actor(
+    mut ctx: actor::Context<Terminate, ThreadLocal>,
+    stopped: Stopped,
+    id: usize,
+    delay: Duration,
+) {
+    let _ = ctx.receive_next().await;
+    Timer::after(&mut ctx, delay).await;
+    stopped.lock().unwrap().push(id);
+}
+
+fn spawn_stage(stopped: &Stopped, id: usize, delay: Duration) -> ActorRef<Terminate> {
+    let stage_actor = stage_actor as fn(_, _, _, _) -> _;
+    let args = (stopped.clone(), id, delay);
+    try_spawn_local(NoSupervisor, stage_actor, args, ActorOptions::default()).unwrap()
+}
+
+async fn shutdown_actor(
+    mut ctx: actor::Context<!, ThreadLocal>,
+    shutdown: Shutdown,
+    expected_timed_out: &'static [&'static str],
+) {
+    match shutdown.run(&mut ctx).await {
+        Ok(()) => assert!(expected_timed_out.is_empty()),
+        Err(err) => assert_eq!(err.timed_out(), expected_timed_out),
+    }
+}
+
+fn run_shutdown(shutdown: Shutdown, expected_timed_out: &'static [&'static str]) {
+    let shutdown_actor = shutdown_actor as fn(_, _, _) -> _;
+    let args = (shutdown, expected_timed_out);
+    let actor_ref =
+        try_spawn_local(NoSupervisor, shutdown_actor, args, ActorOptions::default()).unwrap();
+    join(&actor_ref, TIMEOUT).unwrap();
+}
+
+#[test]
+fn reverse_startup_order() {
+    let stopped = Stopped::default();
+    let shutdown = Shutdown::new()
+        .stage(
+            "first",
+            spawn_stage(&stopped, 0, Duration::ZERO).into(),
+            TIMEOUT,
+        )
+        // Even though the second stage takes longer to stop, it should still
+        // stop before the first stage.
+        .stage(
+            "second",
+            spawn_stage(&stopped, 1, Duration::from_millis(20)).into(),
+            TIMEOUT,
+        )
+        .stage(
+            "third",
+            spawn_stage(&stopped, 2, Duration::ZERO).into(),
+            TIMEOUT,
+        );
+    assert_eq!(shutdown.len(), 3);
+
+    run_shutdown(shutdown, &[]);
+    assert_eq!(*stopped.lock().unwrap(), [2, 1, 0]);
+}
+
+#[test]
+fn stage_timeout() {
+    let stopped = Stopped::default();
+    let slow = spawn_stage(&stopped, 1, Duration::from_millis(500));
+    let shutdown = Shutdown::new()
+        .stage(
+            "fast",
+            spawn_stage(&stopped, 0, Duration::ZERO).into(),
+            TIMEOUT,
+        )
+        .stage("slow", slow.clone().into(), Duration::from_millis(10));
+
+    run_shutdown(shutdown, &["slow"]);
+    // The fast stage should be stopped, even though the slow stage timed out.
+    assert_eq!(*stopped.lock().unwrap(), [0]);
+
+    join(&slow, TIMEOUT).unwrap();
+    assert_eq!(*stopped.lock().unwrap(), [0, 1]);
+}
+
+#[test]
+fn empty() {
+    let shutdown = Shutdown::new().stage("empty", ActorGroup::empty(), TIMEOUT);
+    assert!(!shutdown.is_empty());
+    run_shutdown(shutdown, &[]);
+    assert!(Shutdown::new().is_empty());
+}
@@ -5,6 +5,7 @@ use std::marker::PhantomData;
 
 use heph::actor::{self, NewActor};
 use heph::rt::{Runtime, RuntimeRef, ThreadLocal, ThreadSafe};
+use heph::spawn::options::{ActorOptions, FutureOptions, Priority, SyncActorOptions};
 use heph::spawn::Spawn;
 use heph::supervisor::NoSupervisor;
 
@@ -70,3 +71,45 @@ fn thread_local() {
 fn thread_safe() {
     can_spawn_thread_safe::<ThreadSafe>();
 }
+
+#[test]
+fn const_options() {
+    const ACTOR_OPTIONS: ActorOptions = ActorOptions::new()
+        .with_priority(Priority::HIGH)
+        .with_name("test_actor")
+        .mark_ready(false)
+        .catch_panics();
+    assert_eq!(ACTOR_OPTIONS.priority(), Priority::HIGH);
+    assert_eq!(ACTOR_OPTIONS.name(), Some("test_actor"));
+    assert!(!ACTOR_OPTIONS.is_ready());
+    assert!(ACTOR_OPTIONS.catches_panics());
+
+    const FUTURE_OPTIONS: FutureOptions = FutureOptions::new().with_priority(Priority::LOW);
+    assert_eq!(FUTURE_OPTIONS.priority(), Priority::LOW);
+
+    const SYNC_OPTIONS: SyncActorOptions = SyncActorOptions::new();
+    assert_eq!(SYNC_OPTIONS.name(), None);
+}
+
+#[test]
+fn require_readiness_not_ready() {
+    // Requiring readiness marks the actor as ready to run.
+    let options = ActorOptions::new().mark_ready(false).require_readiness();
+    assert!(options.is_ready());
+    assert!(options.readiness_required());
+}
+
+#[test]
+fn not_ready_require_readiness() {
+    // Marking the actor as not ready clears the readiness requirement.
+    let options = ActorOptions::new().require_readiness().mark_ready(false);
+    assert!(!options.is_ready());
+    assert!(!options.readiness_required());
+}
+
+#[test]
+fn require_readiness_ready() {
+    let options = ActorOptions::new().require_readiness().mark_ready(true);
+    assert!(options.is_ready());
+    assert!(options.readiness_required());
+}
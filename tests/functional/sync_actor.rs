@@ -1,13 +1,15 @@
 use std::future::Future;
 use std::pin::Pin;
+use std::sync::atomic::{AtomicUsize, Ordering};
 use std::sync::{Arc, Mutex};
 use std::task::{self, Poll};
 use std::thread::sleep;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 
 use heph::actor::{RecvError, SyncContext};
+use heph::restart_supervisor;
 use heph::spawn::SyncActorOptions;
-use heph::supervisor::{NoSupervisor, SupervisorStrategy};
+use heph::supervisor::{NoSupervisor, RestartPolicy, SupervisorStrategy};
 use heph::test::spawn_sync_actor;
 
 #[derive(Clone, Debug)]
@@ -157,3 +159,63 @@ fn bad_actor_supervisor(err_count: usize) -> SupervisorStrategy<usize> {
 fn bad_actor(_: SyncContext<!>, count: usize) -> Result<(), usize> {
     Err(count + 1)
 }
+
+#[test]
+fn restart_supervisor_backoff() {
+    static ATTEMPTS: AtomicUsize = AtomicUsize::new(0);
+
+    restart_supervisor!(
+        BackoffSupervisor,
+        "failing actor",
+        (),
+        2,
+        Duration::from_secs(5)
+    );
+
+    fn failing_actor(_: SyncContext<!>) -> Result<(), &'static str> {
+        let _ = ATTEMPTS.fetch_add(1, Ordering::SeqCst);
+        Err("oops")
+    }
+
+    let policy =
+        RestartPolicy::exponential_backoff(Duration::from_millis(10), 2, Duration::from_secs(1));
+    let supervisor = BackoffSupervisor::new().with_restart_policy(policy);
+    let start = Instant::now();
+    let (handle, _) = spawn_sync_actor(
+        supervisor,
+        failing_actor as fn(_) -> _,
+        (),
+        SyncActorOptions::default(),
+    )
+    .unwrap();
+
+    handle.join().unwrap();
+    // Initial run and two restarts, after 10 and 20 milliseconds.
+    assert_eq!(ATTEMPTS.load(Ordering::SeqCst), 3);
+    assert!(start.elapsed() >= Duration::from_millis(30));
+}
+
+#[test]
+fn stop_during_restart_delay() {
+    fn supervisor(_: ()) -> SupervisorStrategy<()> {
+        SupervisorStrategy::RestartAfter((), Duration::from_secs(10))
+    }
+
+    fn failing_actor(_: SyncContext<!>) -> Result<(), ()> {
+        Err(())
+    }
+
+    let start = Instant::now();
+    let (handle, actor_ref) = spawn_sync_actor(
+        supervisor as fn(_) -> _,
+        failing_actor as fn(_) -> _,
+        (),
+        SyncActorOptions::default(),
+    )
+    .unwrap();
+
+    // Shouldn't restart the actor.
+    actor_ref.stop();
+    handle.join().unwrap();
+    assert!(start.elapsed() < Duration::from_secs(10));
+}
@@ -0,0 +1,120 @@
+//! Tests for `BufferedStream`.
+
+use std::io::{self, Read, Write};
+use std::net::{self, SocketAddr};
+use std::time::Duration;
+
+use heph::actor;
+use heph::net::tcp::BufferedStream;
+use heph::net::TcpStream;
+use heph::rt::ThreadLocal;
+use heph::spawn::ActorOptions;
+use heph::test::{join, try_spawn_local, PanicSupervisor};
+
+use crate::util::any_local_address;
+
+#[test]
+fn read_line() {
+    async fn actor(mut ctx: actor::Context<!, ThreadLocal>, address: SocketAddr) -> io::Result<()> {
+        let mut stream = TcpStream::connect(&mut ctx, address)?.await?.buffered();
+
+        let mut line = String::new();
+        assert_eq!(stream.read_line(&mut line).await?, 6);
+        assert_eq!(line, "Hello\n");
+        line.clear();
+        assert_eq!(stream.read_line(&mut line).await?, 7);
+        assert_eq!(line, "world!\n");
+        line.clear();
+        // Last line without a newline.
+        assert_eq!(stream.read_line(&mut line).await?, 3);
+        assert_eq!(line, "End");
+        line.clear();
+        // End of the stream.
+        assert_eq!(stream.read_line(&mut line).await?, 0);
+        assert_eq!(line, "");
+
+        Ok(())
+    }
+
+    let listener = net::TcpListener::bind(any_local_address()).unwrap();
+    let address = listener.local_addr().unwrap();
+
+    let actor = actor as fn(_, _) -> _;
+    let actor_ref =
+        try_spawn_local(PanicSupervisor, actor, address, ActorOptions::default()).unwrap();
+
+    let (mut stream, _) = listener.accept().unwrap();
+    stream.write_all(b"Hello\nworld!\nEnd").unwrap();
+    drop(stream);
+
+    join(&actor_ref, Duration::from_secs(1)).unwrap();
+}
+
+#[test]
+fn fill_buf_and_read_until() {
+    async fn actor(mut ctx: actor::Context<!, ThreadLocal>, address: SocketAddr) -> io::Result<()> {
+        let stream = TcpStream::connect(&mut ctx, address)?.await?;
+        // Small read buffer so we need multiple reads for a single value.
+        let mut stream = BufferedStream::with_capacity(4, 4, stream);
+
+        let buf = stream.fill_buf().await?;
+        assert_eq!(buf, b"key=");
+        stream.consume(2);
+        assert_eq!(stream.buffer(), b"y=");
+        stream.consume(2);
+        assert!(stream.buffer().is_empty());
+
+        let mut value = Vec::new();
+        assert_eq!(stream.read_until(b';', &mut value).await?, 11);
+        assert_eq!(value, b"long value;");
+
+        value.clear();
+        assert_eq!(stream.read_until(b';', &mut value).await?, 0);
+        assert!(value.is_empty());
+
+        Ok(())
+    }
+
+    let listener = net::TcpListener::bind(any_local_address()).unwrap();
+    let address = listener.local_addr().unwrap();
+
+    let actor = actor as fn(_, _) -> _;
+    let actor_ref =
+        try_spawn_local(PanicSupervisor, actor, address, ActorOptions::default()).unwrap();
+
+    let (mut stream, _) = listener.accept().unwrap();
+    stream.write_all(b"key=long value;").unwrap();
+    drop(stream);
+
+    join(&actor_ref, Duration::from_secs(1)).unwrap();
+}
+
+#[test]
+fn buffered_send() {
+    async fn actor(mut ctx: actor::Context<!, ThreadLocal>, address: SocketAddr) -> io::Result<()> {
+        let stream = TcpStream::connect(&mut ctx, address)?.await?;
+        let mut stream = BufferedStream::with_capacity(16, 16, stream);
+
+        stream.send_all(b"Hello ").await?;
+        stream.send_all(b"world").await?;
+        // Larger than the buffer, send directly after flushing the buffer.
+        stream.send_all(b", and goodbye world!").await?;
+        stream.send_all(b" End").await?;
+        stream.flush().await?;
+        Ok(())
+    }
+
+    let listener = net::TcpListener::bind(any_local_address()).unwrap();
+    let address = listener.local_addr().unwrap();
+
+    let actor = actor as fn(_, _) -> _;
+    let actor_ref =
+        try_spawn_local(PanicSupervisor, actor, address, ActorOptions::default()).unwrap();
+
+    let (mut stream, _) = listener.accept().unwrap();
+    join(&actor_ref, Duration::from_secs(1)).unwrap();
+
+    let mut buf = Vec::new();
+    stream.read_to_end(&mut buf).unwrap();
+    assert_eq!(buf, b"Hello world, and goodbye world! End");
+}
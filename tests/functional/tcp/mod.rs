@@ -1,5 +1,6 @@
 //! Tests for the TCP types.
 
+mod buffered;
 mod listener;
 mod server;
 mod stream;
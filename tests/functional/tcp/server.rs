@@ -1,7 +1,9 @@
 use std::convert::TryFrom;
+use std::io;
 use std::marker::PhantomData;
 use std::net::SocketAddr;
 use std::pin::Pin;
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::task::{self, Poll};
 use std::time::Duration;
 
@@ -10,9 +12,10 @@ use heph::actor::{self, Actor, NewActor};
 use heph::net::tcp::server;
 use heph::net::{TcpServer, TcpStream};
 use heph::rt::{self, Signal, ThreadLocal};
+use heph::spawn::options::Priority;
 use heph::spawn::ActorOptions;
 use heph::supervisor::{NoSupervisor, Supervisor, SupervisorStrategy};
-use heph::test::{join_many, try_spawn_local, PanicSupervisor};
+use heph::test::{join, join_many, try_spawn_local, PanicSupervisor};
 use heph::{ActorRef, Runtime};
 
 use crate::util::any_local_address;
@@ -30,6 +33,15 @@ fn message_from_process_signal() {
     }
 }
 
+#[test]
+fn error_conversion() {
+    let err: server::Error<!> = server::Error::Accept(io::Error::new(io::ErrorKind::Other, "oops"));
+    assert!(std::error::Error::source(&err).is_some());
+    let err = io::Error::from(err);
+    assert_eq!(err.kind(), io::ErrorKind::Other);
+    assert_eq!(err.to_string(), "oops");
+}
+
 async fn actor<RT>(_: actor::Context<!, RT>, mut stream: TcpStream, _: SocketAddr)
 where
     RT: rt::Access,
@@ -126,6 +138,67 @@ fn zero_port() {
     assert!(server.local_addr().port() != 0);
 }
 
+struct NewActorErrorGenerator<RT>(PhantomData<*const RT>);
+
+// `*const RT` is `!Send`, but we don't actually store it.
+unsafe impl<RT> Send for NewActorErrorGenerator<RT> {}
+unsafe impl<RT> Sync for NewActorErrorGenerator<RT> {}
+
+impl<RT> Copy for NewActorErrorGenerator<RT> {}
+
+impl<RT> Clone for NewActorErrorGenerator<RT> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<RT> NewActor for NewActorErrorGenerator<RT>
+where
+    RT: rt::Access,
+{
+    type Message = !;
+    type Argument = (TcpStream, SocketAddr);
+    type Actor = ActorErrorGenerator;
+    type Error = ();
+    type RuntimeAccess = RT;
+
+    fn new(
+        &mut self,
+        _: actor::Context<Self::Message, Self::RuntimeAccess>,
+        _: Self::Argument,
+    ) -> Result<Self::Actor, Self::Error> {
+        Err(())
+    }
+}
+
+struct ActorErrorGenerator;
+
+impl Actor for ActorErrorGenerator {
+    type Error = ();
+
+    fn try_poll(self: Pin<&mut Self>, _: &mut task::Context<'_>) -> Poll<Result<(), Self::Error>> {
+        Poll::Ready(Ok(()))
+    }
+}
+
+#[derive(Copy, Clone)]
+struct ErrorSupervisor;
+
+impl<NA> Supervisor<NA> for ErrorSupervisor
+where
+    NA: NewActor,
+{
+    fn decide(&mut self, _: <NA::Actor as Actor>::Error) -> SupervisorStrategy<NA::Argument> {
+        SupervisorStrategy::Stop
+    }
+
+    fn decide_on_restart_error(&mut self, _: NA::Error) -> SupervisorStrategy<NA::Argument> {
+        SupervisorStrategy::Stop
+    }
+
+    fn second_restart_error(&mut self, _: NA::Error) {}
+}
+
 #[test]
 fn new_actor_error() {
     struct ServerWrapper<T>(T);
@@ -178,65 +251,58 @@ fn new_actor_error() {
         }
     }
 
-    struct NewActorErrorGenerator<RT>(PhantomData<*const RT>);
-
-    // `*const RT` is `!Send`, but we don't actually store it.
-    unsafe impl<RT> Send for NewActorErrorGenerator<RT> {}
-    unsafe impl<RT> Sync for NewActorErrorGenerator<RT> {}
-
-    impl<RT> Copy for NewActorErrorGenerator<RT> {}
-
-    impl<RT> Clone for NewActorErrorGenerator<RT> {
-        fn clone(&self) -> Self {
-            *self
-        }
-    }
+    let server = TcpServer::setup(
+        any_local_address(),
+        ErrorSupervisor,
+        NewActorErrorGenerator(PhantomData),
+        ActorOptions::default(),
+    )
+    .unwrap();
+    let address = server.local_addr();
+    let server = ServerWrapper(server);
+    let server_ref = try_spawn_local(ErrorSupervisor, server, (), ActorOptions::default()).unwrap();
 
-    impl<RT> NewActor for NewActorErrorGenerator<RT>
+    async fn stream_actor<M, RT>(mut ctx: actor::Context<M, RT>, address: SocketAddr)
     where
         RT: rt::Access,
     {
-        type Message = !;
-        type Argument = (TcpStream, SocketAddr);
-        type Actor = ActorErrorGenerator;
-        type Error = ();
-        type RuntimeAccess = RT;
+        let stream = TcpStream::connect(&mut ctx, address)
+            .unwrap()
+            .await
+            .unwrap();
 
-        fn new(
-            &mut self,
-            _: actor::Context<Self::Message, Self::RuntimeAccess>,
-            _: Self::Argument,
-        ) -> Result<Self::Actor, Self::Error> {
-            Err(())
-        }
+        // Just need to create the connection.
+        drop(stream);
     }
 
-    struct ActorErrorGenerator;
-
-    impl Actor for ActorErrorGenerator {
-        type Error = ();
+    let stream_actor = stream_actor as fn(_, _) -> _;
+    let stream_ref =
+        try_spawn_local(NoSupervisor, stream_actor, address, ActorOptions::default()).unwrap();
 
-        fn try_poll(
-            self: Pin<&mut Self>,
-            _: &mut task::Context<'_>,
-        ) -> Poll<Result<(), Self::Error>> {
-            Poll::Ready(Ok(()))
-        }
-    }
+    join_many(&[server_ref, stream_ref], Duration::from_secs(1)).unwrap();
+}
 
-    #[derive(Copy, Clone)]
-    struct ErrorSupervisor;
+#[test]
+fn listener_reuse() {
+    /// Restarts the server once, stopping it after the second error.
+    #[derive(Clone)]
+    struct RestartOnceSupervisor(bool);
 
-    impl<NA> Supervisor<NA> for ErrorSupervisor
+    impl<NA> Supervisor<NA> for RestartOnceSupervisor
     where
-        NA: NewActor,
+        NA: NewActor<Argument = ()>,
     {
-        fn decide(&mut self, _: <NA::Actor as Actor>::Error) -> SupervisorStrategy<NA::Argument> {
-            SupervisorStrategy::Stop
+        fn decide(&mut self, _: <NA::Actor as Actor>::Error) -> SupervisorStrategy<()> {
+            if self.0 {
+                SupervisorStrategy::Stop
+            } else {
+                self.0 = true;
+                SupervisorStrategy::Restart(())
+            }
         }
 
-        fn decide_on_restart_error(&mut self, _: NA::Error) -> SupervisorStrategy<NA::Argument> {
-            SupervisorStrategy::Stop
+        fn decide_on_restart_error(&mut self, err: NA::Error) -> SupervisorStrategy<()> {
+            panic!("unexpected restart error: {:?}", err)
         }
 
         fn second_restart_error(&mut self, _: NA::Error) {}
@@ -248,22 +314,25 @@ fn new_actor_error() {
         NewActorErrorGenerator(PhantomData),
         ActorOptions::default(),
     )
-    .unwrap();
+    .unwrap()
+    .with_listener_reuse();
     let address = server.local_addr();
-    let server = ServerWrapper(server);
-    let server_ref = try_spawn_local(ErrorSupervisor, server, (), ActorOptions::default()).unwrap();
+    let supervisor = RestartOnceSupervisor(false);
+    let server_ref = try_spawn_local(supervisor, server, (), ActorOptions::default()).unwrap();
 
     async fn stream_actor<M, RT>(mut ctx: actor::Context<M, RT>, address: SocketAddr)
     where
         RT: rt::Access,
     {
-        let stream = TcpStream::connect(&mut ctx, address)
-            .unwrap()
-            .await
-            .unwrap();
-
-        // Just need to create the connection.
-        drop(stream);
+        // The first connection causes the server to be restarted, the second
+        // must be accepted by the restarted server using the same listener.
+        for _ in 0..2 {
+            let stream = TcpStream::connect(&mut ctx, address)
+                .unwrap()
+                .await
+                .unwrap();
+            drop(stream);
+        }
     }
 
     let stream_actor = stream_actor as fn(_, _) -> _;
@@ -272,3 +341,37 @@ fn new_actor_error() {
 
     join_many(&[server_ref, stream_ref], Duration::from_secs(1)).unwrap();
 }
+
+#[test]
+fn connection_priority() {
+    static CALLED: AtomicBool = AtomicBool::new(false);
+
+    fn connection_priority(address: SocketAddr) -> Priority {
+        assert!(address.ip().is_loopback());
+        CALLED.store(true, Ordering::SeqCst);
+        Priority::HIGH
+    }
+
+    let server = TcpServer::setup(
+        any_local_address(),
+        |err| panic!("unexpect error: {}", err),
+        actor as fn(_, _, _) -> _,
+        ActorOptions::default(),
+    )
+    .unwrap()
+    .with_connection_priority(connection_priority);
+    let server_address = server.local_addr();
+    let server_ref = try_spawn_local(PanicSupervisor, server, (), ActorOptions::default()).unwrap();
+    let stream_actor = stream_actor as fn(_, _, _) -> _;
+    let stream_ref = try_spawn_local(
+        NoSupervisor,
+        stream_actor,
+        (server_address, server_ref.clone()),
+        ActorOptions::default(),
+    )
+    .unwrap();
+
+    join(&stream_ref, Duration::from_secs(1)).unwrap();
+    join(&server_ref, Duration::from_secs(1)).unwrap();
+    assert!(CALLED.load(Ordering::SeqCst));
+}
@@ -11,6 +11,8 @@ use std::time::Duration;
 
 use heph::actor::{self, Bound};
 use heph::actor_ref::{ActorRef, RpcMessage};
+use heph::net::tcp::proxy::ProxyConfig;
+use heph::net::tcp::stream::ConnectOptions;
 use heph::net::{TcpListener, TcpStream};
 use heph::rt::{self, Runtime, RuntimeRef, ThreadLocal};
 use heph::spawn::ActorOptions;
@@ -140,6 +142,139 @@ fn connect_connection_refused() {
     join(&actor_ref, Duration::from_secs(1)).unwrap();
 }
 
+#[test]
+fn connect_any() {
+    async fn actor(mut ctx: actor::Context<!, ThreadLocal>, address: SocketAddr) -> io::Result<()> {
+        // The first address refuses the connection, so we should connect to
+        // the second address.
+        let addresses = [refused_address(), address];
+        let stream = TcpStream::connect_any(&mut ctx, &addresses[..])?.await?;
+        drop(stream);
+        Ok(())
+    }
+
+    let listener = net::TcpListener::bind(any_local_address()).unwrap();
+    let address = listener.local_addr().unwrap();
+
+    let actor = actor as fn(_, _) -> _;
+    let actor_ref =
+        try_spawn_local(PanicSupervisor, actor, address, ActorOptions::default()).unwrap();
+
+    let (mut stream, _) = listener.accept().unwrap();
+    let mut buf = [0; 2];
+    assert_eq!(stream.read(&mut buf).unwrap(), 0);
+    drop(stream);
+
+    join(&actor_ref, Duration::from_secs(1)).unwrap();
+}
+
+#[test]
+fn connect_any_all_refused() {
+    async fn actor(mut ctx: actor::Context<!, ThreadLocal>) -> io::Result<()> {
+        let addresses = [refused_address(), refused_address()];
+        match TcpStream::connect_any(&mut ctx, &addresses[..])?.await {
+            Ok(..) => panic!("unexpected success"),
+            Err(err) => assert_eq!(
+                err.kind(),
+                io::ErrorKind::ConnectionRefused,
+                "unexpected error: {:?}",
+                err
+            ),
+        }
+        Ok(())
+    }
+
+    let actor = actor as fn(_) -> _;
+    let actor_ref = try_spawn_local(PanicSupervisor, actor, (), ActorOptions::default()).unwrap();
+    join(&actor_ref, Duration::from_secs(1)).unwrap();
+}
+
+#[test]
+fn connect_any_no_addresses() {
+    async fn actor(mut ctx: actor::Context<!, ThreadLocal>) -> io::Result<()> {
+        let addresses: [SocketAddr; 0] = [];
+        match TcpStream::connect_any(&mut ctx, &addresses[..]) {
+            Ok(..) => panic!("unexpected success"),
+            Err(err) => assert_eq!(err.kind(), io::ErrorKind::InvalidInput),
+        }
+        Ok(())
+    }
+
+    let actor = actor as fn(_) -> _;
+    let actor_ref = try_spawn_local(PanicSupervisor, actor, (), ActorOptions::default()).unwrap();
+    join(&actor_ref, Duration::from_secs(1)).unwrap();
+}
+
+#[test]
+fn connect_to_socks5_proxy() {
+    async fn actor(mut ctx: actor::Context<!, ThreadLocal>, address: SocketAddr) -> io::Result<()> {
+        let proxy = ProxyConfig::socks5(address).with_auth("user", "password");
+        let options = ConnectOptions::default().via_proxy(proxy);
+        let mut stream = TcpStream::connect_to(&mut ctx, "example.com", 80, options)?.await?;
+        stream.send_all(DATA).await
+    }
+
+    let listener = net::TcpListener::bind(any_local_address()).unwrap();
+    let address = listener.local_addr().unwrap();
+
+    let actor = actor as fn(_, _) -> _;
+    let actor_ref =
+        try_spawn_local(PanicSupervisor, actor, address, ActorOptions::default()).unwrap();
+
+    let (mut stream, _) = listener.accept().unwrap();
+    let mut buf = [0; 4];
+    stream.read_exact(&mut buf).unwrap();
+    assert_eq!(buf, [5, 2, 0, 2]); // Version 5, no or username/password auth.
+    stream.write_all(&[5, 2]).unwrap();
+    let mut buf = [0; 15];
+    stream.read_exact(&mut buf).unwrap();
+    assert_eq!(&buf, b"\x01\x04user\x08password");
+    stream.write_all(&[1, 0]).unwrap();
+    let mut buf = [0; 18];
+    stream.read_exact(&mut buf).unwrap();
+    assert_eq!(&buf, b"\x05\x01\x00\x03\x0bexample.com\x00\x50");
+    stream
+        .write_all(&[5, 0, 0, 1, 127, 0, 0, 1, 0, 80])
+        .unwrap();
+
+    let mut buf = [0; DATA.len()];
+    stream.read_exact(&mut buf).unwrap();
+    assert_eq!(buf, DATA);
+
+    join(&actor_ref, Duration::from_secs(1)).unwrap();
+}
+
+#[test]
+fn connect_to_http_proxy() {
+    async fn actor(mut ctx: actor::Context<!, ThreadLocal>, address: SocketAddr) -> io::Result<()> {
+        let options = ConnectOptions::default().via_proxy(ProxyConfig::http(address));
+        let mut stream = TcpStream::connect_to(&mut ctx, "example.com", 80, options)?.await?;
+        // The bytes send after the proxy's response must not be lost.
+        let mut buf = Vec::with_capacity(DATA.len() + 1);
+        stream.recv_n(&mut buf, DATA.len()).await?;
+        assert_eq!(buf, DATA);
+        Ok(())
+    }
+
+    let listener = net::TcpListener::bind(any_local_address()).unwrap();
+    let address = listener.local_addr().unwrap();
+
+    let actor = actor as fn(_, _) -> _;
+    let actor_ref =
+        try_spawn_local(PanicSupervisor, actor, address, ActorOptions::default()).unwrap();
+
+    let (mut stream, _) = listener.accept().unwrap();
+    let expected = b"CONNECT example.com:80 HTTP/1.1\r\nHost: example.com:80\r\n\r\n";
+    let mut buf = vec![0; expected.len()];
+    stream.read_exact(&mut buf).unwrap();
+    assert_eq!(buf, expected);
+    let mut response = b"HTTP/1.1 200 Connection established\r\n\r\n".to_vec();
+    response.extend_from_slice(DATA);
+    stream.write_all(&response).unwrap();
+
+    join(&actor_ref, Duration::from_secs(1)).unwrap();
+}
+
 #[test]
 fn try_recv() {
     async fn actor(mut ctx: actor::Context<!, ThreadLocal>, address: SocketAddr) -> io::Result<()> {
@@ -895,6 +1030,39 @@ fn send_entire_file() {
     join_many(&[actor_ref1, actor_ref2], Duration::from_secs(1)).unwrap();
 }
 
+#[test]
+fn send_entire_file_async_file() {
+    async fn actor(
+        mut ctx: actor::Context<!, ThreadLocal>,
+        address: SocketAddr,
+        path: &'static str,
+    ) -> io::Result<()> {
+        let file = heph::fs::File::open(path).await?;
+        let mut stream = TcpStream::connect(&mut ctx, address)?.await?;
+        stream.send_entire_file(&file).await
+    }
+
+    let listener = net::TcpListener::bind(any_local_address()).unwrap();
+    let address = listener.local_addr().unwrap();
+
+    let actor = actor as fn(_, _, _) -> _;
+    let args = (address, TEST_FILE1);
+    let actor_ref = try_spawn_local(PanicSupervisor, actor, args, ActorOptions::default()).unwrap();
+    let (mut stream, _) = listener.accept().unwrap();
+    stream.set_nonblocking(true).unwrap();
+
+    let mut offset = 0;
+    let mut buf = vec![0; 4096];
+    for _ in 0..20 {
+        if send_file_check_actor(&mut stream, &EXPECTED1, &mut offset, &mut buf) {
+            break;
+        }
+        sleep(Duration::from_millis(10));
+    }
+
+    join(&actor_ref, Duration::from_secs(1)).unwrap();
+}
+
 /// Returns `true` if `actor` send all `expected` bytes to `stream`.
 #[track_caller]
 fn send_file_check_actor(
@@ -1125,6 +1293,104 @@ fn shutdown_both() {
     join_many(&[stream_ref, listener_ref], Duration::from_secs(1)).unwrap();
 }
 
+#[test]
+fn close_graceful() {
+    async fn listener_actor<M>(
+        mut ctx: actor::Context<M, ThreadLocal>,
+        actor_ref: ActorRef<SocketAddr>,
+    ) {
+        let mut listener = TcpListener::bind(&mut ctx, any_local_address()).unwrap();
+
+        let address = listener.local_addr().unwrap();
+        actor_ref.send(address).await.unwrap();
+
+        let (stream, _) = listener.accept().await.unwrap();
+        let mut stream = stream.bind_to(&mut ctx).unwrap();
+
+        // Peer sends data, which we don't read, before closing.
+        stream.send_all(DATA).await.unwrap();
+
+        let mut buf = Vec::with_capacity(DATA.len() + 1);
+        stream.recv_n(&mut buf, DATA.len()).await.unwrap();
+        assert_eq!(buf, DATA);
+        // After which the peer should see EOF.
+        buf.clear();
+        let n = stream.recv(&mut buf).await.unwrap();
+        assert_eq!(n, 0);
+    }
+
+    async fn stream_actor(mut ctx: actor::Context<SocketAddr, ThreadLocal>) {
+        let address = ctx.receive_next().await.unwrap();
+        let mut stream = TcpStream::connect(&mut ctx, address)
+            .unwrap()
+            .await
+            .unwrap();
+
+        stream.send_all(DATA).await.unwrap();
+        stream
+            .close_graceful(&mut ctx, Duration::from_millis(500))
+            .await
+            .unwrap();
+    }
+
+    let stream_actor = stream_actor as fn(_) -> _;
+    let stream_ref =
+        try_spawn_local(NoSupervisor, stream_actor, (), ActorOptions::default()).unwrap();
+
+    let listener_actor = listener_actor as fn(_, _) -> _;
+    let s_ref = stream_ref.clone();
+    let listener_ref =
+        try_spawn_local(NoSupervisor, listener_actor, s_ref, ActorOptions::default()).unwrap();
+
+    join_many(&[stream_ref, listener_ref], Duration::from_secs(1)).unwrap();
+}
+
+#[test]
+fn close_graceful_timeout() {
+    async fn listener_actor<M>(
+        mut ctx: actor::Context<M, ThreadLocal>,
+        actor_ref: ActorRef<SocketAddr>,
+    ) {
+        let mut listener = TcpListener::bind(&mut ctx, any_local_address()).unwrap();
+
+        let address = listener.local_addr().unwrap();
+        actor_ref.send(address).await.unwrap();
+
+        let (stream, _) = listener.accept().await.unwrap();
+        let mut stream = stream.bind_to(&mut ctx).unwrap();
+
+        // Never close our side of the connection, until the peer does.
+        let mut buf = Vec::with_capacity(2);
+        let n = stream.recv(&mut buf).await.unwrap();
+        assert_eq!(n, 0);
+    }
+
+    async fn stream_actor(mut ctx: actor::Context<SocketAddr, ThreadLocal>) {
+        let address = ctx.receive_next().await.unwrap();
+        let stream = TcpStream::connect(&mut ctx, address)
+            .unwrap()
+            .await
+            .unwrap();
+
+        let err = stream
+            .close_graceful(&mut ctx, Duration::from_millis(50))
+            .await
+            .unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::TimedOut);
+    }
+
+    let stream_actor = stream_actor as fn(_) -> _;
+    let stream_ref =
+        try_spawn_local(NoSupervisor, stream_actor, (), ActorOptions::default()).unwrap();
+
+    let listener_actor = listener_actor as fn(_, _) -> _;
+    let s_ref = stream_ref.clone();
+    let listener_ref =
+        try_spawn_local(NoSupervisor, listener_actor, s_ref, ActorOptions::default()).unwrap();
+
+    join_many(&[stream_ref, listener_ref], Duration::from_secs(1)).unwrap();
+}
+
 #[test]
 fn actor_bound() {
     type Message = RpcMessage<TcpStream, ()>;
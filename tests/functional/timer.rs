@@ -47,6 +47,42 @@ fn timer() {
     assert_eq!(poll_actor(Pin::as_mut(&mut actor)), Poll::Ready(Ok(())));
 }
 
+#[test]
+fn timer_reset() {
+    async fn actor(mut ctx: actor::Context<!, ThreadLocal>) {
+        let start = Instant::now();
+        let mut timer = Timer::after(&mut ctx, SMALL_TIMEOUT);
+        assert!(timer.deadline() < start + TIMEOUT);
+
+        // Move the deadline further into the future.
+        timer.reset(start + TIMEOUT);
+        assert_eq!(timer.deadline(), start + TIMEOUT);
+        let _ = (&mut timer).await;
+        assert!(Instant::now() >= start + TIMEOUT);
+
+        // Reset a timer that has already passed.
+        timer.reset(Instant::now() + SMALL_TIMEOUT);
+        assert!(!timer.has_passed());
+        let _ = (&mut timer).await;
+        assert!(timer.has_passed());
+    }
+
+    let actor = actor as fn(_) -> _;
+    let (actor, _) = init_local_actor(actor, ()).unwrap();
+    let mut actor = Box::pin(actor);
+    assert_eq!(poll_actor(Pin::as_mut(&mut actor)), Poll::Pending);
+
+    thread::sleep(SMALL_TIMEOUT);
+    // The original deadline has passed, but the timer was reset.
+    assert_eq!(poll_actor(Pin::as_mut(&mut actor)), Poll::Pending);
+
+    thread::sleep(TIMEOUT - SMALL_TIMEOUT);
+    assert_eq!(poll_actor(Pin::as_mut(&mut actor)), Poll::Pending);
+
+    thread::sleep(SMALL_TIMEOUT);
+    assert_eq!(poll_actor(Pin::as_mut(&mut actor)), Poll::Ready(Ok(())));
+}
+
 #[derive(Clone, Debug, Eq, PartialEq)]
 struct AlwaysPending;
 
@@ -216,6 +252,10 @@ fn timers_dont_trigger_after_drop() {
         expect_pending(poll_future(Pin::new(&mut timer)));
         // Dropping it should remove the timer.
         drop(timer);
+        // Same for canceling it.
+        let mut timer = Timer::after(&mut ctx, SMALL_TIMEOUT);
+        expect_pending(poll_future(Pin::new(&mut timer)));
+        timer.cancel();
 
         let timer = Timer::after(&mut ctx, TIMEOUT);
         let (_, poll_count) = count_polls(timer).await;
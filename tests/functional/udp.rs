@@ -392,3 +392,94 @@ fn actor_bound() {
 
     runtime.start().unwrap();
 }
+
+#[test]
+fn socket_options() {
+    async fn actor(mut ctx: actor::Context<!, ThreadLocal>) -> io::Result<()> {
+        let mut socket = UdpSocket::bind(&mut ctx, any_local_address())?;
+
+        let ttl = socket.ttl()?;
+        socket.set_ttl(ttl + 1)?;
+        assert_eq!(socket.ttl()?, ttl + 1);
+
+        let broadcast = socket.broadcast()?;
+        socket.set_broadcast(!broadcast)?;
+        assert_eq!(socket.broadcast()?, !broadcast);
+
+        let multicast_loop = socket.multicast_loop_v4()?;
+        socket.set_multicast_loop_v4(!multicast_loop)?;
+        assert_eq!(socket.multicast_loop_v4()?, !multicast_loop);
+
+        socket.set_multicast_ttl_v4(8)?;
+        assert_eq!(socket.multicast_ttl_v4()?, 8);
+
+        Ok(())
+    }
+
+    let actor = actor as fn(_) -> _;
+    let actor_ref = try_spawn_local(PanicSupervisor, actor, (), ActorOptions::default()).unwrap();
+    join(&actor_ref, Duration::from_secs(1)).unwrap();
+}
+
+#[test]
+#[cfg(target_os = "linux")]
+fn recv_error() {
+    use heph::net::udp::ErrorOrigin;
+
+    async fn actor(mut ctx: actor::Context<!, ThreadLocal>) -> io::Result<()> {
+        // Get an address on which no socket is listening.
+        let address = std::net::UdpSocket::bind(any_local_address())?.local_addr()?;
+
+        let mut socket = UdpSocket::bind(&mut ctx, any_local_address())?;
+        socket.set_recv_error(true)?;
+        let mut socket = socket.connect(address)?;
+
+        let n = socket.send(DATA).await?;
+        assert_eq!(n, DATA.len());
+
+        let err = socket.recv_error().await?;
+        assert_eq!(err.error().kind(), io::ErrorKind::ConnectionRefused);
+        assert_eq!(err.origin(), ErrorOrigin::Icmp);
+        assert_eq!(err.offender().map(|a| a.ip()), Some(address.ip()));
+        assert_eq!(err.mtu(), None);
+
+        // Error queue should be empty now.
+        let err = socket.try_recv_error().unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::WouldBlock);
+        Ok(())
+    }
+
+    let actor = actor as fn(_) -> _;
+    let actor_ref = try_spawn_local(PanicSupervisor, actor, (), ActorOptions::default()).unwrap();
+    join(&actor_ref, Duration::from_secs(1)).unwrap();
+}
+
+#[test]
+#[cfg(target_os = "linux")]
+fn path_mtu_options() {
+    async fn actor(mut ctx: actor::Context<!, ThreadLocal>) -> io::Result<()> {
+        let peer = std::net::UdpSocket::bind(any_local_address())?;
+        let peer_address = peer.local_addr()?;
+
+        let mut socket = UdpSocket::bind(&mut ctx, any_local_address())?;
+        socket.set_dontfrag(true)?;
+        socket.set_dontfrag(false)?;
+        socket.set_dontfrag(true)?;
+
+        let max_segments = socket.max_gso_segments();
+        assert!(max_segments >= 1);
+        if max_segments > 1 {
+            socket.set_segment_size(1200)?;
+            socket.set_segment_size(0)?;
+        }
+
+        let mut socket = socket.connect(peer_address)?;
+        let mtu = socket.path_mtu()?;
+        assert!(mtu > 0);
+        Ok(())
+    }
+
+    let actor = actor as fn(_) -> _;
+    let actor_ref = try_spawn_local(PanicSupervisor, actor, (), ActorOptions::default()).unwrap();
+    join(&actor_ref, Duration::from_secs(1)).unwrap();
+}
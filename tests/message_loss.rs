@@ -13,7 +13,7 @@ use std::task::Poll;
 
 use heph::actor::{self, NoMessages};
 use heph::rt::ThreadLocal;
-use heph::test::{init_local_actor, poll_actor, set_message_loss};
+use heph::test::{init_local_actor, poll_actor, poll_future, set_message_loss};
 
 async fn expect_1_messages(mut ctx: actor::Context<usize, ThreadLocal>) {
     let msg = ctx.receive_next().await.expect("missing first message");
@@ -38,6 +38,9 @@ fn actor_ref_message_loss() {
     set_message_loss(100);
     actor_ref.try_send(456_usize).unwrap();
     actor_ref.try_send(789_usize).unwrap();
+    let mut send = Box::pin(actor_ref.send(101_usize));
+    assert_eq!(poll_future(send.as_mut()), Poll::Ready(Ok(())));
+    drop(send);
 
     drop(actor_ref);
     assert_eq!(poll_actor(Pin::as_mut(&mut actor)), Poll::Ready(Ok(())));